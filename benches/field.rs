@@ -0,0 +1,38 @@
+//! Benchmarking inverting field elements one at a time against inverting them with
+//! `field::batch_inverse`
+use core::time::Duration;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use halo2curves::{bn256::Fr as Scalar, ff::Field};
+use nova_snark::field::batch_inverse;
+use rayon::prelude::*;
+
+criterion_group! {
+name = field;
+config = Criterion::default().warm_up_time(Duration::from_millis(3000)).sample_size(10);
+targets = bench_invert
+}
+
+criterion_main!(field);
+
+fn bench_invert(c: &mut Criterion) {
+  for size in [1 << 10, 1 << 14, 1 << 18] {
+    let v = (0..size)
+      .into_par_iter()
+      .map(|_| Scalar::random(&mut rand::thread_rng()))
+      .collect::<Vec<_>>();
+
+    c.bench_function(&format!("invert_individually_{size}"), |b| {
+      b.iter(|| {
+        black_box(
+          v.par_iter()
+            .map(|x| x.invert().unwrap())
+            .collect::<Vec<_>>(),
+        )
+      })
+    });
+
+    c.bench_function(&format!("invert_batch_{size}"), |b| {
+      b.iter(|| black_box(batch_inverse(&v).unwrap()))
+    });
+  }
+}
@@ -0,0 +1,29 @@
+//! Benchmarks the computation of the cross-term of folding on random shapes of up to 2^20
+//! constraints, against a baseline that materializes the products `AZ`, `BZ`, and `CZ` in full.
+//! Run with `cargo bench --bench nifs --features testing`.
+use core::time::Duration;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nova_snark::{provider::Bn256EngineKZG, testing::CrossTermBenchmark};
+
+criterion_group! {
+name = nifs;
+config = Criterion::default().warm_up_time(Duration::from_millis(3000)).sample_size(10);
+targets = bench_cross_term
+}
+
+criterion_main!(nifs);
+
+fn bench_cross_term(c: &mut Criterion) {
+  type E = Bn256EngineKZG;
+
+  for log_num_cons in [16, 18, 20] {
+    let bench = CrossTermBenchmark::<E>::new(1 << log_num_cons);
+
+    let mut group = c.benchmark_group(format!("CrossTerm-NumCons-2^{log_num_cons}"));
+    group.bench_function("Tiled", |b| b.iter(|| black_box(&bench).cross_term()));
+    group.bench_function("Unfused", |b| {
+      b.iter(|| black_box(&bench).cross_term_unfused())
+    });
+    group.finish();
+  }
+}
@@ -2,7 +2,7 @@
 
 use criterion::*;
 use nova_snark::{
-  nova::{PublicParams, RecursiveSNARK},
+  nova::{FixedCircuitProver, PublicParams, RecursiveSNARK},
   provider::{Bn256EngineKZG, GrumpkinEngine},
   traits::{circuit::NonTrivialCircuit, snark::default_ck_hint, Engine},
 };
@@ -90,6 +90,16 @@ fn bench_recursive_snark(c: &mut Criterion) {
       })
     });
 
+    // Bench the same step through a prover that allocates the assignments up front
+    let prover = FixedCircuitProver::new(&pp, c.clone()).unwrap();
+    group.bench_function("ProveFixedCircuit", |b| {
+      b.iter(|| {
+        assert!(black_box(&prover)
+          .prove_step(black_box(&mut recursive_snark.clone()))
+          .is_ok());
+      })
+    });
+
     // Benchmark the verification time
     group.bench_function("Verify", |b| {
       b.iter(|| {
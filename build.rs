@@ -0,0 +1,28 @@
+//! Records the git commit that the crate is built from, for `nova_snark::build_info`.
+//!
+//! The commit is taken from `NOVA_GIT_COMMIT` when it is set, e.g., by builds from a source archive,
+//! and from `git rev-parse HEAD` otherwise; it is left unset when neither is available.
+use std::{path::Path, process::Command};
+
+fn main() {
+  println!("cargo:rerun-if-env-changed=NOVA_GIT_COMMIT");
+  for path in [".git/HEAD", ".git/refs", ".git/packed-refs"] {
+    if Path::new(path).exists() {
+      println!("cargo:rerun-if-changed={path}");
+    }
+  }
+
+  let commit = std::env::var("NOVA_GIT_COMMIT").ok().or_else(|| {
+    let output = Command::new("git")
+      .args(["rev-parse", "HEAD"])
+      .output()
+      .ok()?;
+    output
+      .status
+      .success()
+      .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+  });
+  if let Some(commit) = commit.filter(|c| !c.is_empty()) {
+    println!("cargo:rustc-env=NOVA_GIT_COMMIT={commit}");
+  }
+}
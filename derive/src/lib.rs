@@ -0,0 +1,226 @@
+//! This crate implements `#[derive(StepCircuit)]`, which `nova-snark` re-exports as
+//! `nova_snark::traits::circuit::StepCircuit`. See the documentation there.
+#![deny(
+  warnings,
+  unused,
+  future_incompatible,
+  nonstandard_style,
+  rust_2018_idioms,
+  missing_docs
+)]
+#![forbid(unsafe_code)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+  parse_macro_input, parse_quote, Data, DeriveInput, Error, Expr, Fields, GenericParam, Type,
+};
+
+/// A field of a derived step circuit
+struct StepField {
+  ident: syn::Ident,
+  /// Whether the field is part of the state `z` rather than advice
+  state: bool,
+  /// The length of the field if it is an array, and `None` if it is a single field element
+  len: Option<Expr>,
+}
+
+/// Derives `StepCircuit` for a struct whose fields are field elements or fixed-size arrays of them
+#[proc_macro_derive(StepCircuit, attributes(step_circuit, state))]
+pub fn derive_step_circuit(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand(input)
+    .unwrap_or_else(Error::into_compile_error)
+    .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+  let name = &input.ident;
+  let vis = &input.vis;
+  let vars = format_ident!("{}Vars", name);
+  let field_ty = field_type(&input)?;
+
+  let Data::Struct(data) = &input.data else {
+    return Err(Error::new_spanned(
+      name,
+      "StepCircuit can only be derived for structs",
+    ));
+  };
+  let Fields::Named(named) = &data.fields else {
+    return Err(Error::new_spanned(
+      name,
+      "StepCircuit can only be derived for structs with named fields",
+    ));
+  };
+  let fields = named
+    .named
+    .iter()
+    .map(|field| StepField {
+      ident: field.ident.clone().unwrap(),
+      state: field.attrs.iter().any(|attr| attr.path().is_ident("state")),
+      len: match &field.ty {
+        Type::Array(array) => Some(array.len.clone()),
+        _ => None,
+      },
+    })
+    .collect::<Vec<_>>();
+
+  let nova = quote!(::nova_snark);
+  let num = quote!(#nova::frontend::num::AllocatedNum);
+
+  // the allocated fields, which `StepLogic::step` receives
+  let vars_fields = named.named.iter().zip(&fields).map(|(field, f)| {
+    let field_vis = &field.vis;
+    let ident = &f.ident;
+    match &f.len {
+      Some(len) => quote!(#field_vis #ident: [#num<F>; #len]),
+      None => quote!(#field_vis #ident: #num<F>),
+    }
+  });
+  let vars_doc = format!(
+    "The allocated fields of `{name}`, which its `StepLogic::step` receives (generated by \
+     `#[derive(StepCircuit)]`)"
+  );
+
+  let arity = fields.iter().filter(|f| f.state).map(|f| match &f.len {
+    Some(len) => quote!(#len),
+    None => quote!(1),
+  });
+  let arity = quote!(0usize #(+ #arity)*);
+
+  // the state is read from `z` and the advice is allocated, in the order of the fields
+  let assignments = fields.iter().map(|f| {
+    let ident = &f.ident;
+    let label = ident.to_string();
+    let value = match (f.state, &f.len) {
+      (true, None) => quote!({
+        __nova_offset += 1;
+        z[__nova_offset - 1].clone()
+      }),
+      (true, Some(len)) => quote!({
+        let v: [#num<#field_ty>; #len] =
+          ::core::array::from_fn(|i| z[__nova_offset + i].clone());
+        __nova_offset += #len;
+        v
+      }),
+      (false, None) => quote!(
+        #num::alloc(#nova::frontend::ConstraintSystem::namespace(cs, || #label), || {
+          Ok(self.#ident)
+        })?
+      ),
+      (false, Some(len)) => quote!({
+        let v = (0..#len)
+          .map(|i| {
+            #num::alloc(
+              #nova::frontend::ConstraintSystem::namespace(cs, || format!("{}[{}]", #label, i)),
+              || Ok(self.#ident[i]),
+            )
+          })
+          .collect::<Result<Vec<_>, _>>()?;
+        match <[#num<#field_ty>; #len]>::try_from(v) {
+          Ok(v) => v,
+          Err(_) => unreachable!(),
+        }
+      }),
+    };
+    quote!(#ident: #value)
+  });
+
+  let initial_inputs = fields.iter().filter(|f| f.state).map(|f| {
+    let ident = &f.ident;
+    match &f.len {
+      Some(_) => quote!(z.extend_from_slice(&self.#ident);),
+      None => quote!(z.push(self.#ident);),
+    }
+  });
+
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+  let mut step_where_clause = where_clause.cloned().unwrap_or_else(|| parse_quote!(where));
+  step_where_clause.predicates.push(parse_quote!(
+    Self: #nova::traits::circuit::StepLogic<#field_ty, Vars = #vars<#field_ty>>
+      + ::core::marker::Send
+      + ::core::marker::Sync
+      + ::core::clone::Clone
+  ));
+
+  Ok(quote! {
+    #[doc = #vars_doc]
+    #vis struct #vars<F: #nova::__private::PrimeField> {
+      #(#vars_fields,)*
+    }
+
+    impl #impl_generics #name #ty_generics #where_clause {
+      /// Returns the values of the state fields, in the order of the fields, which serve as the
+      /// initial inputs `z0` of the computation
+      #vis fn initial_inputs(&self) -> Vec<#field_ty> {
+        let mut z = Vec::with_capacity(#arity);
+        #(#initial_inputs)*
+        z
+      }
+    }
+
+    impl #impl_generics #nova::traits::circuit::StepCircuit<#field_ty> for #name #ty_generics
+      #step_where_clause
+    {
+      fn arity(&self) -> usize {
+        #arity
+      }
+
+      fn synthesize<CS: #nova::frontend::ConstraintSystem<#field_ty>>(
+        &self,
+        cs: &mut CS,
+        z: &[#num<#field_ty>],
+      ) -> Result<Vec<#num<#field_ty>>, #nova::frontend::SynthesisError> {
+        #[allow(unused)]
+        let mut __nova_offset = 0usize;
+        let vars = #vars {
+          #(#assignments,)*
+        };
+        #nova::traits::circuit::StepLogic::step(self, cs, vars)
+      }
+    }
+  })
+}
+
+/// Returns the field of the circuit, given by `#[step_circuit(field = ...)]`, and otherwise the
+/// first type parameter of the struct
+fn field_type(input: &DeriveInput) -> Result<Type, Error> {
+  let mut field = None;
+  for attr in input
+    .attrs
+    .iter()
+    .filter(|attr| attr.path().is_ident("step_circuit"))
+  {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("field") {
+        field = Some(meta.value()?.parse::<Type>()?);
+        Ok(())
+      } else {
+        Err(meta.error("unsupported step_circuit attribute"))
+      }
+    })?;
+  }
+  if let Some(field) = field {
+    return Ok(field);
+  }
+
+  input
+    .generics
+    .params
+    .iter()
+    .find_map(|param| match param {
+      GenericParam::Type(param) => {
+        let ident = &param.ident;
+        Some(parse_quote!(#ident))
+      }
+      _ => None,
+    })
+    .ok_or_else(|| {
+      Error::new(
+        Span::call_site(),
+        "the field of the circuit must be a type parameter or be given by \
+         #[step_circuit(field = ...)]",
+      )
+    })
+}
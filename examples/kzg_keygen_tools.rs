@@ -1,6 +1,7 @@
 use std::{
   fs::OpenOptions,
   io::{BufReader, BufWriter},
+  path::PathBuf,
 };
 
 use halo2curves::bn256;
@@ -8,6 +9,7 @@ use nova_snark::{
   provider::{
     check_sanity_of_ptau_file,
     hyperkzg::{CommitmentEngine, CommitmentKey},
+    key_file::{check_key_file, key_file_path},
     Bn256EngineKZG,
   },
   traits::commitment::CommitmentEngineTrait,
@@ -18,13 +20,8 @@ type E = Bn256EngineKZG;
 
 const KZG_KEY_DIR: &str = "/tmp/";
 
-pub fn get_key_file_path(num_gens: usize) -> String {
-  let id = std::any::type_name::<E>().chars()
-        .filter(|c| c.is_alphanumeric())  // Keep only alphanumeric characters
-        .collect::<String>();
-
-  let base_dir = KZG_KEY_DIR.trim_end_matches("/");
-  format!("{base_dir}/kzg_{id}_{num_gens}.keys")
+pub fn get_key_file_path(num_gens: usize) -> PathBuf {
+  key_file_path::<E>(KZG_KEY_DIR, num_gens)
 }
 
 const LABEL: &[u8; 4] = b"test";
@@ -67,14 +64,15 @@ fn keygen_save_large() {
     println!(
       "Saved {} keys to {} in {:?}, file size={}MB",
       MAX_NUM_GENS,
-      &path,
+      path.display(),
       dur,
       file.metadata().unwrap().len() / 1024 / 1024
     );
   } else {
-    println!("Key file already exists at {}", &path);
+    println!("Key file already exists at {}", path.display());
   }
 
+  check_key_file::<E>(&path, MAX_NUM_GENS).unwrap();
   let (res, dur) = timeit!(|| {
     let file = OpenOptions::new().read(true).open(&path).unwrap();
     let mut reader = BufReader::new(file);
@@ -83,7 +81,12 @@ fn keygen_save_large() {
 
   assert!(res.is_ok());
 
-  println!("Loaded {} keys from {} in {:?}", MAX_NUM_GENS, &path, dur);
+  println!(
+    "Loaded {} keys from {} in {:?}",
+    MAX_NUM_GENS,
+    path.display(),
+    dur
+  );
 }
 
 fn main() {
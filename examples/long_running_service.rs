@@ -0,0 +1,333 @@
+//! This example runs a proving service that folds a stream of events into an IVC proof, and
+//! exercises the persistence APIs together:
+//! * public parameters are cached on disk as separate prover and verifier parts, and the keys of
+//!   the compressing SNARK are cached next to them,
+//! * the recursive SNARK is checkpointed periodically and resumed from the last checkpoint after a
+//!   restart, once the checkpoint has been verified, and
+//! * a compressed proof is produced periodically, verified, and exported in bincode and calldata form.
+//!
+//! Usage: `cargo run --release --example long_running_service -- [<state directory>]`
+//!
+//! Without arguments, the example uses a fresh directory in the temporary directory, and runs two
+//! sessions of the service: the first one stops half-way, as if the process were restarted, and
+//! the second one resumes from the state persisted by the first. The final outputs are checked
+//! against a direct computation, so the example doubles as an integration test.
+use ff::{Field, PrimeField};
+use nova_snark::{
+  frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+  nova::{
+    CompressedSNARK, ProverKey, ProverParams, PublicParams, RecursiveSNARK, VerifierKey,
+    VerifierParams,
+  },
+  provider::{Bn256EngineKZG, GrumpkinEngine},
+  traits::{calldata::to_calldata, circuit::StepCircuit, snark::RelaxedR1CSSNARKTrait, Engine},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+  error::Error,
+  fs::{self, File},
+  io::{BufReader, BufWriter, Write},
+  path::{Path, PathBuf},
+  time::Instant,
+};
+
+type E1 = Bn256EngineKZG;
+type E2 = GrumpkinEngine;
+type EE1 = nova_snark::provider::hyperkzg::EvaluationEngine<E1>;
+type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
+type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>; // non-preprocessing SNARK
+type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>; // non-preprocessing SNARK
+type F = <E1 as Engine>::Scalar;
+type C = EventCircuit<F>;
+
+/// The number of steps after which the recursive SNARK is checkpointed
+const CHECKPOINT_EVERY: usize = 2;
+/// The number of steps after which a compressed proof is produced
+const COMPRESS_EVERY: usize = 4;
+/// The factor by which the accumulator is multiplied before absorbing an event
+const MULTIPLIER: u64 = 7;
+
+/// A step circuit that counts events and folds them into an accumulator:
+/// `(count, acc) -> (count + 1, acc * MULTIPLIER + event)`
+#[derive(Clone, Debug, Default)]
+struct EventCircuit<F: PrimeField> {
+  event: F,
+}
+
+impl<F: PrimeField> StepCircuit<F> for EventCircuit<F> {
+  fn arity(&self) -> usize {
+    2
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    let (count, acc) = (&z[0], &z[1]);
+    let event = AllocatedNum::alloc(cs.namespace(|| "event"), || Ok(self.event))?;
+
+    let count_next = AllocatedNum::alloc(cs.namespace(|| "count_next"), || {
+      count
+        .get_value()
+        .map(|count| count + F::ONE)
+        .ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    cs.enforce(
+      || "count_next = count + 1",
+      |lc| lc + count.get_variable() + CS::one(),
+      |lc| lc + CS::one(),
+      |lc| lc + count_next.get_variable(),
+    );
+
+    let multiplier = F::from(MULTIPLIER);
+    let acc_next = AllocatedNum::alloc(cs.namespace(|| "acc_next"), || {
+      acc
+        .get_value()
+        .map(|acc| absorb(acc, self.event))
+        .ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    cs.enforce(
+      || "acc_next = acc * multiplier + event",
+      |lc| lc + (multiplier, acc.get_variable()) + event.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + acc_next.get_variable(),
+    );
+
+    Ok(vec![count_next, acc_next])
+  }
+}
+
+/// Folds an event into the accumulator, as the step circuit does
+fn absorb<F: PrimeField>(acc: F, event: F) -> F {
+  acc * F::from(MULTIPLIER) + event
+}
+
+/// The event that the service receives at step `i`; a real service would read it from a queue
+fn event(i: usize) -> F {
+  F::from((i * i + 1) as u64)
+}
+
+fn z0() -> Vec<F> {
+  vec![F::ZERO, F::ONE]
+}
+
+fn load<T: DeserializeOwned>(path: &Path) -> Result<T, Box<dyn Error>> {
+  Ok(bincode::deserialize_from(BufReader::new(File::open(
+    path,
+  )?))?)
+}
+
+/// Writes `value` to a temporary file that is then renamed to `path`, so that a crash while
+/// writing never leaves a truncated file behind
+fn save<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+  let tmp = path.with_extension("tmp");
+  {
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    bincode::serialize_into(&mut writer, value)?;
+    writer.flush()?;
+  }
+  fs::rename(tmp, path)?;
+  Ok(())
+}
+
+/// The state of the service: its keys and the recursive SNARK being extended
+struct Service {
+  dir: PathBuf,
+  pp: PublicParams<E1, E2, C>,
+  pk: ProverKey<E1, E2, C, S1, S2>,
+  vk: VerifierKey<E1, E2, C, S1, S2>,
+  recursive_snark: Option<RecursiveSNARK<E1, E2, C>>,
+}
+
+impl Service {
+  /// Starts the service, loading its keys and checkpoint from `dir` if they are present
+  fn start(dir: &Path) -> Result<Self, Box<dyn Error>> {
+    fs::create_dir_all(dir.join("proofs"))?;
+    let pp = Self::public_params(dir)?;
+    pp.prepare_commitment_keys();
+    let (pk, vk) = Self::compression_keys(dir, &pp)?;
+    let recursive_snark = Self::resume(dir, &pp)?;
+    Ok(Self {
+      dir: dir.to_path_buf(),
+      pp,
+      pk,
+      vk,
+      recursive_snark,
+    })
+  }
+
+  /// Loads the public parameters from their prover and verifier parts, or produces and caches them
+  fn public_params(dir: &Path) -> Result<PublicParams<E1, E2, C>, Box<dyn Error>> {
+    let (prover_path, verifier_path) = (
+      dir.join("prover_params.bin"),
+      dir.join("verifier_params.bin"),
+    );
+    let start = Instant::now();
+    if prover_path.exists() && verifier_path.exists() {
+      let prover: ProverParams<E1, E2, C> = load(&prover_path)?;
+      let verifier: VerifierParams<E1, E2, C> = load(&verifier_path)?;
+      let pp = PublicParams::join(prover, verifier)?;
+      println!("Loaded cached public parameters in {:?}", start.elapsed());
+      return Ok(pp);
+    }
+
+    let pp = PublicParams::<E1, E2, C>::setup(&C::default(), &*S1::ck_floor(), &*S2::ck_floor())?;
+    let (prover, verifier) = pp.split();
+    save(&prover_path, &prover)?;
+    save(&verifier_path, &verifier)?;
+    println!(
+      "Produced and cached public parameters in {:?}",
+      start.elapsed()
+    );
+    Ok(PublicParams::join(prover, verifier)?)
+  }
+
+  /// Loads the keys of the compressing SNARK if they were derived from `pp`, or produces and caches them
+  #[allow(clippy::type_complexity)]
+  fn compression_keys(
+    dir: &Path,
+    pp: &PublicParams<E1, E2, C>,
+  ) -> Result<(ProverKey<E1, E2, C, S1, S2>, VerifierKey<E1, E2, C, S1, S2>), Box<dyn Error>> {
+    let path = dir.join("compression_keys.bin");
+    let start = Instant::now();
+    if path.exists() {
+      let (pk, vk): (ProverKey<E1, E2, C, S1, S2>, VerifierKey<E1, E2, C, S1, S2>) = load(&path)?;
+      let verifier_params: VerifierParams<E1, E2, C> = load(&dir.join("verifier_params.bin"))?;
+      if vk.matches(&verifier_params) {
+        println!("Loaded cached compression keys in {:?}", start.elapsed());
+        return Ok((pk, vk));
+      }
+      println!("Cached compression keys belong to other public parameters, producing new ones");
+    }
+
+    let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(pp)?;
+    save(&path, &(&pk, &vk))?;
+    println!(
+      "Produced and cached compression keys in {:?}",
+      start.elapsed()
+    );
+    Ok((pk, vk))
+  }
+
+  /// Loads and verifies the last checkpoint, if any
+  fn resume(
+    dir: &Path,
+    pp: &PublicParams<E1, E2, C>,
+  ) -> Result<Option<RecursiveSNARK<E1, E2, C>>, Box<dyn Error>> {
+    let path = dir.join("checkpoint.bin");
+    if !path.exists() {
+      return Ok(None);
+    }
+
+    let recursive_snark: RecursiveSNARK<E1, E2, C> = load(&path)?;
+    // a checkpoint is only trusted once it verifies against the current public parameters
+    recursive_snark.verify(pp, recursive_snark.num_steps(), &z0())?;
+    println!(
+      "Resumed from a checkpoint after {} steps",
+      recursive_snark.num_steps()
+    );
+    Ok(Some(recursive_snark))
+  }
+
+  fn num_steps(&self) -> usize {
+    self
+      .recursive_snark
+      .as_ref()
+      .map_or(0, |recursive_snark| recursive_snark.num_steps())
+  }
+
+  /// Proves steps until `num_steps` events have been folded, checkpointing and compressing on the way
+  fn run_until(&mut self, num_steps: usize) -> Result<(), Box<dyn Error>> {
+    while self.num_steps() < num_steps {
+      let circuit = EventCircuit {
+        event: event(self.num_steps()),
+      };
+
+      let start = Instant::now();
+      let recursive_snark = match self.recursive_snark.as_mut() {
+        Some(recursive_snark) => recursive_snark,
+        None => self
+          .recursive_snark
+          .insert(RecursiveSNARK::new(&self.pp, &circuit, &z0())?),
+      };
+      recursive_snark.prove_step(&self.pp, &circuit)?;
+      let step = recursive_snark.num_steps();
+      println!("Step {step}: proved in {:?}", start.elapsed());
+
+      if step % CHECKPOINT_EVERY == 0 {
+        save(&self.dir.join("checkpoint.bin"), recursive_snark)?;
+        println!("Step {step}: checkpointed");
+      }
+      if step % COMPRESS_EVERY == 0 {
+        self.export_proof()?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Compresses the recursive SNARK, verifies the compressed proof, and exports it
+  fn export_proof(&self) -> Result<(), Box<dyn Error>> {
+    let recursive_snark = self
+      .recursive_snark
+      .as_ref()
+      .ok_or("no step has been proved yet")?;
+    let step = recursive_snark.num_steps();
+
+    let start = Instant::now();
+    let compressed_snark = CompressedSNARK::prove(&self.pp, &self.pk, recursive_snark)?;
+    compressed_snark.verify(&self.vk, step, &z0())?;
+
+    let path = self.dir.join("proofs").join(format!("proof_{step}.bin"));
+    save(&path, &compressed_snark)?;
+    let calldata = to_calldata(&compressed_snark);
+    let hex = calldata
+      .iter()
+      .map(|b| format!("{b:02x}"))
+      .collect::<String>();
+    fs::write(path.with_extension("calldata"), format!("0x{hex}"))?;
+    println!(
+      "Step {step}: exported a compressed proof of {} bytes ({} bytes of calldata) in {:?}",
+      bincode::serialized_size(&compressed_snark)?,
+      calldata.len(),
+      start.elapsed()
+    );
+    Ok(())
+  }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let (dir, demo) = match std::env::args().nth(1) {
+    Some(dir) => (PathBuf::from(dir), false),
+    None => (std::env::temp_dir().join("nova_long_running_service"), true),
+  };
+  if demo && dir.exists() {
+    fs::remove_dir_all(&dir)?;
+  }
+  println!("State directory: {}", dir.display());
+
+  let num_steps = 2 * COMPRESS_EVERY + 2;
+  if demo {
+    println!("=========================================================");
+    println!("First session, stopped after {} steps", num_steps / 2);
+    println!("---------------------------------------------------------");
+    let mut service = Service::start(&dir)?;
+    service.run_until(num_steps / 2)?;
+  }
+
+  println!("=========================================================");
+  println!("Session running until {num_steps} steps");
+  println!("---------------------------------------------------------");
+  let mut service = Service::start(&dir)?;
+  service.run_until(num_steps)?;
+  println!("=========================================================");
+
+  // the steps proved before the restart are only re-proved from the last checkpoint, so the
+  // outputs must match an uninterrupted computation
+  let expected = (0..num_steps).fold(z0(), |z, i| vec![z[0] + F::ONE, absorb(z[1], event(i))]);
+  let recursive_snark = service.recursive_snark.as_ref().ok_or("no steps")?;
+  recursive_snark.verify(&service.pp, num_steps, &z0())?;
+  assert_eq!(recursive_snark.outputs(), expected.as_slice());
+  println!("Outputs after {num_steps} steps match a direct computation");
+  Ok(())
+}
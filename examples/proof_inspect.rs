@@ -0,0 +1,115 @@
+//! This example prints a human-readable summary of serialized public parameters, verifier keys,
+//! and proofs, which helps debug mismatches between prover and verifier deployments.
+//!
+//! Usage: `cargo run --release --example proof_inspect -- [<kind> <file>]...`
+//! where `<kind>` is one of `pp`, `recursive`, `vk`, or `compressed`, and `<file>` holds the
+//! bincode serialization of a value of that kind, as produced by `bincode::serialize_into`.
+//! The values are assumed to use Bn256 with HyperKZG as the primary engine, Grumpkin with IPA as
+//! the secondary engine, and Spartan without preprocessing as the compressing SNARK.
+//!
+//! Without arguments, the example produces small parameters and proofs for a trivial circuit,
+//! writes them to a temporary directory, and inspects those files.
+use ff::Field;
+use nova_snark::{
+  nova::{CompressedSNARK, Inspection, PublicParams, RecursiveSNARK, VerifierKey},
+  provider::{Bn256EngineKZG, GrumpkinEngine},
+  traits::{circuit::TrivialCircuit, snark::RelaxedR1CSSNARKTrait, Engine},
+};
+use serde::de::DeserializeOwned;
+use std::{
+  error::Error,
+  fs::File,
+  io::{BufReader, BufWriter},
+  path::{Path, PathBuf},
+};
+
+type E1 = Bn256EngineKZG;
+type E2 = GrumpkinEngine;
+type EE1 = nova_snark::provider::hyperkzg::EvaluationEngine<E1>;
+type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
+type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>; // non-preprocessing SNARK
+type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>; // non-preprocessing SNARK
+
+// the step circuit is not part of the serialization, so any circuit can be used to read it
+type C = TrivialCircuit<<E1 as Engine>::Scalar>;
+
+fn load<T: DeserializeOwned>(path: &Path) -> Result<T, Box<dyn Error>> {
+  let file = File::open(path)?;
+  Ok(bincode::deserialize_from(BufReader::new(file))?)
+}
+
+fn save<T: serde::Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+  let file = File::create(path)?;
+  Ok(bincode::serialize_into(BufWriter::new(file), value)?)
+}
+
+fn inspect(kind: &str, path: &Path) -> Result<Inspection, Box<dyn Error>> {
+  Ok(match kind {
+    "pp" => load::<PublicParams<E1, E2, C>>(path)?.inspect(),
+    "recursive" => load::<RecursiveSNARK<E1, E2, C>>(path)?.inspect(),
+    "vk" => load::<VerifierKey<E1, E2, C, S1, S2>>(path)?.inspect(),
+    "compressed" => load::<CompressedSNARK<E1, E2, C, S1, S2>>(path)?.inspect(),
+    _ => return Err(format!("unknown kind `{kind}`").into()),
+  })
+}
+
+/// Produces parameters and proofs for a trivial circuit and returns the files they were written to
+fn demo_files() -> Result<Vec<(String, PathBuf)>, Box<dyn Error>> {
+  let dir = std::env::temp_dir();
+  let circuit = C::default();
+  let num_steps = 3;
+
+  println!("Producing public parameters and proofs for a trivial circuit...");
+  let pp = PublicParams::<E1, E2, C>::setup(&circuit, &*S1::ck_floor(), &*S2::ck_floor())?;
+  let z0 = [<E1 as Engine>::Scalar::ONE];
+  let mut recursive_snark = RecursiveSNARK::<E1, E2, C>::new(&pp, &circuit, &z0)?;
+  for _ in 0..num_steps {
+    recursive_snark.prove_step(&pp, &circuit)?;
+  }
+  let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp)?;
+  let compressed_snark = CompressedSNARK::<_, _, _, S1, S2>::prove(&pp, &pk, &recursive_snark)?;
+  compressed_snark.verify(&vk, num_steps, &z0)?;
+
+  let files = vec![
+    ("pp".to_string(), dir.join("nova_inspect_pp.bin")),
+    (
+      "recursive".to_string(),
+      dir.join("nova_inspect_recursive.bin"),
+    ),
+    ("vk".to_string(), dir.join("nova_inspect_vk.bin")),
+    (
+      "compressed".to_string(),
+      dir.join("nova_inspect_compressed.bin"),
+    ),
+  ];
+  save(&files[0].1, &pp)?;
+  save(&files[1].1, &recursive_snark)?;
+  save(&files[2].1, &vk)?;
+  save(&files[3].1, &compressed_snark)?;
+  Ok(files)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let args = std::env::args().skip(1).collect::<Vec<_>>();
+  if args.len() % 2 != 0 {
+    return Err("expected pairs of `<kind> <file>` arguments".into());
+  }
+
+  let files = if args.is_empty() {
+    demo_files()?
+  } else {
+    args
+      .chunks(2)
+      .map(|pair| (pair[0].clone(), PathBuf::from(&pair[1])))
+      .collect()
+  };
+
+  for (kind, path) in files {
+    println!("=========================================================");
+    println!("{kind}: {}", path.display());
+    println!("---------------------------------------------------------");
+    print!("{}", inspect(&kind, &path)?);
+  }
+  println!("=========================================================");
+  Ok(())
+}
@@ -0,0 +1,175 @@
+//! This example proves a hash chain `H(...H(H(seed, b_0), b_1)..., b_{n-1})` over the blocks `b_i`
+//! of a file, which it reads block by block and absorbs one block per step. It serves as a template
+//! for data-provenance applications: the prover never holds more than one block of the data, and
+//! anyone holding the data can recompute the final link to check what the proof is about.
+//!
+//! Each step is proven with its own instance of the step circuit, which holds the block of that
+//! step as non-deterministic advice. The parameters are set up once with an empty block, as the
+//! shape of the circuit only depends on the block size.
+//!
+//! `cargo run --release --example stream_hashchain -- [path]`
+//!
+//! Without a path, the example hashes a file of random data that it writes to a temporary directory.
+use ff::Field;
+use nova_snark::{
+  frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+  gadgets::hash_chain::{block_len, encode_block, hash_link, hash_link_gadget},
+  nova::{CompressedSNARK, PublicParams, RecursiveSNARK},
+  provider::{poseidon::PoseidonConstantsCircuit, Bn256EngineKZG, GrumpkinEngine},
+  traits::{circuit::StepCircuit, snark::RelaxedR1CSSNARKTrait, Engine},
+};
+use rand::RngCore;
+use std::{
+  fs::File,
+  io::{self, BufReader, Read},
+  path::PathBuf,
+  time::Instant,
+};
+
+type E1 = Bn256EngineKZG;
+type E2 = GrumpkinEngine;
+type EE1 = nova_snark::provider::hyperkzg::EvaluationEngine<E1>;
+type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
+type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>; // non-preprocessing SNARK
+type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>; // non-preprocessing SNARK
+type F = <E1 as Engine>::Scalar;
+
+/// The number of bytes absorbed per step
+const BLOCK_SIZE: usize = 4096;
+
+/// A step that hashes the running link `z` with a block of the stream
+#[derive(Clone)]
+struct StreamHashCircuit {
+  constants: PoseidonConstantsCircuit<F>,
+  block: Vec<F>,
+}
+
+impl StreamHashCircuit {
+  fn new(block: &[u8]) -> Self {
+    Self {
+      constants: PoseidonConstantsCircuit::default(),
+      block: encode_block(block, BLOCK_SIZE).unwrap(),
+    }
+  }
+}
+
+impl StepCircuit<F> for StreamHashCircuit {
+  fn arity(&self) -> usize {
+    1
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    assert_eq!(self.block.len(), block_len::<F>(BLOCK_SIZE));
+
+    // the block is advice: it is only bound to the output through the hash
+    let block = self
+      .block
+      .iter()
+      .enumerate()
+      .map(|(i, x)| AllocatedNum::alloc(cs.namespace(|| format!("block_{i}")), || Ok(*x)))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let z_out = hash_link_gadget(cs.namespace(|| "link"), &self.constants, &z[0], &block)?;
+    Ok(vec![z_out])
+  }
+}
+
+/// Fills `buf` from `reader`, returning fewer bytes than its length only at the end of the stream
+fn read_block(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+  let mut len = 0;
+  while len < buf.len() {
+    match reader.read(&mut buf[len..])? {
+      0 => break,
+      n => len += n,
+    }
+  }
+  Ok(len)
+}
+
+/// Writes a file of random data to hash when no path is given
+fn sample_file() -> PathBuf {
+  let path = std::env::temp_dir().join("nova_stream_hashchain.bin");
+  let mut data = vec![0u8; 5 * BLOCK_SIZE + 1000];
+  rand::thread_rng().fill_bytes(&mut data);
+  std::fs::write(&path, data).unwrap();
+  path
+}
+
+fn main() {
+  println!("=========================================================");
+  println!("Nova-based hash chain over a stream");
+  println!("=========================================================");
+
+  let path = std::env::args()
+    .nth(1)
+    .map(PathBuf::from)
+    .unwrap_or_else(sample_file);
+  let mut reader = BufReader::new(File::open(&path).unwrap());
+  println!("Hashing {} in blocks of {BLOCK_SIZE} bytes", path.display());
+
+  // produce public parameters, whose shape does not depend on the contents of the block
+  let start = Instant::now();
+  let pp = PublicParams::<E1, E2, StreamHashCircuit>::setup(
+    &StreamHashCircuit::new(&[]),
+    &*S1::ck_floor(),
+    &*S2::ck_floor(),
+  )
+  .unwrap();
+  println!("PublicParams::setup, took {:?} ", start.elapsed());
+  println!(
+    "Number of constraints per step (primary circuit): {}",
+    pp.num_constraints().0
+  );
+
+  // the seed, and the chain as recomputed by anyone holding the data
+  let seed = F::ZERO;
+  let constants = PoseidonConstantsCircuit::<F>::default();
+  let mut expected = seed;
+
+  // prove one step per block, reading the next block only once the previous one is proven; an
+  // empty stream is hashed as a single empty block
+  let mut buf = vec![0u8; BLOCK_SIZE];
+  let len = read_block(&mut reader, &mut buf).unwrap();
+  let mut circuit = StreamHashCircuit::new(&buf[..len]);
+  let mut recursive_snark = RecursiveSNARK::<E1, E2, _>::new(&pp, &circuit, &[seed]).unwrap();
+  let mut num_steps = 0;
+  loop {
+    let start = Instant::now();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    expected = hash_link(&constants, &expected, &circuit.block);
+    num_steps += 1;
+    println!(
+      "RecursiveSNARK::prove_step {num_steps}: took {:?}",
+      start.elapsed()
+    );
+
+    let len = read_block(&mut reader, &mut buf).unwrap();
+    if len == 0 {
+      break;
+    }
+    circuit = StreamHashCircuit::new(&buf[..len]);
+  }
+
+  // verify the recursive SNARK, whose output is the final link of the chain
+  println!("Verifying a RecursiveSNARK...");
+  let z_n = recursive_snark.verify(&pp, num_steps, &[seed]).unwrap();
+  assert_eq!(z_n, vec![expected]);
+  println!("RecursiveSNARK::verify: ok, final link {:?}", z_n[0]);
+
+  // produce and verify a compressed SNARK
+  println!("Generating a CompressedSNARK using Spartan with HyperKZG...");
+  let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+  let start = Instant::now();
+  let compressed_snark = CompressedSNARK::<_, _, _, S1, S2>::prove(&pp, &pk, &recursive_snark);
+  let compressed_snark = compressed_snark.unwrap();
+  println!("CompressedSNARK::prove: took {:?}", start.elapsed());
+
+  let res = compressed_snark.verify(&vk, num_steps, &[seed]);
+  assert_eq!(res.unwrap(), vec![expected]);
+  println!("CompressedSNARK::verify: ok");
+  println!("=========================================================");
+}
@@ -0,0 +1,51 @@
+//! This example traces the proving and verification of a recursive SNARK and its compression.
+//!
+//! It exports the spans to a local Jaeger instance by default, e.g., one started with
+//! `docker run -p 16686:16686 -p 4318:4318 jaegertracing/all-in-one`, to any other OTLP collector
+//! with `otlp <endpoint>`, or to a file for `chrome://tracing` with `chrome <path>`:
+//!
+//! `cargo run --release --features telemetry --example telemetry -- chrome trace.json`
+use ff::Field;
+use nova_snark::{
+  nova::{CompressedSNARK, PublicParams, RecursiveSNARK},
+  provider::{ipa_pc, PallasEngine, VestaEngine},
+  spartan::snark::RelaxedR1CSSNARK,
+  telemetry::{init_chrome_trace, init_jaeger, init_otlp},
+  traits::{circuit::NonTrivialCircuit, snark::RelaxedR1CSSNARKTrait, Engine},
+};
+
+type E1 = PallasEngine;
+type E2 = VestaEngine;
+type F = <E1 as Engine>::Scalar;
+type C = NonTrivialCircuit<F>;
+type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+
+fn main() {
+  let args = std::env::args().skip(1).collect::<Vec<_>>();
+  let guard = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+    [] => init_jaeger("nova-example"),
+    ["otlp", endpoint] => init_otlp(endpoint),
+    ["chrome", path] => init_chrome_trace(path),
+    _ => panic!("usage: telemetry [otlp <endpoint> | chrome <path>]"),
+  }
+  .expect("failed to install the tracing pipeline");
+
+  let circuit = C::new(1 << 12);
+  let pp = PublicParams::<E1, E2, C>::setup(&circuit, &*S::<E1>::ck_floor(), &*S::<E2>::ck_floor())
+    .unwrap();
+
+  let num_steps = 5;
+  let z0 = vec![F::ONE];
+  let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+  for _ in 0..num_steps {
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+  }
+  recursive_snark.verify(&pp, num_steps, &z0).unwrap();
+
+  let (pk, vk) = CompressedSNARK::<_, _, _, S<E1>, S<E2>>::setup(&pp).unwrap();
+  let compressed_snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+  compressed_snark.verify(&vk, num_steps, &z0).unwrap();
+
+  // flushes the spans
+  drop(guard);
+}
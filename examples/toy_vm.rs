@@ -0,0 +1,366 @@
+//! This example proves the execution of a program on a toy register machine.
+//! The machine has a program counter and two registers `a` and `b`, and supports five instructions.
+//! Each step of Nova's recursion executes one instruction of a fixed program, which computes 5!.
+//!
+//! Nova folds a single step circuit, so the step circuit of the machine synthesizes every
+//! instruction and selects the effect of the one at the program counter. The instruction is
+//! fetched from the program with a one-hot encoding of the program counter, so a proof attests
+//! that the program was followed.
+use ff::PrimeField;
+use flate2::{write::ZlibEncoder, Compression};
+use nova_snark::{
+  frontend::{
+    num::AllocatedNum, AllocatedBit, ConstraintSystem, LinearCombination, SynthesisError,
+  },
+  nova::{CompressedSNARK, PublicParams, RecursiveSNARK},
+  provider::{Bn256EngineKZG, GrumpkinEngine},
+  traits::{circuit::StepCircuit, snark::RelaxedR1CSSNARKTrait, Engine},
+};
+use std::time::Instant;
+
+type E1 = Bn256EngineKZG;
+type E2 = GrumpkinEngine;
+type EE1 = nova_snark::provider::hyperkzg::EvaluationEngine<E1>;
+type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
+type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>; // non-preprocessing SNARK
+type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>; // non-preprocessing SNARK
+
+/// An instruction of the toy machine
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Instruction {
+  /// a <- a + imm
+  AddImm(u64),
+  /// a <- a * b
+  Mul,
+  /// b <- b - 1
+  DecB,
+  /// jump to the target if b is not zero
+  JumpIfB(usize),
+  /// stay at the current instruction
+  Halt,
+}
+
+/// The state of the toy machine
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct State {
+  pc: usize,
+  a: u64,
+  b: u64,
+}
+
+impl State {
+  fn to_z<F: PrimeField>(self) -> Vec<F> {
+    vec![F::from(self.pc as u64), F::from(self.a), F::from(self.b)]
+  }
+
+  /// Executes one instruction natively
+  fn step(self, program: &[Instruction]) -> Self {
+    let next = Self {
+      pc: self.pc + 1,
+      ..self
+    };
+    match program[self.pc] {
+      Instruction::AddImm(imm) => Self {
+        a: self.a + imm,
+        ..next
+      },
+      Instruction::Mul => Self {
+        a: self.a * self.b,
+        ..next
+      },
+      Instruction::DecB => Self {
+        b: self.b - 1,
+        ..next
+      },
+      Instruction::JumpIfB(target) if self.b != 0 => Self { pc: target, ..self },
+      Instruction::JumpIfB(_) => next,
+      Instruction::Halt => self,
+    }
+  }
+}
+
+/// The step circuit of the toy machine, which executes one instruction of `program`
+#[derive(Clone, Debug)]
+struct ToyVmCircuit {
+  program: Vec<Instruction>,
+}
+
+impl ToyVmCircuit {
+  /// Returns the linear combination of the selectors with the coefficients returned by `coeff`
+  /// for each address and instruction of the program
+  fn select<F: PrimeField>(
+    &self,
+    selectors: &[AllocatedBit],
+    coeff: impl Fn(usize, &Instruction) -> Option<F>,
+  ) -> LinearCombination<F> {
+    self
+      .program
+      .iter()
+      .enumerate()
+      .zip(selectors)
+      .filter_map(|((i, instr), s)| coeff(i, instr).map(|c| (c, s.get_variable())))
+      .fold(LinearCombination::zero(), |lc, (c, v)| lc + (c, v))
+  }
+
+  /// Returns the instruction at the program counter, which is only available when generating a witness
+  fn fetch<F: PrimeField>(&self, pc: &AllocatedNum<F>) -> Result<Instruction, SynthesisError> {
+    let pc = pc.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+    (0..self.program.len())
+      .find(|i| F::from(*i as u64) == pc)
+      .map(|i| self.program[i])
+      .ok_or(SynthesisError::Unsatisfiable)
+  }
+}
+
+impl<F: PrimeField> StepCircuit<F> for ToyVmCircuit {
+  fn arity(&self) -> usize {
+    3
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    let (pc, a, b) = (&z[0], &z[1], &z[2]);
+
+    // fetch: one selector per instruction of the program, set for the instruction at pc
+    let selectors = (0..self.program.len())
+      .map(|i| {
+        let value = pc.get_value().map(|pc| pc == F::from(i as u64));
+        AllocatedBit::alloc(cs.namespace(|| format!("selector {i}")), value)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let one_hot = self.select(&selectors, |_, _| Some(F::ONE));
+    cs.enforce(
+      || "exactly one instruction is selected",
+      |_| one_hot,
+      |lc| lc + CS::one(),
+      |lc| lc + CS::one(),
+    );
+    let address = self.select(&selectors, |i, _| Some(F::from(i as u64)));
+    cs.enforce(
+      || "the selected instruction is at pc",
+      |_| address,
+      |lc| lc + CS::one(),
+      |lc| lc + pc.get_variable(),
+    );
+
+    // decode: the immediate of the selected instruction is zero unless it is an `AddImm`,
+    // and the jump target relative to the next instruction is zero unless it is a `JumpIfB`
+    let add_imm = self.select(&selectors, |_, instr| match instr {
+      Instruction::AddImm(imm) => Some(F::from(*imm)),
+      _ => None,
+    });
+    let jump_offset = self.select(&selectors, |i, instr| match instr {
+      Instruction::JumpIfB(target) => Some(F::from(*target as u64) - F::from(i as u64 + 1)),
+      _ => None,
+    });
+    let is = |expected: Instruction| {
+      self.select(&selectors, move |_, instr| {
+        (*instr == expected).then_some(F::ONE)
+      })
+    };
+    let (is_mul, is_dec_b, is_halt) = (
+      is(Instruction::Mul),
+      is(Instruction::DecB),
+      is(Instruction::Halt),
+    );
+
+    // b_nonzero = 1 if b != 0, and 0 otherwise
+    let b_inv = AllocatedNum::alloc(cs.namespace(|| "b_inv"), || {
+      let b = b.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+      Ok(b.invert().unwrap_or(F::ZERO))
+    })?;
+    let b_nonzero = AllocatedNum::alloc(cs.namespace(|| "b_nonzero"), || {
+      let b = b.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+      Ok(if b.is_zero_vartime() { F::ZERO } else { F::ONE })
+    })?;
+    cs.enforce(
+      || "b_nonzero = b * b_inv",
+      |lc| lc + b.get_variable(),
+      |lc| lc + b_inv.get_variable(),
+      |lc| lc + b_nonzero.get_variable(),
+    );
+    cs.enforce(
+      || "b * (1 - b_nonzero) = 0",
+      |lc| lc + b.get_variable(),
+      |lc| lc + CS::one() - b_nonzero.get_variable(),
+      |lc| lc,
+    );
+
+    // execute: a' = a + add_imm + is_mul * (a * b - a)
+    let ab = a.mul(cs.namespace(|| "a * b"), b)?;
+    let mul_delta = AllocatedNum::alloc(cs.namespace(|| "mul_delta"), || {
+      let is_mul = self.fetch(pc)? == Instruction::Mul;
+      let (a, ab) = (a.get_value(), ab.get_value());
+      let delta = ab.zip(a).map(|(ab, a)| ab - a);
+      let delta = delta.ok_or(SynthesisError::AssignmentMissing)?;
+      Ok(if is_mul { delta } else { F::ZERO })
+    })?;
+    cs.enforce(
+      || "mul_delta = is_mul * (a * b - a)",
+      |_| is_mul,
+      |lc| lc + ab.get_variable() - a.get_variable(),
+      |lc| lc + mul_delta.get_variable(),
+    );
+    let a_next = AllocatedNum::alloc(cs.namespace(|| "a_next"), || {
+      let a = a.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+      let mul_delta = mul_delta
+        .get_value()
+        .ok_or(SynthesisError::AssignmentMissing)?;
+      let imm = match self.fetch(pc)? {
+        Instruction::AddImm(imm) => F::from(imm),
+        _ => F::ZERO,
+      };
+      Ok(a + imm + mul_delta)
+    })?;
+    cs.enforce(
+      || "a_next = a + add_imm + mul_delta",
+      |lc| lc + a.get_variable() + &add_imm + mul_delta.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + a_next.get_variable(),
+    );
+
+    // b' = b - is_dec_b
+    let b_next = AllocatedNum::alloc(cs.namespace(|| "b_next"), || {
+      let b = b.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+      let dec = self.fetch(pc)? == Instruction::DecB;
+      Ok(if dec { b - F::ONE } else { b })
+    })?;
+    cs.enforce(
+      || "b_next = b - is_dec_b",
+      |lc| lc + b.get_variable() - &is_dec_b,
+      |lc| lc + CS::one(),
+      |lc| lc + b_next.get_variable(),
+    );
+
+    // pc' = pc + 1 - is_halt + b_nonzero * jump_offset
+    let jump_delta = AllocatedNum::alloc(cs.namespace(|| "jump_delta"), || {
+      let b_nonzero = b_nonzero
+        .get_value()
+        .ok_or(SynthesisError::AssignmentMissing)?;
+      let pc_value = pc.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+      Ok(match self.fetch(pc)? {
+        Instruction::JumpIfB(target) => b_nonzero * (F::from(target as u64) - pc_value - F::ONE),
+        _ => F::ZERO,
+      })
+    })?;
+    cs.enforce(
+      || "jump_delta = b_nonzero * jump_offset",
+      |lc| lc + b_nonzero.get_variable(),
+      |_| jump_offset,
+      |lc| lc + jump_delta.get_variable(),
+    );
+    let pc_next = AllocatedNum::alloc(cs.namespace(|| "pc_next"), || {
+      let pc_value = pc.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+      let jump_delta = jump_delta
+        .get_value()
+        .ok_or(SynthesisError::AssignmentMissing)?;
+      Ok(match self.fetch(pc)? {
+        Instruction::Halt => pc_value,
+        _ => pc_value + F::ONE + jump_delta,
+      })
+    })?;
+    cs.enforce(
+      || "pc_next = pc + 1 - is_halt + jump_delta",
+      |lc| lc + pc.get_variable() + CS::one() - &is_halt + jump_delta.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + pc_next.get_variable(),
+    );
+    Ok(vec![pc_next, a_next, b_next])
+  }
+}
+
+fn main() {
+  println!("=========================================================");
+  println!("Nova-based toy VM example");
+  println!("=========================================================");
+
+  // computes 5! in register a
+  let program = vec![
+    Instruction::AddImm(1),  // 0: a <- 1
+    Instruction::Mul,        // 1: a <- a * b
+    Instruction::DecB,       // 2: b <- b - 1
+    Instruction::JumpIfB(1), // 3: loop while b != 0
+    Instruction::Halt,       // 4
+  ];
+  let init = State { pc: 0, a: 0, b: 5 };
+
+  // execute the program natively to learn the length of the trace
+  let mut trace = vec![init];
+  while program[trace.last().unwrap().pc] != Instruction::Halt {
+    trace.push(trace.last().unwrap().step(&program));
+  }
+  let num_steps = trace.len() - 1;
+  let last = *trace.last().unwrap();
+  println!("Executed {num_steps} instructions, final state: {last:?}");
+
+  let circuit = ToyVmCircuit { program };
+
+  // produce public parameters
+  let start = Instant::now();
+  println!("Producing public parameters...");
+  let pp =
+    PublicParams::<E1, E2, ToyVmCircuit>::setup(&circuit, &*S1::ck_floor(), &*S2::ck_floor())
+      .unwrap();
+  println!("PublicParams::setup, took {:?} ", start.elapsed());
+  println!(
+    "Number of constraints per step (primary circuit): {}",
+    pp.num_constraints().0
+  );
+
+  // produce a recursive SNARK, executing one instruction per step
+  println!("Generating a RecursiveSNARK...");
+  let z0 = init.to_z::<<E1 as Engine>::Scalar>();
+  let mut recursive_snark =
+    RecursiveSNARK::<E1, E2, ToyVmCircuit>::new(&pp, &circuit, &z0).unwrap();
+  let start = Instant::now();
+  for _ in 0..num_steps {
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+  }
+  println!(
+    "RecursiveSNARK::prove {num_steps} instructions: took {:?} ",
+    start.elapsed()
+  );
+
+  // verify the recursive SNARK
+  println!("Verifying a RecursiveSNARK...");
+  let res = recursive_snark.verify(&pp, num_steps, &z0);
+  println!("RecursiveSNARK::verify: {:?}", res.is_ok());
+  assert_eq!(res.unwrap(), last.to_z());
+
+  // produce a compressed SNARK
+  println!("Generating a CompressedSNARK using Spartan with HyperKZG...");
+  let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+
+  let start = Instant::now();
+  let res = CompressedSNARK::<_, _, _, S1, S2>::prove(&pp, &pk, &recursive_snark);
+  println!(
+    "CompressedSNARK::prove: {:?}, took {:?}",
+    res.is_ok(),
+    start.elapsed()
+  );
+  assert!(res.is_ok());
+  let compressed_snark = res.unwrap();
+
+  let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+  bincode::serialize_into(&mut encoder, &compressed_snark).unwrap();
+  let compressed_snark_encoded = encoder.finish().unwrap();
+  println!(
+    "CompressedSNARK::len {:?} bytes",
+    compressed_snark_encoded.len()
+  );
+
+  // verify the compressed SNARK
+  println!("Verifying a CompressedSNARK...");
+  let start = Instant::now();
+  let res = compressed_snark.verify(&vk, num_steps, &z0);
+  println!(
+    "CompressedSNARK::verify: {:?}, took {:?}",
+    res.is_ok(),
+    start.elapsed()
+  );
+  assert_eq!(res.unwrap(), last.to_z());
+  println!("=========================================================");
+}
@@ -0,0 +1,13 @@
+//! Writes the seeds returned by `nova_snark::fuzz::seeds` to `corpus/<target>/`, next to this
+//! crate's manifest, where `cargo fuzz run <target>` picks them up.
+use std::{fs, path::Path};
+
+fn main() -> std::io::Result<()> {
+  let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+  for (i, (target, data)) in nova_snark::fuzz::seeds().into_iter().enumerate() {
+    let dir = corpus.join(target);
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(format!("seed-{i}")), data)?;
+  }
+  Ok(())
+}
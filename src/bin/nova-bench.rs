@@ -0,0 +1,422 @@
+//! `nova-bench` runs calibrated proving workloads and reports their timings as JSON, so that
+//! operators can validate hardware installations and driver versions, and compare machines or
+//! successive runs on the same machine.
+//!
+//! Each workload proves a few steps of a circuit with a given number of constraints and arity,
+//! then compresses and verifies the proof with a given evaluation engine. Every proof is verified,
+//! and the digest of the public parameters is recorded, so a run also checks that a machine
+//! computes the same parameters as the others. Multi-scalar multiplications are timed separately
+//! on every available backend, which isolates the GPU from the rest of the prover.
+//!
+//! Usage: `cargo run --release --bin nova-bench -- [options]`, with the options
+//! * `--num-cons <n,...>`: numbers of constraints of the step circuit (default `1024,16384`),
+//! * `--arity <n,...>`: arities of the step circuit (default `1,4`),
+//! * `--ee <name,...>`: evaluation engines of the primary curve, among `hyperkzg` and `ipa`
+//!   (default both),
+//! * `--steps <n>`: number of steps proven by each workload (default 3),
+//! * `--msm-sizes <n,...>`: sizes of the timed multi-scalar multiplications (default `65536`),
+//! * `--output <path>`: writes the JSON report to `path` instead of the standard output,
+//! * `--compare <path>`: compares the run with the JSON report at `path`, and fails if a timing
+//!   regressed by more than the tolerance or if a parameter digest differs,
+//! * `--tolerance <fraction>`: the tolerated slowdown when comparing (default `0.25`).
+use ff::{Field, PrimeField};
+use nova_snark::{
+  frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+  nova::{CompressedSNARK, PublicParams, RecursiveSNARK},
+  provider::{
+    msm_backend, set_msm_backend, Bn256EngineIPA, Bn256EngineKZG, GrumpkinEngine, MsmBackend,
+  },
+  traits::{
+    circuit::StepCircuit, commitment::CommitmentEngineTrait, evaluation::EvaluationEngineTrait,
+    snark::RelaxedR1CSSNARKTrait, Engine,
+  },
+};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, marker::PhantomData, process::ExitCode, time::Instant};
+
+/// The identifier of the report format, which changes whenever the format does
+const FORMAT: &str = "nova-bench/1";
+
+/// A step circuit that squares each of its `arity` inputs repeatedly, with `num_cons` constraints
+/// in total
+#[derive(Clone, Debug)]
+struct BenchCircuit<F: PrimeField> {
+  num_cons: usize,
+  arity: usize,
+  _p: PhantomData<F>,
+}
+
+impl<F: PrimeField> StepCircuit<F> for BenchCircuit<F> {
+  fn arity(&self) -> usize {
+    self.arity
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    z.iter()
+      .enumerate()
+      .map(|(i, x)| {
+        // the constraints are spread evenly over the inputs, with the remainder on the first ones
+        let num_cons = self.num_cons / self.arity + usize::from(i < self.num_cons % self.arity);
+        (0..num_cons).try_fold(x.clone(), |x, j| {
+          x.square(cs.namespace(|| format!("x_{i}_sq_{j}")))
+        })
+      })
+      .collect()
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Report {
+  format: String,
+  version: String,
+  system: System,
+  msm: Vec<MsmTiming>,
+  workloads: Vec<WorkloadTiming>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct System {
+  os: String,
+  arch: String,
+  threads: usize,
+  msm_backends: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MsmTiming {
+  backend: String,
+  size: usize,
+  millis: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WorkloadTiming {
+  ee: String,
+  num_cons: usize,
+  arity: usize,
+  steps: usize,
+  pp_digest: String,
+  setup_millis: f64,
+  prove_step_millis: f64,
+  verify_millis: f64,
+  compress_millis: f64,
+  verify_compressed_millis: f64,
+}
+
+impl WorkloadTiming {
+  fn key(&self) -> (&str, usize, usize, usize) {
+    (&self.ee, self.num_cons, self.arity, self.steps)
+  }
+
+  fn timings(&self) -> [(&'static str, f64); 5] {
+    [
+      ("setup", self.setup_millis),
+      ("prove_step", self.prove_step_millis),
+      ("verify", self.verify_millis),
+      ("compress", self.compress_millis),
+      ("verify_compressed", self.verify_compressed_millis),
+    ]
+  }
+}
+
+struct Options {
+  num_cons: Vec<usize>,
+  arities: Vec<usize>,
+  ees: Vec<String>,
+  steps: usize,
+  msm_sizes: Vec<usize>,
+  output: Option<String>,
+  compare: Option<String>,
+  tolerance: f64,
+}
+
+impl Options {
+  fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, Box<dyn Error>> {
+    let mut options = Options {
+      num_cons: vec![1024, 16384],
+      arities: vec![1, 4],
+      ees: vec!["hyperkzg".to_string(), "ipa".to_string()],
+      steps: 3,
+      msm_sizes: vec![65536],
+      output: None,
+      compare: None,
+      tolerance: 0.25,
+    };
+
+    fn list(value: &str) -> Result<Vec<usize>, Box<dyn Error>> {
+      Ok(value.split(',').map(str::parse).collect::<Result<_, _>>()?)
+    }
+
+    while let Some(arg) = args.next() {
+      let value = args
+        .next()
+        .ok_or_else(|| format!("missing value for {arg}"))?;
+      match arg.as_str() {
+        "--num-cons" => options.num_cons = list(&value)?,
+        "--arity" => options.arities = list(&value)?,
+        "--ee" => options.ees = value.split(',').map(str::to_string).collect(),
+        "--steps" => options.steps = value.parse()?,
+        "--msm-sizes" => options.msm_sizes = list(&value)?,
+        "--output" => options.output = Some(value),
+        "--compare" => options.compare = Some(value),
+        "--tolerance" => options.tolerance = value.parse()?,
+        _ => return Err(format!("unknown option {arg}").into()),
+      }
+    }
+
+    if options.arities.contains(&0) || options.steps == 0 {
+      return Err("arities and the number of steps must be positive".into());
+    }
+    Ok(options)
+  }
+}
+
+fn millis(start: Instant) -> f64 {
+  start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Returns the backends available in this build, with the default one first
+fn msm_backends() -> Vec<MsmBackend> {
+  let mut backends = vec![msm_backend()];
+  if !backends.contains(&MsmBackend::Cpu) {
+    backends.push(MsmBackend::Cpu);
+  }
+  backends
+}
+
+/// Times a commitment to `size` random scalars on every available backend, and checks that all
+/// backends compute the same commitment as the CPU
+fn bench_msm(size: usize) -> Result<Vec<MsmTiming>, Box<dyn Error>> {
+  type E = Bn256EngineIPA;
+  let ck = <E as Engine>::CE::setup(b"nova-bench", size);
+  let mut rng = ChaCha20Rng::seed_from_u64(size as u64);
+  let v = (0..size)
+    .map(|_| <E as Engine>::Scalar::random(&mut rng))
+    .collect::<Vec<_>>();
+  let commit = || <E as Engine>::CE::commit(&ck, &v, &<E as Engine>::Scalar::ZERO);
+
+  let default = msm_backend();
+  set_msm_backend(MsmBackend::Cpu);
+  let expected = commit();
+
+  let mut timings = Vec::new();
+  for backend in msm_backends() {
+    set_msm_backend(backend);
+    // the first commitment warms up the backend, e.g., by initializing the GPU
+    commit();
+    let start = Instant::now();
+    let comm = commit();
+    let millis = millis(start);
+    if comm != expected {
+      set_msm_backend(default);
+      return Err(format!("the {backend:?} backend computed a wrong commitment").into());
+    }
+    timings.push(MsmTiming {
+      backend: format!("{backend:?}"),
+      size,
+      millis,
+    });
+  }
+  set_msm_backend(default);
+  Ok(timings)
+}
+
+fn bench_workload<E1, E2, EE1, EE2>(
+  ee: &str,
+  num_cons: usize,
+  arity: usize,
+  steps: usize,
+) -> Result<WorkloadTiming, Box<dyn Error>>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  EE1: EvaluationEngineTrait<E1>,
+  EE2: EvaluationEngineTrait<E2>,
+{
+  type S<E, EE> = nova_snark::spartan::snark::RelaxedR1CSSNARK<E, EE>;
+
+  let c = BenchCircuit {
+    num_cons,
+    arity,
+    _p: PhantomData,
+  };
+  let z0 = (1..=arity as u64).map(E1::Scalar::from).collect::<Vec<_>>();
+
+  let start = Instant::now();
+  let pp =
+    PublicParams::<E1, E2, _>::setup(&c, &*S::<E1, EE1>::ck_floor(), &*S::<E2, EE2>::ck_floor())?;
+  let (pk, vk) = CompressedSNARK::<_, _, _, S<E1, EE1>, S<E2, EE2>>::setup(&pp)?;
+  let setup_millis = millis(start);
+
+  let start = Instant::now();
+  let mut recursive_snark = RecursiveSNARK::new(&pp, &c, &z0)?;
+  for _ in 0..steps {
+    recursive_snark.prove_step(&pp, &c)?;
+  }
+  let prove_step_millis = millis(start) / steps as f64;
+
+  let start = Instant::now();
+  let zn = recursive_snark.verify(&pp, steps, &z0)?;
+  let verify_millis = millis(start);
+
+  let start = Instant::now();
+  let compressed_snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark)?;
+  let compress_millis = millis(start);
+
+  let start = Instant::now();
+  if compressed_snark.verify(&vk, steps, &z0)? != zn {
+    return Err("the compressed proof has different outputs".into());
+  }
+  let verify_compressed_millis = millis(start);
+
+  Ok(WorkloadTiming {
+    ee: ee.to_string(),
+    num_cons,
+    arity,
+    steps,
+    pp_digest: hex(pp.digest().to_repr().as_ref()),
+    setup_millis,
+    prove_step_millis,
+    verify_millis,
+    compress_millis,
+    verify_compressed_millis,
+  })
+}
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Compares `report` with `baseline`, printing the differences, and returns whether they are
+/// within the tolerance
+fn compare(report: &Report, baseline: &Report, tolerance: f64) -> bool {
+  let mut ok = true;
+  for timing in &report.msm {
+    let old = baseline
+      .msm
+      .iter()
+      .find(|old| old.backend == timing.backend && old.size == timing.size);
+    if let Some(old) = old {
+      ok &= compare_timing(
+        &format!("msm {} {}", timing.backend, timing.size),
+        old.millis,
+        timing.millis,
+        tolerance,
+      );
+    }
+  }
+  for workload in &report.workloads {
+    let Some(old) = baseline
+      .workloads
+      .iter()
+      .find(|old| old.key() == workload.key())
+    else {
+      continue;
+    };
+    let (ee, num_cons, arity, steps) = workload.key();
+    let name = format!("{ee} num_cons={num_cons} arity={arity} steps={steps}");
+    if old.pp_digest != workload.pp_digest {
+      eprintln!("{name}: the digest of the public parameters differs from the baseline");
+      ok = false;
+    }
+    for ((label, old), (_, new)) in old.timings().into_iter().zip(workload.timings()) {
+      ok &= compare_timing(&format!("{name} {label}"), old, new, tolerance);
+    }
+  }
+  ok
+}
+
+fn compare_timing(name: &str, old: f64, new: f64, tolerance: f64) -> bool {
+  let ratio = new / old;
+  let ok = ratio <= 1.0 + tolerance;
+  eprintln!(
+    "{name}: {old:.1} ms -> {new:.1} ms ({ratio:.2}x){}",
+    if ok { "" } else { " REGRESSION" }
+  );
+  ok
+}
+
+fn run() -> Result<bool, Box<dyn Error>> {
+  let options = Options::parse(std::env::args().skip(1))?;
+
+  let mut msm = Vec::new();
+  for &size in &options.msm_sizes {
+    eprintln!("timing multi-scalar multiplications of size {size}");
+    msm.extend(bench_msm(size)?);
+  }
+
+  let mut workloads = Vec::new();
+  for ee in &options.ees {
+    for &num_cons in &options.num_cons {
+      for &arity in &options.arities {
+        eprintln!("running workload {ee} num_cons={num_cons} arity={arity}");
+        type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<GrumpkinEngine>;
+        let workload = match ee.as_str() {
+          "hyperkzg" => bench_workload::<
+            Bn256EngineKZG,
+            GrumpkinEngine,
+            nova_snark::provider::hyperkzg::EvaluationEngine<Bn256EngineKZG>,
+            EE2,
+          >(ee, num_cons, arity, options.steps)?,
+          "ipa" => bench_workload::<
+            Bn256EngineIPA,
+            GrumpkinEngine,
+            nova_snark::provider::ipa_pc::EvaluationEngine<Bn256EngineIPA>,
+            EE2,
+          >(ee, num_cons, arity, options.steps)?,
+          _ => return Err(format!("unknown evaluation engine {ee}").into()),
+        };
+        workloads.push(workload);
+      }
+    }
+  }
+
+  let report = Report {
+    format: FORMAT.to_string(),
+    version: env!("CARGO_PKG_VERSION").to_string(),
+    system: System {
+      os: std::env::consts::OS.to_string(),
+      arch: std::env::consts::ARCH.to_string(),
+      threads: rayon::current_num_threads(),
+      msm_backends: msm_backends()
+        .iter()
+        .map(|backend| format!("{backend:?}"))
+        .collect(),
+    },
+    msm,
+    workloads,
+  };
+
+  let json = serde_json::to_string_pretty(&report)?;
+  match &options.output {
+    Some(path) => fs::write(path, json)?,
+    None => println!("{json}"),
+  }
+
+  match &options.compare {
+    Some(path) => {
+      let baseline: Report = serde_json::from_str(&fs::read_to_string(path)?)?;
+      if baseline.format != FORMAT {
+        return Err(format!("{path} is not a {FORMAT} report").into());
+      }
+      Ok(compare(&report, &baseline, options.tolerance))
+    }
+    None => Ok(true),
+  }
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(true) => ExitCode::SUCCESS,
+    Ok(false) => ExitCode::FAILURE,
+    Err(e) => {
+      eprintln!("error: {e}");
+      ExitCode::from(2)
+    }
+  }
+}
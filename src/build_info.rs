@@ -0,0 +1,183 @@
+//! This module reports how the library was built: its version, the git commit it was built from, the
+//! features enabled, and the engines compiled in, for debugging proofs that verify on one machine
+//! but not on another.
+//!
+//! `build_info` returns the report for the running binary. `Stamped` attaches it to a proof, or to
+//! any other serializable value, so that the report travels with the proof and
+//! `BuildInfo::differences` lists what differs between the builds of the prover and the verifier.
+use crate::{
+  provider::{
+    Bn256EngineIPA, Bn256EngineKZG, GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine,
+    VestaEngine,
+  },
+  traits::Engine,
+};
+use serde::{Deserialize, Serialize};
+
+/// The features of the crate that `BuildInfo` reports, with whether each is enabled
+const FEATURES: [(&str, bool); 8] = [
+  ("asm", cfg!(feature = "asm")),
+  ("blitzar", cfg!(feature = "blitzar")),
+  ("ark-compat", cfg!(feature = "ark-compat")),
+  ("timing", cfg!(feature = "timing")),
+  ("telemetry", cfg!(feature = "telemetry")),
+  ("experimental", cfg!(feature = "experimental")),
+  ("fuzzing", cfg!(feature = "fuzzing")),
+  ("testing", cfg!(feature = "testing")),
+];
+
+/// A report of how the library was built
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+  /// The version of the crate
+  pub version: String,
+  /// The git commit the crate was built from, if it was known at build time
+  pub git_commit: Option<String>,
+  /// The features of the crate that were enabled, e.g., `blitzar` for GPU-accelerated MSMs
+  pub features: Vec<String>,
+  /// The identifiers of the engines compiled in (see `Engine::ID`)
+  pub engines: Vec<String>,
+  /// The architecture of the target, e.g., `x86_64`
+  pub target_arch: String,
+}
+
+/// Returns the report of how the library in the running binary was built
+pub fn build_info() -> BuildInfo {
+  BuildInfo {
+    version: env!("CARGO_PKG_VERSION").to_string(),
+    git_commit: option_env!("NOVA_GIT_COMMIT").map(str::to_string),
+    features: FEATURES
+      .iter()
+      .filter(|(_, enabled)| *enabled)
+      .map(|(name, _)| name.to_string())
+      .collect(),
+    engines: [
+      Bn256EngineKZG::ID,
+      Bn256EngineIPA::ID,
+      GrumpkinEngine::ID,
+      Secp256k1Engine::ID,
+      Secq256k1Engine::ID,
+      PallasEngine::ID,
+      VestaEngine::ID,
+    ]
+    .iter()
+    .map(|id| id.to_string())
+    .collect(),
+    target_arch: std::env::consts::ARCH.to_string(),
+  }
+}
+
+impl BuildInfo {
+  /// Returns whether the feature `name` was enabled
+  pub fn has_feature(&self, name: &str) -> bool {
+    self.features.iter().any(|f| f == name)
+  }
+
+  /// Returns a description of each difference between the builds that `self` and `other` report
+  pub fn differences(&self, other: &Self) -> Vec<String> {
+    let mut diffs = vec![];
+    if self.version != other.version {
+      diffs.push(format!("version: {} != {}", self.version, other.version));
+    }
+    if self.git_commit != other.git_commit {
+      diffs.push(format!(
+        "git commit: {} != {}",
+        self.git_commit.as_deref().unwrap_or("unknown"),
+        other.git_commit.as_deref().unwrap_or("unknown")
+      ));
+    }
+    for f in &self.features {
+      if !other.has_feature(f) {
+        diffs.push(format!("feature {f} is enabled only in the first build"));
+      }
+    }
+    for f in &other.features {
+      if !self.has_feature(f) {
+        diffs.push(format!("feature {f} is enabled only in the second build"));
+      }
+    }
+    if self.engines != other.engines {
+      diffs.push(format!(
+        "engines: {:?} != {:?}",
+        self.engines, other.engines
+      ));
+    }
+    if self.target_arch != other.target_arch {
+      diffs.push(format!(
+        "target architecture: {} != {}",
+        self.target_arch, other.target_arch
+      ));
+    }
+    diffs
+  }
+}
+
+/// A value, typically a proof, along with the report of the build that produced it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Stamped<T> {
+  value: T,
+  build: BuildInfo,
+}
+
+impl<T> Stamped<T> {
+  /// Attaches the report of the running build to `value`
+  pub fn new(value: T) -> Self {
+    Self {
+      value,
+      build: build_info(),
+    }
+  }
+
+  /// Returns the value
+  pub fn value(&self) -> &T {
+    &self.value
+  }
+
+  /// Returns the report of the build that produced the value
+  pub fn build_info(&self) -> &BuildInfo {
+    &self.build
+  }
+
+  /// Returns the differences between the build that produced the value and the running build
+  pub fn differences(&self) -> Vec<String> {
+    self.build.differences(&build_info())
+  }
+
+  /// Returns the value, dropping the report
+  pub fn into_inner(self) -> T {
+    self.value
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_build_info() {
+    let info = build_info();
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(info.has_feature("asm"), cfg!(feature = "asm"));
+    assert!(info.engines.contains(&Bn256EngineKZG::ID.to_string()));
+    assert!(info.differences(&info).is_empty());
+
+    // the report survives serialization alongside the value it stamps
+    let stamped = Stamped::new(vec![1u8, 2, 3]);
+    let bytes = bincode::serialize(&stamped).unwrap();
+    let decoded = bincode::deserialize::<Stamped<Vec<u8>>>(&bytes).unwrap();
+    assert_eq!(decoded.build_info(), &info);
+    assert!(decoded.differences().is_empty());
+    assert_eq!(decoded.into_inner(), vec![1, 2, 3]);
+
+    let mut other = info.clone();
+    other.version = "0.0.0".to_string();
+    other.features.push("blitzar".to_string());
+    other.features.retain(|f| f != "timing");
+    let diffs = info.differences(&other);
+    assert!(diffs.iter().any(|d| d.starts_with("version")));
+    assert_eq!(
+      diffs.len(),
+      1 + !info.has_feature("blitzar") as usize + info.has_feature("timing") as usize
+    );
+  }
+}
@@ -0,0 +1,9 @@
+//! This module checks this crate against other implementations of Nova.
+//!
+//! Each submodule pins, in a fixture of test vectors, the values that this crate derives from
+//! fixed inputs (digests of public parameters, challenges of transcripts and random oracles,
+//! commitments), and records for each of them whether the other implementation derives the same
+//! value or why it does not. Proofs are randomized by their blinds, so they are not compared
+//! directly: two implementations that agree on these values produce proofs that verify against
+//! each other's verifier when their encodings align.
+mod upstream;
@@ -0,0 +1,241 @@
+//! Test vectors against upstream Nova (microsoft/Nova), which this crate forked from.
+//!
+//! The fixture `upstream.json` lists, for each vector, the value that this crate derives
+//! (`value`), the value that upstream Nova derives for identical circuits and parameters
+//! (`upstream_value`, or `null` where upstream has no counterpart), and `divergence`, which is
+//! `null` if the two values must agree, and otherwise explains why this crate departs from
+//! upstream on purpose. The tests check that the vectors the crate computes are exactly those of
+//! the fixture, that they have the recorded values, and that the values agree with upstream
+//! unless a divergence is recorded. A change that makes the crate diverge from upstream thus
+//! fails until the fixture records why.
+//!
+//! Values are field elements and commitments, written as the lowercase hex encoding of their
+//! canonical representation (`PrimeField::to_repr`), and coordinates are joined by a colon. The
+//! upstream values were computed at the release recorded in the fixture.
+use crate::{
+  frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+  nova::PublicParams,
+  provider::{
+    ipa_pc, keccak::Keccak256Transcript, pedersen::CommitmentKeyExtTrait,
+    poseidon::PoseidonTranscript, traits::DlogGroup, Bn256EngineIPA, GrumpkinEngine, PallasEngine,
+    Secp256k1Engine, Secq256k1Engine, VestaEngine,
+  },
+  spartan::ppsnark::RelaxedR1CSSNARK,
+  traits::{
+    circuit::{StepCircuit, TrivialCircuit},
+    commitment::{CommitmentEngineTrait, CommitmentTrait},
+    snark::RelaxedR1CSSNARKTrait,
+    Engine, ROConstants, ROTrait, TranscriptEngineTrait,
+  },
+};
+use ff::{Field, PrimeField};
+use halo2curves::bn256::G2;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// The fixture of test vectors
+#[derive(Deserialize)]
+struct Fixture {
+  format: String,
+  version: u32,
+  upstream: String,
+  vectors: Vec<Vector>,
+}
+
+/// A test vector and how it compares to upstream
+#[derive(Deserialize)]
+struct Vector {
+  name: String,
+  value: String,
+  upstream_value: Option<String>,
+  divergence: Option<String>,
+}
+
+const FORMAT: &str = "nova-compat-vectors";
+const VERSION: u32 = 1;
+
+fn hex<F: PrimeField>(f: &F) -> String {
+  f.to_repr()
+    .as_ref()
+    .iter()
+    .map(|b| format!("{b:02x}"))
+    .collect()
+}
+
+fn hex_point<F: PrimeField>((x, y, is_infinity): (F, F, bool)) -> String {
+  format!("{}:{}:{}", hex(&x), hex(&y), is_infinity as u8)
+}
+
+/// A step circuit that computes `x^3 + x + 5`, as in upstream's tests
+#[derive(Clone, Default)]
+struct CubicCircuit;
+
+impl<F: PrimeField> StepCircuit<F> for CubicCircuit {
+  fn arity(&self) -> usize {
+    1
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    let x = &z[0];
+    let x_sq = x.square(cs.namespace(|| "x_sq"))?;
+    let x_cu = x_sq.mul(cs.namespace(|| "x_cu"), x)?;
+    let y = AllocatedNum::alloc(cs.namespace(|| "y"), || {
+      Ok(x_cu.get_value().unwrap_or(F::ZERO) + x.get_value().unwrap_or(F::ZERO) + F::from(5u64))
+    })?;
+    cs.enforce(
+      || "y = x^3 + x + 5",
+      |lc| lc + x_cu.get_variable() + x.get_variable() + (F::from(5u64), CS::one()),
+      |lc| lc + CS::one(),
+      |lc| lc + y.get_variable(),
+    );
+    Ok(vec![y])
+  }
+}
+
+/// Returns the digest of the public parameters for `circuit`, sized for a spark-compressed SNARK
+/// with the IPA as upstream's `test_pp_digest` does
+fn pp_digest<E1, E2, C>(circuit: &C) -> String
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  E1::GE: DlogGroup,
+  E2::GE: DlogGroup,
+  C: StepCircuit<E1::Scalar>,
+  <E1::CE as CommitmentEngineTrait<E1>>::CommitmentKey: CommitmentKeyExtTrait<E1>,
+  <E2::CE as CommitmentEngineTrait<E2>>::CommitmentKey: CommitmentKeyExtTrait<E2>,
+{
+  type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+  let pp = PublicParams::<E1, E2, C>::setup(circuit, &*S::<E1>::ck_floor(), &*S::<E2>::ck_floor())
+    .unwrap();
+  hex(&pp.digest())
+}
+
+/// Returns the challenges that a transcript `T` squeezes after absorbing fixed scalars
+fn transcript_challenges<E: Engine, T: TranscriptEngineTrait<E>>() -> String {
+  let mut transcript = T::new(b"compat");
+  let scalars = (1..=3u64).map(E::Scalar::from).collect::<Vec<_>>();
+  transcript.absorb(b"scalars", &scalars.as_slice());
+  let c1 = transcript.squeeze(b"c1").unwrap();
+  transcript.dom_sep(b"compat_dom_sep");
+  let c2 = transcript.squeeze(b"c2").unwrap();
+  format!("{}:{}", hex(&c1), hex(&c2))
+}
+
+/// Returns the challenges that the random oracle of `E` squeezes from fixed inputs
+fn ro_challenges<E: Engine>() -> String {
+  let mut ro = E::RO::new(ROConstants::<E>::default());
+  for i in 1..=3u64 {
+    ro.absorb(E::Base::from(i));
+  }
+  let c1 = ro.squeeze(128);
+  ro.absorb(E::Base::from(4u64));
+  let c2 = ro.squeeze(128);
+  format!("{}:{}", hex(&c1), hex(&c2))
+}
+
+/// Returns a commitment to fixed scalars with a zero blind, under a key derived from a label
+fn commitment<E: Engine>() -> String {
+  let ck = E::CE::setup(b"compat", 4);
+  let v = (1..=4u64).map(E::Scalar::from).collect::<Vec<_>>();
+  hex_point(E::CE::commit(&ck, &v, &E::Scalar::ZERO).to_coordinates())
+}
+
+/// Computes the vectors that the fixture pins, by name
+fn vectors() -> BTreeMap<&'static str, String> {
+  type T<E> = Keccak256Transcript<E>;
+  BTreeMap::from([
+    (
+      "pp_digest/pallas_vesta/trivial",
+      pp_digest::<PallasEngine, VestaEngine, _>(&TrivialCircuit::default()),
+    ),
+    (
+      "pp_digest/pallas_vesta/cubic",
+      pp_digest::<PallasEngine, VestaEngine, _>(&CubicCircuit),
+    ),
+    (
+      "pp_digest/bn256_grumpkin/trivial",
+      pp_digest::<Bn256EngineIPA, GrumpkinEngine, _>(&TrivialCircuit::default()),
+    ),
+    (
+      "pp_digest/secp_secq/trivial",
+      pp_digest::<Secp256k1Engine, Secq256k1Engine, _>(&TrivialCircuit::default()),
+    ),
+    (
+      "transcript/keccak/pallas",
+      transcript_challenges::<PallasEngine, T<PallasEngine>>(),
+    ),
+    (
+      "transcript/keccak/bn256",
+      transcript_challenges::<Bn256EngineIPA, T<Bn256EngineIPA>>(),
+    ),
+    (
+      "transcript/poseidon/pallas",
+      transcript_challenges::<PallasEngine, PoseidonTranscript<PallasEngine>>(),
+    ),
+    ("ro/poseidon/pallas", ro_challenges::<PallasEngine>()),
+    ("ro/poseidon/bn256", ro_challenges::<Bn256EngineIPA>()),
+    ("commitment/pedersen/pallas", commitment::<PallasEngine>()),
+    (
+      "commitment/pedersen/grumpkin",
+      commitment::<GrumpkinEngine>(),
+    ),
+    (
+      "generators/bn256_g2/from_label",
+      G2::from_label(b"compat", 2)
+        .iter()
+        .map(|g| {
+          let (x, y, is_infinity) = G2::group(g).to_coordinate_limbs();
+          let limbs = [x, y].concat().iter().map(hex).collect::<Vec<_>>();
+          format!("{}:{}", limbs.join(":"), is_infinity as u8)
+        })
+        .collect::<Vec<_>>()
+        .join(","),
+    ),
+  ])
+}
+
+fn fixture() -> Fixture {
+  serde_json::from_str(include_str!("upstream.json")).unwrap()
+}
+
+#[test]
+fn test_upstream_vectors() {
+  let fixture = fixture();
+  assert_eq!(fixture.format, FORMAT);
+  assert_eq!(fixture.version, VERSION);
+  assert!(!fixture.upstream.is_empty());
+
+  let mut computed = vectors();
+  for vector in &fixture.vectors {
+    let value = computed
+      .remove(vector.name.as_str())
+      .unwrap_or_else(|| panic!("{}: not computed, or listed twice", vector.name));
+    assert_eq!(value, vector.value, "{}: value changed", vector.name);
+
+    match (&vector.divergence, &vector.upstream_value) {
+      (None, Some(upstream_value)) => assert_eq!(
+        &vector.value, upstream_value,
+        "{}: diverges from upstream without a recorded divergence",
+        vector.name
+      ),
+      (None, None) => panic!("{}: no upstream value to agree with", vector.name),
+      (Some(divergence), upstream_value) => {
+        assert!(!divergence.is_empty(), "{}: empty divergence", vector.name);
+        assert_ne!(
+          Some(&vector.value),
+          upstream_value.as_ref(),
+          "{}: agrees with upstream despite a recorded divergence",
+          vector.name
+        );
+      }
+    }
+  }
+  assert!(
+    computed.is_empty(),
+    "vectors missing from the fixture: {computed:#?}"
+  );
+}
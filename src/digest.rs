@@ -27,6 +27,19 @@ impl<T: SimpleDigestible> Digestible for T {
   }
 }
 
+/// Returns the SHA3-256 digest of the serialization of `value`, in the encoding that
+/// `SimpleDigestible` values are digested in, so that values with equal digests serialize to the
+/// same bytes
+pub(crate) fn reproducibility_digest<T: Serialize>(value: &T) -> [u8; 32] {
+  let mut hasher = Sha3_256::new();
+  bincode::DefaultOptions::new()
+    .with_little_endian()
+    .with_fixint_encoding()
+    .serialize_into(&mut hasher, value)
+    .expect("serializing to a hasher cannot fail");
+  hasher.finalize().into()
+}
+
 pub struct DigestComputer<'a, F: PrimeField, T> {
   inner: &'a T,
   _phantom: PhantomData<F>,
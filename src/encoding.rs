@@ -0,0 +1,194 @@
+//! This module defines the canonical byte layouts of field elements and points, which transcripts,
+//! calldata, and the interchange formats of this crate are built from, so that verifiers written
+//! in other languages can reproduce them without depending on the representations of the
+//! underlying curve libraries.
+//!
+//! * The canonical value of a field element is its integer in `[0, p)`, written with as many
+//!   bytes as the representation of the field, which is 32 bytes for the fields of the provided
+//!   curves. `PrimeField::to_repr` of every field supported by this crate is this value in
+//!   little-endian order.
+//! * Transcripts absorb field elements in big-endian order (`TRANSCRIPT_BYTE_ORDER`), as do
+//!   calldata words. The binary interchange format of R1CS shapes stores them in little-endian
+//!   order.
+//! * Transcripts absorb an affine point `(x, y)` as `x || y`, and a commitment as
+//!   `x || y || flag`, where `flag` is `1` for a finite point and `0` for the point at infinity,
+//!   whose coordinates are then both zero.
+//!
+//! Decoding rejects inputs of the wrong length and non-canonical values, i.e., integers that are
+//! not smaller than the modulus, so every value has exactly one encoding.
+use crate::errors::NovaError;
+use ff::PrimeField;
+
+/// The order of the bytes of the canonical value of a field element
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+  /// The most significant byte first
+  BigEndian,
+  /// The least significant byte first, as in `PrimeField::to_repr`
+  LittleEndian,
+}
+
+/// The order in which transcripts absorb field elements
+pub const TRANSCRIPT_BYTE_ORDER: ByteOrder = ByteOrder::BigEndian;
+
+/// The flag of `encode_point` for a finite point
+pub const POINT_FINITE: u8 = 1;
+
+/// The flag of `encode_point` for the point at infinity
+pub const POINT_INFINITY: u8 = 0;
+
+fn invalid_encoding(reason: &str) -> NovaError {
+  NovaError::InvalidEncoding {
+    reason: reason.to_string(),
+  }
+}
+
+/// Returns the number of bytes of the encoding of an element of `F`
+pub fn scalar_len<F: PrimeField>() -> usize {
+  F::Repr::default().as_ref().len()
+}
+
+/// Encodes the canonical value of `x` in the given byte order
+pub fn encode_scalar<F: PrimeField>(x: &F, order: ByteOrder) -> Vec<u8> {
+  let mut bytes = x.to_repr().as_ref().to_vec();
+  if order == ByteOrder::BigEndian {
+    bytes.reverse();
+  }
+  bytes
+}
+
+/// Decodes a field element from the encoding of its canonical value in the given byte order
+pub fn decode_scalar<F: PrimeField>(bytes: &[u8], order: ByteOrder) -> Result<F, NovaError> {
+  let mut repr = F::Repr::default();
+  if bytes.len() != repr.as_ref().len() {
+    return Err(invalid_encoding("field element of the wrong length"));
+  }
+  repr.as_mut().copy_from_slice(bytes);
+  if order == ByteOrder::BigEndian {
+    repr.as_mut().reverse();
+  }
+  Option::from(F::from_repr(repr)).ok_or_else(|| invalid_encoding("non-canonical field element"))
+}
+
+/// Encodes the coordinates of an affine point as `x || y`, in the order of transcripts
+pub fn encode_coordinates<F: PrimeField>(x: &F, y: &F) -> Vec<u8> {
+  [
+    encode_scalar(x, TRANSCRIPT_BYTE_ORDER),
+    encode_scalar(y, TRANSCRIPT_BYTE_ORDER),
+  ]
+  .concat()
+}
+
+/// Encodes a point, given as in `CommitmentTrait::to_coordinates`, as `x || y || flag`, in the
+/// order of transcripts
+pub fn encode_point<F: PrimeField>(x: &F, y: &F, is_infinity: bool) -> Vec<u8> {
+  let flag = if is_infinity {
+    POINT_INFINITY
+  } else {
+    POINT_FINITE
+  };
+  [encode_coordinates(x, y), vec![flag]].concat()
+}
+
+/// Decodes the coordinates and the flag of a point encoded by `encode_point`.
+///
+/// This only checks the layout: whether `(x, y)` is on a curve is up to the caller.
+pub fn decode_point<F: PrimeField>(bytes: &[u8]) -> Result<(F, F, bool), NovaError> {
+  let n = scalar_len::<F>();
+  if bytes.len() != 2 * n + 1 {
+    return Err(invalid_encoding("point of the wrong length"));
+  }
+  let x = decode_scalar(&bytes[..n], TRANSCRIPT_BYTE_ORDER)?;
+  let y = decode_scalar(&bytes[n..2 * n], TRANSCRIPT_BYTE_ORDER)?;
+  match bytes[2 * n] {
+    POINT_FINITE => Ok((x, y, false)),
+    POINT_INFINITY if x == F::ZERO && y == F::ZERO => Ok((x, y, true)),
+    POINT_INFINITY => Err(invalid_encoding(
+      "point at infinity with nonzero coordinates",
+    )),
+    _ => Err(invalid_encoding("invalid point flag")),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::{
+      Bn256EngineKZG, GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine, VestaEngine,
+    },
+    traits::{commitment::CommitmentEngineTrait, Engine, TranscriptReprTrait},
+  };
+  use ff::Field;
+  use rand::rngs::OsRng;
+
+  fn test_round_trip_with<F: PrimeField>() {
+    // the canonical value of one is 1, so `to_repr` is little-endian
+    let one = encode_scalar(&F::ONE, ByteOrder::LittleEndian);
+    assert_eq!(one.len(), scalar_len::<F>());
+    assert_eq!(one[0], 1);
+    assert!(one[1..].iter().all(|b| *b == 0));
+    let mut one_be = one.clone();
+    one_be.reverse();
+    assert_eq!(encode_scalar(&F::ONE, ByteOrder::BigEndian), one_be);
+
+    for order in [ByteOrder::BigEndian, ByteOrder::LittleEndian] {
+      let x = F::random(OsRng);
+      let bytes = encode_scalar(&x, order);
+      assert_eq!(decode_scalar::<F>(&bytes, order), Ok(x));
+      assert!(decode_scalar::<F>(&bytes[1..], order).is_err());
+      // all-ones exceeds the modulus of every supported field
+      assert!(decode_scalar::<F>(&vec![0xff; bytes.len()], order).is_err());
+    }
+
+    let (x, y) = (F::random(OsRng), F::random(OsRng));
+    let bytes = encode_point(&x, &y, false);
+    assert_eq!(bytes.len(), 2 * scalar_len::<F>() + 1);
+    assert_eq!(decode_point::<F>(&bytes), Ok((x, y, false)));
+    let bytes = encode_point(&F::ZERO, &F::ZERO, true);
+    assert_eq!(decode_point::<F>(&bytes), Ok((F::ZERO, F::ZERO, true)));
+    assert!(decode_point::<F>(&encode_point(&x, &y, true)).is_err());
+    let mut bytes = encode_point(&x, &y, false);
+    *bytes.last_mut().unwrap() = 2;
+    assert!(decode_point::<F>(&bytes).is_err());
+  }
+
+  fn test_transcript_layout_with<E: Engine>() {
+    // transcripts absorb scalars and commitments in the canonical layouts
+    let x = E::Scalar::random(OsRng);
+    assert_eq!(
+      <E::Scalar as TranscriptReprTrait<E::GE>>::to_transcript_bytes(&x),
+      encode_scalar(&x, TRANSCRIPT_BYTE_ORDER)
+    );
+    let ck = E::CE::setup(b"test", 2);
+    let comm = E::CE::commit(&ck, &[x, x.double()], &E::Scalar::ZERO);
+    let (cx, cy, is_infinity) = crate::traits::commitment::CommitmentTrait::to_coordinates(&comm);
+    assert_eq!(
+      comm.to_transcript_bytes(),
+      encode_point(&cx, &cy, is_infinity)
+    );
+    let zero = E::CE::commit(&ck, &[E::Scalar::ZERO], &E::Scalar::ZERO);
+    assert_eq!(
+      decode_point::<E::Base>(&zero.to_transcript_bytes()),
+      Ok((E::Base::ZERO, E::Base::ZERO, true))
+    );
+  }
+
+  #[test]
+  fn test_encoding_round_trip() {
+    test_round_trip_with::<<PallasEngine as Engine>::Scalar>();
+    test_round_trip_with::<<VestaEngine as Engine>::Scalar>();
+    test_round_trip_with::<<Bn256EngineKZG as Engine>::Scalar>();
+    test_round_trip_with::<<GrumpkinEngine as Engine>::Scalar>();
+    test_round_trip_with::<<Secp256k1Engine as Engine>::Scalar>();
+    test_round_trip_with::<<Secq256k1Engine as Engine>::Scalar>();
+  }
+
+  #[test]
+  fn test_transcript_layout() {
+    test_transcript_layout_with::<PallasEngine>();
+    test_transcript_layout_with::<Bn256EngineKZG>();
+    test_transcript_layout_with::<GrumpkinEngine>();
+    test_transcript_layout_with::<Secp256k1Engine>();
+  }
+}
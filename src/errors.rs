@@ -35,6 +35,21 @@ pub enum NovaError {
   /// returned if the provided commitment key is not of sufficient length
   #[error("InvalidCommitmentKeyLength")]
   InvalidCommitmentKeyLength,
+  /// returned if the commitment key of a circuit is too small for the SNARK that compresses it
+  #[error(
+    "CommitmentKeyTooSmall: the {circuit} commitment key has {length} generators, but {required} are required"
+  )]
+  CommitmentKeyTooSmall {
+    /// The circuit whose commitment key is too small, i.e., "primary" or "secondary"
+    circuit: String,
+    /// The number of generators of the commitment key
+    length: usize,
+    /// The number of generators that the SNARK requires
+    required: usize,
+  },
+  /// returned if a commitment key does not match an attestation
+  #[error("CommitmentKeyMismatch")]
+  CommitmentKeyMismatch,
   /// returned if the provided number of steps is zero
   #[error("InvalidNumSteps")]
   InvalidNumSteps,
@@ -68,6 +83,41 @@ pub enum NovaError {
     /// The reason for circuit synthesis failure
     reason: String,
   },
+  /// returned when the options passed to set up public parameters are invalid or cannot be satisfied
+  #[error("SetupError: {reason}")]
+  SetupError {
+    /// The reason for the setup error
+    reason: String,
+  },
+  /// returned when decoding a value from its encoding fails
+  #[error("InvalidEncoding: {reason}")]
+  InvalidEncoding {
+    /// The reason for the decoding failure
+    reason: String,
+  },
+  /// returned when proving with a `RecursiveSNARK` whose witnesses were wiped
+  #[error("WitnessWiped")]
+  WitnessWiped,
+  /// returned when a prover would hold more memory than it is allowed to
+  #[error("MemoryLimitExceeded: {required} bytes required, {available} bytes available")]
+  MemoryLimitExceeded {
+    /// The number of bytes required
+    required: usize,
+    /// The number of bytes available
+    available: usize,
+  },
+  /// returned when a SNARK that cannot bound the memory of its tables is given a `MemoryBudget`
+  #[error("MemoryBudgetUnsupported")]
+  MemoryBudgetUnsupported,
+  /// returned when a tracing pipeline cannot be installed, see `telemetry`
+  #[error("TelemetryError: {reason}")]
+  TelemetryError {
+    /// The reason the pipeline cannot be installed
+    reason: String,
+  },
+  /// returned when proving is interrupted by a `CancellationToken`
+  #[error("Interrupted")]
+  Interrupted,
   /// returned when there is an error creating a digest
   #[error("DigestError")]
   DigestError,
@@ -0,0 +1,73 @@
+//! This module implements utilities over fields that the prover and the verifier share.
+//!
+//! `batch_invert` inverts many field elements at the cost of one inversion and three
+//! multiplications per element (Montgomery's trick), splitting long vectors into chunks that are
+//! inverted in parallel, each with its own inversion.
+use crate::errors::NovaError;
+use ff::Field;
+use rayon::prelude::*;
+
+/// The number of elements below which `batch_invert` does not split its input across threads
+const PAR_CHUNK_SIZE: usize = 1 << 12;
+
+/// Inverts every element of `v` in place, or returns `NovaError::InternalError`, leaving `v`
+/// unchanged, if one of them is zero
+pub fn batch_invert<F: Field>(v: &mut [F]) -> Result<(), NovaError> {
+  if v.par_iter().any(|x| bool::from(x.is_zero())) {
+    return Err(NovaError::InternalError);
+  }
+  if v.len() <= PAR_CHUNK_SIZE {
+    batch_invert_serial(v);
+  } else {
+    v.par_chunks_mut(PAR_CHUNK_SIZE)
+      .for_each(batch_invert_serial);
+  }
+  Ok(())
+}
+
+/// Returns the inverses of the elements of `v`, or `NovaError::InternalError` if one of them is
+/// zero
+pub fn batch_inverse<F: Field>(v: &[F]) -> Result<Vec<F>, NovaError> {
+  let mut inv = v.to_vec();
+  batch_invert(&mut inv)?;
+  Ok(inv)
+}
+
+/// Inverts the nonzero elements of `v` in place with a single inversion
+fn batch_invert_serial<F: Field>(v: &mut [F]) {
+  let mut prefix = Vec::with_capacity(v.len());
+  let mut acc = F::ONE;
+  for x in v.iter() {
+    prefix.push(acc);
+    acc *= x;
+  }
+  let mut inv = acc.invert().unwrap();
+  for (x, p) in v.iter_mut().zip(prefix).rev() {
+    let next = inv * *x;
+    *x = inv * p;
+    inv = next;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::provider::bn256_grumpkin::bn256;
+
+  type F = bn256::Scalar;
+
+  #[test]
+  fn test_batch_invert() {
+    let mut rng = rand::thread_rng();
+    for n in [0, 1, 2, 7, PAR_CHUNK_SIZE, 3 * PAR_CHUNK_SIZE + 5] {
+      let v = (0..n).map(|_| F::random(&mut rng)).collect::<Vec<_>>();
+      let inv = batch_inverse(&v).unwrap();
+      assert!(v.iter().zip(&inv).all(|(x, y)| *x * y == F::ONE));
+    }
+
+    // a zero fails the inversion without changing the input
+    let mut v = vec![F::from(2), F::ZERO, F::from(3)];
+    assert!(batch_invert(&mut v).is_err());
+    assert_eq!(v, vec![F::from(2), F::ZERO, F::from(3)]);
+  }
+}
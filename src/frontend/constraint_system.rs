@@ -1,4 +1,4 @@
-use std::{io, marker::PhantomData};
+use std::{collections::HashSet, io, marker::PhantomData};
 
 use ff::PrimeField;
 
@@ -90,6 +90,19 @@ pub trait ConstraintSystem<Scalar: PrimeField>: Sized + Send {
     A: FnOnce() -> AR,
     AR: Into<String>;
 
+  /// Allocate a private variable whose assignment is nondeterministic advice, i.e., a value
+  /// computed outside of the circuit that is only sound once a constraint ties it down.
+  /// Constraint systems that track hints report those that appear in no constraint, and shape
+  /// synthesis panics on them in debug builds.
+  fn alloc_hint<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+  where
+    F: FnOnce() -> Result<Scalar, SynthesisError>,
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    self.alloc(annotation, f)
+  }
+
   /// Enforce that `A` * `B` = `C`. The `annotation` function is invoked in testing contexts
   /// in order to derive a unique name for the constraint in the current namespace.
   fn enforce<A, AR, LA, LB, LC>(&mut self, annotation: A, a: LA, b: LB, c: LC)
@@ -265,6 +278,15 @@ impl<Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Scalar>
     self.0.alloc_input(annotation, f)
   }
 
+  fn alloc_hint<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+  where
+    F: FnOnce() -> Result<Scalar, SynthesisError>,
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    self.0.alloc_hint(annotation, f)
+  }
+
   fn enforce<A, AR, LA, LB, LC>(&mut self, annotation: A, a: LA, b: LB, c: LC)
   where
     A: FnOnce() -> AR,
@@ -353,6 +375,15 @@ impl<Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Scalar>
     (**self).alloc_input(annotation, f)
   }
 
+  fn alloc_hint<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+  where
+    F: FnOnce() -> Result<Scalar, SynthesisError>,
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    (**self).alloc_hint(annotation, f)
+  }
+
   fn enforce<A, AR, LA, LB, LC>(&mut self, annotation: A, a: LA, b: LB, c: LC)
   where
     A: FnOnce() -> AR,
@@ -428,3 +459,29 @@ impl<Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Scalar>
     (**self).aux_slice()
   }
 }
+
+/// Returns the annotations of the hints, given as pairs of an auxiliary index and an annotation,
+/// that have a non-zero coefficient in none of the linear combinations of `constraints`
+pub(crate) fn unconstrained_hints<'a, Scalar: PrimeField>(
+  hints: &'a [(usize, String)],
+  constraints: impl Iterator<Item = [&'a LinearCombination<Scalar>; 3]>,
+) -> Vec<&'a str> {
+  if hints.is_empty() {
+    return Vec::new();
+  }
+
+  let constrained = constraints
+    .flatten()
+    .flat_map(|lc| lc.iter())
+    .filter_map(|(var, coeff)| match var.get_unchecked() {
+      Index::Aux(index) if !bool::from(coeff.is_zero()) => Some(index),
+      _ => None,
+    })
+    .collect::<HashSet<_>>();
+
+  hints
+    .iter()
+    .filter(|(index, _)| !constrained.contains(index))
+    .map(|(_, annotation)| annotation.as_str())
+    .collect()
+}
@@ -23,6 +23,12 @@ impl AllocatedBit {
     self.variable
   }
 
+  /// Wraps a variable that other constraints already force to be zero or one,
+  /// without constraining it again.
+  pub(crate) fn from_constrained(variable: Variable, value: Option<bool>) -> Self {
+    AllocatedBit { variable, value }
+  }
+
   /// Allocate a variable in the constraint system which can only be a
   /// boolean value. Further, constrain that the boolean is false
   /// unless the condition is false.
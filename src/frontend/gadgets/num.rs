@@ -58,6 +58,31 @@ impl<Scalar: PrimeField> AllocatedNum<Scalar> {
     Self::alloc(cs, || Ok(value())).unwrap()
   }
 
+  /// Allocate a `Variable(Aux)` whose value is nondeterministic advice in a `ConstraintSystem`,
+  /// see `ConstraintSystem::alloc_hint`. The caller must tie it down with a constraint.
+  pub fn alloc_hint<CS, F>(mut cs: CS, value: F) -> Result<Self, SynthesisError>
+  where
+    CS: ConstraintSystem<Scalar>,
+    F: FnOnce() -> Result<Scalar, SynthesisError>,
+  {
+    let mut new_value = None;
+    let var = cs.alloc_hint(
+      || "hint",
+      || {
+        let tmp = value()?;
+
+        new_value = Some(tmp);
+
+        Ok(tmp)
+      },
+    )?;
+
+    Ok(AllocatedNum {
+      value: new_value,
+      variable: var,
+    })
+  }
+
   /// Allocate a `Variable(Input)` in a `ConstraintSystem`.
   pub fn alloc_input<CS, F>(mut cs: CS, value: F) -> Result<Self, SynthesisError>
   where
@@ -1,6 +1,12 @@
 //! The underlying Poseidon sponge code is ported from https://github.com/argumentcomputer/neptune.
 use ff::PrimeField;
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
+use std::{
+  any::{Any, TypeId},
+  collections::HashMap,
+  sync::{Arc, Mutex},
+};
 
 mod circuit2;
 mod circuit2_witness;
@@ -25,7 +31,7 @@ pub use sponge::{
 };
 
 /// The strength of the Poseidon hash function
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Strength {
   /// Standard strength
   Standard,
@@ -35,20 +41,46 @@ pub enum Strength {
 
 const DEFAULT_STRENGTH: Strength = Strength::Standard;
 
-fn round_numbers(arity: usize, strength: &Strength) -> (usize, usize) {
+fn round_numbers<F: PrimeField>(arity: usize, strength: &Strength) -> (usize, usize) {
   match strength {
-    Strength::Standard => round_numbers_base(arity),
-    Strength::Strengthened => round_numbers_strengthened(arity),
+    Strength::Standard => round_numbers_base(arity, F::NUM_BITS),
+    Strength::Strengthened => round_numbers_strengthened(arity, F::NUM_BITS),
   }
 }
 
 const SBOX: u8 = 1; // x^5
 const FIELD: u8 = 1; // Gf(p)
 
+/// The round constants generated so far, by field, arity, and strength
+type RoundConstantsCache = HashMap<(TypeId, usize, Strength), Arc<dyn Any + Send + Sync>>;
+
+/// Returns the round constants of Poseidon over `F`, generated with the Grain LFSR on first use
+/// and cached for the lifetime of the process, so that providers over new fields need not import
+/// constants and setting up many instances of the same hash stays cheap
 fn round_constants<F: PrimeField>(arity: usize, strength: &Strength) -> Vec<F> {
+  static CACHE: OnceCell<Mutex<RoundConstantsCache>> = OnceCell::new();
+  let key = (TypeId::of::<F>(), arity, *strength);
+
+  let cache = CACHE.get_or_init(Default::default);
+  if let Some(constants) = cache.lock().unwrap().get(&key) {
+    if let Some(constants) = constants.downcast_ref::<Vec<F>>() {
+      return constants.clone();
+    }
+  }
+
+  // the lock is not held while generating, so that fields do not wait on each other
+  let constants = generate_round_constants::<F>(arity, strength);
+  cache
+    .lock()
+    .unwrap()
+    .insert(key, Arc::new(constants.clone()));
+  constants
+}
+
+fn generate_round_constants<F: PrimeField>(arity: usize, strength: &Strength) -> Vec<F> {
   let t = arity + 1;
 
-  let (full_rounds, partial_rounds) = round_numbers(arity, strength);
+  let (full_rounds, partial_rounds) = round_numbers::<F>(arity, strength);
 
   let r_f = full_rounds as u16;
   let r_p = partial_rounds as u16;
@@ -146,7 +146,7 @@ where
     let arity = A::to_usize();
     let width = arity + 1;
     let mds = generate_mds(width);
-    let (full_rounds, partial_rounds) = round_numbers(arity, &strength);
+    let (full_rounds, partial_rounds) = round_numbers::<F>(arity, &strength);
     let round_constants = round_constants(arity, &strength);
 
     // Now call new_from_parameters with all the necessary parameters.
@@ -1,4 +1,5 @@
 use ff::PrimeField;
+use std::collections::VecDeque;
 
 /// From the paper ():
 /// The round constants are generated using the Grain LFSR [23] in a self-shrinking
@@ -26,6 +27,10 @@ use ff::PrimeField;
 /// Following https://extgit.iaik.tugraz.at/krypto/hadeshash/blob/master/code/scripts/create_rcs_grain.sage
 /// The script was updated and can currently be found at:
 /// https://extgit.iaik.tugraz.at/krypto/hadeshash/blob/master/code/generate_parameters_grain.sage
+///
+/// Any prime field whose representation has `ceil(field_size / 8)` bytes is supported, in either
+/// byte order. Panics if the S-Box `x^5` is not a permutation of the field, i.e., if 5 divides
+/// `p - 1`, since no round constants make Poseidon secure over such a field.
 pub(crate) fn generate_constants<F: PrimeField>(
   field: u8,
   sbox: u8,
@@ -35,10 +40,12 @@ pub(crate) fn generate_constants<F: PrimeField>(
   r_p: u16,
 ) -> Vec<F> {
   let n_bytes = F::Repr::default().as_ref().len();
-  if n_bytes != 32 {
-    unimplemented!("neptune currently supports 32-byte fields exclusively");
-  }
-  assert_eq!((f32::from(field_size) / 8.0).ceil() as usize, n_bytes);
+  assert_eq!(usize::from(field_size.div_ceil(8)), n_bytes);
+  assert!(
+    sbox != 1 || modulus_minus_one_rem::<F>(5) != 0,
+    "x^5 is not a permutation of the field"
+  );
+  let little_endian = is_little_endian::<F>();
 
   let num_constants = (r_f + r_p) * t;
   let mut init_sequence: Vec<bool> = Vec::new();
@@ -56,7 +63,7 @@ pub(crate) fn generate_constants<F: PrimeField>(
     1 => {
       for _ in 0..num_constants {
         loop {
-          // Generate 32 bytes and interpret them as a big-endian integer. Bytes are
+          // Generate `n_bytes` bytes and interpret them as a big-endian integer. Bytes are
           // big-endian to agree with the integers generated by grain_random_bits in the
           // reference implementation:
           //
@@ -66,7 +73,9 @@ pub(crate) fn generate_constants<F: PrimeField>(
           //     return random_int
           let mut repr = F::Repr::default();
           grain.get_next_bytes(repr.as_mut());
-          repr.as_mut().reverse();
+          if little_endian {
+            repr.as_mut().reverse();
+          }
           if let Some(f) = F::from_repr_vartime(repr) {
             round_constants.push(f);
             break;
@@ -81,6 +90,25 @@ pub(crate) fn generate_constants<F: PrimeField>(
   round_constants
 }
 
+/// Returns whether the representation of `F` is little-endian, as for the fields of the curves of
+/// the crate, rather than big-endian
+fn is_little_endian<F: PrimeField>() -> bool {
+  F::ONE.to_repr().as_ref()[0] == 1
+}
+
+/// Returns the remainder of the division of `p - 1` by `d`, where `p` is the modulus of `F`
+fn modulus_minus_one_rem<F: PrimeField>(d: u64) -> u64 {
+  let repr = (-F::ONE).to_repr();
+  let bytes: Vec<u8> = if is_little_endian::<F>() {
+    repr.as_ref().iter().rev().copied().collect()
+  } else {
+    repr.as_ref().to_vec()
+  };
+  bytes
+    .iter()
+    .fold(0, |rem, byte| ((rem << 8) | u64::from(*byte)) % d)
+}
+
 fn append_bits<T: Into<u128>>(vec: &mut Vec<bool>, n: usize, from: T) {
   let val = from.into();
   for i in (0..n).rev() {
@@ -89,7 +117,7 @@ fn append_bits<T: Into<u128>>(vec: &mut Vec<bool>, n: usize, from: T) {
 }
 
 struct Grain {
-  state: Vec<bool>,
+  state: VecDeque<bool>,
   field_size: u16,
 }
 
@@ -97,7 +125,7 @@ impl Grain {
   fn new(init_sequence: Vec<bool>, field_size: u16) -> Self {
     assert_eq!(80, init_sequence.len());
     let mut g = Grain {
-      state: init_sequence,
+      state: init_sequence.into(),
       field_size,
     };
     for _ in 0..160 {
@@ -110,8 +138,8 @@ impl Grain {
   fn generate_new_bit(&mut self) -> bool {
     let new_bit =
       self.bit(62) ^ self.bit(51) ^ self.bit(38) ^ self.bit(23) ^ self.bit(13) ^ self.bit(0);
-    self.state.remove(0);
-    self.state.push(new_bit);
+    self.state.pop_front();
+    self.state.push_back(new_bit);
     new_bit
   }
 
@@ -165,3 +193,43 @@ impl Iterator for Grain {
     Some(new_bit)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::gadgets::poseidon::{round_constants, Strength},
+    provider::poseidon::PoseidonConstantsCircuit,
+  };
+  use ff::Field;
+  use halo2curves::{bn256, pluto_eris, secp256r1};
+
+  #[test]
+  fn test_generate_constants_for_new_fields() {
+    assert!(is_little_endian::<bn256::Fr>());
+    assert_eq!(modulus_minus_one_rem::<pluto_eris::Fp>(5), 2);
+    assert_eq!(modulus_minus_one_rem::<secp256r1::Fp>(5), 0);
+
+    // a 446-bit field with a 56-byte representation
+    type F = pluto_eris::Fp;
+    let constants = round_constants::<F>(2, &Strength::Standard);
+    let mut distinct = constants.clone();
+    distinct.sort_by_key(|c| c.to_repr().as_ref().to_vec());
+    distinct.dedup();
+    assert_eq!(distinct.len(), constants.len());
+    assert_eq!(round_constants::<F>(2, &Strength::Standard), constants);
+    assert_ne!(round_constants::<F>(2, &Strength::Strengthened), constants);
+
+    // the random oracle of a provider over the field needs no imported constants
+    let constants = PoseidonConstantsCircuit::<F>::default();
+    let hash = |x: u64| constants.hash(&[F::from(x), F::ONE]);
+    assert_eq!(hash(1), hash(1));
+    assert_ne!(hash(1), hash(2));
+  }
+
+  #[test]
+  #[should_panic(expected = "x^5 is not a permutation of the field")]
+  fn test_generate_constants_insecure_sbox() {
+    round_constants::<secp256r1::Fp>(2, &Strength::Standard);
+  }
+}
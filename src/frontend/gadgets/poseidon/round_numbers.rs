@@ -1,12 +1,13 @@
 //! A port of `calc_round_numbers.py`
 //! https://extgit.iaik.tugraz.at/krypto/hadeshash/-/blob/9d80ec0473ad7cde5a12f3aac46439ad0da68c0a/code/scripts/calc_round_numbers.py
-//! from Python2 to Rust for a (roughly) 256-bit prime field (e.g. BLS12-381's scalar field) and
+//! from Python2 to Rust for a prime field of at least 256 bits (e.g. BLS12-381's scalar field) and
 //! 128-bit security level.
 
-// The number of bits of the Poseidon prime field modulus. Denoted `n` in the Poseidon paper
-// (where `n = ceil(log2(p))`). Note that BLS12-381's scalar field modulus is 255 bits, however we
-// use 256 bits for simplicity when operating on bytes as the single bit difference does not affect
-// the round number security properties.
+// The smallest number of bits of the Poseidon prime field modulus. Denoted `n` in the Poseidon
+// paper (where `n = ceil(log2(p))`). Note that BLS12-381's scalar field modulus is 255 bits,
+// however we use 256 bits for simplicity when operating on bytes as the single bit difference does
+// not affect the round number security properties. Larger fields use their own number of bits,
+// since the bounds of the Gröbner basis attacks grow with it.
 const PRIME_BITLEN: usize = 256;
 
 // Security level (in bits), denoted `M` in the Poseidon paper.
@@ -19,10 +20,10 @@ const fn n_sboxes(t: usize, rf: usize, rp: usize) -> usize {
   t * rf + rp
 }
 
-// Returns the round numbers for a given arity `(R_F, R_P)`.
-pub(crate) fn round_numbers_base(arity: usize) -> (usize, usize) {
+// Returns the round numbers for a given arity `(R_F, R_P)`, over a field of `num_bits` bits.
+pub(crate) fn round_numbers_base(arity: usize, num_bits: u32) -> (usize, usize) {
   let t = arity + 1;
-  calc_round_numbers(t, true)
+  calc_round_numbers(t, true, PRIME_BITLEN.max(num_bits as usize))
 }
 
 // In case of newly-discovered attacks, we may need stronger security.
@@ -33,8 +34,8 @@ pub(crate) fn round_numbers_base(arity: usize) -> (usize, usize) {
 // Then it is unlikely that a new attack breaks through this number,
 // but even if this happens then the complexity is almost surely above 2^64, and you will be safe."
 // - D Khovratovich
-pub(crate) fn round_numbers_strengthened(arity: usize) -> (usize, usize) {
-  let (full_round, partial_rounds) = round_numbers_base(arity);
+pub(crate) fn round_numbers_strengthened(arity: usize, num_bits: u32) -> (usize, usize) {
+  let (full_round, partial_rounds) = round_numbers_base(arity, num_bits);
 
   // Increase by 25%, rounding up.
   let strengthened_partial_rounds = f64::ceil(partial_rounds as f64 * 1.25) as usize;
@@ -45,14 +46,14 @@ pub(crate) fn round_numbers_strengthened(arity: usize) -> (usize, usize) {
 // Returns the round numbers for a given width `t`. Here, the `security_margin` parameter does not
 // indicate that we are calculating `R_F` and `R_P` for the "strengthened" round numbers, done in
 // the function `round_numbers_strengthened()`.
-pub(crate) fn calc_round_numbers(t: usize, security_margin: bool) -> (usize, usize) {
+pub(crate) fn calc_round_numbers(t: usize, security_margin: bool, n: usize) -> (usize, usize) {
   let mut rf = 0;
   let mut rp = 0;
   let mut n_sboxes_min = usize::MAX;
 
   for mut rf_test in (2..=1000).step_by(2) {
     for mut rp_test in 4..200 {
-      if round_numbers_are_secure(t, rf_test, rp_test) {
+      if round_numbers_are_secure(t, rf_test, rp_test, n) {
         if security_margin {
           rf_test += 2;
           rp_test = (1.075 * rp_test as f32).ceil() as usize;
@@ -72,8 +73,8 @@ pub(crate) fn calc_round_numbers(t: usize, security_margin: bool) -> (usize, usi
 
 // Returns `true` if the provided round numbers satisfy the security inequalities specified in the
 // Poseidon paper.
-fn round_numbers_are_secure(t: usize, rf: usize, rp: usize) -> bool {
-  let (rp, t, n, m) = (rp as f32, t as f32, PRIME_BITLEN as f32, M as f32);
+fn round_numbers_are_secure(t: usize, rf: usize, rp: usize, n: usize) -> bool {
+  let (rp, t, n, m) = (rp as f32, t as f32, n as f32, M as f32);
   let rf_stat = if m <= (n - 3.0) * (t + 1.0) {
     6.0
   } else {
@@ -34,7 +34,9 @@ mod tests {
       r1cs::{NovaShape, NovaWitness},
       shape_cs::ShapeCS,
       solver::SatisfyingAssignment,
-      ConstraintSystem,
+      test_cs::TestConstraintSystem,
+      test_shape_cs::TestShapeCS,
+      ConstraintSystem, SynthesisError,
     },
     provider::{Bn256EngineKZG, PallasEngine, Secp256k1Engine},
     traits::{snark::default_ck_hint, Engine},
@@ -82,4 +84,76 @@ mod tests {
     test_alloc_bit_with::<Bn256EngineKZG>();
     test_alloc_bit_with::<Secp256k1Engine>();
   }
+
+  fn synthesize_inverse<Fr: PrimeField, CS: ConstraintSystem<Fr>>(
+    cs: &mut CS,
+    constrain: bool,
+  ) -> Result<(), SynthesisError> {
+    // the inverse of x is a hint, which is only sound once x * inv = 1 is enforced
+    let x = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(Fr::from(5u64)))?;
+    let inv = AllocatedNum::alloc_hint(cs.namespace(|| "inv"), || {
+      Ok(
+        x.get_value()
+          .ok_or(SynthesisError::AssignmentMissing)?
+          .invert()
+          .unwrap(),
+      )
+    })?;
+    if constrain {
+      cs.enforce(
+        || "x * inv = 1",
+        |lc| lc + x.get_variable(),
+        |lc| lc + inv.get_variable(),
+        |lc| lc + CS::one(),
+      );
+    }
+    Ok(())
+  }
+
+  fn test_alloc_hint_with<E: Engine>() {
+    for constrain in [true, false] {
+      let mut cs = TestConstraintSystem::<E::Scalar>::new();
+      synthesize_inverse(&mut cs, constrain).unwrap();
+      assert!(cs.is_satisfied());
+      assert_eq!(
+        cs.which_hint_is_unconstrained(),
+        (!constrain).then_some("inv/hint")
+      );
+
+      let mut cs: TestShapeCS<E> = TestShapeCS::new();
+      synthesize_inverse(&mut cs, constrain).unwrap();
+      let expected: &[&str] = if constrain { &[] } else { &["inv/hint"] };
+      assert_eq!(cs.unconstrained_hints(), expected);
+
+      let mut cs: ShapeCS<E> = ShapeCS::new();
+      synthesize_inverse(&mut cs, constrain).unwrap();
+      let expected: &[&str] = if constrain { &[] } else { &["hint"] };
+      assert_eq!(cs.unconstrained_hints(), expected);
+    }
+
+    // a constrained hint is an ordinary witness variable
+    let mut cs: ShapeCS<E> = ShapeCS::new();
+    synthesize_inverse(&mut cs, true).unwrap();
+    let (shape, ck) = cs.r1cs_shape(&*default_ck_hint());
+    let mut cs = SatisfyingAssignment::<E>::new();
+    synthesize_inverse(&mut cs, true).unwrap();
+    let (inst, witness) = cs.r1cs_instance_and_witness(&shape, &ck).unwrap();
+    assert!(shape.is_sat(&ck, &inst, &witness).is_ok());
+  }
+
+  #[test]
+  fn test_alloc_hint() {
+    test_alloc_hint_with::<PallasEngine>();
+    test_alloc_hint_with::<Bn256EngineKZG>();
+    test_alloc_hint_with::<Secp256k1Engine>();
+  }
+
+  #[cfg(debug_assertions)]
+  #[test]
+  #[should_panic(expected = "hints appear in no constraint")]
+  fn test_unconstrained_hint_panics_in_shape() {
+    let mut cs: ShapeCS<Bn256EngineKZG> = ShapeCS::new();
+    synthesize_inverse(&mut cs, false).unwrap();
+    let _ = cs.r1cs_shape_without_ck();
+  }
 }
@@ -2,15 +2,20 @@
 
 #![allow(non_snake_case)]
 
-use super::{shape_cs::ShapeCS, solver::SatisfyingAssignment, test_shape_cs::TestShapeCS};
+use super::{
+  shape_cs::ShapeCS, solver::SatisfyingAssignment, test_shape_cs::TestShapeCS,
+  util_cs::streaming_cs::StreamingWitnessCS,
+};
 use crate::{
   errors::NovaError,
-  frontend::{Index, LinearCombination},
+  frontend::{Index, LinearCombination, SynthesisError},
   r1cs::{CommitmentKeyHint, R1CSInstance, R1CSShape, R1CSWitness, SparseMatrix},
-  traits::Engine,
-  CommitmentKey,
+  traits::{commitment::CommitmentEngineTrait, Engine},
+  Commitment, CommitmentKey,
 };
-use ff::PrimeField;
+use ff::{Field, PrimeField};
+use rand_core::OsRng;
+use std::sync::mpsc::{channel, Sender};
 
 /// `NovaWitness` provide a method for acquiring an `R1CSInstance` and `R1CSWitness` from implementers.
 pub trait NovaWitness<E: Engine> {
@@ -27,7 +32,14 @@ pub trait NovaShape<E: Engine> {
   /// Return an appropriate `R1CSShape` and `CommitmentKey` structs.
   /// A `CommitmentKeyHint` should be provided to help guide the construction of the `CommitmentKey`.
   /// This parameter is documented in `r1cs::R1CS::commitment_key`.
-  fn r1cs_shape(&self, ck_hint: &CommitmentKeyHint<E>) -> (R1CSShape<E>, CommitmentKey<E>);
+  fn r1cs_shape(&self, ck_hint: &CommitmentKeyHint<E>) -> (R1CSShape<E>, CommitmentKey<E>) {
+    let S = self.r1cs_shape_without_ck();
+    let ck = S.commitment_key(ck_hint);
+    (S, ck)
+  }
+
+  /// Return an appropriate `R1CSShape`, without generating a `CommitmentKey` for it.
+  fn r1cs_shape_without_ck(&self) -> R1CSShape<E>;
 }
 
 impl<E: Engine> NovaWitness<E> for SatisfyingAssignment<E> {
@@ -47,13 +59,100 @@ impl<E: Engine> NovaWitness<E> for SatisfyingAssignment<E> {
   }
 }
 
+/// A `StreamingWitnessCS` whose chunks are committed to on a background thread
+pub type StreamingCommitCS<E> =
+  StreamingWitnessCS<<E as Engine>::Scalar, Sender<(usize, Vec<<E as Engine>::Scalar>)>>;
+
+/// Runs `synthesize` to generate a witness for `shape`, while a background thread commits to
+/// every `chunk_size` auxiliary assignments as soon as they are produced, so that the commitment
+/// is mostly computed by the time synthesis completes. Returns the instance and witness, along
+/// with the value returned by `synthesize`.
+pub fn r1cs_instance_and_witness_streaming<E: Engine, T>(
+  shape: &R1CSShape<E>,
+  ck: &CommitmentKey<E>,
+  chunk_size: usize,
+  synthesize: impl FnOnce(&mut StreamingCommitCS<E>) -> Result<T, SynthesisError>,
+) -> Result<(R1CSInstance<E>, R1CSWitness<E>, T), NovaError> {
+  let (sender, receiver) = channel::<(usize, Vec<E::Scalar>)>();
+
+  let (cs, res, comm) = std::thread::scope(|s| {
+    let committer = s.spawn(move || {
+      receiver
+        .into_iter()
+        .filter(|(offset, _)| *offset < shape.num_vars)
+        .fold(Commitment::<E>::default(), |acc, (offset, chunk)| {
+          let end = chunk.len().min(shape.num_vars - offset);
+          acc + E::CE::commit_at(ck, &chunk[..end], offset)
+        })
+    });
+
+    let mut cs = StreamingWitnessCS::new(sender, chunk_size);
+    let res = synthesize(&mut cs);
+    let (cs, sender) = cs.finish();
+    drop(sender);
+
+    committer
+      .join()
+      .map(|comm| (cs, res, comm))
+      .map_err(|_| NovaError::InternalError)
+  })?;
+  let res = res?;
+
+  if cs.aux_assignment().len() > shape.num_vars {
+    return Err(NovaError::InvalidWitnessLength);
+  }
+
+  let W = R1CSWitness::<E>::new(shape, cs.aux_assignment())?;
+  let X = &cs.input_assignment()[1..];
+
+  // the streamed chunks were committed to with a zero blind
+  let comm_W = comm + E::CE::commit(ck, &[], &W.r_W);
+
+  let instance = R1CSInstance::<E>::new(shape, &comm_W, X)?;
+
+  Ok((instance, W, res))
+}
+
+/// Consumes `cs` and returns an instance and witness for `shape`, reusing the buffer holding the
+/// auxiliary assignments as the witness instead of copying it as `NovaWitness` does
+pub fn into_r1cs_instance_and_witness<E: Engine>(
+  cs: SatisfyingAssignment<E>,
+  shape: &R1CSShape<E>,
+  ck: &CommitmentKey<E>,
+) -> Result<(R1CSInstance<E>, R1CSWitness<E>), NovaError> {
+  let mut W = cs.aux_assignment;
+  if W.len() > shape.num_vars {
+    return Err(NovaError::InvalidWitnessLength);
+  }
+  W.resize(shape.num_vars, E::Scalar::ZERO);
+
+  let W = R1CSWitness {
+    W,
+    r_W: E::Scalar::random(&mut OsRng),
+  };
+  let comm_W = W.commit(ck);
+
+  let instance = R1CSInstance::<E>::new(shape, &comm_W, &cs.input_assignment[1..])?;
+
+  Ok((instance, W))
+}
+
 macro_rules! impl_nova_shape {
   ( $name:ident) => {
     impl<E: Engine> NovaShape<E> for $name<E>
     where
       E::Scalar: PrimeField,
     {
-      fn r1cs_shape(&self, ck_hint: &CommitmentKeyHint<E>) -> (R1CSShape<E>, CommitmentKey<E>) {
+      fn r1cs_shape_without_ck(&self) -> R1CSShape<E> {
+        #[cfg(debug_assertions)]
+        {
+          let unconstrained = self.unconstrained_hints();
+          assert!(
+            unconstrained.is_empty(),
+            "hints appear in no constraint: {unconstrained:?}"
+          );
+        }
+
         let mut A = SparseMatrix::<E::Scalar>::empty();
         let mut B = SparseMatrix::<E::Scalar>::empty();
         let mut C = SparseMatrix::<E::Scalar>::empty();
@@ -80,10 +179,7 @@ macro_rules! impl_nova_shape {
         C.cols = num_vars + num_inputs;
 
         // Don't count One as an input for shape's purposes.
-        let S = R1CSShape::new(num_constraints, num_vars, num_inputs - 1, A, B, C).unwrap();
-        let ck = S.commitment_key(ck_hint);
-
-        (S, ck)
+        R1CSShape::new(num_constraints, num_vars, num_inputs - 1, A, B, C).unwrap()
       }
     }
   };
@@ -1,7 +1,10 @@
 //! Support for generating R1CS shape using bellpepper.
 
 use crate::{
-  frontend::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable},
+  frontend::{
+    constraint_system::unconstrained_hints, ConstraintSystem, Index, LinearCombination,
+    SynthesisError, Variable,
+  },
   traits::Engine,
 };
 use ff::PrimeField;
@@ -19,6 +22,7 @@ where
   )>,
   inputs: usize,
   aux: usize,
+  hints: Vec<(usize, String)>,
 }
 
 impl<E: Engine> ShapeCS<E> {
@@ -41,6 +45,14 @@ impl<E: Engine> ShapeCS<E> {
   pub fn num_aux(&self) -> usize {
     self.aux
   }
+
+  /// Returns the annotations of the hints allocated with `alloc_hint` that appear in no constraint
+  pub fn unconstrained_hints(&self) -> Vec<&str> {
+    unconstrained_hints(
+      &self.hints,
+      self.constraints.iter().map(|(a, b, c)| [a, b, c]),
+    )
+  }
 }
 
 impl<E: Engine> Default for ShapeCS<E> {
@@ -49,6 +61,7 @@ impl<E: Engine> Default for ShapeCS<E> {
       constraints: vec![],
       inputs: 1,
       aux: 0,
+      hints: vec![],
     }
   }
 }
@@ -78,6 +91,17 @@ impl<E: Engine> ConstraintSystem<E::Scalar> for ShapeCS<E> {
     Ok(Variable::new_unchecked(Index::Input(self.inputs - 1)))
   }
 
+  fn alloc_hint<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+  where
+    F: FnOnce() -> Result<E::Scalar, SynthesisError>,
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    // namespaces are not tracked, so a hint is identified by its annotation only
+    self.hints.push((self.aux, annotation().into()));
+    self.alloc(|| "", f)
+  }
+
   fn enforce<A, AR, LA, LB, LC>(&mut self, _annotation: A, a: LA, b: LB, c: LC)
   where
     A: FnOnce() -> AR,
@@ -7,7 +7,10 @@ use std::{
 };
 
 use crate::{
-  frontend::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable},
+  frontend::{
+    constraint_system::unconstrained_hints, ConstraintSystem, Index, LinearCombination,
+    SynthesisError, Variable,
+  },
   traits::Engine,
 };
 use core::fmt::Write;
@@ -63,6 +66,7 @@ pub struct TestShapeCS<E: Engine> {
   )>,
   inputs: Vec<String>,
   aux: Vec<String>,
+  hints: Vec<(usize, String)>,
 }
 
 fn proc_lc<Scalar: PrimeField>(
@@ -116,6 +120,14 @@ where
     self.aux.len()
   }
 
+  /// Returns the paths of the hints allocated with `alloc_hint` that appear in no constraint
+  pub fn unconstrained_hints(&self) -> Vec<&str> {
+    unconstrained_hints(
+      &self.hints,
+      self.constraints.iter().map(|(a, b, c, _)| [a, b, c]),
+    )
+  }
+
   /// Print all public inputs, aux inputs, and constraint names.
   #[allow(dead_code)]
   pub fn pretty_print_list(&self) -> Vec<String> {
@@ -226,6 +238,7 @@ impl<E: Engine> Default for TestShapeCS<E> {
       constraints: vec![],
       inputs: vec![String::from("ONE")],
       aux: vec![],
+      hints: vec![],
     }
   }
 }
@@ -260,6 +273,19 @@ where
     Ok(Variable::new_unchecked(Index::Input(self.inputs.len() - 1)))
   }
 
+  fn alloc_hint<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+  where
+    F: FnOnce() -> Result<E::Scalar, SynthesisError>,
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    let var = self.alloc(annotation, f)?;
+    let index = self.aux.len() - 1;
+    self.hints.push((index, self.aux[index].clone()));
+
+    Ok(var)
+  }
+
   fn enforce<A, AR, LA, LB, LC>(&mut self, annotation: A, a: LA, b: LB, c: LC)
   where
     A: FnOnce() -> AR,
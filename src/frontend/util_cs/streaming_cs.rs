@@ -0,0 +1,209 @@
+//! Support for streaming a witness out of a constraint system while it is being generated.
+//!
+//! `StreamingWitnessCS` behaves like `WitnessCS`, but whenever `chunk_size` new auxiliary values
+//! have been assigned, it hands them to a `WitnessSink`. A sink can, for example, copy the values
+//! into a page-locked buffer that an accelerator consumes, or forward them to a thread that
+//! commits to them, so that committing to the witness overlaps with synthesizing the rest of it.
+
+use ff::PrimeField;
+use std::sync::mpsc::Sender;
+
+use crate::frontend::{
+  util_cs::witness_cs::WitnessCS, ConstraintSystem, LinearCombination, SynthesisError, Variable,
+};
+
+/// A consumer of auxiliary assignments produced by a `StreamingWitnessCS`
+pub trait WitnessSink<Scalar: PrimeField>: Send {
+  /// Consumes `values`, which are the auxiliary assignments starting at position `offset`.
+  /// Chunks are delivered in order, and each assignment is delivered exactly once.
+  fn write(&mut self, offset: usize, values: &[Scalar]);
+}
+
+/// A sink that forwards chunks over a channel, e.g., to a thread that commits to them
+impl<Scalar: PrimeField> WitnessSink<Scalar> for Sender<(usize, Vec<Scalar>)> {
+  fn write(&mut self, offset: usize, values: &[Scalar]) {
+    // if the receiving end hung up, there is nobody left to consume the witness
+    let _ = self.send((offset, values.to_vec()));
+  }
+}
+
+/// A `ConstraintSystem` which calculates witness values and streams them to a `WitnessSink`.
+///
+/// A constraint system created with `ConstraintSystem::new` has no sink, and only calculates the
+/// witness values, like `WitnessCS`.
+#[derive(Debug)]
+pub struct StreamingWitnessCS<Scalar: PrimeField, S: WitnessSink<Scalar>> {
+  cs: WitnessCS<Scalar>,
+  sink: Option<S>,
+  chunk_size: usize,
+  flushed: usize, // number of auxiliary assignments handed to the sink
+}
+
+impl<Scalar: PrimeField, S: WitnessSink<Scalar>> StreamingWitnessCS<Scalar, S> {
+  /// Creates a new constraint system that hands every `chunk_size` auxiliary assignments to `sink`
+  pub fn new(sink: S, chunk_size: usize) -> Self {
+    Self {
+      cs: WitnessCS::new(),
+      sink: Some(sink),
+      chunk_size: chunk_size.max(1),
+      flushed: 0,
+    }
+  }
+
+  /// Hands the remaining auxiliary assignments to the sink, and returns the witness and the sink,
+  /// if any
+  pub fn finish(mut self) -> (WitnessCS<Scalar>, Option<S>) {
+    self.flush(true);
+    (self.cs, self.sink)
+  }
+
+  fn flush(&mut self, force: bool) {
+    let Some(sink) = self.sink.as_mut() else {
+      return;
+    };
+    let aux = self.cs.aux_slice();
+    while aux.len() - self.flushed >= self.chunk_size || (force && aux.len() > self.flushed) {
+      let end = aux.len().min(self.flushed + self.chunk_size);
+      sink.write(self.flushed, &aux[self.flushed..end]);
+      self.flushed = end;
+    }
+  }
+}
+
+impl<Scalar: PrimeField, S: WitnessSink<Scalar>> ConstraintSystem<Scalar>
+  for StreamingWitnessCS<Scalar, S>
+{
+  type Root = Self;
+
+  fn new() -> Self {
+    Self {
+      cs: WitnessCS::new(),
+      sink: None,
+      chunk_size: usize::MAX,
+      flushed: 0,
+    }
+  }
+
+  fn alloc<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+  where
+    F: FnOnce() -> Result<Scalar, SynthesisError>,
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    // values handed out through `allocate_empty` are filled in by now, so they can be flushed too
+    self.flush(false);
+    self.cs.alloc(annotation, f)
+  }
+
+  fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+  where
+    F: FnOnce() -> Result<Scalar, SynthesisError>,
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    self.cs.alloc_input(annotation, f)
+  }
+
+  fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, _a: LA, _b: LB, _c: LC)
+  where
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+    LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+  {
+    // Do nothing: we don't care about linear-combination evaluations in this context.
+  }
+
+  fn push_namespace<NR, N>(&mut self, _: N)
+  where
+    NR: Into<String>,
+    N: FnOnce() -> NR,
+  {
+    // Do nothing; we don't care about namespaces in this context.
+  }
+
+  fn pop_namespace(&mut self) {
+    // Do nothing; we don't care about namespaces in this context.
+  }
+
+  fn get_root(&mut self) -> &mut Self::Root {
+    self
+  }
+
+  ////////////////////////////////////////////////////////////////////////////////
+  // Witness generator
+  fn is_witness_generator(&self) -> bool {
+    true
+  }
+
+  fn extend_inputs(&mut self, new_inputs: &[Scalar]) {
+    self.cs.extend_inputs(new_inputs);
+  }
+
+  fn extend_aux(&mut self, new_aux: &[Scalar]) {
+    self.cs.extend_aux(new_aux);
+    self.flush(false);
+  }
+
+  fn allocate_empty(&mut self, aux_n: usize, inputs_n: usize) -> (&mut [Scalar], &mut [Scalar]) {
+    self.cs.allocate_empty(aux_n, inputs_n)
+  }
+
+  fn inputs_slice(&self) -> &[Scalar] {
+    self.cs.inputs_slice()
+  }
+
+  fn aux_slice(&self) -> &[Scalar] {
+    self.cs.aux_slice()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::provider::PallasEngine;
+  use crate::traits::Engine;
+
+  type Scalar = <PallasEngine as Engine>::Scalar;
+
+  #[derive(Default)]
+  struct Chunks(Vec<(usize, Vec<Scalar>)>);
+
+  impl WitnessSink<Scalar> for Chunks {
+    fn write(&mut self, offset: usize, values: &[Scalar]) {
+      self.0.push((offset, values.to_vec()));
+    }
+  }
+
+  #[test]
+  fn test_streaming_witness_cs() {
+    let mut cs = StreamingWitnessCS::new(Chunks::default(), 3);
+    for i in 0..8u64 {
+      cs.alloc(|| format!("a_{i}"), || Ok(Scalar::from(i)))
+        .unwrap();
+    }
+    cs.extend_aux(&[Scalar::from(8u64), Scalar::from(9u64)]);
+    let (witness, Some(Chunks(chunks))) = cs.finish() else {
+      panic!("the sink is returned");
+    };
+
+    let offsets = chunks.iter().map(|(o, _)| *o).collect::<Vec<_>>();
+    assert_eq!(offsets, vec![0, 3, 6, 9]);
+    let streamed = chunks.into_iter().flat_map(|(_, c)| c).collect::<Vec<_>>();
+    assert_eq!(streamed, witness.aux_assignment());
+
+    // without a sink, the witness is only calculated
+    let mut cs = <StreamingWitnessCS<Scalar, Chunks> as ConstraintSystem<Scalar>>::new();
+    for i in 0..8u64 {
+      cs.alloc(|| format!("a_{i}"), || Ok(Scalar::from(i)))
+        .unwrap();
+    }
+    let (witness_without_sink, sink) = cs.finish();
+    assert!(sink.is_none());
+    assert_eq!(
+      witness_without_sink.aux_assignment(),
+      &witness.aux_assignment()[..8]
+    );
+  }
+}
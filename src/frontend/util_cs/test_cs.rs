@@ -2,7 +2,10 @@
 
 use std::collections::HashMap;
 
-use crate::frontend::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use crate::frontend::{
+  constraint_system::unconstrained_hints, ConstraintSystem, Index, LinearCombination,
+  SynthesisError, Variable,
+};
 
 use ff::PrimeField;
 
@@ -27,6 +30,7 @@ pub struct TestConstraintSystem<Scalar: PrimeField> {
   )>,
   inputs: Vec<(Scalar, String)>,
   aux: Vec<(Scalar, String)>,
+  hints: Vec<(usize, String)>,
 }
 
 fn _eval_lc2<Scalar: PrimeField>(
@@ -80,6 +84,7 @@ impl<Scalar: PrimeField> Default for TestConstraintSystem<Scalar> {
       constraints: vec![],
       inputs: vec![(Scalar::ONE, "ONE".into())],
       aux: vec![],
+      hints: vec![],
     }
   }
 }
@@ -112,6 +117,16 @@ impl<Scalar: PrimeField> TestConstraintSystem<Scalar> {
     None
   }
 
+  /// Get path of the first hint allocated with `alloc_hint` that appears in no constraint
+  pub fn which_hint_is_unconstrained(&self) -> Option<&str> {
+    unconstrained_hints(
+      &self.hints,
+      self.constraints.iter().map(|(a, b, c, _)| [a, b, c]),
+    )
+    .first()
+    .copied()
+  }
+
   /// Check if the constraint system is satisfied.
   pub fn is_satisfied(&self) -> bool {
     match self.which_is_unsatisfied() {
@@ -165,6 +180,19 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for TestConstraintSystem<Scala
     Ok(var)
   }
 
+  fn alloc_hint<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+  where
+    F: FnOnce() -> Result<Scalar, SynthesisError>,
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    let var = self.alloc(annotation, f)?;
+    let index = self.aux.len() - 1;
+    self.hints.push((index, self.aux[index].1.clone()));
+
+    Ok(var)
+  }
+
   fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
   where
     F: FnOnce() -> Result<Scalar, SynthesisError>,
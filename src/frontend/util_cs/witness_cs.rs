@@ -47,6 +47,19 @@ impl<Scalar> WitnessCS<Scalar>
 where
   Scalar: PrimeField,
 {
+  /// Creates a constraint system with room for `num_inputs` inputs, including the constant one,
+  /// and `num_aux` auxiliary variables, so that synthesizing a circuit of known size does not
+  /// reallocate its assignments
+  pub fn with_capacity(num_inputs: usize, num_aux: usize) -> Self {
+    let mut input_assignment = Vec::with_capacity(num_inputs.max(1));
+    input_assignment.push(Scalar::ONE);
+
+    Self {
+      input_assignment,
+      aux_assignment: Vec::with_capacity(num_aux),
+    }
+  }
+
   /// Get input assignment
   pub fn input_assignment(&self) -> &[Scalar] {
     &self.input_assignment
@@ -0,0 +1,230 @@
+//! This module defines the entry points of the fuzz targets in `fuzz/`, which exercise the
+//! decoders of untrusted inputs and the verifiers, and the seeds of their corpora.
+//!
+//! Each entry point takes arbitrary bytes, decodes them as one kind of artifact, and verifies
+//! what it decoded against a fixture: public parameters, keys, and a proof for a trivial step
+//! circuit over BN254 and Grumpkin. Decoding or verification may fail, but must never panic.
+//! The fixture is set up deterministically, so the seeds returned by `seeds` verify in every
+//! process that fuzzes them.
+//!
+//! With `cargo-fuzz` installed, the corpora are seeded and a target is run from `fuzz/` with:
+//! ```text
+//! cargo run --release --example seed_corpus
+//! cargo +nightly fuzz run compressed_snark
+//! ```
+use crate::{
+  nova::{CompressedSNARK, PublicParams, RecursiveSNARK, VerifierKey, VerifierParams},
+  provider::{ipa_pc, Bn256EngineIPA, GrumpkinEngine},
+  r1cs::R1CSShape,
+  spartan::snark::RelaxedR1CSSNARK,
+  traits::{
+    calldata::{from_calldata, to_calldata},
+    circuit::TrivialCircuit,
+    commitment::CommitmentEngineTrait,
+    snark::default_ck_hint,
+    Engine,
+  },
+};
+use ff::Field;
+use once_cell::sync::OnceCell;
+use std::io::Cursor;
+
+type E1 = Bn256EngineIPA;
+type E2 = GrumpkinEngine;
+type F = <E1 as Engine>::Scalar;
+type C = TrivialCircuit<F>;
+type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+type Snark = CompressedSNARK<E1, E2, C, S<E1>, S<E2>>;
+
+/// The number of steps of the proofs of the fixture
+const NUM_STEPS: usize = 3;
+
+/// The label and the number of generators of the key file of the fixture, kept small so that
+/// mutations of it are cheap to load
+const KEY_LABEL: &[u8] = b"fuzz";
+const KEY_LEN: usize = 16;
+
+/// The names of the fuzz targets, which are also the names of their corpus directories
+pub const TARGETS: [&str; 7] = [
+  "key_file",
+  "r1cs_shape",
+  "verifier_params",
+  "verifier_key",
+  "recursive_snark",
+  "compressed_snark",
+  "compressed_snark_calldata",
+];
+
+struct Fixture {
+  pp: PublicParams<E1, E2, C>,
+  vp: VerifierParams<E1, E2, C>,
+  vk: VerifierKey<E1, E2, C, S<E1>, S<E2>>,
+  recursive_snark: RecursiveSNARK<E1, E2, C>,
+  compressed_snark: Snark,
+}
+
+fn z0() -> [F; 1] {
+  [F::ONE]
+}
+
+fn fixture() -> &'static Fixture {
+  static FIXTURE: OnceCell<Fixture> = OnceCell::new();
+  FIXTURE.get_or_init(|| {
+    let circuit = C::default();
+    let pp = PublicParams::setup(&circuit, &*default_ck_hint(), &*default_ck_hint())
+      .expect("setting up the fixture cannot fail");
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0()).unwrap();
+    for _ in 0..NUM_STEPS {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let (pk, vk) = Snark::setup(&pp).unwrap();
+    let compressed_snark = Snark::prove(&pp, &pk, &recursive_snark).unwrap();
+
+    let (prover, vp) = pp.split();
+    let pp = PublicParams::join(prover, vp.clone()).unwrap();
+    Fixture {
+      pp,
+      vp,
+      vk,
+      recursive_snark,
+      compressed_snark,
+    }
+  })
+}
+
+/// Loads a commitment key from a key file (see `CommitmentKey::save_to`)
+pub fn key_file(data: &[u8]) {
+  let _ = <E1 as Engine>::CE::load_setup(&mut Cursor::new(data), KEY_LABEL, KEY_LEN);
+}
+
+/// Decodes a shape from the binary and the JSON interchange formats, and checks that a decoded
+/// shape encodes back to a shape that decodes to itself
+pub fn r1cs_shape(data: &[u8]) {
+  if let Ok(shape) = R1CSShape::<E1>::from_bytes(data) {
+    assert_eq!(R1CSShape::<E1>::from_bytes(&shape.to_bytes()), Ok(shape));
+  }
+  if let Ok(json) = std::str::from_utf8(data) {
+    if let Ok(shape) = R1CSShape::<E1>::from_json(json) {
+      assert_eq!(R1CSShape::<E1>::from_json(&shape.to_json()), Ok(shape));
+    }
+  }
+}
+
+/// Decodes verifier parameters and matches them against the verifier key of the fixture
+pub fn verifier_params(data: &[u8]) {
+  if let Ok(vp) = bincode::deserialize::<VerifierParams<E1, E2, C>>(data) {
+    let _ = fixture().vk.matches(&vp);
+  }
+}
+
+/// Decodes a verifier key and verifies the compressed SNARK of the fixture with it
+pub fn verifier_key(data: &[u8]) {
+  if let Ok(vk) = bincode::deserialize::<VerifierKey<E1, E2, C, S<E1>, S<E2>>>(data) {
+    let _ = vk.matches(&fixture().vp);
+    let _ = fixture().compressed_snark.verify(&vk, NUM_STEPS, &z0());
+  }
+}
+
+/// Decodes a recursive SNARK and verifies it with the public parameters of the fixture
+pub fn recursive_snark(data: &[u8]) {
+  if let Ok(snark) = bincode::deserialize::<RecursiveSNARK<E1, E2, C>>(data) {
+    let _ = snark.verify(&fixture().pp, NUM_STEPS, &z0());
+  }
+}
+
+/// Decodes a compressed SNARK and verifies it with the verifier key of the fixture
+pub fn compressed_snark(data: &[u8]) {
+  if let Ok(snark) = bincode::deserialize::<Snark>(data) {
+    let _ = snark.verify(&fixture().vk, NUM_STEPS, &z0());
+  }
+}
+
+/// Decodes a compressed SNARK from calldata and verifies it with the verifier key of the fixture
+pub fn compressed_snark_calldata(data: &[u8]) {
+  if let Ok(snark) = from_calldata::<Snark>(data) {
+    let _ = snark.verify(&fixture().vk, NUM_STEPS, &z0());
+  }
+}
+
+/// Runs the entry point of the fuzz target `target` on `data`, returning false if there is no
+/// such target
+pub fn run(target: &str, data: &[u8]) -> bool {
+  match target {
+    "key_file" => key_file(data),
+    "r1cs_shape" => r1cs_shape(data),
+    "verifier_params" => verifier_params(data),
+    "verifier_key" => verifier_key(data),
+    "recursive_snark" => recursive_snark(data),
+    "compressed_snark" => compressed_snark(data),
+    "compressed_snark_calldata" => compressed_snark_calldata(data),
+    _ => return false,
+  }
+  true
+}
+
+/// Returns valid artifacts of the fixture, as `(target, bytes)` pairs, to seed the corpora of the
+/// fuzz targets
+pub fn seeds() -> Vec<(&'static str, Vec<u8>)> {
+  let fixture = fixture();
+  let (shape, _) = PublicParams::<E1, E2, C>::synthesize_shapes(&C::default()).unwrap();
+
+  let ck = <E1 as Engine>::CE::setup(KEY_LABEL, KEY_LEN);
+  let mut key_file = Vec::new();
+  ck.save_to(&mut key_file).unwrap();
+  let mut key_file_compressed = Vec::new();
+  ck.save_compressed_to(&mut key_file_compressed).unwrap();
+
+  vec![
+    ("key_file", key_file),
+    ("key_file", key_file_compressed),
+    ("r1cs_shape", shape.to_bytes()),
+    ("r1cs_shape", shape.to_json().into_bytes()),
+    ("verifier_params", bincode::serialize(&fixture.vp).unwrap()),
+    ("verifier_key", bincode::serialize(&fixture.vk).unwrap()),
+    (
+      "recursive_snark",
+      bincode::serialize(&fixture.recursive_snark).unwrap(),
+    ),
+    (
+      "compressed_snark",
+      bincode::serialize(&fixture.compressed_snark).unwrap(),
+    ),
+    (
+      "compressed_snark_calldata",
+      to_calldata(&fixture.compressed_snark),
+    ),
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_seeds() {
+    let seeds = seeds();
+    for target in TARGETS {
+      assert!(seeds.iter().any(|(t, _)| *t == target));
+    }
+
+    // the seeds are valid artifacts
+    let fixture = fixture();
+    assert!(R1CSShape::<E1>::from_bytes(&seeds[2].1).is_ok());
+    assert!(R1CSShape::<E1>::from_json(std::str::from_utf8(&seeds[3].1).unwrap()).is_ok());
+    let snark: Snark = bincode::deserialize(&seeds[7].1).unwrap();
+    assert!(snark.verify(&fixture.vk, NUM_STEPS, &z0()).is_ok());
+    let snark = from_calldata::<Snark>(&seeds[8].1).unwrap();
+    assert!(snark.verify(&fixture.vk, NUM_STEPS, &z0()).is_ok());
+    assert!(
+      <E1 as Engine>::CE::load_setup(&mut Cursor::new(&seeds[0].1), KEY_LABEL, KEY_LEN).is_ok()
+    );
+
+    // neither the seeds nor their truncations make the targets panic
+    for (target, data) in &seeds {
+      for len in [0, 1, data.len() / 2, data.len() - 1, data.len()] {
+        assert!(run(target, &data[..len]));
+      }
+    }
+    assert!(!run("unknown", &[]));
+  }
+}
@@ -0,0 +1,358 @@
+//! This module implements conversions of values between the scalar fields of the two curves of a
+//! cycle, natively and in circuits.
+//!
+//! The scalar field of each curve of a cycle is the base field of the other, and the two moduli
+//! differ. On bn256/grumpkin, for instance, the scalar field of bn256 is smaller than its base
+//! field, so an element of the base field only has a counterpart in the scalar field if it is below
+//! the modulus of the scalar field, and a circuit over the scalar field cannot hold every element of
+//! the base field in a single variable. Reinterpreting the bits of a value in the other field, as
+//! `utils::field_switch` does, silently reduces values that do not fit, so values that cross the
+//! boundary should go through the checked conversions of this module instead:
+//! * `try_field_switch` converts a value that fits in the other field, and fails otherwise,
+//! * `to_limbs` and `from_limbs` split a value into limbs that fit in any field of similar size,
+//! * `assert_in_field` constrains a variable to hold an element of another, smaller field, and
+//! * `alloc_limbs` and `assert_limbs_in_field` constrain limbs to hold an element of another field,
+//!   which may be larger than the field of the circuit.
+use crate::frontend::{
+  num::AllocatedNum, AllocatedBit, ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::{PrimeField, PrimeFieldBits};
+
+/// Returns the little-endian bits of the integer that `x` represents, truncated to the number of
+/// bits of the modulus
+fn to_bits<F: PrimeFieldBits>(x: &F) -> Vec<bool> {
+  x.to_le_bits()
+    .into_iter()
+    .take(F::NUM_BITS as usize)
+    .collect()
+}
+
+/// Returns the little-endian bits of the largest element of `F`, i.e., of its modulus minus one
+fn max_bits<F: PrimeFieldBits>() -> Vec<bool> {
+  to_bits(&-F::ONE)
+}
+
+/// Returns whether the integer with little-endian bits `a` is at most the one with bits `b`
+fn bits_le(a: &[bool], b: &[bool]) -> bool {
+  let len = a.len().max(b.len());
+  let bit = |bits: &[bool], i: usize| bits.get(i).copied().unwrap_or(false);
+  (0..len)
+    .rev()
+    .find(|&i| bit(a, i) != bit(b, i))
+    .map_or(true, |i| bit(b, i))
+}
+
+/// Returns the element of `F` with little-endian bits `bits`, which must fit in `F`
+fn from_bits<F: PrimeField>(bits: &[bool]) -> F {
+  bits
+    .iter()
+    .rev()
+    .fold(F::ZERO, |acc, bit| acc.double() + F::from(*bit as u64))
+}
+
+/// Converts `x` into the element of `F2` that represents the same integer, or returns `None` if the
+/// integer is not below the modulus of `F2`
+pub fn try_field_switch<F1, F2>(x: F1) -> Option<F2>
+where
+  F1: PrimeFieldBits,
+  F2: PrimeFieldBits,
+{
+  let bits = to_bits(&x);
+  bits_le(&bits, &max_bits::<F2>()).then(|| from_bits(&bits))
+}
+
+/// Returns the number of limbs of `limb_bits` bits that hold any element of `F`
+pub fn num_limbs<F: PrimeField>(limb_bits: usize) -> usize {
+  (F::NUM_BITS as usize).div_ceil(limb_bits)
+}
+
+/// Splits `x` into `num_limbs::<F1>(limb_bits)` little-endian limbs of `limb_bits` bits, as elements
+/// of `F2`, which must hold `limb_bits` bits
+pub fn to_limbs<F1, F2>(x: &F1, limb_bits: usize) -> Vec<F2>
+where
+  F1: PrimeFieldBits,
+  F2: PrimeField,
+{
+  assert!(limb_bits > 0 && limb_bits <= F2::CAPACITY as usize);
+  let mut bits = to_bits(x);
+  bits.resize(num_limbs::<F1>(limb_bits) * limb_bits, false);
+  bits.chunks(limb_bits).map(from_bits).collect()
+}
+
+/// Recombines little-endian limbs of `limb_bits` bits into an element of `F1`, or returns `None`
+/// if a limb does not fit in `limb_bits` bits or if the integer they form is not below the modulus
+/// of `F1`
+pub fn from_limbs<F1, F2>(limbs: &[F2], limb_bits: usize) -> Option<F1>
+where
+  F1: PrimeFieldBits,
+  F2: PrimeFieldBits,
+{
+  let mut bits = Vec::with_capacity(limbs.len() * limb_bits);
+  for limb in limbs {
+    let mut limb = to_bits(limb);
+    if limb.iter().skip(limb_bits).any(|bit| *bit) {
+      return None;
+    }
+    limb.resize(limb_bits, false);
+    bits.extend(limb);
+  }
+  bits_le(&bits, &max_bits::<F1>()).then(|| from_bits(&bits))
+}
+
+/// Constrains `x` to hold an element of the field `G`, i.e., to be below the modulus of `G`, and
+/// returns its `G::NUM_BITS` little-endian bits.
+///
+/// The modulus of `G` must be at most the one of `F`, and otherwise `SynthesisError::Unsatisfiable`
+/// is returned, since elements of `G` must then be held in limbs (see `alloc_limbs`).
+pub fn assert_in_field<G, F, CS>(
+  mut cs: CS,
+  x: &AllocatedNum<F>,
+) -> Result<Vec<AllocatedBit>, SynthesisError>
+where
+  G: PrimeFieldBits,
+  F: PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  if !bits_le(&max_bits::<G>(), &max_bits::<F>()) {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+
+  // the bits may exceed the capacity of `F`, but they are unique once bounded by the modulus of `G`
+  let values = x.get_value().map(|v| to_bits(&v));
+  let bits = (0..G::NUM_BITS as usize)
+    .map(|i| {
+      AllocatedBit::alloc(
+        cs.namespace(|| format!("bit {i}")),
+        values.as_ref().map(|v| v.get(i).copied().unwrap_or(false)),
+      )
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let mut sum = LinearCombination::zero();
+  let mut coeff = F::ONE;
+  for bit in &bits {
+    sum = sum + (coeff, bit.get_variable());
+    coeff = coeff.double();
+  }
+  cs.enforce(
+    || "bits sum to the number",
+    |_| sum,
+    |lc| lc + CS::one(),
+    |lc| lc + x.get_variable(),
+  );
+
+  enforce_at_most(cs.namespace(|| "below modulus"), &bits, &max_bits::<G>())?;
+  Ok(bits)
+}
+
+/// Allocates the `num_limbs::<G>(limb_bits)` little-endian limbs of `limb_bits` bits of `value`,
+/// and constrains them to hold an element of `G` (see `assert_limbs_in_field`)
+pub fn alloc_limbs<G, F, CS>(
+  mut cs: CS,
+  value: Option<G>,
+  limb_bits: usize,
+) -> Result<Vec<AllocatedNum<F>>, SynthesisError>
+where
+  G: PrimeFieldBits,
+  F: PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  if limb_bits == 0 || limb_bits > F::CAPACITY as usize {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+  let values = value.map(|v| to_limbs::<G, F>(&v, limb_bits));
+  let limbs = (0..num_limbs::<G>(limb_bits))
+    .map(|i| {
+      AllocatedNum::alloc(cs.namespace(|| format!("limb {i}")), || {
+        values
+          .as_ref()
+          .map(|v| v[i])
+          .ok_or(SynthesisError::AssignmentMissing)
+      })
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  assert_limbs_in_field::<G, F, _>(cs.namespace(|| "in field"), &limbs, limb_bits)?;
+  Ok(limbs)
+}
+
+/// Constrains each of the little-endian `limbs` to fit in `limb_bits` bits and the integer they
+/// form to be below the modulus of `G`, and returns the little-endian bits of that integer
+pub fn assert_limbs_in_field<G, F, CS>(
+  mut cs: CS,
+  limbs: &[AllocatedNum<F>],
+  limb_bits: usize,
+) -> Result<Vec<AllocatedBit>, SynthesisError>
+where
+  G: PrimeFieldBits,
+  F: PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  if limb_bits == 0 || limb_bits > F::CAPACITY as usize || limbs.len() != num_limbs::<G>(limb_bits)
+  {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+
+  let mut bits = Vec::with_capacity(limbs.len() * limb_bits);
+  for (i, limb) in limbs.iter().enumerate() {
+    bits.extend(super::range::assert_bits(
+      cs.namespace(|| format!("limb {i}")),
+      limb,
+      limb_bits,
+    )?);
+  }
+  enforce_at_most(cs.namespace(|| "below modulus"), &bits, &max_bits::<G>())?;
+  Ok(bits)
+}
+
+/// Constrains the integer with little-endian bits `bits` to be at most the constant with
+/// little-endian bits `bound`.
+///
+/// Scanning from the most significant bit, `eq` tracks whether the bits equal those of the bound so
+/// far. Where the bound has a zero, a bit must be zero while `eq` holds, and where the bound has a
+/// one, `eq` only survives if the bit is one too.
+fn enforce_at_most<F, CS>(
+  mut cs: CS,
+  bits: &[AllocatedBit],
+  bound: &[bool],
+) -> Result<(), SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  // bits above the bound must all be zero
+  for (i, bit) in bits.iter().enumerate().skip(bound.len()) {
+    cs.enforce(
+      || format!("bit {i} is zero"),
+      |lc| lc + bit.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc,
+    );
+  }
+
+  // `None` stands for the constant true
+  let mut eq: Option<AllocatedBit> = None;
+  for i in (0..bits.len().min(bound.len())).rev() {
+    let bit = &bits[i];
+    if bound[i] {
+      eq = Some(match &eq {
+        None => bit.clone(),
+        Some(eq) => AllocatedBit::and(cs.namespace(|| format!("eq {i}")), eq, bit)?,
+      });
+    } else {
+      let eq_lc = |lc: LinearCombination<F>| match &eq {
+        None => lc + CS::one(),
+        Some(eq) => lc + eq.get_variable(),
+      };
+      cs.enforce(
+        || format!("bit {i} is zero if the higher bits are equal"),
+        eq_lc,
+        |lc| lc + bit.get_variable(),
+        |lc| lc,
+      );
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::frontend::test_cs::TestConstraintSystem;
+  use ff::Field;
+  use halo2curves::bn256::{Fq, Fr};
+  use rand_core::OsRng;
+
+  #[test]
+  fn test_native_conversions() {
+    // the scalar field of bn256 is smaller than its base field
+    let r_minus_one = -Fr::ONE;
+    let q_minus_one = -Fq::ONE;
+    assert_eq!(
+      try_field_switch::<Fr, Fq>(r_minus_one),
+      Some(r_as_fq() - Fq::ONE)
+    );
+    assert_eq!(try_field_switch::<Fq, Fr>(r_as_fq()), None);
+    assert_eq!(try_field_switch::<Fq, Fr>(q_minus_one), None);
+    assert_eq!(
+      try_field_switch::<Fq, Fr>(Fq::from(42u64)),
+      Some(Fr::from(42u64))
+    );
+
+    for _ in 0..10 {
+      let x = Fq::random(OsRng);
+      let limbs = to_limbs::<Fq, Fr>(&x, 64);
+      assert_eq!(limbs.len(), 4);
+      assert_eq!(from_limbs::<Fq, Fr>(&limbs, 64), Some(x));
+    }
+
+    // limbs that overflow their width, or that exceed the modulus, are rejected
+    assert_eq!(from_limbs::<Fq, Fr>(&[Fr::from(1u64 << 10)], 10), None);
+    let limbs = to_limbs::<Fq, Fr>(&r_as_fq(), 64);
+    assert_eq!(from_limbs::<Fr, Fr>(&limbs, 64), None);
+    assert_eq!(from_limbs::<Fq, Fr>(&limbs, 64), Some(r_as_fq()));
+  }
+
+  /// Returns the modulus of `Fr` as an element of `Fq`
+  fn r_as_fq() -> Fq {
+    from_bits::<Fq>(&max_bits::<Fr>()) + Fq::ONE
+  }
+
+  #[test]
+  fn test_assert_in_field() {
+    let check = |x: Fq| {
+      let mut cs = TestConstraintSystem::<Fq>::new();
+      let x = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(x)).unwrap();
+      assert_in_field::<Fr, _, _>(cs.namespace(|| "in field"), &x).unwrap();
+      cs.is_satisfied()
+    };
+    assert!(check(Fq::ZERO));
+    assert!(check(Fq::from(42u64)));
+    assert!(check(r_as_fq() - Fq::ONE));
+    assert!(!check(r_as_fq()));
+    assert!(!check(-Fq::ONE));
+
+    // elements of a larger field do not fit in a single variable
+    let mut cs = TestConstraintSystem::<Fr>::new();
+    let x = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(Fr::ONE)).unwrap();
+    assert!(matches!(
+      assert_in_field::<Fq, _, _>(cs.namespace(|| "in field"), &x),
+      Err(SynthesisError::Unsatisfiable)
+    ));
+  }
+
+  #[test]
+  fn test_limbs_in_field() {
+    // any element of the larger field is held in limbs
+    for x in [Fq::ZERO, Fq::random(OsRng), -Fq::ONE] {
+      let mut cs = TestConstraintSystem::<Fr>::new();
+      let limbs = alloc_limbs::<Fq, _, _>(cs.namespace(|| "limbs"), Some(x), 64).unwrap();
+      assert!(cs.is_satisfied());
+      let values = limbs
+        .iter()
+        .map(|limb| limb.get_value().unwrap())
+        .collect::<Vec<_>>();
+      assert_eq!(from_limbs::<Fq, Fr>(&values, 64), Some(x));
+    }
+
+    // limbs that form the modulus, or a limb that overflows its width, are rejected
+    let check = |limbs: &[Fr]| {
+      let mut cs = TestConstraintSystem::<Fr>::new();
+      let limbs = limbs
+        .iter()
+        .enumerate()
+        .map(|(i, v)| AllocatedNum::alloc(cs.namespace(|| format!("limb {i}")), || Ok(*v)).unwrap())
+        .collect::<Vec<_>>();
+      assert_limbs_in_field::<Fr, _, _>(cs.namespace(|| "in field"), &limbs, 64).unwrap();
+      cs.is_satisfied()
+    };
+    let r_limbs = to_limbs::<Fq, Fr>(&r_as_fq(), 64);
+    let mut below = r_limbs.clone();
+    below[0] -= Fr::ONE;
+    assert!(check(&below));
+    assert!(!check(&r_limbs));
+    let mut overflow = below.clone();
+    overflow[0] += Fr::from(u64::MAX) + Fr::ONE;
+    overflow[1] -= Fr::ONE;
+    assert!(!check(&overflow));
+  }
+}
@@ -3,20 +3,29 @@
 use crate::{
   constants::{BN_LIMB_WIDTH as LIMB_WIDTH, BN_N_LIMBS as N_LIMBS},
   frontend::{
-    num::AllocatedNum, AllocatedBit, Assignment, Boolean, ConstraintSystem, SynthesisError,
+    num::AllocatedNum, AllocatedBit, Assignment, Boolean, ConstraintSystem, LinearCombination,
+    SynthesisError,
   },
   gadgets::{
-    nonnative::{bignat::BigNat, util::f_to_nat},
+    nonnative::{
+      bignat::{nat_to_limbs, BigNat, BigNatParams, Polynomial},
+      util::{f_to_nat, nat_to_f},
+    },
     utils::{
       alloc_bignat_constant, alloc_num_equals, alloc_one, alloc_zero, conditionally_select,
       conditionally_select2, conditionally_select_bignat, select_num_or_one, select_num_or_zero,
       select_num_or_zero2, select_one_or_diff2, select_one_or_num2, select_zero_or_num2,
     },
   },
-  traits::{Engine, Group, ROCircuitTrait},
+  provider::traits::DlogGroup,
+  traits::{Engine, Group, PrimeFieldExt, ROCircuitTrait},
 };
 use ff::{Field, PrimeField};
 use num_bigint::BigInt;
+use sha3::{
+  digest::{ExtendableOutput, Update, XofReader},
+  Shake256,
+};
 
 /// `AllocatedPoint` provides an elliptic curve abstraction inside a circuit.
 #[derive(Clone)]
@@ -853,6 +862,608 @@ impl<E: Engine> AllocatedNonnativePoint<E> {
   }
 }
 
+/// The label from which the offset of the accumulator of `AllocatedNonnativePoint::mul_add` is derived
+const MUL_ADD_OFFSET_LABEL: &[u8] = b"NovaNonnativeMulAddOffset";
+
+/// The number of bits of the scalar that `AllocatedNonnativePoint::mul_add` processes at a time
+const MUL_ADD_WINDOW: usize = 4;
+
+impl<E: Engine> AllocatedNonnativePoint<E>
+where
+  E::GE: DlogGroup,
+{
+  /// Returns `other + r * self`, where `r` is the scalar with the little-endian bits `bits`.
+  ///
+  /// `self` is checked to be on the curve unless it is the point at infinity, while `other` is
+  /// expected to be on the curve or the point at infinity, e.g., because it is bound by a hash.
+  /// The result is the point at infinity only when `self` is the point at infinity and `other` is.
+  /// Other results at infinity, e.g., when `r` is zero and `other` is the point at infinity, have no
+  /// witness, which an honest prover hits with negligible probability when `r` is a random challenge.
+  ///
+  /// The arithmetic uses incomplete affine formulas on an accumulator that is offset by a fixed
+  /// point, so only curves of the form `y^2 = x^3 + b` are supported.
+  pub fn mul_add<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    bits: &[AllocatedBit],
+    other: &Self,
+  ) -> Result<Self, SynthesisError> {
+    let curve = NonnativeCurve::new::<E>()?;
+
+    let self_is_infinity = self.is_infinity_bit(cs.namespace(|| "self is infinity"))?;
+    let other_is_infinity = other.is_infinity_bit(cs.namespace(|| "other is infinity"))?;
+
+    // the point at infinity is replaced by the generator, and results computed from it are discarded
+    let gen = NonnativeAffine::constant::<CS>(&E::GE::gen().to_coordinates())?;
+    let q = NonnativeAffine::from_point(cs.namespace(|| "range check self"), self)?;
+    let q = gen.select(cs.namespace(|| "self or generator"), &self_is_infinity, &q)?;
+    let p = NonnativeAffine::from_point(cs.namespace(|| "range check other"), other)?;
+    let p = gen.select(
+      cs.namespace(|| "other or generator"),
+      &other_is_infinity,
+      &p,
+    )?;
+    q.check_on_curve(cs.namespace(|| "self on curve"), &curve)?;
+
+    // table[d] = d * q, except for table[0], which is never added
+    let mut table = vec![
+      q.clone(),
+      q.clone(),
+      q.double(cs.namespace(|| "2 * self"), &curve)?,
+    ];
+    for d in 3..1 << MUL_ADD_WINDOW {
+      // (d - 1) * q and q have distinct x-coordinates since the group has a large prime order
+      let entry = table[d - 1].add(cs.namespace(|| format!("{d} * self")), &q, &curve, false)?;
+      table.push(entry);
+    }
+
+    // the accumulator starts at a fixed offset A so that it never is the point at infinity
+    let windows = bits
+      .iter()
+      .map(|bit| Boolean::from(bit.clone()))
+      .chain(std::iter::repeat(Boolean::Constant(false)))
+      .take(bits.len().div_ceil(MUL_ADD_WINDOW) * MUL_ADD_WINDOW)
+      .collect::<Vec<_>>();
+    let num_windows = windows.len() / MUL_ADD_WINDOW;
+    let offset = E::GE::gen() * mul_add_offset::<E>();
+    let mut acc = NonnativeAffine::constant::<CS>(
+      &(offset * E::Scalar::from(1 << MUL_ADD_WINDOW as u64)).to_coordinates(),
+    )?;
+    for (j, window) in windows.chunks(MUL_ADD_WINDOW).rev().enumerate() {
+      let mut cs = cs.namespace(|| format!("window {j}"));
+      if j > 0 {
+        for k in 0..MUL_ADD_WINDOW {
+          acc = acc.double(cs.namespace(|| format!("double {k}")), &curve)?;
+        }
+      }
+
+      let entry = NonnativeAffine::lookup(cs.namespace(|| "lookup"), &table, window)?;
+      let sum = acc.add(cs.namespace(|| "add entry"), &entry, &curve, true)?;
+      let mut is_zero = window[0].not();
+      for (k, bit) in window.iter().enumerate().skip(1) {
+        is_zero = Boolean::and(
+          cs.namespace(|| format!("is zero {k}")),
+          &is_zero,
+          &bit.not(),
+        )?;
+      }
+      acc = acc.select(cs.namespace(|| "select sum"), &is_zero, &sum)?;
+    }
+
+    // acc = 2^(|windows| * MUL_ADD_WINDOW) * A + r * q
+    let sum = acc.add(cs.namespace(|| "add other"), &p, &curve, true)?;
+    let acc = acc.select(cs.namespace(|| "select other"), &other_is_infinity, &sum)?;
+    let correction = E::GE::zero()
+      - offset * E::Scalar::from(2).pow_vartime([(num_windows * MUL_ADD_WINDOW) as u64]);
+    let correction = NonnativeAffine::constant::<CS>(&correction.to_coordinates())?;
+    // the result is discarded when self is the point at infinity, in which case the offset is kept
+    // so that the addition below succeeds even if the result would be the point at infinity
+    let correction = gen.select(
+      cs.namespace(|| "correction or generator"),
+      &self_is_infinity,
+      &correction,
+    )?;
+    let res = acc.add(cs.namespace(|| "remove offset"), &correction, &curve, true)?;
+
+    let x = conditionally_select_bignat(
+      cs.namespace(|| "select x"),
+      &other.x,
+      &res.x,
+      &self_is_infinity,
+    )?;
+    let y = conditionally_select_bignat(
+      cs.namespace(|| "select y"),
+      &other.y,
+      &res.y,
+      &self_is_infinity,
+    )?;
+    let zero = alloc_zero(cs.namespace(|| "zero"));
+    let is_infinity = conditionally_select(
+      cs.namespace(|| "select is_infinity"),
+      &other.is_infinity,
+      &zero,
+      &self_is_infinity,
+    )?;
+
+    Ok(Self { x, y, is_infinity })
+  }
+
+  /// Returns `self.is_infinity` as a `Boolean`
+  fn is_infinity_bit<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+  ) -> Result<Boolean, SynthesisError> {
+    let bit = AllocatedBit::alloc(
+      cs.namespace(|| "bit"),
+      self.is_infinity.get_value().map(|v| v == E::Scalar::ONE),
+    )?;
+    cs.enforce(
+      || "bit = is_infinity",
+      |lc| lc + bit.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + self.is_infinity.get_variable(),
+    );
+    Ok(Boolean::from(bit))
+  }
+}
+
+/// Returns the discrete logarithm of the offset of the accumulator of `mul_add`
+fn mul_add_offset<E: Engine>() -> E::Scalar {
+  let mut shake = Shake256::default();
+  shake.update(MUL_ADD_OFFSET_LABEL);
+  let mut bytes = [0u8; 64];
+  shake.finalize_xof().read(&mut bytes);
+  E::Scalar::from_uniform(&bytes)
+}
+
+/// The parameters of the curve `y^2 = x^3 + b` of `AllocatedNonnativePoint`s
+struct NonnativeCurve {
+  modulus: BigInt,
+  b: BigInt,
+}
+
+impl NonnativeCurve {
+  fn new<E: Engine>() -> Result<Self, SynthesisError> {
+    let (a, b, _, modulus) = E::GE::group_params();
+    if a != E::Base::ZERO {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    Ok(Self {
+      modulus,
+      b: f_to_nat(&b),
+    })
+  }
+}
+
+/// The affine coordinates of a point on the curve of `AllocatedNonnativePoint`s that is not the
+/// point at infinity. The coordinates are range checked to `N_LIMBS` limbs of `LIMB_WIDTH` bits.
+#[derive(Clone)]
+struct NonnativeAffine<E: Engine> {
+  x: BigNat<E::Scalar>,
+  y: BigNat<E::Scalar>,
+}
+
+impl<E: Engine> NonnativeAffine<E> {
+  /// Range checks the coordinates of `p`
+  fn from_point<CS: ConstraintSystem<E::Scalar>>(
+    mut cs: CS,
+    p: &AllocatedNonnativePoint<E>,
+  ) -> Result<Self, SynthesisError> {
+    p.x.assert_well_formed(cs.namespace(|| "x"))?;
+    p.y.assert_well_formed(cs.namespace(|| "y"))?;
+    Ok(Self {
+      x: p.x.clone(),
+      y: p.y.clone(),
+    })
+  }
+
+  /// Returns the point with the coordinates `coords` as a constant
+  fn constant<CS: ConstraintSystem<E::Scalar>>(
+    coords: &(E::Base, E::Base, bool),
+  ) -> Result<Self, SynthesisError> {
+    let constant = |v: &E::Base| -> Result<BigNat<E::Scalar>, SynthesisError> {
+      let value = f_to_nat(v);
+      let limbs = nat_to_limbs::<E::Scalar>(&value, LIMB_WIDTH, N_LIMBS)?;
+      Ok(BigNat {
+        limbs: limbs
+          .iter()
+          .map(|limb| LinearCombination::zero() + (*limb, CS::one()))
+          .collect(),
+        limb_values: Some(limbs),
+        value: Some(value),
+        params: BigNatParams::new(LIMB_WIDTH, N_LIMBS),
+      })
+    };
+    Ok(Self {
+      x: constant(&coords.0)?,
+      y: constant(&coords.1)?,
+    })
+  }
+
+  /// Allocates a coordinate with the value `v` and range checks it
+  fn alloc_coordinate<CS: ConstraintSystem<E::Scalar>>(
+    mut cs: CS,
+    v: Option<E::Base>,
+  ) -> Result<BigNat<E::Scalar>, SynthesisError> {
+    let n = BigNat::alloc_from_nat(
+      cs.namespace(|| "alloc"),
+      || Ok(f_to_nat(v.get()?)),
+      LIMB_WIDTH,
+      N_LIMBS,
+    )?;
+    n.assert_well_formed(cs.namespace(|| "rangecheck"))?;
+    Ok(n)
+  }
+
+  /// Returns the values of the coordinates
+  fn values(&self) -> Option<(E::Base, E::Base)> {
+    let value = |n: &BigNat<E::Scalar>| n.value.as_ref().and_then(nat_to_f::<E::Base>);
+    value(&self.x).zip(value(&self.y))
+  }
+
+  /// Enforces `y^2 = x^3 + b`
+  fn check_on_curve<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    curve: &NonnativeCurve,
+  ) -> Result<(), SynthesisError> {
+    let x_sq = Self::alloc_coordinate(
+      cs.namespace(|| "x^2"),
+      self.values().map(|(x, _)| x.square()),
+    )?;
+    enforce_congruent(
+      cs.namespace(|| "x * x = x^2"),
+      &[Term::Product(1, &self.x, &self.x)],
+      &[Term::Linear(1, &x_sq)],
+      &curve.modulus,
+    )?;
+    enforce_congruent(
+      cs.namespace(|| "y * y = x * x^2 + b"),
+      &[Term::Product(1, &self.y, &self.y)],
+      &[Term::Product(1, &self.x, &x_sq), Term::Constant(&curve.b)],
+      &curve.modulus,
+    )
+  }
+
+  /// Returns `self + other`, which requires `self.x != other.x`. This is enforced when
+  /// `check_distinct` is set, and must otherwise be guaranteed by the caller.
+  fn add<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    other: &Self,
+    curve: &NonnativeCurve,
+    check_distinct: bool,
+  ) -> Result<Self, SynthesisError> {
+    let values = self.values().zip(other.values());
+    let inv = values
+      .map(|((x1, _), (x2, _))| Option::<E::Base>::from((x2 - x1).invert()).unwrap_or_default());
+
+    // lambda * (x2 - x1) = y2 - y1
+    let lambda = Self::alloc_coordinate(
+      cs.namespace(|| "lambda"),
+      values
+        .zip(inv)
+        .map(|(((_, y1), (_, y2)), inv)| (y2 - y1) * inv),
+    )?;
+    enforce_congruent(
+      cs.namespace(|| "check lambda"),
+      &[
+        Term::Product(1, &lambda, &other.x),
+        Term::Linear(1, &self.y),
+      ],
+      &[
+        Term::Product(1, &lambda, &self.x),
+        Term::Linear(1, &other.y),
+      ],
+      &curve.modulus,
+    )?;
+
+    let res = self.chord(cs.namespace(|| "result"), &lambda, &other.x, curve)?;
+
+    if check_distinct {
+      // (x2 - x1) has an inverse
+      let inv = Self::alloc_coordinate(cs.namespace(|| "inv"), inv)?;
+      enforce_congruent(
+        cs.namespace(|| "check inv"),
+        &[Term::Product(1, &inv, &other.x)],
+        &[
+          Term::Product(1, &inv, &self.x),
+          Term::Constant(&BigInt::from(1)),
+        ],
+        &curve.modulus,
+      )?;
+    }
+
+    Ok(res)
+  }
+
+  /// Returns `2 * self`
+  fn double<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    curve: &NonnativeCurve,
+  ) -> Result<Self, SynthesisError> {
+    // lambda * 2y = 3x^2, where y != 0 as the group has no point of order 2
+    let lambda = Self::alloc_coordinate(
+      cs.namespace(|| "lambda"),
+      self.values().map(|(x, y)| {
+        x.square()
+          * E::Base::from(3)
+          * Option::<E::Base>::from(y.double().invert()).unwrap_or_default()
+      }),
+    )?;
+    enforce_congruent(
+      cs.namespace(|| "check lambda"),
+      &[Term::Product(2, &lambda, &self.y)],
+      &[Term::Product(3, &self.x, &self.x)],
+      &curve.modulus,
+    )?;
+
+    self.chord(cs.namespace(|| "result"), &lambda, &self.x, curve)
+  }
+
+  /// Returns the third point on the line through `self` with slope `lambda` that meets the curve
+  /// at a point with the x-coordinate `x2`, negated
+  fn chord<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    lambda: &BigNat<E::Scalar>,
+    x2: &BigNat<E::Scalar>,
+    curve: &NonnativeCurve,
+  ) -> Result<Self, SynthesisError> {
+    let lambda_v = lambda.value.as_ref().and_then(nat_to_f::<E::Base>);
+    let x2_v = x2.value.as_ref().and_then(nat_to_f::<E::Base>);
+
+    // x3 = lambda^2 - x1 - x2
+    let x3_v = self
+      .values()
+      .zip(lambda_v.zip(x2_v))
+      .map(|((x1, _), (lambda, x2))| lambda.square() - x1 - x2);
+    let x = Self::alloc_coordinate(cs.namespace(|| "x"), x3_v)?;
+    enforce_congruent(
+      cs.namespace(|| "check x"),
+      &[Term::Product(1, lambda, lambda)],
+      &[
+        Term::Linear(1, &x),
+        Term::Linear(1, &self.x),
+        Term::Linear(1, x2),
+      ],
+      &curve.modulus,
+    )?;
+
+    // y3 = lambda * (x1 - x3) - y1
+    let y3_v = self
+      .values()
+      .zip(lambda_v.zip(x3_v))
+      .map(|((x1, y1), (lambda, x3))| lambda * (x1 - x3) - y1);
+    let y = Self::alloc_coordinate(cs.namespace(|| "y"), y3_v)?;
+    enforce_congruent(
+      cs.namespace(|| "check y"),
+      &[Term::Product(1, lambda, &self.x)],
+      &[
+        Term::Product(1, lambda, &x),
+        Term::Linear(1, &y),
+        Term::Linear(1, &self.y),
+      ],
+      &curve.modulus,
+    )?;
+
+    Ok(Self { x, y })
+  }
+
+  /// If condition outputs self otherwise outputs other
+  fn select<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    condition: &Boolean,
+    other: &Self,
+  ) -> Result<Self, SynthesisError> {
+    Ok(Self {
+      x: conditionally_select_bignat(cs.namespace(|| "select x"), &self.x, &other.x, condition)?,
+      y: conditionally_select_bignat(cs.namespace(|| "select y"), &self.y, &other.y, condition)?,
+    })
+  }
+
+  /// Returns `table[d]`, where `d` is the number with the little-endian bits `bits`
+  fn lookup<CS: ConstraintSystem<E::Scalar>>(
+    mut cs: CS,
+    table: &[Self],
+    bits: &[Boolean],
+  ) -> Result<Self, SynthesisError> {
+    let mut entries = table.to_vec();
+    for (i, bit) in bits.iter().enumerate() {
+      entries = entries
+        .chunks(2)
+        .enumerate()
+        .map(|(j, pair)| pair[1].select(cs.namespace(|| format!("select {i} {j}")), bit, &pair[0]))
+        .collect::<Result<Vec<_>, _>>()?;
+    }
+    Ok(entries.swap_remove(0))
+  }
+}
+
+/// A term of an expression over the base field that is evaluated in a circuit over the scalar field
+enum Term<'a, F: PrimeField> {
+  /// `c * a * b`
+  Product(u64, &'a BigNat<F>, &'a BigNat<F>),
+  /// `c * a`
+  Linear(u64, &'a BigNat<F>),
+  /// A constant
+  Constant(&'a BigInt),
+}
+
+/// The sum of terms, as a polynomial in the limb base
+struct TermSum<F: PrimeField> {
+  poly: Polynomial<F>,
+  // an upper bound on the coefficients of `poly`
+  max_word: BigInt,
+  value: Option<BigInt>,
+}
+
+impl<F: PrimeField> TermSum<F> {
+  fn alloc<CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    terms: &[Term<'_, F>],
+  ) -> Result<Self, SynthesisError> {
+    let mut coefficients: Vec<LinearCombination<F>> = Vec::new();
+    let mut values = Some(Vec::new());
+    let mut max_word = BigInt::from(0);
+    let mut value = Some(BigInt::from(0));
+
+    for (i, term) in terms.iter().enumerate() {
+      let (c, poly, term_max_word, term_value) = match term {
+        Term::Product(c, a, b) => {
+          let poly = Polynomial::from((*a).clone()).alloc_product(
+            cs.namespace(|| format!("product {i}")),
+            &Polynomial::from((*b).clone()),
+          )?;
+          let term_max_word = BigInt::from(a.limbs.len().min(b.limbs.len()))
+            * &a.params.max_word
+            * &b.params.max_word;
+          let term_value = a.value.as_ref().zip(b.value.as_ref()).map(|(a, b)| a * b);
+          (*c, poly, term_max_word, term_value)
+        }
+        Term::Linear(c, a) => (
+          *c,
+          Polynomial::from((*a).clone()),
+          a.params.max_word.clone(),
+          a.value.clone(),
+        ),
+        Term::Constant(k) => {
+          let n_limbs = (k.bits() as usize).div_ceil(LIMB_WIDTH).max(1);
+          let limbs = nat_to_limbs::<F>(k, LIMB_WIDTH, n_limbs)?;
+          let poly = Polynomial {
+            coefficients: limbs
+              .iter()
+              .map(|limb| LinearCombination::zero() + (*limb, CS::one()))
+              .collect(),
+            values: Some(limbs),
+          };
+          (
+            1,
+            poly,
+            BigNatParams::new(LIMB_WIDTH, n_limbs).max_word,
+            Some((*k).clone()),
+          )
+        }
+      };
+
+      let c_f = F::from(c);
+      if coefficients.len() < poly.coefficients.len() {
+        coefficients.resize(poly.coefficients.len(), LinearCombination::zero());
+      }
+      for (acc, lc) in coefficients.iter_mut().zip(&poly.coefficients) {
+        *acc = std::mem::take(acc) + (c_f, lc);
+      }
+      values = values
+        .zip(poly.values)
+        .map(|(mut acc, vs): (Vec<F>, Vec<F>)| {
+          if acc.len() < vs.len() {
+            acc.resize(vs.len(), F::ZERO);
+          }
+          for (acc, v) in acc.iter_mut().zip(vs) {
+            *acc += c_f * v;
+          }
+          acc
+        });
+      max_word += term_max_word * c;
+      value = value.zip(term_value).map(|(acc, v)| acc + v * c);
+    }
+
+    Ok(Self {
+      poly: Polynomial {
+        coefficients,
+        values,
+      },
+      max_word,
+      value,
+    })
+  }
+
+  /// Returns an upper bound on the value of the sum
+  fn bound(&self) -> BigInt {
+    (0..self.poly.coefficients.len()).fold(BigInt::from(0), |acc, _| {
+      (acc << LIMB_WIDTH) + &self.max_word
+    })
+  }
+}
+
+/// Enforces that the sums of `lhs` and of `rhs` are congruent modulo `modulus`.
+///
+/// This proves that `lhs + K = rhs + q * modulus` over the integers for a quotient `q` and a
+/// multiple `K` of the modulus that exceeds `rhs`, so every term of both sides is non-negative.
+fn enforce_congruent<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  lhs: &[Term<'_, F>],
+  rhs: &[Term<'_, F>],
+  modulus: &BigInt,
+) -> Result<(), SynthesisError> {
+  let lhs = TermSum::alloc(cs.namespace(|| "lhs"), lhs)?;
+  let rhs = TermSum::alloc(cs.namespace(|| "rhs"), rhs)?;
+
+  let k = (rhs.bound() / modulus + 1) * modulus;
+  let quotient_bound: BigInt = (lhs.bound() + &k) / modulus;
+  let quotient_limbs = quotient_bound.bits().div_ceil(LIMB_WIDTH as u64).max(1) as usize;
+  let quotient = BigNat::alloc_from_nat(
+    cs.namespace(|| "quotient"),
+    || Ok((lhs.value.get()? + &k - rhs.value.get()?) / modulus),
+    LIMB_WIDTH,
+    quotient_limbs,
+  )?;
+  quotient.assert_well_formed(cs.namespace(|| "quotient rangecheck"))?;
+
+  // lhs + K
+  let k_limbs = nat_to_limbs::<F>(&k, LIMB_WIDTH, (k.bits() as usize).div_ceil(LIMB_WIDTH))?;
+  let mut left = lhs.poly.sum(&Polynomial {
+    coefficients: k_limbs
+      .iter()
+      .map(|limb| LinearCombination::zero() + (*limb, CS::one()))
+      .collect(),
+    values: Some(k_limbs.clone()),
+  });
+
+  // rhs + q * modulus, where the product with the constant modulus is linear in q
+  let m_limbs = nat_to_limbs::<F>(
+    modulus,
+    LIMB_WIDTH,
+    (modulus.bits() as usize).div_ceil(LIMB_WIDTH),
+  )?;
+  let n_product = quotient_limbs + m_limbs.len() - 1;
+  let mut product = Polynomial {
+    coefficients: vec![LinearCombination::zero(); n_product],
+    values: quotient
+      .limb_values
+      .as_ref()
+      .map(|_| vec![F::ZERO; n_product]),
+  };
+  for (i, q) in quotient.limbs.iter().enumerate() {
+    for (j, m) in m_limbs.iter().enumerate() {
+      product.coefficients[i + j] = std::mem::take(&mut product.coefficients[i + j]) + (*m, q);
+      if let (Some(vs), Some(qs)) = (product.values.as_mut(), quotient.limb_values.as_ref()) {
+        vs[i + j] += *m * qs[i];
+      }
+    }
+  }
+  let mut right = rhs.poly.sum(&product);
+
+  // both sides are carried over the same number of limbs
+  let n = left.coefficients.len().max(right.coefficients.len());
+  for poly in [&mut left, &mut right] {
+    poly.coefficients.resize(n, LinearCombination::zero());
+    if let Some(vs) = poly.values.as_mut() {
+      vs.resize(n, F::ZERO);
+    }
+  }
+
+  let limb_max = BigNatParams::new(LIMB_WIDTH, 1).max_word;
+  let left_max_word = &lhs.max_word + &limb_max;
+  let right_max_word =
+    &rhs.max_word + BigInt::from(quotient_limbs.min(m_limbs.len())) * &limb_max * &limb_max;
+  let left = BigNat::from_poly(left, LIMB_WIDTH, left_max_word);
+  let right = BigNat::from_poly(right, LIMB_WIDTH, right_max_word);
+  left.equal_when_carried_regroup(cs.namespace(|| "carry"), &right)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -860,6 +1471,7 @@ mod tests {
     frontend::{
       r1cs::{NovaShape, NovaWitness},
       solver::SatisfyingAssignment,
+      test_cs::TestConstraintSystem,
       test_shape_cs::TestShapeCS,
     },
     provider::{
@@ -1239,4 +1851,93 @@ mod tests {
     // Make sure that it is satisfiable
     assert!(shape.is_sat(&ck, &inst, &witness).is_ok());
   }
+
+  fn synthesize_mul_add<E, CS>(
+    mut cs: CS,
+    q: (E::Base, E::Base, bool),
+    p: (E::Base, E::Base, bool),
+    r: u64,
+    num_bits: usize,
+  ) -> AllocatedNonnativePoint<E>
+  where
+    E: Engine,
+    E::GE: DlogGroup,
+    CS: ConstraintSystem<E::Scalar>,
+  {
+    let q = AllocatedNonnativePoint::<E>::alloc(cs.namespace(|| "q"), Some(q)).unwrap();
+    let p = AllocatedNonnativePoint::<E>::alloc(cs.namespace(|| "p"), Some(p)).unwrap();
+    let bits = (0..num_bits)
+      .map(|i| AllocatedBit::alloc(cs.namespace(|| format!("bit {i}")), Some((r >> i) & 1 == 1)))
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+    q.mul_add(cs.namespace(|| "p + r * q"), &bits, &p).unwrap()
+  }
+
+  #[test]
+  fn test_nonnative_mul_add() {
+    test_nonnative_mul_add_with::<PallasEngine>();
+    test_nonnative_mul_add_with::<Bn256EngineKZG>();
+    test_nonnative_mul_add_with::<Secp256k1Engine>();
+  }
+
+  fn test_nonnative_mul_add_with<E: Engine>()
+  where
+    E::GE: DlogGroup,
+  {
+    let q = E::GE::gen() * E::Scalar::random(&mut OsRng);
+    let p = E::GE::gen() * E::Scalar::random(&mut OsRng);
+    let zero = E::GE::zero();
+
+    // scalars whose number of bits is not a multiple of the window, including zero
+    for (r, num_bits) in [
+      (1, 1),
+      (17, 5),
+      (0b10_1101_0110, 10),
+      (0, 6),
+      (u64::MAX, 64),
+    ] {
+      for (q, p) in [(q, p), (zero, p), (q, zero), (zero, zero)] {
+        let mut cs = TestConstraintSystem::<E::Scalar>::new();
+        let res = synthesize_mul_add::<E, _>(
+          cs.namespace(|| "mul_add"),
+          q.to_coordinates(),
+          p.to_coordinates(),
+          r,
+          num_bits,
+        );
+
+        // the incomplete formulas cannot produce the point at infinity from a point that is not
+        let expected = p + q * E::Scalar::from(r);
+        if expected == zero && q != zero {
+          assert!(!cs.is_satisfied());
+          continue;
+        }
+        assert!(cs.is_satisfied());
+
+        let (x, y, is_infinity) = expected.to_coordinates();
+        assert_eq!(res.x.value, Some(f_to_nat(&x)));
+        assert_eq!(res.y.value, Some(f_to_nat(&y)));
+        assert_eq!(
+          res.is_infinity.get_value(),
+          Some(if is_infinity {
+            E::Scalar::ONE
+          } else {
+            E::Scalar::ZERO
+          })
+        );
+      }
+    }
+
+    // a point that is not on the curve is rejected
+    let (x, y, _) = q.to_coordinates();
+    let mut cs = TestConstraintSystem::<E::Scalar>::new();
+    let _ = synthesize_mul_add::<E, _>(
+      cs.namespace(|| "mul_add"),
+      (x, y + E::Base::ONE, false),
+      p.to_coordinates(),
+      3,
+      2,
+    );
+    assert!(!cs.is_satisfied());
+  }
 }
@@ -0,0 +1,496 @@
+//! This module implements fixed-point arithmetic gadgets, such as those needed to fold the layers
+//! of a quantized neural network step by step.
+//!
+//! A `FixedPointFormat` has `total_bits` bits, including the sign, of which the last `frac_bits`
+//! are fractional: a number `x` is represented by the signed integer `round(x * 2^frac_bits)` in
+//! `[-2^(total_bits - 1), 2^(total_bits - 1))`, and by that integer modulo the order of the field in
+//! circuits. Integer arithmetic is the special case where `frac_bits` is zero.
+//!
+//! Every `FixedPoint` is range checked, so overflows make the constraint system unsatisfiable
+//! rather than wrapping around. Products are rounded towards negative infinity, like an arithmetic
+//! right shift. `FixedPoint::dot` rounds the sum of products once, which is both cheaper and more
+//! precise than rounding each product, so layers should prefer it to chains of `mul` and `add`.
+use crate::{
+  frontend::{num::AllocatedNum, Boolean, ConstraintSystem, LinearCombination, SynthesisError},
+  gadgets::{range::decompose, select::select},
+};
+use ff::{PrimeField, PrimeFieldBits};
+
+/// The format of fixed-point numbers: their total number of bits, including the sign, and the
+/// number of fractional bits among them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedPointFormat {
+  total_bits: u32,
+  frac_bits: u32,
+}
+
+impl FixedPointFormat {
+  /// Creates a format with `total_bits` bits, at most 64, of which `frac_bits` are fractional
+  pub fn new(total_bits: u32, frac_bits: u32) -> Self {
+    assert!(
+      frac_bits < total_bits && total_bits <= 64,
+      "a fixed-point format needs fewer fractional bits than its at most 64 bits"
+    );
+    Self {
+      total_bits,
+      frac_bits,
+    }
+  }
+
+  /// Creates a format for signed integers of `total_bits` bits
+  pub fn integer(total_bits: u32) -> Self {
+    Self::new(total_bits, 0)
+  }
+
+  /// Returns the total number of bits, including the sign
+  pub fn total_bits(&self) -> u32 {
+    self.total_bits
+  }
+
+  /// Returns the number of fractional bits
+  pub fn frac_bits(&self) -> u32 {
+    self.frac_bits
+  }
+
+  /// Returns the smallest and the largest representation in the format
+  pub fn bounds(&self) -> (i64, i64) {
+    let half = 1i128 << (self.total_bits - 1);
+    (-half as i64, (half - 1) as i64)
+  }
+
+  /// Returns the representation of `x`, rounded to the nearest and clamped to the bounds
+  pub fn quantize(&self, x: f64) -> i64 {
+    let (lo, hi) = self.bounds();
+    (x * (1u64 << self.frac_bits) as f64)
+      .round()
+      .clamp(lo as f64, hi as f64) as i64
+  }
+
+  /// Returns the number that `v` represents
+  pub fn dequantize(&self, v: i64) -> f64 {
+    v as f64 / (1u64 << self.frac_bits) as f64
+  }
+
+  /// Returns `2^(total_bits - 1)`, which shifts representations into `[0, 2^total_bits)`
+  fn offset<F: PrimeField>(&self) -> F {
+    F::from_u128(1 << (self.total_bits - 1))
+  }
+}
+
+/// Returns the field element that represents the signed integer `v`
+pub fn signed_to_field<F: PrimeField>(v: i128) -> F {
+  if v < 0 {
+    -F::from_u128(v.unsigned_abs())
+  } else {
+    F::from_u128(v as u128)
+  }
+}
+
+/// An allocated fixed-point number in a `FixedPointFormat`
+#[derive(Clone)]
+pub struct FixedPoint<F: PrimeField> {
+  num: AllocatedNum<F>,
+  value: Option<i64>,
+  format: FixedPointFormat,
+}
+
+impl<F: PrimeField + PrimeFieldBits> FixedPoint<F> {
+  /// Allocates the representation `value` in `format` and checks that it is within the bounds of
+  /// the format, with `total_bits + 1` constraints
+  pub fn alloc<CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    format: FixedPointFormat,
+    value: Option<i64>,
+  ) -> Result<Self, SynthesisError> {
+    let num = AllocatedNum::alloc(cs.namespace(|| "num"), || {
+      value
+        .map(|v| signed_to_field(v as i128))
+        .ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    Self::from_num(cs.namespace(|| "range"), format, num)
+  }
+
+  /// Interprets `num` as a representation in `format`, and checks that it is within the bounds of
+  /// the format, with `total_bits + 1` constraints
+  pub fn from_num<CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    format: FixedPointFormat,
+    num: AllocatedNum<F>,
+  ) -> Result<Self, SynthesisError> {
+    let (shifted, lc) = shifted::<F, CS>(&num, &format);
+    decompose(
+      cs.namespace(|| "in range"),
+      shifted,
+      lc,
+      format.total_bits as usize,
+    )?;
+    let value = num.get_value().and_then(|v| to_signed(v, &format));
+    Ok(Self { num, value, format })
+  }
+
+  /// Returns the allocated representation
+  pub fn num(&self) -> &AllocatedNum<F> {
+    &self.num
+  }
+
+  /// Returns the representation, if it is known
+  pub fn get_value(&self) -> Option<i64> {
+    self.value
+  }
+
+  /// Returns the format of the number
+  pub fn format(&self) -> FixedPointFormat {
+    self.format
+  }
+
+  /// Returns `self + other`, with `total_bits + 2` constraints
+  pub fn add<CS: ConstraintSystem<F>>(
+    &self,
+    mut cs: CS,
+    other: &Self,
+  ) -> Result<Self, SynthesisError> {
+    check_formats(self, other)?;
+    let sum = self.num.add(cs.namespace(|| "sum"), &other.num)?;
+    Self::from_num(cs.namespace(|| "range"), self.format, sum)
+  }
+
+  /// Returns `self - other`, with `total_bits + 2` constraints
+  pub fn sub<CS: ConstraintSystem<F>>(
+    &self,
+    mut cs: CS,
+    other: &Self,
+  ) -> Result<Self, SynthesisError> {
+    check_formats(self, other)?;
+    let diff = AllocatedNum::alloc(cs.namespace(|| "diff"), || {
+      Ok(
+        self
+          .num
+          .get_value()
+          .ok_or(SynthesisError::AssignmentMissing)?
+          - other
+            .num
+            .get_value()
+            .ok_or(SynthesisError::AssignmentMissing)?,
+      )
+    })?;
+    cs.enforce(
+      || "diff = self - other",
+      |lc| lc + self.num.get_variable() - other.num.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + diff.get_variable(),
+    );
+    Self::from_num(cs.namespace(|| "range"), self.format, diff)
+  }
+
+  /// Returns `self * other`, rounded towards negative infinity, with `total_bits + frac_bits + 4`
+  /// constraints
+  pub fn mul<CS: ConstraintSystem<F>>(&self, cs: CS, other: &Self) -> Result<Self, SynthesisError> {
+    Self::dot(cs, std::slice::from_ref(self), std::slice::from_ref(other))
+  }
+
+  /// Returns the sum of the products of the entries of `a` and `b`, rounded once towards negative
+  /// infinity, with `a.len() + total_bits + frac_bits + 3` constraints
+  pub fn dot<CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    a: &[Self],
+    b: &[Self],
+  ) -> Result<Self, SynthesisError> {
+    if a.len() != b.len() || a.is_empty() {
+      return Err(SynthesisError::IncompatibleLengthVector(format!(
+        "cannot take the dot product of {} and {} numbers",
+        a.len(),
+        b.len()
+      )));
+    }
+    for (x, y) in a.iter().zip(b) {
+      check_formats(&a[0], x)?;
+      check_formats(&a[0], y)?;
+    }
+    let format = a[0].format;
+    // the sum of the products must fit in an i128 and not wrap around the field
+    let bits = 2 * format.total_bits as usize + (usize::BITS - a.len().leading_zeros()) as usize;
+    if bits >= (F::CAPACITY as usize).min(127) {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+
+    let mut sum = LinearCombination::zero();
+    let mut sum_value = Some(0i128);
+    for (i, (x, y)) in a.iter().zip(b).enumerate() {
+      let p = x.num.mul(cs.namespace(|| format!("product {i}")), &y.num)?;
+      sum = sum + p.get_variable();
+      sum_value = sum_value
+        .zip(x.value.zip(y.value))
+        .map(|(s, (x, y))| s + x as i128 * y as i128);
+    }
+
+    // sum = q * 2^frac_bits + r, where 0 <= r < 2^frac_bits
+    let (q_value, r_value) = match sum_value {
+      Some(s) => (
+        Some(s >> format.frac_bits),
+        Some(s & ((1 << format.frac_bits) - 1)),
+      ),
+      None => (None, None),
+    };
+    let q = AllocatedNum::alloc(cs.namespace(|| "q"), || {
+      q_value
+        .map(signed_to_field)
+        .ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    let r = AllocatedNum::alloc(cs.namespace(|| "r"), || {
+      r_value
+        .map(signed_to_field)
+        .ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    decompose(
+      cs.namespace(|| "r in range"),
+      r.get_value(),
+      LinearCombination::zero() + r.get_variable(),
+      format.frac_bits as usize,
+    )?;
+    let scale = F::from_u128(1 << format.frac_bits);
+    cs.enforce(
+      || "sum = q * 2^frac_bits + r",
+      |_| sum,
+      |lc| lc + CS::one(),
+      |lc| lc + (scale, q.get_variable()) + r.get_variable(),
+    );
+    Self::from_num(cs.namespace(|| "q in range"), format, q)
+  }
+
+  /// Returns `max(self, 0)`, with `total_bits + 2` constraints
+  pub fn relu<CS: ConstraintSystem<F>>(&self, mut cs: CS) -> Result<Self, SynthesisError> {
+    let non_negative = self.is_non_negative(cs.namespace(|| "sign"))?;
+    let num = AllocatedNum::alloc(cs.namespace(|| "relu"), || {
+      match (non_negative.get_value(), self.num.get_value()) {
+        (Some(true), Some(v)) => Ok(v),
+        (Some(false), _) => Ok(F::ZERO),
+        _ => Err(SynthesisError::AssignmentMissing),
+      }
+    })?;
+    cs.enforce(
+      || "relu = non_negative * self",
+      |_| non_negative.lc(CS::one(), F::ONE),
+      |lc| lc + self.num.get_variable(),
+      |lc| lc + num.get_variable(),
+    );
+    Ok(Self {
+      num,
+      value: self.value.map(|v| v.max(0)),
+      format: self.format,
+    })
+  }
+
+  /// Returns a boolean that is true if and only if `self < other`, with `total_bits + 2`
+  /// constraints
+  pub fn less_than<CS: ConstraintSystem<F>>(
+    &self,
+    mut cs: CS,
+    other: &Self,
+  ) -> Result<Boolean, SynthesisError> {
+    check_formats(self, other)?;
+    // self - other + 2^total_bits lies in [1, 2^(total_bits + 1)), and its top bit is set if and
+    // only if self >= other
+    let n = self.format.total_bits as usize;
+    let offset = F::from_u128(1 << n);
+    let value = self
+      .num
+      .get_value()
+      .zip(other.num.get_value())
+      .map(|(a, b)| a - b + offset);
+    let lc = LinearCombination::zero() + self.num.get_variable() - other.num.get_variable()
+      + (offset, CS::one());
+    let bits = decompose(cs.namespace(|| "difference"), value, lc, n + 1)?;
+    Ok(Boolean::from(bits[n].clone()).not())
+  }
+
+  /// Returns the larger of `self` and `other`, with `total_bits + 3` constraints
+  pub fn max<CS: ConstraintSystem<F>>(
+    &self,
+    mut cs: CS,
+    other: &Self,
+  ) -> Result<Self, SynthesisError> {
+    let lt = self.less_than(cs.namespace(|| "lt"), other)?;
+    let num = select(cs.namespace(|| "select"), &lt, &other.num, &self.num)?;
+    Ok(Self {
+      num,
+      value: self.value.zip(other.value).map(|(a, b)| a.max(b)),
+      format: self.format,
+    })
+  }
+
+  /// Returns a boolean that is true if and only if `self >= 0`, with `total_bits + 1` constraints
+  fn is_non_negative<CS: ConstraintSystem<F>>(
+    &self,
+    mut cs: CS,
+  ) -> Result<Boolean, SynthesisError> {
+    let (shifted, lc) = shifted::<F, CS>(&self.num, &self.format);
+    let bits = decompose(
+      cs.namespace(|| "shifted"),
+      shifted,
+      lc,
+      self.format.total_bits as usize,
+    )?;
+    Ok(Boolean::from(
+      bits[self.format.total_bits as usize - 1].clone(),
+    ))
+  }
+}
+
+/// Returns `num + 2^(total_bits - 1)`, which lies in `[0, 2^total_bits)` exactly when `num` is within
+/// the bounds of `format`, as a value and as a linear combination
+fn shifted<F: PrimeField, CS: ConstraintSystem<F>>(
+  num: &AllocatedNum<F>,
+  format: &FixedPointFormat,
+) -> (Option<F>, LinearCombination<F>) {
+  let offset = format.offset::<F>();
+  let value = num.get_value().map(|v| v + offset);
+  let lc = LinearCombination::zero() + num.get_variable() + (offset, CS::one());
+  (value, lc)
+}
+
+/// Returns the signed integer that `v` represents in `format`, if it is within its bounds
+fn to_signed<F: PrimeFieldBits>(v: F, format: &FixedPointFormat) -> Option<i64> {
+  let shifted = v + format.offset::<F>();
+  let bits = shifted.to_le_bits();
+  if bits.iter().skip(format.total_bits as usize).any(|b| *b) {
+    return None;
+  }
+  let u = bits
+    .iter()
+    .take(format.total_bits as usize)
+    .rev()
+    .fold(0u128, |acc, b| (acc << 1) | *b as u128);
+  Some((u as i128 - (1i128 << (format.total_bits - 1))) as i64)
+}
+
+/// Checks that `a` and `b` have the same format
+fn check_formats<F: PrimeField>(
+  a: &FixedPoint<F>,
+  b: &FixedPoint<F>,
+) -> Result<(), SynthesisError> {
+  if a.format == b.format {
+    Ok(())
+  } else {
+    Err(SynthesisError::Unsatisfiable)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{frontend::test_cs::TestConstraintSystem, provider::PallasEngine, traits::Engine};
+
+  type F = <PallasEngine as Engine>::Scalar;
+
+  const Q8_8: FixedPointFormat = FixedPointFormat {
+    total_bits: 16,
+    frac_bits: 8,
+  };
+
+  fn alloc<CS: ConstraintSystem<F>>(cs: &mut CS, name: &str, x: f64) -> FixedPoint<F> {
+    FixedPoint::alloc(cs.namespace(|| name), Q8_8, Some(Q8_8.quantize(x))).unwrap()
+  }
+
+  #[test]
+  fn test_quantize() {
+    assert_eq!(Q8_8.quantize(1.5), 384);
+    assert_eq!(Q8_8.quantize(-0.25), -64);
+    assert_eq!(Q8_8.dequantize(Q8_8.quantize(-3.375)), -3.375);
+    // values out of range are clamped
+    assert_eq!(Q8_8.quantize(1000.0), i16::MAX as i64);
+    assert_eq!(Q8_8.quantize(-1000.0), i16::MIN as i64);
+    assert_eq!(FixedPointFormat::integer(8).bounds(), (-128, 127));
+  }
+
+  #[test]
+  fn test_arithmetic() {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let a = alloc(&mut cs, "a", 1.5);
+    let b = alloc(&mut cs, "b", -2.25);
+    assert_eq!(cs.num_constraints(), 2 * 17);
+
+    let sum = a.add(cs.namespace(|| "add"), &b).unwrap();
+    assert_eq!(Q8_8.dequantize(sum.get_value().unwrap()), -0.75);
+    let diff = a.sub(cs.namespace(|| "sub"), &b).unwrap();
+    assert_eq!(Q8_8.dequantize(diff.get_value().unwrap()), 3.75);
+
+    let base = cs.num_constraints();
+    let prod = a.mul(cs.namespace(|| "mul"), &b).unwrap();
+    assert_eq!(Q8_8.dequantize(prod.get_value().unwrap()), -3.375);
+    assert_eq!(cs.num_constraints(), base + 16 + 8 + 4);
+
+    // products are rounded towards negative infinity
+    let c = FixedPoint::alloc(cs.namespace(|| "c"), Q8_8, Some(-1)).unwrap();
+    let half = alloc(&mut cs, "half", 0.5);
+    let prod = c.mul(cs.namespace(|| "round"), &half).unwrap();
+    assert_eq!(prod.get_value(), Some(-1));
+
+    let dot = FixedPoint::dot(
+      cs.namespace(|| "dot"),
+      &[a.clone(), b.clone()],
+      &[b.clone(), half.clone()],
+    )
+    .unwrap();
+    assert_eq!(Q8_8.dequantize(dot.get_value().unwrap()), -3.375 - 1.125);
+    assert!(cs.is_satisfied());
+  }
+
+  #[test]
+  fn test_relu_and_comparison() {
+    let mut cs = TestConstraintSystem::<F>::new();
+    let a = alloc(&mut cs, "a", 1.5);
+    let b = alloc(&mut cs, "b", -2.25);
+
+    let base = cs.num_constraints();
+    assert_eq!(
+      a.relu(cs.namespace(|| "relu a")).unwrap().get_value(),
+      a.get_value()
+    );
+    assert_eq!(cs.num_constraints(), base + 18);
+    assert_eq!(
+      b.relu(cs.namespace(|| "relu b")).unwrap().get_value(),
+      Some(0)
+    );
+
+    for (x, y) in [(&a, &b), (&b, &a), (&a, &a)] {
+      let lt = x.less_than(cs.namespace(|| format!("{:?} < {:?}", x.value, y.value)), y);
+      assert_eq!(lt.unwrap().get_value(), Some(x.value < y.value));
+    }
+    let max = b.max(cs.namespace(|| "max"), &a).unwrap();
+    assert_eq!(max.get_value(), a.get_value());
+    assert!(cs.is_satisfied());
+  }
+
+  #[test]
+  fn test_overflow() {
+    // values out of range are unsatisfiable
+    let mut cs = TestConstraintSystem::<F>::new();
+    FixedPoint::alloc(cs.namespace(|| "x"), Q8_8, Some(1 << 15)).unwrap();
+    assert!(!cs.is_satisfied());
+
+    // as are results out of range
+    let mut cs = TestConstraintSystem::<F>::new();
+    let a = alloc(&mut cs, "a", 100.0);
+    let sum = a.add(cs.namespace(|| "add"), &a).unwrap();
+    assert_eq!(sum.get_value(), None);
+    assert!(!cs.is_satisfied());
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let a = alloc(&mut cs, "a", 100.0);
+    a.mul(cs.namespace(|| "mul"), &a).unwrap();
+    assert!(!cs.is_satisfied());
+
+    // and integers are fixed-point numbers without fractional bits
+    let int = FixedPointFormat::integer(8);
+    let mut cs = TestConstraintSystem::<F>::new();
+    let x = FixedPoint::alloc(cs.namespace(|| "x"), int, Some(-7)).unwrap();
+    let y = FixedPoint::alloc(cs.namespace(|| "y"), int, Some(9)).unwrap();
+    assert_eq!(
+      x.mul(cs.namespace(|| "mul"), &y).unwrap().get_value(),
+      Some(-63)
+    );
+    assert!(cs.is_satisfied());
+    let z = FixedPoint::alloc(cs.namespace(|| "z"), Q8_8, Some(1)).unwrap();
+    assert!(x.add(cs.namespace(|| "mixed"), &z).is_err());
+  }
+}
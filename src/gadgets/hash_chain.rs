@@ -0,0 +1,102 @@
+//! This module provides gadgets to prove hash chains over streamed data, i.e., that
+//! `z_n = H(...H(H(z_0, b_0), b_1)..., b_{n-1})` for a seed `z_0` and blocks of bytes `b_i`, such as
+//! the chunks of a file, with one block absorbed per step.
+//!
+//! A step circuit must have the same shape at every step, so each block of at most `block_size`
+//! bytes is encoded into `block_len(block_size)` field elements: its `pack_bytes` encoding, which
+//! starts with its number of bytes, padded with zeros. Shorter blocks, such as the last one of a
+//! stream, are thus distinguished from blocks that end in zeros. Links are hashed with the Poseidon
+//! instance of `gadgets::poseidon`, so that chains computed on the host match those proven.
+use crate::{
+  errors::NovaError,
+  frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+  gadgets::poseidon::{hash, hash_gadget},
+  provider::poseidon::{bytes_per_element, pack_bytes, PoseidonConstantsCircuit},
+};
+use ff::PrimeField;
+
+/// Returns the number of field elements that blocks of at most `block_size` bytes are encoded into
+pub fn block_len<F: PrimeField>(block_size: usize) -> usize {
+  1 + block_size.div_ceil(bytes_per_element::<F>())
+}
+
+/// Encodes `block`, of at most `block_size` bytes, into `block_len(block_size)` field elements
+pub fn encode_block<F: PrimeField>(block: &[u8], block_size: usize) -> Result<Vec<F>, NovaError> {
+  if block.len() > block_size {
+    return Err(NovaError::InvalidInputLength);
+  }
+  let mut elts = pack_bytes(block);
+  elts.resize(block_len::<F>(block_size), F::ZERO);
+  Ok(elts)
+}
+
+/// Computes the link that follows `z` in a chain by hashing it with an encoded block
+pub fn hash_link<F: PrimeField>(constants: &PoseidonConstantsCircuit<F>, z: &F, block: &[F]) -> F {
+  let input = [core::slice::from_ref(z), block].concat();
+  hash(constants, &input)
+}
+
+/// Constrains the output to be the link that follows `z` in a chain, as computed by `hash_link`
+pub fn hash_link_gadget<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  constants: &PoseidonConstantsCircuit<F>,
+  z: &AllocatedNum<F>,
+  block: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let input = [core::slice::from_ref(z), block].concat();
+  hash_gadget(cs, constants, &input)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::test_cs::TestConstraintSystem,
+    provider::{Bn256EngineKZG, PallasEngine},
+    traits::Engine,
+  };
+  use ff::Field;
+  use rand::rngs::OsRng;
+
+  fn test_hash_link_with<E: Engine>() {
+    let constants = PoseidonConstantsCircuit::<E::Scalar>::default();
+    let block_size = 100;
+    let n = block_len::<E::Scalar>(block_size);
+
+    // a full block, and a shorter one whose encoding is padded
+    let full = encode_block::<E::Scalar>(&[7u8; 100], block_size).unwrap();
+    let short = encode_block::<E::Scalar>(&[7u8; 10], block_size).unwrap();
+    assert_eq!((full.len(), short.len()), (n, n));
+    assert_ne!(full, short);
+    assert_eq!(
+      encode_block::<E::Scalar>(&[7u8; 101], block_size),
+      Err(NovaError::InvalidInputLength)
+    );
+
+    let mut cs = TestConstraintSystem::<E::Scalar>::new();
+    let z = E::Scalar::random(OsRng);
+    let z_var = AllocatedNum::alloc_infallible(cs.namespace(|| "z"), || z);
+    for (i, block) in [full, short].iter().enumerate() {
+      let block_vars = block
+        .iter()
+        .enumerate()
+        .map(|(j, x)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("b {i} {j}")), || *x))
+        .collect::<Vec<_>>();
+      let out = hash_link_gadget(
+        cs.namespace(|| format!("link {i}")),
+        &constants,
+        &z_var,
+        &block_vars,
+      )
+      .unwrap();
+      assert_eq!(out.get_value(), Some(hash_link(&constants, &z, block)));
+    }
+    assert!(cs.is_satisfied());
+  }
+
+  #[test]
+  fn test_hash_link() {
+    test_hash_link_with::<PallasEngine>();
+    test_hash_link_with::<Bn256EngineKZG>();
+  }
+}
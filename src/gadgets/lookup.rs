@@ -0,0 +1,407 @@
+//! This module implements lookups into a fixed table whose cost folds across steps, with the
+//! logarithmic-derivative (logUp) argument: values `q_i` all belong to a table `t` if and only if
+//! `sum_i 1 / (alpha - q_i) = sum_j m_j / (alpha - t_j)` for a random `alpha`, where `m_j` counts the
+//! occurrences of `t_j` among the `q_i`.
+//!
+//! Rather than proving lookups anew at every step, a step circuit carries a `LookupState` of
+//! `LOOKUP_STATE_LEN` elements in its state `z`: the challenge `alpha`, the running sum of
+//! `1 / (alpha - q_i)` over the queries so far, and a hash chain of these queries. Each query then
+//! costs one constraint, on top of one constraint and one Poseidon hash per step, and the table side of the
+//! argument is checked only once, by `LookupTable::finalize`, against the outputs of the
+//! computation after it is verified (e.g., once a compressed SNARK is verified).
+//!
+//! The challenge must be chosen after the queries, so the prover first runs the computation on the
+//! host, recording its queries with `LookupTrace::record` in the order the circuit makes them. The
+//! challenge is then the hash of the final link of the chain and of the multiplicities, and the
+//! computation is proven with `LookupTrace::initial_state` in its initial inputs. The verifier of
+//! the outputs receives the multiplicities, which `LookupTrace::counts` returns, alongside the proof.
+use crate::{
+  errors::NovaError,
+  field::batch_invert,
+  frontend::{num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError},
+  gadgets::poseidon::{hash, hash_gadget},
+  provider::poseidon::PoseidonConstantsCircuit,
+};
+use ff::PrimeField;
+use std::collections::HashMap;
+
+/// The number of elements of the state `z` that a `LookupState` occupies
+pub const LOOKUP_STATE_LEN: usize = 3;
+
+/// The state of the lookups of a computation, which its step circuits carry in `z`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LookupState<F: PrimeField> {
+  /// The challenge `alpha` of the argument, which is the same at every step
+  pub challenge: F,
+  /// The sum of `1 / (alpha - q)` over the queries `q` so far
+  pub sum: F,
+  /// The link of the hash chain of the queries so far
+  pub hash: F,
+}
+
+impl<F: PrimeField> LookupState<F> {
+  /// Returns the `LOOKUP_STATE_LEN` elements of the state, in the order the circuit expects them
+  pub fn to_vec(&self) -> Vec<F> {
+    vec![self.challenge, self.sum, self.hash]
+  }
+
+  /// Reads a state from the first `LOOKUP_STATE_LEN` elements of `z`
+  pub fn from_slice(z: &[F]) -> Result<Self, NovaError> {
+    match z {
+      [challenge, sum, hash, ..] => Ok(Self {
+        challenge: *challenge,
+        sum: *sum,
+        hash: *hash,
+      }),
+      _ => Err(NovaError::InvalidInputLength),
+    }
+  }
+}
+
+/// A table of values that a computation looks up
+#[derive(Clone, Debug)]
+pub struct LookupTable<F: PrimeField> {
+  values: Vec<F>,
+  index: HashMap<Vec<u8>, usize>,
+}
+
+impl<F: PrimeField> LookupTable<F> {
+  /// Creates a table of `values`, in which duplicates are allowed but only looked up once
+  pub fn new(values: Vec<F>) -> Self {
+    let mut index = HashMap::with_capacity(values.len());
+    for (i, v) in values.iter().enumerate() {
+      index.entry(v.to_repr().as_ref().to_vec()).or_insert(i);
+    }
+    Self { values, index }
+  }
+
+  /// Returns the values of the table
+  pub fn values(&self) -> &[F] {
+    &self.values
+  }
+
+  /// Checks the table side of the argument for the final `state` of a computation whose queries
+  /// occur `counts` times each, which completes the proof that all its queries are in the table.
+  ///
+  /// `state` must come from verified outputs of the computation, and the initial state must have
+  /// been `LookupState { challenge, sum: 0, hash: 0 }`, for the challenge checked here.
+  pub fn finalize(
+    &self,
+    constants: &PoseidonConstantsCircuit<F>,
+    counts: &[u64],
+    state: &LookupState<F>,
+  ) -> Result<(), NovaError> {
+    if counts.len() != self.values.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    if state.challenge != challenge(constants, &state.hash, counts) {
+      return Err(NovaError::ProofVerifyError {
+        reason: "the lookup challenge does not depend on the queries".to_string(),
+      });
+    }
+
+    let mut denominators = self
+      .values
+      .iter()
+      .map(|t| state.challenge - t)
+      .collect::<Vec<_>>();
+    batch_invert(&mut denominators).map_err(|_| NovaError::ProofVerifyError {
+      reason: "the lookup challenge is a value of the table".to_string(),
+    })?;
+    let sum = denominators
+      .iter()
+      .zip(counts)
+      .map(|(inv, m)| *inv * F::from(*m))
+      .sum::<F>();
+    if sum != state.sum {
+      return Err(NovaError::ProofVerifyError {
+        reason: "the lookups are not all in the table".to_string(),
+      });
+    }
+    Ok(())
+  }
+}
+
+/// The queries that a computation makes to a table, which the prover records on the host before
+/// proving the computation, to choose the challenge of the argument
+#[derive(Clone)]
+pub struct LookupTrace<'a, F: PrimeField> {
+  table: &'a LookupTable<F>,
+  constants: PoseidonConstantsCircuit<F>,
+  counts: Vec<u64>,
+  hash: F,
+}
+
+impl<'a, F: PrimeField> LookupTrace<'a, F> {
+  /// Starts a trace of the queries to `table`
+  pub fn new(table: &'a LookupTable<F>, constants: PoseidonConstantsCircuit<F>) -> Self {
+    Self {
+      table,
+      constants,
+      counts: vec![0; table.values.len()],
+      hash: F::ZERO,
+    }
+  }
+
+  /// Records the queries that one invocation of `lookup_gadget` makes, in the same order.
+  ///
+  /// Returns `NovaError::IncorrectWitness` if a query is not in the table, in which case nothing is
+  /// recorded.
+  pub fn record(&mut self, queries: &[F]) -> Result<(), NovaError> {
+    let indices = queries
+      .iter()
+      .map(|q| {
+        self
+          .table
+          .index
+          .get(q.to_repr().as_ref())
+          .copied()
+          .ok_or(NovaError::IncorrectWitness)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    for i in indices {
+      self.counts[i] += 1;
+    }
+    self.hash = hash_queries(&self.constants, &self.hash, queries);
+    Ok(())
+  }
+
+  /// Returns the number of times each value of the table was looked up
+  pub fn counts(&self) -> &[u64] {
+    &self.counts
+  }
+
+  /// Returns the lookup state that the initial inputs of the computation must hold, with the
+  /// challenge derived from the queries recorded so far
+  pub fn initial_state(&self) -> LookupState<F> {
+    LookupState {
+      challenge: challenge(&self.constants, &self.hash, &self.counts),
+      sum: F::ZERO,
+      hash: F::ZERO,
+    }
+  }
+}
+
+/// Looks up each of `queries` in the table, updating `state`, the `LOOKUP_STATE_LEN` elements of
+/// the lookup state, and returns the updated state, which the step circuit outputs in its place.
+///
+/// The table is not needed here: that the queries are in it is checked by `LookupTable::finalize`.
+/// Returns `SynthesisError::DivisionByZero` if a query equals the challenge.
+pub fn lookup_gadget<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  constants: &PoseidonConstantsCircuit<F>,
+  state: &[AllocatedNum<F>],
+  queries: &[AllocatedNum<F>],
+) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+  let [challenge, sum, link] = state else {
+    return Err(SynthesisError::Unsatisfiable);
+  };
+
+  // inv * (alpha - q) = 1 for each query
+  let mut new_sum_lc = LinearCombination::from_variable(sum.get_variable());
+  let mut new_sum_value = sum.get_value();
+  for (i, q) in queries.iter().enumerate() {
+    let inv = AllocatedNum::alloc(cs.namespace(|| format!("inv {i}")), || {
+      let d = challenge
+        .get_value()
+        .ok_or(SynthesisError::AssignmentMissing)?
+        - q.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+      Option::from(d.invert()).ok_or(SynthesisError::DivisionByZero)
+    })?;
+    cs.enforce(
+      || format!("inv {i} * (alpha - q {i}) = 1"),
+      |lc| lc + inv.get_variable(),
+      |lc| lc + challenge.get_variable() - q.get_variable(),
+      |lc| lc + CS::one(),
+    );
+    new_sum_lc = new_sum_lc + inv.get_variable();
+    new_sum_value = new_sum_value.zip(inv.get_value()).map(|(s, v)| s + v);
+  }
+  let new_sum = AllocatedNum::alloc(cs.namespace(|| "sum"), || {
+    new_sum_value.ok_or(SynthesisError::AssignmentMissing)
+  })?;
+  cs.enforce(
+    || "sum is the sum of the inverses",
+    |_| new_sum_lc,
+    |lc| lc + CS::one(),
+    |lc| lc + new_sum.get_variable(),
+  );
+
+  let input = [core::slice::from_ref(link), queries].concat();
+  let new_link = hash_gadget(cs.namespace(|| "hash"), constants, &input)?;
+
+  Ok(vec![challenge.clone(), new_sum, new_link])
+}
+
+/// Computes the link that follows `link` in the hash chain of the queries, as `lookup_gadget` does
+fn hash_queries<F: PrimeField>(
+  constants: &PoseidonConstantsCircuit<F>,
+  link: &F,
+  queries: &[F],
+) -> F {
+  let input = [core::slice::from_ref(link), queries].concat();
+  hash(constants, &input)
+}
+
+/// Derives the challenge from the final link of the hash chain of the queries and from their
+/// multiplicities
+fn challenge<F: PrimeField>(
+  constants: &PoseidonConstantsCircuit<F>,
+  link: &F,
+  counts: &[u64],
+) -> F {
+  let input = core::iter::once(*link)
+    .chain(counts.iter().map(|m| F::from(*m)))
+    .collect::<Vec<_>>();
+  hash(constants, &input)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::test_cs::TestConstraintSystem,
+    nova::{PublicParams, RecursiveSNARK},
+    provider::{Bn256EngineKZG, GrumpkinEngine, PallasEngine, VestaEngine},
+    traits::{circuit::StepCircuit, snark::default_ck_hint, Engine},
+  };
+  use ff::Field;
+
+  /// Looks up `x` and `x + 1` and increments `x`, with the lookup state before `x` in `z`
+  #[derive(Clone)]
+  struct IncrementCircuit<F: PrimeField> {
+    constants: PoseidonConstantsCircuit<F>,
+  }
+
+  impl<F: PrimeField> StepCircuit<F> for IncrementCircuit<F> {
+    fn arity(&self) -> usize {
+      LOOKUP_STATE_LEN + 1
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      let x = &z[LOOKUP_STATE_LEN];
+      let x1 = AllocatedNum::alloc(cs.namespace(|| "x + 1"), || {
+        Ok(x.get_value().ok_or(SynthesisError::AssignmentMissing)? + F::ONE)
+      })?;
+      cs.enforce(
+        || "x + 1",
+        |lc| lc + x.get_variable() + CS::one(),
+        |lc| lc + CS::one(),
+        |lc| lc + x1.get_variable(),
+      );
+      let mut state = lookup_gadget(
+        cs.namespace(|| "lookup"),
+        &self.constants,
+        &z[..LOOKUP_STATE_LEN],
+        &[x.clone(), x1.clone()],
+      )?;
+      state.push(x1);
+      Ok(state)
+    }
+  }
+
+  fn test_lookup_with<E1, E2>()
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+  {
+    type F<E> = <E as Engine>::Scalar;
+    let constants = PoseidonConstantsCircuit::<F<E1>>::default();
+    let table = LookupTable::new((0..8u64).map(F::<E1>::from).collect());
+    let num_steps = 5;
+
+    // the prover records the queries of the computation before proving it
+    let mut trace = LookupTrace::new(&table, constants.clone());
+    for i in 0..num_steps {
+      trace
+        .record(&[F::<E1>::from(i), F::<E1>::from(i + 1)])
+        .unwrap();
+    }
+    assert_eq!(trace.counts(), [1, 2, 2, 2, 2, 1, 0, 0]);
+    assert_eq!(
+      trace.clone().record(&[F::<E1>::from(8)]),
+      Err(NovaError::IncorrectWitness)
+    );
+
+    let circuit = IncrementCircuit {
+      constants: constants.clone(),
+    };
+    let pp =
+      PublicParams::<E1, E2, _>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+    let mut z0 = trace.initial_state().to_vec();
+    z0.push(F::<E1>::ZERO);
+    let mut snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..num_steps {
+      snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let zn = snark.verify(&pp, num_steps as usize, &z0).unwrap();
+
+    // the lookups are finalized against the verified outputs
+    let state = LookupState::from_slice(&zn).unwrap();
+    assert!(table.finalize(&constants, trace.counts(), &state).is_ok());
+    let mut counts = trace.counts().to_vec();
+    counts.swap(0, 7);
+    assert!(matches!(
+      table.finalize(&constants, &counts, &state),
+      Err(NovaError::ProofVerifyError { .. })
+    ));
+  }
+
+  #[test]
+  fn test_lookup() {
+    test_lookup_with::<PallasEngine, VestaEngine>();
+    test_lookup_with::<Bn256EngineKZG, GrumpkinEngine>();
+  }
+
+  #[test]
+  fn test_lookup_not_in_table() {
+    type F = <PallasEngine as Engine>::Scalar;
+    let constants = PoseidonConstantsCircuit::<F>::default();
+    let table = LookupTable::new((0..8u64).map(F::from).collect());
+
+    // a prover that looks up a value outside the table, but claims multiplicities that would
+    // match, cannot find a sum that the table side accepts
+    let queries = [F::from(3), F::from(9)];
+    let mut counts = vec![0u64; 8];
+    counts[3] = 1;
+    counts[1] = 1;
+    let link = hash_queries(&constants, &F::ZERO, &queries);
+    let initial = LookupState {
+      challenge: challenge(&constants, &link, &counts),
+      sum: F::ZERO,
+      hash: F::ZERO,
+    };
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let state = initial
+      .to_vec()
+      .iter()
+      .enumerate()
+      .map(|(i, v)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("z {i}")), || *v))
+      .collect::<Vec<_>>();
+    let queries_var = queries
+      .iter()
+      .enumerate()
+      .map(|(i, v)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("q {i}")), || *v))
+      .collect::<Vec<_>>();
+    let out = lookup_gadget(cs.namespace(|| "lookup"), &constants, &state, &queries_var).unwrap();
+    assert!(cs.is_satisfied());
+
+    let state = LookupState::from_slice(
+      &out
+        .iter()
+        .map(|v| v.get_value().unwrap())
+        .collect::<Vec<_>>(),
+    )
+    .unwrap();
+    assert_eq!(state.hash, link);
+    assert!(matches!(
+      table.finalize(&constants, &counts, &state),
+      Err(NovaError::ProofVerifyError { .. })
+    ));
+  }
+}
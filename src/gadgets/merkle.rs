@@ -0,0 +1,449 @@
+//! This module provides an append-only Merkle tree over field elements, which can be built
+//! incrementally both outside and inside circuits, and whose nodes are hashed with the Poseidon
+//! instance of `gadgets::poseidon`.
+//!
+//! A tree of depth `d` has `2^d` leaf positions, which are filled from left to right and are zero
+//! until then. Appending only needs the number of leaves and the frontier of the tree: the `d`
+//! nodes that are left siblings of the path to the next free position. A circuit can thus carry
+//! the tree in `d + 1` field elements and append to it with `d` hashes, as `WithStateTree` does to
+//! commit to every state of an incremental computation. At most `2^d - 1` leaves are appended, so
+//! that the next free position always exists.
+//!
+//! Membership in the tree is proven with the siblings of the path from a leaf to the root, which
+//! `merkle_path` computes from the leaves and which `verify_path` and `path_root_gadget` check.
+use crate::{
+  errors::NovaError,
+  frontend::{num::AllocatedNum, Boolean, ConstraintSystem, SynthesisError},
+  gadgets::{
+    poseidon::{hash, hash_gadget},
+    range::assert_bits,
+    utils::conditionally_select,
+  },
+  provider::poseidon::PoseidonConstantsCircuit,
+};
+use ff::{PrimeField, PrimeFieldBits};
+
+/// The largest supported depth, at which the number of leaves still fits in a `u64`
+pub const MAX_DEPTH: usize = 63;
+
+/// Hashes two sibling nodes into their parent
+fn hash_node<F: PrimeField>(constants: &PoseidonConstantsCircuit<F>, left: &F, right: &F) -> F {
+  hash(constants, &[*left, *right])
+}
+
+/// Returns the roots of empty subtrees of heights `0` to `depth`
+pub fn empty_roots<F: PrimeField>(constants: &PoseidonConstantsCircuit<F>, depth: usize) -> Vec<F> {
+  let mut roots = vec![F::ZERO];
+  for h in 0..depth {
+    roots.push(hash_node(constants, &roots[h], &roots[h]));
+  }
+  roots
+}
+
+/// An append-only Merkle tree that only stores its frontier
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IncrementalMerkleTree<F: PrimeField> {
+  num_leaves: u64,
+  frontier: Vec<F>,
+}
+
+impl<F: PrimeField> IncrementalMerkleTree<F> {
+  /// Creates an empty tree of the given depth.
+  ///
+  /// Panics if `depth` is zero or exceeds `MAX_DEPTH`.
+  pub fn new(depth: usize) -> Self {
+    assert!((1..=MAX_DEPTH).contains(&depth), "unsupported depth");
+    Self {
+      num_leaves: 0,
+      frontier: vec![F::ZERO; depth],
+    }
+  }
+
+  /// Creates a tree from its number of leaves and its frontier, as carried in a circuit
+  pub fn from_parts(num_leaves: u64, frontier: Vec<F>) -> Result<Self, NovaError> {
+    if frontier.is_empty() || frontier.len() > MAX_DEPTH {
+      return Err(NovaError::InvalidInputLength);
+    }
+    if num_leaves >= (1 << frontier.len()) {
+      return Err(NovaError::InvalidIndex);
+    }
+    Ok(Self {
+      num_leaves,
+      frontier,
+    })
+  }
+
+  /// Returns the depth of the tree
+  pub fn depth(&self) -> usize {
+    self.frontier.len()
+  }
+
+  /// Returns the number of leaves appended so far
+  pub fn num_leaves(&self) -> u64 {
+    self.num_leaves
+  }
+
+  /// Returns the frontier of the tree. Only the entries at the positions of the bits set in the
+  /// number of leaves are meaningful; the others hold stale nodes.
+  pub fn frontier(&self) -> &[F] {
+    &self.frontier
+  }
+
+  /// Appends `leaf` to the tree, or returns `NovaError::InvalidIndex` if it already holds
+  /// `2^depth - 1` leaves
+  pub fn append(
+    &mut self,
+    constants: &PoseidonConstantsCircuit<F>,
+    leaf: F,
+  ) -> Result<(), NovaError> {
+    let num_leaves = self.num_leaves + 1;
+    if num_leaves >= (1 << self.depth()) {
+      return Err(NovaError::InvalidIndex);
+    }
+
+    // the new leaf completes the subtrees of the trailing ones of the old number of leaves, and
+    // the root of the subtree it completes last is stored at the lowest bit set in the new one
+    let mut node = leaf;
+    for h in 0..self.depth() {
+      if (num_leaves >> h) & 1 == 1 {
+        self.frontier[h] = node;
+        break;
+      }
+      node = hash_node(constants, &self.frontier[h], &node);
+    }
+    self.num_leaves = num_leaves;
+    Ok(())
+  }
+
+  /// Returns the root of the tree
+  pub fn root(&self, constants: &PoseidonConstantsCircuit<F>) -> F {
+    let empty = empty_roots(constants, self.depth());
+    let mut node = F::ZERO;
+    for (h, (left, empty)) in self.frontier.iter().zip(&empty).enumerate() {
+      node = if (self.num_leaves >> h) & 1 == 1 {
+        hash_node(constants, left, &node)
+      } else {
+        hash_node(constants, &node, empty)
+      };
+    }
+    node
+  }
+}
+
+/// Returns the siblings, from the leaf up, of the path from the leaf at `index` to the root of the
+/// tree of depth `depth` whose first leaves are `leaves`
+pub fn merkle_path<F: PrimeField>(
+  constants: &PoseidonConstantsCircuit<F>,
+  depth: usize,
+  leaves: &[F],
+  index: usize,
+) -> Result<Vec<F>, NovaError> {
+  if depth > MAX_DEPTH || index >= leaves.len() || (leaves.len() as u64) >= (1 << depth) {
+    return Err(NovaError::InvalidIndex);
+  }
+  let empty = empty_roots(constants, depth);
+  let mut layer = leaves.to_vec();
+  let mut path = Vec::with_capacity(depth);
+  for (h, empty) in empty.iter().take(depth).enumerate() {
+    let sibling = (index >> h) ^ 1;
+    path.push(layer.get(sibling).copied().unwrap_or(*empty));
+    layer = layer
+      .chunks(2)
+      .map(|pair| hash_node(constants, &pair[0], pair.get(1).unwrap_or(empty)))
+      .collect();
+  }
+  Ok(path)
+}
+
+/// Checks that `path`, as computed by `merkle_path`, proves that `leaf` is at `index` in the tree
+/// whose root is `root`
+pub fn verify_path<F: PrimeField>(
+  constants: &PoseidonConstantsCircuit<F>,
+  root: &F,
+  leaf: &F,
+  index: u64,
+  path: &[F],
+) -> bool {
+  if path.is_empty() || path.len() > MAX_DEPTH || index >= (1 << path.len()) {
+    return false;
+  }
+  let node = path.iter().enumerate().fold(*leaf, |node, (h, sibling)| {
+    if (index >> h) & 1 == 1 {
+      hash_node(constants, sibling, &node)
+    } else {
+      hash_node(constants, &node, sibling)
+    }
+  });
+  node == *root
+}
+
+/// Allocates a variable constrained to equal `value`
+fn alloc_constant<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  value: F,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let num = AllocatedNum::alloc(cs.namespace(|| "alloc"), || Ok(value))?;
+  cs.enforce(
+    || "check constant",
+    |lc| lc + num.get_variable(),
+    |lc| lc + CS::one(),
+    |lc| lc + (value, CS::one()),
+  );
+  Ok(num)
+}
+
+/// Hashes two sibling nodes, ordered by whether the node is a right child, into their parent
+fn hash_ordered_gadget<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  constants: &PoseidonConstantsCircuit<F>,
+  node: &AllocatedNum<F>,
+  sibling: &AllocatedNum<F>,
+  is_right: &Boolean,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let left = conditionally_select(cs.namespace(|| "left"), sibling, node, is_right)?;
+  let right = conditionally_select(cs.namespace(|| "right"), node, sibling, is_right)?;
+  hash_gadget(cs.namespace(|| "hash"), constants, &[left, right])
+}
+
+/// Appends `leaf` to the tree with `num_leaves` leaves and the given frontier inside a circuit, as
+/// `IncrementalMerkleTree::append` does, and returns the new number of leaves and frontier.
+///
+/// The circuit is unsatisfiable if the tree already holds `2^depth - 1` leaves.
+pub fn append_gadget<F, CS>(
+  mut cs: CS,
+  constants: &PoseidonConstantsCircuit<F>,
+  num_leaves: &AllocatedNum<F>,
+  frontier: &[AllocatedNum<F>],
+  leaf: &AllocatedNum<F>,
+) -> Result<(AllocatedNum<F>, Vec<AllocatedNum<F>>), SynthesisError>
+where
+  F: PrimeField + PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  let num_leaves_next = AllocatedNum::alloc(cs.namespace(|| "num_leaves_next"), || {
+    num_leaves
+      .get_value()
+      .map(|n| n + F::ONE)
+      .ok_or(SynthesisError::AssignmentMissing)
+  })?;
+  cs.enforce(
+    || "increment num_leaves",
+    |lc| lc + num_leaves.get_variable() + CS::one(),
+    |lc| lc + CS::one(),
+    |lc| lc + num_leaves_next.get_variable(),
+  );
+  let bits = assert_bits(
+    cs.namespace(|| "num_leaves_next bits"),
+    &num_leaves_next,
+    frontier.len(),
+  )?;
+
+  // without early exits, the node is stored at the lowest bit set, and hashed at every level
+  let mut node = leaf.clone();
+  let mut stored = Boolean::Constant(false);
+  let mut frontier_next = Vec::with_capacity(frontier.len());
+  for (h, (bit, sibling)) in bits.iter().zip(frontier).enumerate() {
+    let bit = Boolean::Is(bit.clone());
+    let store = Boolean::and(cs.namespace(|| format!("store {h}")), &bit, &stored.not())?;
+    frontier_next.push(conditionally_select(
+      cs.namespace(|| format!("frontier {h}")),
+      &node,
+      sibling,
+      &store,
+    )?);
+    if h + 1 < frontier.len() {
+      stored = Boolean::or(cs.namespace(|| format!("stored {h}")), &stored, &bit)?;
+      node = hash_gadget(
+        cs.namespace(|| format!("node {h}")),
+        constants,
+        &[sibling.clone(), node],
+      )?;
+    }
+  }
+  Ok((num_leaves_next, frontier_next))
+}
+
+/// Computes the root of the tree with `num_leaves` leaves and the given frontier inside a
+/// circuit, as `IncrementalMerkleTree::root` does
+pub fn root_gadget<F, CS>(
+  mut cs: CS,
+  constants: &PoseidonConstantsCircuit<F>,
+  num_leaves: &AllocatedNum<F>,
+  frontier: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError>
+where
+  F: PrimeField + PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  let bits = assert_bits(
+    cs.namespace(|| "num_leaves bits"),
+    num_leaves,
+    frontier.len(),
+  )?;
+  let empty = empty_roots(constants, frontier.len());
+  let mut node = alloc_constant(cs.namespace(|| "empty leaf"), F::ZERO)?;
+  for (h, (bit, left)) in bits.iter().zip(frontier).enumerate() {
+    // a right child has the frontier node as its left sibling, and a left child an empty subtree
+    let is_right = Boolean::Is(bit.clone());
+    let empty = alloc_constant(cs.namespace(|| format!("empty {h}")), empty[h])?;
+    let sibling = conditionally_select(
+      cs.namespace(|| format!("sibling {h}")),
+      left,
+      &empty,
+      &is_right,
+    )?;
+    node = hash_ordered_gadget(
+      cs.namespace(|| format!("node {h}")),
+      constants,
+      &node,
+      &sibling,
+      &is_right,
+    )?;
+  }
+  Ok(node)
+}
+
+/// Computes inside a circuit the root that `path` leads to from `leaf`, at the position whose
+/// little-endian bits are `index`, so that a circuit can prove membership in a tree by constraining
+/// the result to equal its root
+pub fn path_root_gadget<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  constants: &PoseidonConstantsCircuit<F>,
+  leaf: &AllocatedNum<F>,
+  index: &[Boolean],
+  path: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  if index.len() != path.len() {
+    return Err(SynthesisError::IncompatibleLengthVector(
+      "index".to_string(),
+    ));
+  }
+  let mut node = leaf.clone();
+  for (h, (is_right, sibling)) in index.iter().zip(path).enumerate() {
+    node = hash_ordered_gadget(
+      cs.namespace(|| format!("node {h}")),
+      constants,
+      &node,
+      sibling,
+      is_right,
+    )?;
+  }
+  Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::{test_cs::TestConstraintSystem, AllocatedBit},
+    provider::{Bn256EngineKZG, PallasEngine},
+    traits::Engine,
+  };
+  use ff::Field;
+  use rand::rngs::OsRng;
+
+  fn test_merkle_tree_with<E: Engine>() {
+    let constants = PoseidonConstantsCircuit::<E::Scalar>::default();
+    let depth = 3;
+    let leaves = (0..7).map(|_| E::Scalar::random(OsRng)).collect::<Vec<_>>();
+
+    let mut cs = TestConstraintSystem::<E::Scalar>::new();
+    let mut tree = IncrementalMerkleTree::new(depth);
+    let mut num_leaves = alloc_constant(cs.namespace(|| "num_leaves"), E::Scalar::ZERO).unwrap();
+    let mut frontier = (0..depth)
+      .map(|h| alloc_constant(cs.namespace(|| format!("frontier {h}")), E::Scalar::ZERO).unwrap())
+      .collect::<Vec<_>>();
+    for (i, leaf) in leaves.iter().enumerate() {
+      tree.append(&constants, *leaf).unwrap();
+      let leaf_var = AllocatedNum::alloc_infallible(cs.namespace(|| format!("leaf {i}")), || *leaf);
+      (num_leaves, frontier) = append_gadget(
+        cs.namespace(|| format!("append {i}")),
+        &constants,
+        &num_leaves,
+        &frontier,
+        &leaf_var,
+      )
+      .unwrap();
+      let root = root_gadget(
+        cs.namespace(|| format!("root {i}")),
+        &constants,
+        &num_leaves,
+        &frontier,
+      )
+      .unwrap();
+
+      // the incremental root matches the root of the leaves appended so far, and so do the gadgets
+      let expected = merkle_path(&constants, depth, &leaves[..=i], 0)
+        .map(|path| {
+          path.iter().fold(leaves[0], |node, sibling| {
+            hash_node(&constants, &node, sibling)
+          })
+        })
+        .unwrap();
+      assert_eq!(tree.root(&constants), expected);
+      assert_eq!(root.get_value(), Some(expected));
+      assert_eq!(
+        frontier
+          .iter()
+          .map(|x| x.get_value().unwrap())
+          .collect::<Vec<_>>(),
+        tree.frontier()
+      );
+    }
+    assert!(cs.is_satisfied());
+    assert_eq!(
+      tree.append(&constants, E::Scalar::ONE),
+      Err(NovaError::InvalidIndex)
+    );
+
+    // membership proofs of every leaf verify, outside and inside circuits
+    let root = tree.root(&constants);
+    for (i, leaf) in leaves.iter().enumerate() {
+      let path = merkle_path(&constants, depth, &leaves, i).unwrap();
+      assert!(verify_path(&constants, &root, leaf, i as u64, &path));
+      assert!(!verify_path(&constants, &root, leaf, (i as u64) ^ 1, &path));
+
+      let leaf_var =
+        AllocatedNum::alloc_infallible(cs.namespace(|| format!("member {i}")), || *leaf);
+      let index = (0..depth)
+        .map(|h| {
+          let bit = AllocatedBit::alloc(
+            cs.namespace(|| format!("index {i} {h}")),
+            Some((i >> h) & 1 == 1),
+          );
+          Boolean::Is(bit.unwrap())
+        })
+        .collect::<Vec<_>>();
+      let path_vars = path
+        .iter()
+        .enumerate()
+        .map(|(h, x)| {
+          AllocatedNum::alloc_infallible(cs.namespace(|| format!("path {i} {h}")), || *x)
+        })
+        .collect::<Vec<_>>();
+      let computed = path_root_gadget(
+        cs.namespace(|| format!("path root {i}")),
+        &constants,
+        &leaf_var,
+        &index,
+        &path_vars,
+      )
+      .unwrap();
+      assert_eq!(computed.get_value(), Some(root));
+    }
+    assert!(cs.is_satisfied());
+
+    // the frontier round trips through its parts
+    assert_eq!(
+      IncrementalMerkleTree::from_parts(tree.num_leaves(), tree.frontier().to_vec()),
+      Ok(tree)
+    );
+    assert!(IncrementalMerkleTree::from_parts(8, vec![E::Scalar::ZERO; depth]).is_err());
+  }
+
+  #[test]
+  fn test_merkle_tree() {
+    test_merkle_tree_with::<PallasEngine>();
+    test_merkle_tree_with::<Bn256EngineKZG>();
+  }
+}
@@ -1,4 +1,12 @@
 //! This module implements various gadgets necessary for Nova and applications built with Nova.
+pub mod cross_field;
 pub(crate) mod ecc;
+pub mod fixed_point;
+pub mod hash_chain;
+pub mod lookup;
+pub mod merkle;
 pub(crate) mod nonnative;
+pub mod poseidon;
+pub mod range;
+pub mod select;
 pub(crate) mod utils;
@@ -0,0 +1,349 @@
+//! This module exposes the Poseidon instance that Nova's random oracle uses, as a hash function and
+//! a sponge that are available both outside and inside circuits.
+//!
+//! The out-of-circuit and in-circuit versions use the same constants (`PoseidonConstantsCircuit`)
+//! and the same sponge pattern, so they produce identical outputs: an application can hash data in
+//! its step circuit and compare the result against a digest computed on the host. `PoseidonSponge`
+//! and `PoseidonSpongeGadget` follow the random oracle exactly: squeezing hashes everything absorbed
+//! since the last squeeze, together with the previous output, if any. `PoseidonTranscriptGadget`
+//! replays `provider::poseidon::PoseidonTranscript` in the same way.
+use crate::{
+  frontend::{num::AllocatedNum, Boolean, ConstraintSystem, LinearCombination, SynthesisError},
+  provider::poseidon::{bytes_per_element, PoseidonConstantsCircuit},
+};
+use ff::PrimeField;
+
+/// Hashes `input` outside circuits
+pub fn hash<F: PrimeField>(constants: &PoseidonConstantsCircuit<F>, input: &[F]) -> F {
+  constants.hash(input)
+}
+
+/// Hashes `input` inside a circuit, producing the same output as `hash`
+pub fn hash_gadget<F: PrimeField, CS: ConstraintSystem<F>>(
+  cs: CS,
+  constants: &PoseidonConstantsCircuit<F>,
+  input: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  constants.hash_circuit(cs, input)
+}
+
+/// A Poseidon sponge to use outside circuits
+#[derive(Clone, Default)]
+pub struct PoseidonSponge<F: PrimeField> {
+  state: Vec<F>,
+  constants: PoseidonConstantsCircuit<F>,
+}
+
+impl<F: PrimeField> PoseidonSponge<F> {
+  /// Creates a sponge with the given constants
+  pub fn new(constants: PoseidonConstantsCircuit<F>) -> Self {
+    Self {
+      state: Vec::new(),
+      constants,
+    }
+  }
+
+  /// Absorbs `e` into the sponge
+  pub fn absorb(&mut self, e: F) {
+    self.state.push(e);
+  }
+
+  /// Absorbs `es` into the sponge, in order
+  pub fn absorb_batch(&mut self, es: &[F]) {
+    self.state.extend_from_slice(es);
+  }
+
+  /// Hashes the state of the sponge, which is then reset to the output
+  pub fn squeeze(&mut self) -> F {
+    let hash = self.constants.hash(&self.state);
+    self.state = vec![hash];
+    hash
+  }
+}
+
+/// A Poseidon sponge to use inside circuits, producing the same outputs as `PoseidonSponge`
+#[derive(Clone, Default)]
+pub struct PoseidonSpongeGadget<F: PrimeField> {
+  state: Vec<AllocatedNum<F>>,
+  constants: PoseidonConstantsCircuit<F>,
+}
+
+impl<F: PrimeField> PoseidonSpongeGadget<F> {
+  /// Creates a sponge with the given constants
+  pub fn new(constants: PoseidonConstantsCircuit<F>) -> Self {
+    Self {
+      state: Vec::new(),
+      constants,
+    }
+  }
+
+  /// Absorbs `e` into the sponge
+  pub fn absorb(&mut self, e: &AllocatedNum<F>) {
+    self.state.push(e.clone());
+  }
+
+  /// Hashes the state of the sponge, which is then reset to the output
+  pub fn squeeze<CS: ConstraintSystem<F>>(
+    &mut self,
+    cs: CS,
+  ) -> Result<AllocatedNum<F>, SynthesisError> {
+    let hash = self.constants.hash_circuit(cs, &self.state)?;
+    self.state = vec![hash.clone()];
+    Ok(hash)
+  }
+}
+
+/// The circuit counterpart of `provider::poseidon::PoseidonTranscript`, which derives the same
+/// challenges from the same absorbed values.
+///
+/// Values are absorbed as the bits of their transcript representation (`TranscriptReprTrait`),
+/// byte after byte and least-significant bit first within each byte, so a circuit can absorb
+/// values that it only knows as bits. Labels and domain separators are constants.
+pub struct PoseidonTranscriptGadget<F: PrimeField> {
+  sponge: PoseidonSpongeGadget<F>,
+}
+
+impl<F: PrimeField> PoseidonTranscriptGadget<F> {
+  /// Creates a transcript with the given label, like `TranscriptEngineTrait::new`
+  pub fn new<CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    constants: PoseidonConstantsCircuit<F>,
+    label: &[u8],
+  ) -> Result<Self, SynthesisError> {
+    let mut transcript = Self {
+      sponge: PoseidonSpongeGadget::new(constants),
+    };
+    transcript.absorb_constant(cs.namespace(|| "label"), &[b"NoTR", label].concat())?;
+    Ok(transcript)
+  }
+
+  /// Absorbs a value under a label, like `TranscriptEngineTrait::absorb`, given the bits of its
+  /// transcript representation
+  pub fn absorb<CS: ConstraintSystem<F>>(
+    &mut self,
+    mut cs: CS,
+    label: &[u8],
+    bits: &[Boolean],
+  ) -> Result<(), SynthesisError> {
+    if bits.len() % 8 != 0 {
+      return Err(SynthesisError::Unsatisfiable);
+    }
+    self.absorb_constant(cs.namespace(|| "label"), label)?;
+    self.absorb_bits(cs.namespace(|| "value"), bits)
+  }
+
+  /// Adds a domain separator, like `TranscriptEngineTrait::dom_sep`
+  pub fn dom_sep<CS: ConstraintSystem<F>>(
+    &mut self,
+    cs: CS,
+    bytes: &[u8],
+  ) -> Result<(), SynthesisError> {
+    self.absorb_constant(cs, &[b"NoDS", bytes].concat())
+  }
+
+  /// Squeezes a challenge under a label, like `TranscriptEngineTrait::squeeze`
+  pub fn squeeze<CS: ConstraintSystem<F>>(
+    &mut self,
+    mut cs: CS,
+    label: &[u8],
+  ) -> Result<AllocatedNum<F>, SynthesisError> {
+    self.absorb_constant(cs.namespace(|| "label"), label)?;
+    self.sponge.squeeze(cs.namespace(|| "squeeze"))
+  }
+
+  fn absorb_constant<CS: ConstraintSystem<F>>(
+    &mut self,
+    cs: CS,
+    bytes: &[u8],
+  ) -> Result<(), SynthesisError> {
+    let bits = bytes
+      .iter()
+      .flat_map(|b| (0..8).map(move |i| Boolean::constant((b >> i) & 1 == 1)))
+      .collect::<Vec<_>>();
+    self.absorb_bits(cs, &bits)
+  }
+
+  /// Absorbs the number of bytes and the bytes packed into field elements, like `pack_bytes`
+  fn absorb_bits<CS: ConstraintSystem<F>>(
+    &mut self,
+    mut cs: CS,
+    bits: &[Boolean],
+  ) -> Result<(), SynthesisError> {
+    let len = F::from((bits.len() / 8) as u64);
+    let len = alloc_packed(
+      cs.namespace(|| "length"),
+      LinearCombination::zero() + (len, CS::one()),
+      Some(len),
+    )?;
+    self.sponge.absorb(&len);
+
+    for (i, chunk) in bits.chunks(8 * bytes_per_element::<F>()).enumerate() {
+      let mut lc = LinearCombination::zero();
+      let mut value = Some(F::ZERO);
+      let mut coeff = F::ONE;
+      for bit in chunk {
+        lc = lc + &bit.lc(CS::one(), coeff);
+        value = value
+          .zip(bit.get_value())
+          .map(|(v, b)| if b { v + coeff } else { v });
+        coeff = coeff.double();
+      }
+      let e = alloc_packed(cs.namespace(|| format!("chunk {i}")), lc, value)?;
+      self.sponge.absorb(&e);
+    }
+    Ok(())
+  }
+}
+
+/// Allocates a number constrained to equal `lc`, whose value is `value`
+fn alloc_packed<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  lc: LinearCombination<F>,
+  value: Option<F>,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+  let num = AllocatedNum::alloc(cs.namespace(|| "packed"), || {
+    value.ok_or(SynthesisError::AssignmentMissing)
+  })?;
+  cs.enforce(
+    || "packing",
+    |_| lc,
+    |lc| lc + CS::one(),
+    |lc| lc + num.get_variable(),
+  );
+  Ok(num)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    constants::NUM_CHALLENGE_BITS,
+    frontend::test_cs::TestConstraintSystem,
+    frontend::AllocatedBit,
+    provider::{
+      poseidon::{PoseidonRO, PoseidonTranscript},
+      Bn256EngineKZG, PallasEngine,
+    },
+    traits::{Engine, ROTrait, TranscriptEngineTrait, TranscriptReprTrait},
+  };
+  use ff::{Field, PrimeFieldBits};
+  use rand::rngs::OsRng;
+
+  fn test_poseidon_with<E: Engine>()
+  where
+    E::Scalar: PrimeFieldBits,
+  {
+    let constants = PoseidonConstantsCircuit::<E::Scalar>::default();
+    let mut cs = TestConstraintSystem::<E::Scalar>::new();
+    let mut sponge = PoseidonSponge::new(constants.clone());
+    let mut gadget = PoseidonSpongeGadget::new(constants.clone());
+    let mut ro = PoseidonRO::new(constants.clone());
+
+    for round in 0..3 {
+      for i in 0..30 {
+        let x = E::Scalar::random(OsRng);
+        sponge.absorb(x);
+        ro.absorb(x);
+        let x = AllocatedNum::alloc_infallible(cs.namespace(|| format!("x {round} {i}")), || x);
+        gadget.absorb(&x);
+      }
+      let out = sponge.squeeze();
+      let out_gadget = gadget
+        .squeeze(cs.namespace(|| format!("squeeze {round}")))
+        .unwrap();
+      assert_eq!(out_gadget.get_value(), Some(out));
+
+      // the sponge is the random oracle before the output is truncated to a challenge
+      let challenge = ro.squeeze(NUM_CHALLENGE_BITS);
+      let bits = out.to_le_bits();
+      let expected = (0..NUM_CHALLENGE_BITS)
+        .rev()
+        .fold(E::Scalar::ZERO, |acc, i| {
+          acc.double() + E::Scalar::from(bits[i] as u64)
+        });
+      assert_eq!(challenge, expected);
+    }
+
+    let input = (0..5).map(|_| E::Scalar::random(OsRng)).collect::<Vec<_>>();
+    let input_gadget = input
+      .iter()
+      .enumerate()
+      .map(|(i, x)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("input {i}")), || *x))
+      .collect::<Vec<_>>();
+    let out = hash_gadget(cs.namespace(|| "hash"), &constants, &input_gadget).unwrap();
+    assert_eq!(out.get_value(), Some(hash(&constants, &input)));
+    assert!(cs.is_satisfied());
+  }
+
+  #[test]
+  fn test_poseidon() {
+    test_poseidon_with::<PallasEngine>();
+    test_poseidon_with::<Bn256EngineKZG>();
+  }
+
+  fn test_poseidon_transcript_with<E: Engine>() {
+    let constants = PoseidonConstantsCircuit::<E::Scalar>::default();
+    let mut cs = TestConstraintSystem::<E::Scalar>::new();
+    let mut transcript = PoseidonTranscript::<E>::new(b"test");
+    let mut gadget =
+      PoseidonTranscriptGadget::new(cs.namespace(|| "new"), constants, b"test").unwrap();
+
+    let alloc_bits = |cs: &mut TestConstraintSystem<E::Scalar>, name: &str, bytes: &[u8]| {
+      bytes
+        .iter()
+        .flat_map(|b| (0..8).map(move |i| (b >> i) & 1 == 1))
+        .enumerate()
+        .map(|(i, b)| {
+          Boolean::from(
+            AllocatedBit::alloc(cs.namespace(|| format!("{name} {i}")), Some(b)).unwrap(),
+          )
+        })
+        .collect::<Vec<_>>()
+    };
+
+    for round in 0..3 {
+      transcript.dom_sep(b"round");
+      gadget
+        .dom_sep(cs.namespace(|| format!("dom_sep {round}")), b"round")
+        .unwrap();
+
+      // a scalar and a vector of scalars, whose representation spans several field elements
+      let s = E::Scalar::random(OsRng);
+      let v = (0..3).map(|_| E::Scalar::random(OsRng)).collect::<Vec<_>>();
+      transcript.absorb(b"s", &s);
+      transcript.absorb(b"v", &v.as_slice());
+      let bits = alloc_bits(&mut cs, &format!("s {round}"), &s.to_transcript_bytes());
+      gadget
+        .absorb(cs.namespace(|| format!("absorb s {round}")), b"s", &bits)
+        .unwrap();
+      let bits = alloc_bits(
+        &mut cs,
+        &format!("v {round}"),
+        &v.as_slice().to_transcript_bytes(),
+      );
+      gadget
+        .absorb(cs.namespace(|| format!("absorb v {round}")), b"v", &bits)
+        .unwrap();
+
+      let c = transcript.squeeze(b"c").unwrap();
+      let c_gadget = gadget
+        .squeeze(cs.namespace(|| format!("squeeze {round}")), b"c")
+        .unwrap();
+      assert_eq!(c_gadget.get_value(), Some(c));
+    }
+    assert!(cs.is_satisfied());
+
+    // labels are bound to the challenges
+    let mut other = PoseidonTranscript::<E>::new(b"test");
+    assert_ne!(
+      other.squeeze(b"c").unwrap(),
+      PoseidonTranscript::<E>::new(b"test").squeeze(b"d").unwrap()
+    );
+  }
+
+  #[test]
+  fn test_poseidon_transcript() {
+    test_poseidon_transcript_with::<PallasEngine>();
+    test_poseidon_transcript_with::<Bn256EngineKZG>();
+  }
+}
@@ -0,0 +1,193 @@
+//! This module implements range-check gadgets for allocated numbers.
+//!
+//! A range check constrains a number to fit in a fixed number of bits. The strategy used to enforce
+//! it is chosen from the number of bits by `RangeCheckStrategy::for_bits`; bit decomposition, which
+//! costs one constraint per bit plus one, is currently the only strategy.
+use crate::frontend::{
+  num::AllocatedNum, AllocatedBit, Boolean, ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::{PrimeField, PrimeFieldBits};
+
+/// A strategy used to enforce a range check
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RangeCheckStrategy {
+  /// Decompose the number into bits and constrain their weighted sum to equal the number
+  BitDecomposition,
+}
+
+impl RangeCheckStrategy {
+  /// Returns the strategy used to check that a number fits in `num_bits` bits
+  pub fn for_bits(_num_bits: usize) -> Self {
+    Self::BitDecomposition
+  }
+}
+
+/// Constrains `x` to fit in 32 bits and returns its little-endian bits
+pub fn assert_u32<F, CS>(cs: CS, x: &AllocatedNum<F>) -> Result<Vec<AllocatedBit>, SynthesisError>
+where
+  F: PrimeField + PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  assert_bits(cs, x, 32)
+}
+
+/// Constrains `x` to fit in 64 bits and returns its little-endian bits
+pub fn assert_u64<F, CS>(cs: CS, x: &AllocatedNum<F>) -> Result<Vec<AllocatedBit>, SynthesisError>
+where
+  F: PrimeField + PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  assert_bits(cs, x, 64)
+}
+
+/// Constrains `x` to fit in `num_bits` bits and returns its little-endian bits.
+///
+/// Returns `SynthesisError::Unsatisfiable` if `num_bits` exceeds the capacity of the field, since
+/// the check would then be vacuous.
+pub fn assert_bits<F, CS>(
+  mut cs: CS,
+  x: &AllocatedNum<F>,
+  num_bits: usize,
+) -> Result<Vec<AllocatedBit>, SynthesisError>
+where
+  F: PrimeField + PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  match RangeCheckStrategy::for_bits(num_bits) {
+    RangeCheckStrategy::BitDecomposition => decompose(
+      cs.namespace(|| "decompose"),
+      x.get_value(),
+      LinearCombination::zero() + x.get_variable(),
+      num_bits,
+    ),
+  }
+}
+
+/// Returns a boolean that is true if and only if `a < b`.
+///
+/// Both `a` and `b` must already be constrained to fit in `num_bits` bits, for example with
+/// `assert_u32` or `assert_u64`, and `num_bits` must be smaller than the capacity of the field.
+pub fn less_than<F, CS>(
+  mut cs: CS,
+  a: &AllocatedNum<F>,
+  b: &AllocatedNum<F>,
+  num_bits: usize,
+) -> Result<Boolean, SynthesisError>
+where
+  F: PrimeField + PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  // a - b + 2^num_bits lies in [1, 2^(num_bits + 1)), and its top bit is set if and only if a >= b
+  let offset = F::from(2u64).pow_vartime([num_bits as u64]);
+  let diff = a
+    .get_value()
+    .zip(b.get_value())
+    .map(|(a, b)| a - b + offset);
+  let lc = LinearCombination::zero() + a.get_variable() - b.get_variable() + (offset, CS::one());
+  let bits = decompose(
+    cs.namespace(|| "decompose difference"),
+    diff,
+    lc,
+    num_bits + 1,
+  )?;
+  Ok(Boolean::from(bits[num_bits].clone()).not())
+}
+
+/// Allocates the `num_bits` little-endian bits of `value` and constrains their weighted sum to equal `lc`
+pub(crate) fn decompose<F, CS>(
+  mut cs: CS,
+  value: Option<F>,
+  lc: LinearCombination<F>,
+  num_bits: usize,
+) -> Result<Vec<AllocatedBit>, SynthesisError>
+where
+  F: PrimeField + PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  if num_bits > F::CAPACITY as usize {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+
+  let values = value.map(|v| v.to_le_bits());
+  let bits = (0..num_bits)
+    .map(|i| {
+      AllocatedBit::alloc(
+        cs.namespace(|| format!("bit {i}")),
+        values.as_ref().map(|v| v[i]),
+      )
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let mut sum = LinearCombination::zero();
+  let mut coeff = F::ONE;
+  for bit in &bits {
+    sum = sum + (coeff, bit.get_variable());
+    coeff = coeff.double();
+  }
+  cs.enforce(
+    || "bits sum to the number",
+    |_| sum,
+    |lc| lc + CS::one(),
+    |_| lc,
+  );
+
+  Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{frontend::test_cs::TestConstraintSystem, provider::PallasEngine, traits::Engine};
+
+  type F = <PallasEngine as Engine>::Scalar;
+
+  fn alloc<CS: ConstraintSystem<F>>(cs: &mut CS, name: &str, value: F) -> AllocatedNum<F> {
+    AllocatedNum::alloc(cs.namespace(|| name), || Ok(value)).unwrap()
+  }
+
+  #[test]
+  fn test_assert_bits() {
+    let check_u32 = |value: F| {
+      let mut cs = TestConstraintSystem::<F>::new();
+      let x = alloc(&mut cs, "x", value);
+      let bits = assert_u32(cs.namespace(|| "range"), &x).unwrap();
+      assert_eq!(bits.len(), 32);
+      // one constraint per bit and one for their sum
+      assert_eq!(cs.num_constraints(), 33);
+      cs.is_satisfied()
+    };
+    assert!(check_u32(F::from(0u64)));
+    assert!(check_u32(F::from(u32::MAX as u64)));
+    assert!(!check_u32(F::from(1u64 << 32)));
+    assert!(!check_u32(-F::from(1u64)));
+
+    let check_u64 = |value: F| {
+      let mut cs = TestConstraintSystem::<F>::new();
+      let x = alloc(&mut cs, "x", value);
+      assert_u64(cs.namespace(|| "range"), &x).unwrap();
+      cs.is_satisfied()
+    };
+    assert!(check_u64(F::from(u64::MAX)));
+    assert!(!check_u64(F::from(u64::MAX) + F::from(1u64)));
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let x = alloc(&mut cs, "x", F::from(1u64));
+    assert!(assert_bits(&mut cs, &x, F::CAPACITY as usize + 1).is_err());
+  }
+
+  #[test]
+  fn test_less_than() {
+    let values = [0u64, 1, 2, 1000, u32::MAX as u64 - 1, u32::MAX as u64];
+    for a in values {
+      for b in values {
+        let mut cs = TestConstraintSystem::<F>::new();
+        let a_num = alloc(&mut cs, "a", F::from(a));
+        let b_num = alloc(&mut cs, "b", F::from(b));
+        let lt = less_than(cs.namespace(|| "lt"), &a_num, &b_num, 32).unwrap();
+        assert_eq!(lt.get_value(), Some(a < b));
+        assert!(cs.is_satisfied());
+      }
+    }
+  }
+}
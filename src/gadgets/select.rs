@@ -0,0 +1,411 @@
+//! This module implements selection gadgets for allocated numbers: conditional selects and swaps,
+//! multiplexers that pick one of `k` numbers, and boolean logic over many bits, as needed by step
+//! circuits that branch.
+//!
+//! The gadgets use as few constraints as they can: a select costs one constraint, and none when its
+//! condition is constant, a swap two, and a `k`-way multiplexer `k - 1` selects. `all`, `any`, and
+//! `equals` cost two constraints however many bits they combine, where chaining `Boolean::and`
+//! costs one constraint per bit.
+use crate::{
+  frontend::{
+    num::AllocatedNum, AllocatedBit, Boolean, ConstraintSystem, LinearCombination, SynthesisError,
+  },
+  gadgets::{range, utils::conditionally_select},
+};
+use ff::{PrimeField, PrimeFieldBits};
+
+/// Returns `a` if `condition` is true and `b` otherwise
+pub fn select<F, CS>(
+  cs: CS,
+  condition: &Boolean,
+  a: &AllocatedNum<F>,
+  b: &AllocatedNum<F>,
+) -> Result<AllocatedNum<F>, SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  match condition {
+    Boolean::Constant(true) => Ok(a.clone()),
+    Boolean::Constant(false) => Ok(b.clone()),
+    _ => conditionally_select(cs, a, b, condition),
+  }
+}
+
+/// Returns `a` if `condition` is true and `b` otherwise, entry by entry
+pub fn select_vec<F, CS>(
+  mut cs: CS,
+  condition: &Boolean,
+  a: &[AllocatedNum<F>],
+  b: &[AllocatedNum<F>],
+) -> Result<Vec<AllocatedNum<F>>, SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  if a.len() != b.len() {
+    return Err(SynthesisError::IncompatibleLengthVector(format!(
+      "cannot select between {} and {} numbers",
+      a.len(),
+      b.len()
+    )));
+  }
+  a.iter()
+    .zip(b)
+    .enumerate()
+    .map(|(i, (a, b))| select(cs.namespace(|| format!("select {i}")), condition, a, b))
+    .collect()
+}
+
+/// Returns `(b, a)` if `condition` is true and `(a, b)` otherwise
+pub fn swap<F, CS>(
+  mut cs: CS,
+  condition: &Boolean,
+  a: &AllocatedNum<F>,
+  b: &AllocatedNum<F>,
+) -> Result<(AllocatedNum<F>, AllocatedNum<F>), SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  if let Boolean::Constant(c) = condition {
+    return Ok(if *c {
+      (b.clone(), a.clone())
+    } else {
+      (a.clone(), b.clone())
+    });
+  }
+  let first = select(cs.namespace(|| "first"), condition, b, a)?;
+  let second = AllocatedNum::alloc(cs.namespace(|| "second"), || {
+    Ok(
+      a.get_value().ok_or(SynthesisError::AssignmentMissing)?
+        + b.get_value().ok_or(SynthesisError::AssignmentMissing)?
+        - first.get_value().ok_or(SynthesisError::AssignmentMissing)?,
+    )
+  })?;
+  cs.enforce(
+    || "second = a + b - first",
+    |lc| lc + a.get_variable() + b.get_variable() - first.get_variable(),
+    |lc| lc + CS::one(),
+    |lc| lc + second.get_variable(),
+  );
+  Ok((first, second))
+}
+
+/// Returns the entry of `values` at the index whose little-endian bits are `bits`, where `values`
+/// has `2^bits.len()` entries
+pub fn mux<F, CS>(
+  cs: CS,
+  bits: &[Boolean],
+  values: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  if values.len() != 1 << bits.len() {
+    return Err(SynthesisError::IncompatibleLengthVector(format!(
+      "a multiplexer with {} selector bits needs {} values, not {}",
+      bits.len(),
+      1usize << bits.len(),
+      values.len()
+    )));
+  }
+  select_tree(cs, bits, values)
+}
+
+/// Returns the entry of `values` at `index`, which is constrained to be smaller than the number of
+/// values.
+///
+/// This decomposes `index` into bits and selects with them as `mux` does. When the number of values
+/// is not a power of two, it additionally checks that `index` is in range, with `range::less_than`.
+pub fn mux_index<F, CS>(
+  mut cs: CS,
+  index: &AllocatedNum<F>,
+  values: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError>
+where
+  F: PrimeField + PrimeFieldBits,
+  CS: ConstraintSystem<F>,
+{
+  if values.is_empty() {
+    return Err(SynthesisError::IncompatibleLengthVector(
+      "a multiplexer needs at least one value".to_string(),
+    ));
+  }
+  let num_bits = values.len().next_power_of_two().trailing_zeros() as usize;
+  let bits = range::assert_bits(cs.namespace(|| "index bits"), index, num_bits)?;
+  if !values.len().is_power_of_two() {
+    let len = AllocatedNum::alloc(cs.namespace(|| "len"), || Ok(F::from(values.len() as u64)))?;
+    cs.enforce(
+      || "len is constant",
+      |lc| lc + len.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc + (F::from(values.len() as u64), CS::one()),
+    );
+    let in_range = range::less_than(cs.namespace(|| "in range"), index, &len, num_bits)?;
+    Boolean::enforce_equal(
+      cs.namespace(|| "index < len"),
+      &in_range,
+      &Boolean::constant(true),
+    )?;
+  }
+  let bits = bits.into_iter().map(Boolean::from).collect::<Vec<_>>();
+  select_tree(cs.namespace(|| "select"), &bits, values)
+}
+
+/// Selects the entry of `values` at the index with little-endian bits `bits`, pairing entries level
+/// by level. An entry left without a pair at a level is passed up as is, which is sound because
+/// the index lies below `values.len()`, so its bit at that level is zero whenever it falls in the
+/// entry's range.
+fn select_tree<F, CS>(
+  mut cs: CS,
+  bits: &[Boolean],
+  values: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  let mut level = values.to_vec();
+  for (j, bit) in bits.iter().enumerate() {
+    level = level
+      .chunks(2)
+      .enumerate()
+      .map(|(i, pair)| match pair {
+        [lo, hi] => select(cs.namespace(|| format!("level {j} pair {i}")), bit, hi, lo),
+        [single] => Ok(single.clone()),
+        _ => unreachable!(),
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+  }
+  Ok(level.swap_remove(0))
+}
+
+/// Returns a boolean that is true if and only if all of `bits` are true
+pub fn all<F, CS>(cs: CS, bits: &[Boolean]) -> Result<Boolean, SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  if bits.iter().any(|b| matches!(b, Boolean::Constant(false))) {
+    return Ok(Boolean::constant(false));
+  }
+  let bits = bits.iter().filter(|b| !b.is_constant()).collect::<Vec<_>>();
+  match bits.as_slice() {
+    [] => Ok(Boolean::constant(true)),
+    [bit] => Ok((*bit).clone()),
+    _ => {
+      // all of the bits are true if and only if none of them is false
+      let value = count(&bits, |b| !b);
+      let lc = bits.iter().fold(LinearCombination::zero(), |lc, b| {
+        lc + &b.not().lc(CS::one(), F::ONE)
+      });
+      Ok(Boolean::from(is_zero(cs, value, lc)?))
+    }
+  }
+}
+
+/// Returns a boolean that is true if and only if at least one of `bits` is true
+pub fn any<F, CS>(cs: CS, bits: &[Boolean]) -> Result<Boolean, SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  let negated = bits.iter().map(Boolean::not).collect::<Vec<_>>();
+  Ok(all(cs, &negated)?.not())
+}
+
+/// Returns a boolean that is true if and only if `a` equals `b`
+pub fn equals<F, CS>(
+  cs: CS,
+  a: &AllocatedNum<F>,
+  b: &AllocatedNum<F>,
+) -> Result<Boolean, SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  let value = a.get_value().zip(b.get_value()).map(|(a, b)| a - b);
+  let lc = LinearCombination::zero() + a.get_variable() - b.get_variable();
+  Ok(Boolean::from(is_zero(cs, value, lc)?))
+}
+
+/// Returns the number of `bits` for which `f` holds, as a field element, if all of them are known
+fn count<F: PrimeField>(bits: &[&Boolean], f: impl Fn(bool) -> bool) -> Option<F> {
+  bits.iter().try_fold(F::ZERO, |sum, b| {
+    b.get_value().map(|b| if f(b) { sum + F::ONE } else { sum })
+  })
+}
+
+/// Returns a bit that is set if and only if `lc`, whose value is `value`, is zero.
+///
+/// The two constraints `inv * lc = 1 - r` and `r * lc = 0` force `r` to be zero or one, so `r` is
+/// not constrained to be a bit again.
+fn is_zero<F, CS>(
+  mut cs: CS,
+  value: Option<F>,
+  lc: LinearCombination<F>,
+) -> Result<AllocatedBit, SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  let r_value = value.map(|v| v.is_zero_vartime());
+  let r = cs.alloc(
+    || "r",
+    || {
+      r_value
+        .map(|r| if r { F::ONE } else { F::ZERO })
+        .ok_or(SynthesisError::AssignmentMissing)
+    },
+  )?;
+  let inv = cs.alloc(
+    || "inv",
+    || {
+      value
+        .map(|v| v.invert().unwrap_or(F::ZERO))
+        .ok_or(SynthesisError::AssignmentMissing)
+    },
+  )?;
+  cs.enforce(
+    || "inv * lc = 1 - r",
+    |l| l + inv,
+    |l| l + &lc,
+    |l| l + CS::one() - r,
+  );
+  cs.enforce(|| "r * lc = 0", |l| l + r, |l| l + &lc, |l| l);
+  Ok(AllocatedBit::from_constrained(r, r_value))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{frontend::test_cs::TestConstraintSystem, provider::PallasEngine, traits::Engine};
+
+  type F = <PallasEngine as Engine>::Scalar;
+
+  fn alloc<CS: ConstraintSystem<F>>(cs: &mut CS, name: &str, value: u64) -> AllocatedNum<F> {
+    AllocatedNum::alloc(cs.namespace(|| name), || Ok(F::from(value))).unwrap()
+  }
+
+  fn alloc_bit<CS: ConstraintSystem<F>>(cs: &mut CS, name: &str, value: bool) -> Boolean {
+    Boolean::from(AllocatedBit::alloc(cs.namespace(|| name), Some(value)).unwrap())
+  }
+
+  #[test]
+  fn test_select_and_swap() {
+    for c in [false, true] {
+      let mut cs = TestConstraintSystem::<F>::new();
+      let (a, b) = (alloc(&mut cs, "a", 3), alloc(&mut cs, "b", 5));
+      let cond = alloc_bit(&mut cs, "c", c);
+      let base = cs.num_constraints();
+
+      let s = select(cs.namespace(|| "select"), &cond, &a, &b).unwrap();
+      assert_eq!(s.get_value(), Some(F::from(if c { 3 } else { 5 })));
+      assert_eq!(cs.num_constraints(), base + 1);
+
+      let (x, y) = swap(cs.namespace(|| "swap"), &cond, &a, &b).unwrap();
+      let expected = if c { (5, 3) } else { (3, 5) };
+      assert_eq!(x.get_value(), Some(F::from(expected.0)));
+      assert_eq!(y.get_value(), Some(F::from(expected.1)));
+      assert_eq!(cs.num_constraints(), base + 3);
+      assert!(cs.is_satisfied());
+
+      // constant conditions cost nothing
+      let s = select(cs.namespace(|| "const"), &Boolean::constant(c), &a, &b).unwrap();
+      assert_eq!(s.get_value(), Some(F::from(if c { 3 } else { 5 })));
+      assert_eq!(cs.num_constraints(), base + 3);
+    }
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let a = alloc(&mut cs, "a", 1);
+    assert!(select_vec(
+      &mut cs,
+      &Boolean::constant(true),
+      std::slice::from_ref(&a),
+      &[]
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn test_mux() {
+    for k in 1..=9usize {
+      for i in 0..k {
+        let mut cs = TestConstraintSystem::<F>::new();
+        let values = (0..k)
+          .map(|j| alloc(&mut cs, &format!("v{j}"), 10 + j as u64))
+          .collect::<Vec<_>>();
+        let index = alloc(&mut cs, "index", i as u64);
+        let base = cs.num_constraints();
+        let v = mux_index(cs.namespace(|| "mux"), &index, &values).unwrap();
+        assert_eq!(v.get_value(), Some(F::from(10 + i as u64)));
+        assert!(cs.is_satisfied());
+        if k.is_power_of_two() {
+          // the decomposition of the index and k - 1 selects
+          let num_bits = k.trailing_zeros() as usize;
+          assert_eq!(cs.num_constraints(), base + num_bits + 1 + k - 1);
+        }
+      }
+
+      // an index out of range is unsatisfiable
+      let mut cs = TestConstraintSystem::<F>::new();
+      let values = (0..k)
+        .map(|j| alloc(&mut cs, &format!("v{j}"), j as u64))
+        .collect::<Vec<_>>();
+      let index = alloc(&mut cs, "index", k as u64);
+      let _ = mux_index(cs.namespace(|| "mux"), &index, &values);
+      assert!(!cs.is_satisfied());
+    }
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let values = (0..4)
+      .map(|j| alloc(&mut cs, &format!("v{j}"), j))
+      .collect::<Vec<_>>();
+    let bits = [
+      alloc_bit(&mut cs, "b0", true),
+      alloc_bit(&mut cs, "b1", true),
+    ];
+    let v = mux(cs.namespace(|| "mux"), &bits, &values).unwrap();
+    assert_eq!(v.get_value(), Some(F::from(3)));
+    assert!(mux(cs.namespace(|| "short"), &bits, &values[..3]).is_err());
+  }
+
+  #[test]
+  fn test_all_any_equals() {
+    for n in 0..4usize {
+      for mask in 0..1u32 << n {
+        let mut cs = TestConstraintSystem::<F>::new();
+        let bits = (0..n)
+          .map(|i| alloc_bit(&mut cs, &format!("b{i}"), mask >> i & 1 == 1))
+          .collect::<Vec<_>>();
+        let base = cs.num_constraints();
+        let a = all(cs.namespace(|| "all"), &bits).unwrap();
+        let o = any(cs.namespace(|| "any"), &bits).unwrap();
+        assert_eq!(a.get_value(), Some(mask == (1 << n) - 1));
+        assert_eq!(o.get_value(), Some(mask != 0));
+        assert!(cs.is_satisfied());
+        if n >= 2 {
+          assert_eq!(cs.num_constraints(), base + 4);
+        }
+      }
+    }
+
+    let mut cs = TestConstraintSystem::<F>::new();
+    let bit = alloc_bit(&mut cs, "b", true);
+    let a = all(&mut cs, &[bit.clone(), Boolean::constant(false)]).unwrap();
+    assert_eq!(a.get_value(), Some(false));
+    let o = any(&mut cs, &[bit, Boolean::constant(true)]).unwrap();
+    assert_eq!(o.get_value(), Some(true));
+
+    for (x, y) in [(3, 3), (3, 4)] {
+      let mut cs = TestConstraintSystem::<F>::new();
+      let (a, b) = (alloc(&mut cs, "a", x), alloc(&mut cs, "b", y));
+      let e = equals(cs.namespace(|| "eq"), &a, &b).unwrap();
+      assert_eq!(e.get_value(), Some(x == y));
+      assert_eq!(cs.num_constraints(), 2);
+      assert!(cs.is_satisfied());
+    }
+  }
+}
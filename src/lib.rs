@@ -10,25 +10,51 @@
 #![allow(non_snake_case)]
 #![forbid(unsafe_code)]
 
+// lets the code generated by `nova-snark-derive` refer to this crate in its own tests
+#[cfg(test)]
+extern crate self as nova_snark;
+
 // main APIs exposed by this library
 pub mod nova;
+pub mod prelude;
 
 #[cfg(feature = "experimental")]
 pub mod neutron;
 
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+
 // public modules
+pub mod encoding;
 pub mod errors;
+pub mod field;
 pub mod frontend;
 pub mod gadgets;
 pub mod provider;
 pub mod spartan;
 pub mod traits;
 
+/// Items that the code generated by `nova-snark-derive` uses, which are not part of the API
+#[doc(hidden)]
+pub mod __private {
+  pub use ff::PrimeField;
+}
+
 // private modules
+mod build_info;
+#[cfg(test)]
+mod compat;
 mod constants;
 mod digest;
 mod r1cs;
 
+pub use build_info::{build_info, BuildInfo, Stamped};
 use traits::{commitment::CommitmentEngineTrait, Engine};
 
 // some type aliases
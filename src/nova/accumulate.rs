@@ -0,0 +1,248 @@
+//! This module defines `Accumulator`, which folds R1CS instances produced elsewhere into a running
+//! relaxed R1CS instance, so that generating witnesses and folding them can run on different
+//! machines.
+//!
+//! Producers and the accumulator share `AccumulationParams`, which hold the shape of a circuit and
+//! the keys to commit to its witnesses. A producer synthesizes the circuit on its inputs with
+//! `AccumulationParams::produce`, which commits to the witness and serializes the instance and
+//! the witness along with the digest of the shape. `Accumulator::fold` decodes such a pair,
+//! rejects it unless it is for the same shape and satisfies it, and folds it into the running
+//! instance with `NIFSRelaxed`, which absorbs both instances in its transcript since they are
+//! unrelated. The running instance is satisfiable if and only if every folded instance is.
+use super::nifs::NIFSRelaxed;
+use crate::{
+  errors::NovaError,
+  frontend::{
+    r1cs::{NovaShape, NovaWitness},
+    shape_cs::ShapeCS,
+    solver::SatisfyingAssignment,
+    Circuit, ConstraintSystem,
+  },
+  r1cs::{
+    CommitmentKeyHint, R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance,
+    RelaxedR1CSWitness,
+  },
+  traits::{Engine, ROConstants},
+  CommitmentKey,
+};
+use serde::{Deserialize, Serialize};
+
+/// The shape of a circuit whose instances are folded by an `Accumulator`, along with the keys to
+/// commit to and fold them
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct AccumulationParams<E: Engine> {
+  ck: CommitmentKey<E>,
+  ro_consts: ROConstants<E>,
+  shape: R1CSShape<E>,
+}
+
+/// An instance and a witness for the shape with the given digest, as exchanged between producers
+/// and the accumulator
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+struct ExternalInstance<E: Engine> {
+  digest: E::Scalar,
+  U: R1CSInstance<E>,
+  W: R1CSWitness<E>,
+}
+
+impl<E: Engine> AccumulationParams<E> {
+  /// Synthesizes the shape of `circuit` and creates a commitment key for it
+  pub fn setup<C: Circuit<E::Scalar>>(
+    circuit: C,
+    ck_hint: &CommitmentKeyHint<E>,
+  ) -> Result<Self, NovaError> {
+    let mut cs: ShapeCS<E> = ShapeCS::new();
+    circuit.synthesize(&mut cs)?;
+    let (shape, ck) = cs.r1cs_shape(ck_hint);
+    Ok(Self {
+      ck,
+      ro_consts: ROConstants::<E>::default(),
+      shape,
+    })
+  }
+
+  /// Returns the digest of the shape, which binds the folded instances to it
+  pub fn digest(&self) -> E::Scalar {
+    self.shape.digest()
+  }
+
+  /// Returns the number of constraints, of variables, and of public inputs of the shape
+  pub fn num_cons_vars_io(&self) -> (usize, usize, usize) {
+    (self.shape.num_cons, self.shape.num_vars, self.shape.num_io)
+  }
+
+  /// Synthesizes `circuit` on its inputs, commits to its witness, and returns the serialized
+  /// instance and witness to be folded by an `Accumulator`
+  pub fn produce<C: Circuit<E::Scalar>>(&self, circuit: C) -> Result<Vec<u8>, NovaError> {
+    let mut cs = SatisfyingAssignment::<E>::new();
+    circuit.synthesize(&mut cs)?;
+    let (U, W) = cs.r1cs_instance_and_witness(&self.shape, &self.ck)?;
+    let external = ExternalInstance {
+      digest: self.digest(),
+      U,
+      W,
+    };
+    bincode::serialize(&external).map_err(|e| NovaError::InvalidEncoding {
+      reason: e.to_string(),
+    })
+  }
+}
+
+/// A running relaxed R1CS instance and witness into which externally produced instances are folded
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Accumulator<E: Engine> {
+  U: RelaxedR1CSInstance<E>,
+  W: RelaxedR1CSWitness<E>,
+  num_folded: usize,
+}
+
+impl<E: Engine> Accumulator<E> {
+  /// Creates an accumulator for the shape of `params` that holds no instance yet
+  pub fn new(params: &AccumulationParams<E>) -> Self {
+    Self {
+      U: RelaxedR1CSInstance::default(&params.ck, &params.shape),
+      W: RelaxedR1CSWitness::default(&params.shape),
+      num_folded: 0,
+    }
+  }
+
+  /// Returns the number of instances folded so far
+  pub fn num_folded(&self) -> usize {
+    self.num_folded
+  }
+
+  /// Decodes an instance and a witness produced by `AccumulationParams::produce` and folds them
+  /// into the running instance.
+  ///
+  /// The pair is rejected with `NovaError::InvalidEncoding` if it cannot be decoded or is for
+  /// another shape, and with `NovaError::IncorrectWitness` if the witness does not satisfy the
+  /// instance. A rejected pair leaves the accumulator unchanged.
+  pub fn fold(&mut self, params: &AccumulationParams<E>, bytes: &[u8]) -> Result<(), NovaError> {
+    let external: ExternalInstance<E> =
+      bincode::deserialize(bytes).map_err(|e| NovaError::InvalidEncoding {
+        reason: e.to_string(),
+      })?;
+    if external.digest != params.digest() {
+      return Err(NovaError::InvalidEncoding {
+        reason: "instance is for another shape".to_string(),
+      });
+    }
+    params
+      .shape
+      .is_sat(&params.ck, &external.U, &external.W)
+      .map_err(|_| NovaError::IncorrectWitness)?;
+
+    let U = RelaxedR1CSInstance::from_r1cs_instance(&params.ck, &params.shape, &external.U);
+    let W = RelaxedR1CSWitness::from_r1cs_witness(&params.shape, &external.W);
+    let (_, (U, W)) = NIFSRelaxed::prove(
+      &params.ck,
+      &params.ro_consts,
+      &params.digest(),
+      &params.shape,
+      &self.U,
+      &self.W,
+      &U,
+      &W,
+    )?;
+    self.U = U;
+    self.W = W;
+    self.num_folded += 1;
+    Ok(())
+  }
+
+  /// Checks that the running instance is satisfied by the running witness
+  pub fn verify(&self, params: &AccumulationParams<E>) -> Result<(), NovaError> {
+    params.shape.is_sat_relaxed(&params.ck, &self.U, &self.W)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::{num::AllocatedNum, SynthesisError},
+    provider::PallasEngine,
+    traits::snark::default_ck_hint,
+  };
+  use ff::{Field, PrimeField};
+
+  type E = PallasEngine;
+  type F = <E as Engine>::Scalar;
+
+  /// Proves knowledge of `x` such that `x^3 + x + 5 = y` for a public `y`
+  struct CubicCircuit<F: PrimeField> {
+    x: Option<F>,
+  }
+
+  impl<F: PrimeField> Circuit<F> for CubicCircuit<F> {
+    fn synthesize<CS: ConstraintSystem<F>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+      let x = AllocatedNum::alloc(cs.namespace(|| "x"), || {
+        self.x.ok_or(SynthesisError::AssignmentMissing)
+      })?;
+      let x_sq = x.square(cs.namespace(|| "x_sq"))?;
+      let x_cu = x_sq.mul(cs.namespace(|| "x_cu"), &x)?;
+      let y = AllocatedNum::alloc_input(cs.namespace(|| "y"), || {
+        Ok(
+          x_cu.get_value().ok_or(SynthesisError::AssignmentMissing)?
+            + x.get_value().ok_or(SynthesisError::AssignmentMissing)?
+            + F::from(5u64),
+        )
+      })?;
+      cs.enforce(
+        || "y = x^3 + x + 5",
+        |lc| lc + x_cu.get_variable() + x.get_variable() + (F::from(5u64), CS::one()),
+        |lc| lc + CS::one(),
+        |lc| lc + y.get_variable(),
+      );
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn test_accumulator() {
+    let params =
+      AccumulationParams::<E>::setup(CubicCircuit::<F> { x: None }, &*default_ck_hint()).unwrap();
+    assert_eq!(params.num_cons_vars_io().2, 1);
+
+    // instances produced independently, e.g., on other machines, are folded in any order
+    let produced = (0..4u64)
+      .map(|x| {
+        params
+          .produce(CubicCircuit {
+            x: Some(F::from(x)),
+          })
+          .unwrap()
+      })
+      .collect::<Vec<_>>();
+    let mut acc = Accumulator::new(&params);
+    for bytes in produced.iter().rev() {
+      acc.fold(&params, bytes).unwrap();
+    }
+    assert_eq!(acc.num_folded(), 4);
+    assert!(acc.verify(&params).is_ok());
+
+    // pairs that cannot be decoded, are for another shape, or are unsatisfied are rejected
+    let bytes = &produced[0];
+    assert!(matches!(
+      acc.fold(&params, &bytes[..bytes.len() - 1]),
+      Err(NovaError::InvalidEncoding { .. })
+    ));
+    let mut external: ExternalInstance<E> = bincode::deserialize(bytes).unwrap();
+    external.digest += F::ONE;
+    assert!(matches!(
+      acc.fold(&params, &bincode::serialize(&external).unwrap()),
+      Err(NovaError::InvalidEncoding { .. })
+    ));
+    external.digest = params.digest();
+    external.U.X[0] += F::ONE;
+    assert_eq!(
+      acc.fold(&params, &bincode::serialize(&external).unwrap()),
+      Err(NovaError::IncorrectWitness)
+    );
+    assert_eq!(acc.num_folded(), 4);
+    assert!(acc.verify(&params).is_ok());
+  }
+}
@@ -0,0 +1,290 @@
+//! This module defines `ShapeAdvisor`, which reports how the size of a step circuit relates to the
+//! power-of-two sizes that commitment keys and compressing SNARKs round up to.
+//!
+//! A primary circuit whose size is just above a power of two needs twice the generators of one
+//! just below it, so a few constraints can double the size of the commitment key and the cost of
+//! compressing. The advisor reports the next power-of-two target, the headroom below it, and
+//! whether trimming or splitting the step circuit would halve the commitment key.
+use super::PublicParams;
+use crate::{
+  errors::NovaError,
+  frontend::{num::AllocatedNum, shape_cs::ShapeCS, ConstraintSystem},
+  r1cs::{CommitmentKeyHint, R1CSShape},
+  traits::{circuit::StepCircuit, snark::default_ck_hint, Engine},
+};
+use core::{fmt, marker::PhantomData};
+use ff::Field;
+
+/// The largest number of steps `ShapeAdvisor` considers splitting a step circuit into
+const MAX_SPLIT: usize = 8;
+
+/// A suggestion of `ShapeAdvisor` on how to change a step circuit
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShapeSuggestion {
+  /// The circuit makes good use of its power-of-two size, and no change is suggested
+  Keep,
+  /// The circuit exceeds the previous power of two by `excess` constraints or variables, and
+  /// removing them from the step circuit halves the commitment key
+  Trim {
+    /// The number of constraints or variables over the previous power of two
+    excess: usize,
+  },
+  /// Spreading the work of the step circuit over `steps` steps, each with a `1 / steps` share of
+  /// it, brings the commitment key down to `ck_size` generators
+  Split {
+    /// The number of steps to split the step circuit into
+    steps: usize,
+    /// The number of generators of the commitment key after splitting
+    ck_size: usize,
+  },
+}
+
+/// The size of the primary augmented circuit of a step circuit, relative to power-of-two targets
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShapeAdvice {
+  /// The number of constraints of the primary augmented circuit
+  pub num_cons: usize,
+  /// The number of variables of the primary augmented circuit
+  pub num_vars: usize,
+  /// The number of constraints of the step circuit on its own
+  pub step_num_cons: usize,
+  /// The number of variables of the step circuit on its own
+  pub step_num_vars: usize,
+  /// The number of generators of the commitment key
+  pub ck_size: usize,
+  /// The power of two that the commitment key and compressing SNARKs round up to
+  pub target: usize,
+  /// The number of constraints and variables that the step circuit can grow by without
+  /// exceeding `target`, i.e., the work that padding to `target` would otherwise waste
+  pub headroom: usize,
+  /// The suggested change to the step circuit
+  pub suggestion: ShapeSuggestion,
+}
+
+impl fmt::Display for ShapeAdvice {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+      f,
+      "augmented circuit: {} constraints, {} variables",
+      self.num_cons, self.num_vars
+    )?;
+    writeln!(
+      f,
+      "step circuit: {} constraints, {} variables",
+      self.step_num_cons, self.step_num_vars
+    )?;
+    writeln!(
+      f,
+      "commitment key: {} generators, padded to {} ({} headroom)",
+      self.ck_size, self.target, self.headroom
+    )?;
+    match &self.suggestion {
+      ShapeSuggestion::Keep => writeln!(f, "suggestion: keep the step circuit as is"),
+      ShapeSuggestion::Trim { excess } => writeln!(
+        f,
+        "suggestion: remove {excess} constraints or variables to halve the commitment key"
+      ),
+      ShapeSuggestion::Split { steps, ck_size } => writeln!(
+        f,
+        "suggestion: split the step circuit over {steps} steps for a commitment key of {ck_size} generators"
+      ),
+    }
+  }
+}
+
+/// Analyzes the shape of the primary augmented circuit of a step circuit, see `ShapeAdvice`
+pub struct ShapeAdvisor<E1, E2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  _p: PhantomData<(E1, E2)>,
+}
+
+impl<E1, E2> ShapeAdvisor<E1, E2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  /// Reports the size of the primary augmented circuit of `c`, with `default_ck_hint()`
+  pub fn suggest<C: StepCircuit<E1::Scalar>>(c: &C) -> Result<ShapeAdvice, NovaError> {
+    Self::suggest_with_hint(c, &*default_ck_hint())
+  }
+
+  /// Reports the size of the primary augmented circuit of `c`, for a commitment key set up with
+  /// `ck_hint` (see `PublicParams::setup`).
+  ///
+  /// A circuit that exceeds the previous power of two by at most an eighth of it is suggested to
+  /// be trimmed. Otherwise, the smallest split into at most `MAX_SPLIT` steps that halves the
+  /// commitment key is suggested, assuming the size of the step circuit divides evenly.
+  pub fn suggest_with_hint<C: StepCircuit<E1::Scalar>>(
+    c: &C,
+    ck_hint: &CommitmentKeyHint<E1>,
+  ) -> Result<ShapeAdvice, NovaError> {
+    let (shape, _) = PublicParams::<E1, E2, C>::synthesize_shapes(c)?;
+    let (step_num_cons, step_num_vars) = step_size::<E1, C>(c)?;
+
+    let ck_size = shape.commitment_key_size(ck_hint);
+    let target = ck_size.next_power_of_two();
+
+    // the augmented circuit without the step circuit, which every step pays for
+    let overhead_cons = shape.num_cons.saturating_sub(step_num_cons);
+    let overhead_vars = shape.num_vars.saturating_sub(step_num_vars);
+    let split_ck_size = |steps: usize| {
+      let cons = overhead_cons + step_num_cons.div_ceil(steps);
+      let vars = overhead_vars + step_num_vars.div_ceil(steps);
+      commitment_key_size(&shape, cons, vars, ck_hint).next_power_of_two()
+    };
+
+    let prev = target / 2;
+    let suggestion = if ck_size == target || prev == 0 {
+      ShapeSuggestion::Keep
+    } else if ck_size - prev <= prev / 8 {
+      ShapeSuggestion::Trim {
+        excess: ck_size - prev,
+      }
+    } else {
+      (2..=MAX_SPLIT)
+        .map(|steps| (steps, split_ck_size(steps)))
+        .find(|(_, size)| *size < target)
+        .map_or(ShapeSuggestion::Keep, |(steps, ck_size)| {
+          ShapeSuggestion::Split { steps, ck_size }
+        })
+    };
+
+    Ok(ShapeAdvice {
+      num_cons: shape.num_cons,
+      num_vars: shape.num_vars,
+      step_num_cons,
+      step_num_vars,
+      ck_size,
+      target,
+      headroom: target - ck_size,
+      suggestion,
+    })
+  }
+}
+
+/// Returns the number of constraints and variables of the step circuit `c` on its own
+fn step_size<E: Engine, C: StepCircuit<E::Scalar>>(c: &C) -> Result<(usize, usize), NovaError> {
+  let mut cs: ShapeCS<E> = ShapeCS::new();
+  let z = (0..c.arity())
+    .map(|i| AllocatedNum::alloc(cs.namespace(|| format!("z{i}")), || Ok(E::Scalar::ZERO)))
+    .collect::<Result<Vec<_>, _>>()?;
+  c.synthesize(&mut cs, &z)?;
+  Ok((cs.num_constraints(), cs.num_aux() - z.len()))
+}
+
+/// Returns the commitment key size of a shape like `shape` with `num_cons` constraints and
+/// `num_vars` variables, assuming that the floor of `ck_hint` scales with the size of the shape
+fn commitment_key_size<E: Engine>(
+  shape: &R1CSShape<E>,
+  num_cons: usize,
+  num_vars: usize,
+  ck_hint: &CommitmentKeyHint<E>,
+) -> usize {
+  let size = num_cons.max(num_vars);
+  let shape_size = shape.num_cons.max(shape.num_vars).max(1);
+  let floor = (ck_hint(shape) as u128 * size as u128 / shape_size as u128) as usize;
+  size.max(floor)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::SynthesisError,
+    provider::{PallasEngine, VestaEngine},
+    traits::circuit::TrivialCircuit,
+  };
+  use ff::PrimeField;
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+
+  /// A step circuit that squares its input `n` times
+  #[derive(Clone, Debug)]
+  struct SquaringCircuit {
+    n: usize,
+  }
+
+  impl<F: PrimeField> StepCircuit<F> for SquaringCircuit {
+    fn arity(&self) -> usize {
+      1
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      let mut x = z[0].clone();
+      for i in 0..self.n {
+        x = x.square(cs.namespace(|| format!("square {i}")))?;
+      }
+      Ok(vec![x])
+    }
+  }
+
+  #[test]
+  fn test_shape_advisor() {
+    let trivial =
+      ShapeAdvisor::<E1, E2>::suggest(&TrivialCircuit::<<E1 as Engine>::Scalar>::default())
+        .unwrap();
+    assert_eq!(trivial.step_num_cons, 0);
+    assert_eq!(trivial.step_num_vars, 0);
+
+    // each squaring adds a constraint and a variable
+    let overhead = trivial.ck_size;
+    let size = |n: usize| overhead + n;
+    let target = overhead.next_power_of_two();
+
+    // a circuit that fits its power of two exactly
+    let advice = ShapeAdvisor::<E1, E2>::suggest(&SquaringCircuit {
+      n: target - overhead,
+    })
+    .unwrap();
+    assert_eq!(advice.step_num_cons, target - overhead);
+    assert_eq!(advice.ck_size, target);
+    assert_eq!(advice.headroom, 0);
+    assert_eq!(advice.suggestion, ShapeSuggestion::Keep);
+
+    // a circuit just above the power of two can be trimmed
+    let n = target - overhead + 10;
+    let advice = ShapeAdvisor::<E1, E2>::suggest(&SquaringCircuit { n }).unwrap();
+    assert_eq!(advice.ck_size, size(n));
+    assert_eq!(advice.target, 2 * target);
+    assert_eq!(advice.headroom, 2 * target - size(n));
+    assert_eq!(advice.suggestion, ShapeSuggestion::Trim { excess: 10 });
+
+    // a circuit well above the power of two can be split
+    let n = 2 * target + target / 2 - overhead;
+    let advice = ShapeAdvisor::<E1, E2>::suggest(&SquaringCircuit { n }).unwrap();
+    let steps = (2..=MAX_SPLIT)
+      .find(|steps| size(n.div_ceil(*steps)) <= 2 * target)
+      .unwrap();
+    assert_eq!(advice.target, 4 * target);
+    assert_eq!(
+      advice.suggestion,
+      ShapeSuggestion::Split {
+        steps,
+        ck_size: size(n.div_ceil(steps)).next_power_of_two()
+      }
+    );
+    assert!(advice
+      .to_string()
+      .contains(&format!("split the step circuit over {steps} steps")));
+  }
+
+  #[test]
+  fn test_shape_advisor_ck_hint() {
+    // a floor on the commitment key that exceeds the circuit determines its size
+    let c = SquaringCircuit { n: 1 };
+    let advice = ShapeAdvisor::<E1, E2>::suggest_with_hint(&c, &|shape: &R1CSShape<E1>| {
+      4 * shape.num_cons.max(shape.num_vars)
+    })
+    .unwrap();
+    assert_eq!(advice.ck_size, 4 * advice.num_cons.max(advice.num_vars));
+    assert_eq!(advice.target, advice.ck_size.next_power_of_two());
+  }
+}
@@ -0,0 +1,172 @@
+//! This module defines `BackgroundCompression`, which compresses a snapshot of a `RecursiveSNARK`
+//! on a separate thread.
+//!
+//! `CompressedSNARK::prove` only reads the running instances and witnesses of a recursive SNARK,
+//! so a service that emits periodic compressed proofs can hand a snapshot of the chain to a
+//! background thread and keep calling `prove_step` on the original. The public parameters and the
+//! prover key are shared with the background thread through an `Arc`, since they are only read.
+use super::{CompressedSNARK, ProverKey, PublicParams, RecursiveSNARK};
+use crate::{
+  errors::NovaError,
+  traits::{circuit::StepCircuit, snark::RelaxedR1CSSNARKTrait, Engine},
+};
+use std::{
+  panic,
+  sync::Arc,
+  thread::{self, JoinHandle},
+};
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Returns a copy of the recursive SNARK at the current step, for compressing or verifying it
+  /// while `self` proves further steps.
+  ///
+  /// Unlike `fork`, the snapshot drops the witness cache, which compression and verification never
+  /// read. Returns `NovaError::WitnessWiped` if the witnesses were wiped.
+  pub fn snapshot(&self) -> Result<Self, NovaError> {
+    let mut snapshot = self.fork()?;
+    snapshot.witness_cache = None;
+    Ok(snapshot)
+  }
+}
+
+/// A `CompressedSNARK` being produced on a background thread, see
+/// `CompressedSNARK::prove_in_background`
+pub struct BackgroundCompression<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  num_steps: usize,
+  zn: Vec<E1::Scalar>,
+  handle: JoinHandle<Result<CompressedSNARK<E1, E2, C, S1, S2>, NovaError>>,
+}
+
+impl<E1, E2, C, S1, S2> BackgroundCompression<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// The number of steps of the snapshot being compressed, which `CompressedSNARK::verify`
+  /// expects
+  pub fn num_steps(&self) -> usize {
+    self.num_steps
+  }
+
+  /// The outputs of the snapshot being compressed
+  pub fn outputs(&self) -> &[E1::Scalar] {
+    &self.zn
+  }
+
+  /// Returns whether the background thread is done, so that `wait` returns without blocking
+  pub fn is_finished(&self) -> bool {
+    self.handle.is_finished()
+  }
+
+  /// Blocks until the background thread is done and returns the compressed SNARK.
+  ///
+  /// A panic on the background thread is resumed on the calling thread.
+  pub fn wait(self) -> Result<CompressedSNARK<E1, E2, C, S1, S2>, NovaError> {
+    self
+      .handle
+      .join()
+      .unwrap_or_else(|err| panic::resume_unwind(err))
+  }
+}
+
+impl<E1, E2, C, S1, S2> CompressedSNARK<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar> + 'static,
+  E2: Engine<Base = <E1 as Engine>::Scalar> + 'static,
+  C: StepCircuit<E1::Scalar> + 'static,
+  S1: RelaxedR1CSSNARKTrait<E1> + 'static,
+  S2: RelaxedR1CSSNARKTrait<E2> + 'static,
+{
+  /// Starts compressing a snapshot of `recursive_snark` (see `RecursiveSNARK::snapshot`) on a
+  /// background thread and returns a handle to the result.
+  ///
+  /// The snapshot is taken before this returns, so the caller may continue with `prove_step` on
+  /// `recursive_snark` right away. The compressed SNARK proves the steps up to the snapshot, and
+  /// verifies with `num_steps` of the handle.
+  pub fn prove_in_background(
+    pp: Arc<PublicParams<E1, E2, C>>,
+    pk: Arc<ProverKey<E1, E2, C, S1, S2>>,
+    recursive_snark: &RecursiveSNARK<E1, E2, C>,
+  ) -> Result<BackgroundCompression<E1, E2, C, S1, S2>, NovaError> {
+    let snapshot = recursive_snark.snapshot()?;
+    let num_steps = snapshot.num_steps();
+    let zn = snapshot.outputs().to_vec();
+
+    let handle = thread::spawn(move || Self::prove(&pp, &pk, &snapshot));
+
+    Ok(BackgroundCompression {
+      num_steps,
+      zn,
+      handle,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::{ipa_pc, PallasEngine, VestaEngine},
+    spartan::snark::RelaxedR1CSSNARK,
+    traits::{circuit::TrivialCircuit, snark::default_ck_hint},
+  };
+  use ff::Field;
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+  type C = TrivialCircuit<F>;
+  type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+
+  #[test]
+  fn test_prove_in_background() {
+    let circuit = C::default();
+    let pp = Arc::new(
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap(),
+    );
+    let (pk, vk) = CompressedSNARK::<_, _, _, S<E1>, S<E2>>::setup(&pp).unwrap();
+    let pk = Arc::new(pk);
+
+    let z0 = [F::ONE];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+
+    let background =
+      CompressedSNARK::prove_in_background(pp.clone(), pk.clone(), &recursive_snark).unwrap();
+    assert_eq!(background.num_steps(), 1);
+
+    // the chain continues while the snapshot is compressed
+    for _ in 0..2 {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    assert_eq!(recursive_snark.num_steps(), 3);
+    recursive_snark.verify(&pp, 3, &z0).unwrap();
+
+    let compressed_snark = background.wait().unwrap();
+    let zn = compressed_snark.verify(&vk, 1, &z0).unwrap();
+    assert_eq!(zn, z0.to_vec());
+    assert!(compressed_snark.verify(&vk, 3, &z0).is_err());
+
+    // wiped witnesses cannot be snapshotted
+    recursive_snark.wipe_witnesses();
+    assert!(matches!(
+      CompressedSNARK::prove_in_background(pp, pk, &recursive_snark),
+      Err(NovaError::WitnessWiped)
+    ));
+  }
+}
@@ -0,0 +1,321 @@
+//! This module implements `RecursiveSNARK::prove_step_batch`, which proves a step of many
+//! independent IVC chains that share public parameters at once.
+//!
+//! A step of a single chain computes four MSMs one after the other, since each of them depends on
+//! the previous one: the commitments to the cross-term of the secondary folding, to the witness of
+//! the primary circuit, to the cross-term of the primary folding, and to the witness of the
+//! secondary circuit. The MSMs of a small circuit are too short to occupy a GPU, so proving
+//! thousands of small chains one step at a time leaves the device mostly idle. Steps of different
+//! chains are independent, so `prove_step_batch` runs each of these phases for all the chains,
+//! and hands the MSMs of a phase to `CommitmentEngineTrait::batch_commit` in one call, which the
+//! provided commitment engines compute with the batch MSM entry point of the backend in use.
+use super::{
+  check_memory_limit,
+  circuit::NovaAugmentedCircuit,
+  nifs::{FoldInputs, NIFS},
+  PublicParams, RecursiveSNARK,
+};
+use crate::{
+  errors::NovaError,
+  frontend::{solver::SatisfyingAssignment, ConstraintSystem, SynthesisError},
+  gadgets::utils::scalar_as_base,
+  provider::timing::phase,
+  r1cs::{R1CSInstance, R1CSShape, R1CSWitness},
+  traits::{
+    circuit::{StepCircuit, TrivialCircuit},
+    commitment::CommitmentEngineTrait,
+    Engine,
+  },
+  CommitmentKey,
+};
+use ff::Field;
+use rand_core::OsRng;
+use rayon::prelude::*;
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Executes a step of the incremental computation of every chain in `snarks`, where the step of
+  /// `snarks[j]` runs `circuits[j]`, and commits to the vectors of all the chains in one batch per
+  /// phase of the step (see the module documentation).
+  ///
+  /// This produces the same chains as calling `prove_step` on each of them. The steps either all
+  /// succeed or leave every chain unchanged. Chains that have not yet executed their first step,
+  /// which `RecursiveSNARK::new` already synthesized, only advance their step count, as with
+  /// `prove_step`. Witness caches are used, but commitments are not streamed and constant segments
+  /// are committed to as part of the witness.
+  pub fn prove_step_batch(
+    pp: &PublicParams<E1, E2, C>,
+    snarks: &mut [Self],
+    circuits: &[C],
+  ) -> Result<(), NovaError> {
+    let _phase = phase!("prove_step_batch");
+    if snarks.len() != circuits.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    if snarks.iter().any(|snark| snark.witnesses_wiped) {
+      return Err(NovaError::WitnessWiped);
+    }
+
+    // first steps were already done in the constructors
+    let mut chains = snarks
+      .iter_mut()
+      .zip(circuits)
+      .filter(|(snark, _)| snark.i > 0)
+      .collect::<Vec<_>>();
+    for (snark, _) in &chains {
+      check_memory_limit(pp.memory_estimate().prove_step(), snark.memory_limit)?;
+    }
+    let pp_digest = pp.digest();
+
+    // fold the secondary circuits' instances
+    let folds = chains
+      .iter()
+      .map(|(snark, _)| {
+        (
+          &snark.r_U_secondary,
+          &snark.r_W_secondary,
+          &snark.l_u_secondary,
+          &snark.l_w_secondary,
+        )
+      })
+      .collect::<Vec<FoldInputs<'_, E2>>>();
+    let folded_secondary = NIFS::prove_batch(
+      &pp.ck_secondary,
+      &pp.ro_consts_secondary,
+      &scalar_as_base::<E1>(pp_digest),
+      &pp.r1cs_shape_secondary,
+      &folds,
+    )?;
+
+    // synthesize the primary circuits, and commit to their witnesses
+    let r_next_primary = random_scalars::<E1>(chains.len());
+    let synthesized_primary = chains
+      .par_iter_mut()
+      .zip(&folded_secondary)
+      .zip(&r_next_primary)
+      .map(|(((snark, c), (nifs_secondary, _)), r_next)| {
+        let inputs = snark.inputs_primary(pp_digest, nifs_secondary, *r_next);
+        let circuit: NovaAugmentedCircuit<'_, E2, C> =
+          NovaAugmentedCircuit::new(true, Some(inputs), *c, pp.ro_consts_circuit_primary.clone())
+            .with_witness_cache(snark.witness_cache.as_mut());
+        let mut cs = SatisfyingAssignment::<E1>::new();
+        let zi = circuit
+          .synthesize(&mut cs)?
+          .iter()
+          .map(|v| v.get_value().ok_or(SynthesisError::AssignmentMissing))
+          .collect::<Result<Vec<_>, _>>()?;
+        Ok((cs, zi))
+      })
+      .collect::<Result<Vec<_>, NovaError>>()?;
+    let (cs_primary, zi_primary): (Vec<_>, Vec<_>) = synthesized_primary.into_iter().unzip();
+    let committed_primary =
+      batch_instances_and_witnesses(&pp.r1cs_shape_primary, &pp.ck_primary, cs_primary)?;
+
+    // fold the primary circuits' instances
+    let folds = chains
+      .iter()
+      .zip(&committed_primary)
+      .map(|((snark, _), (l_u_primary, l_w_primary))| {
+        (
+          &snark.r_U_primary,
+          &snark.r_W_primary,
+          l_u_primary,
+          l_w_primary,
+        )
+      })
+      .collect::<Vec<FoldInputs<'_, E1>>>();
+    let folded_primary = NIFS::prove_batch(
+      &pp.ck_primary,
+      &pp.ro_consts_primary,
+      &pp_digest,
+      &pp.r1cs_shape_primary,
+      &folds,
+    )?;
+
+    // synthesize the secondary circuits, and commit to their witnesses
+    let r_next_secondary = random_scalars::<E2>(chains.len());
+    let cs_secondary = chains
+      .par_iter()
+      .zip(&committed_primary)
+      .zip(&folded_primary)
+      .zip(&r_next_secondary)
+      .map(
+        |((((snark, _), (l_u_primary, _)), (nifs_primary, _)), r_next)| {
+          let inputs =
+            snark.inputs_secondary(pp_digest, l_u_primary.clone(), nifs_primary, *r_next);
+          let tc = TrivialCircuit::<E2::Scalar>::default();
+          let circuit: NovaAugmentedCircuit<'_, E1, _> = NovaAugmentedCircuit::new(
+            false,
+            Some(inputs),
+            &tc,
+            pp.ro_consts_circuit_secondary.clone(),
+          );
+          let mut cs = SatisfyingAssignment::<E2>::new();
+          circuit.synthesize(&mut cs).map_err(|_e| NovaError::UnSat {
+            reason: "Unable to generate a satisfying witness on the secondary curve".to_string(),
+          })?;
+          Ok(cs)
+        },
+      )
+      .collect::<Result<Vec<_>, NovaError>>()?;
+    let committed_secondary =
+      batch_instances_and_witnesses(&pp.r1cs_shape_secondary, &pp.ck_secondary, cs_secondary)?;
+
+    // every step succeeded, so update the running instances and witnesses of all the chains
+    let steps = committed_primary
+      .into_iter()
+      .zip(committed_secondary)
+      .zip(folded_primary.into_iter().zip(folded_secondary))
+      .zip(zi_primary)
+      .zip(r_next_primary.into_iter().zip(r_next_secondary));
+    for ((snark, _), step) in chains.iter_mut().zip(steps) {
+      let ((((l_primary, l_secondary), (folded_primary, folded_secondary)), zi), r_next) = step;
+      let (_l_u_primary, _l_w_primary) = l_primary;
+      let (_, (r_U_primary, r_W_primary)) = folded_primary;
+      let (_, (r_U_secondary, r_W_secondary)) = folded_secondary;
+
+      // wipe the witnesses that are superseded below
+      #[cfg(feature = "zeroize")]
+      {
+        let mut l_w_primary = _l_w_primary;
+        l_w_primary.wipe();
+        snark.l_w_secondary.wipe();
+        snark.r_W_primary.wipe();
+        snark.r_W_secondary.wipe();
+      }
+
+      snark.zi = zi;
+      (snark.l_u_secondary, snark.l_w_secondary) = l_secondary;
+      snark.r_U_primary = r_U_primary;
+      snark.r_W_primary = r_W_primary;
+      snark.r_U_secondary = r_U_secondary;
+      snark.r_W_secondary = r_W_secondary;
+      (snark.ri_primary, snark.ri_secondary) = r_next;
+      snark.i += 1;
+    }
+    drop(chains);
+
+    for snark in snarks.iter_mut().filter(|snark| snark.i == 0) {
+      snark.i = 1;
+    }
+
+    Ok(())
+  }
+}
+
+/// Samples `n` random scalars
+fn random_scalars<E: Engine>(n: usize) -> Vec<E::Scalar> {
+  (0..n).map(|_| E::Scalar::random(&mut OsRng)).collect()
+}
+
+/// Returns an instance and witness for `shape` from each of the assignments in `cs`, committing to
+/// all the witnesses in one call to `CommitmentEngineTrait::batch_commit`
+fn batch_instances_and_witnesses<E: Engine>(
+  shape: &R1CSShape<E>,
+  ck: &CommitmentKey<E>,
+  cs: Vec<SatisfyingAssignment<E>>,
+) -> Result<Vec<(R1CSInstance<E>, R1CSWitness<E>)>, NovaError> {
+  let mut Ws = Vec::with_capacity(cs.len());
+  let mut Xs = Vec::with_capacity(cs.len());
+  for cs in cs {
+    let mut W = cs.aux_assignment;
+    if W.len() > shape.num_vars {
+      return Err(NovaError::InvalidWitnessLength);
+    }
+    W.resize(shape.num_vars, E::Scalar::ZERO);
+    Ws.push(W);
+    Xs.push(cs.input_assignment[1..].to_vec());
+  }
+  let r_Ws = random_scalars::<E>(Ws.len());
+  let comm_Ws = E::CE::batch_commit(ck, &Ws, &r_Ws);
+
+  Ws.into_iter()
+    .zip(r_Ws)
+    .zip(comm_Ws.iter().zip(&Xs))
+    .map(|((W, r_W), (comm_W, X))| {
+      let instance = R1CSInstance::new(shape, comm_W, X)?;
+      Ok((instance, R1CSWitness { W, r_W }))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+    nova::{PublicParams, RecursiveSNARK},
+    provider::{Bn256EngineKZG, GrumpkinEngine, PallasEngine, VestaEngine},
+    traits::{circuit::StepCircuit, snark::default_ck_hint, Engine},
+  };
+  use ff::{Field, PrimeField};
+
+  /// Adds a per-chain constant to the state at every step
+  #[derive(Clone, Debug)]
+  struct AddCircuit<F: PrimeField> {
+    c: F,
+  }
+
+  impl<F: PrimeField> StepCircuit<F> for AddCircuit<F> {
+    fn arity(&self) -> usize {
+      1
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      let c = AllocatedNum::alloc(cs.namespace(|| "c"), || Ok(self.c))?;
+      let y = z[0].add(cs.namespace(|| "z + c"), &c)?;
+      Ok(vec![y])
+    }
+  }
+
+  fn test_prove_step_batch_with<E1, E2>()
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+  {
+    let num_chains = 5;
+    let num_steps = 4;
+    let circuits = (0..num_chains)
+      .map(|j| AddCircuit {
+        c: E1::Scalar::from(j as u64 + 1),
+      })
+      .collect::<Vec<_>>();
+    let pp =
+      PublicParams::<E1, E2, _>::setup(&circuits[0], &*default_ck_hint(), &*default_ck_hint())
+        .unwrap();
+    let z0 = [E1::Scalar::ZERO];
+    let mut snarks = circuits
+      .iter()
+      .map(|c| RecursiveSNARK::new(&pp, c, &z0).unwrap())
+      .collect::<Vec<_>>();
+
+    for _ in 0..num_steps {
+      RecursiveSNARK::prove_step_batch(&pp, &mut snarks, &circuits).unwrap();
+    }
+    for (j, snark) in snarks.iter().enumerate() {
+      let zn = snark.verify(&pp, num_steps, &z0).unwrap();
+      assert_eq!(zn, vec![E1::Scalar::from((num_steps * (j + 1)) as u64)]);
+    }
+
+    // batched steps can be interleaved with single steps
+    snarks[0].prove_step(&pp, &circuits[0]).unwrap();
+    assert!(snarks[0].verify(&pp, num_steps + 1, &z0).is_ok());
+
+    // a mismatched batch leaves every chain unchanged
+    assert!(RecursiveSNARK::prove_step_batch(&pp, &mut snarks, &circuits[1..]).is_err());
+    assert!(snarks[1].verify(&pp, num_steps, &z0).is_ok());
+  }
+
+  #[test]
+  fn test_prove_step_batch() {
+    test_prove_step_batch_with::<PallasEngine, VestaEngine>();
+    test_prove_step_batch_with::<Bn256EngineKZG, GrumpkinEngine>();
+  }
+}
@@ -0,0 +1,379 @@
+//! This module defines `PublicParamsBuilder`, which configures and validates the setup of `PublicParams`.
+//!
+//! Setting up public parameters is dominated by generating (or loading) commitment keys. The builder
+//! checks every option it is given, as well as the shape of the step circuit, before that work
+//! starts, and reports progress through an optional callback while it runs.
+use super::PublicParams;
+use crate::{
+  errors::NovaError,
+  provider::key_file::check_key_file,
+  r1cs::CommitmentKeyHint,
+  traits::{
+    circuit::StepCircuit,
+    commitment::{BlindingGenerator, CommitmentEngineTrait},
+    snark::{default_ck_hint, RelaxedR1CSSNARKTrait},
+    Engine,
+  },
+  CommitmentKey,
+};
+use std::{
+  fs::File,
+  io::BufReader,
+  path::{Path, PathBuf},
+};
+
+/// A stage of setting up public parameters, reported to the progress callback when it starts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SetupStage {
+  /// The shapes of the primary and secondary augmented circuits are being synthesized
+  SynthesizingShapes,
+  /// The commitment key of the primary circuit, with `size` generators, is being generated or loaded
+  CommitmentKeyPrimary {
+    /// The number of generators required
+    size: usize,
+  },
+  /// The commitment key of the secondary circuit, with `size` generators, is being generated or loaded
+  CommitmentKeySecondary {
+    /// The number of generators required
+    size: usize,
+  },
+  /// The digest of the public parameters is being computed
+  Digest,
+}
+
+/// A builder for `PublicParams`
+pub struct PublicParamsBuilder<'a, E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  circuit: &'a C,
+  arity: Option<usize>,
+  ck_hint1: Box<CommitmentKeyHint<E1>>,
+  ck_hint2: Box<CommitmentKeyHint<E2>>,
+  key_file_primary: Option<PathBuf>,
+  key_file_secondary: Option<PathBuf>,
+  blinding_primary: BlindingGenerator<E1>,
+  blinding_secondary: BlindingGenerator<E2>,
+  progress: Option<Box<dyn FnMut(SetupStage) + 'a>>,
+}
+
+impl<E1, E2, C> PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Returns a builder that sets up public parameters for the circuit `c`
+  pub fn builder(c: &C) -> PublicParamsBuilder<'_, E1, E2, C> {
+    PublicParamsBuilder::new(c)
+  }
+}
+
+impl<'a, E1, E2, C> PublicParamsBuilder<'a, E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Creates a builder for the circuit `c` that, unless configured otherwise, behaves like
+  /// `PublicParams::setup` with `default_ck_hint()` for both circuits
+  pub fn new(c: &'a C) -> Self {
+    Self {
+      circuit: c,
+      arity: None,
+      ck_hint1: default_ck_hint(),
+      ck_hint2: default_ck_hint(),
+      key_file_primary: None,
+      key_file_secondary: None,
+      blinding_primary: BlindingGenerator::default(),
+      blinding_secondary: BlindingGenerator::default(),
+      progress: None,
+    }
+  }
+
+  /// Requires the circuit to have the provided arity
+  pub fn arity(mut self, arity: usize) -> Self {
+    self.arity = Some(arity);
+    self
+  }
+
+  /// Sets the commitment key hint for the primary circuit
+  pub fn ck_hint_primary(mut self, ck_hint: Box<CommitmentKeyHint<E1>>) -> Self {
+    self.ck_hint1 = ck_hint;
+    self
+  }
+
+  /// Sets the commitment key hint for the secondary circuit
+  pub fn ck_hint_secondary(mut self, ck_hint: Box<CommitmentKeyHint<E2>>) -> Self {
+    self.ck_hint2 = ck_hint;
+    self
+  }
+
+  /// Sets the commitment key hints to those required by the compressing SNARKs `S1` and `S2`
+  pub fn for_snarks<S1, S2>(self) -> Self
+  where
+    S1: RelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<E2>,
+  {
+    self
+      .ck_hint_primary(S1::ck_floor())
+      .ck_hint_secondary(S2::ck_floor())
+  }
+
+  /// Loads the commitment key of the primary circuit from a key file (see `CommitmentEngineTrait::load_setup`)
+  /// instead of generating it. Key files named by `provider::key_file::key_file_name` must be
+  /// named for the primary engine.
+  pub fn key_file_primary(mut self, path: impl Into<PathBuf>) -> Self {
+    self.key_file_primary = Some(path.into());
+    self
+  }
+
+  /// Loads the commitment key of the secondary circuit from a key file (see `CommitmentEngineTrait::load_setup`)
+  /// instead of generating it. Key files named by `provider::key_file::key_file_name` must be
+  /// named for the secondary engine.
+  pub fn key_file_secondary(mut self, path: impl Into<PathBuf>) -> Self {
+    self.key_file_secondary = Some(path.into());
+    self
+  }
+
+  /// Sets where the blinding generator of the primary commitment key comes from, e.g., to commit
+  /// with the same blinding generator as an external system. It cannot be combined with a key file.
+  pub fn blinding_generator_primary(mut self, blinding: BlindingGenerator<E1>) -> Self {
+    self.blinding_primary = blinding;
+    self
+  }
+
+  /// Sets where the blinding generator of the secondary commitment key comes from. It cannot be
+  /// combined with a key file.
+  pub fn blinding_generator_secondary(mut self, blinding: BlindingGenerator<E2>) -> Self {
+    self.blinding_secondary = blinding;
+    self
+  }
+
+  /// Registers a callback that is invoked as each stage of the setup starts
+  pub fn on_progress(mut self, progress: impl FnMut(SetupStage) + 'a) -> Self {
+    self.progress = Some(Box::new(progress));
+    self
+  }
+
+  /// Checks the options of the builder without synthesizing the circuit or generating keys
+  pub fn validate(&self) -> Result<(), NovaError> {
+    if let Some(arity) = self.arity {
+      if self.circuit.arity() != arity {
+        return Err(NovaError::SetupError {
+          reason: format!(
+            "the circuit has arity {}, but arity {arity} is required",
+            self.circuit.arity()
+          ),
+        });
+      }
+    }
+
+    if (self.key_file_primary.is_some() && self.blinding_primary != BlindingGenerator::FromKeyLabel)
+      || (self.key_file_secondary.is_some()
+        && self.blinding_secondary != BlindingGenerator::FromKeyLabel)
+    {
+      return Err(NovaError::SetupError {
+        reason:
+          "the blinding generator of a commitment key loaded from a key file cannot be chosen"
+            .to_string(),
+      });
+    }
+
+    for path in [&self.key_file_primary, &self.key_file_secondary]
+      .into_iter()
+      .flatten()
+    {
+      if !path.is_file() {
+        return Err(NovaError::SetupError {
+          reason: format!("the key file {} does not exist", path.display()),
+        });
+      }
+    }
+    if let Some(path) = &self.key_file_primary {
+      check_key_file::<E1>(path, 0)?;
+    }
+    if let Some(path) = &self.key_file_secondary {
+      check_key_file::<E2>(path, 0)?;
+    }
+
+    Ok(())
+  }
+
+  /// Validates the options and the circuit, and then sets up the public parameters
+  pub fn build(mut self) -> Result<PublicParams<E1, E2, C>, NovaError> {
+    self.validate()?;
+
+    self.report(SetupStage::SynthesizingShapes);
+    let (r1cs_shape_primary, r1cs_shape_secondary) =
+      PublicParams::<E1, E2, C>::synthesize_shapes(self.circuit)?;
+
+    let size = r1cs_shape_primary.commitment_key_size(&*self.ck_hint1);
+    self.report(SetupStage::CommitmentKeyPrimary { size });
+    let ck_primary = commitment_key::<E1>(size, &self.key_file_primary, &self.blinding_primary)?;
+
+    let size = r1cs_shape_secondary.commitment_key_size(&*self.ck_hint2);
+    self.report(SetupStage::CommitmentKeySecondary { size });
+    let ck_secondary =
+      commitment_key::<E2>(size, &self.key_file_secondary, &self.blinding_secondary)?;
+
+    self.report(SetupStage::Digest);
+    Ok(PublicParams::from_parts(
+      self.circuit.arity(),
+      r1cs_shape_primary,
+      ck_primary,
+      r1cs_shape_secondary,
+      ck_secondary,
+    ))
+  }
+
+  fn report(&mut self, stage: SetupStage) {
+    if let Some(progress) = self.progress.as_mut() {
+      progress(stage);
+    }
+  }
+}
+
+/// Loads a commitment key with `size` generators from `key_file` if provided, and generates one
+/// with the blinding generator `blinding` otherwise
+fn commitment_key<E: Engine>(
+  size: usize,
+  key_file: &Option<PathBuf>,
+  blinding: &BlindingGenerator<E>,
+) -> Result<CommitmentKey<E>, NovaError> {
+  let Some(path) = key_file else {
+    return E::CE::setup_with_blinding(b"ck", size, blinding);
+  };
+
+  let key_file_error = |path: &Path, e: &dyn std::fmt::Display| NovaError::SetupError {
+    reason: format!("failed to load the key file {}: {e}", path.display()),
+  };
+  check_key_file::<E>(path, size)?;
+  let file = File::open(path).map_err(|e| key_file_error(path, &e))?;
+  E::CE::load_setup(&mut BufReader::new(file), b"ck", size).map_err(|e| key_file_error(path, &e))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+    provider::{key_file::key_file_name, PallasEngine, VestaEngine},
+    traits::circuit::TrivialCircuit,
+  };
+  use ff::PrimeField;
+  use std::{cell::RefCell, io::BufWriter};
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+
+  #[derive(Clone, Debug, Default)]
+  struct WrongOutputCircuit;
+
+  impl<F: PrimeField> StepCircuit<F> for WrongOutputCircuit {
+    fn arity(&self) -> usize {
+      2
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      _cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      Ok(z[..1].to_vec())
+    }
+  }
+
+  #[test]
+  fn test_builder() {
+    let circuit = TrivialCircuit::<F>::default();
+    let pp = PublicParams::<E1, E2, TrivialCircuit<F>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    // the builder reports every stage and produces the same parameters as `setup`
+    let stages = RefCell::new(Vec::new());
+    let pp_built = PublicParams::<E1, E2, _>::builder(&circuit)
+      .arity(1)
+      .on_progress(|stage| stages.borrow_mut().push(stage))
+      .build()
+      .unwrap();
+    assert_eq!(pp_built.digest(), pp.digest());
+    let stages = stages.into_inner();
+    assert_eq!(stages.len(), 4);
+    assert_eq!(stages[0], SetupStage::SynthesizingShapes);
+    assert_eq!(
+      stages[1],
+      SetupStage::CommitmentKeyPrimary {
+        size: pp
+          .r1cs_shape_primary
+          .num_cons
+          .max(pp.r1cs_shape_primary.num_vars)
+      }
+    );
+
+    // commitment keys loaded from key files yield the same parameters
+    let path_primary = std::env::temp_dir().join("nova_builder_test_primary.keys");
+    let path_secondary = std::env::temp_dir().join("nova_builder_test_secondary.keys");
+    pp.ck_primary
+      .save_to(&mut BufWriter::new(File::create(&path_primary).unwrap()))
+      .unwrap();
+    pp.ck_secondary
+      .save_to(&mut BufWriter::new(File::create(&path_secondary).unwrap()))
+      .unwrap();
+    let pp_loaded = PublicParams::<E1, E2, _>::builder(&circuit)
+      .key_file_primary(&path_primary)
+      .key_file_secondary(&path_secondary)
+      .build()
+      .unwrap();
+    assert_eq!(pp_loaded.digest(), pp.digest());
+  }
+
+  #[test]
+  fn test_builder_validation() {
+    let circuit = TrivialCircuit::<F>::default();
+    let fail = |builder: PublicParamsBuilder<'_, E1, E2, TrivialCircuit<F>>| {
+      let mut started = false;
+      let res = builder.on_progress(|_| started = true).build();
+      assert!(matches!(res, Err(NovaError::SetupError { .. })));
+      // invalid options are rejected before any work starts
+      assert!(!started);
+    };
+
+    fail(PublicParams::builder(&circuit).arity(2));
+    fail(PublicParams::builder(&circuit).key_file_primary("/nonexistent/nova.keys"));
+
+    // key files named for the other engine are rejected
+    let path = std::env::temp_dir().join(key_file_name::<E2>(1));
+    File::create(&path).unwrap();
+    fail(PublicParams::builder(&circuit).key_file_primary(&path));
+    std::fs::remove_file(&path).unwrap();
+
+    // the blinding generator of a key loaded from a key file cannot be chosen
+    let path = std::env::temp_dir().join(key_file_name::<E1>(1));
+    File::create(&path).unwrap();
+    fail(
+      PublicParams::builder(&circuit)
+        .key_file_primary(&path)
+        .blinding_generator_primary(BlindingGenerator::Label(b"blinding")),
+    );
+    std::fs::remove_file(&path).unwrap();
+
+    // a step circuit whose output does not match its arity is rejected before keys are generated
+    let res = PublicParams::<E1, E2, _>::builder(&WrongOutputCircuit).build();
+    assert!(matches!(res, Err(NovaError::SynthesisError { .. })));
+    // while `setup` reports it as having an invalid IO
+    let res = PublicParams::<E1, E2, _>::setup(
+      &WrongOutputCircuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    );
+    assert!(matches!(res, Err(NovaError::InvalidStepCircuitIO)));
+  }
+}
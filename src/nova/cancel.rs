@@ -0,0 +1,222 @@
+//! This module defines `CancellationToken`, which interrupts proving cooperatively.
+//!
+//! An orchestrator that preempts long proving jobs hands a token to
+//! `RecursiveSNARK::prove_step_cancellable`, `RecursiveSNARK::prove_steps`, or
+//! `CompressedSNARK::prove_cancellable`, and cancels it from another thread, or gives it a deadline.
+//! The provers check the token between the phases of their work (synthesizing and committing to a
+//! witness, folding, or producing a SNARK), so a phase that has started runs to completion. An
+//! interrupted step returns `NovaError::Interrupted` before it updates the recursive SNARK, which is
+//! therefore left at the previous step, and proving can resume from it with a fresh token.
+use super::{CompressedSNARK, ProverKey, PublicParams, RecursiveSNARK, StepContext};
+use crate::{
+  errors::NovaError,
+  traits::{
+    circuit::StepCircuit,
+    snark::{MemoryBudget, RelaxedR1CSSNARKTrait},
+    Engine,
+  },
+};
+use std::{
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  time::{Duration, Instant},
+};
+
+/// A token that interrupts the provers it is handed to once it is cancelled or its deadline passes.
+/// Clones of a token share its cancellation, so a clone can be cancelled from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+  cancelled: Arc<AtomicBool>,
+  deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+  /// Creates a token that interrupts provers only once it is cancelled
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a token that also interrupts provers once `deadline` passes
+  pub fn with_deadline(deadline: Instant) -> Self {
+    Self {
+      cancelled: Arc::default(),
+      deadline: Some(deadline),
+    }
+  }
+
+  /// Creates a token that also interrupts provers once `timeout` elapses from now
+  pub fn with_timeout(timeout: Duration) -> Self {
+    Self::with_deadline(Instant::now() + timeout)
+  }
+
+  /// Cancels the token and its clones
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::Relaxed);
+  }
+
+  /// Returns whether the token was cancelled or its deadline passed
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::Relaxed)
+      || self
+        .deadline
+        .is_some_and(|deadline| Instant::now() >= deadline)
+  }
+
+  /// Returns `NovaError::Interrupted` if the token was cancelled or its deadline passed
+  pub(crate) fn check(&self) -> Result<(), NovaError> {
+    if self.is_cancelled() {
+      Err(NovaError::Interrupted)
+    } else {
+      Ok(())
+    }
+  }
+}
+
+/// Returns `NovaError::Interrupted` if `token` is set and was cancelled
+pub(crate) fn check_cancelled(token: Option<&CancellationToken>) -> Result<(), NovaError> {
+  token.map_or(Ok(()), CancellationToken::check)
+}
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Executes a step of the incremental computation like `prove_step`, unless `token` interrupts
+  /// it, in which case it returns `NovaError::Interrupted` and leaves the recursive SNARK unchanged
+  pub fn prove_step_cancellable(
+    &mut self,
+    pp: &PublicParams<E1, E2, C>,
+    c: &C,
+    token: &CancellationToken,
+  ) -> Result<(), NovaError> {
+    self.prove_step_with(pp, c, &StepContext::new(false), None, Some(token))
+  }
+
+  /// Executes a step of the incremental computation for each of `circuits`, until they run out or
+  /// `token` interrupts a step, and returns the number of steps executed.
+  ///
+  /// An interruption is not an error: the recursive SNARK holds the steps executed so far, and
+  /// proving resumes with the circuits after the first ones, as many as were returned. Bounding
+  /// `circuits`, e.g., with `Iterator::take`, bounds the number of steps.
+  pub fn prove_steps<'a>(
+    &mut self,
+    pp: &PublicParams<E1, E2, C>,
+    circuits: impl IntoIterator<Item = &'a C>,
+    token: &CancellationToken,
+  ) -> Result<usize, NovaError>
+  where
+    C: 'a,
+  {
+    let ctx = StepContext::new(false);
+    let mut num_steps = 0;
+    for c in circuits {
+      match self.prove_step_with(pp, c, &ctx, None, Some(token)) {
+        Ok(()) => num_steps += 1,
+        Err(NovaError::Interrupted) => break,
+        Err(e) => return Err(e),
+      }
+    }
+    Ok(num_steps)
+  }
+}
+
+impl<E1, E2, C, S1, S2> CompressedSNARK<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Creates a new `CompressedSNARK` like `prove`, unless `token` interrupts it, in which case it
+  /// returns `NovaError::Interrupted`
+  pub fn prove_cancellable(
+    pp: &PublicParams<E1, E2, C>,
+    pk: &ProverKey<E1, E2, C, S1, S2>,
+    recursive_snark: &RecursiveSNARK<E1, E2, C>,
+    token: &CancellationToken,
+  ) -> Result<Self, NovaError> {
+    Self::prove_with(
+      pp,
+      pk,
+      recursive_snark,
+      MemoryBudget::Unlimited,
+      Some(token),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::{ipa_pc, PallasEngine, VestaEngine},
+    spartan::snark::RelaxedR1CSSNARK,
+    traits::{circuit::NonTrivialCircuit, snark::default_ck_hint},
+  };
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+  type C = NonTrivialCircuit<F>;
+  type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+
+  #[test]
+  fn test_cancellation() {
+    let circuit = C::new(4);
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+    let z0 = [F::from(2)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+
+    // a cancelled token, or one past its deadline, interrupts a step without changing the SNARK
+    let token = CancellationToken::new();
+    token.clone().cancel();
+    for token in [token, CancellationToken::with_timeout(Duration::ZERO)] {
+      assert_eq!(
+        recursive_snark.prove_step_cancellable(&pp, &circuit, &token),
+        Err(NovaError::Interrupted)
+      );
+      assert_eq!(recursive_snark.num_steps(), 0);
+    }
+
+    // steps stop once the token is cancelled, and resume from where they stopped
+    let token = CancellationToken::with_timeout(Duration::from_secs(3600));
+    let circuits = vec![circuit.clone(); 5];
+    let num_steps = recursive_snark
+      .prove_steps(
+        &pp,
+        circuits.iter().enumerate().map(|(i, c)| {
+          if i == 3 {
+            token.cancel();
+          }
+          c
+        }),
+        &token,
+      )
+      .unwrap();
+    assert_eq!(num_steps, 3);
+    assert_eq!(recursive_snark.num_steps(), 3);
+    let token = CancellationToken::new();
+    assert_eq!(
+      recursive_snark.prove_steps(&pp, &circuits[num_steps..], &token),
+      Ok(2)
+    );
+    assert!(recursive_snark.verify(&pp, 5, &z0).is_ok());
+
+    let (pk, vk) = CompressedSNARK::<_, _, _, S<E1>, S<E2>>::setup(&pp).unwrap();
+    let cancelled = CancellationToken::new();
+    cancelled.cancel();
+    assert!(matches!(
+      CompressedSNARK::prove_cancellable(&pp, &pk, &recursive_snark, &cancelled),
+      Err(NovaError::Interrupted)
+    ));
+    let compressed_snark =
+      CompressedSNARK::prove_cancellable(&pp, &pk, &recursive_snark, &token).unwrap();
+    assert!(compressed_snark.verify(&vk, 5, &z0).is_ok());
+  }
+}
@@ -17,7 +17,9 @@ use crate::{
   },
   r1cs::{R1CSInstance, RelaxedR1CSInstance},
   traits::{
-    circuit::StepCircuit, commitment::CommitmentTrait, Engine, ROCircuitTrait, ROConstantsCircuit,
+    circuit::{StepCircuit, WitnessSource},
+    commitment::CommitmentTrait,
+    Engine, ROCircuitTrait, ROConstantsCircuit,
   },
   Commitment,
 };
@@ -25,7 +27,9 @@ use ff::Field;
 use serde::{Deserialize, Serialize};
 
 mod r1cs;
+pub(crate) mod witness_cache;
 use r1cs::{AllocatedR1CSInstance, AllocatedRelaxedR1CSInstance};
+use witness_cache::WitnessCache;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
@@ -75,6 +79,9 @@ pub struct NovaAugmentedCircuit<'a, E: Engine, SC: StepCircuit<E::Base>> {
   ro_consts: ROConstantsCircuit<E>,
   inputs: Option<NovaAugmentedCircuitInputs<E>>,
   step_circuit: &'a SC, // The function that is applied for each step
+  witness_cache: Option<&'a mut WitnessCache<E::Base>>,
+  witness_source: Option<&'a dyn WitnessSource<E::Base>>,
+  step_offset: Option<&'a mut usize>,
 }
 
 impl<'a, E: Engine, SC: StepCircuit<E::Base>> NovaAugmentedCircuit<'a, E, SC> {
@@ -90,6 +97,110 @@ impl<'a, E: Engine, SC: StepCircuit<E::Base>> NovaAugmentedCircuit<'a, E, SC> {
       inputs,
       step_circuit,
       ro_consts,
+      witness_cache: None,
+      witness_source: None,
+      step_offset: None,
+    }
+  }
+
+  /// Consults and populates `cache` when generating the witness of the step circuit
+  pub fn with_witness_cache(mut self, cache: Option<&'a mut WitnessCache<E::Base>>) -> Self {
+    self.witness_cache = cache;
+    self
+  }
+
+  /// Takes the witness of the step circuit from `source` instead of synthesizing it
+  pub fn with_witness_source(mut self, source: Option<&'a dyn WitnessSource<E::Base>>) -> Self {
+    self.witness_source = source;
+    self
+  }
+
+  /// Records in `offset` the index of the first auxiliary variable of the step circuit in the
+  /// witness, when generating the witness
+  pub fn with_step_offset(mut self, offset: &'a mut usize) -> Self {
+    self.step_offset = Some(offset);
+    self
+  }
+
+  /// Allocates the assignment that `source` computes for the step circuit on input `z_input`
+  fn alloc_step_assignment<CS: ConstraintSystem<<E as Engine>::Base>>(
+    &self,
+    mut cs: CS,
+    source: &dyn WitnessSource<E::Base>,
+    z_input: &[AllocatedNum<E::Base>],
+  ) -> Result<Vec<AllocatedNum<E::Base>>, SynthesisError> {
+    let z = z_input
+      .iter()
+      .map(|z| z.get_value())
+      .collect::<Option<Vec<_>>>()
+      .ok_or(SynthesisError::AssignmentMissing)?;
+    let assignment = source.assignment(&z)?;
+    if assignment.outputs.len() != z.len()
+      || assignment
+        .outputs
+        .iter()
+        .any(|o| *o >= assignment.aux.len())
+    {
+      return Err(SynthesisError::IncompatibleLengthVector(
+        "outputs of the step assignment".to_string(),
+      ));
+    }
+
+    let nums = assignment
+      .aux
+      .iter()
+      .enumerate()
+      .map(|(j, v)| AllocatedNum::alloc(cs.namespace(|| format!("external aux {j}")), || Ok(*v)))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(
+      assignment
+        .outputs
+        .iter()
+        .map(|o| nums[*o].clone())
+        .collect(),
+    )
+  }
+
+  /// Synthesizes the step circuit, taking its witness from the witness source or replaying it
+  /// from the cache when possible
+  fn synthesize_step<CS: ConstraintSystem<<E as Engine>::Base>>(
+    &mut self,
+    mut cs: CS,
+    z_input: &[AllocatedNum<E::Base>],
+  ) -> Result<Vec<AllocatedNum<E::Base>>, SynthesisError> {
+    if let Some(offset) = self.step_offset.as_deref_mut() {
+      if cs.is_witness_generator() {
+        *offset = cs.aux_slice().len();
+      }
+    }
+
+    if let Some(source) = self.witness_source {
+      if cs.is_witness_generator() {
+        return self.alloc_step_assignment(cs, source, z_input);
+      }
+    }
+
+    let key = match (&self.witness_cache, &self.inputs) {
+      (Some(cache), Some(inputs)) if cs.is_witness_generator() => cache.key(
+        &inputs.pp_digest,
+        self.step_circuit.witness_cache_key(),
+        z_input,
+      ),
+      _ => None,
+    };
+
+    match (self.witness_cache.as_mut(), key) {
+      (Some(cache), Some(key)) => {
+        if let Some(z_next) = cache.replay(&mut cs, &key)? {
+          return Ok(z_next);
+        }
+        let aux_start = cs.aux_slice().len();
+        let input_start = cs.inputs_slice().len();
+        let z_next = self.step_circuit.synthesize(&mut cs, z_input)?;
+        cache.insert(&cs, key, aux_start, input_start, &z_next);
+        Ok(z_next)
+      }
+      _ => self.step_circuit.synthesize(&mut cs, z_input),
     }
   }
 
@@ -244,7 +355,7 @@ impl<'a, E: Engine, SC: StepCircuit<E::Base>> NovaAugmentedCircuit<'a, E, SC> {
 impl<E: Engine, SC: StepCircuit<E::Base>> NovaAugmentedCircuit<'_, E, SC> {
   /// synthesize circuit giving constraint system
   pub fn synthesize<CS: ConstraintSystem<<E as Engine>::Base>>(
-    self,
+    mut self,
     cs: &mut CS,
   ) -> Result<Vec<AllocatedNum<E::Base>>, SynthesisError> {
     let arity = self.step_circuit.arity();
@@ -326,9 +437,7 @@ impl<E: Engine, SC: StepCircuit<E::Base>> NovaAugmentedCircuit<'_, E, SC> {
       &Boolean::from(is_base_case),
     )?;
 
-    let z_next = self
-      .step_circuit
-      .synthesize(&mut cs.namespace(|| "F"), &z_input)?;
+    let z_next = self.synthesize_step(cs.namespace(|| "F"), &z_input)?;
 
     if z_next.len() != arity {
       return Err(SynthesisError::IncompatibleLengthVector(
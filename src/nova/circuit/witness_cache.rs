@@ -0,0 +1,144 @@
+//! This module implements a content-addressed cache for the witness of a step circuit.
+//!
+//! When the same step circuit is invoked on the same inputs (e.g., padding steps), the values it
+//! allocates are identical across steps. The cache stores these values keyed by a digest of the
+//! public parameters, the key returned by `StepCircuit::witness_cache_key`, and the step inputs,
+//! so that witness generation can replay them instead of re-running the circuit.
+use crate::{
+  frontend::{num::AllocatedNum, ConstraintSystem, Index, SynthesisError},
+  r1cs::wipe,
+};
+use ff::PrimeField;
+use sha3::{Digest, Sha3_256};
+use std::collections::HashMap;
+
+/// The values allocated by a step circuit, and which of them form the output of the step
+#[derive(Clone, Debug)]
+struct CachedStepWitness<F: PrimeField> {
+  aux: Vec<F>,
+  outputs: Vec<usize>, // offsets into `aux`
+}
+
+/// A bounded cache of step-circuit witnesses
+#[derive(Clone, Debug)]
+pub struct WitnessCache<F: PrimeField> {
+  max_entries: usize,
+  entries: HashMap<[u8; 32], CachedStepWitness<F>>,
+  hits: usize,
+  fixed_circuit: bool, // the cache only lives while a single circuit is being proven
+}
+
+impl<F: PrimeField> WitnessCache<F> {
+  /// Creates an empty cache that holds at most `max_entries` witnesses
+  pub fn new(max_entries: usize) -> Self {
+    Self {
+      max_entries,
+      entries: HashMap::new(),
+      hits: 0,
+      fixed_circuit: false,
+    }
+  }
+
+  /// Creates an empty cache that is only used while proving steps of a single circuit,
+  /// so circuits that do not provide a `witness_cache_key` can be cached as well
+  pub fn for_fixed_circuit(max_entries: usize) -> Self {
+    Self {
+      fixed_circuit: true,
+      ..Self::new(max_entries)
+    }
+  }
+
+  /// Returns the number of times a witness was replayed from the cache
+  pub fn hits(&self) -> usize {
+    self.hits
+  }
+
+  /// Returns the number of bytes held by the cached witnesses
+  pub fn memory_usage(&self) -> usize {
+    self
+      .entries
+      .values()
+      .map(|entry| {
+        entry.aux.len() * std::mem::size_of::<F>()
+          + entry.outputs.len() * std::mem::size_of::<usize>()
+      })
+      .sum()
+  }
+
+  /// Overwrites the cached witnesses with zeros and empties the cache
+  pub fn wipe(&mut self) {
+    for (_, mut entry) in self.entries.drain() {
+      wipe(&mut entry.aux);
+    }
+  }
+
+  /// Computes the cache key, or returns `None` if the circuit opted out or an input is unknown
+  pub fn key<S: PrimeField>(
+    &self,
+    pp_digest: &S,
+    circuit_key: Option<Vec<u8>>,
+    z: &[AllocatedNum<F>],
+  ) -> Option<[u8; 32]> {
+    let circuit_key = circuit_key.or_else(|| self.fixed_circuit.then(Vec::new))?;
+    let mut hasher = Sha3_256::new();
+    hasher.update(pp_digest.to_repr().as_ref());
+    hasher.update((circuit_key.len() as u64).to_le_bytes());
+    hasher.update(&circuit_key);
+    for z_j in z {
+      hasher.update(z_j.get_value()?.to_repr().as_ref());
+    }
+    Some(hasher.finalize().into())
+  }
+
+  /// Replays a cached witness into `cs`, returning the outputs of the step, if `key` is present
+  pub fn replay<CS: ConstraintSystem<F>>(
+    &mut self,
+    cs: &mut CS,
+    key: &[u8; 32],
+  ) -> Result<Option<Vec<AllocatedNum<F>>>, SynthesisError> {
+    let Some(entry) = self.entries.get(key) else {
+      return Ok(None);
+    };
+
+    let nums = entry
+      .aux
+      .iter()
+      .enumerate()
+      .map(|(j, v)| AllocatedNum::alloc(cs.namespace(|| format!("cached aux {j}")), || Ok(*v)))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    self.hits += 1;
+    Ok(Some(
+      entry.outputs.iter().map(|o| nums[*o].clone()).collect(),
+    ))
+  }
+
+  /// Records the values allocated in `cs` since it held `aux_start` auxiliary values and
+  /// `input_start` inputs. Steps that allocate inputs or return values they did not allocate
+  /// are not cached, and nothing is inserted once the cache is full.
+  pub fn insert<CS: ConstraintSystem<F>>(
+    &mut self,
+    cs: &CS,
+    key: [u8; 32],
+    aux_start: usize,
+    input_start: usize,
+    outputs: &[AllocatedNum<F>],
+  ) {
+    if self.entries.len() >= self.max_entries || cs.inputs_slice().len() != input_start {
+      return;
+    }
+
+    let outputs = outputs
+      .iter()
+      .map(|o| match o.get_variable().get_unchecked() {
+        Index::Aux(i) if i >= aux_start => Some(i - aux_start),
+        _ => None,
+      })
+      .collect::<Option<Vec<_>>>();
+
+    if let Some(outputs) = outputs {
+      let aux = cs.aux_slice()[aux_start..].to_vec();
+      self.entries.insert(key, CachedStepWitness { aux, outputs });
+    }
+  }
+}
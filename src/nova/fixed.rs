@@ -0,0 +1,107 @@
+//! This module defines `FixedCircuitProver`, a prover for incremental computations that apply the
+//! same step circuit at every step.
+//!
+//! `RecursiveSNARK::prove_step` accepts a possibly different circuit at every step, so it copies
+//! the synthesized assignments into a witness. When the step circuit is fixed, the prover instead
+//! checks once that the circuit matches the public parameters, and then synthesizes each step into
+//! assignments that are allocated up front at the size of the shapes and moved into the witness
+//! without a copy.
+use super::{PublicParams, RecursiveSNARK, StepContext};
+use crate::{errors::NovaError, traits::circuit::StepCircuit, traits::Engine};
+
+/// Proves steps of an incremental computation whose step circuit never changes
+pub struct FixedCircuitProver<'a, E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  pp: &'a PublicParams<E1, E2, C>,
+  circuit: C,
+  ctx: StepContext,
+}
+
+impl<'a, E1, E2, C> FixedCircuitProver<'a, E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Creates a prover that applies `circuit` at every step, after checking that the augmented
+  /// circuits it induces have the shapes that `pp` was produced for
+  pub fn new(pp: &'a PublicParams<E1, E2, C>, circuit: C) -> Result<Self, NovaError> {
+    let (shape_primary, shape_secondary) = PublicParams::<E1, E2, C>::synthesize_shapes(&circuit)?;
+    if circuit.arity() != pp.F_arity
+      || shape_primary.digest() != pp.r1cs_shape_primary.digest()
+      || shape_secondary.digest() != pp.r1cs_shape_secondary.digest()
+    {
+      return Err(NovaError::SetupError {
+        reason: "The step circuit does not match the public parameters".to_string(),
+      });
+    }
+
+    // every step commits with the same keys, so converting them for the MSM backend pays off
+    pp.prepare_commitment_keys();
+
+    Ok(Self {
+      pp,
+      circuit,
+      ctx: StepContext::new(true),
+    })
+  }
+
+  /// Returns the step circuit applied at every step
+  pub fn circuit(&self) -> &C {
+    &self.circuit
+  }
+
+  /// Creates a `RecursiveSNARK` for the computation starting from `z0`
+  pub fn recursive_snark(&self, z0: &[E1::Scalar]) -> Result<RecursiveSNARK<E1, E2, C>, NovaError> {
+    RecursiveSNARK::new(self.pp, &self.circuit, z0)
+  }
+
+  /// Updates `recursive_snark` by executing a step of the incremental computation, which is
+  /// equivalent to `recursive_snark.prove_step(pp, circuit)`
+  pub fn prove_step(
+    &self,
+    recursive_snark: &mut RecursiveSNARK<E1, E2, C>,
+  ) -> Result<(), NovaError> {
+    recursive_snark.prove_step_with(self.pp, &self.circuit, &self.ctx, None, None)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::{PallasEngine, VestaEngine},
+    traits::{circuit::NonTrivialCircuit, snark::default_ck_hint},
+  };
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+  type C = NonTrivialCircuit<F>;
+
+  #[test]
+  fn test_fixed_circuit_prover() {
+    let circuit = C::new(8);
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+    let z0 = [F::from(2u64)];
+    let num_steps = 4;
+
+    let prover = FixedCircuitProver::new(&pp, circuit.clone()).unwrap();
+    let mut fixed = prover.recursive_snark(&z0).unwrap();
+    let mut general = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for i in 0..num_steps {
+      prover.prove_step(&mut fixed).unwrap();
+      general.prove_step(&pp, &circuit).unwrap();
+      assert_eq!(fixed.outputs(), general.outputs());
+      fixed.verify(&pp, i + 1, &z0).unwrap();
+    }
+
+    // a circuit with a different shape is rejected up front
+    assert!(FixedCircuitProver::new(&pp, C::new(9)).is_err());
+  }
+}
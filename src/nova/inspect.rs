@@ -0,0 +1,257 @@
+//! This module defines `Inspection`, a human-readable summary of the structure of public parameters,
+//! keys, and proofs.
+//!
+//! An inspection lists the sizes, digests, commitments, and types that a value was produced with,
+//! so that the summaries obtained by a prover and a verifier deployment can be compared line by line.
+use super::{CompressedSNARK, PublicParams, RecursiveSNARK, VerifierKey};
+use crate::{
+  encoding::{encode_scalar, ByteOrder},
+  r1cs::{R1CSInstance, R1CSShape, RelaxedR1CSInstance},
+  traits::{
+    circuit::StepCircuit,
+    commitment::{CommitmentTrait, Len},
+    snark::RelaxedR1CSSNARKTrait,
+    Engine,
+  },
+  Commitment,
+};
+use core::{any::type_name, fmt};
+use ff::PrimeField;
+use serde::Serialize;
+
+/// A summary of the structure of a value, as an ordered list of named entries
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Inspection {
+  entries: Vec<(String, String)>,
+}
+
+impl Inspection {
+  /// Returns all entries, in the order they were recorded
+  pub fn entries(&self) -> &[(String, String)] {
+    &self.entries
+  }
+
+  /// Returns the value of the entry with the provided name, if it is part of the inspection
+  pub fn get(&self, name: &str) -> Option<&str> {
+    self
+      .entries
+      .iter()
+      .find(|(n, _)| n == name)
+      .map(|(_, v)| v.as_str())
+  }
+
+  fn push(&mut self, name: impl Into<String>, value: impl ToString) {
+    self.entries.push((name.into(), value.to_string()));
+  }
+
+  fn push_shape<E: Engine>(&mut self, prefix: &str, shape: &R1CSShape<E>) {
+    self.push(format!("{prefix}.num_cons"), shape.num_cons);
+    self.push(format!("{prefix}.num_vars"), shape.num_vars);
+    self.push(format!("{prefix}.num_io"), shape.num_io);
+    self.push(format!("{prefix}.digest"), to_hex(&shape.digest()));
+  }
+
+  fn push_instance<E: Engine>(&mut self, prefix: &str, U: &R1CSInstance<E>) {
+    self.push(
+      format!("{prefix}.comm_W"),
+      commitment_to_hex::<E>(&U.comm_W),
+    );
+    self.push(format!("{prefix}.X"), scalars_to_hex(&U.X));
+  }
+
+  fn push_relaxed_instance<E: Engine>(&mut self, prefix: &str, U: &RelaxedR1CSInstance<E>) {
+    self.push(
+      format!("{prefix}.comm_W"),
+      commitment_to_hex::<E>(&U.comm_W),
+    );
+    self.push(
+      format!("{prefix}.comm_E"),
+      commitment_to_hex::<E>(&U.comm_E),
+    );
+    self.push(format!("{prefix}.X"), scalars_to_hex(&U.X));
+    self.push(format!("{prefix}.u"), to_hex(&U.u));
+  }
+
+  fn push_serialized_size<T: Serialize>(&mut self, name: &str, value: &T) {
+    // serializing into a size counter cannot fail for the types of this crate
+    if let Ok(size) = bincode::serialized_size(value) {
+      self.push(format!("{name}.bytes"), size);
+    }
+  }
+}
+
+impl fmt::Display for Inspection {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let width = self.entries.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+    for (name, value) in &self.entries {
+      writeln!(f, "{name:width$}  {value}")?;
+    }
+    Ok(())
+  }
+}
+
+/// Renders the canonical value of a field element as big-endian hex
+fn to_hex<F: PrimeField>(f: &F) -> String {
+  let hex = encode_scalar(f, ByteOrder::BigEndian)
+    .iter()
+    .map(|b| format!("{b:02x}"))
+    .collect::<String>();
+  format!("0x{hex}")
+}
+
+fn scalars_to_hex<F: PrimeField>(v: &[F]) -> String {
+  format!("[{}]", v.iter().map(to_hex).collect::<Vec<_>>().join(", "))
+}
+
+/// Renders a commitment as the hex of its affine coordinates, or `infinity` for the identity
+fn commitment_to_hex<E: Engine>(comm: &Commitment<E>) -> String {
+  let (x, y, is_infinity) = comm.to_coordinates();
+  if is_infinity {
+    "infinity".to_string()
+  } else {
+    format!("({}, {})", to_hex(&x), to_hex(&y))
+  }
+}
+
+impl<E1, E2, C> PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Summarizes the public parameters: their digest, the engines, and the sizes of both circuits
+  /// and commitment keys
+  pub fn inspect(&self) -> Inspection {
+    let mut inspection = Inspection::default();
+    inspection.push("digest", to_hex(&self.digest()));
+    inspection.push("engine_primary", type_name::<E1>());
+    inspection.push("engine_secondary", type_name::<E2>());
+    inspection.push("arity", self.F_arity);
+    inspection.push_shape("primary", &self.r1cs_shape_primary);
+    inspection.push("primary.ck_size", self.ck_primary.length());
+    inspection.push_shape("secondary", &self.r1cs_shape_secondary);
+    inspection.push("secondary.ck_size", self.ck_secondary.length());
+    inspection
+  }
+}
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Summarizes the recursive SNARK: the number of steps, the inputs and outputs, and the
+  /// running and last instances
+  pub fn inspect(&self) -> Inspection {
+    let mut inspection = Inspection::default();
+    inspection.push("num_steps", self.i);
+    inspection.push("z0", scalars_to_hex(&self.z0));
+    inspection.push("zi", scalars_to_hex(&self.zi));
+    inspection.push_relaxed_instance("r_U_primary", &self.r_U_primary);
+    inspection.push_relaxed_instance("r_U_secondary", &self.r_U_secondary);
+    inspection.push_instance("l_u_secondary", &self.l_u_secondary);
+    inspection.push("witness_size_primary", self.r_W_primary.W.len());
+    inspection.push("witness_size_secondary", self.r_W_secondary.W.len());
+    inspection
+  }
+}
+
+impl<E1, E2, C, S1, S2> VerifierKey<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Summarizes the verifier key: the digest of the public parameters it was derived from and
+  /// the compressing SNARKs it verifies
+  pub fn inspect(&self) -> Inspection {
+    let mut inspection = Inspection::default();
+    inspection.push("pp_digest", to_hex(&self.light.pp_digest));
+    inspection.push("arity", self.light.F_arity);
+    inspection.push("snark_primary", type_name::<S1>());
+    inspection.push("snark_secondary", type_name::<S2>());
+    inspection.push_serialized_size("vk_primary", &self.light.vk_primary);
+    inspection.push_serialized_size("vk_secondary", &self.light.vk_secondary);
+    inspection
+  }
+}
+
+impl<E1, E2, C, S1, S2> CompressedSNARK<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Summarizes the compressed SNARK: the outputs, the folded instances, and the compressing
+  /// SNARKs along with their sizes
+  pub fn inspect(&self) -> Inspection {
+    let mut inspection = Inspection::default();
+    inspection.push("snark_primary", type_name::<S1>());
+    inspection.push("snark_secondary", type_name::<S2>());
+    inspection.push("zn", scalars_to_hex(&self.zn));
+    inspection.push_relaxed_instance("r_U_primary", &self.r_U_primary);
+    inspection.push_relaxed_instance("l_ur_primary", &self.l_ur_primary);
+    inspection.push_relaxed_instance("r_U_secondary", &self.r_U_secondary);
+    inspection.push_instance("l_u_secondary", &self.l_u_secondary);
+    inspection.push_relaxed_instance("l_ur_secondary", &self.l_ur_secondary);
+    inspection.push_serialized_size("snark_primary", &self.snark_primary);
+    inspection.push_serialized_size("snark_secondary", &self.snark_secondary);
+    inspection.push_serialized_size("total", self);
+    inspection
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::{PallasEngine, VestaEngine},
+    traits::{circuit::TrivialCircuit, snark::default_ck_hint},
+  };
+  use ff::Field;
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+
+  #[test]
+  fn test_inspect() {
+    let circuit = TrivialCircuit::<F>::default();
+    let pp = PublicParams::<E1, E2, TrivialCircuit<F>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    let inspection = pp.inspect();
+    assert_eq!(
+      inspection.get("digest"),
+      Some(to_hex(&pp.digest()).as_str())
+    );
+    assert_eq!(
+      inspection.get("primary.num_cons"),
+      Some(pp.num_constraints().0.to_string().as_str())
+    );
+    assert!(inspection.to_string().lines().count() == inspection.entries().len());
+
+    let z0 = [F::from(3u64)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+
+    let inspection = recursive_snark.inspect();
+    assert_eq!(inspection.get("num_steps"), Some("2"));
+    assert_eq!(inspection.get("zi"), Some(scalars_to_hex(&z0).as_str()));
+    assert!(inspection.get("r_U_primary.comm_W").is_some());
+
+    // field elements are rendered in big-endian order
+    assert!(to_hex(&F::ONE).ends_with("01"));
+    assert!(to_hex(&F::ONE).starts_with("0x00"));
+  }
+}
@@ -0,0 +1,254 @@
+//! This module defines `Ivc`, a high-level interface to Nova that covers the common use of
+//! proving repeated applications of one step circuit and compressing the result.
+//!
+//! A `Cycle` fixes the pair of engines along with the SNARKs that compress proofs, so that only the
+//! cycle and the step circuit need to be named. `Ivc` sets up the public parameters with commitment
+//! keys large enough for compression, proves steps, and sets up the keys of the compressed SNARK
+//! the first time they are needed. The engines use Poseidon as their random oracle.
+//!
+//! ```no_run
+//! use nova_snark::prelude::*;
+//!
+//! #[derive(Clone)]
+//! struct Square;
+//!
+//! impl<F: PrimeField> StepCircuit<F> for Square {
+//!   fn arity(&self) -> usize {
+//!     1
+//!   }
+//!
+//!   fn synthesize<CS: ConstraintSystem<F>>(
+//!     &self,
+//!     cs: &mut CS,
+//!     z: &[AllocatedNum<F>],
+//!   ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+//!     Ok(vec![z[0].square(cs.namespace(|| "square"))?])
+//!   }
+//! }
+//!
+//! # fn main() -> Result<(), NovaError> {
+//! let z0 = [CycleScalar::<Bn256Cycle>::from(3u64)];
+//! let mut ivc = Ivc::<Bn256Cycle, _>::new(Square, &z0)?;
+//! ivc.prove(10)?;
+//! let proof = ivc.compress()?;
+//! let outputs = proof.verify(ivc.verifier_key()?)?;
+//! assert_eq!(outputs, ivc.outputs());
+//! # Ok(())
+//! # }
+//! ```
+use super::{CompressedSNARK, ProverKey, PublicParams, RecursiveSNARK, VerifierKey};
+use crate::{
+  errors::NovaError,
+  provider::{hyperkzg, ipa_pc, Bn256EngineKZG, GrumpkinEngine, PallasEngine, VestaEngine},
+  spartan::snark::RelaxedR1CSSNARK,
+  traits::{
+    circuit::StepCircuit,
+    snark::{CommitmentKeyHintPreset, RelaxedR1CSSNARKTrait},
+    Engine,
+  },
+};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+/// A cycle of engines, along with the SNARKs that compress proofs on either side of the cycle
+pub trait Cycle: Send + Sync + 'static {
+  /// The primary engine, over whose scalar field the step circuit is defined
+  type E1: Engine<Base = <Self::E2 as Engine>::Scalar>;
+  /// The secondary engine
+  type E2: Engine<Base = <Self::E1 as Engine>::Scalar>;
+  /// The SNARK that compresses the primary running instance
+  type S1: RelaxedR1CSSNARKTrait<Self::E1>;
+  /// The SNARK that compresses the secondary running instance
+  type S2: RelaxedR1CSSNARKTrait<Self::E2>;
+}
+
+/// The scalar field of the step circuits of a cycle
+pub type CycleScalar<Y> = <<Y as Cycle>::E1 as Engine>::Scalar;
+
+/// The BN256/Grumpkin cycle, which compresses with Spartan over HyperKZG on BN256 and over IPA on
+/// Grumpkin, so that the primary side of proofs can be verified with pairings
+pub struct Bn256Cycle;
+
+impl Cycle for Bn256Cycle {
+  type E1 = Bn256EngineKZG;
+  type E2 = GrumpkinEngine;
+  type S1 = RelaxedR1CSSNARK<Bn256EngineKZG, hyperkzg::EvaluationEngine<Bn256EngineKZG>>;
+  type S2 = RelaxedR1CSSNARK<GrumpkinEngine, ipa_pc::EvaluationEngine<GrumpkinEngine>>;
+}
+
+/// The Pallas/Vesta cycle, which compresses with Spartan over IPA on both sides and needs no
+/// trusted setup
+pub struct PastaCycle;
+
+impl Cycle for PastaCycle {
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type S1 = RelaxedR1CSSNARK<PallasEngine, ipa_pc::EvaluationEngine<PallasEngine>>;
+  type S2 = RelaxedR1CSSNARK<VestaEngine, ipa_pc::EvaluationEngine<VestaEngine>>;
+}
+
+/// The keys of the compressed SNARK of a cycle
+type Keys<Y, C> = (
+  ProverKey<<Y as Cycle>::E1, <Y as Cycle>::E2, C, <Y as Cycle>::S1, <Y as Cycle>::S2>,
+  VerifierKey<<Y as Cycle>::E1, <Y as Cycle>::E2, C, <Y as Cycle>::S1, <Y as Cycle>::S2>,
+);
+
+/// Incrementally verifiable computation of repeated applications of the step circuit `C` over
+/// the cycle `Y`
+pub struct Ivc<Y: Cycle, C: StepCircuit<CycleScalar<Y>>> {
+  pp: PublicParams<Y::E1, Y::E2, C>,
+  circuit: C,
+  z0: Vec<CycleScalar<Y>>,
+  snark: RecursiveSNARK<Y::E1, Y::E2, C>,
+  keys: OnceCell<Keys<Y, C>>,
+}
+
+impl<Y: Cycle, C: StepCircuit<CycleScalar<Y>>> Ivc<Y, C> {
+  /// Sets up the public parameters for `circuit`, with commitment keys large enough to compress
+  /// proofs, and starts the computation with the initial inputs `z0`
+  pub fn new(circuit: C, z0: &[CycleScalar<Y>]) -> Result<Self, NovaError> {
+    let pp = PublicParams::setup(
+      &circuit,
+      &*CommitmentKeyHintPreset::for_compressed_snark::<Y::S1>().into_hint(),
+      &*CommitmentKeyHintPreset::for_compressed_snark::<Y::S2>().into_hint(),
+    )?;
+    let snark = RecursiveSNARK::new(&pp, &circuit, z0)?;
+    Ok(Self {
+      pp,
+      circuit,
+      z0: z0.to_vec(),
+      snark,
+      keys: OnceCell::new(),
+    })
+  }
+
+  /// Proves `num_steps` further steps of the computation
+  pub fn prove(&mut self, num_steps: usize) -> Result<(), NovaError> {
+    for _ in 0..num_steps {
+      self.snark.prove_step(&self.pp, &self.circuit)?;
+    }
+    Ok(())
+  }
+
+  /// Verifies the steps proven so far, and returns the outputs of the computation
+  pub fn verify(&self) -> Result<Vec<CycleScalar<Y>>, NovaError> {
+    self.snark.verify(&self.pp, self.num_steps(), &self.z0)
+  }
+
+  /// Compresses the steps proven so far into a succinct proof
+  pub fn compress(&self) -> Result<IvcProof<Y, C>, NovaError> {
+    let (pk, _) = self.keys()?;
+    let snark = CompressedSNARK::prove(&self.pp, pk, &self.snark)?;
+    Ok(IvcProof {
+      snark,
+      num_steps: self.num_steps(),
+      z0: self.z0.clone(),
+    })
+  }
+
+  /// Returns the key that verifies compressed proofs, which is set up the first time it is needed
+  pub fn verifier_key(&self) -> Result<&VerifierKey<Y::E1, Y::E2, C, Y::S1, Y::S2>, NovaError> {
+    Ok(&self.keys()?.1)
+  }
+
+  /// Returns the number of steps proven so far
+  pub fn num_steps(&self) -> usize {
+    self.snark.num_steps()
+  }
+
+  /// Returns the outputs of the computation after the steps proven so far
+  pub fn outputs(&self) -> &[CycleScalar<Y>] {
+    self.snark.outputs()
+  }
+
+  /// Returns the public parameters, e.g., to use the low-level API with them
+  pub fn public_params(&self) -> &PublicParams<Y::E1, Y::E2, C> {
+    &self.pp
+  }
+
+  /// Returns the recursive SNARK of the steps proven so far
+  pub fn recursive_snark(&self) -> &RecursiveSNARK<Y::E1, Y::E2, C> {
+    &self.snark
+  }
+
+  fn keys(&self) -> Result<&Keys<Y, C>, NovaError> {
+    self
+      .keys
+      .get_or_try_init(|| CompressedSNARK::setup(&self.pp))
+  }
+}
+
+/// A compressed proof of the steps of an `Ivc`, along with the number of steps and the initial
+/// inputs that it is verified against
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct IvcProof<Y: Cycle, C: StepCircuit<CycleScalar<Y>>> {
+  snark: CompressedSNARK<Y::E1, Y::E2, C, Y::S1, Y::S2>,
+  num_steps: usize,
+  z0: Vec<CycleScalar<Y>>,
+}
+
+impl<Y: Cycle, C: StepCircuit<CycleScalar<Y>>> IvcProof<Y, C> {
+  /// Verifies the proof with `vk`, and returns the outputs of the computation
+  pub fn verify(
+    &self,
+    vk: &VerifierKey<Y::E1, Y::E2, C, Y::S1, Y::S2>,
+  ) -> Result<Vec<CycleScalar<Y>>, NovaError> {
+    self.snark.verify(vk, self.num_steps, &self.z0)
+  }
+
+  /// Returns the number of steps the proof covers
+  pub fn num_steps(&self) -> usize {
+    self.num_steps
+  }
+
+  /// Returns the initial inputs of the computation
+  pub fn initial_inputs(&self) -> &[CycleScalar<Y>] {
+    &self.z0
+  }
+
+  /// Returns the underlying compressed SNARK
+  pub fn into_inner(self) -> CompressedSNARK<Y::E1, Y::E2, C, Y::S1, Y::S2> {
+    self.snark
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::traits::circuit::NonTrivialCircuit;
+  use ff::Field;
+
+  fn test_ivc_with<Y: Cycle>() {
+    type F<Y> = CycleScalar<Y>;
+    let z0 = [F::<Y>::from(2u64)];
+    let mut ivc = Ivc::<Y, _>::new(NonTrivialCircuit::<F<Y>>::new(4), &z0).unwrap();
+    ivc.prove(3).unwrap();
+    assert_eq!(ivc.num_steps(), 3);
+    let outputs = ivc.verify().unwrap();
+    assert_eq!(outputs, ivc.outputs());
+
+    let proof = ivc.compress().unwrap();
+    let proof: IvcProof<Y, NonTrivialCircuit<F<Y>>> =
+      bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+    assert_eq!(proof.num_steps(), 3);
+    assert_eq!(proof.initial_inputs(), z0);
+    assert_eq!(proof.verify(ivc.verifier_key().unwrap()).unwrap(), outputs);
+
+    // proving more steps does not set up the keys again
+    ivc.prove(1).unwrap();
+    let proof = ivc.compress().unwrap();
+    assert_eq!(
+      proof.verify(ivc.verifier_key().unwrap()).unwrap(),
+      ivc.outputs()
+    );
+    assert_ne!(ivc.outputs(), outputs);
+    assert_ne!(outputs[0], F::<Y>::ZERO);
+  }
+
+  #[test]
+  fn test_ivc() {
+    test_ivc_with::<PastaCycle>();
+    test_ivc_with::<Bn256Cycle>();
+  }
+}
@@ -0,0 +1,388 @@
+//! This module defines `LightVerifier`, which verifies `CompressedSNARK`s with nothing but the
+//! verifier's part of the keys.
+//!
+//! A `VerifierKey` is tied to the type of the step circuit, although verification never uses it. A
+//! `LightVerifier` drops the step circuit from the type, so that a service that only verifies proofs
+//! depends on neither the circuit, the public parameters, nor the `R1CSShape`s of the augmented
+//! circuits. It is built from a verifier key and the `VerifierParams` it must match, and serializes
+//! on its own. Proofs can be deserialized with any step circuit type, e.g., `TrivialCircuit`, since
+//! the type is not part of their encoding.
+//!
+//! With `spartan::ppsnark`, the verifier keys of the SNARKs hold commitments to the R1CS matrices
+//! rather than the matrices, so the verifier's state is also independent of the size of the circuit.
+use super::{CompressedSNARK, StopCondition, VerificationReport, VerifierKey, VerifierParams};
+use crate::{
+  constants::NUM_HASH_BITS,
+  errors::NovaError,
+  gadgets::utils::{base_as_scalar, scalar_as_base},
+  provider::timing::phase,
+  traits::{
+    circuit::StepCircuit, snark::RelaxedR1CSSNARKTrait, AbsorbInROTrait, Engine, ROConstants,
+    ROTrait,
+  },
+  DerandKey,
+};
+use ff::Field;
+use serde::{Deserialize, Serialize};
+
+/// A verifier of `CompressedSNARK`s that holds only the verifier's part of the keys
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct LightVerifier<E1, E2, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  pub(super) F_arity: usize,
+  pub(super) ro_consts_primary: ROConstants<E1>,
+  pub(super) ro_consts_secondary: ROConstants<E2>,
+  pub(super) pp_digest: E1::Scalar,
+  pub(super) vk_primary: S1::VerifierKey,
+  pub(super) vk_secondary: S2::VerifierKey,
+  pub(super) dk_primary: DerandKey<E1>,
+  pub(super) dk_secondary: DerandKey<E2>,
+  #[serde(default)]
+  pub(super) stop_conditions: Vec<StopCondition<E1::Scalar>>,
+}
+
+impl<E1, E2, S1, S2> LightVerifier<E1, E2, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Creates a verifier from `vk`, failing unless it was derived from the public parameters that
+  /// `vp` was split from
+  pub fn new<C: StepCircuit<E1::Scalar>>(
+    vp: &VerifierParams<E1, E2, C>,
+    vk: VerifierKey<E1, E2, C, S1, S2>,
+  ) -> Result<Self, NovaError> {
+    if !vk.matches(vp) {
+      return Err(NovaError::SetupError {
+        reason: "The verifier key does not belong to the verifier parameters".to_string(),
+      });
+    }
+    Ok(vk.light)
+  }
+
+  /// Returns the digest of the public parameters the verifier was derived from
+  pub fn digest(&self) -> E1::Scalar {
+    self.pp_digest
+  }
+
+  /// Returns the arity of the step circuit
+  pub fn arity(&self) -> usize {
+    self.F_arity
+  }
+
+  /// Precomputes, once, what every verification derives from the keys of the verifier (see
+  /// `RelaxedR1CSSNARKTrait::preprocess`), for servers that verify many proofs of the same circuit
+  pub fn preprocess(&self) {
+    S1::preprocess(&self.vk_primary);
+    S2::preprocess(&self.vk_secondary);
+  }
+
+  /// Verifies `snark` like `CompressedSNARK::verify`, and returns its outputs
+  pub fn verify<C: StepCircuit<E1::Scalar>>(
+    &self,
+    snark: &CompressedSNARK<E1, E2, C, S1, S2>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+  ) -> Result<Vec<E1::Scalar>, NovaError> {
+    self
+      .verify_with(snark, num_steps, z0, VerificationReport::fail_fast())
+      .into_result()
+      .map(|_| snark.zn.clone())
+  }
+
+  /// Verifies `snark` like `CompressedSNARK::verify_detailed`
+  pub fn verify_detailed<C: StepCircuit<E1::Scalar>>(
+    &self,
+    snark: &CompressedSNARK<E1, E2, C, S1, S2>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+  ) -> VerificationReport {
+    self.verify_with(snark, num_steps, z0, VerificationReport::default())
+  }
+
+  /// Performs the checks of `verify` and records them in `report`, skipping the costly ones once
+  /// a check failed if `report` stops at the first failure
+  fn verify_with<C: StepCircuit<E1::Scalar>>(
+    &self,
+    snark: &CompressedSNARK<E1, E2, C, S1, S2>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+    mut report: VerificationReport,
+  ) -> VerificationReport {
+    let _phase = phase!("verify_compressed");
+    report.record_digest("pp_digest", &self.pp_digest);
+
+    // the number of steps cannot be zero
+    report.record(
+      "num_steps_nonzero",
+      if num_steps == 0 {
+        Err(NovaError::ProofVerifyError {
+          reason: "Number of steps cannot be zero".to_string(),
+        })
+      } else {
+        Ok(())
+      },
+    );
+
+    // the computation stops where the application committed to
+    if !self.stop_conditions.is_empty() {
+      report.record(
+        "stop_condition",
+        self.check_stop_conditions(num_steps, &snark.zn),
+      );
+    }
+
+    // check if the (relaxed) R1CS instances have two public outputs
+    let has_two_outputs = report.record(
+      "instance_io_len",
+      if snark.l_u_secondary.X.len() != 2
+        || snark.r_U_primary.X.len() != 2
+        || snark.r_U_secondary.X.len() != 2
+        || snark.l_ur_primary.X.len() != 2
+        || snark.l_ur_secondary.X.len() != 2
+      {
+        Err(NovaError::ProofVerifyError {
+          reason: "Invalid number of outputs in R1CS instances".to_string(),
+        })
+      } else {
+        Ok(())
+      },
+    );
+
+    if !has_two_outputs || report.halted() {
+      report.skip_all(&[
+        "hash_primary",
+        "hash_secondary",
+        "nifs_Uf_secondary",
+        "nifs_Un_secondary",
+        "nifs_Un_primary",
+        "snark_primary",
+        "snark_secondary",
+      ]);
+      return report;
+    }
+
+    // check if the output hashes in R1CS instances point to the right running instances
+    let (hash_primary, hash_secondary) = {
+      let mut hasher = <E2 as Engine>::RO::new(self.ro_consts_secondary.clone());
+      hasher.absorb(self.pp_digest);
+      hasher.absorb(E1::Scalar::from(num_steps as u64));
+      for e in z0 {
+        hasher.absorb(*e);
+      }
+      for e in &snark.zn {
+        hasher.absorb(*e);
+      }
+      snark.r_U_secondary.absorb_in_ro(&mut hasher);
+      hasher.absorb(snark.ri_primary);
+
+      let mut hasher2 = <E1 as Engine>::RO::new(self.ro_consts_primary.clone());
+      hasher2.absorb(scalar_as_base::<E1>(self.pp_digest));
+      hasher2.absorb(E2::Scalar::from(num_steps as u64));
+      hasher2.absorb(E2::Scalar::ZERO);
+      hasher2.absorb(E2::Scalar::ZERO);
+      snark.r_U_primary.absorb_in_ro(&mut hasher2);
+      hasher2.absorb(snark.ri_secondary);
+
+      (
+        hasher.squeeze(NUM_HASH_BITS),
+        hasher2.squeeze(NUM_HASH_BITS),
+      )
+    };
+    report.record_digest("hash_primary", &hash_primary);
+    report.record_digest("hash_secondary", &hash_secondary);
+
+    let invalid_hash = |cond: bool| {
+      if cond {
+        Ok(())
+      } else {
+        Err(NovaError::ProofVerifyError {
+          reason: "Invalid output hash in R1CS instances".to_string(),
+        })
+      }
+    };
+    report.record(
+      "hash_primary",
+      invalid_hash(hash_primary == base_as_scalar::<E1>(snark.l_u_secondary.X[0])),
+    );
+    report.record(
+      "hash_secondary",
+      invalid_hash(hash_secondary == snark.l_u_secondary.X[1]),
+    );
+
+    if report.halted() {
+      report.skip_all(&[
+        "nifs_Uf_secondary",
+        "nifs_Un_secondary",
+        "nifs_Un_primary",
+        "snark_primary",
+        "snark_secondary",
+      ]);
+      return report;
+    }
+
+    // fold secondary U/W with secondary u/w to get Uf/Wf
+    let r_Uf_secondary = report.record_value(
+      "nifs_Uf_secondary",
+      snark.nifs_Uf_secondary.verify(
+        &self.ro_consts_secondary,
+        &scalar_as_base::<E1>(self.pp_digest),
+        &snark.r_U_secondary,
+        &snark.l_u_secondary,
+      ),
+    );
+
+    // fold Uf/Wf with random inst/wit to get U1/W1
+    let r_Un_secondary = match &r_Uf_secondary {
+      Some(r_Uf_secondary) => report.record_value(
+        "nifs_Un_secondary",
+        snark.nifs_Un_secondary.verify(
+          &self.ro_consts_secondary,
+          &scalar_as_base::<E1>(self.pp_digest),
+          r_Uf_secondary,
+          &snark.l_ur_secondary,
+        ),
+      ),
+      None => {
+        report.skip("nifs_Un_secondary");
+        None
+      }
+    };
+
+    // fold primary U/W with random inst/wit to get U2/W2
+    let r_Un_primary = report.record_value(
+      "nifs_Un_primary",
+      snark.nifs_Un_primary.verify(
+        &self.ro_consts_primary,
+        &self.pp_digest,
+        &snark.r_U_primary,
+        &snark.l_ur_primary,
+      ),
+    );
+
+    // derandomize/unblind commitments
+    let derandom_r_Un_primary = r_Un_primary.map(|r_Un_primary| {
+      r_Un_primary.derandomize(
+        &self.dk_primary,
+        &snark.wit_blind_r_Wn_primary,
+        &snark.err_blind_r_Wn_primary,
+      )
+    });
+    let derandom_r_Un_secondary = r_Un_secondary.map(|r_Un_secondary| {
+      r_Un_secondary.derandomize(
+        &self.dk_secondary,
+        &snark.wit_blind_r_Wn_secondary,
+        &snark.err_blind_r_Wn_secondary,
+      )
+    });
+
+    if report.halted() {
+      report.skip_all(&["snark_primary", "snark_secondary"]);
+      return report;
+    }
+
+    // check the satisfiability of the folded instances using
+    // SNARKs proving the knowledge of their satisfying witnesses
+    let (res_primary, res_secondary) = rayon::join(
+      || {
+        derandom_r_Un_primary
+          .as_ref()
+          .map(|U| snark.snark_primary.verify(&self.vk_primary, U))
+      },
+      || {
+        derandom_r_Un_secondary
+          .as_ref()
+          .map(|U| snark.snark_secondary.verify(&self.vk_secondary, U))
+      },
+    );
+
+    for (name, res) in [
+      ("snark_primary", res_primary),
+      ("snark_secondary", res_secondary),
+    ] {
+      match res {
+        Some(res) => {
+          report.record(name, res);
+        }
+        None => report.skip(name),
+      }
+    }
+
+    report
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    nova::{PublicParams, RecursiveSNARK},
+    provider::{ipa_pc, PallasEngine, VestaEngine},
+    spartan::ppsnark::RelaxedR1CSSNARK,
+    traits::{
+      circuit::{NonTrivialCircuit, TrivialCircuit},
+      snark::CommitmentKeyHintPreset,
+    },
+  };
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+  type C = NonTrivialCircuit<F>;
+  type S1 = RelaxedR1CSSNARK<E1, ipa_pc::EvaluationEngine<E1>>;
+  type S2 = RelaxedR1CSSNARK<E2, ipa_pc::EvaluationEngine<E2>>;
+
+  fn setup(num_cons: usize) -> PublicParams<E1, E2, C> {
+    PublicParams::setup(
+      &C::new(num_cons),
+      &*CommitmentKeyHintPreset::for_compressed_snark::<S1>().into_hint(),
+      &*CommitmentKeyHintPreset::for_compressed_snark::<S2>().into_hint(),
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn test_light_verifier() {
+    let pp = setup(4);
+    let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+
+    let circuit = C::new(4);
+    let z0 = [F::from(2u64)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..3 {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let snark = CompressedSNARK::<_, _, _, S1, S2>::prove(&pp, &pk, &recursive_snark).unwrap();
+    let zn = snark.verify(&vk, 3, &z0).unwrap();
+
+    // the verifier is built from the verifier's parts alone, and serialized on its own
+    let (_, vp) = pp.split();
+    let (_, other_vp) = setup(5).split();
+    assert!(LightVerifier::new(&other_vp, vk.clone()).is_err());
+    let verifier = LightVerifier::new(&vp, vk).unwrap();
+    assert_eq!(verifier.digest(), vp.digest());
+    assert_eq!(verifier.arity(), 1);
+    let verifier: LightVerifier<E1, E2, S1, S2> =
+      bincode::deserialize(&bincode::serialize(&verifier).unwrap()).unwrap();
+
+    // the proof is deserialized without the type of the step circuit
+    let snark: CompressedSNARK<E1, E2, TrivialCircuit<F>, S1, S2> =
+      bincode::deserialize(&bincode::serialize(&snark).unwrap()).unwrap();
+    assert_eq!(verifier.verify(&snark, 3, &z0).unwrap(), zn);
+    assert!(verifier.verify(&snark, 2, &z0).is_err());
+    assert!(verifier.verify(&snark, 3, &[F::from(3u64)]).is_err());
+
+    // a preprocessed verifier accepts and rejects the same proofs
+    verifier.preprocess();
+    assert_eq!(verifier.verify(&snark, 3, &z0).unwrap(), zn);
+    assert!(verifier.verify(&snark, 2, &z0).is_err());
+  }
+}
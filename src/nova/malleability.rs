@@ -0,0 +1,300 @@
+//! This module tests that the verifiers of the folding schemes and of `CompressedSNARK` reject
+//! mutated proofs, as a regression suite for their soundness.
+//!
+//! Compressed SNARKs are mutated word by word in their calldata encoding (see
+//! `traits::calldata`), which covers every commitment, sum-check coefficient, evaluation, and
+//! public value they hold, truncated in both their calldata and bincode encodings, and have their
+//! instances swapped with each other or with those of another proof. Foldings are checked with
+//! mutated cross terms, instances, and digests, which must yield folded instances that the
+//! folded witness does not satisfy.
+use super::*;
+use crate::{
+  provider::{ipa_pc, Bn256EngineIPA, GrumpkinEngine},
+  spartan::snark::RelaxedR1CSSNARK,
+  traits::{
+    calldata::{from_calldata, to_calldata, CalldataEncoder, CalldataEncoding, WORD_SIZE},
+    circuit::NonTrivialCircuit,
+    snark::default_ck_hint,
+  },
+};
+use std::ops::Range;
+
+type E1 = Bn256EngineIPA;
+type E2 = GrumpkinEngine;
+type F = <E1 as Engine>::Scalar;
+type G = <E2 as Engine>::Scalar;
+type C = NonTrivialCircuit<F>;
+type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+type Snark = CompressedSNARK<E1, E2, C, S<E1>, S<E2>>;
+
+/// The number of steps of the first proof of the fixture, the second one has one more
+const NUM_STEPS: usize = 3;
+
+struct Fixture {
+  pp: PublicParams<E1, E2, C>,
+  vk: VerifierKey<E1, E2, C, S<E1>, S<E2>>,
+  recursive_snark: RecursiveSNARK<E1, E2, C>,
+  // proofs of `NUM_STEPS` and `NUM_STEPS + 1` steps
+  snarks: [Snark; 2],
+}
+
+fn z0() -> [F; 1] {
+  [F::from(2)]
+}
+
+fn fixture() -> &'static Fixture {
+  static FIXTURE: OnceCell<Fixture> = OnceCell::new();
+  FIXTURE.get_or_init(|| {
+    let circuit = C::new(3);
+    let pp = PublicParams::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+    let (pk, vk) = Snark::setup(&pp).unwrap();
+
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0()).unwrap();
+    for _ in 0..NUM_STEPS {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let first = Snark::prove(&pp, &pk, &recursive_snark).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    let second = Snark::prove(&pp, &pk, &recursive_snark).unwrap();
+
+    Fixture {
+      pp,
+      vk,
+      recursive_snark,
+      snarks: [first, second],
+    }
+  })
+}
+
+/// Returns whether the verifier key of the fixture rejects `snark` as a proof of `num_steps` steps
+fn rejects(snark: &Snark, num_steps: usize) -> bool {
+  snark.verify(&fixture().vk, num_steps, &z0()).is_err()
+}
+
+/// Returns whether `bytes` fail to decode as calldata of a compressed SNARK, or decode to a proof
+/// of `NUM_STEPS` steps that the verifier rejects
+fn rejects_calldata(bytes: &[u8]) -> bool {
+  from_calldata::<Snark>(bytes).map_or(true, |snark| rejects(&snark, NUM_STEPS))
+}
+
+/// Returns the names of the fields of `snark` along with the ranges of words that encode them
+fn segments(snark: &Snark) -> Vec<(&'static str, Range<usize>)> {
+  let fields: [(&'static str, &dyn Fn(&mut CalldataEncoder)); 17] = [
+    ("r_U_secondary", &|enc| snark.r_U_secondary.encode(enc)),
+    ("ri_secondary", &|enc| snark.ri_secondary.encode(enc)),
+    ("l_u_secondary", &|enc| snark.l_u_secondary.encode(enc)),
+    ("nifs_Uf_secondary", &|enc| {
+      snark.nifs_Uf_secondary.encode(enc)
+    }),
+    ("l_ur_secondary", &|enc| snark.l_ur_secondary.encode(enc)),
+    ("nifs_Un_secondary", &|enc| {
+      snark.nifs_Un_secondary.encode(enc)
+    }),
+    ("r_U_primary", &|enc| snark.r_U_primary.encode(enc)),
+    ("ri_primary", &|enc| snark.ri_primary.encode(enc)),
+    ("l_ur_primary", &|enc| snark.l_ur_primary.encode(enc)),
+    ("nifs_Un_primary", &|enc| snark.nifs_Un_primary.encode(enc)),
+    ("wit_blind_r_Wn_primary", &|enc| {
+      snark.wit_blind_r_Wn_primary.encode(enc)
+    }),
+    ("err_blind_r_Wn_primary", &|enc| {
+      snark.err_blind_r_Wn_primary.encode(enc)
+    }),
+    ("wit_blind_r_Wn_secondary", &|enc| {
+      snark.wit_blind_r_Wn_secondary.encode(enc)
+    }),
+    ("err_blind_r_Wn_secondary", &|enc| {
+      snark.err_blind_r_Wn_secondary.encode(enc)
+    }),
+    ("snark_primary", &|enc| snark.snark_primary.encode(enc)),
+    ("snark_secondary", &|enc| snark.snark_secondary.encode(enc)),
+    ("zn", &|enc| snark.zn.encode(enc)),
+  ];
+
+  let mut start = 0;
+  fields
+    .iter()
+    .map(|(name, encode)| {
+      let mut enc = CalldataEncoder::default();
+      encode(&mut enc);
+      let len = enc.into_bytes().len() / WORD_SIZE;
+      let range = start..start + len;
+      start += len;
+      (*name, range)
+    })
+    .collect()
+}
+
+#[test]
+fn test_honest_proofs_verify() {
+  let fixture = fixture();
+  for (num_steps, snark) in (NUM_STEPS..).zip(&fixture.snarks) {
+    assert!(snark.verify(&fixture.vk, num_steps, &z0()).is_ok());
+  }
+  assert!(!rejects_calldata(&to_calldata(&fixture.snarks[0])));
+}
+
+#[test]
+fn test_compressed_snark_word_mutations() {
+  let snark = &fixture().snarks[0];
+  let bytes = to_calldata(snark);
+  let segments = segments(snark);
+  // the segments must tile the encoding, or the names below would point at the wrong fields
+  assert_eq!(segments.last().unwrap().1.end * WORD_SIZE, bytes.len());
+
+  for (name, words) in segments {
+    let rejected = words
+      .clone()
+      .into_par_iter()
+      .map(|word| {
+        // flipping the lowest bit changes a field element or a length, and moves a point off the
+        // curve, so each mutation yields a different proof or a malformed one
+        let mut mutated = bytes.clone();
+        mutated[(word + 1) * WORD_SIZE - 1] ^= 1;
+        (word, rejects_calldata(&mutated))
+      })
+      .collect::<Vec<_>>();
+    for (word, rejected) in rejected {
+      assert!(
+        rejected,
+        "a mutation of word {} of {name} was accepted",
+        word - words.start
+      );
+    }
+  }
+}
+
+#[test]
+fn test_compressed_snark_truncations() {
+  let snark = &fixture().snarks[0];
+  let bytes = to_calldata(snark);
+
+  // every prefix of the words, and encodings that are not made of whole words, are rejected
+  let num_words = bytes.len() / WORD_SIZE;
+  assert!((0..num_words)
+    .into_par_iter()
+    .all(|len| rejects_calldata(&bytes[..len * WORD_SIZE])));
+  assert!(rejects_calldata(&bytes[..bytes.len() - 1]));
+
+  // so are trailing words
+  let mut extended = bytes.clone();
+  extended.extend_from_slice(&[0u8; WORD_SIZE]);
+  assert!(rejects_calldata(&extended));
+
+  // and every prefix of the bincode encoding
+  let bytes = bincode::serialize(snark).unwrap();
+  assert!((0..bytes.len()).into_par_iter().all(|len| {
+    bincode::deserialize::<Snark>(&bytes[..len]).map_or(true, |snark| rejects(&snark, NUM_STEPS))
+  }));
+}
+
+#[test]
+fn test_compressed_snark_swaps() {
+  let fixture = fixture();
+  let [first, second] = &fixture.snarks;
+
+  // the running and random instances of each curve, swapped with each other
+  let mut snark = first.clone();
+  std::mem::swap(&mut snark.r_U_primary, &mut snark.l_ur_primary);
+  assert!(rejects(&snark, NUM_STEPS));
+  let mut snark = first.clone();
+  std::mem::swap(&mut snark.r_U_secondary, &mut snark.l_ur_secondary);
+  assert!(rejects(&snark, NUM_STEPS));
+
+  // the instances, foldings, and SNARKs of a proof, swapped with those of another proof
+  let swaps: [(&str, fn(&mut Snark, &Snark)); 8] = [
+    ("r_U_primary", |a, b| a.r_U_primary = b.r_U_primary.clone()),
+    ("l_ur_primary", |a, b| {
+      a.l_ur_primary = b.l_ur_primary.clone()
+    }),
+    ("nifs_Un_primary", |a, b| {
+      a.nifs_Un_primary = b.nifs_Un_primary.clone()
+    }),
+    ("r_U_secondary", |a, b| {
+      a.r_U_secondary = b.r_U_secondary.clone()
+    }),
+    ("l_u_secondary", |a, b| {
+      a.l_u_secondary = b.l_u_secondary.clone()
+    }),
+    ("nifs_Uf_secondary", |a, b| {
+      a.nifs_Uf_secondary = b.nifs_Uf_secondary.clone()
+    }),
+    ("snark_primary", |a, b| {
+      a.snark_primary = b.snark_primary.clone()
+    }),
+    ("snark_secondary", |a, b| {
+      a.snark_secondary = b.snark_secondary.clone()
+    }),
+  ];
+  for (name, swap) in swaps {
+    let mut snark = first.clone();
+    swap(&mut snark, second);
+    assert!(
+      rejects(&snark, NUM_STEPS),
+      "a proof with the {name} of another proof was accepted"
+    );
+  }
+
+  // proofs are bound to their number of steps and their initial inputs
+  assert!(rejects(first, NUM_STEPS + 1));
+  assert!(rejects(second, NUM_STEPS));
+  assert!(first.verify(&fixture.vk, NUM_STEPS, &[F::from(3)]).is_err());
+}
+
+#[test]
+fn test_nifs_mutations() {
+  let Fixture {
+    pp,
+    recursive_snark: rs,
+    ..
+  } = fixture();
+  let (ck, ro_consts, S) = (
+    &pp.ck_secondary,
+    &pp.ro_consts_secondary,
+    &pp.r1cs_shape_secondary,
+  );
+  // the secondary circuit folds with the digest of the public parameters as a base field element
+  let digest = scalar_as_base::<E1>(pp.digest());
+  let (U1, W1) = (&rs.r_U_secondary, &rs.r_W_secondary);
+  let (U2, W2) = (&rs.l_u_secondary, &rs.l_w_secondary);
+
+  let (nifs, (U, W)) = NIFS::prove(ck, ro_consts, &digest, S, U1, W1, U2, W2).unwrap();
+  assert_eq!(nifs.verify(ro_consts, &digest, U1, U2).unwrap(), U);
+  assert!(S.is_sat_relaxed(ck, &U, &W).is_ok());
+
+  // the folded witness satisfies none of the instances that mutated inputs fold into
+  let mut mutated = nifs.clone();
+  mutated.comm_T *= G::from(2);
+  let mut U2_mutated = U2.clone();
+  U2_mutated.X[0] += G::ONE;
+  let mut U1_mutated = U1.clone();
+  U1_mutated.comm_E = U1_mutated.comm_E + U1.comm_W;
+  let folded = [
+    ("comm_T", mutated.verify(ro_consts, &digest, U1, U2)),
+    ("U2.X", nifs.verify(ro_consts, &digest, U1, &U2_mutated)),
+    (
+      "U1.comm_E",
+      nifs.verify(ro_consts, &digest, &U1_mutated, U2),
+    ),
+    (
+      "pp_digest",
+      nifs.verify(ro_consts, &(digest + G::ONE), U1, U2),
+    ),
+  ];
+  for (name, U_mutated) in folded {
+    let U_mutated = U_mutated.unwrap();
+    assert_ne!(
+      U_mutated, U,
+      "a mutation of {name} folded into the same instance"
+    );
+    assert!(S.is_sat_relaxed(ck, &U_mutated, &W).is_err());
+  }
+
+  // relaxed foldings are not symmetric in their instances
+  let (U2, W2) = (U.clone(), W.clone());
+  let (nifs, (U, W)) = NIFSRelaxed::prove(ck, ro_consts, &digest, S, U1, W1, &U2, &W2).unwrap();
+  assert_eq!(nifs.verify(ro_consts, &digest, U1, &U2).unwrap(), U);
+  assert!(S.is_sat_relaxed(ck, &U, &W).is_ok());
+  let U_swapped = nifs.verify(ro_consts, &digest, &U2, U1).unwrap();
+  assert!(S.is_sat_relaxed(ck, &U_swapped, &W).is_err());
+}
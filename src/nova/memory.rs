@@ -0,0 +1,194 @@
+//! This module estimates the memory that proving needs for given public parameters, and checks the
+//! estimate against an optional limit before proving starts.
+//!
+//! Proving a large circuit may otherwise run out of memory after minutes of work. With a limit set
+//! by `RecursiveSNARK::set_memory_limit`, `RecursiveSNARK::prove_step` and `CompressedSNARK::prove`
+//! return `NovaError::MemoryLimitExceeded` before they allocate, with the estimated number of bytes
+//! they would need. The estimates count the vectors that dominate the footprint of the prover, and
+//! leave out the public parameters and the recursive SNARK, which are already allocated.
+use super::{PublicParams, RecursiveSNARK};
+use crate::{
+  errors::NovaError,
+  provider::{msm_backend, MsmBackend},
+  r1cs::R1CSShape,
+  traits::{circuit::StepCircuit, snark::MemoryBudget, Engine},
+};
+use std::mem;
+
+/// Returns `NovaError::MemoryLimitExceeded` if `required` bytes exceed `limit`
+pub(crate) fn check_memory_limit(required: usize, limit: Option<usize>) -> Result<(), NovaError> {
+  match limit {
+    Some(available) if required > available => Err(NovaError::MemoryLimitExceeded {
+      required,
+      available,
+    }),
+    _ => Ok(()),
+  }
+}
+
+/// The estimated peak allocations of proving with some public parameters, in bytes, see
+/// `PublicParams::memory_estimate`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryEstimate {
+  /// The witnesses of the augmented circuits, synthesized at each step
+  pub witness: usize,
+  /// The cross terms of the foldings, along with the folded witnesses and error vectors
+  pub cross_terms: usize,
+  /// The tables of the sum-checks of the Spartan provers that compress the recursive SNARK
+  pub sumcheck: usize,
+  /// The scalars and bases staged for the GPU by MSMs on the GPU backend, zero on the CPU backend
+  pub gpu_buffers: usize,
+}
+
+impl MemoryEstimate {
+  /// Returns the estimated peak allocations of `RecursiveSNARK::prove_step`
+  pub fn prove_step(&self) -> usize {
+    self.witness + self.cross_terms + self.gpu_buffers
+  }
+
+  /// Returns the estimated peak allocations of `CompressedSNARK::prove`
+  pub fn compress(&self) -> usize {
+    self.cross_terms + self.sumcheck + self.gpu_buffers
+  }
+}
+
+/// Returns the number of bytes of the witness of a circuit with shape `S`
+fn witness_bytes<E: Engine>(S: &R1CSShape<E>) -> usize {
+  (S.num_vars + S.num_io) * mem::size_of::<E::Scalar>()
+}
+
+/// Returns the number of bytes of a folding with shape `S`: the sum of the two instances' `Z`,
+/// the cross term, and the folded witness and error vector
+fn folding_bytes<E: Engine>(S: &R1CSShape<E>) -> usize {
+  (2 * S.num_vars + S.num_io + 1 + 2 * S.num_cons) * mem::size_of::<E::Scalar>()
+}
+
+/// Returns the number of bytes of the sum-check tables of Spartan's prover for shape `S`, which
+/// it pads to a square, power-of-two shape
+fn sumcheck_bytes<E: Engine>(S: &R1CSShape<E>) -> usize {
+  let m = S.num_vars.max(S.num_cons).max(S.num_io).next_power_of_two();
+  // the witness, z, and the inner sum-check tables span up to 11 vectors of m elements, and the
+  // error vector along with the outer sum-check tables span up to 7 more
+  18 * m * mem::size_of::<E::Scalar>()
+}
+
+/// Returns the number of bytes that the GPU backend stages for the largest MSM with shape `S`
+fn gpu_bytes<E: Engine>(S: &R1CSShape<E>) -> usize {
+  if msm_backend() == MsmBackend::Cpu || !E::capabilities().gpu_msm {
+    return 0;
+  }
+  // each scalar is staged along with its base, an affine point of two coordinates
+  S.num_vars.max(S.num_cons) * (mem::size_of::<E::Scalar>() + 2 * mem::size_of::<E::Base>())
+}
+
+impl<E1, E2, C> PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Returns the estimated peak allocations of proving steps and compressing recursive SNARKs with
+  /// these public parameters, which provers check against `RecursiveSNARK::set_memory_limit`
+  pub fn memory_estimate(&self) -> MemoryEstimate {
+    let (primary, secondary) = (&self.r1cs_shape_primary, &self.r1cs_shape_secondary);
+    MemoryEstimate {
+      witness: witness_bytes(primary) + witness_bytes(secondary),
+      cross_terms: folding_bytes(primary) + folding_bytes(secondary),
+      // the two Spartan provers run concurrently
+      sumcheck: sumcheck_bytes(primary) + sumcheck_bytes(secondary),
+      gpu_buffers: gpu_bytes(primary).max(gpu_bytes(secondary)),
+    }
+  }
+
+  /// Returns the estimated peak allocations of compressing with `budget`
+  pub(crate) fn compress_memory(&self, budget: MemoryBudget) -> usize {
+    let estimate = self.memory_estimate();
+    let sumcheck = match budget {
+      // the provers stream their tables to stay within the budget, each of them, or reject it
+      MemoryBudget::Bytes(bytes) => estimate.sumcheck.min(bytes.saturating_mul(2)),
+      MemoryBudget::Unlimited => estimate.sumcheck,
+    };
+    MemoryEstimate {
+      sumcheck,
+      ..estimate
+    }
+    .compress()
+  }
+}
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Limits the estimated allocations (see `PublicParams::memory_estimate`) of proving steps of the
+  /// recursive SNARK and of compressing it, in bytes. The limit is not serialized.
+  pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+    self.memory_limit = limit;
+  }
+
+  /// Returns the limit on the estimated allocations of proving, if one is set
+  pub fn memory_limit(&self) -> Option<usize> {
+    self.memory_limit
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    nova::CompressedSNARK,
+    provider::{ipa_pc, PallasEngine, VestaEngine},
+    spartan::snark::RelaxedR1CSSNARK,
+    traits::{circuit::NonTrivialCircuit, snark::default_ck_hint},
+  };
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+  type C = NonTrivialCircuit<F>;
+  type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+
+  #[test]
+  fn test_memory_estimate() {
+    let circuit = C::new(4);
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+
+    // the estimate grows with the circuit
+    let estimate = pp.memory_estimate();
+    assert!(estimate.witness > 0 && estimate.cross_terms > 0 && estimate.sumcheck > 0);
+    let larger =
+      PublicParams::<E1, E2, C>::setup(&C::new(64), &*default_ck_hint(), &*default_ck_hint())
+        .unwrap()
+        .memory_estimate();
+    assert!(larger.prove_step() > estimate.prove_step());
+    assert!(larger.compress() > estimate.compress());
+
+    let z0 = [F::from(2)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    let (pk, _vk) = CompressedSNARK::<_, _, _, S<E1>, S<E2>>::setup(&pp).unwrap();
+
+    // provers fail early when their estimate exceeds the limit, and leave the SNARK unchanged
+    recursive_snark.set_memory_limit(Some(estimate.prove_step() - 1));
+    assert_eq!(
+      recursive_snark.prove_step(&pp, &circuit),
+      Err(NovaError::MemoryLimitExceeded {
+        required: estimate.prove_step(),
+        available: estimate.prove_step() - 1,
+      })
+    );
+    recursive_snark.set_memory_limit(Some(estimate.compress() - 1));
+    assert!(matches!(
+      CompressedSNARK::prove(&pp, &pk, &recursive_snark),
+      Err(NovaError::MemoryLimitExceeded { .. })
+    ));
+
+    recursive_snark.set_memory_limit(Some(estimate.prove_step().max(estimate.compress())));
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    assert!(CompressedSNARK::prove(&pp, &pk, &recursive_snark).is_ok());
+    assert!(recursive_snark.verify(&pp, 2, &z0).is_ok());
+  }
+}
@@ -2,38 +2,99 @@
 
 use crate::{
   constants::NUM_HASH_BITS,
-  digest::{DigestComputer, SimpleDigestible},
+  digest::{reproducibility_digest, DigestComputer, SimpleDigestible},
   errors::NovaError,
   frontend::{
-    r1cs::{NovaShape, NovaWitness},
+    r1cs::{
+      into_r1cs_instance_and_witness, r1cs_instance_and_witness_streaming, NovaShape, NovaWitness,
+    },
     shape_cs::ShapeCS,
     solver::SatisfyingAssignment,
     ConstraintSystem, SynthesisError,
   },
-  gadgets::utils::{base_as_scalar, scalar_as_base},
+  gadgets::utils::scalar_as_base,
+  provider::timing::phase,
   r1cs::{
     CommitmentKeyHint, R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance,
     RelaxedR1CSWitness,
   },
   traits::{
-    circuit::{StepCircuit, TrivialCircuit},
-    commitment::CommitmentEngineTrait,
-    snark::RelaxedR1CSSNARKTrait,
+    calldata::{CalldataDecoder, CalldataEncoder, CalldataEncoding},
+    circuit::{PaddedCircuit, StepCircuit, TrivialCircuit, WitnessSource},
+    commitment::{CommitmentEngineTrait, Len},
+    snark::{MemoryBudget, RelaxedR1CSSNARKTrait},
     AbsorbInROTrait, Engine, ROConstants, ROConstantsCircuit, ROTrait,
   },
-  CommitmentKey, DerandKey,
+  Commitment, CommitmentKey,
 };
 use core::marker::PhantomData;
 use ff::Field;
 use once_cell::sync::OnceCell;
 use rand_core::OsRng;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
+mod accumulate;
+mod advisor;
+mod background;
+mod batch;
+mod builder;
+mod cancel;
 mod circuit;
+mod fixed;
+mod inspect;
+mod ivc;
+mod light;
+#[cfg(test)]
+mod malleability;
+mod memory;
 pub(crate) mod nifs;
-
-use circuit::{NovaAugmentedCircuit, NovaAugmentedCircuitInputs};
+mod openings;
+mod params;
+mod pool;
+mod report;
+mod rollover;
+mod segment;
+mod setup_cache;
+mod single_curve;
+mod stop;
+
+pub use accumulate::{AccumulationParams, Accumulator};
+pub use advisor::{ShapeAdvice, ShapeAdvisor, ShapeSuggestion};
+pub use background::BackgroundCompression;
+pub use builder::{PublicParamsBuilder, SetupStage};
+use cancel::check_cancelled;
+pub use cancel::CancellationToken;
+use circuit::{witness_cache::WitnessCache, NovaAugmentedCircuit, NovaAugmentedCircuitInputs};
+pub use fixed::FixedCircuitProver;
+pub use inspect::Inspection;
+pub use ivc::{Bn256Cycle, Cycle, CycleScalar, Ivc, IvcProof, PastaCycle};
+pub use light::LightVerifier;
+use memory::check_memory_limit;
+pub use memory::MemoryEstimate;
 use nifs::{NIFSRelaxed, NIFS};
+pub use openings::{CommittedPoly, CommittedWitness, Opening};
+pub use params::{ProverParams, VerifierParams};
+pub use pool::{ProverPool, ProverSession, SessionId};
+pub use report::{CheckOutcome, CheckStatus, VerificationReport};
+pub use rollover::{rollover_digest, RolloverLink};
+use segment::ConstantSegment;
+pub use setup_cache::SetupCache;
+pub use single_curve::{PublicParamsSingleCurve, RecursiveSNARKSingleCurve};
+pub use stop::StopCondition;
+
+/// Describes what `PublicParams::setup_from` reused from the previous public parameters
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SetupReuse {
+  /// The number of leading constraints of the primary circuit that are unchanged
+  pub shared_constraints_primary: usize,
+  /// Whether the shape of the primary circuit is unchanged
+  pub shape_primary_unchanged: bool,
+  /// Whether the commitment key of the primary circuit was reused
+  pub reused_ck_primary: bool,
+  /// Whether the commitment key of the secondary circuit was reused
+  pub reused_ck_secondary: bool,
+}
 
 /// A type that holds public parameters of Nova
 #[derive(Serialize, Deserialize)]
@@ -88,6 +149,8 @@ where
   /// implementing `RelaxedR1CSSNARKTrait::ck_floor()`, which can be passed to this function.
   ///
   /// If you're not using such a SNARK, pass `nova_snark::traits::snark::default_ck_hint()` instead.
+  /// Alternatively, the named presets in `nova_snark::traits::snark::CommitmentKeyHintPreset`
+  /// can be passed via `CommitmentKeyHintPreset::into_hint`.
   ///
   /// # Arguments
   ///
@@ -122,42 +185,150 @@ where
     ck_hint1: &CommitmentKeyHint<E1>,
     ck_hint2: &CommitmentKeyHint<E2>,
   ) -> Result<Self, NovaError> {
-    let ro_consts_primary: ROConstants<E1> = ROConstants::<E1>::default();
-    let ro_consts_secondary: ROConstants<E2> = ROConstants::<E2>::default();
+    Self::setup_with(c, ck_hint1, ck_hint2, None, None).map(|(pp, _)| pp)
+  }
+
+  /// Creates a new `PublicParams` for a circuit `C`, reusing the commitment keys of `prev`,
+  /// which were set up for a (possibly different) circuit, whenever they are large enough.
+  ///
+  /// This is meant for iterating on a step circuit during development: generating commitment keys
+  /// dominates the cost of `setup` for large circuits, and changes to later gadgets of a circuit
+  /// often leave the required key sizes unchanged. The returned `SetupReuse` reports what was
+  /// reused and how many leading constraints the new circuit shares with the previous one.
+  pub fn setup_from<C2: StepCircuit<E1::Scalar>>(
+    prev: &PublicParams<E1, E2, C2>,
+    c: &C,
+    ck_hint1: &CommitmentKeyHint<E1>,
+    ck_hint2: &CommitmentKeyHint<E2>,
+  ) -> Result<(Self, SetupReuse), NovaError> {
+    Self::setup_with(
+      c,
+      ck_hint1,
+      ck_hint2,
+      Some((&prev.r1cs_shape_primary, &prev.ck_primary)),
+      Some((&prev.r1cs_shape_secondary, &prev.ck_secondary)),
+    )
+  }
+
+  /// Creates a new `PublicParams` for a circuit `C` whose secondary circuit commits with
+  /// `ck_secondary` rather than with a key derived from a fixed label, e.g., with a key derived
+  /// from a public ceremony that auditors can re-derive (see `CommitmentKey::from_beacon` and
+  /// `PublicParams::ck_secondary`). Returns `NovaError::InvalidCommitmentKeyLength` if the key
+  /// has fewer generators than the secondary circuit requires.
+  pub fn setup_with_ck_secondary(
+    c: &C,
+    ck_hint1: &CommitmentKeyHint<E1>,
+    ck_hint2: &CommitmentKeyHint<E2>,
+    ck_secondary: CommitmentKey<E2>,
+  ) -> Result<Self, NovaError> {
+    let (r1cs_shape_primary, r1cs_shape_secondary) = Self::synthesize_setup_shapes(c)?;
+    if ck_secondary.length() < r1cs_shape_secondary.commitment_key_size(ck_hint2) {
+      return Err(NovaError::InvalidCommitmentKeyLength);
+    }
+    let ck_primary = r1cs_shape_primary.commitment_key(ck_hint1);
+
+    Ok(Self::from_parts(
+      c.arity(),
+      r1cs_shape_primary,
+      ck_primary,
+      r1cs_shape_secondary,
+      ck_secondary,
+    ))
+  }
+
+  fn setup_with(
+    c: &C,
+    ck_hint1: &CommitmentKeyHint<E1>,
+    ck_hint2: &CommitmentKeyHint<E2>,
+    prev_primary: Option<(&R1CSShape<E1>, &CommitmentKey<E1>)>,
+    prev_secondary: Option<(&R1CSShape<E2>, &CommitmentKey<E2>)>,
+  ) -> Result<(Self, SetupReuse), NovaError> {
+    let (r1cs_shape_primary, r1cs_shape_secondary) = Self::synthesize_setup_shapes(c)?;
+
+    // Reuse previous commitment keys if they have enough generators, and generate new ones otherwise
+    let reusable_ck_primary = prev_primary
+      .map(|(_, ck)| ck)
+      .filter(|ck| ck.length() >= r1cs_shape_primary.commitment_key_size(ck_hint1));
+    let reusable_ck_secondary = prev_secondary
+      .map(|(_, ck)| ck)
+      .filter(|ck| ck.length() >= r1cs_shape_secondary.commitment_key_size(ck_hint2));
+
+    let reuse = SetupReuse {
+      shared_constraints_primary: prev_primary
+        .map_or(0, |(S, _)| S.common_prefix_len(&r1cs_shape_primary)),
+      shape_primary_unchanged: prev_primary.is_some_and(|(S, _)| *S == r1cs_shape_primary),
+      reused_ck_primary: reusable_ck_primary.is_some(),
+      reused_ck_secondary: reusable_ck_secondary.is_some(),
+    };
+
+    let ck_primary = reusable_ck_primary
+      .cloned()
+      .unwrap_or_else(|| r1cs_shape_primary.commitment_key(ck_hint1));
+    let ck_secondary = reusable_ck_secondary
+      .cloned()
+      .unwrap_or_else(|| r1cs_shape_secondary.commitment_key(ck_hint2));
+
+    let pp = Self::from_parts(
+      c.arity(),
+      r1cs_shape_primary,
+      ck_primary,
+      r1cs_shape_secondary,
+      ck_secondary,
+    );
 
-    let F_arity = c.arity();
+    Ok((pp, reuse))
+  }
 
-    // ro_consts_circuit_primary are parameterized by E2 because the type alias uses E2::Base = E1::Scalar
-    let ro_consts_circuit_primary: ROConstantsCircuit<E2> = ROConstantsCircuit::<E2>::default();
-    let ro_consts_circuit_secondary: ROConstantsCircuit<E1> = ROConstantsCircuit::<E1>::default();
+  /// Synthesizes the shapes of the augmented circuits like `synthesize_shapes`, except that circuits
+  /// that fail to synthesize are reported as `NovaError::InvalidStepCircuitIO`, as `setup` does
+  fn synthesize_setup_shapes(c: &C) -> Result<(R1CSShape<E1>, R1CSShape<E2>), NovaError> {
+    Self::synthesize_shapes(c).map_err(|e| match e {
+      NovaError::SynthesisError { .. } => NovaError::InvalidStepCircuitIO,
+      e => e,
+    })
+  }
 
-    // Initialize ck for the primary
+  /// Synthesizes the shapes of the primary and secondary augmented circuits for a circuit `C`
+  pub(crate) fn synthesize_shapes(c: &C) -> Result<(R1CSShape<E1>, R1CSShape<E2>), NovaError> {
+    // Initialize shape for the primary
     let circuit_primary: NovaAugmentedCircuit<'_, E2, C> =
-      NovaAugmentedCircuit::new(true, None, c, ro_consts_circuit_primary.clone());
+      NovaAugmentedCircuit::new(true, None, c, ROConstantsCircuit::<E2>::default());
     let mut cs: ShapeCS<E1> = ShapeCS::new();
-    let _ = circuit_primary.synthesize(&mut cs);
-    let (r1cs_shape_primary, ck_primary) = cs.r1cs_shape(ck_hint1);
+    circuit_primary.synthesize(&mut cs)?;
+    let r1cs_shape_primary = cs.r1cs_shape_without_ck();
 
-    // Initialize ck for the secondary
+    // Initialize shape for the secondary
     let tc = TrivialCircuit::<E2::Scalar>::default();
     let circuit_secondary: NovaAugmentedCircuit<'_, E1, _> =
-      NovaAugmentedCircuit::new(false, None, &tc, ro_consts_circuit_secondary.clone());
+      NovaAugmentedCircuit::new(false, None, &tc, ROConstantsCircuit::<E1>::default());
     let mut cs: ShapeCS<E2> = ShapeCS::new();
-    let _ = circuit_secondary.synthesize(&mut cs);
-    let (r1cs_shape_secondary, ck_secondary) = cs.r1cs_shape(ck_hint2);
+    circuit_secondary.synthesize(&mut cs)?;
+    let r1cs_shape_secondary = cs.r1cs_shape_without_ck();
 
     if r1cs_shape_primary.num_io != 2 || r1cs_shape_secondary.num_io != 2 {
       return Err(NovaError::InvalidStepCircuitIO);
     }
 
+    Ok((r1cs_shape_primary, r1cs_shape_secondary))
+  }
+
+  /// Assembles public parameters from the shapes of the augmented circuits and their commitment keys
+  fn from_parts(
+    F_arity: usize,
+    r1cs_shape_primary: R1CSShape<E1>,
+    ck_primary: CommitmentKey<E1>,
+    r1cs_shape_secondary: R1CSShape<E2>,
+    ck_secondary: CommitmentKey<E2>,
+  ) -> Self {
     let pp = PublicParams {
       F_arity,
 
-      ro_consts_primary,
-      ro_consts_circuit_primary,
+      ro_consts_primary: ROConstants::<E1>::default(),
+      // ro_consts_circuit_primary are parameterized by E2 because the type alias uses E2::Base = E1::Scalar
+      ro_consts_circuit_primary: ROConstantsCircuit::<E2>::default(),
 
-      ro_consts_secondary,
-      ro_consts_circuit_secondary,
+      ro_consts_secondary: ROConstants::<E2>::default(),
+      ro_consts_circuit_secondary: ROConstantsCircuit::<E1>::default(),
 
       ck_primary,
       r1cs_shape_primary,
@@ -172,7 +343,7 @@ where
     // call pp.digest() so the digest is computed here rather than in RecursiveSNARK methods
     let _ = pp.digest();
 
-    Ok(pp)
+    pp
   }
 
   /// Retrieve the digest of the public parameters.
@@ -184,6 +355,15 @@ where
       .expect("Failure in retrieving digest")
   }
 
+  /// Returns the SHA3-256 digest of the serialized public parameters, including the commitment
+  /// keys, so that builds of the public parameters on different machines can be checked to be
+  /// byte-identical by comparing 32 bytes, whatever the number of threads they were built with.
+  /// Only commitment keys derived from a label are reproducible; those sampled from a random
+  /// trapdoor, as HyperKZG's `setup` does, differ across builds.
+  pub fn reproducibility_digest(&self) -> [u8; 32] {
+    reproducibility_digest(self)
+  }
+
   /// Returns the number of constraints in the primary and secondary circuits
   pub const fn num_constraints(&self) -> (usize, usize) {
     (
@@ -199,6 +379,103 @@ where
       self.r1cs_shape_secondary.num_vars,
     )
   }
+
+  /// Returns the number of generators that the commitment keys of the primary and secondary
+  /// circuits need for the public parameters to be compressed with the SNARKs `S1` and `S2`, i.e.,
+  /// how large the keys must be regenerated if `CompressedSNARK::setup` reports
+  /// `NovaError::CommitmentKeyTooSmall`
+  pub fn required_ck_sizes<S1, S2>(&self) -> (usize, usize)
+  where
+    S1: RelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<E2>,
+  {
+    (
+      self
+        .r1cs_shape_primary
+        .commitment_key_size(&*S1::ck_floor()),
+      self
+        .r1cs_shape_secondary
+        .commitment_key_size(&*S2::ck_floor()),
+    )
+  }
+
+  /// Checks that the commitment keys are large enough for the SNARKs `S1` and `S2` (see
+  /// `required_ck_sizes`)
+  fn check_ck_sizes<S1, S2>(&self) -> Result<(), NovaError>
+  where
+    S1: RelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<E2>,
+  {
+    let (required_primary, required_secondary) = self.required_ck_sizes::<S1, S2>();
+    for (circuit, length, required) in [
+      ("primary", self.ck_primary.length(), required_primary),
+      ("secondary", self.ck_secondary.length(), required_secondary),
+    ] {
+      if length < required {
+        return Err(NovaError::CommitmentKeyTooSmall {
+          circuit: circuit.to_string(),
+          length,
+          required,
+        });
+      }
+    }
+    Ok(())
+  }
+
+  /// Returns the commitment key of the primary circuit, e.g., to set up an evaluation engine that
+  /// opens a `CommittedWitness`
+  pub fn ck_primary(&self) -> &CommitmentKey<E1> {
+    &self.ck_primary
+  }
+
+  /// Returns the commitment key of the secondary circuit, e.g., to check that its generators were
+  /// derived from a public ceremony (see `CommitmentKey::verify_beacon`)
+  pub fn ck_secondary(&self) -> &CommitmentKey<E2> {
+    &self.ck_secondary
+  }
+
+  /// Converts both commitment keys, once, into the representation preferred by the MSM backend in
+  /// use (see `CommitmentEngineTrait::prepare`), which speeds up the commitments of every step
+  pub fn prepare_commitment_keys(&self) {
+    E1::CE::prepare(&self.ck_primary);
+    E2::CE::prepare(&self.ck_secondary);
+  }
+}
+
+/// How every step of the recursion synthesizes its circuits
+struct StepContext {
+  // whether assignments are allocated up front at the size of the shapes and moved into the witness
+  preallocate: bool,
+}
+
+impl StepContext {
+  fn new(preallocate: bool) -> Self {
+    Self { preallocate }
+  }
+
+  /// Runs `synthesize` on a fresh witness generator and returns the instance and witness it
+  /// produces for `shape`, along with the value returned by `synthesize`. The commitment to the
+  /// witness reuses the commitment to `segment`, if any.
+  fn synthesize<E: Engine, T>(
+    &self,
+    shape: &R1CSShape<E>,
+    ck: &CommitmentKey<E>,
+    segment: Option<&ConstantSegment<E>>,
+    synthesize: impl FnOnce(&mut SatisfyingAssignment<E>) -> Result<T, SynthesisError>,
+  ) -> Result<(R1CSInstance<E>, R1CSWitness<E>, T), NovaError> {
+    let mut cs = if self.preallocate {
+      SatisfyingAssignment::<E>::with_capacity(shape.num_io + 1, shape.num_vars)
+    } else {
+      SatisfyingAssignment::<E>::new()
+    };
+    let res = synthesize(&mut cs)?;
+    let (u, w) = match segment {
+      Some(segment) => segment.r1cs_instance_and_witness(cs, shape, ck)?,
+      None if self.preallocate => into_r1cs_instance_and_witness(cs, shape, ck)?,
+      None => cs.r1cs_instance_and_witness(shape, ck)?,
+    };
+    Ok((u, w, res))
+  }
 }
 
 /// A SNARK that proves the correct execution of an incremental computation
@@ -227,6 +504,21 @@ where
 
   zi: Vec<E1::Scalar>,
 
+  #[serde(skip)]
+  witness_cache: Option<WitnessCache<E1::Scalar>>,
+
+  #[serde(skip)]
+  commit_chunk_size: Option<usize>,
+
+  #[serde(skip)]
+  witnesses_wiped: bool,
+
+  #[serde(skip)]
+  memory_limit: Option<usize>,
+
+  #[serde(skip)]
+  constant_segment: Option<ConstantSegment<E1>>,
+
   _p: PhantomData<C>,
 }
 
@@ -259,15 +551,18 @@ where
       None,
     );
 
+    let mut step_offset = 0;
     let circuit_primary: NovaAugmentedCircuit<'_, E2, C> = NovaAugmentedCircuit::new(
       true,
       Some(inputs_primary),
       c,
       pp.ro_consts_circuit_primary.clone(),
-    );
+    )
+    .with_step_offset(&mut step_offset);
     let zi_primary = circuit_primary.synthesize(&mut cs_primary)?;
     let (u_primary, w_primary) =
       cs_primary.r1cs_instance_and_witness(&pp.r1cs_shape_primary, &pp.ck_primary)?;
+    let constant_segment = Self::constant_segment(pp, c, step_offset, &w_primary.W)?;
 
     // base case for the secondary
     let mut cs_secondary = SatisfyingAssignment::<E2>::new();
@@ -334,23 +629,113 @@ where
 
       zi: zi_primary,
 
+      witness_cache: None,
+
+      commit_chunk_size: None,
+
+      witnesses_wiped: false,
+      memory_limit: None,
+
+      constant_segment,
+
       _p: Default::default(),
     })
   }
 
+  /// Commits to the values that `c` declares constant in `W`, given the offset of the variables of
+  /// `c` in the witness of the augmented circuit
+  fn constant_segment(
+    pp: &PublicParams<E1, E2, C>,
+    c: &C,
+    step_offset: usize,
+    W: &[E1::Scalar],
+  ) -> Result<Option<ConstantSegment<E1>>, NovaError> {
+    let range = c.constant_vars();
+    if range.is_empty() {
+      return Ok(None);
+    }
+    ConstantSegment::new(
+      &pp.ck_primary,
+      W,
+      step_offset + range.start..step_offset + range.end,
+    )
+    .map(Some)
+  }
+
+  /// Enables caching of the step circuit's witness across steps that invoke a circuit with the same
+  /// `StepCircuit::witness_cache_key` on the same inputs, holding at most `max_entries` witnesses.
+  /// The cache trades memory for synthesis time, so it is disabled by default and is not serialized.
+  pub fn enable_witness_cache(&mut self, max_entries: usize) {
+    self.witness_cache = Some(WitnessCache::new(max_entries));
+  }
+
+  /// Disables the witness cache and frees the memory it holds
+  pub fn disable_witness_cache(&mut self) {
+    self.witness_cache = None;
+  }
+
+  /// Commits to the primary witness in chunks of `chunk_size` assignments on a background thread
+  /// while the rest of the witness is being synthesized, rather than after synthesis completes
+  pub fn enable_streaming_commit(&mut self, chunk_size: usize) {
+    self.commit_chunk_size = Some(chunk_size);
+  }
+
+  /// Returns the number of steps whose witness was replayed from the cache
+  pub fn witness_cache_hits(&self) -> usize {
+    self.witness_cache.as_ref().map_or(0, |cache| cache.hits())
+  }
+
   /// Updates the provided `RecursiveSNARK` by executing a step of the incremental computation
   pub fn prove_step(&mut self, pp: &PublicParams<E1, E2, C>, c: &C) -> Result<(), NovaError> {
+    self.prove_step_with(pp, c, &StepContext::new(false), None, None)
+  }
+
+  /// Updates the provided `RecursiveSNARK` by executing a step of the incremental computation,
+  /// taking the witness of the step circuit from `source` instead of synthesizing `c`.
+  ///
+  /// The witness of the augmented circuit is checked against its shape before it is folded,
+  /// so an assignment that does not satisfy the step circuit is rejected with
+  /// `NovaError::IncorrectWitness` and leaves the `RecursiveSNARK` unchanged. As with `prove_step`,
+  /// the first step is the one executed by `RecursiveSNARK::new`, which synthesizes `c`.
+  pub fn prove_step_with_witness(
+    &mut self,
+    pp: &PublicParams<E1, E2, C>,
+    c: &C,
+    source: &dyn WitnessSource<E1::Scalar>,
+  ) -> Result<(), NovaError> {
+    self.prove_step_with(pp, c, &StepContext::new(false), Some(source), None)
+  }
+
+  /// Executes a step of the incremental computation, synthesizing its circuits as `ctx` says,
+  /// checking `token` between its phases
+  fn prove_step_with(
+    &mut self,
+    pp: &PublicParams<E1, E2, C>,
+    c: &C,
+    ctx: &StepContext,
+    source: Option<&dyn WitnessSource<E1::Scalar>>,
+    token: Option<&CancellationToken>,
+  ) -> Result<(), NovaError> {
+    let _phase = phase!("prove_step");
+    if self.witnesses_wiped {
+      return Err(NovaError::WitnessWiped);
+    }
+    check_cancelled(token)?;
+
     // first step was already done in the constructor
     if self.i == 0 {
       self.i = 1;
       return Ok(());
     }
 
+    check_memory_limit(pp.memory_estimate().prove_step(), self.memory_limit)?;
+    let pp_digest = pp.digest();
+
     // fold the secondary circuit's instance
     let (nifs_secondary, (r_U_secondary, r_W_secondary)) = NIFS::prove(
       &pp.ck_secondary,
       &pp.ro_consts_secondary,
-      &scalar_as_base::<E1>(pp.digest()),
+      &scalar_as_base::<E1>(pp_digest),
       &pp.r1cs_shape_secondary,
       &self.r_U_secondary,
       &self.r_W_secondary,
@@ -360,35 +745,52 @@ where
 
     let r_next_primary = E1::Scalar::random(&mut OsRng);
 
-    let mut cs_primary = SatisfyingAssignment::<E1>::new();
-    let inputs_primary: NovaAugmentedCircuitInputs<E2> = NovaAugmentedCircuitInputs::new(
-      scalar_as_base::<E1>(pp.digest()),
-      E1::Scalar::from(self.i as u64),
-      self.z0.to_vec(),
-      Some(self.zi.clone()),
-      Some(self.r_U_secondary.clone()),
-      Some(self.ri_primary),
-      r_next_primary,
-      Some(self.l_u_secondary.clone()),
-      Some(nifs_secondary.comm_T),
-    );
+    let inputs_primary = self.inputs_primary(pp_digest, &nifs_secondary, r_next_primary);
+    check_cancelled(token)?;
 
+    let mut step_offset = 0;
     let circuit_primary: NovaAugmentedCircuit<'_, E2, C> = NovaAugmentedCircuit::new(
       true,
       Some(inputs_primary),
       c,
       pp.ro_consts_circuit_primary.clone(),
-    );
-    let zi_primary = circuit_primary.synthesize(&mut cs_primary)?;
+    )
+    .with_witness_cache(self.witness_cache.as_mut())
+    .with_witness_source(source)
+    .with_step_offset(&mut step_offset);
+    let (l_u_primary, l_w_primary, zi_primary) = match self.commit_chunk_size {
+      Some(chunk_size) => r1cs_instance_and_witness_streaming(
+        &pp.r1cs_shape_primary,
+        &pp.ck_primary,
+        chunk_size,
+        |cs| circuit_primary.synthesize(cs),
+      )?,
+      None => ctx.synthesize(
+        &pp.r1cs_shape_primary,
+        &pp.ck_primary,
+        self.constant_segment.as_ref(),
+        |cs| circuit_primary.synthesize(cs),
+      )?,
+    };
+    // the segment is not serialized, so it is committed to again after deserialization
+    let constant_segment = match self.constant_segment {
+      Some(_) => None,
+      None => Self::constant_segment(pp, c, step_offset, &l_w_primary.W)?,
+    };
 
-    let (l_u_primary, l_w_primary) =
-      cs_primary.r1cs_instance_and_witness(&pp.r1cs_shape_primary, &pp.ck_primary)?;
+    // an external witness is not produced by the constraint system, so it is checked explicitly
+    if source.is_some() {
+      pp.r1cs_shape_primary
+        .check_witness(&l_u_primary.X, &l_w_primary.W)
+        .map_err(|_| NovaError::IncorrectWitness)?;
+    }
+    check_cancelled(token)?;
 
     // fold the primary circuit's instance
     let (nifs_primary, (r_U_primary, r_W_primary)) = NIFS::prove(
       &pp.ck_primary,
       &pp.ro_consts_primary,
-      &pp.digest(),
+      &pp_digest,
       &pp.r1cs_shape_primary,
       &self.r_U_primary,
       &self.r_W_primary,
@@ -398,18 +800,8 @@ where
 
     let r_next_secondary = E2::Scalar::random(&mut OsRng);
 
-    let mut cs_secondary = SatisfyingAssignment::<E2>::new();
-    let inputs_secondary: NovaAugmentedCircuitInputs<E1> = NovaAugmentedCircuitInputs::new(
-      pp.digest(),
-      E2::Scalar::from(self.i as u64),
-      vec![E2::Scalar::ZERO],
-      Some(vec![E2::Scalar::ZERO]),
-      Some(self.r_U_primary.clone()),
-      Some(self.ri_secondary),
-      r_next_secondary,
-      Some(l_u_primary),
-      Some(nifs_primary.comm_T),
-    );
+    let inputs_secondary =
+      self.inputs_secondary(pp_digest, l_u_primary, &nifs_primary, r_next_secondary);
 
     let tc = TrivialCircuit::<E2::Scalar>::default();
     let circuit_secondary: NovaAugmentedCircuit<'_, E1, _> = NovaAugmentedCircuit::new(
@@ -418,20 +810,36 @@ where
       &tc,
       pp.ro_consts_circuit_secondary.clone(),
     );
-    let _ = circuit_secondary.synthesize(&mut cs_secondary)?;
-
-    let (l_u_secondary, l_w_secondary) = cs_secondary
-      .r1cs_instance_and_witness(&pp.r1cs_shape_secondary, &pp.ck_secondary)
-      .map_err(|_e| NovaError::UnSat {
-        reason: "Unable to generate a satisfying witness on the secondary curve".to_string(),
+    let (l_u_secondary, l_w_secondary, _) = ctx
+      .synthesize(&pp.r1cs_shape_secondary, &pp.ck_secondary, None, |cs| {
+        circuit_secondary.synthesize(cs)
+      })
+      .map_err(|e| match e {
+        NovaError::SynthesisError { .. } => e,
+        _ => NovaError::UnSat {
+          reason: "Unable to generate a satisfying witness on the secondary curve".to_string(),
+        },
       })?;
+    check_cancelled(token)?;
 
-    // update the running instances and witnesses
-    self.zi = zi_primary
+    let zi = zi_primary
       .iter()
       .map(|v| v.get_value().ok_or(SynthesisError::AssignmentMissing))
       .collect::<Result<Vec<<E1 as Engine>::Scalar>, _>>()?;
 
+    // wipe the witnesses that are superseded below, once nothing can fail anymore
+    #[cfg(feature = "zeroize")]
+    {
+      let mut l_w_primary = l_w_primary;
+      l_w_primary.wipe();
+      self.l_w_secondary.wipe();
+      self.r_W_primary.wipe();
+      self.r_W_secondary.wipe();
+    }
+
+    // update the running instances and witnesses
+    self.zi = zi;
+
     self.l_u_secondary = l_u_secondary;
     self.l_w_secondary = l_w_secondary;
 
@@ -446,9 +854,56 @@ where
     self.ri_primary = r_next_primary;
     self.ri_secondary = r_next_secondary;
 
+    if constant_segment.is_some() {
+      self.constant_segment = constant_segment;
+    }
+
     Ok(())
   }
 
+  /// Returns the inputs of the primary augmented circuit of the next step, which folds the
+  /// running secondary instance with `nifs_secondary`
+  fn inputs_primary(
+    &self,
+    pp_digest: E1::Scalar,
+    nifs_secondary: &NIFS<E2>,
+    r_next_primary: E1::Scalar,
+  ) -> NovaAugmentedCircuitInputs<E2> {
+    NovaAugmentedCircuitInputs::new(
+      scalar_as_base::<E1>(pp_digest),
+      E1::Scalar::from(self.i as u64),
+      self.z0.to_vec(),
+      Some(self.zi.clone()),
+      Some(self.r_U_secondary.clone()),
+      Some(self.ri_primary),
+      r_next_primary,
+      Some(self.l_u_secondary.clone()),
+      Some(nifs_secondary.comm_T),
+    )
+  }
+
+  /// Returns the inputs of the secondary augmented circuit of the next step, which folds
+  /// `l_u_primary` into the running primary instance with `nifs_primary`
+  fn inputs_secondary(
+    &self,
+    pp_digest: E1::Scalar,
+    l_u_primary: R1CSInstance<E1>,
+    nifs_primary: &NIFS<E1>,
+    r_next_secondary: E2::Scalar,
+  ) -> NovaAugmentedCircuitInputs<E1> {
+    NovaAugmentedCircuitInputs::new(
+      pp_digest,
+      E2::Scalar::from(self.i as u64),
+      vec![E2::Scalar::ZERO],
+      Some(vec![E2::Scalar::ZERO]),
+      Some(self.r_U_primary.clone()),
+      Some(self.ri_secondary),
+      r_next_secondary,
+      Some(l_u_primary),
+      Some(nifs_primary.comm_T),
+    )
+  }
+
   /// Verify the correctness of the `RecursiveSNARK`
   pub fn verify(
     &self,
@@ -456,28 +911,75 @@ where
     num_steps: usize,
     z0: &[E1::Scalar],
   ) -> Result<Vec<E1::Scalar>, NovaError> {
+    self
+      .verify_with(pp, num_steps, z0, VerificationReport::fail_fast())
+      .into_result()
+      .map(|_| self.zi.clone())
+  }
+
+  /// Verifies the `RecursiveSNARK` like `verify`, but performs every check it can rather than
+  /// stopping at the first failure, and returns a report of the outcome of each check
+  /// along with the intermediate digests
+  pub fn verify_detailed(
+    &self,
+    pp: &PublicParams<E1, E2, C>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+  ) -> VerificationReport {
+    self.verify_with(pp, num_steps, z0, VerificationReport::default())
+  }
+
+  /// Performs the checks of `verify` and records them in `report`, skipping the costly ones once
+  /// a check failed if `report` stops at the first failure
+  fn verify_with(
+    &self,
+    pp: &PublicParams<E1, E2, C>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+    mut report: VerificationReport,
+  ) -> VerificationReport {
+    let _phase = phase!("verify");
+    report.record_digest("pp_digest", &pp.digest());
+
+    let invalid_steps_or_inputs = || NovaError::ProofVerifyError {
+      reason: "Invalid number of steps or inputs".to_string(),
+    };
+    let check = |cond: bool| {
+      if cond {
+        Ok(())
+      } else {
+        Err(invalid_steps_or_inputs())
+      }
+    };
+
     // number of steps cannot be zero
-    let is_num_steps_zero = num_steps == 0;
+    report.record("num_steps_nonzero", check(num_steps != 0));
 
     // check if the provided proof has executed num_steps
-    let is_num_steps_not_match = self.i != num_steps;
+    report.record("num_steps_match", check(self.i == num_steps));
 
     // check if the initial inputs match
-    let is_inputs_not_match = self.z0 != z0;
+    report.record("z0_match", check(self.z0 == z0));
 
     // check if the (relaxed) R1CS instances have two public outputs
-    let is_instance_has_two_outputs = self.l_u_secondary.X.len() != 2
-      || self.r_U_primary.X.len() != 2
-      || self.r_U_secondary.X.len() != 2;
-
-    if is_num_steps_zero
-      || is_num_steps_not_match
-      || is_inputs_not_match
-      || is_instance_has_two_outputs
-    {
-      return Err(NovaError::ProofVerifyError {
-        reason: "Invalid number of steps or inputs".to_string(),
-      });
+    let has_two_outputs = report.record(
+      "instance_io_len",
+      check(
+        self.l_u_secondary.X.len() == 2
+          && self.r_U_primary.X.len() == 2
+          && self.r_U_secondary.X.len() == 2,
+      ),
+    );
+
+    if !has_two_outputs || report.halted() {
+      report.skip_all(&[
+        "hash_primary",
+        "hash_secondary",
+        "sat_running_primary",
+        "sat_running_secondary",
+        "sat_last_secondary",
+      ]);
+      return report;
     }
 
     // check if the output hashes in R1CS instances point to the right running instances
@@ -507,13 +1009,34 @@ where
         hasher2.squeeze(NUM_HASH_BITS),
       )
     };
+    report.record_digest("hash_primary", &hash_primary);
+    report.record_digest("hash_secondary", &hash_secondary);
+
+    let invalid_hash = |cond: bool| {
+      if cond {
+        Ok(())
+      } else {
+        Err(NovaError::ProofVerifyError {
+          reason: "Invalid output hash in R1CS instances".to_string(),
+        })
+      }
+    };
+    report.record(
+      "hash_primary",
+      invalid_hash(hash_primary == scalar_as_base::<E2>(self.l_u_secondary.X[0])),
+    );
+    report.record(
+      "hash_secondary",
+      invalid_hash(hash_secondary == self.l_u_secondary.X[1]),
+    );
 
-    if hash_primary != scalar_as_base::<E2>(self.l_u_secondary.X[0])
-      || hash_secondary != self.l_u_secondary.X[1]
-    {
-      return Err(NovaError::ProofVerifyError {
-        reason: "Invalid output hash in R1CS instances".to_string(),
-      });
+    if report.halted() {
+      report.skip_all(&[
+        "sat_running_primary",
+        "sat_running_secondary",
+        "sat_last_secondary",
+      ]);
+      return report;
     }
 
     // check the satisfiability of the provided instances
@@ -542,12 +1065,11 @@ where
       },
     );
 
-    // check the returned res objects
-    res_r_primary?;
-    res_r_secondary?;
-    res_l_secondary?;
+    report.record("sat_running_primary", res_r_primary);
+    report.record("sat_running_secondary", res_r_secondary);
+    report.record("sat_last_secondary", res_l_secondary);
 
-    Ok(self.zi.clone())
+    report
   }
 
   /// Get the outputs after the last step of computation.
@@ -559,6 +1081,96 @@ where
   pub fn num_steps(&self) -> usize {
     self.i
   }
+
+  /// Overwrites the witnesses held by the recursive SNARK, including their blinds and any cached
+  /// step-circuit witnesses, with zeros.
+  ///
+  /// This is meant to be called once the recursive SNARK has been compressed, so that no witness
+  /// data lingers in memory. Afterwards, the outputs and the number of steps remain available, but
+  /// `prove_step` and `CompressedSNARK::prove` return `NovaError::WitnessWiped`, and `verify` fails
+  /// since it checks the witnesses.
+  pub fn wipe_witnesses(&mut self) {
+    self.r_W_primary.wipe();
+    self.r_W_secondary.wipe();
+    self.l_w_secondary.wipe();
+    if let Some(cache) = self.witness_cache.as_mut() {
+      cache.wipe();
+    }
+    self.witnesses_wiped = true;
+  }
+
+  /// Returns a branch of the recursive SNARK that continues from the current step independently.
+  ///
+  /// The branch holds its own copy of the running instances and witnesses, so proving steps on
+  /// either the branch or `self` leaves the other unchanged, and each of them verifies with its
+  /// own number of steps. Both keep using the same `PublicParams`, which are only read while
+  /// proving. The witness cache and the streaming commit setting carry over to the branch.
+  ///
+  /// Note that the first step is synthesized by `RecursiveSNARK::new`, so branches taken before the
+  /// first call to `prove_step` diverge from the second step onwards. Returns
+  /// `NovaError::WitnessWiped` if the witnesses were wiped, since such a branch could not be extended.
+  pub fn fork(&self) -> Result<Self, NovaError> {
+    if self.witnesses_wiped {
+      return Err(NovaError::WitnessWiped);
+    }
+
+    Ok(self.clone())
+  }
+
+  /// Verifies recursive SNARKs for the same public parameters, e.g., snapshots of a chain taken
+  /// at several checkpoints, and returns the outcome of `verify` for each `(num_steps, z0, snark)`
+  /// in the order provided.
+  ///
+  /// The commitment keys are prepared for the MSM backend once for all checks (see
+  /// `PublicParams::prepare_commitment_keys`), and the checks run in parallel.
+  pub fn verify_many(
+    pp: &PublicParams<E1, E2, C>,
+    checkpoints: &[(usize, &[E1::Scalar], &Self)],
+  ) -> Vec<Result<Vec<E1::Scalar>, NovaError>> {
+    pp.prepare_commitment_keys();
+    checkpoints
+      .par_iter()
+      .map(|(num_steps, z0, snark)| snark.verify(pp, *num_steps, z0))
+      .collect()
+  }
+}
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, PaddedCircuit<E1::Scalar, C>>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Pads the computation with `k` no-op steps of `c`, which leave `z_i` unchanged.
+  ///
+  /// Since all no-op steps have the same input, the witness of the step circuit is generated once
+  /// and replayed for the remaining steps, regardless of whether the witness cache is enabled.
+  pub fn prove_noop_steps(
+    &mut self,
+    pp: &PublicParams<E1, E2, PaddedCircuit<E1::Scalar, C>>,
+    c: &C,
+    k: usize,
+  ) -> Result<(), NovaError> {
+    let noop = PaddedCircuit::noop(c.clone());
+    let cache = self
+      .witness_cache
+      .replace(WitnessCache::for_fixed_circuit(1));
+    let res = (0..k).try_for_each(|_| self.prove_step(pp, &noop));
+    self.witness_cache = cache;
+    res
+  }
+}
+
+#[cfg(feature = "zeroize")]
+impl<E1, E2, C> zeroize::Zeroize for RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  fn zeroize(&mut self) {
+    self.wipe_witnesses();
+  }
 }
 
 /// A type that holds the prover key for `CompressedSNARK`
@@ -578,7 +1190,7 @@ where
 }
 
 /// A type that holds the verifier key for `CompressedSNARK`
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct VerifierKey<E1, E2, C, S1, S2>
 where
@@ -588,34 +1200,80 @@ where
   S1: RelaxedR1CSSNARKTrait<E1>,
   S2: RelaxedR1CSSNARKTrait<E2>,
 {
-  F_arity: usize,
-  ro_consts_primary: ROConstants<E1>,
-  ro_consts_secondary: ROConstants<E2>,
-  pp_digest: E1::Scalar,
-  vk_primary: S1::VerifierKey,
-  vk_secondary: S2::VerifierKey,
-  dk_primary: DerandKey<E1>,
-  dk_secondary: DerandKey<E2>,
+  light: LightVerifier<E1, E2, S1, S2>,
   _p: PhantomData<C>,
 }
 
-/// A SNARK that proves the knowledge of a valid `RecursiveSNARK`
-#[derive(Clone, Serialize, Deserialize)]
-#[serde(bound = "")]
-pub struct CompressedSNARK<E1, E2, C, S1, S2>
+impl<E1, E2, C, S1, S2> Clone for VerifierKey<E1, E2, C, S1, S2>
 where
   E1: Engine<Base = <E2 as Engine>::Scalar>,
   E2: Engine<Base = <E1 as Engine>::Scalar>,
   C: StepCircuit<E1::Scalar>,
   S1: RelaxedR1CSSNARKTrait<E1>,
   S2: RelaxedR1CSSNARKTrait<E2>,
+  LightVerifier<E1, E2, S1, S2>: Clone,
 {
-  r_U_secondary: RelaxedR1CSInstance<E2>,
-  ri_secondary: E2::Scalar,
-  l_u_secondary: R1CSInstance<E2>,
-  nifs_Uf_secondary: NIFS<E2>,
-
-  l_ur_secondary: RelaxedR1CSInstance<E2>,
+  fn clone(&self) -> Self {
+    Self {
+      light: self.light.clone(),
+      _p: PhantomData,
+    }
+  }
+}
+
+impl<E1, E2, C, S1, S2> ProverKey<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Returns the SHA3-256 digest of the serialized prover key, see
+  /// `PublicParams::reproducibility_digest`
+  pub fn reproducibility_digest(&self) -> [u8; 32] {
+    reproducibility_digest(self)
+  }
+}
+
+impl<E1, E2, C, S1, S2> VerifierKey<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Returns the SHA3-256 digest of the serialized verifier key, see
+  /// `PublicParams::reproducibility_digest`
+  pub fn reproducibility_digest(&self) -> [u8; 32] {
+    reproducibility_digest(self)
+  }
+  /// Precomputes, once, what every `CompressedSNARK::verify` with the key derives from its fixed
+  /// elements, such as the digests of the keys of the SNARKs and the tables of their evaluation
+  /// engines. This suits servers that verify many proofs for the same circuit.
+  pub fn preprocess(&self) {
+    self.light.preprocess();
+  }
+}
+
+/// A SNARK that proves the knowledge of a valid `RecursiveSNARK`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CompressedSNARK<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  r_U_secondary: RelaxedR1CSInstance<E2>,
+  ri_secondary: E2::Scalar,
+  l_u_secondary: R1CSInstance<E2>,
+  nifs_Uf_secondary: NIFS<E2>,
+
+  l_ur_secondary: RelaxedR1CSInstance<E2>,
   nifs_Un_secondary: NIFSRelaxed<E2>,
 
   r_U_primary: RelaxedR1CSInstance<E1>,
@@ -636,6 +1294,74 @@ where
   _p: PhantomData<C>,
 }
 
+/// Encodes a `CompressedSNARK` into calldata (see `nova_snark::traits::calldata` for the layout)
+impl<E1, E2, C, S1, S2> CalldataEncoding for CompressedSNARK<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1> + CalldataEncoding,
+  S2: RelaxedR1CSSNARKTrait<E2> + CalldataEncoding,
+  E1::Scalar: CalldataEncoding,
+  E2::Scalar: CalldataEncoding,
+  Commitment<E1>: CalldataEncoding,
+  Commitment<E2>: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.r_U_secondary.encode(enc);
+    self.ri_secondary.encode(enc);
+    self.l_u_secondary.encode(enc);
+    self.nifs_Uf_secondary.encode(enc);
+
+    self.l_ur_secondary.encode(enc);
+    self.nifs_Un_secondary.encode(enc);
+
+    self.r_U_primary.encode(enc);
+    self.ri_primary.encode(enc);
+    self.l_ur_primary.encode(enc);
+    self.nifs_Un_primary.encode(enc);
+
+    self.wit_blind_r_Wn_primary.encode(enc);
+    self.err_blind_r_Wn_primary.encode(enc);
+    self.wit_blind_r_Wn_secondary.encode(enc);
+    self.err_blind_r_Wn_secondary.encode(enc);
+
+    self.snark_primary.encode(enc);
+    self.snark_secondary.encode(enc);
+
+    self.zn.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      r_U_secondary: CalldataEncoding::decode(dec)?,
+      ri_secondary: CalldataEncoding::decode(dec)?,
+      l_u_secondary: CalldataEncoding::decode(dec)?,
+      nifs_Uf_secondary: CalldataEncoding::decode(dec)?,
+
+      l_ur_secondary: CalldataEncoding::decode(dec)?,
+      nifs_Un_secondary: CalldataEncoding::decode(dec)?,
+
+      r_U_primary: CalldataEncoding::decode(dec)?,
+      ri_primary: CalldataEncoding::decode(dec)?,
+      l_ur_primary: CalldataEncoding::decode(dec)?,
+      nifs_Un_primary: CalldataEncoding::decode(dec)?,
+
+      wit_blind_r_Wn_primary: CalldataEncoding::decode(dec)?,
+      err_blind_r_Wn_primary: CalldataEncoding::decode(dec)?,
+      wit_blind_r_Wn_secondary: CalldataEncoding::decode(dec)?,
+      err_blind_r_Wn_secondary: CalldataEncoding::decode(dec)?,
+
+      snark_primary: CalldataEncoding::decode(dec)?,
+      snark_secondary: CalldataEncoding::decode(dec)?,
+
+      zn: CalldataEncoding::decode(dec)?,
+
+      _p: PhantomData,
+    })
+  }
+}
+
 impl<E1, E2, C, S1, S2> CompressedSNARK<E1, E2, C, S1, S2>
 where
   E1: Engine<Base = <E2 as Engine>::Scalar>,
@@ -648,6 +1374,10 @@ where
   pub fn setup(
     pp: &PublicParams<E1, E2, C>,
   ) -> Result<(ProverKey<E1, E2, C, S1, S2>, VerifierKey<E1, E2, C, S1, S2>), NovaError> {
+    // check that the public parameters were generated with a large enough commitment key,
+    // e.g., using `CommitmentKeyHintPreset::for_compressed_snark`
+    pp.check_ck_sizes::<S1, S2>()?;
+
     let (pk_primary, vk_primary) = S1::setup(&pp.ck_primary, &pp.r1cs_shape_primary)?;
     let (pk_secondary, vk_secondary) = S2::setup(&pp.ck_secondary, &pp.r1cs_shape_secondary)?;
 
@@ -658,14 +1388,17 @@ where
     };
 
     let vk = VerifierKey {
-      F_arity: pp.F_arity,
-      ro_consts_primary: pp.ro_consts_primary.clone(),
-      ro_consts_secondary: pp.ro_consts_secondary.clone(),
-      pp_digest: pp.digest(),
-      vk_primary,
-      vk_secondary,
-      dk_primary: E1::CE::derand_key(&pp.ck_primary),
-      dk_secondary: E2::CE::derand_key(&pp.ck_secondary),
+      light: LightVerifier {
+        F_arity: pp.F_arity,
+        ro_consts_primary: pp.ro_consts_primary.clone(),
+        ro_consts_secondary: pp.ro_consts_secondary.clone(),
+        pp_digest: pp.digest(),
+        vk_primary,
+        vk_secondary,
+        dk_primary: E1::CE::derand_key(&pp.ck_primary),
+        dk_secondary: E2::CE::derand_key(&pp.ck_secondary),
+        stop_conditions: Vec::new(),
+      },
       _p: Default::default(),
     };
 
@@ -678,6 +1411,62 @@ where
     pk: &ProverKey<E1, E2, C, S1, S2>,
     recursive_snark: &RecursiveSNARK<E1, E2, C>,
   ) -> Result<Self, NovaError> {
+    Self::prove_with_budget(pp, pk, recursive_snark, MemoryBudget::Unlimited)
+  }
+
+  /// Create a new `CompressedSNARK` like `prove`, but with each of the two SNARKs keeping its
+  /// intermediate tables within `budget` (see `RelaxedR1CSSNARKTrait::prove_with_budget`).
+  ///
+  /// The two SNARKs are produced concurrently, so the prover may use up to twice the budget.
+  pub fn prove_with_budget(
+    pp: &PublicParams<E1, E2, C>,
+    pk: &ProverKey<E1, E2, C, S1, S2>,
+    recursive_snark: &RecursiveSNARK<E1, E2, C>,
+    budget: MemoryBudget,
+  ) -> Result<Self, NovaError> {
+    Self::prove_with(pp, pk, recursive_snark, budget, None)
+  }
+
+  /// Creates a new `CompressedSNARK` within `budget`, checking `token` between the phases of
+  /// proving
+  fn prove_with(
+    pp: &PublicParams<E1, E2, C>,
+    pk: &ProverKey<E1, E2, C, S1, S2>,
+    recursive_snark: &RecursiveSNARK<E1, E2, C>,
+    budget: MemoryBudget,
+    token: Option<&CancellationToken>,
+  ) -> Result<Self, NovaError> {
+    Self::prove_committed(pp, pk, recursive_snark, budget, token).map(|(snark, _)| snark)
+  }
+
+  /// Creates a new `CompressedSNARK` like `prove`, and returns along with it the witness of the
+  /// folded primary instance that it proves, which opens the polynomials that the SNARK commits to
+  /// at other points (see `CommittedWitness`)
+  pub fn prove_with_openings(
+    pp: &PublicParams<E1, E2, C>,
+    pk: &ProverKey<E1, E2, C, S1, S2>,
+    recursive_snark: &RecursiveSNARK<E1, E2, C>,
+  ) -> Result<(Self, CommittedWitness<E1>), NovaError> {
+    Self::prove_committed(pp, pk, recursive_snark, MemoryBudget::Unlimited, None)
+  }
+
+  fn prove_committed(
+    pp: &PublicParams<E1, E2, C>,
+    pk: &ProverKey<E1, E2, C, S1, S2>,
+    recursive_snark: &RecursiveSNARK<E1, E2, C>,
+    budget: MemoryBudget,
+    token: Option<&CancellationToken>,
+  ) -> Result<(Self, CommittedWitness<E1>), NovaError> {
+    let _phase = phase!("compress");
+    if recursive_snark.witnesses_wiped {
+      return Err(NovaError::WitnessWiped);
+    }
+    // `pk` may have been set up from other public parameters, so the keys of `pp` are checked
+    // again rather than letting the SNARKs fail deep in proving
+    pp.check_ck_sizes::<S1, S2>()?;
+    check_memory_limit(pp.compress_memory(budget), recursive_snark.memory_limit)?;
+    check_cancelled(token)?;
+
     // prove three foldings
 
     // fold secondary U/W with secondary u/w to get Uf/Wf
@@ -691,6 +1480,7 @@ where
       &recursive_snark.l_u_secondary,
       &recursive_snark.l_w_secondary,
     )?;
+    check_cancelled(token)?;
 
     // fold Uf/Wf with random inst/wit to get U1/W1
     let (l_ur_secondary, l_wr_secondary) = pp
@@ -707,6 +1497,7 @@ where
       &l_ur_secondary,
       &l_wr_secondary,
     )?;
+    check_cancelled(token)?;
 
     // fold primary U/W with random inst/wit to get U2/W2
     let (l_ur_primary, l_wr_primary) = pp
@@ -740,30 +1531,48 @@ where
       &wit_blind_r_Wn_secondary,
       &err_blind_r_Wn_secondary,
     );
+    check_cancelled(token)?;
 
     // create SNARKs proving the knowledge of Wn primary/secondary
     let (snark_primary, snark_secondary) = rayon::join(
       || {
-        S1::prove(
+        S1::prove_with_budget(
           &pp.ck_primary,
           &pk.pk_primary,
           &pp.r1cs_shape_primary,
           &derandom_r_Un_primary,
           &derandom_r_Wn_primary,
+          budget,
         )
       },
       || {
-        S2::prove(
+        S2::prove_with_budget(
           &pp.ck_secondary,
           &pk.pk_secondary,
           &pp.r1cs_shape_secondary,
           &derandom_r_Un_secondary,
           &derandom_r_Wn_secondary,
+          budget,
         )
       },
     );
 
-    Ok(Self {
+    #[cfg(feature = "zeroize")]
+    {
+      for mut W in [
+        r_Wf_secondary,
+        l_wr_secondary,
+        r_Wn_secondary,
+        derandom_r_Wn_secondary,
+      ] {
+        W.wipe();
+      }
+      for mut W in [l_wr_primary, r_Wn_primary] {
+        W.wipe();
+      }
+    }
+
+    let snark = Self {
       r_U_secondary: recursive_snark.r_U_secondary.clone(),
       ri_secondary: recursive_snark.ri_secondary,
       l_u_secondary: recursive_snark.l_u_secondary.clone(),
@@ -788,7 +1597,11 @@ where
       zn: recursive_snark.zi.clone(),
 
       _p: Default::default(),
-    })
+    };
+    Ok((
+      snark,
+      CommittedWitness::new(derandom_r_Un_primary, derandom_r_Wn_primary),
+    ))
   }
 
   /// Verify the correctness of the `CompressedSNARK` (provides zero-knowledge)
@@ -798,116 +1611,19 @@ where
     num_steps: usize,
     z0: &[E1::Scalar],
   ) -> Result<Vec<E1::Scalar>, NovaError> {
-    // the number of steps cannot be zero
-    if num_steps == 0 {
-      return Err(NovaError::ProofVerifyError {
-        reason: "Number of steps cannot be zero".to_string(),
-      });
-    }
-
-    // check if the (relaxed) R1CS instances have two public outputs
-    if self.l_u_secondary.X.len() != 2
-      || self.r_U_primary.X.len() != 2
-      || self.r_U_secondary.X.len() != 2
-      || self.l_ur_primary.X.len() != 2
-      || self.l_ur_secondary.X.len() != 2
-    {
-      return Err(NovaError::ProofVerifyError {
-        reason: "Invalid number of outputs in R1CS instances".to_string(),
-      });
-    }
-
-    // check if the output hashes in R1CS instances point to the right running instances
-    let (hash_primary, hash_secondary) = {
-      let mut hasher = <E2 as Engine>::RO::new(vk.ro_consts_secondary.clone());
-      hasher.absorb(vk.pp_digest);
-      hasher.absorb(E1::Scalar::from(num_steps as u64));
-      for e in z0 {
-        hasher.absorb(*e);
-      }
-      for e in &self.zn {
-        hasher.absorb(*e);
-      }
-      self.r_U_secondary.absorb_in_ro(&mut hasher);
-      hasher.absorb(self.ri_primary);
-
-      let mut hasher2 = <E1 as Engine>::RO::new(vk.ro_consts_primary.clone());
-      hasher2.absorb(scalar_as_base::<E1>(vk.pp_digest));
-      hasher2.absorb(E2::Scalar::from(num_steps as u64));
-      hasher2.absorb(E2::Scalar::ZERO);
-      hasher2.absorb(E2::Scalar::ZERO);
-      self.r_U_primary.absorb_in_ro(&mut hasher2);
-      hasher2.absorb(self.ri_secondary);
-
-      (
-        hasher.squeeze(NUM_HASH_BITS),
-        hasher2.squeeze(NUM_HASH_BITS),
-      )
-    };
-
-    if hash_primary != base_as_scalar::<E1>(self.l_u_secondary.X[0])
-      || hash_secondary != self.l_u_secondary.X[1]
-    {
-      return Err(NovaError::ProofVerifyError {
-        reason: "Invalid output hash in R1CS instances".to_string(),
-      });
-    }
-
-    // fold secondary U/W with secondary u/w to get Uf/Wf
-    let r_Uf_secondary = self.nifs_Uf_secondary.verify(
-      &vk.ro_consts_secondary,
-      &scalar_as_base::<E1>(vk.pp_digest),
-      &self.r_U_secondary,
-      &self.l_u_secondary,
-    )?;
-
-    // fold Uf/Wf with random inst/wit to get U1/W1
-    let r_Un_secondary = self.nifs_Un_secondary.verify(
-      &vk.ro_consts_secondary,
-      &scalar_as_base::<E1>(vk.pp_digest),
-      &r_Uf_secondary,
-      &self.l_ur_secondary,
-    )?;
-
-    // fold primary U/W with random inst/wit to get U2/W2
-    let r_Un_primary = self.nifs_Un_primary.verify(
-      &vk.ro_consts_primary,
-      &vk.pp_digest,
-      &self.r_U_primary,
-      &self.l_ur_primary,
-    )?;
-
-    // derandomize/unblind commitments
-    let derandom_r_Un_primary = r_Un_primary.derandomize(
-      &vk.dk_primary,
-      &self.wit_blind_r_Wn_primary,
-      &self.err_blind_r_Wn_primary,
-    );
-    let derandom_r_Un_secondary = r_Un_secondary.derandomize(
-      &vk.dk_secondary,
-      &self.wit_blind_r_Wn_secondary,
-      &self.err_blind_r_Wn_secondary,
-    );
-
-    // check the satisfiability of the folded instances using
-    // SNARKs proving the knowledge of their satisfying witnesses
-    let (res_primary, res_secondary) = rayon::join(
-      || {
-        self
-          .snark_primary
-          .verify(&vk.vk_primary, &derandom_r_Un_primary)
-      },
-      || {
-        self
-          .snark_secondary
-          .verify(&vk.vk_secondary, &derandom_r_Un_secondary)
-      },
-    );
-
-    res_primary?;
-    res_secondary?;
+    vk.light.verify(self, num_steps, z0)
+  }
 
-    Ok(self.zn.clone())
+  /// Verifies the `CompressedSNARK` like `verify`, but performs every check it can rather than
+  /// stopping at the first failure, and returns a report of the outcome of each check
+  /// along with the intermediate digests
+  pub fn verify_detailed(
+    &self,
+    vk: &VerifierKey<E1, E2, C, S1, S2>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+  ) -> VerificationReport {
+    vk.light.verify_detailed(self, num_steps, z0)
   }
 }
 
@@ -915,16 +1631,25 @@ where
 mod tests {
   use super::*;
   use crate::{
-    frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+    frontend::{num::AllocatedNum, AllocatedBit, ConstraintSystem, SynthesisError},
+    gadgets::merkle,
     provider::{
-      pedersen::CommitmentKeyExtTrait, traits::DlogGroup, Bn256EngineIPA, Bn256EngineKZG,
-      GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine, VestaEngine,
+      pedersen::CommitmentKeyExtTrait,
+      poseidon::{PoseidonConstantsCircuit, PoseidonRO, PoseidonROCircuit},
+      traits::DlogGroup,
+      Bn256EngineIPA, Bn256EngineKZG, GrumpkinEngine, PallasEngine, Secp256k1Engine,
+      Secq256k1Engine, VestaEngine,
+    },
+    traits::{
+      circuit::{StepAssignment, TrivialCircuit, WithStateTree, WithStepInputs},
+      evaluation::EvaluationEngineTrait,
+      snark::{default_ck_hint, CommitmentKeyHintPreset},
+      ROCircuitTrait,
     },
-    traits::{circuit::TrivialCircuit, evaluation::EvaluationEngineTrait, snark::default_ck_hint},
   };
   use core::{fmt::Write, marker::PhantomData};
   use expect_test::{expect, Expect};
-  use ff::PrimeField;
+  use ff::{PrimeField, PrimeFieldBits};
 
   type EE<E> = crate::provider::ipa_pc::EvaluationEngine<E>;
   type EEPrime<E> = crate::provider::hyperkzg::EvaluationEngine<E>;
@@ -1118,6 +1843,567 @@ mod tests {
     test_ivc_nontrivial_with::<Secp256k1Engine, Secq256k1Engine>();
   }
 
+  #[derive(Clone, Debug, Default)]
+  struct SquareCircuit<F: PrimeField> {
+    _p: PhantomData<F>,
+  }
+
+  impl<F: PrimeField> StepCircuit<F> for SquareCircuit<F> {
+    fn arity(&self) -> usize {
+      1
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      Ok(vec![z[0].square(cs.namespace(|| "x_sq"))?])
+    }
+
+    fn witness_cache_key(&self) -> Option<Vec<u8>> {
+      Some(b"SquareCircuit".to_vec())
+    }
+  }
+
+  #[test]
+  fn test_ivc_witness_cache() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type F = <E1 as Engine>::Scalar;
+
+    let circuit = SquareCircuit::<F>::default();
+    let pp = PublicParams::<E1, E2, SquareCircuit<F>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    let num_steps = 5;
+    for (z0, expected_hits) in [(F::ONE, num_steps - 2), (F::from(2u64), 0)] {
+      let mut recursive_snark =
+        RecursiveSNARK::<E1, E2, SquareCircuit<F>>::new(&pp, &circuit, &[z0]).unwrap();
+      recursive_snark.enable_witness_cache(4);
+
+      for _i in 0..num_steps {
+        recursive_snark.prove_step(&pp, &circuit).unwrap();
+      }
+
+      // the first step is proven in `new` and the second one is the first to populate the cache,
+      // after which a fixed point of the circuit hits the cache on every step
+      assert_eq!(recursive_snark.witness_cache_hits(), expected_hits);
+      assert!(recursive_snark.verify(&pp, num_steps, &[z0]).is_ok());
+    }
+  }
+
+  /// Computes the assignment of `CubicCircuit` natively, optionally with an incorrect output
+  struct CubicWitness {
+    tamper: bool,
+  }
+
+  impl<F: PrimeField> WitnessSource<F> for CubicWitness {
+    fn assignment(&self, z: &[F]) -> Result<StepAssignment<F>, SynthesisError> {
+      let x = z[0];
+      let y = x * x * x + x + F::from(5u64) + F::from(self.tamper as u64);
+      Ok(StepAssignment {
+        aux: vec![x * x, x * x * x, y],
+        outputs: vec![2],
+      })
+    }
+  }
+
+  #[test]
+  fn test_ivc_external_witness() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type F = <E1 as Engine>::Scalar;
+
+    let circuit = CubicCircuit::<F>::default();
+    let pp = PublicParams::<E1, E2, CubicCircuit<F>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    let num_steps = 3;
+    let z0 = [F::ONE];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    let mut expected = z0.to_vec();
+    for _i in 0..num_steps {
+      recursive_snark
+        .prove_step_with_witness(&pp, &circuit, &CubicWitness { tamper: false })
+        .unwrap();
+      expected = circuit.output(&expected);
+    }
+    assert_eq!(recursive_snark.outputs(), expected.as_slice());
+    recursive_snark.verify(&pp, num_steps, &z0).unwrap();
+
+    // an assignment that does not satisfy the step circuit is rejected before it is folded
+    assert!(matches!(
+      recursive_snark.prove_step_with_witness(&pp, &circuit, &CubicWitness { tamper: true }),
+      Err(NovaError::IncorrectWitness)
+    ));
+    recursive_snark.verify(&pp, num_steps, &z0).unwrap();
+  }
+
+  #[test]
+  fn test_ivc_step_inputs() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type F = <E1 as Engine>::Scalar;
+    type C = WithStepInputs<F, CubicCircuit<F>>;
+
+    let constants = std::sync::Arc::new(PoseidonConstantsCircuit::<F>::default());
+    let step = |inputs: Vec<F>| C::new(CubicCircuit::default(), inputs, constants.clone());
+    let pp = PublicParams::<E1, E2, C>::setup(
+      &step(vec![F::ZERO; 2]),
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    // each step absorbs two values, such as a block hash and a timestamp
+    let num_steps = 3;
+    let inputs = (0..num_steps)
+      .map(|i| vec![F::from(100 + i as u64), F::from(200 + i as u64)])
+      .collect::<Vec<_>>();
+    let z0 = C::z0(&[F::ONE]);
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &step(inputs[0].clone()), &z0).unwrap();
+    for x in &inputs {
+      recursive_snark.prove_step(&pp, &step(x.clone())).unwrap();
+    }
+    let zn = recursive_snark.verify(&pp, num_steps, &z0).unwrap();
+
+    let mut expected = vec![F::ONE];
+    for _ in 0..num_steps {
+      expected = CubicCircuit::default().output(&expected);
+    }
+    assert_eq!(
+      C::check_outputs(&constants, &zn, &inputs).unwrap(),
+      expected
+    );
+
+    // a verifier with different per-step values rejects the proof
+    let mut tampered = inputs.clone();
+    tampered[1][0] += F::ONE;
+    assert!(C::check_outputs(&constants, &zn, &tampered).is_err());
+    assert!(C::check_outputs(&constants, &zn, &inputs[..2]).is_err());
+  }
+
+  #[test]
+  fn test_ivc_state_tree() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type F = <E1 as Engine>::Scalar;
+    type C = WithStateTree<F, CubicCircuit<F>>;
+
+    let depth = 4;
+    let constants = std::sync::Arc::new(PoseidonConstantsCircuit::<F>::default());
+    let circuit = C::new(CubicCircuit::default(), depth, constants.clone());
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+
+    let num_steps = 5;
+    let z0 = C::z0(&constants, depth, &[F::ONE]);
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..num_steps {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let zn = recursive_snark.verify(&pp, num_steps, &z0).unwrap();
+
+    // the tree holds the hashes of the states z_0, ..., z_{n-1} of the wrapped computation
+    let mut states = vec![vec![F::ONE]];
+    for _ in 0..num_steps {
+      states.push(CubicCircuit::default().output(states.last().unwrap()));
+    }
+    let (zn_inner, tree) = C::split_outputs(&constants, depth, &zn).unwrap();
+    assert_eq!(zn_inner, states[num_steps]);
+    assert_eq!(tree.num_leaves(), num_steps as u64);
+
+    // any intermediate state is proven against the root alone
+    let root = tree.root(&constants);
+    let leaves = states[..num_steps]
+      .iter()
+      .map(|z| C::leaf(&constants, z))
+      .collect::<Vec<_>>();
+    let path = merkle::merkle_path(&constants, depth, &leaves, 2).unwrap();
+    assert!(merkle::verify_path(&constants, &root, &leaves[2], 2, &path));
+    let other = C::leaf(&constants, &[F::from(42)]);
+    assert!(!merkle::verify_path(&constants, &root, &other, 2, &path));
+
+    // a root that does not match the frontier is rejected
+    let mut tampered = zn.clone();
+    *tampered.last_mut().unwrap() += F::ONE;
+    assert!(C::split_outputs(&constants, depth, &tampered).is_err());
+  }
+
+  #[test]
+  fn test_ivc_streaming_commit() {
+    type E1 = Bn256EngineKZG;
+    type E2 = GrumpkinEngine;
+    type C = CubicCircuit<<E1 as Engine>::Scalar>;
+
+    let circuit = C::default();
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+
+    let z0 = [<E1 as Engine>::Scalar::ZERO];
+    let mut recursive_snark = RecursiveSNARK::<E1, E2, C>::new(&pp, &circuit, &z0).unwrap();
+    recursive_snark.enable_streaming_commit(1000);
+
+    let num_steps = 3;
+    for _i in 0..num_steps {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+
+    let zn = recursive_snark.verify(&pp, num_steps, &z0).unwrap();
+    assert_eq!(zn, vec![<E1 as Engine>::Scalar::from(2460515u64)]);
+  }
+
+  #[test]
+  fn test_verify_detailed() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type C = CubicCircuit<<E1 as Engine>::Scalar>;
+    type S1 = S<E1, EE<E1>>;
+    type S2 = S<E2, EE<E2>>;
+
+    let circuit = C::default();
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+
+    let z0 = [<E1 as Engine>::Scalar::ZERO];
+    let mut recursive_snark = RecursiveSNARK::<E1, E2, C>::new(&pp, &circuit, &z0).unwrap();
+    for _i in 0..2 {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+
+    let report = recursive_snark.verify_detailed(&pp, 2, &z0);
+    assert!(report.is_ok());
+    assert_eq!(report.checks().len(), 9);
+    assert_eq!(report.digests().len(), 3);
+
+    // a wrong initial input is pinpointed, while the remaining checks are still performed
+    let z0_wrong = [<E1 as Engine>::Scalar::ONE];
+    let report = recursive_snark.verify_detailed(&pp, 2, &z0_wrong);
+    assert!(matches!(
+      report.status("z0_match"),
+      Some(CheckStatus::Failed(_))
+    ));
+    assert!(matches!(
+      report.status("hash_primary"),
+      Some(CheckStatus::Failed(_))
+    ));
+    assert_eq!(
+      report.status("sat_running_primary"),
+      Some(&CheckStatus::Passed)
+    );
+    assert_eq!(
+      report.into_result(),
+      recursive_snark.verify(&pp, 2, &z0_wrong).map(|_| ())
+    );
+
+    // verify stops at the first failure, without checking the satisfiability of the instances
+    let report = recursive_snark.verify_with(&pp, 2, &z0_wrong, VerificationReport::fail_fast());
+    assert_eq!(
+      report.status("sat_running_primary"),
+      Some(&CheckStatus::Skipped)
+    );
+
+    let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+    let compressed_snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+    assert!(compressed_snark.verify_detailed(&vk, 2, &z0).is_ok());
+
+    let report = compressed_snark.verify_detailed(&vk, 3, &z0);
+    assert!(matches!(
+      report.status("hash_primary"),
+      Some(CheckStatus::Failed(_))
+    ));
+    assert_eq!(report.status("snark_primary"), Some(&CheckStatus::Passed));
+    assert!(compressed_snark.verify(&vk, 3, &z0).is_err());
+  }
+
+  #[test]
+  fn test_ivc_noop_padding() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type F = <E1 as Engine>::Scalar;
+    type C = PaddedCircuit<F, CubicCircuit<F>>;
+
+    let circuit = CubicCircuit::<F>::default();
+    let pp = PublicParams::<E1, E2, C>::setup(
+      &PaddedCircuit::step(circuit.clone()),
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    let z0 = [F::ZERO];
+    let step = PaddedCircuit::step(circuit.clone());
+    let mut recursive_snark = RecursiveSNARK::<E1, E2, C>::new(&pp, &step, &z0).unwrap();
+    for _i in 0..2 {
+      recursive_snark.prove_step(&pp, &step).unwrap();
+    }
+    recursive_snark.prove_noop_steps(&pp, &circuit, 3).unwrap();
+
+    // the padding steps count towards the number of steps but leave the output unchanged
+    let zn = recursive_snark.verify(&pp, 5, &z0).unwrap();
+    assert_eq!(zn, circuit.output(&circuit.output(&z0)));
+  }
+
+  #[derive(Clone, Debug, Default)]
+  struct AddCircuit<F: PrimeField> {
+    c: F,
+  }
+
+  impl<F: PrimeField> StepCircuit<F> for AddCircuit<F> {
+    fn arity(&self) -> usize {
+      1
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      let c = AllocatedNum::alloc(cs.namespace(|| "c"), || Ok(self.c))?;
+      Ok(vec![z[0].add(cs.namespace(|| "z + c"), &c)?])
+    }
+  }
+
+  #[test]
+  fn test_fork() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type F = <E1 as Engine>::Scalar;
+
+    let add = |c: u64| AddCircuit { c: F::from(c) };
+    let pp = PublicParams::<E1, E2, AddCircuit<F>>::setup(
+      &add(0),
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    let z0 = [F::ZERO];
+    let mut trunk = RecursiveSNARK::new(&pp, &add(1), &z0).unwrap();
+    for _ in 0..2 {
+      trunk.prove_step(&pp, &add(1)).unwrap();
+    }
+
+    // both branches continue from step 2 with different circuits
+    let mut branch_a = trunk.fork().unwrap();
+    let mut branch_b = trunk.fork().unwrap();
+    for _ in 0..2 {
+      branch_a.prove_step(&pp, &add(10)).unwrap();
+    }
+    branch_b.prove_step(&pp, &add(100)).unwrap();
+
+    assert_eq!(branch_a.verify(&pp, 4, &z0).unwrap(), vec![F::from(22u64)]);
+    assert_eq!(branch_b.verify(&pp, 3, &z0).unwrap(), vec![F::from(102u64)]);
+
+    // the trunk is unaffected by its branches and can still be extended
+    assert_eq!(trunk.verify(&pp, 2, &z0).unwrap(), vec![F::from(2u64)]);
+    trunk.prove_step(&pp, &add(1)).unwrap();
+    assert_eq!(trunk.verify(&pp, 3, &z0).unwrap(), vec![F::from(3u64)]);
+
+    // a recursive SNARK whose witnesses were wiped cannot be forked
+    trunk.wipe_witnesses();
+    assert!(matches!(trunk.fork(), Err(NovaError::WitnessWiped)));
+  }
+
+  #[test]
+  fn test_reproducibility_digest() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type C = CubicCircuit<<E1 as Engine>::Scalar>;
+    type S1 = S<E1, EE<E1>>;
+    type S2 = S<E2, EE<E2>>;
+
+    // builds the public parameters and keys on a rayon pool with `num_threads` threads
+    let build = |num_threads: usize| {
+      rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .unwrap()
+        .install(|| {
+          let pp =
+            PublicParams::<E1, E2, C>::setup(&C::default(), &*S1::ck_floor(), &*S2::ck_floor())
+              .unwrap();
+          let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+          (
+            pp.reproducibility_digest(),
+            pk.reproducibility_digest(),
+            vk.reproducibility_digest(),
+          )
+        })
+    };
+
+    let digests = build(1);
+    assert_eq!(build(2), digests);
+    assert_eq!(build(3), digests);
+  }
+
+  #[test]
+  fn test_verify_many() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type F = <E1 as Engine>::Scalar;
+
+    let add = AddCircuit { c: F::ONE };
+    let pp =
+      PublicParams::<E1, E2, AddCircuit<F>>::setup(&add, &*default_ck_hint(), &*default_ck_hint())
+        .unwrap();
+    let z0 = [F::ZERO];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &add, &z0).unwrap();
+    let mut snapshots = Vec::new();
+    for _ in 0..3 {
+      recursive_snark.prove_step(&pp, &add).unwrap();
+      snapshots.push(recursive_snark.fork().unwrap());
+    }
+
+    let checkpoints = snapshots
+      .iter()
+      .enumerate()
+      .map(|(i, snark)| (i + 1, &z0[..], snark))
+      .collect::<Vec<_>>();
+    let res = RecursiveSNARK::verify_many(&pp, &checkpoints);
+    for (i, res) in res.into_iter().enumerate() {
+      assert_eq!(res.unwrap(), vec![F::from(i as u64 + 1)]);
+    }
+
+    // each check fails on its own
+    let wrong_z0 = [F::ONE];
+    let res = RecursiveSNARK::verify_many(
+      &pp,
+      &[
+        (1, &z0[..], &snapshots[0]),
+        (3, &z0[..], &snapshots[1]),
+        (3, &wrong_z0[..], &snapshots[2]),
+        (3, &z0[..], &snapshots[2]),
+      ],
+    );
+    assert!(res[0].is_ok());
+    assert!(res[1].is_err());
+    assert!(res[2].is_err());
+    assert!(res[3].is_ok());
+  }
+
+  #[test]
+  fn test_wipe_witnesses() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type F = <E1 as Engine>::Scalar;
+
+    let circuit = CubicCircuit::<F>::default();
+    let pp = PublicParams::<E1, E2, CubicCircuit<F>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    let z0 = [F::ZERO];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..2 {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let zn = recursive_snark.verify(&pp, 2, &z0).unwrap();
+
+    recursive_snark.wipe_witnesses();
+    assert!(recursive_snark
+      .r_W_primary
+      .W
+      .iter()
+      .all(|w| w.is_zero_vartime()));
+    assert!(recursive_snark
+      .r_W_primary
+      .E
+      .to_dense()
+      .iter()
+      .all(|e| e.is_zero_vartime()));
+    assert!(recursive_snark.r_W_secondary.r_W.is_zero_vartime());
+    assert!(recursive_snark
+      .l_w_secondary
+      .W
+      .iter()
+      .all(|w| w.is_zero_vartime()));
+
+    // the outputs remain available, but the recursive SNARK can no longer be extended or verified
+    assert_eq!(recursive_snark.outputs(), &zn[..]);
+    assert_eq!(recursive_snark.num_steps(), 2);
+    assert_eq!(
+      recursive_snark.prove_step(&pp, &circuit),
+      Err(NovaError::WitnessWiped)
+    );
+    assert!(recursive_snark.verify(&pp, 2, &z0).is_err());
+  }
+
+  #[test]
+  fn test_ck_hint_presets() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type C = TrivialCircuit<<E1 as Engine>::Scalar>;
+    type S1 = SPrime<E1, EE<E1>>;
+    type S2 = SPrime<E2, EE<E2>>;
+
+    let circuit = C::default();
+
+    // parameters for recursion only are too small for a SNARK with computation commitments
+    let pp = PublicParams::<E1, E2, C>::setup(
+      &circuit,
+      &*CommitmentKeyHintPreset::ForRecursiveOnly.into_hint(),
+      &*CommitmentKeyHintPreset::ForRecursiveOnly.into_hint(),
+    )
+    .unwrap();
+    let (required_primary, required_secondary) = pp.required_ck_sizes::<S1, S2>();
+    assert!(pp.ck_primary.length() < required_primary);
+    assert_eq!(
+      CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).err(),
+      Some(NovaError::CommitmentKeyTooSmall {
+        circuit: "primary".to_string(),
+        length: pp.ck_primary.length(),
+        required: required_primary,
+      })
+    );
+
+    // keys of the reported sizes are large enough
+    let pp_custom = PublicParams::<E1, E2, C>::setup(
+      &circuit,
+      &*CommitmentKeyHintPreset::Custom(required_primary).into_hint(),
+      &*CommitmentKeyHintPreset::Custom(required_secondary).into_hint(),
+    )
+    .unwrap();
+    assert!(CompressedSNARK::<_, _, _, S1, S2>::setup(&pp_custom).is_ok());
+
+    let pp_compressed = PublicParams::<E1, E2, C>::setup(
+      &circuit,
+      &*CommitmentKeyHintPreset::for_compressed_snark::<S1>().into_hint(),
+      &*CommitmentKeyHintPreset::for_compressed_snark::<S2>().into_hint(),
+    )
+    .unwrap();
+    let (pk, _vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp_compressed).unwrap();
+
+    // proving checks the keys too, since the prover key may come from other parameters
+    let z0 = [<E1 as Engine>::Scalar::ZERO];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    assert!(matches!(
+      CompressedSNARK::<_, _, _, S1, S2>::prove(&pp, &pk, &recursive_snark),
+      Err(NovaError::CommitmentKeyTooSmall { .. })
+    ));
+
+    // a custom floor is honored
+    let floor = 1 << 16;
+    let pp = PublicParams::<E1, E2, C>::setup(
+      &circuit,
+      &*CommitmentKeyHintPreset::Custom(floor).into_hint(),
+      &*CommitmentKeyHintPreset::ForRecursiveOnly.into_hint(),
+    )
+    .unwrap();
+    assert!(pp.ck_primary.length() >= floor);
+  }
+
   fn test_ivc_nontrivial_with_compression_with<E1, E2, EE1, EE2>()
   where
     E1: Engine<Base = <E2 as Engine>::Scalar>,
@@ -1257,6 +2543,110 @@ mod tests {
     assert!(res.is_ok());
   }
 
+  #[test]
+  fn test_compressed_snark_calldata() {
+    use crate::traits::calldata::{from_calldata, to_calldata};
+
+    type E1 = Bn256EngineKZG;
+    type E2 = GrumpkinEngine;
+    type CS = CompressedSNARK<E1, E2, TrivialCircuit<F>, S<E1, EEPrime<E1>>, S<E2, EE<E2>>>;
+    type F = <E1 as Engine>::Scalar;
+
+    let circuit = TrivialCircuit::<F>::default();
+    let pp = PublicParams::<E1, E2, TrivialCircuit<F>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    let z0 = [F::from(7u64)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+
+    let (pk, vk) = CS::setup(&pp).unwrap();
+    let compressed_snark = CS::prove(&pp, &pk, &recursive_snark).unwrap();
+
+    // the proof survives a round trip through calldata
+    let calldata = to_calldata(&compressed_snark);
+    let decoded = from_calldata::<CS>(&calldata).unwrap();
+    assert_eq!(to_calldata(&decoded), calldata);
+    assert_eq!(decoded.verify(&vk, 1, &z0).unwrap(), z0.to_vec());
+
+    // the output is stored in the last words as big-endian integers
+    assert_eq!(calldata[calldata.len() - 1], 7);
+    assert_eq!(calldata[calldata.len() - 33], 1);
+
+    // truncated calldata is rejected
+    assert!(matches!(
+      from_calldata::<CS>(&calldata[..calldata.len() - 32]),
+      Err(NovaError::InvalidEncoding { .. })
+    ));
+  }
+
+  #[test]
+  fn test_compressed_snark_memory_budget() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type S1 = S<E1, EE<E1>>;
+    type S2 = S<E2, EE<E2>>;
+    type F = <E1 as Engine>::Scalar;
+
+    let circuit = CubicCircuit::<F>::default();
+    let pp = PublicParams::<E1, E2, CubicCircuit<F>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    let z0 = [F::ONE];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..3 {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+
+    // streaming produces the same SNARK as holding every table
+    let prove = |budget| {
+      S1::prove_with_budget(
+        &pp.ck_primary,
+        &pk.pk_primary,
+        &pp.r1cs_shape_primary,
+        &recursive_snark.r_U_primary,
+        &recursive_snark.r_W_primary,
+        budget,
+      )
+      .unwrap()
+    };
+    assert_eq!(
+      bincode::serialize(&prove(MemoryBudget::Unlimited)).unwrap(),
+      bincode::serialize(&prove(MemoryBudget::Bytes(0))).unwrap()
+    );
+
+    // a budget that no table fits in forces streaming, which yields a valid proof
+    let compressed_snark = CompressedSNARK::<_, _, _, S1, S2>::prove_with_budget(
+      &pp,
+      &pk,
+      &recursive_snark,
+      MemoryBudget::Bytes(0),
+    )
+    .unwrap();
+    assert!(compressed_snark.verify(&vk, 3, &z0).is_ok());
+
+    // provers that cannot stream their tables reject budgets
+    type SP1 = SPrime<E1, EE<E1>>;
+    let ck = pp.r1cs_shape_primary.commitment_key(&*SP1::ck_floor());
+    let (pk, _) = SP1::setup(&ck, &pp.r1cs_shape_primary).unwrap();
+    let res = SP1::prove_with_budget(
+      &ck,
+      &pk,
+      &pp.r1cs_shape_primary,
+      &recursive_snark.r_U_primary,
+      &recursive_snark.r_W_primary,
+      MemoryBudget::Bytes(usize::MAX),
+    );
+    assert_eq!(res.err(), Some(NovaError::MemoryBudgetUnsupported));
+  }
+
   #[test]
   fn test_ivc_nontrivial_with_spark_compression() {
     test_ivc_nontrivial_with_spark_compression_with::<PallasEngine, VestaEngine, EE<_>, EE<_>>();
@@ -1436,6 +2826,198 @@ mod tests {
     test_ivc_base_with::<Secp256k1Engine, Secq256k1Engine>();
   }
 
+  /// A step circuit of arity zero that proves that its advice `y` is the square of its advice `x`
+  #[derive(Clone, Debug, Default)]
+  struct SquareAdviceCircuit<F: PrimeField> {
+    x: F,
+    y: F,
+  }
+
+  impl<F: PrimeField> StepCircuit<F> for SquareAdviceCircuit<F> {
+    fn arity(&self) -> usize {
+      0
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      assert!(z.is_empty());
+      let x = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(self.x))?;
+      let y = AllocatedNum::alloc(cs.namespace(|| "y"), || Ok(self.y))?;
+      cs.enforce(
+        || "x * x = y",
+        |lc| lc + x.get_variable(),
+        |lc| lc + x.get_variable(),
+        |lc| lc + y.get_variable(),
+      );
+      Ok(vec![])
+    }
+  }
+
+  fn test_ivc_arity_zero_with<E1, E2, EE1, EE2>()
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+    EE1: EvaluationEngineTrait<E1>,
+    EE2: EvaluationEngineTrait<E2>,
+  {
+    type C<E> = SquareAdviceCircuit<<E as Engine>::Scalar>;
+    let circuits = (1..=4u64)
+      .map(|x| C::<E1> {
+        x: E1::Scalar::from(x),
+        y: E1::Scalar::from(x * x),
+      })
+      .collect::<Vec<_>>();
+    let pp = PublicParams::<E1, E2, C<E1>>::setup(
+      &circuits[0],
+      &*S::<E1, EE1>::ck_floor(),
+      &*S::<E2, EE2>::ck_floor(),
+    )
+    .unwrap();
+    assert_eq!(pp.F_arity, 0);
+
+    // the initial inputs must be empty
+    assert!(matches!(
+      RecursiveSNARK::new(&pp, &circuits[0], &[E1::Scalar::ZERO]),
+      Err(NovaError::InvalidInitialInputLength)
+    ));
+
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuits[0], &[]).unwrap();
+    for circuit in &circuits {
+      recursive_snark.prove_step(&pp, circuit).unwrap();
+    }
+    let num_steps = circuits.len();
+    assert_eq!(recursive_snark.verify(&pp, num_steps, &[]), Ok(vec![]));
+    assert!(recursive_snark
+      .verify(&pp, num_steps, &[E1::Scalar::ZERO])
+      .is_err());
+
+    let (pk, vk) = CompressedSNARK::<_, _, _, S<E1, EE1>, S<E2, EE2>>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+    assert_eq!(snark.verify(&vk, num_steps, &[]), Ok(vec![]));
+    assert!(snark.verify(&vk, num_steps, &[E1::Scalar::ZERO]).is_err());
+
+    // a step whose advice does not satisfy the circuit is caught
+    let bad = C::<E1> {
+      x: E1::Scalar::from(2),
+      y: E1::Scalar::from(5),
+    };
+    recursive_snark.prove_step(&pp, &bad).unwrap();
+    assert!(recursive_snark.verify(&pp, num_steps + 1, &[]).is_err());
+  }
+
+  #[test]
+  fn test_ivc_arity_zero() {
+    test_ivc_arity_zero_with::<PallasEngine, VestaEngine, EE<_>, EE<_>>();
+    test_ivc_arity_zero_with::<Bn256EngineKZG, GrumpkinEngine, EEPrime<_>, EE<_>>();
+  }
+
+  /// A RO over `Base` that squeezes Poseidon twice, which stands in for an RO other than Poseidon
+  struct DoubleSqueezeRO<Base: PrimeField>(PoseidonRO<Base>);
+
+  /// The circuit model of `DoubleSqueezeRO`
+  struct DoubleSqueezeROCircuit<Base: PrimeField>(PoseidonROCircuit<Base>);
+
+  impl<Base> ROTrait<Base> for DoubleSqueezeRO<Base>
+  where
+    Base: PrimeFieldBits + Serialize + for<'de> Deserialize<'de>,
+  {
+    type CircuitRO = DoubleSqueezeROCircuit<Base>;
+    type Constants = PoseidonConstantsCircuit<Base>;
+
+    fn new(constants: Self::Constants) -> Self {
+      Self(PoseidonRO::new(constants))
+    }
+
+    fn absorb(&mut self, e: Base) {
+      self.0.absorb(e);
+    }
+
+    fn squeeze(&mut self, num_bits: usize) -> Base {
+      self.0.squeeze(num_bits);
+      self.0.squeeze(num_bits)
+    }
+  }
+
+  impl<Base> ROCircuitTrait<Base> for DoubleSqueezeROCircuit<Base>
+  where
+    Base: PrimeFieldBits + Serialize + for<'de> Deserialize<'de>,
+  {
+    type NativeRO = DoubleSqueezeRO<Base>;
+    type Constants = PoseidonConstantsCircuit<Base>;
+
+    fn new(constants: Self::Constants) -> Self {
+      Self(PoseidonROCircuit::new(constants))
+    }
+
+    fn absorb(&mut self, e: &AllocatedNum<Base>) {
+      self.0.absorb(e);
+    }
+
+    fn squeeze<CS: ConstraintSystem<Base>>(
+      &mut self,
+      mut cs: CS,
+      num_bits: usize,
+    ) -> Result<Vec<AllocatedBit>, SynthesisError> {
+      self.0.squeeze(cs.namespace(|| "first"), num_bits)?;
+      self.0.squeeze(cs.namespace(|| "second"), num_bits)
+    }
+  }
+
+  /// Vesta with `DoubleSqueezeRO`, which is recomputed in the primary circuit of a cycle with
+  /// Pallas
+  #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+  struct VestaDoubleSqueezeEngine;
+
+  impl Engine for VestaDoubleSqueezeEngine {
+    const ID: &'static str = "vesta-double-squeeze-test-v1";
+
+    type Base = <VestaEngine as Engine>::Base;
+    type Scalar = <VestaEngine as Engine>::Scalar;
+    type GE = <VestaEngine as Engine>::GE;
+    type RO = DoubleSqueezeRO<Self::Base>;
+    type ROCircuit = DoubleSqueezeROCircuit<Self::Base>;
+    type RO2 = <VestaEngine as Engine>::RO2;
+    type RO2Circuit = <VestaEngine as Engine>::RO2Circuit;
+    type TE = crate::provider::keccak::Keccak256Transcript<Self>;
+    type CE = crate::provider::pedersen::CommitmentEngine<Self>;
+  }
+
+  #[test]
+  fn test_ivc_mixed_ro() {
+    type E1 = PallasEngine;
+    type E2 = VestaDoubleSqueezeEngine;
+    type C = CubicCircuit<<E1 as Engine>::Scalar>;
+
+    let circuit = C::default();
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+
+    // the RO of the secondary engine only changes the primary circuit
+    let pp_poseidon =
+      PublicParams::<E1, VestaEngine, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint())
+        .unwrap();
+    assert!(pp.r1cs_shape_primary.num_cons > pp_poseidon.r1cs_shape_primary.num_cons);
+    assert_eq!(
+      pp.r1cs_shape_secondary.num_cons,
+      pp_poseidon.r1cs_shape_secondary.num_cons
+    );
+
+    let z0 = [<E1 as Engine>::Scalar::ZERO];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..3 {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let zn = recursive_snark.verify(&pp, 3, &z0).unwrap();
+    assert_eq!(zn, vec![<E1 as Engine>::Scalar::from(2460515u64)]);
+
+    let (pk, vk) = CompressedSNARK::<_, _, _, S<E1, EE<E1>>, S<E2, EE<E2>>>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+    assert_eq!(snark.verify(&vk, 3, &z0), Ok(zn));
+  }
+
   fn test_setup_with<E1, E2>()
   where
     E1: Engine<Base = <E2 as Engine>::Scalar>,
@@ -1487,4 +3069,111 @@ mod tests {
   fn test_setup() {
     test_setup_with::<Bn256EngineKZG, GrumpkinEngine>();
   }
+
+  #[test]
+  fn test_setup_from() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type F = <E1 as Engine>::Scalar;
+
+    let circuit = CubicCircuit::<F>::default();
+    let pp = PublicParams::<E1, E2, CubicCircuit<F>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    // the same circuit reuses everything and yields the same parameters
+    let (pp_same, reuse) = PublicParams::<E1, E2, CubicCircuit<F>>::setup_from(
+      &pp,
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    assert!(reuse.shape_primary_unchanged && reuse.reused_ck_primary && reuse.reused_ck_secondary);
+    assert_eq!(
+      reuse.shared_constraints_primary,
+      pp.r1cs_shape_primary.num_cons
+    );
+    assert_eq!(pp_same.digest(), pp.digest());
+
+    // a circuit that changes the step function shares the constraints synthesized before it
+    let square = SquareCircuit::<F>::default();
+    let (pp_square, reuse) = PublicParams::<E1, E2, SquareCircuit<F>>::setup_from(
+      &pp,
+      &square,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    assert!(!reuse.shape_primary_unchanged);
+    assert!(reuse.shared_constraints_primary > 0);
+    assert!(reuse.shared_constraints_primary < pp_square.r1cs_shape_primary.num_cons);
+    assert!(reuse.reused_ck_primary && reuse.reused_ck_secondary);
+
+    // the resulting parameters are usable
+    let z0 = [F::from(2u64)];
+    let mut recursive_snark =
+      RecursiveSNARK::<E1, E2, SquareCircuit<F>>::new(&pp_square, &square, &z0).unwrap();
+    recursive_snark.prove_step(&pp_square, &square).unwrap();
+    recursive_snark.prove_step(&pp_square, &square).unwrap();
+    assert_eq!(
+      recursive_snark.verify(&pp_square, 2, &z0).unwrap(),
+      vec![F::from(16u64)]
+    );
+  }
+
+  #[test]
+  fn test_setup_with_ck_secondary() {
+    type E1 = PallasEngine;
+    type E2 = VestaEngine;
+    type F = <E1 as Engine>::Scalar;
+
+    let circuit = CubicCircuit::<F>::default();
+    let beacon = b"ceremony transcript";
+    let required = PublicParams::<E1, E2, CubicCircuit<F>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap()
+    .ck_secondary()
+    .length();
+
+    let ck = CommitmentKey::<E2>::from_beacon(beacon, required);
+    let pp = PublicParams::<E1, E2, CubicCircuit<F>>::setup_with_ck_secondary(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+      ck,
+    )
+    .unwrap();
+
+    // auditors confirm the generators of the parameters from the beacon alone
+    assert!(pp.ck_secondary().verify_beacon(beacon).is_ok());
+    assert!(pp
+      .ck_secondary()
+      .verify_beacon(b"other transcript")
+      .is_err());
+
+    let z0 = [F::from(2u64)];
+    let mut recursive_snark =
+      RecursiveSNARK::<E1, E2, CubicCircuit<F>>::new(&pp, &circuit, &z0).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    assert!(recursive_snark.verify(&pp, 1, &z0).is_ok());
+
+    let small = CommitmentKey::<E2>::from_beacon(beacon, required / 2);
+    assert_eq!(
+      PublicParams::<E1, E2, CubicCircuit<F>>::setup_with_ck_secondary(
+        &circuit,
+        &*default_ck_hint(),
+        &*default_ck_hint(),
+        small,
+      )
+      .err(),
+      Some(NovaError::InvalidCommitmentKeyLength)
+    );
+  }
 }
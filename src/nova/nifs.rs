@@ -4,14 +4,30 @@ use crate::{
   constants::NUM_CHALLENGE_BITS,
   errors::NovaError,
   gadgets::utils::{base_as_scalar, scalar_as_base},
-  r1cs::{R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance, RelaxedR1CSWitness},
-  traits::{AbsorbInROTrait, Engine, ROConstants, ROTrait},
+  r1cs::{
+    wipe_scratch, R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance, RelaxedR1CSWitness,
+  },
+  traits::{
+    calldata::{CalldataDecoder, CalldataEncoder, CalldataEncoding},
+    commitment::CommitmentEngineTrait,
+    AbsorbInROTrait, Engine, ROConstants, ROTrait,
+  },
   Commitment, CommitmentKey,
 };
 use ff::Field;
 use rand_core::OsRng;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
+/// The running instance and witness, and the new instance and witness, of a folding of
+/// `NIFS::prove_batch`
+pub(crate) type FoldInputs<'a, E> = (
+  &'a RelaxedR1CSInstance<E>,
+  &'a RelaxedR1CSWitness<E>,
+  &'a R1CSInstance<E>,
+  &'a R1CSWitness<E>,
+);
+
 /// An NIFS message from Nova's folding scheme
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -20,6 +36,21 @@ pub struct NIFS<E: Engine> {
   pub(crate) comm_T: Commitment<E>,
 }
 
+impl<E: Engine> CalldataEncoding for NIFS<E>
+where
+  Commitment<E>: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.comm_T.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      comm_T: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
+
 impl<E: Engine> NIFS<E> {
   /// Takes as input a Relaxed R1CS instance-witness tuple `(U1, W1)` and
   /// an R1CS instance-witness tuple `(U2, W2)` with the same structure `shape`
@@ -54,7 +85,7 @@ impl<E: Engine> NIFS<E> {
 
     // compute a commitment to the cross-term
     let r_T = E::Scalar::random(&mut OsRng);
-    let (T, comm_T) = S.commit_T(ck, U1, W1, U2, W2, &r_T)?;
+    let (mut T, comm_T) = S.commit_T(ck, U1, W1, U2, W2, &r_T)?;
 
     // append `comm_T` to the transcript and obtain a challenge
     comm_T.absorb_in_ro(&mut ro);
@@ -66,12 +97,53 @@ impl<E: Engine> NIFS<E> {
     let U = U1.fold(U2, &comm_T, &r);
 
     // fold the witness using `r` and `T`
-    let W = W1.fold(W2, &T, &r_T, &r)?;
+    let W = W1.fold(W2, &T, &r_T, &r);
+    wipe_scratch(&mut T);
+    let W = W?;
 
     // return the folded instance and witness
     Ok((Self { comm_T }, (U, W)))
   }
 
+  /// Same as `prove`, but for many independent foldings with the same shape, whose cross-terms
+  /// are committed to in one call to `CommitmentEngineTrait::batch_commit`
+  pub(crate) fn prove_batch(
+    ck: &CommitmentKey<E>,
+    ro_consts: &ROConstants<E>,
+    pp_digest: &E::Scalar,
+    S: &R1CSShape<E>,
+    folds: &[FoldInputs<'_, E>],
+  ) -> Result<Vec<(NIFS<E>, (RelaxedR1CSInstance<E>, RelaxedR1CSWitness<E>))>, NovaError> {
+    // compute the cross-terms, and commit to all of them at once
+    let Ts = folds
+      .par_iter()
+      .map(|(U1, W1, U2, W2)| S.compute_T(U1, W1, U2, W2))
+      .collect::<Result<Vec<_>, _>>()?;
+    let r_Ts = (0..folds.len())
+      .map(|_| E::Scalar::random(&mut OsRng))
+      .collect::<Vec<_>>();
+    let comm_Ts = E::CE::batch_commit(ck, &Ts, &r_Ts);
+
+    folds
+      .par_iter()
+      .zip(Ts.into_par_iter())
+      .zip(r_Ts.par_iter().zip(comm_Ts.par_iter()))
+      .map(|(((U1, W1, U2, W2), mut T), (r_T, comm_T))| {
+        // absorb the same values as `prove`
+        let mut ro = E::RO::new(ro_consts.clone());
+        ro.absorb(scalar_as_base::<E>(*pp_digest));
+        U2.absorb_in_ro(&mut ro);
+        comm_T.absorb_in_ro(&mut ro);
+        let r = base_as_scalar::<E>(ro.squeeze(NUM_CHALLENGE_BITS));
+
+        let U = U1.fold(U2, comm_T, &r);
+        let W = W1.fold(W2, &T, r_T, &r);
+        wipe_scratch(&mut T);
+        Ok((Self { comm_T: *comm_T }, (U, W?)))
+      })
+      .collect()
+  }
+
   /// Takes as input a relaxed R1CS instance `U1` and R1CS instance `U2`
   /// with the same shape and defined with respect to the same parameters,
   /// and outputs a folded instance `U` with the same shape,
@@ -115,6 +187,21 @@ pub struct NIFSRelaxed<E: Engine> {
   pub(crate) comm_T: Commitment<E>,
 }
 
+impl<E: Engine> CalldataEncoding for NIFSRelaxed<E>
+where
+  Commitment<E>: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.comm_T.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      comm_T: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
+
 impl<E: Engine> NIFSRelaxed<E> {
   /// Same as `prove`, but takes two Relaxed R1CS Instance/Witness pairs
   pub fn prove(
@@ -149,7 +236,7 @@ impl<E: Engine> NIFSRelaxed<E> {
     // compute a commitment to the cross-term
     let r_T = E::Scalar::random(&mut OsRng);
     E::Scalar::random(&mut OsRng);
-    let (T, comm_T) = S.commit_T_relaxed(ck, U1, W1, U2, W2, &r_T)?;
+    let (mut T, comm_T) = S.commit_T_relaxed(ck, U1, W1, U2, W2, &r_T)?;
 
     // append `comm_T` to the transcript and obtain a challenge
     comm_T.absorb_in_ro(&mut ro);
@@ -161,7 +248,9 @@ impl<E: Engine> NIFSRelaxed<E> {
     let U = U1.fold_relaxed(U2, &comm_T, &base_as_scalar::<E>(r));
 
     // fold the witness using `r` and `T`
-    let W = W1.fold_relaxed(W2, &T, &r_T, &base_as_scalar::<E>(r))?;
+    let W = W1.fold_relaxed(W2, &T, &r_T, &base_as_scalar::<E>(r));
+    wipe_scratch(&mut T);
+    let W = W?;
 
     // return the folded instance and witness
     Ok((Self { comm_T }, (U, W)))
@@ -215,6 +304,7 @@ mod tests {
     },
     provider::{Bn256EngineKZG, PallasEngine, Secp256k1Engine},
     r1cs::SparseMatrix,
+    testing::MockEngine,
     traits::{commitment::CommitmentEngineTrait, snark::default_ck_hint, Engine, ROConstants},
   };
   use ff::{Field, PrimeField};
@@ -294,6 +384,7 @@ mod tests {
     test_tiny_r1cs_bellpepper_with::<PallasEngine>();
     test_tiny_r1cs_bellpepper_with::<Bn256EngineKZG>();
     test_tiny_r1cs_bellpepper_with::<Secp256k1Engine>();
+    test_tiny_r1cs_bellpepper_with::<MockEngine>();
   }
 
   fn execute_sequence<E: Engine>(
@@ -565,6 +656,7 @@ mod tests {
     test_tiny_r1cs_relaxed_with::<PallasEngine>();
     test_tiny_r1cs_relaxed_with::<Bn256EngineKZG>();
     test_tiny_r1cs_relaxed_with::<Secp256k1Engine>();
+    test_tiny_r1cs_relaxed_with::<MockEngine>();
   }
 
   fn test_tiny_r1cs_with<E: Engine>() {
@@ -697,5 +789,6 @@ mod tests {
     test_tiny_r1cs_with::<PallasEngine>();
     test_tiny_r1cs_with::<Bn256EngineKZG>();
     test_tiny_r1cs_with::<Secp256k1Engine>();
+    test_tiny_r1cs_with::<MockEngine>();
   }
 }
@@ -0,0 +1,277 @@
+//! This module exposes the polynomials that a `CompressedSNARK` commits to, so that the same
+//! commitments can answer queries beyond the SNARK, e.g., audits of the witness of the primary
+//! circuit by a data-availability layer.
+//!
+//! `CompressedSNARK::prove_with_openings` returns, along with the SNARK, a `CommittedWitness` that
+//! holds the witness `W` and the error vector `E` of the folded primary instance whose
+//! satisfiability the SNARK proves. It opens either of them, with any evaluation engine, at points
+//! chosen by the caller. Verifiers recover the commitments from the SNARK itself with
+//! `CompressedSNARK::commitment`, and check each `Opening` against them. As for evaluation engines,
+//! a vector of length `n` is a multilinear polynomial in `log2(n.next_power_of_two())` variables,
+//! whose evaluations on the hypercube are the vector padded with zeros.
+use super::{CompressedSNARK, VerifierKey};
+use crate::{
+  errors::NovaError,
+  r1cs::{RelaxedR1CSInstance, RelaxedR1CSWitness},
+  spartan::polys::multilinear::MultilinearPolynomial,
+  traits::{
+    circuit::StepCircuit, evaluation::EvaluationEngineTrait, snark::RelaxedR1CSSNARKTrait, Engine,
+    TranscriptEngineTrait,
+  },
+  Commitment, CommitmentKey,
+};
+use ff::Field;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// A polynomial committed to by the folded primary instance of a `CompressedSNARK`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommittedPoly {
+  /// The witness `W`
+  Witness,
+  /// The error vector `E`
+  Error,
+}
+
+/// The witness and the error vector of the folded primary instance of a `CompressedSNARK`, as
+/// returned to its prover by `CompressedSNARK::prove_with_openings`.
+///
+/// If the `zeroize` feature is enabled, both vectors are overwritten with zeros when it is dropped.
+pub struct CommittedWitness<E: Engine> {
+  U: RelaxedR1CSInstance<E>,
+  W: RelaxedR1CSWitness<E>,
+}
+
+/// The evaluation of a committed polynomial at a point, along with an argument of its correctness
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Opening<E: Engine, EE: EvaluationEngineTrait<E>> {
+  eval: E::Scalar,
+  arg: EE::EvaluationArgument,
+}
+
+/// Returns a transcript for openings that binds `comm`, `point`, and `eval`
+fn transcript<E: Engine>(comm: &Commitment<E>, point: &[E::Scalar], eval: &E::Scalar) -> E::TE {
+  let mut transcript = E::TE::new(b"CommittedWitness");
+  transcript.absorb(b"C", comm);
+  transcript.absorb(b"r", &point);
+  transcript.absorb(b"v", eval);
+  transcript
+}
+
+impl<E: Engine> CommittedWitness<E> {
+  /// Creates a handle to a derandomized instance and witness, whose commitments have zero blinds
+  pub(crate) fn new(U: RelaxedR1CSInstance<E>, W: RelaxedR1CSWitness<E>) -> Self {
+    Self { U, W }
+  }
+
+  fn values(&self, poly: CommittedPoly) -> Cow<'_, [E::Scalar]> {
+    match poly {
+      CommittedPoly::Witness => Cow::Borrowed(&self.W.W),
+      CommittedPoly::Error => self.W.E.to_dense(),
+    }
+  }
+
+  /// Returns the commitment to `poly`, which `CompressedSNARK::commitment` recovers from the SNARK
+  pub fn commitment(&self, poly: CommittedPoly) -> Commitment<E> {
+    match poly {
+      CommittedPoly::Witness => self.U.comm_W,
+      CommittedPoly::Error => self.U.comm_E,
+    }
+  }
+
+  /// Returns the number of variables of `poly`, which is the length of the points to open it at
+  pub fn num_vars(&self, poly: CommittedPoly) -> usize {
+    let len = match poly {
+      CommittedPoly::Witness => self.W.W.len(),
+      CommittedPoly::Error => self.W.E.len(),
+    };
+    len.next_power_of_two().trailing_zeros() as usize
+  }
+
+  /// Returns `poly` padded with zeros to a power-of-two length
+  fn padded(&self, poly: CommittedPoly) -> Vec<E::Scalar> {
+    let mut values = self.values(poly).into_owned();
+    values.resize(values.len().next_power_of_two(), E::Scalar::ZERO);
+    values
+  }
+
+  /// Returns the evaluation of `poly` at `point`
+  pub fn evaluate(&self, poly: CommittedPoly, point: &[E::Scalar]) -> Result<E::Scalar, NovaError> {
+    if point.len() != self.num_vars(poly) {
+      return Err(NovaError::InvalidInputLength);
+    }
+    Ok(MultilinearPolynomial::evaluate_with(
+      &self.padded(poly),
+      point,
+    ))
+  }
+
+  /// Opens `poly` at `point` with the evaluation engine `EE`, whose keys are set up from `ck`, the
+  /// commitment key of the primary circuit (see `PublicParams::ck_primary`)
+  pub fn open<EE: EvaluationEngineTrait<E>>(
+    &self,
+    ck: &CommitmentKey<E>,
+    pk: &EE::ProverKey,
+    poly: CommittedPoly,
+    point: &[E::Scalar],
+  ) -> Result<Opening<E, EE>, NovaError> {
+    if point.len() != self.num_vars(poly) {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let values = self.padded(poly);
+    let eval = MultilinearPolynomial::evaluate_with(&values, point);
+    let comm = self.commitment(poly);
+    let arg = EE::prove(
+      ck,
+      pk,
+      &mut transcript::<E>(&comm, point, &eval),
+      &comm,
+      &values,
+      point,
+      &eval,
+    )?;
+    Ok(Opening { eval, arg })
+  }
+}
+
+#[cfg(feature = "zeroize")]
+impl<E: Engine> Drop for CommittedWitness<E> {
+  fn drop(&mut self) {
+    self.W.wipe();
+  }
+}
+
+impl<E: Engine, EE: EvaluationEngineTrait<E>> Opening<E, EE> {
+  /// Returns the claimed evaluation, which is only meaningful once `verify` succeeds
+  pub fn eval(&self) -> E::Scalar {
+    self.eval
+  }
+
+  /// Checks the opening against `comm` at `point`, and returns the evaluation it proves
+  pub fn verify(
+    &self,
+    vk: &EE::VerifierKey,
+    comm: &Commitment<E>,
+    point: &[E::Scalar],
+  ) -> Result<E::Scalar, NovaError> {
+    EE::verify(
+      vk,
+      &mut transcript::<E>(comm, point, &self.eval),
+      comm,
+      point,
+      &self.eval,
+      &self.arg,
+    )?;
+    Ok(self.eval)
+  }
+}
+
+impl<E1, E2, C, S1, S2> CompressedSNARK<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Returns the commitment to `poly` of the folded primary instance that the SNARK proves, as
+  /// recomputed from the SNARK. It is only bound to the recursive SNARK once `verify` succeeds.
+  pub fn commitment(
+    &self,
+    vk: &VerifierKey<E1, E2, C, S1, S2>,
+    poly: CommittedPoly,
+  ) -> Result<Commitment<E1>, NovaError> {
+    let r_Un_primary = self.nifs_Un_primary.verify(
+      &vk.light.ro_consts_primary,
+      &vk.light.pp_digest,
+      &self.r_U_primary,
+      &self.l_ur_primary,
+    )?;
+    let U = r_Un_primary.derandomize(
+      &vk.light.dk_primary,
+      &self.wit_blind_r_Wn_primary,
+      &self.err_blind_r_Wn_primary,
+    );
+    Ok(match poly {
+      CommittedPoly::Witness => U.comm_W,
+      CommittedPoly::Error => U.comm_E,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    nova::{PublicParams, RecursiveSNARK},
+    provider::{ipa_pc, PallasEngine, VestaEngine},
+    spartan::snark::RelaxedR1CSSNARK,
+    traits::circuit::NonTrivialCircuit,
+  };
+  use rand_core::OsRng;
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+  type C = NonTrivialCircuit<F>;
+  type EE<E> = ipa_pc::EvaluationEngine<E>;
+  type S<E> = RelaxedR1CSSNARK<E, EE<E>>;
+
+  #[test]
+  fn test_openings() {
+    let circuit = C::new(4);
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*S::<E1>::ck_floor(), &*S::<E2>::ck_floor())
+        .unwrap();
+    let z0 = vec![F::from(2u64)];
+    let mut rs = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..3 {
+      rs.prove_step(&pp, &circuit).unwrap();
+    }
+
+    let (pk, vk) = CompressedSNARK::<_, _, _, S<E1>, S<E2>>::setup(&pp).unwrap();
+    let (snark, committed) = CompressedSNARK::prove_with_openings(&pp, &pk, &rs).unwrap();
+    assert!(snark.verify(&vk, 3, &z0).is_ok());
+
+    let (pk_ee, vk_ee) = EE::<E1>::setup(pp.ck_primary());
+    for poly in [CommittedPoly::Witness, CommittedPoly::Error] {
+      // the verifier recovers the commitments that the prover opens from the SNARK
+      let comm = snark.commitment(&vk, poly).unwrap();
+      assert_eq!(comm, committed.commitment(poly));
+
+      let point = (0..committed.num_vars(poly))
+        .map(|_| F::random(&mut OsRng))
+        .collect::<Vec<_>>();
+      let opening = committed
+        .open::<EE<E1>>(pp.ck_primary(), &pk_ee, poly, &point)
+        .unwrap();
+      assert_eq!(
+        opening.verify(&vk_ee, &comm, &point).unwrap(),
+        committed.evaluate(poly, &point).unwrap()
+      );
+
+      // openings are bound to their point, commitment, and evaluation
+      let mut other_point = point.clone();
+      other_point[0] += F::ONE;
+      assert!(opening.verify(&vk_ee, &comm, &other_point).is_err());
+      let other = match poly {
+        CommittedPoly::Witness => CommittedPoly::Error,
+        CommittedPoly::Error => CommittedPoly::Witness,
+      };
+      assert!(opening
+        .verify(&vk_ee, &snark.commitment(&vk, other).unwrap(), &point)
+        .is_err());
+      let mut forged = opening.clone();
+      forged.eval += F::ONE;
+      assert!(forged.verify(&vk_ee, &comm, &point).is_err());
+
+      assert_eq!(
+        committed
+          .open::<EE<E1>>(pp.ck_primary(), &pk_ee, poly, &point[1..])
+          .err(),
+        Some(NovaError::InvalidInputLength)
+      );
+    }
+  }
+}
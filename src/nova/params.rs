@@ -0,0 +1,303 @@
+//! This module defines `ProverParams` and `VerifierParams`, the prover-only and verifier-only parts
+//! of `PublicParams`, which can be serialized and deployed independently.
+//!
+//! The commitment keys and the shapes of the augmented circuits dominate the size of the public
+//! parameters, but only the prover needs them. `PublicParams::split` moves them into
+//! `ProverParams`, and leaves the arity, the constants of the random oracles, and the digest of the
+//! public parameters in `VerifierParams`. Both parts record the digest of the public parameters
+//! they were split from, so that `PublicParams::join` only accepts parts that belong together, and
+//! `CompressedSNARK::verify_with_params` only accepts verifier keys derived from the public
+//! parameters that the verifier's part was split from.
+use super::{CompressedSNARK, PublicParams, VerifierKey};
+use crate::{
+  errors::NovaError,
+  r1cs::R1CSShape,
+  traits::{
+    circuit::StepCircuit, commitment::Len, snark::RelaxedR1CSSNARKTrait, Engine, ROConstants,
+    ROConstantsCircuit,
+  },
+  CommitmentKey,
+};
+use core::marker::PhantomData;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+/// The part of `PublicParams` that only the prover needs
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ProverParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  ro_consts_circuit_primary: ROConstantsCircuit<E2>,
+  ro_consts_circuit_secondary: ROConstantsCircuit<E1>,
+
+  ck_primary: CommitmentKey<E1>,
+  r1cs_shape_primary: R1CSShape<E1>,
+
+  ck_secondary: CommitmentKey<E2>,
+  r1cs_shape_secondary: R1CSShape<E2>,
+
+  pp_digest: E1::Scalar,
+  _p: PhantomData<C>,
+}
+
+/// The part of `PublicParams` that verifiers need
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct VerifierParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  F_arity: usize,
+  ro_consts_primary: ROConstants<E1>,
+  ro_consts_secondary: ROConstants<E2>,
+  num_cons: (usize, usize),
+  num_vars: (usize, usize),
+  ck_size: (usize, usize),
+  pp_digest: E1::Scalar,
+  _p: PhantomData<C>,
+}
+
+impl<E1, E2, C> PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Splits the public parameters into the part that only the prover needs and the part that
+  /// verifiers need
+  pub fn split(self) -> (ProverParams<E1, E2, C>, VerifierParams<E1, E2, C>) {
+    let pp_digest = self.digest();
+
+    let verifier = VerifierParams {
+      F_arity: self.F_arity,
+      ro_consts_primary: self.ro_consts_primary,
+      ro_consts_secondary: self.ro_consts_secondary,
+      num_cons: (
+        self.r1cs_shape_primary.num_cons,
+        self.r1cs_shape_secondary.num_cons,
+      ),
+      num_vars: (
+        self.r1cs_shape_primary.num_vars,
+        self.r1cs_shape_secondary.num_vars,
+      ),
+      ck_size: (self.ck_primary.length(), self.ck_secondary.length()),
+      pp_digest,
+      _p: Default::default(),
+    };
+
+    let prover = ProverParams {
+      ro_consts_circuit_primary: self.ro_consts_circuit_primary,
+      ro_consts_circuit_secondary: self.ro_consts_circuit_secondary,
+      ck_primary: self.ck_primary,
+      r1cs_shape_primary: self.r1cs_shape_primary,
+      ck_secondary: self.ck_secondary,
+      r1cs_shape_secondary: self.r1cs_shape_secondary,
+      pp_digest,
+      _p: Default::default(),
+    };
+
+    (prover, verifier)
+  }
+
+  /// Reassembles the public parameters from the parts returned by `split`, failing if the parts
+  /// were split from different public parameters or were modified since
+  pub fn join(
+    prover: ProverParams<E1, E2, C>,
+    verifier: VerifierParams<E1, E2, C>,
+  ) -> Result<Self, NovaError> {
+    let mismatch = || NovaError::SetupError {
+      reason: "Prover and verifier parameters do not belong to the same public parameters"
+        .to_string(),
+    };
+    if prover.pp_digest != verifier.pp_digest {
+      return Err(mismatch());
+    }
+
+    let pp = PublicParams {
+      F_arity: verifier.F_arity,
+      ro_consts_primary: verifier.ro_consts_primary,
+      ro_consts_circuit_primary: prover.ro_consts_circuit_primary,
+      ro_consts_secondary: verifier.ro_consts_secondary,
+      ro_consts_circuit_secondary: prover.ro_consts_circuit_secondary,
+      ck_primary: prover.ck_primary,
+      r1cs_shape_primary: prover.r1cs_shape_primary,
+      ck_secondary: prover.ck_secondary,
+      r1cs_shape_secondary: prover.r1cs_shape_secondary,
+      digest: OnceCell::new(),
+      _p: Default::default(),
+    };
+
+    if pp.digest() != verifier.pp_digest {
+      return Err(mismatch());
+    }
+
+    Ok(pp)
+  }
+}
+
+impl<E1, E2, C> ProverParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Returns the digest of the public parameters these parameters were split from
+  pub fn digest(&self) -> E1::Scalar {
+    self.pp_digest
+  }
+}
+
+impl<E1, E2, C> VerifierParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Returns the digest of the public parameters these parameters were split from
+  pub fn digest(&self) -> E1::Scalar {
+    self.pp_digest
+  }
+
+  /// Returns the arity of the step circuit
+  pub fn arity(&self) -> usize {
+    self.F_arity
+  }
+
+  /// Returns the number of constraints in the primary and secondary circuits
+  pub fn num_constraints(&self) -> (usize, usize) {
+    self.num_cons
+  }
+
+  /// Returns the number of variables in the primary and secondary circuits
+  pub fn num_variables(&self) -> (usize, usize) {
+    self.num_vars
+  }
+
+  /// Returns the number of generators in the primary and secondary commitment keys
+  pub fn ck_size(&self) -> (usize, usize) {
+    self.ck_size
+  }
+}
+
+impl<E1, E2, C, S1, S2> VerifierKey<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Returns true if the verifier key was derived from the public parameters that `vp` was split from
+  pub fn matches(&self, vp: &VerifierParams<E1, E2, C>) -> bool {
+    self.light.pp_digest == vp.pp_digest && self.light.F_arity == vp.F_arity
+  }
+}
+
+impl<E1, E2, C, S1, S2> CompressedSNARK<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Verifies the `CompressedSNARK` like `verify`, with the verifier's part of the public
+  /// parameters, failing unless `vk` was derived from the public parameters `vp` was split from.
+  ///
+  /// A `RecursiveSNARK` cannot be verified with `VerifierParams` alone, since its verification
+  /// checks the running witnesses against the shapes and the commitment keys, which only
+  /// `ProverParams` hold.
+  pub fn verify_with_params(
+    &self,
+    vp: &VerifierParams<E1, E2, C>,
+    vk: &VerifierKey<E1, E2, C, S1, S2>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+  ) -> Result<Vec<E1::Scalar>, NovaError> {
+    if !vk.matches(vp) {
+      return Err(NovaError::ProofVerifyError {
+        reason: "The verifier key does not belong to the verifier parameters".to_string(),
+      });
+    }
+    if z0.len() != vp.F_arity {
+      return Err(NovaError::InvalidInitialInputLength);
+    }
+    self.verify(vk, num_steps, z0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    nova::{CompressedSNARK, RecursiveSNARK},
+    provider::{ipa_pc, PallasEngine, VestaEngine},
+    spartan::snark::RelaxedR1CSSNARK,
+    traits::{circuit::NonTrivialCircuit, snark::default_ck_hint},
+  };
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+  type C = NonTrivialCircuit<F>;
+  type S1 = RelaxedR1CSSNARK<E1, ipa_pc::EvaluationEngine<E1>>;
+  type S2 = RelaxedR1CSSNARK<E2, ipa_pc::EvaluationEngine<E2>>;
+
+  fn setup(num_cons: usize) -> PublicParams<E1, E2, C> {
+    PublicParams::setup(&C::new(num_cons), &*default_ck_hint(), &*default_ck_hint()).unwrap()
+  }
+
+  #[test]
+  fn test_split_join() {
+    let pp = setup(4);
+    let digest = pp.digest();
+    let num_constraints = pp.num_constraints();
+    let (_, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+
+    let (prover, verifier) = pp.split();
+    assert_eq!(prover.digest(), digest);
+    assert_eq!(verifier.digest(), digest);
+    assert_eq!(verifier.num_constraints(), num_constraints);
+    assert!(vk.matches(&verifier));
+
+    // both parts are serialized independently, and the verifier's part is much smaller
+    let prover_bytes = bincode::serialize(&prover).unwrap();
+    let verifier_bytes = bincode::serialize(&verifier).unwrap();
+    assert!(verifier_bytes.len() * 10 < prover_bytes.len());
+    let prover: ProverParams<E1, E2, C> = bincode::deserialize(&prover_bytes).unwrap();
+    let verifier: VerifierParams<E1, E2, C> = bincode::deserialize(&verifier_bytes).unwrap();
+
+    let pp = PublicParams::join(prover, verifier.clone()).unwrap();
+    assert_eq!(pp.digest(), digest);
+    let circuit = C::new(4);
+    let z0 = [F::from(2u64)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    recursive_snark.verify(&pp, 2, &z0).unwrap();
+
+    // compressed proofs are verified with the verifier's part alone
+    let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+    let compressed_snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+    assert_eq!(
+      compressed_snark
+        .verify_with_params(&verifier, &vk, 2, &z0)
+        .unwrap(),
+      recursive_snark.outputs()
+    );
+
+    // parts split from different public parameters are rejected
+    let (other_prover, other_verifier) = setup(5).split();
+    assert!(!vk.matches(&other_verifier));
+    assert!(compressed_snark
+      .verify_with_params(&other_verifier, &vk, 2, &z0)
+      .is_err());
+    assert!(PublicParams::join(other_prover, verifier).is_err());
+  }
+}
@@ -0,0 +1,337 @@
+//! This module defines `ProverPool`, which runs many `RecursiveSNARK` sessions, e.g., the IVC
+//! chains of different customers, against one set of public parameters on one machine.
+//!
+//! Sessions share the public parameters through an `Arc`, and are proven concurrently from
+//! different threads. The MSMs of a step saturate the device that computes them (a GPU or all the
+//! cores of the machine), so the pool admits at most a fixed number of steps at a time, in the order
+//! in which the sessions asked for them. A session that keeps proving steps thus queues behind the
+//! others rather than starving them. The pool also accounts for the memory that each session holds,
+//! and refuses to open sessions beyond an optional memory limit.
+use super::{PublicParams, RecursiveSNARK};
+use crate::{
+  errors::NovaError,
+  traits::{circuit::StepCircuit, Engine},
+};
+use std::{
+  collections::{HashMap, VecDeque},
+  mem,
+  sync::{Arc, Condvar, Mutex},
+};
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Returns the number of bytes held by the witnesses, the inputs and outputs, and the witness
+  /// cache of the recursive SNARK, which dominate its memory footprint
+  pub fn memory_usage(&self) -> usize {
+    let primary =
+      self.z0.len() + self.zi.len() + self.r_W_primary.W.len() + self.r_W_primary.E.len();
+    let secondary =
+      self.r_W_secondary.W.len() + self.r_W_secondary.E.len() + self.l_w_secondary.W.len();
+    let cache = self
+      .witness_cache
+      .as_ref()
+      .map_or(0, |cache| cache.memory_usage());
+
+    primary * mem::size_of::<E1::Scalar>() + secondary * mem::size_of::<E2::Scalar>() + cache
+  }
+}
+
+/// Identifies a session of a `ProverPool`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SessionId(u64);
+
+/// Admits steps to the device in the order in which they were requested
+struct Scheduler {
+  slots: usize,
+  state: Mutex<SchedulerState>,
+  turn: Condvar,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+  next_ticket: u64,
+  waiting: VecDeque<u64>,
+  running: usize,
+}
+
+impl Scheduler {
+  fn new(slots: usize) -> Self {
+    Self {
+      slots,
+      state: Mutex::new(SchedulerState::default()),
+      turn: Condvar::new(),
+    }
+  }
+
+  /// Blocks until every step requested earlier was admitted and a slot is free
+  fn acquire(&self) -> DeviceSlot<'_> {
+    let mut state = self.state.lock().unwrap();
+    let ticket = state.next_ticket;
+    state.next_ticket += 1;
+    state.waiting.push_back(ticket);
+
+    while state.waiting.front() != Some(&ticket) || state.running >= self.slots {
+      state = self.turn.wait(state).unwrap();
+    }
+    state.waiting.pop_front();
+    state.running += 1;
+
+    // the next ticket may be admitted as well if a slot is left
+    self.turn.notify_all();
+    DeviceSlot { scheduler: self }
+  }
+}
+
+/// A slot of the device, which is released when dropped
+struct DeviceSlot<'a> {
+  scheduler: &'a Scheduler,
+}
+
+impl Drop for DeviceSlot<'_> {
+  fn drop(&mut self) {
+    self.scheduler.state.lock().unwrap().running -= 1;
+    self.scheduler.turn.notify_all();
+  }
+}
+
+#[derive(Default)]
+struct Accounts {
+  next_id: u64,
+  memory_usage: HashMap<SessionId, usize>,
+}
+
+/// Runs many recursive SNARKs that share public parameters, see the module documentation
+pub struct ProverPool<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  pp: Arc<PublicParams<E1, E2, C>>,
+  memory_limit: Option<usize>,
+  scheduler: Scheduler,
+  accounts: Mutex<Accounts>,
+}
+
+impl<E1, E2, C> ProverPool<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Creates a pool whose sessions prove against `pp`, and which proves at most `device_slots`
+  /// steps at a time, e.g., one per GPU.
+  ///
+  /// Panics if `device_slots` is zero.
+  pub fn new(pp: Arc<PublicParams<E1, E2, C>>, device_slots: usize) -> Self {
+    assert!(device_slots > 0, "a pool needs at least one device slot");
+
+    // every session commits with the same keys, so converting them for the MSM backend pays off
+    pp.prepare_commitment_keys();
+
+    Self {
+      pp,
+      memory_limit: None,
+      scheduler: Scheduler::new(device_slots),
+      accounts: Mutex::new(Accounts::default()),
+    }
+  }
+
+  /// Limits the number of bytes that the sessions of the pool hold altogether, as measured by
+  /// `RecursiveSNARK::memory_usage`. The limit is checked when sessions are opened.
+  pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+    self.memory_limit = Some(bytes);
+    self
+  }
+
+  /// Returns the public parameters shared by the sessions
+  pub fn public_params(&self) -> &Arc<PublicParams<E1, E2, C>> {
+    &self.pp
+  }
+
+  /// Returns the number of open sessions
+  pub fn num_sessions(&self) -> usize {
+    self.accounts.lock().unwrap().memory_usage.len()
+  }
+
+  /// Returns the number of bytes held by the open sessions altogether
+  pub fn memory_usage(&self) -> usize {
+    self.accounts.lock().unwrap().memory_usage.values().sum()
+  }
+
+  /// Returns the number of bytes held by the session `id`, or `None` if it is not open
+  pub fn session_memory_usage(&self, id: SessionId) -> Option<usize> {
+    self.accounts.lock().unwrap().memory_usage.get(&id).copied()
+  }
+
+  /// Opens a session proving the incremental computation of `c` from `z0`.
+  ///
+  /// Returns `NovaError::MemoryLimitExceeded` if the session would take the pool over its
+  /// memory limit.
+  pub fn open(
+    self: &Arc<Self>,
+    c: &C,
+    z0: &[E1::Scalar],
+  ) -> Result<ProverSession<E1, E2, C>, NovaError> {
+    let recursive_snark = RecursiveSNARK::new(&self.pp, c, z0)?;
+    let required = recursive_snark.memory_usage();
+
+    let mut accounts = self.accounts.lock().unwrap();
+    if let Some(limit) = self.memory_limit {
+      let available = limit.saturating_sub(accounts.memory_usage.values().sum());
+      if required > available {
+        return Err(NovaError::MemoryLimitExceeded {
+          required,
+          available,
+        });
+      }
+    }
+
+    let id = SessionId(accounts.next_id);
+    accounts.next_id += 1;
+    accounts.memory_usage.insert(id, required);
+
+    Ok(ProverSession {
+      id,
+      pool: self.clone(),
+      recursive_snark,
+    })
+  }
+
+  fn record(&self, id: SessionId, bytes: usize) {
+    self.accounts.lock().unwrap().memory_usage.insert(id, bytes);
+  }
+}
+
+/// A recursive SNARK proven in a `ProverPool`, whose memory is released from the pool's accounts
+/// when the session is dropped
+pub struct ProverSession<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  id: SessionId,
+  pool: Arc<ProverPool<E1, E2, C>>,
+  recursive_snark: RecursiveSNARK<E1, E2, C>,
+}
+
+impl<E1, E2, C> ProverSession<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Returns the identifier of the session in its pool
+  pub fn id(&self) -> SessionId {
+    self.id
+  }
+
+  /// Returns the recursive SNARK of the session, e.g., to verify or compress it
+  pub fn recursive_snark(&self) -> &RecursiveSNARK<E1, E2, C> {
+    &self.recursive_snark
+  }
+
+  /// Enables the witness cache of the recursive SNARK (see `RecursiveSNARK::enable_witness_cache`),
+  /// whose memory is accounted to the session as it fills
+  pub fn enable_witness_cache(&mut self, max_entries: usize) {
+    self.recursive_snark.enable_witness_cache(max_entries);
+  }
+
+  /// Proves a step of `c` once the steps that other sessions requested earlier were admitted to
+  /// the device, and updates the memory accounted to the session
+  pub fn prove_step(&mut self, c: &C) -> Result<(), NovaError> {
+    let result = {
+      let _slot = self.pool.scheduler.acquire();
+      self.recursive_snark.prove_step(&self.pool.pp, c)
+    };
+    self
+      .pool
+      .record(self.id, self.recursive_snark.memory_usage());
+    result
+  }
+}
+
+impl<E1, E2, C> Drop for ProverSession<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  fn drop(&mut self) {
+    self
+      .pool
+      .accounts
+      .lock()
+      .unwrap()
+      .memory_usage
+      .remove(&self.id);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::{PallasEngine, VestaEngine},
+    traits::{circuit::NonTrivialCircuit, snark::default_ck_hint},
+  };
+  use ff::Field;
+  use std::thread;
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+  type C = NonTrivialCircuit<F>;
+
+  #[test]
+  fn test_prover_pool() {
+    let circuit = C::new(3);
+    let pp = Arc::new(
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap(),
+    );
+    let pool = Arc::new(ProverPool::new(pp.clone(), 1));
+
+    // sessions of different customers are proven concurrently
+    let handles = (0..3u64)
+      .map(|j| {
+        let pool = pool.clone();
+        let circuit = circuit.clone();
+        thread::spawn(move || {
+          let z0 = [F::from(j)];
+          let mut session = pool.open(&circuit, &z0).unwrap();
+          for _ in 0..3 {
+            session.prove_step(&circuit).unwrap();
+          }
+          session
+            .recursive_snark()
+            .verify(pool.public_params(), 3, &z0)
+        })
+      })
+      .collect::<Vec<_>>();
+    for handle in handles {
+      handle.join().unwrap().unwrap();
+    }
+    assert_eq!(pool.num_sessions(), 0);
+    assert_eq!(pool.memory_usage(), 0);
+
+    // the memory held by the sessions is accounted for, and limited
+    let session = pool.open(&circuit, &[F::ONE]).unwrap();
+    let usage = pool.session_memory_usage(session.id()).unwrap();
+    assert_eq!(usage, session.recursive_snark().memory_usage());
+    assert!(usage > 0);
+
+    let pool = Arc::new(ProverPool::new(pp, 1).with_memory_limit(usage + usage / 2));
+    let first = pool.open(&circuit, &[F::ONE]).unwrap();
+    assert!(matches!(
+      pool.open(&circuit, &[F::ONE]),
+      Err(NovaError::MemoryLimitExceeded { .. })
+    ));
+    drop(first);
+    assert!(pool.open(&circuit, &[F::ONE]).is_ok());
+  }
+}
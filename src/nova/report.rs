@@ -0,0 +1,152 @@
+//! This module defines `VerificationReport`, a structured account of the checks a verifier performs.
+//!
+//! `verify` methods stop at the first failing check and return a single error, whereas their
+//! `verify_detailed` counterparts record the outcome of every check along with the intermediate
+//! digests they computed, which helps pinpoint why a proof is rejected.
+use crate::errors::NovaError;
+use core::fmt;
+use ff::PrimeField;
+
+/// The status of a single check
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckStatus {
+  /// The check passed
+  Passed,
+  /// The check failed with the provided error
+  Failed(NovaError),
+  /// The check was not performed because a check it depends on failed
+  Skipped,
+}
+
+/// The outcome of a single named check
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckOutcome {
+  /// The name of the check
+  pub name: &'static str,
+  /// The status of the check
+  pub status: CheckStatus,
+}
+
+/// The outcomes of all checks performed while verifying a proof, in the order they were performed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerificationReport {
+  checks: Vec<CheckOutcome>,
+  digests: Vec<(&'static str, Vec<u8>)>,
+  fail_fast: bool,
+}
+
+impl VerificationReport {
+  /// Creates a report for `verify`, whose checks stop at the first failure
+  pub(crate) fn fail_fast() -> Self {
+    Self {
+      fail_fast: true,
+      ..Self::default()
+    }
+  }
+
+  /// Returns true if the report stops at the first failure and a check already failed, in which
+  /// case the remaining checks are skipped rather than performed
+  pub(crate) fn halted(&self) -> bool {
+    self.fail_fast && !self.is_ok()
+  }
+
+  /// Records that each of the checks was skipped
+  pub(crate) fn skip_all(&mut self, names: &[&'static str]) {
+    for name in names {
+      self.skip(name);
+    }
+  }
+
+  /// Returns the outcomes of all checks
+  pub fn checks(&self) -> &[CheckOutcome] {
+    &self.checks
+  }
+
+  /// Returns the status of the check with the provided name, if it is part of the report
+  pub fn status(&self, name: &str) -> Option<&CheckStatus> {
+    self
+      .checks
+      .iter()
+      .find(|c| c.name == name)
+      .map(|c| &c.status)
+  }
+
+  /// Returns the intermediate digests computed during verification, in canonical byte representation
+  pub fn digests(&self) -> &[(&'static str, Vec<u8>)] {
+    &self.digests
+  }
+
+  /// Returns true if every check passed
+  pub fn is_ok(&self) -> bool {
+    self.checks.iter().all(|c| c.status == CheckStatus::Passed)
+  }
+
+  /// Returns the error of the first failed check, if any
+  pub fn into_result(self) -> Result<(), NovaError> {
+    match self
+      .checks
+      .into_iter()
+      .find(|c| c.status != CheckStatus::Passed)
+    {
+      None => Ok(()),
+      Some(CheckOutcome {
+        status: CheckStatus::Failed(e),
+        ..
+      }) => Err(e),
+      // checks are only skipped after an earlier check failed
+      Some(_) => Err(NovaError::InternalError),
+    }
+  }
+
+  /// Records the outcome of a check and returns true if it passed
+  pub(crate) fn record(&mut self, name: &'static str, result: Result<(), NovaError>) -> bool {
+    self.record_value(name, result).is_some()
+  }
+
+  /// Records the outcome of a check that produces a value, and returns the value if it passed
+  pub(crate) fn record_value<T>(
+    &mut self,
+    name: &'static str,
+    result: Result<T, NovaError>,
+  ) -> Option<T> {
+    let (status, value) = match result {
+      Ok(v) => (CheckStatus::Passed, Some(v)),
+      Err(e) => (CheckStatus::Failed(e), None),
+    };
+    self.checks.push(CheckOutcome { name, status });
+    value
+  }
+
+  /// Records that a check was skipped
+  pub(crate) fn skip(&mut self, name: &'static str) {
+    self.checks.push(CheckOutcome {
+      name,
+      status: CheckStatus::Skipped,
+    });
+  }
+
+  /// Records an intermediate digest
+  pub(crate) fn record_digest<F: PrimeField>(&mut self, name: &'static str, value: &F) {
+    self.digests.push((name, value.to_repr().as_ref().to_vec()));
+  }
+}
+
+impl fmt::Display for VerificationReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for c in &self.checks {
+      match &c.status {
+        CheckStatus::Passed => writeln!(f, "{}: passed", c.name)?,
+        CheckStatus::Failed(e) => writeln!(f, "{}: FAILED ({e})", c.name)?,
+        CheckStatus::Skipped => writeln!(f, "{}: skipped", c.name)?,
+      }
+    }
+    for (name, bytes) in &self.digests {
+      write!(f, "{name}: ")?;
+      for b in bytes {
+        write!(f, "{b:02x}")?;
+      }
+      writeln!(f)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,247 @@
+//! This module lets an incremental computation move to a new version of its step circuit midway,
+//! by finalizing the current chain into a `CompressedSNARK` and starting a new chain that is
+//! linked to it.
+//!
+//! `RecursiveSNARK::rollover` takes the compressed proof of the current chain and starts a new
+//! chain with other public parameters, whose initial inputs are the outputs of the current chain
+//! followed by the digest of the finalized chain (see `rollover_digest`). The step circuit of the
+//! new chain thus has one more input than that of the current chain, as a last entry of `z`, like
+//! the wrappers of `traits::circuit`. The digest binds the public parameters, the number of steps,
+//! and the public IO of the finalized chain, so `RolloverLink::verify` accepts the initial inputs of
+//! the new chain only if they follow from a valid proof of the previous one.
+use super::{CompressedSNARK, PublicParams, RecursiveSNARK, VerifierKey};
+use crate::{
+  constants::NUM_HASH_BITS,
+  errors::NovaError,
+  traits::{circuit::StepCircuit, snark::RelaxedR1CSSNARKTrait, Engine, ROTrait},
+};
+use serde::{Deserialize, Serialize};
+
+/// Returns the digest of a chain with public parameters of digest `pp_digest`, which proved
+/// `num_steps` steps from `z0` to `zn`
+pub fn rollover_digest<E1, E2>(
+  pp_digest: &E1::Scalar,
+  num_steps: usize,
+  z0: &[E1::Scalar],
+  zn: &[E1::Scalar],
+) -> E1::Scalar
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  let mut ro = <E2 as Engine>::RO::new(Default::default());
+  ro.absorb(*pp_digest);
+  ro.absorb(E1::Scalar::from(num_steps as u64));
+  ro.absorb(io_digest::<E1, E2>(z0, zn));
+  ro.squeeze(NUM_HASH_BITS)
+}
+
+/// Returns the digest of the lengths and the values of `z0` and `zn`
+fn io_digest<E1, E2>(z0: &[E1::Scalar], zn: &[E1::Scalar]) -> E1::Scalar
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  let mut ro = <E2 as Engine>::RO::new(Default::default());
+  for z in [z0, zn] {
+    ro.absorb(E1::Scalar::from(z.len() as u64));
+    ro.absorb_batch(z);
+  }
+  ro.squeeze(NUM_HASH_BITS)
+}
+
+/// The compressed proof of a finalized chain along with its public IO, which links it to the chain
+/// that `RecursiveSNARK::rollover` started from it
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RolloverLink<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  snark: CompressedSNARK<E1, E2, C, S1, S2>,
+  num_steps: usize,
+  z0: Vec<E1::Scalar>,
+}
+
+impl<E1, E2, C, S1, S2> RolloverLink<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Returns the compressed proof of the finalized chain
+  pub fn snark(&self) -> &CompressedSNARK<E1, E2, C, S1, S2> {
+    &self.snark
+  }
+
+  /// Returns the number of steps of the finalized chain
+  pub fn num_steps(&self) -> usize {
+    self.num_steps
+  }
+
+  /// Returns the initial inputs of the finalized chain
+  pub fn inputs(&self) -> &[E1::Scalar] {
+    &self.z0
+  }
+
+  /// Returns the outputs of the finalized chain
+  pub fn outputs(&self) -> &[E1::Scalar] {
+    &self.snark.zn
+  }
+
+  /// Returns the initial inputs of the chain that continues the finalized chain, i.e., its outputs
+  /// followed by the digest of the finalized chain under the public parameters of digest
+  /// `pp_digest`
+  pub fn next_inputs(&self, pp_digest: &E1::Scalar) -> Vec<E1::Scalar> {
+    let digest = rollover_digest::<E1, E2>(pp_digest, self.num_steps, &self.z0, &self.snark.zn);
+    [self.snark.zn.as_slice(), &[digest]].concat()
+  }
+
+  /// Verifies the compressed proof of the finalized chain with its verifier key, and checks that
+  /// `next_z0`, the initial inputs of the new chain, are those the finalized chain links to
+  pub fn verify(
+    &self,
+    vk: &VerifierKey<E1, E2, C, S1, S2>,
+    next_z0: &[E1::Scalar],
+  ) -> Result<(), NovaError> {
+    self.snark.verify(vk, self.num_steps, &self.z0)?;
+    if self.next_inputs(&vk.light.pp_digest) == next_z0 {
+      Ok(())
+    } else {
+      Err(NovaError::ProofVerifyError {
+        reason: "The initial inputs do not continue the finalized chain".to_string(),
+      })
+    }
+  }
+}
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Finalizes the chain with `snark`, its compressed proof under `pp`, and starts a new chain for
+  /// the step circuit `c` under `new_pp`, whose initial inputs are those of
+  /// `RolloverLink::next_inputs`. Returns the new chain along with the link that verifiers check
+  /// its initial inputs against.
+  pub fn rollover<C2, S1, S2>(
+    self,
+    pp: &PublicParams<E1, E2, C>,
+    new_pp: &PublicParams<E1, E2, C2>,
+    c: &C2,
+    snark: CompressedSNARK<E1, E2, C, S1, S2>,
+  ) -> Result<(RecursiveSNARK<E1, E2, C2>, RolloverLink<E1, E2, C, S1, S2>), NovaError>
+  where
+    C2: StepCircuit<E1::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<E2>,
+  {
+    if self.i == 0 {
+      return Err(NovaError::InvalidNumSteps);
+    }
+    if snark.zn != self.zi {
+      return Err(NovaError::ProofVerifyError {
+        reason: "The compressed SNARK does not prove the chain".to_string(),
+      });
+    }
+    let link = RolloverLink {
+      snark,
+      num_steps: self.i,
+      z0: self.z0,
+    };
+    let next = RecursiveSNARK::new(new_pp, c, &link.next_inputs(&pp.digest()))?;
+    Ok((next, link))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+    provider::{ipa_pc, PallasEngine, VestaEngine},
+    spartan::snark::RelaxedR1CSSNARK,
+    traits::{circuit::NonTrivialCircuit, snark::default_ck_hint},
+  };
+  use ff::{Field, PrimeField};
+  use std::marker::PhantomData;
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+  type C = NonTrivialCircuit<F>;
+  type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+
+  /// A new version of `NonTrivialCircuit`, which cubes rather than squares, and carries the digest
+  /// of the previous chain as its last input
+  #[derive(Clone, Default)]
+  struct CubeCircuit<F: PrimeField>(PhantomData<F>);
+
+  impl<F: PrimeField> StepCircuit<F> for CubeCircuit<F> {
+    fn arity(&self) -> usize {
+      2
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      let x_sq = z[0].square(cs.namespace(|| "x_sq"))?;
+      let x_cu = x_sq.mul(cs.namespace(|| "x_cu"), &z[0])?;
+      Ok(vec![x_cu, z[1].clone()])
+    }
+  }
+
+  #[test]
+  fn test_rollover() {
+    let circuit = C::new(2);
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+    let (pk, vk) = CompressedSNARK::<_, _, _, S<E1>, S<E2>>::setup(&pp).unwrap();
+
+    let z0 = [F::from(3)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..3 {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+
+    // the new chain starts from the outputs of the finalized chain and its digest
+    let circuit2 = CubeCircuit::<F>::default();
+    let pp2 = PublicParams::<E1, E2, CubeCircuit<F>>::setup(
+      &circuit2,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    let zn = recursive_snark.outputs().to_vec();
+    let (mut next, link) = recursive_snark
+      .rollover(&pp, &pp2, &circuit2, snark)
+      .unwrap();
+    let digest = rollover_digest::<E1, E2>(&pp.digest(), 3, &z0, &zn);
+    let next_z0 = [zn[0], digest];
+    assert_eq!(link.next_inputs(&pp.digest()), next_z0);
+    for _ in 0..2 {
+      next.prove_step(&pp2, &circuit2).unwrap();
+    }
+    let (pk2, vk2) = CompressedSNARK::<_, _, _, S<E1>, S<E2>>::setup(&pp2).unwrap();
+    let snark2 = CompressedSNARK::prove(&pp2, &pk2, &next).unwrap();
+
+    // a verifier checks the link, then the new chain from the inputs it links to
+    assert!(link.verify(&vk, &next_z0).is_ok());
+    let outputs = snark2.verify(&vk2, 2, &next_z0).unwrap();
+    assert_eq!(outputs, vec![zn[0].cube().cube(), digest]);
+
+    // other initial inputs of the new chain are not linked to the finalized chain
+    assert!(link.verify(&vk, &[zn[0] + F::ONE, digest]).is_err());
+    assert!(link.verify(&vk, &[zn[0], digest + F::ONE]).is_err());
+  }
+}
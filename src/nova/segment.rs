@@ -0,0 +1,243 @@
+//! This module defines `ConstantSegment`, which spares a `RecursiveSNARK` from committing at every
+//! step to the variables of the step circuit that never change (see `StepCircuit::constant_vars`).
+//!
+//! A commitment to a witness is the sum of the commitments to disjoint segments of it, each made
+//! with the generators at the offset of the segment. The recursive SNARK thus commits to the
+//! constant segment of the primary witness once, and at each step only commits to the rest of the
+//! witness, and adds the commitment to the segment. The result is the commitment to the whole
+//! witness, so folding and verification are unchanged. A step whose witness does not hold the
+//! constant values in the segment is rejected with `NovaError::IncorrectWitness`.
+//!
+//! The segment is not serialized with the recursive SNARK, and is committed to again at the first
+//! step proven after deserialization. Steps that commit while synthesizing (see
+//! `RecursiveSNARK::enable_streaming_commit`) commit to the whole witness.
+use crate::{
+  errors::NovaError,
+  frontend::solver::SatisfyingAssignment,
+  r1cs::{R1CSInstance, R1CSShape, R1CSWitness},
+  traits::{commitment::CommitmentEngineTrait, Engine},
+  Commitment, CommitmentKey,
+};
+use ff::Field;
+use rand_core::OsRng;
+use std::ops::Range;
+
+/// A segment of a witness that holds the same values at every step, along with its commitment
+#[derive(Clone, Debug)]
+pub(crate) struct ConstantSegment<E: Engine> {
+  offset: usize,
+  values: Vec<E::Scalar>,
+  comm: Commitment<E>,
+}
+
+impl<E: Engine> ConstantSegment<E> {
+  /// Commits to the values of `W` at `range`
+  pub(crate) fn new(
+    ck: &CommitmentKey<E>,
+    W: &[E::Scalar],
+    range: Range<usize>,
+  ) -> Result<Self, NovaError> {
+    let values = W
+      .get(range.clone())
+      .ok_or(NovaError::InvalidWitnessLength)?
+      .to_vec();
+    let comm = E::CE::commit_at(ck, &values, range.start);
+    Ok(Self {
+      offset: range.start,
+      values,
+      comm,
+    })
+  }
+
+  /// Returns the range of the segment in the witness
+  pub(crate) fn range(&self) -> Range<usize> {
+    self.offset..self.offset + self.values.len()
+  }
+
+  /// Commits to `W` with the blind `r`, committing only to the values outside of the segment
+  pub(crate) fn commit(
+    &self,
+    ck: &CommitmentKey<E>,
+    W: &[E::Scalar],
+    r: &E::Scalar,
+  ) -> Result<Commitment<E>, NovaError> {
+    let range = self.range();
+    if W.get(range.clone()) != Some(self.values.as_slice()) {
+      return Err(NovaError::IncorrectWitness);
+    }
+    Ok(
+      E::CE::commit(ck, &W[..range.start], r)
+        + E::CE::commit_at(ck, &W[range.end..], range.end)
+        + self.comm,
+    )
+  }
+
+  /// Consumes `cs` and returns an instance and witness for `shape`, like
+  /// `into_r1cs_instance_and_witness`, but committing only to the values outside of the segment
+  pub(crate) fn r1cs_instance_and_witness(
+    &self,
+    cs: SatisfyingAssignment<E>,
+    shape: &R1CSShape<E>,
+    ck: &CommitmentKey<E>,
+  ) -> Result<(R1CSInstance<E>, R1CSWitness<E>), NovaError> {
+    let mut W = cs.aux_assignment;
+    if W.len() > shape.num_vars {
+      return Err(NovaError::InvalidWitnessLength);
+    }
+    W.resize(shape.num_vars, E::Scalar::ZERO);
+
+    let W = R1CSWitness {
+      W,
+      r_W: E::Scalar::random(&mut OsRng),
+    };
+    let comm_W = self.commit(ck, &W.W, &W.r_W)?;
+    let instance = R1CSInstance::new(shape, &comm_W, &cs.input_assignment[1..])?;
+
+    Ok((instance, W))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    errors::NovaError,
+    frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+    nova::{PublicParams, RecursiveSNARK},
+    provider::{PallasEngine, VestaEngine},
+    traits::{
+      circuit::{PaddedCircuit, StepCircuit},
+      snark::default_ck_hint,
+      Engine,
+    },
+  };
+  use ff::{Field, PrimeField};
+  use std::ops::Range;
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+
+  /// A step circuit that allocates a table of constants and adds them to its input. The table
+  /// depends on the input when `varying` is set, which breaks the promise of `constant_vars`.
+  #[derive(Clone)]
+  struct TableCircuit {
+    table: Vec<F>,
+    varying: bool,
+  }
+
+  impl<Scalar: PrimeField> StepCircuit<Scalar> for TableCircuit
+  where
+    F: Into<Scalar>,
+  {
+    fn arity(&self) -> usize {
+      1
+    }
+
+    fn synthesize<CS: ConstraintSystem<Scalar>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<Scalar>],
+    ) -> Result<Vec<AllocatedNum<Scalar>>, SynthesisError> {
+      let table = self
+        .table
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+          AllocatedNum::alloc(cs.namespace(|| format!("table {i}")), || {
+            let v: Scalar = (*v).into();
+            match (self.varying, z[0].get_value()) {
+              (true, Some(z)) => Ok(v + z),
+              _ => Ok(v),
+            }
+          })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+      let z_next = table
+        .iter()
+        .enumerate()
+        .try_fold(z[0].clone(), |acc, (i, v)| {
+          acc.add(cs.namespace(|| format!("add {i}")), v)
+        })?;
+      Ok(vec![z_next])
+    }
+
+    fn constant_vars(&self) -> Range<usize> {
+      0..self.table.len()
+    }
+  }
+
+  fn table_circuit(varying: bool) -> TableCircuit {
+    TableCircuit {
+      table: (0..32).map(|i| F::from(i as u64 + 1)).collect(),
+      varying,
+    }
+  }
+
+  #[test]
+  fn test_constant_segment() {
+    let circuit = table_circuit(false);
+    let pp = PublicParams::<E1, E2, TableCircuit>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    let z0 = [F::ZERO];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    let range = recursive_snark.constant_segment.as_ref().unwrap().range();
+    assert_eq!(range.len(), 32);
+    for _ in 0..3 {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    // each step adds 1 + 2 + ... + 32
+    assert_eq!(
+      recursive_snark.verify(&pp, 3, &z0).unwrap(),
+      vec![F::from(3 * 528)]
+    );
+
+    // the segment is committed to again after the recursive SNARK is deserialized
+    let mut recursive_snark: RecursiveSNARK<E1, E2, TableCircuit> =
+      bincode::deserialize(&bincode::serialize(&recursive_snark).unwrap()).unwrap();
+    assert!(recursive_snark.constant_segment.is_none());
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    assert_eq!(
+      recursive_snark.constant_segment.as_ref().unwrap().range(),
+      range
+    );
+    assert!(recursive_snark.verify(&pp, 4, &z0).is_ok());
+
+    // values that change across steps are rejected
+    let varying = table_circuit(true);
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &varying, &z0).unwrap();
+    recursive_snark.prove_step(&pp, &varying).unwrap();
+    assert_eq!(
+      recursive_snark.prove_step(&pp, &varying),
+      Err(NovaError::IncorrectWitness)
+    );
+  }
+
+  #[test]
+  fn test_constant_segment_padded() {
+    type C = PaddedCircuit<F, TableCircuit>;
+    let circuit = table_circuit(false);
+    let pp = PublicParams::<E1, E2, C>::setup(
+      &C::step(circuit.clone()),
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    let z0 = [F::ZERO];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &C::step(circuit.clone()), &z0).unwrap();
+    recursive_snark
+      .prove_step(&pp, &C::step(circuit.clone()))
+      .unwrap();
+    recursive_snark
+      .prove_step(&pp, &C::step(circuit.clone()))
+      .unwrap();
+    recursive_snark.prove_noop_steps(&pp, &circuit, 2).unwrap();
+    assert_eq!(
+      recursive_snark.verify(&pp, 4, &z0).unwrap(),
+      vec![F::from(2 * 528)]
+    );
+  }
+}
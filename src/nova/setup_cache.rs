@@ -0,0 +1,246 @@
+//! This module defines `SetupCache`, which caches the prover and verifier keys that
+//! `CompressedSNARK::setup` derives from public parameters, so that services that restart
+//! frequently do not redo the setup every time.
+//!
+//! The keys only depend on the public parameters, whose digest covers the commitment keys, and on
+//! the SNARKs `S1` and `S2`, so entries are keyed by the digest together with the type names of the
+//! SNARKs. Entries are kept in memory and, optionally, in a directory, as bincode files named after
+//! their key. Type names may change across compiler versions, and a file that cannot be read or
+//! that was derived from other public parameters is ignored and overwritten, so a stale or
+//! corrupted directory only costs a setup.
+use super::{CompressedSNARK, ProverKey, PublicParams, VerifierKey};
+use crate::{
+  errors::NovaError,
+  traits::{circuit::StepCircuit, snark::RelaxedR1CSSNARKTrait, Engine},
+};
+use core::any::type_name;
+use ff::PrimeField;
+use sha3::{Digest, Sha3_256};
+use std::{
+  collections::HashMap,
+  fs::{self, File},
+  io::{BufReader, BufWriter},
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+  },
+};
+
+/// The extension of the files of a `SetupCache`
+const SETUP_FILE_EXTENSION: &str = "setup";
+
+/// The keys of a `CompressedSNARK`, as shared by a `SetupCache`
+type Keys<E1, E2, C, S1, S2> = (
+  Arc<ProverKey<E1, E2, C, S1, S2>>,
+  Arc<VerifierKey<E1, E2, C, S1, S2>>,
+);
+
+/// A cache of the prover and verifier keys of `CompressedSNARK`, keyed by the digest of the public
+/// parameters they were derived from
+pub struct SetupCache<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  dir: Option<PathBuf>,
+  entries: Mutex<HashMap<[u8; 32], Keys<E1, E2, C, S1, S2>>>,
+  hits: AtomicUsize,
+}
+
+impl<E1, E2, C, S1, S2> Default for SetupCache<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<E1, E2, C, S1, S2> SetupCache<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Creates an empty cache that only keeps keys in memory
+  pub fn new() -> Self {
+    Self {
+      dir: None,
+      entries: Mutex::new(HashMap::new()),
+      hits: AtomicUsize::new(0),
+    }
+  }
+
+  /// Creates a cache that also keeps keys in `dir`, which is created if it does not exist, so that
+  /// they outlive the process
+  pub fn with_dir(dir: impl Into<PathBuf>) -> Result<Self, NovaError> {
+    let dir = dir.into();
+    fs::create_dir_all(&dir).map_err(|err| NovaError::SetupError {
+      reason: format!("cannot create the setup cache {}: {err}", dir.display()),
+    })?;
+    Ok(Self {
+      dir: Some(dir),
+      ..Self::new()
+    })
+  }
+
+  /// Returns the directory of the cache, if any
+  pub fn dir(&self) -> Option<&Path> {
+    self.dir.as_deref()
+  }
+
+  /// Returns the number of setups that were served from memory or from the directory
+  pub fn hits(&self) -> usize {
+    self.hits.load(Ordering::Relaxed)
+  }
+
+  /// Returns the keys that `CompressedSNARK::setup` derives from `pp`, from the cache if they are
+  /// in it, and otherwise by running the setup and adding its keys to the cache.
+  ///
+  /// Keys that cannot be written to the directory are only kept in memory.
+  pub fn setup(&self, pp: &PublicParams<E1, E2, C>) -> Result<Keys<E1, E2, C, S1, S2>, NovaError> {
+    let key = Self::key(pp);
+    if let Some(keys) = self.entries.lock().unwrap().get(&key) {
+      self.hits.fetch_add(1, Ordering::Relaxed);
+      return Ok(keys.clone());
+    }
+
+    let keys = match self.load(&key, pp) {
+      Some(keys) => {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        keys
+      }
+      None => {
+        let (pk, vk) = CompressedSNARK::<E1, E2, C, S1, S2>::setup(pp)?;
+        let keys = (Arc::new(pk), Arc::new(vk));
+        self.store(&key, &keys);
+        keys
+      }
+    };
+    self.entries.lock().unwrap().insert(key, keys.clone());
+    Ok(keys)
+  }
+
+  /// Empties the in-memory part of the cache, leaving the directory untouched
+  pub fn clear(&self) {
+    self.entries.lock().unwrap().clear();
+  }
+
+  /// Computes the key of the entry of the public parameters `pp`
+  fn key(pp: &PublicParams<E1, E2, C>) -> [u8; 32] {
+    Sha3_256::new()
+      .chain_update(pp.digest().to_repr())
+      .chain_update(type_name::<S1>())
+      .chain_update([0u8])
+      .chain_update(type_name::<S2>())
+      .finalize()
+      .into()
+  }
+
+  /// Returns the path of the file of the entry with the given key
+  fn path(&self, key: &[u8; 32]) -> Option<PathBuf> {
+    let name = key.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    let dir = self.dir.as_ref()?;
+    Some(dir.join(name).with_extension(SETUP_FILE_EXTENSION))
+  }
+
+  /// Reads the entry with the given key from the directory, if it holds keys derived from `pp`
+  fn load(&self, key: &[u8; 32], pp: &PublicParams<E1, E2, C>) -> Option<Keys<E1, E2, C, S1, S2>> {
+    let file = File::open(self.path(key)?).ok()?;
+    let (pk, vk): (ProverKey<E1, E2, C, S1, S2>, VerifierKey<E1, E2, C, S1, S2>) =
+      bincode::deserialize_from(BufReader::new(file)).ok()?;
+    (vk.light.pp_digest == pp.digest()).then(|| (Arc::new(pk), Arc::new(vk)))
+  }
+
+  /// Writes an entry to the directory, through a temporary file so that concurrent readers never
+  /// see a partial entry
+  fn store(&self, key: &[u8; 32], keys: &Keys<E1, E2, C, S1, S2>) {
+    let Some(path) = self.path(key) else {
+      return;
+    };
+    let tmp = path.with_extension(format!("{SETUP_FILE_EXTENSION}.{}", std::process::id()));
+    let written = File::create(&tmp)
+      .ok()
+      .and_then(|file| bincode::serialize_into(BufWriter::new(file), &(&*keys.0, &*keys.1)).ok())
+      .and_then(|_| fs::rename(&tmp, &path).ok());
+    if written.is_none() {
+      let _ = fs::remove_file(&tmp);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    nova::RecursiveSNARK,
+    provider::{ipa_pc, PallasEngine, VestaEngine},
+    spartan::snark::RelaxedR1CSSNARK,
+    traits::{
+      circuit::TrivialCircuit,
+      snark::{default_ck_hint, RelaxedR1CSSNARKTrait},
+    },
+  };
+  use ff::Field;
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type C = TrivialCircuit<<E1 as Engine>::Scalar>;
+  type S1 = RelaxedR1CSSNARK<E1, ipa_pc::EvaluationEngine<E1>>;
+  type S2 = RelaxedR1CSSNARK<E2, ipa_pc::EvaluationEngine<E2>>;
+  type Cache = SetupCache<E1, E2, C, S1, S2>;
+
+  #[test]
+  fn test_setup_cache() {
+    let circuit = C::default();
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*S1::ck_floor(), &*S2::ck_floor()).unwrap();
+    let dir = std::env::temp_dir().join(format!("nova_setup_cache_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    // the first setup is computed, and the second one is served from memory
+    let cache = Cache::with_dir(&dir).unwrap();
+    let (pk, vk) = cache.setup(&pp).unwrap();
+    assert_eq!(cache.hits(), 0);
+    let (pk_cached, _) = cache.setup(&pp).unwrap();
+    assert!(Arc::ptr_eq(&pk, &pk_cached));
+    assert_eq!(cache.hits(), 1);
+
+    // a new process is served from the directory, and its keys prove and verify
+    let cache = Cache::with_dir(&dir).unwrap();
+    let (pk, vk_read) = cache.setup(&pp).unwrap();
+    assert_eq!(cache.hits(), 1);
+    assert_eq!(vk_read.light.pp_digest, vk.light.pp_digest);
+
+    let z0 = [<E1 as Engine>::Scalar::ZERO];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    recursive_snark.prove_step(&pp, &circuit).unwrap();
+    let snark = CompressedSNARK::<_, _, _, S1, S2>::prove(&pp, &pk, &recursive_snark).unwrap();
+    snark.verify(&vk_read, 1, &z0).unwrap();
+
+    // entries that cannot be read, or that belong to other public parameters, are recomputed
+    for entry in fs::read_dir(&dir).unwrap() {
+      fs::write(entry.unwrap().path(), b"corrupted").unwrap();
+    }
+    let cache = Cache::with_dir(&dir).unwrap();
+    cache.setup(&pp).unwrap();
+    assert_eq!(cache.hits(), 0);
+
+    let pp_other =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+    let (_, vk_other) = Cache::with_dir(&dir).unwrap().setup(&pp_other).unwrap();
+    assert_eq!(vk_other.light.pp_digest, pp_other.digest());
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
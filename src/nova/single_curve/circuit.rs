@@ -0,0 +1,559 @@
+//! The augmented circuit of the single-curve mode of Nova.
+//! The circuit is over the scalar field of the curve whose points commit to its witness, so the
+//! commitments are emulated with non-native arithmetic and the circuit folds its own previous
+//! invocation into the running instance. The running instance is bound by the hash
+//! H(params = H(shape, ck), i, z0, zi, U, ri), which is the only public IO of the circuit.
+
+use crate::{
+  constants::{NUM_CHALLENGE_BITS, NUM_HASH_BITS},
+  frontend::{
+    num::AllocatedNum, AllocatedBit, Assignment, Boolean, ConstraintSystem, SynthesisError,
+  },
+  gadgets::{
+    ecc::AllocatedNonnativePoint,
+    utils::{
+      alloc_num_equals, alloc_zero, conditionally_select, conditionally_select_vec, le_bits_to_num,
+    },
+  },
+  provider::traits::DlogGroup,
+  r1cs::{R1CSInstance, RelaxedR1CSInstance},
+  traits::{
+    circuit::StepCircuit, commitment::CommitmentTrait, Engine, RO2ConstantsCircuit, ROCircuitTrait,
+  },
+  Commitment,
+};
+use ff::Field;
+use serde::{Deserialize, Serialize};
+
+/// A type that holds the non-deterministic inputs for the single-curve augmented circuit
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SingleCurveAugmentedCircuitInputs<E: Engine> {
+  pp_digest: E::Scalar,
+  i: E::Scalar,
+  z0: Vec<E::Scalar>,
+  zi: Option<Vec<E::Scalar>>,
+  U: Option<RelaxedR1CSInstance<E>>,
+  ri: Option<E::Scalar>,
+  r_next: E::Scalar,
+  u: Option<R1CSInstance<E>>,
+  T: Option<Commitment<E>>,
+}
+
+impl<E: Engine> SingleCurveAugmentedCircuitInputs<E> {
+  /// Create new inputs/witness for the verification circuit
+  pub fn new(
+    pp_digest: E::Scalar,
+    i: E::Scalar,
+    z0: Vec<E::Scalar>,
+    zi: Option<Vec<E::Scalar>>,
+    U: Option<RelaxedR1CSInstance<E>>,
+    ri: Option<E::Scalar>,
+    r_next: E::Scalar,
+    u: Option<R1CSInstance<E>>,
+    T: Option<Commitment<E>>,
+  ) -> Self {
+    Self {
+      pp_digest,
+      i,
+      z0,
+      zi,
+      U,
+      ri,
+      r_next,
+      u,
+      T,
+    }
+  }
+}
+
+/// An allocated R1CS instance with non-native commitments and a single public input
+#[derive(Clone)]
+struct AllocatedNonnativeR1CSInstance<E: Engine> {
+  comm_W: AllocatedNonnativePoint<E>,
+  X: AllocatedNum<E::Scalar>,
+}
+
+impl<E: Engine> AllocatedNonnativeR1CSInstance<E> {
+  /// Allocates the given `R1CSInstance` as a witness of the circuit
+  fn alloc<CS: ConstraintSystem<E::Scalar>>(
+    mut cs: CS,
+    u: Option<&R1CSInstance<E>>,
+  ) -> Result<Self, SynthesisError> {
+    // comm_W is checked to be on the curve when it is folded
+    let comm_W = AllocatedNonnativePoint::alloc(
+      cs.namespace(|| "allocate comm_W"),
+      u.map(|u| u.comm_W.to_coordinates()),
+    )?;
+
+    let X = AllocatedNum::alloc(cs.namespace(|| "allocate X"), || {
+      Ok(u.map_or(E::Scalar::ZERO, |u| u.X[0]))
+    })?;
+
+    Ok(Self { comm_W, X })
+  }
+}
+
+/// An allocated relaxed R1CS instance with non-native commitments and a single public input
+struct AllocatedNonnativeRelaxedR1CSInstance<E: Engine> {
+  W: AllocatedNonnativePoint<E>,
+  E: AllocatedNonnativePoint<E>,
+  u: AllocatedNum<E::Scalar>,
+  X: AllocatedNum<E::Scalar>,
+}
+
+impl<E: Engine> AllocatedNonnativeRelaxedR1CSInstance<E>
+where
+  E::GE: DlogGroup,
+{
+  /// Allocates the given `RelaxedR1CSInstance` as a witness of the circuit
+  fn alloc<CS: ConstraintSystem<E::Scalar>>(
+    mut cs: CS,
+    inst: Option<&RelaxedR1CSInstance<E>>,
+  ) -> Result<Self, SynthesisError> {
+    // We do not need to check that W or E are well-formed (e.g., on the curve) as we do a hash check
+    // in the augmented circuit, which ensures that the relaxed instance came from a prior iteration
+    let W = AllocatedNonnativePoint::alloc(
+      cs.namespace(|| "allocate W"),
+      inst.map(|inst| inst.comm_W.to_coordinates()),
+    )?;
+
+    let E = AllocatedNonnativePoint::alloc(
+      cs.namespace(|| "allocate E"),
+      inst.map(|inst| inst.comm_E.to_coordinates()),
+    )?;
+
+    let u = AllocatedNum::alloc(cs.namespace(|| "allocate u"), || {
+      Ok(inst.map_or(E::Scalar::ZERO, |inst| inst.u))
+    })?;
+
+    let X = AllocatedNum::alloc(cs.namespace(|| "allocate X"), || {
+      Ok(inst.map_or(E::Scalar::ZERO, |inst| inst.X[0]))
+    })?;
+
+    Ok(Self { W, E, u, X })
+  }
+
+  /// Allocates the hardcoded default `RelaxedR1CSInstance` in the circuit.
+  /// W = E = 0, u = 0, X = 0
+  fn default<CS: ConstraintSystem<E::Scalar>>(mut cs: CS) -> Result<Self, SynthesisError> {
+    let W = AllocatedNonnativePoint::default(cs.namespace(|| "allocate W"))?;
+    let E = W.clone();
+
+    let u = alloc_zero(cs.namespace(|| "allocate u"));
+    let X = u.clone();
+
+    Ok(Self { W, E, u, X })
+  }
+
+  /// Absorb the provided instance in the RO
+  fn absorb_in_ro<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    ro: &mut E::RO2Circuit,
+  ) -> Result<(), SynthesisError> {
+    self.W.absorb_in_ro(cs.namespace(|| "absorb W"), ro)?;
+    self.E.absorb_in_ro(cs.namespace(|| "absorb E"), ro)?;
+    ro.absorb(&self.u);
+    ro.absorb(&self.X);
+    Ok(())
+  }
+
+  /// Folds self with an r1cs instance and returns the result
+  fn fold_with_r1cs<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    params: &AllocatedNum<E::Scalar>, // hash of R1CSShape of F'
+    u: &AllocatedNonnativeR1CSInstance<E>,
+    T: &AllocatedNonnativePoint<E>,
+    ro_consts: RO2ConstantsCircuit<E>,
+  ) -> Result<Self, SynthesisError> {
+    // Compute r:
+    let mut ro = E::RO2Circuit::new(ro_consts);
+    ro.absorb(params);
+
+    // running instance `U` does not need to absorbed since u.X = Hash(params, U, i, z0, zi)
+    u.comm_W
+      .absorb_in_ro(cs.namespace(|| "absorb u.W"), &mut ro)?;
+    ro.absorb(&u.X);
+
+    T.absorb_in_ro(cs.namespace(|| "absorb T"), &mut ro)?;
+    let r_bits = ro.squeeze(cs.namespace(|| "r bits"), NUM_CHALLENGE_BITS)?;
+    let r = le_bits_to_num(cs.namespace(|| "r"), &r_bits)?;
+
+    // W_fold = self.W + r * u.W
+    let W_fold = u
+      .comm_W
+      .mul_add(cs.namespace(|| "self.W + r * u.W"), &r_bits, &self.W)?;
+
+    // E_fold = self.E + r * T
+    let E_fold = T.mul_add(cs.namespace(|| "self.E + r * T"), &r_bits, &self.E)?;
+
+    // u_fold = u_r + r
+    let u_fold = AllocatedNum::alloc(cs.namespace(|| "u_fold"), || {
+      Ok(*self.u.get_value().get()? + r.get_value().get()?)
+    })?;
+    cs.enforce(
+      || "Check u_fold",
+      |lc| lc,
+      |lc| lc,
+      |lc| lc + u_fold.get_variable() - self.u.get_variable() - r.get_variable(),
+    );
+
+    // X_fold = self.X + r * u.X
+    let X_fold = AllocatedNum::alloc(cs.namespace(|| "X_fold"), || {
+      Ok(*self.X.get_value().get()? + *r.get_value().get()? * *u.X.get_value().get()?)
+    })?;
+    cs.enforce(
+      || "Check X_fold",
+      |lc| lc + r.get_variable(),
+      |lc| lc + u.X.get_variable(),
+      |lc| lc + X_fold.get_variable() - self.X.get_variable(),
+    );
+
+    Ok(Self {
+      W: W_fold,
+      E: E_fold,
+      u: u_fold,
+      X: X_fold,
+    })
+  }
+
+  /// If the condition is true then returns this otherwise it returns the other
+  fn conditionally_select<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    other: &Self,
+    condition: &Boolean,
+  ) -> Result<Self, SynthesisError> {
+    let W = AllocatedNonnativePoint::conditionally_select(
+      cs.namespace(|| "W = cond ? self.W : other.W"),
+      &self.W,
+      &other.W,
+      condition,
+    )?;
+
+    let E = AllocatedNonnativePoint::conditionally_select(
+      cs.namespace(|| "E = cond ? self.E : other.E"),
+      &self.E,
+      &other.E,
+      condition,
+    )?;
+
+    let u = conditionally_select(
+      cs.namespace(|| "u = cond ? self.u : other.u"),
+      &self.u,
+      &other.u,
+      condition,
+    )?;
+
+    let X = conditionally_select(
+      cs.namespace(|| "X = cond ? self.X : other.X"),
+      &self.X,
+      &other.X,
+      condition,
+    )?;
+
+    Ok(Self { W, E, u, X })
+  }
+}
+
+/// The augmented circuit F' of the single-curve mode that includes a step circuit F
+/// and the circuit for the verifier in Nova's non-interactive folding scheme
+pub struct SingleCurveAugmentedCircuit<'a, E: Engine, SC: StepCircuit<E::Scalar>> {
+  ro_consts: RO2ConstantsCircuit<E>,
+  inputs: Option<SingleCurveAugmentedCircuitInputs<E>>,
+  step_circuit: &'a SC, // The function that is applied for each step
+}
+
+impl<'a, E, SC> SingleCurveAugmentedCircuit<'a, E, SC>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+  SC: StepCircuit<E::Scalar>,
+{
+  /// Create a new verification circuit for the input relaxed r1cs instances
+  pub const fn new(
+    inputs: Option<SingleCurveAugmentedCircuitInputs<E>>,
+    step_circuit: &'a SC,
+    ro_consts: RO2ConstantsCircuit<E>,
+  ) -> Self {
+    Self {
+      inputs,
+      step_circuit,
+      ro_consts,
+    }
+  }
+
+  /// Allocate all witnesses and return
+  fn alloc_witness<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    arity: usize,
+  ) -> Result<
+    (
+      AllocatedNum<E::Scalar>,
+      AllocatedNum<E::Scalar>,
+      Vec<AllocatedNum<E::Scalar>>,
+      Vec<AllocatedNum<E::Scalar>>,
+      AllocatedNonnativeRelaxedR1CSInstance<E>,
+      AllocatedNum<E::Scalar>,
+      AllocatedNum<E::Scalar>,
+      AllocatedNonnativeR1CSInstance<E>,
+      AllocatedNonnativePoint<E>,
+    ),
+    SynthesisError,
+  > {
+    // Allocate the pp_digest
+    let pp_digest = AllocatedNum::alloc(cs.namespace(|| "pp_digest"), || {
+      Ok(self.inputs.get()?.pp_digest)
+    })?;
+
+    // Allocate i
+    let i = AllocatedNum::alloc(cs.namespace(|| "i"), || Ok(self.inputs.get()?.i))?;
+
+    // Allocate z0
+    let z_0 = (0..arity)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("z0_{i}")), || {
+          Ok(self.inputs.get()?.z0[i])
+        })
+      })
+      .collect::<Result<Vec<AllocatedNum<E::Scalar>>, _>>()?;
+
+    // Allocate zi. If inputs.zi is not provided (base case) allocate default value 0
+    let zero = vec![E::Scalar::ZERO; arity];
+    let z_i = (0..arity)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("zi_{i}")), || {
+          Ok(self.inputs.get()?.zi.as_ref().unwrap_or(&zero)[i])
+        })
+      })
+      .collect::<Result<Vec<AllocatedNum<E::Scalar>>, _>>()?;
+
+    // Allocate the running instance
+    let U = AllocatedNonnativeRelaxedR1CSInstance::alloc(
+      cs.namespace(|| "Allocate U"),
+      self.inputs.as_ref().and_then(|inputs| inputs.U.as_ref()),
+    )?;
+
+    // Allocate ri
+    let r_i = AllocatedNum::alloc(cs.namespace(|| "ri"), || {
+      Ok(self.inputs.get()?.ri.unwrap_or(E::Scalar::ZERO))
+    })?;
+
+    // Allocate r_i+1
+    let r_next = AllocatedNum::alloc(cs.namespace(|| "r_i+1"), || Ok(self.inputs.get()?.r_next))?;
+
+    // Allocate the instance to be folded in
+    let u = AllocatedNonnativeR1CSInstance::alloc(
+      cs.namespace(|| "allocate instance u to fold"),
+      self.inputs.as_ref().and_then(|inputs| inputs.u.as_ref()),
+    )?;
+
+    // Allocate T, which is checked to be on the curve when it is folded
+    let T = AllocatedNonnativePoint::alloc(
+      cs.namespace(|| "allocate T"),
+      self
+        .inputs
+        .as_ref()
+        .and_then(|inputs| inputs.T.map(|T| T.to_coordinates())),
+    )?;
+
+    Ok((pp_digest, i, z_0, z_i, U, r_i, r_next, u, T))
+  }
+
+  fn synthesize_hash<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    pp_digest: &AllocatedNum<E::Scalar>,
+    i: &AllocatedNum<E::Scalar>,
+    z_0: &[AllocatedNum<E::Scalar>],
+    z_i: &[AllocatedNum<E::Scalar>],
+    U: &AllocatedNonnativeRelaxedR1CSInstance<E>,
+    r_i: &AllocatedNum<E::Scalar>,
+  ) -> Result<AllocatedNum<E::Scalar>, SynthesisError> {
+    // Hash(pp_digest, i, z_0, z_i, U, r_i)
+    let mut ro = E::RO2Circuit::new(self.ro_consts.clone());
+    ro.absorb(pp_digest);
+    ro.absorb(i);
+    for e in z_0 {
+      ro.absorb(e);
+    }
+    for e in z_i {
+      ro.absorb(e);
+    }
+    U.absorb_in_ro(cs.namespace(|| "absorb U"), &mut ro)?;
+    ro.absorb(r_i);
+
+    let hash_bits = ro.squeeze(cs.namespace(|| "Input hash"), NUM_HASH_BITS)?;
+    le_bits_to_num(cs.namespace(|| "bits to hash"), &hash_bits)
+  }
+
+  /// synthesize circuit giving constraint system
+  pub fn synthesize<CS: ConstraintSystem<E::Scalar>>(
+    self,
+    cs: &mut CS,
+  ) -> Result<Vec<AllocatedNum<E::Scalar>>, SynthesisError> {
+    let arity = self.step_circuit.arity();
+
+    // Allocate all witnesses
+    let (pp_digest, i, z_0, z_i, U, r_i, r_next, u, T) =
+      self.alloc_witness(cs.namespace(|| "allocate the circuit witness"), arity)?;
+
+    // Compute variable indicating if this is the base case
+    let zero = alloc_zero(cs.namespace(|| "zero"));
+    let is_base_case = alloc_num_equals(cs.namespace(|| "Check if base case"), &i.clone(), &zero)?;
+
+    // compute hash of the non-deterministic inputs
+    let hash = self.synthesize_hash(
+      cs.namespace(|| "synthesize input hash check"),
+      &pp_digest,
+      &i,
+      &z_0,
+      &z_i,
+      &U,
+      &r_i,
+    )?;
+
+    let check_non_base_pass = alloc_num_equals(
+      cs.namespace(|| "check consistency of u.X with H(params, U, i, z0, zi)"),
+      &u.X,
+      &hash,
+    )?;
+
+    // In the base case, we simply return the default running instance
+    let Unew_base =
+      AllocatedNonnativeRelaxedR1CSInstance::default(cs.namespace(|| "Allocate U_default"))?;
+
+    // Run NIFS Verifier
+    let Unew_non_base = U.fold_with_r1cs(
+      cs.namespace(|| "compute fold of U and u"),
+      &pp_digest,
+      &u,
+      &T,
+      self.ro_consts.clone(),
+    )?;
+
+    // Either check_non_base_pass=true or we are in the base case
+    let should_be_false = AllocatedBit::nor(
+      cs.namespace(|| "check_non_base_pass nor base_case"),
+      &check_non_base_pass,
+      &is_base_case,
+    )?;
+    cs.enforce(
+      || "check_non_base_pass nor base_case = false",
+      |lc| lc + should_be_false.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc,
+    );
+
+    // Compute the U_new
+    let Unew = Unew_base.conditionally_select(
+      cs.namespace(|| "compute U_new"),
+      &Unew_non_base,
+      &Boolean::from(is_base_case.clone()),
+    )?;
+
+    // Compute i + 1
+    let i_new = AllocatedNum::alloc(cs.namespace(|| "i + 1"), || {
+      Ok(*i.get_value().get()? + E::Scalar::ONE)
+    })?;
+    cs.enforce(
+      || "check i + 1",
+      |lc| lc,
+      |lc| lc,
+      |lc| lc + i_new.get_variable() - CS::one() - i.get_variable(),
+    );
+
+    // Compute z_{i+1}
+    let z_input = conditionally_select_vec(
+      cs.namespace(|| "select input to F"),
+      &z_0,
+      &z_i,
+      &Boolean::from(is_base_case),
+    )?;
+
+    let z_next = self
+      .step_circuit
+      .synthesize(&mut cs.namespace(|| "F"), &z_input)?;
+
+    if z_next.len() != arity {
+      return Err(SynthesisError::IncompatibleLengthVector(
+        "z_next".to_string(),
+      ));
+    }
+
+    // Compute the new hash H(pp_digest, i+1, z0, z_{i+1}, Unew, r_next)
+    let hash = self.synthesize_hash(
+      cs.namespace(|| "synthesize output hash"),
+      &pp_digest,
+      &i_new,
+      &z_0,
+      &z_next,
+      &Unew,
+      &r_next,
+    )?;
+
+    // Outputs the computed hash
+    hash.inputize(cs.namespace(|| "output new hash of this circuit"))?;
+
+    Ok(z_next)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::{
+      r1cs::{NovaShape, NovaWitness},
+      shape_cs::ShapeCS,
+      solver::SatisfyingAssignment,
+    },
+    provider::Bn256EngineKZG,
+    traits::{circuit::TrivialCircuit, snark::default_ck_hint},
+  };
+  use expect_test::{expect, Expect};
+
+  fn test_recursive_circuit_with<E: Engine>(num_constraints: &Expect)
+  where
+    E::GE: DlogGroup,
+  {
+    let ro_consts = RO2ConstantsCircuit::<E>::default();
+    let tc = TrivialCircuit::default();
+
+    let circuit: SingleCurveAugmentedCircuit<'_, E, TrivialCircuit<E::Scalar>> =
+      SingleCurveAugmentedCircuit::new(None, &tc, ro_consts.clone());
+    let mut cs: ShapeCS<E> = ShapeCS::new();
+    let _ = circuit.synthesize(&mut cs);
+    let (shape, ck) = cs.r1cs_shape(&*default_ck_hint());
+    num_constraints.assert_eq(cs.num_constraints().to_string().as_str());
+
+    // Execute the base case
+    let zero = E::Scalar::ZERO;
+    let mut cs = SatisfyingAssignment::<E>::new();
+    let inputs = SingleCurveAugmentedCircuitInputs::new(
+      zero, // pass zero for testing
+      zero,
+      vec![zero],
+      None,
+      None,
+      None,
+      zero,
+      None,
+      None,
+    );
+    let circuit: SingleCurveAugmentedCircuit<'_, E, TrivialCircuit<E::Scalar>> =
+      SingleCurveAugmentedCircuit::new(Some(inputs), &tc, ro_consts);
+    let _ = circuit.synthesize(&mut cs);
+    let (inst, witness) = cs.r1cs_instance_and_witness(&shape, &ck).unwrap();
+    // Make sure that this is satisfiable
+    assert!(shape.is_sat(&ck, &inst, &witness).is_ok());
+  }
+
+  #[test]
+  fn test_single_curve_recursive_circuit() {
+    test_recursive_circuit_with::<Bn256EngineKZG>(&expect!["946995"]);
+  }
+}
@@ -0,0 +1,470 @@
+//! This module implements a mode of Nova's IVC scheme that uses a single curve instead of a cycle.
+//!
+//! The augmented circuit is over the scalar field of the curve and folds its own previous
+//! invocation, so the folding verifier emulates the curve arithmetic with non-native field
+//! arithmetic. This makes each step considerably more expensive to prove than with a cycle of
+//! curves, but a deployment only has to support one curve, e.g., BN254 for on-chain verification.
+//! Only curves of the form `y^2 = x^3 + b` are supported.
+use crate::{
+  constants::{NUM_CHALLENGE_BITS, NUM_HASH_BITS},
+  digest::{DigestComputer, SimpleDigestible},
+  errors::NovaError,
+  frontend::{
+    r1cs::{NovaShape, NovaWitness},
+    shape_cs::ShapeCS,
+    solver::SatisfyingAssignment,
+    ConstraintSystem, SynthesisError,
+  },
+  provider::traits::DlogGroup,
+  r1cs::{
+    CommitmentKeyHint, R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance,
+    RelaxedR1CSWitness,
+  },
+  traits::{
+    circuit::StepCircuit, AbsorbInRO2Trait, Engine, Group, RO2Constants, RO2ConstantsCircuit,
+    ROTrait,
+  },
+  Commitment, CommitmentKey,
+};
+use core::marker::PhantomData;
+use ff::Field;
+use once_cell::sync::OnceCell;
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+
+mod circuit;
+
+use circuit::{SingleCurveAugmentedCircuit, SingleCurveAugmentedCircuitInputs};
+
+/// A type that holds public parameters of the single-curve mode of Nova
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PublicParamsSingleCurve<E, C>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+  C: StepCircuit<E::Scalar>,
+{
+  F_arity: usize,
+
+  ro_consts: RO2Constants<E>,
+  ro_consts_circuit: RO2ConstantsCircuit<E>,
+  ck: CommitmentKey<E>,
+  r1cs_shape: R1CSShape<E>,
+
+  #[serde(skip, default = "OnceCell::new")]
+  digest: OnceCell<E::Scalar>,
+  _p: PhantomData<C>,
+}
+
+impl<E, C> SimpleDigestible for PublicParamsSingleCurve<E, C>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+  C: StepCircuit<E::Scalar>,
+{
+}
+
+impl<E, C> PublicParamsSingleCurve<E, C>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+  C: StepCircuit<E::Scalar>,
+{
+  /// Creates a new `PublicParamsSingleCurve` for a circuit `C`.
+  ///
+  /// Unlike `PublicParams::setup`, there is no secondary circuit, so a single commitment key hint
+  /// is needed. See `PublicParams::setup` for the use of `ck_hint`.
+  pub fn setup(c: &C, ck_hint: &CommitmentKeyHint<E>) -> Result<Self, NovaError> {
+    let (a, _, _, _) = E::GE::group_params();
+    if a != E::Base::ZERO {
+      return Err(NovaError::SetupError {
+        reason: "The single-curve mode requires a curve of the form y^2 = x^3 + b".to_string(),
+      });
+    }
+
+    let F_arity = c.arity();
+
+    let ro_consts: RO2Constants<E> = RO2Constants::<E>::default();
+    let ro_consts_circuit: RO2ConstantsCircuit<E> = RO2ConstantsCircuit::<E>::default();
+
+    let circuit: SingleCurveAugmentedCircuit<'_, E, C> =
+      SingleCurveAugmentedCircuit::new(None, c, ro_consts_circuit.clone());
+    let mut cs: ShapeCS<E> = ShapeCS::new();
+    let _ = circuit.synthesize(&mut cs);
+    let (r1cs_shape, ck) = cs.r1cs_shape(ck_hint);
+
+    if r1cs_shape.num_io != 1 {
+      return Err(NovaError::InvalidStepCircuitIO);
+    }
+
+    let pp = PublicParamsSingleCurve {
+      F_arity,
+
+      ro_consts,
+      ro_consts_circuit,
+      ck,
+      r1cs_shape,
+
+      digest: OnceCell::new(),
+      _p: Default::default(),
+    };
+
+    // call pp.digest() so the digest is computed here rather than in RecursiveSNARK methods
+    let _ = pp.digest();
+
+    Ok(pp)
+  }
+
+  /// Retrieve the digest of the public parameters.
+  pub fn digest(&self) -> E::Scalar {
+    self
+      .digest
+      .get_or_try_init(|| DigestComputer::new(self).digest())
+      .cloned()
+      .expect("Failure in retrieving digest")
+  }
+
+  /// Returns the number of constraints in the augmented circuit
+  pub const fn num_constraints(&self) -> usize {
+    self.r1cs_shape.num_cons
+  }
+
+  /// Returns the number of variables in the augmented circuit
+  pub const fn num_variables(&self) -> usize {
+    self.r1cs_shape.num_vars
+  }
+}
+
+/// A SNARK that proves the correct execution of an incremental computation over a single curve
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RecursiveSNARKSingleCurve<E, C>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+  C: StepCircuit<E::Scalar>,
+{
+  z0: Vec<E::Scalar>,
+
+  r_W: RelaxedR1CSWitness<E>,
+  r_U: RelaxedR1CSInstance<E>,
+  ri: E::Scalar,
+
+  l_w: R1CSWitness<E>,
+  l_u: R1CSInstance<E>,
+
+  i: usize,
+
+  zi: Vec<E::Scalar>,
+
+  _p: PhantomData<C>,
+}
+
+impl<E, C> RecursiveSNARKSingleCurve<E, C>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+  C: StepCircuit<E::Scalar>,
+{
+  /// Create new instance of recursive SNARK
+  pub fn new(
+    pp: &PublicParamsSingleCurve<E, C>,
+    c: &C,
+    z0: &[E::Scalar],
+  ) -> Result<Self, NovaError> {
+    if z0.len() != pp.F_arity {
+      return Err(NovaError::InvalidInitialInputLength);
+    }
+
+    let ri = E::Scalar::random(&mut OsRng);
+
+    // base case
+    let mut cs = SatisfyingAssignment::<E>::new();
+    let inputs = SingleCurveAugmentedCircuitInputs::new(
+      pp.digest(),
+      E::Scalar::ZERO,
+      z0.to_vec(),
+      None,
+      None,
+      None,
+      ri, // "r next"
+      None,
+      None,
+    );
+
+    let circuit: SingleCurveAugmentedCircuit<'_, E, C> =
+      SingleCurveAugmentedCircuit::new(Some(inputs), c, pp.ro_consts_circuit.clone());
+    let zi = circuit.synthesize(&mut cs)?;
+    let (l_u, l_w) = cs.r1cs_instance_and_witness(&pp.r1cs_shape, &pp.ck)?;
+
+    let zi = zi
+      .iter()
+      .map(|v| v.get_value().ok_or(SynthesisError::AssignmentMissing))
+      .collect::<Result<Vec<<E as Engine>::Scalar>, _>>()?;
+
+    Ok(Self {
+      z0: z0.to_vec(),
+      r_W: RelaxedR1CSWitness::default(&pp.r1cs_shape),
+      r_U: RelaxedR1CSInstance::default(&pp.ck, &pp.r1cs_shape),
+      ri,
+      l_w,
+      l_u,
+      i: 0,
+      zi,
+      _p: Default::default(),
+    })
+  }
+
+  /// Updates the provided `RecursiveSNARKSingleCurve` by executing a step of the incremental computation
+  pub fn prove_step(&mut self, pp: &PublicParamsSingleCurve<E, C>, c: &C) -> Result<(), NovaError> {
+    // first step was already done in the constructor
+    if self.i == 0 {
+      self.i = 1;
+      return Ok(());
+    }
+
+    // fold the last instance with the running instance
+    let (comm_T, (r_U, r_W)) = fold(pp, &self.r_U, &self.r_W, &self.l_u, &self.l_w)?;
+
+    let r_next = E::Scalar::random(&mut OsRng);
+
+    let mut cs = SatisfyingAssignment::<E>::new();
+    let inputs = SingleCurveAugmentedCircuitInputs::new(
+      pp.digest(),
+      E::Scalar::from(self.i as u64),
+      self.z0.to_vec(),
+      Some(self.zi.clone()),
+      Some(self.r_U.clone()),
+      Some(self.ri),
+      r_next,
+      Some(self.l_u.clone()),
+      Some(comm_T),
+    );
+
+    let circuit: SingleCurveAugmentedCircuit<'_, E, C> =
+      SingleCurveAugmentedCircuit::new(Some(inputs), c, pp.ro_consts_circuit.clone());
+    let zi = circuit.synthesize(&mut cs)?;
+
+    let (l_u, l_w) = cs.r1cs_instance_and_witness(&pp.r1cs_shape, &pp.ck)?;
+
+    // update the running instances and witnesses
+    self.zi = zi
+      .iter()
+      .map(|v| v.get_value().ok_or(SynthesisError::AssignmentMissing))
+      .collect::<Result<Vec<<E as Engine>::Scalar>, _>>()?;
+
+    self.r_U = r_U;
+    self.r_W = r_W;
+
+    self.i += 1;
+
+    self.ri = r_next;
+
+    self.l_u = l_u;
+    self.l_w = l_w;
+
+    Ok(())
+  }
+
+  /// Verify the correctness of the `RecursiveSNARKSingleCurve`
+  pub fn verify(
+    &self,
+    pp: &PublicParamsSingleCurve<E, C>,
+    num_steps: usize,
+    z0: &[E::Scalar],
+  ) -> Result<Vec<E::Scalar>, NovaError> {
+    // number of steps cannot be zero
+    let is_num_steps_zero = num_steps == 0;
+
+    // check if the provided proof has executed num_steps
+    let is_num_steps_not_match = self.i != num_steps;
+
+    // check if the initial inputs match
+    let is_inputs_not_match = self.z0 != z0;
+
+    // check if the (relaxed) R1CS instances have a single public output
+    let is_instance_has_one_output = self.l_u.X.len() != 1 || self.r_U.X.len() != 1;
+
+    if is_num_steps_zero
+      || is_num_steps_not_match
+      || is_inputs_not_match
+      || is_instance_has_one_output
+    {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid number of steps or inputs".to_string(),
+      });
+    }
+
+    // check if the output hash in the R1CS instance points to the right running instance
+    let hash = {
+      let mut hasher = E::RO2::new(pp.ro_consts.clone());
+      hasher.absorb(pp.digest());
+      hasher.absorb(E::Scalar::from(num_steps as u64));
+      for e in z0 {
+        hasher.absorb(*e);
+      }
+      for e in &self.zi {
+        hasher.absorb(*e);
+      }
+      self.r_U.absorb_in_ro2(&mut hasher);
+      hasher.absorb(self.ri);
+
+      hasher.squeeze(NUM_HASH_BITS)
+    };
+
+    if hash != self.l_u.X[0] {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid output hash in R1CS instance".to_string(),
+      });
+    }
+
+    // check the satisfiability of the provided instances
+    let (res_r, res_l) = rayon::join(
+      || pp.r1cs_shape.is_sat_relaxed(&pp.ck, &self.r_U, &self.r_W),
+      || pp.r1cs_shape.is_sat(&pp.ck, &self.l_u, &self.l_w),
+    );
+
+    // check the returned res objects
+    res_r?;
+    res_l?;
+
+    Ok(self.zi.clone())
+  }
+
+  /// Get the outputs after the last step of computation.
+  pub fn outputs(&self) -> &[E::Scalar] {
+    &self.zi
+  }
+
+  /// The number of steps which have been executed thus far.
+  pub fn num_steps(&self) -> usize {
+    self.i
+  }
+}
+
+/// Folds the instance-witness pair `(u, w)` into the running pair `(U, W)` with the challenge that
+/// the augmented circuit derives, and returns the commitment to the cross term with the result
+fn fold<E, C>(
+  pp: &PublicParamsSingleCurve<E, C>,
+  U: &RelaxedR1CSInstance<E>,
+  W: &RelaxedR1CSWitness<E>,
+  u: &R1CSInstance<E>,
+  w: &R1CSWitness<E>,
+) -> Result<
+  (
+    Commitment<E>,
+    (RelaxedR1CSInstance<E>, RelaxedR1CSWitness<E>),
+  ),
+  NovaError,
+>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+  C: StepCircuit<E::Scalar>,
+{
+  // compute a commitment to the cross-term
+  let r_T = E::Scalar::random(&mut OsRng);
+  let (T, comm_T) = pp.r1cs_shape.commit_T(&pp.ck, U, W, u, w, &r_T)?;
+
+  // U does not need to be absorbed since u.X[0] = Hash(params, U, i, z0, zi)
+  let mut ro = E::RO2::new(pp.ro_consts.clone());
+  ro.absorb(pp.digest());
+  u.absorb_in_ro2(&mut ro);
+  comm_T.absorb_in_ro2(&mut ro);
+  let r = ro.squeeze(NUM_CHALLENGE_BITS);
+
+  let U_fold = U.fold(u, &comm_T, &r);
+  let W_fold = W.fold(w, &T, &r_T, &r)?;
+
+  Ok((comm_T, (U_fold, W_fold)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::num::AllocatedNum, provider::Bn256EngineKZG, traits::snark::default_ck_hint,
+  };
+  use ff::PrimeField;
+
+  #[derive(Clone, Debug, Default)]
+  struct CubicCircuit<F: PrimeField> {
+    _p: PhantomData<F>,
+  }
+
+  impl<F: PrimeField> StepCircuit<F> for CubicCircuit<F> {
+    fn arity(&self) -> usize {
+      1
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      // Consider a cubic equation: `x^3 + x + 5 = y`, where `x` and `y` are respectively the input and output.
+      let x = &z[0];
+      let x_sq = x.square(cs.namespace(|| "x_sq"))?;
+      let x_cu = x_sq.mul(cs.namespace(|| "x_cu"), x)?;
+      let y = AllocatedNum::alloc(cs.namespace(|| "y"), || {
+        Ok(x_cu.get_value().unwrap() + x.get_value().unwrap() + F::from(5u64))
+      })?;
+
+      cs.enforce(
+        || "y = x^3 + x + 5",
+        |lc| {
+          lc + x_cu.get_variable()
+            + x.get_variable()
+            + CS::one()
+            + CS::one()
+            + CS::one()
+            + CS::one()
+            + CS::one()
+        },
+        |lc| lc + CS::one(),
+        |lc| lc + y.get_variable(),
+      );
+
+      Ok(vec![y])
+    }
+  }
+
+  fn test_ivc_single_curve_with<E: Engine>()
+  where
+    E::GE: DlogGroup,
+  {
+    let circuit = CubicCircuit::<E::Scalar>::default();
+    let pp = PublicParamsSingleCurve::<E, _>::setup(&circuit, &*default_ck_hint()).unwrap();
+
+    let num_steps = 3;
+    let z0 = vec![E::Scalar::ONE];
+    let mut recursive_snark = RecursiveSNARKSingleCurve::new(&pp, &circuit, &z0).unwrap();
+    for i in 0..num_steps {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+      assert!(recursive_snark.verify(&pp, i + 1, &z0).is_ok());
+    }
+
+    let mut zi = z0.clone();
+    for _ in 0..num_steps {
+      zi = vec![zi[0] * zi[0] * zi[0] + zi[0] + E::Scalar::from(5u64)];
+    }
+    assert_eq!(recursive_snark.verify(&pp, num_steps, &z0).unwrap(), zi);
+
+    // a proof does not verify for another number of steps or other initial inputs
+    assert!(recursive_snark.verify(&pp, num_steps + 1, &z0).is_err());
+    assert!(recursive_snark
+      .verify(&pp, num_steps, &[E::Scalar::ZERO])
+      .is_err());
+
+    // nor with a tampered running instance
+    let mut tampered = recursive_snark.clone();
+    tampered.r_U.u += E::Scalar::ONE;
+    assert!(tampered.verify(&pp, num_steps, &z0).is_err());
+  }
+
+  #[test]
+  fn test_ivc_single_curve() {
+    test_ivc_single_curve_with::<Bn256EngineKZG>();
+  }
+}
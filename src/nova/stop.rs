@@ -0,0 +1,160 @@
+//! This module lets applications whose protocols require an exact execution make verifiers enforce
+//! where the computation stops, so that proofs of truncated computations are rejected.
+//!
+//! A `CompressedSNARK` is already bound to the number of steps it proves, since the hashes in its
+//! instances absorb it, but `CompressedSNARK::verify` checks it against whatever number of steps
+//! its caller passes. A verifier key with a `StopCondition` (see `VerifierKey::with_stop_condition`)
+//! additionally checks that number, or the outputs of the last step, against what the application
+//! committed to, so that callers cannot accept a proof of a prefix of the computation by passing the
+//! number of steps that the proof claims.
+use super::{LightVerifier, VerifierKey};
+use crate::{
+  errors::NovaError,
+  traits::{circuit::StepCircuit, snark::RelaxedR1CSSNARKTrait, Engine},
+};
+use serde::{Deserialize, Serialize};
+
+/// A condition on where a computation stops, which verifiers check in addition to the proof
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopCondition<F> {
+  /// The computation runs exactly this number of steps
+  Steps(usize),
+  /// The output at `index` after the last step equals `value`, e.g., a flag that the step circuit
+  /// sets once the computation terminates
+  Output {
+    /// The index of the output
+    index: usize,
+    /// The value of the output
+    value: F,
+  },
+}
+
+impl<F: PartialEq> StopCondition<F> {
+  /// Checks that a computation of `num_steps` steps with outputs `zn` satisfies the condition
+  pub fn check(&self, num_steps: usize, zn: &[F]) -> Result<(), NovaError> {
+    let reason = match self {
+      Self::Steps(n) if *n != num_steps => {
+        format!("The computation ran {num_steps} steps rather than {n}")
+      }
+      Self::Output { index, value } if zn.get(*index) != Some(value) => {
+        format!("The output at index {index} does not meet the stop condition")
+      }
+      _ => return Ok(()),
+    };
+    Err(NovaError::ProofVerifyError { reason })
+  }
+}
+
+impl<E1, E2, S1, S2> LightVerifier<E1, E2, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Returns the verifier with `condition` added to the conditions that every proof it verifies
+  /// must meet
+  pub fn with_stop_condition(mut self, condition: StopCondition<E1::Scalar>) -> Self {
+    self.stop_conditions.push(condition);
+    self
+  }
+
+  /// Returns the conditions that every proof the verifier verifies must meet
+  pub fn stop_conditions(&self) -> &[StopCondition<E1::Scalar>] {
+    &self.stop_conditions
+  }
+
+  /// Checks that a computation of `num_steps` steps with outputs `zn` meets the stop conditions
+  pub(super) fn check_stop_conditions(
+    &self,
+    num_steps: usize,
+    zn: &[E1::Scalar],
+  ) -> Result<(), NovaError> {
+    self
+      .stop_conditions
+      .iter()
+      .try_for_each(|c| c.check(num_steps, zn))
+  }
+}
+
+impl<E1, E2, C, S1, S2> VerifierKey<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Returns the verifier key with `condition` added to the conditions that
+  /// `CompressedSNARK::verify` enforces with it
+  pub fn with_stop_condition(mut self, condition: StopCondition<E1::Scalar>) -> Self {
+    self.light = self.light.with_stop_condition(condition);
+    self
+  }
+
+  /// Returns the conditions that `CompressedSNARK::verify` enforces with the verifier key
+  pub fn stop_conditions(&self) -> &[StopCondition<E1::Scalar>] {
+    self.light.stop_conditions()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    nova::{CompressedSNARK, PublicParams, RecursiveSNARK},
+    provider::{ipa_pc, PallasEngine, VestaEngine},
+    spartan::snark::RelaxedR1CSSNARK,
+    traits::{circuit::NonTrivialCircuit, snark::default_ck_hint},
+  };
+  use ff::Field;
+
+  type E1 = PallasEngine;
+  type E2 = VestaEngine;
+  type F = <E1 as Engine>::Scalar;
+  type C = NonTrivialCircuit<F>;
+  type S<E> = RelaxedR1CSSNARK<E, ipa_pc::EvaluationEngine<E>>;
+
+  #[test]
+  fn test_stop_condition() {
+    let circuit = C::new(2);
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+    let (pk, vk) = CompressedSNARK::<_, _, _, S<E1>, S<E2>>::setup(&pp).unwrap();
+
+    let z0 = [F::from(2)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    let mut snarks = vec![];
+    for _ in 0..3 {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+      snarks.push(CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap());
+    }
+    let zn = recursive_snark.outputs()[0];
+
+    // without a condition, a proof of a prefix of the computation verifies
+    assert!(snarks[1].verify(&vk, 2, &z0).is_ok());
+
+    // with one, only the proof of the whole computation does
+    let (_, exact) = CompressedSNARK::<_, _, _, S<E1>, S<E2>>::setup(&pp).unwrap();
+    let exact = exact.with_stop_condition(StopCondition::Steps(3));
+    assert_eq!(exact.stop_conditions(), &[StopCondition::Steps(3)]);
+    assert!(snarks[1].verify(&exact, 2, &z0).is_err());
+    assert!(snarks[2].verify(&exact, 3, &z0).is_ok());
+    let report = snarks[1].verify_detailed(&exact, 2, &z0);
+    assert!(report.into_result().is_err());
+
+    let terminal = vk.with_stop_condition(StopCondition::Output {
+      index: 0,
+      value: zn,
+    });
+    assert!(snarks[2].verify(&terminal, 3, &z0).is_ok());
+    assert!(snarks[0].verify(&terminal, 1, &z0).is_err());
+
+    assert!(StopCondition::Output {
+      index: 1,
+      value: F::ONE
+    }
+    .check(1, &[F::ONE])
+    .is_err());
+  }
+}
@@ -0,0 +1,15 @@
+//! The types and traits that most users of Nova need, which `use nova_snark::prelude::*;` brings
+//! into scope.
+//!
+//! These cover writing a step circuit and proving it with the high-level `Ivc` interface, as well as
+//! naming the main types of the low-level API.
+pub use crate::{
+  errors::NovaError,
+  frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+  nova::{
+    Bn256Cycle, CompressedSNARK, Cycle, CycleScalar, Ivc, IvcProof, PastaCycle, PublicParams,
+    RecursiveSNARK,
+  },
+  traits::{circuit::StepCircuit, Engine},
+};
+pub use ff::{Field, PrimeField};
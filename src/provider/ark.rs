@@ -0,0 +1,319 @@
+//! This module converts the BN256 and Grumpkin types used here, from `halo2curves`, to and from
+//! their `arkworks` counterparts in `ark_bn254` and `ark_grumpkin`, so that tooling built on
+//! arkworks can produce witnesses and consume proofs without encoding them by hand.
+//!
+//! `ToArk` converts scalars, affine points, and commitments to arkworks, and `FromArk` converts
+//! them back, checking that points are on the curve and in the prime-order subgroup. Both extend to
+//! slices, vectors, and arrays, which covers the parts of proofs that their accessors expose, e.g.,
+//! `hyperkzg::EvaluationArgument::com`. Both libraries implement the same curves and fields, so
+//! conversions preserve the group and field operations.
+use crate::{
+  errors::NovaError,
+  provider::{
+    bn256_grumpkin::{bn256, grumpkin},
+    hyperkzg, pedersen,
+    traits::DlogGroup,
+    Bn256EngineIPA, Bn256EngineKZG, GrumpkinEngine,
+  },
+};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, PrimeField as ArkPrimeField};
+use ff::PrimeField;
+use halo2curves::{
+  bn256::{Fq2, G2Affine},
+  group::prime::PrimeCurveAffine,
+  Coordinates, CurveAffine,
+};
+
+/// Converts a value into its arkworks counterpart
+pub trait ToArk {
+  /// The arkworks counterpart of the type
+  type Ark;
+
+  /// Returns the arkworks counterpart of the value
+  fn to_ark(&self) -> Self::Ark;
+}
+
+/// Converts an arkworks value into its counterpart here
+pub trait FromArk: Sized {
+  /// The arkworks counterpart of the type
+  type Ark;
+
+  /// Returns the counterpart of `ark`, or `NovaError::InvalidEncoding` if it has none, e.g., for
+  /// points that are not on the curve or not in the prime-order subgroup
+  fn from_ark(ark: &Self::Ark) -> Result<Self, NovaError>;
+}
+
+macro_rules! impl_field_compat {
+  ($field:ty, $ark:ty) => {
+    impl ToArk for $field {
+      type Ark = $ark;
+
+      fn to_ark(&self) -> $ark {
+        <$ark>::from_le_bytes_mod_order(self.to_repr().as_ref())
+      }
+    }
+
+    impl FromArk for $field {
+      type Ark = $ark;
+
+      fn from_ark(ark: &$ark) -> Result<Self, NovaError> {
+        let mut repr = <$field as PrimeField>::Repr::default();
+        repr
+          .as_mut()
+          .copy_from_slice(&ark.into_bigint().to_bytes_le());
+        Option::from(<$field>::from_repr(repr)).ok_or_else(|| NovaError::InvalidEncoding {
+          reason: "the field element is not canonical".to_string(),
+        })
+      }
+    }
+  };
+}
+
+impl_field_compat!(bn256::Scalar, ark_bn254::Fr);
+impl_field_compat!(bn256::Base, ark_bn254::Fq);
+
+impl ToArk for Fq2 {
+  type Ark = ark_bn254::Fq2;
+
+  fn to_ark(&self) -> Self::Ark {
+    ark_bn254::Fq2::new(self.c0().to_ark(), self.c1().to_ark())
+  }
+}
+
+impl FromArk for Fq2 {
+  type Ark = ark_bn254::Fq2;
+
+  fn from_ark(ark: &Self::Ark) -> Result<Self, NovaError> {
+    Ok(Fq2::new(
+      bn256::Base::from_ark(&ark.c0)?,
+      bn256::Base::from_ark(&ark.c1)?,
+    ))
+  }
+}
+
+macro_rules! impl_point_compat {
+  ($point:ty, $config:ty) => {
+    impl ToArk for $point {
+      type Ark = ark_ec::short_weierstrass::Affine<$config>;
+
+      fn to_ark(&self) -> Self::Ark {
+        match Option::<Coordinates<$point>>::from(self.coordinates()) {
+          Some(c) if !bool::from(self.is_identity()) => {
+            Self::Ark::new_unchecked(c.x().to_ark(), c.y().to_ark())
+          }
+          _ => Self::Ark::zero(),
+        }
+      }
+    }
+
+    impl FromArk for $point {
+      type Ark = ark_ec::short_weierstrass::Affine<$config>;
+
+      fn from_ark(ark: &Self::Ark) -> Result<Self, NovaError> {
+        if ark.infinity {
+          return Ok(<$point>::identity());
+        }
+        if !ark.is_on_curve() || !ark.is_in_correct_subgroup_assuming_on_curve() {
+          return Err(NovaError::InvalidEncoding {
+            reason: "the point is not in the prime-order subgroup of the curve".to_string(),
+          });
+        }
+        let (x, y) = (FromArk::from_ark(&ark.x)?, FromArk::from_ark(&ark.y)?);
+        Option::from(<$point>::from_xy(x, y)).ok_or_else(|| NovaError::InvalidEncoding {
+          reason: "the point is not on the curve".to_string(),
+        })
+      }
+    }
+  };
+}
+
+impl_point_compat!(bn256::Affine, ark_bn254::g1::Config);
+impl_point_compat!(G2Affine, ark_bn254::g2::Config);
+impl_point_compat!(grumpkin::Affine, ark_grumpkin::GrumpkinConfig);
+
+macro_rules! impl_commitment_compat {
+  ($commitment:ty, $affine:ty, $new:expr, $point:expr) => {
+    impl ToArk for $commitment {
+      type Ark = <$affine as ToArk>::Ark;
+
+      fn to_ark(&self) -> Self::Ark {
+        let point: fn(&$commitment) -> $affine = $point;
+        point(self).to_ark()
+      }
+    }
+
+    impl FromArk for $commitment {
+      type Ark = <$affine as ToArk>::Ark;
+
+      fn from_ark(ark: &Self::Ark) -> Result<Self, NovaError> {
+        let new: fn($affine) -> $commitment = $new;
+        Ok(new(<$affine>::from_ark(ark)?))
+      }
+    }
+  };
+}
+
+impl_commitment_compat!(
+  hyperkzg::Commitment<Bn256EngineKZG>,
+  bn256::Affine,
+  |p| hyperkzg::Commitment::new(p.into()),
+  |c| c.into_inner().affine()
+);
+impl_commitment_compat!(
+  pedersen::Commitment<Bn256EngineIPA>,
+  bn256::Affine,
+  |p| pedersen::Commitment { comm: p.into() },
+  |c| c.comm.affine()
+);
+impl_commitment_compat!(
+  pedersen::Commitment<GrumpkinEngine>,
+  grumpkin::Affine,
+  |p| pedersen::Commitment { comm: p.into() },
+  |c| c.comm.affine()
+);
+
+impl<T: ToArk> ToArk for [T] {
+  type Ark = Vec<T::Ark>;
+
+  fn to_ark(&self) -> Self::Ark {
+    self.iter().map(ToArk::to_ark).collect()
+  }
+}
+
+impl<T: ToArk> ToArk for Vec<T> {
+  type Ark = Vec<T::Ark>;
+
+  fn to_ark(&self) -> Self::Ark {
+    self.as_slice().to_ark()
+  }
+}
+
+impl<T: ToArk, const N: usize> ToArk for [T; N] {
+  type Ark = [T::Ark; N];
+
+  fn to_ark(&self) -> Self::Ark {
+    self.each_ref().map(ToArk::to_ark)
+  }
+}
+
+impl<T: FromArk> FromArk for Vec<T> {
+  type Ark = Vec<T::Ark>;
+
+  fn from_ark(ark: &Self::Ark) -> Result<Self, NovaError> {
+    ark.iter().map(T::from_ark).collect()
+  }
+}
+
+impl<T: FromArk, const N: usize> FromArk for [T; N] {
+  type Ark = [T::Ark; N];
+
+  fn from_ark(ark: &Self::Ark) -> Result<Self, NovaError> {
+    let v = ark.iter().map(T::from_ark).collect::<Result<Vec<_>, _>>()?;
+    Ok(v.try_into().unwrap_or_else(|_| unreachable!()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::hyperkzg::{EvaluationArgument, EvaluationEngine},
+    traits::{
+      commitment::CommitmentEngineTrait, evaluation::EvaluationEngineTrait, Engine,
+      TranscriptEngineTrait,
+    },
+  };
+  use ff::Field;
+  use halo2curves::bn256::G2;
+
+  #[test]
+  fn test_fields() {
+    let mut rng = rand::thread_rng();
+    let (a, b) = (
+      bn256::Scalar::random(&mut rng),
+      bn256::Scalar::random(&mut rng),
+    );
+    assert_eq!((a * b + a).to_ark(), a.to_ark() * b.to_ark() + a.to_ark());
+    assert_eq!(bn256::Scalar::from_ark(&a.to_ark()).unwrap(), a);
+
+    let (a, b) = (bn256::Base::random(&mut rng), bn256::Base::random(&mut rng));
+    assert_eq!((a * b - a).to_ark(), a.to_ark() * b.to_ark() - a.to_ark());
+    assert_eq!(bn256::Base::from_ark(&a.to_ark()).unwrap(), a);
+  }
+
+  #[test]
+  fn test_points() {
+    let mut rng = rand::thread_rng();
+    let s = bn256::Scalar::random(&mut rng);
+
+    // the generators agree, and conversions commute with scalar multiplication
+    let g = bn256::Affine::generator();
+    assert_eq!(g.to_ark(), ark_bn254::G1Affine::generator());
+    let p: bn256::Affine = (g * s).into();
+    assert_eq!(
+      p.to_ark(),
+      ark_bn254::G1Affine::from(g.to_ark() * s.to_ark())
+    );
+    assert_eq!(bn256::Affine::from_ark(&p.to_ark()).unwrap(), p);
+    let identity = bn256::Affine::identity();
+    assert_eq!(
+      bn256::Affine::from_ark(&identity.to_ark()).unwrap(),
+      identity
+    );
+
+    let g2 = G2Affine::generator();
+    assert_eq!(g2.to_ark(), ark_bn254::G2Affine::generator());
+    let q: G2Affine = (G2::generator() * s).into();
+    assert_eq!(G2Affine::from_ark(&q.to_ark()).unwrap(), q);
+
+    let h = grumpkin::Affine::generator();
+    let t = grumpkin::Scalar::random(&mut rng);
+    let r: grumpkin::Affine = (h * t).into();
+    assert_eq!(
+      r.to_ark(),
+      ark_grumpkin::Affine::from(h.to_ark() * t.to_ark())
+    );
+    assert_eq!(grumpkin::Affine::from_ark(&r.to_ark()).unwrap(), r);
+
+    // points off the curve are rejected
+    let mut off = p.to_ark();
+    off.y += ark_bn254::Fq::from(1u64);
+    assert!(matches!(
+      bn256::Affine::from_ark(&off),
+      Err(NovaError::InvalidEncoding { .. })
+    ));
+  }
+
+  #[test]
+  fn test_proofs() {
+    type E = Bn256EngineKZG;
+    let mut rng = rand::thread_rng();
+    let poly = (0..8)
+      .map(|_| bn256::Scalar::random(&mut rng))
+      .collect::<Vec<_>>();
+    let point = (0..3)
+      .map(|_| bn256::Scalar::random(&mut rng))
+      .collect::<Vec<_>>();
+    let eval =
+      crate::spartan::polys::multilinear::MultilinearPolynomial::evaluate_with(&poly, &point);
+    let ck = <E as Engine>::CE::setup(b"test", 8);
+    let (pk, vk) = EvaluationEngine::<E>::setup(&ck);
+    let comm = <E as Engine>::CE::commit(&ck, &poly, &bn256::Scalar::ZERO);
+    let mut transcript = <E as Engine>::TE::new(b"test");
+    let arg =
+      EvaluationEngine::<E>::prove(&ck, &pk, &mut transcript, &comm, &poly, &point, &eval).unwrap();
+
+    // a proof converted to arkworks and back still verifies
+    let comm = hyperkzg::Commitment::<E>::from_ark(&comm.to_ark()).unwrap();
+    let arg = EvaluationArgument::<E>::new(
+      Vec::from_ark(&arg.com().to_ark()).unwrap(),
+      <[bn256::Affine; 3]>::from_ark(&arg.w().to_ark().try_into().unwrap()).unwrap(),
+      Vec::from_ark(&arg.v().iter().map(|v| v.to_ark()).collect()).unwrap(),
+    );
+    let mut transcript = <E as Engine>::TE::new(b"test");
+    assert!(
+      EvaluationEngine::<E>::verify(&vk, &mut transcript, &comm, &point, &eval, &arg).is_ok()
+    );
+  }
+}
@@ -1,18 +1,175 @@
 //! This module implements variable time multi-scalar multiplication using Blitzar's GPU acceleration
-use blitzar;
+use crate::errors::NovaError;
+use ark_bn254::G1Affine as ArkAffine;
+use blitzar::{
+  self,
+  compute::{
+    convert_to_ark_bn254_g1_affine, convert_to_halo2_bn256_g1_affine, init_backend_with_config,
+    BackendConfig,
+  },
+};
 use halo2curves::bn256::{Fr as Scalar, G1Affine as Affine, G1 as Point};
+use once_cell::sync::OnceCell;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::{
+  sync::{
+    mpsc::{channel, sync_channel, Receiver, Sender, SyncSender},
+    Arc,
+  },
+  thread::{self, JoinHandle},
+  time::Instant,
+};
+
+/// The device that Blitzar runs its kernels on, which is selected when Blitzar is built: the GPU by
+/// default, or the CPU with the `blitzar-cpu` feature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Device {
+  /// A CUDA device
+  Gpu,
+  /// Blitzar's CPU implementation, e.g., for hosts without a GPU
+  Cpu,
+}
+
+/// The tunables of the Blitzar backend, which are set once with `init_backend`.
+///
+/// The default matches the behavior of Blitzar without any configuration.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+  /// The number of generators for which Blitzar precomputes multiples when it is initialized,
+  /// which speeds up MSMs with Blitzar's own generators at the cost of device memory
+  pub num_precomputed_generators: u64,
+  /// The largest number of MSMs of a batch that are submitted to Blitzar at once, which bounds
+  /// the memory used on the device. `None` submits every batch at once.
+  pub batch_chunk_size: Option<usize>,
+  /// MSMs with fewer scalars than this are computed on the CPU instead, as the cost of copying
+  /// them to the device dominates
+  pub min_msm_size: usize,
+}
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// Initializes Blitzar with `config`.
+///
+/// This must be called before the first MSM on the Blitzar backend, which otherwise initializes
+/// Blitzar with `Config::default()`, and fails if Blitzar is already initialized. Every MSM of this
+/// module initializes Blitzar through `config` before calling into it.
+pub fn init_backend(config: Config) -> Result<(), NovaError> {
+  let mut initialized = false;
+  CONFIG.get_or_init(|| {
+    initialized = true;
+    init_blitzar(config)
+  });
+  if initialized {
+    Ok(())
+  } else {
+    Err(NovaError::SetupError {
+      reason: "the blitzar backend is already initialized".to_string(),
+    })
+  }
+}
+
+/// Initializes Blitzar with `config` and returns it
+fn init_blitzar(config: Config) -> Config {
+  init_backend_with_config(BackendConfig {
+    num_precomputed_generators: config.num_precomputed_generators,
+  });
+  config
+}
+
+/// Returns the device that Blitzar was built to run its kernels on
+pub fn device() -> Device {
+  if cfg!(feature = "blitzar-cpu") {
+    Device::Cpu
+  } else {
+    Device::Gpu
+  }
+}
+
+/// Returns the configuration of the Blitzar backend, initializing Blitzar with
+/// `Config::default()` if `init_backend` was not called
+pub fn config() -> &'static Config {
+  CONFIG.get_or_init(|| init_blitzar(Config::default()))
+}
+
+/// Returns true if an MSM with `len` scalars is large enough to be computed by Blitzar
+pub(crate) fn is_worth_offloading(len: usize) -> bool {
+  len >= config().min_msm_size
+}
+
+/// Splits a batch of MSMs into the chunks that are submitted to Blitzar at once
+fn batch_chunks<T>(batch: &[T]) -> impl Iterator<Item = &[T]> {
+  let chunk_size = config().batch_chunk_size.unwrap_or(batch.len()).max(1);
+  batch.chunks(chunk_size)
+}
+
+/// Runs `kernels`, a call into Blitzar that copies `transfer_bytes` bytes to the device and
+/// `result_bytes` bytes back, within a `DEBUG` span named `blitzar` with the target of the timing
+/// events. Besides `op`, the span records the `device`, the sizes of both transfers, and the duration
+/// of the call in `device_us`, which tells a step bound by transfers from one bound by kernels.
+#[cfg(feature = "timing")]
+fn on_device<T>(
+  op: &'static str,
+  transfer_bytes: usize,
+  result_bytes: usize,
+  kernels: impl FnOnce() -> T,
+) -> T {
+  // Blitzar is initialized with the configuration before its first call
+  config();
+  let span = tracing::debug_span!(
+    target: super::timing::TARGET,
+    "blitzar",
+    op,
+    device = ?device(),
+    transfer_bytes,
+    result_bytes,
+    device_us = tracing::field::Empty,
+  );
+  let _guard = span.enter();
+  let start = std::time::Instant::now();
+  let result = kernels();
+  span.record("device_us", start.elapsed().as_micros() as u64);
+  result
+}
+
+/// Runs `kernels`, a call into Blitzar, which is traced with the `timing` feature
+#[cfg(not(feature = "timing"))]
+fn on_device<T>(
+  _op: &'static str,
+  _transfer_bytes: usize,
+  _result_bytes: usize,
+  kernels: impl FnOnce() -> T,
+) -> T {
+  // Blitzar is initialized with the configuration before its first call
+  config();
+  kernels()
+}
+
+/// Returns the number of bytes of the scalars of a batch that are copied to the device
+fn scalar_bytes_len(scalar_bytes: &[Vec<[u8; 32]>]) -> usize {
+  scalar_bytes
+    .iter()
+    .map(|s| std::mem::size_of_val(s.as_slice()))
+    .sum()
+}
 
 /// A trait that provides the ability to perform multi-scalar multiplication in variable time
 pub fn vartime_multiscalar_mul(scalars: &[Scalar], bases: &[Affine]) -> Point {
+  let _timer = super::timing::start("msm", "blitzar", scalars.len());
   let mut blitzar_commitments = vec![Point::default(); 1];
 
   let scalar_bytes: Vec<[u8; 32]> = scalars.par_iter().map(|s| s.to_bytes()).collect();
 
-  blitzar::compute::compute_bn254_g1_uncompressed_commitments_with_halo2_generators(
-    &mut blitzar_commitments,
-    &[(&scalar_bytes).into()],
-    bases,
+  on_device(
+    "msm",
+    std::mem::size_of_val(scalar_bytes.as_slice()) + std::mem::size_of_val(bases),
+    std::mem::size_of_val(blitzar_commitments.as_slice()),
+    || {
+      blitzar::compute::compute_bn254_g1_uncompressed_commitments_with_halo2_generators(
+        &mut blitzar_commitments,
+        &[(&scalar_bytes).into()],
+        bases,
+      )
+    },
   );
 
   blitzar_commitments[0]
@@ -20,23 +177,248 @@ pub fn vartime_multiscalar_mul(scalars: &[Scalar], bases: &[Affine]) -> Point {
 
 /// A trait that provides the ability to perform a batch of multi-scalar multiplication in variable time
 pub fn batch_vartime_multiscalar_mul(scalars: &[Vec<Scalar>], bases: &[Affine]) -> Vec<Point> {
-  let mut blitzar_commitments = vec![Point::default(); scalars.len()];
+  let _timer = super::timing::start("batch_msm", "blitzar", scalars.iter().map(Vec::len).sum());
+  batch_vartime_multiscalar_mul_bytes(&scalars_to_bytes(scalars), bases)
+}
 
-  let scalar_bytes: Vec<Vec<[u8; 32]>> = scalars
+fn scalars_to_bytes(scalars: &[Vec<Scalar>]) -> Vec<Vec<[u8; 32]>> {
+  scalars
     .par_iter()
     .map(|s| s.par_iter().map(|v| v.to_bytes()).collect())
-    .collect();
+    .collect()
+}
+
+fn batch_vartime_multiscalar_mul_bytes(
+  scalar_bytes: &[Vec<[u8; 32]>],
+  bases: &[Affine],
+) -> Vec<Point> {
+  let mut blitzar_commitments = Vec::with_capacity(scalar_bytes.len());
+
+  for chunk in batch_chunks(scalar_bytes) {
+    let mut commitments = vec![Point::default(); chunk.len()];
+
+    let scalars_table: Vec<blitzar::sequence::Sequence<'_>> =
+      chunk.par_iter().map(|s| s.into()).collect();
+
+    on_device(
+      "batch_msm",
+      scalar_bytes_len(chunk) + std::mem::size_of_val(bases),
+      std::mem::size_of_val(commitments.as_slice()),
+      || {
+        blitzar::compute::compute_bn254_g1_uncompressed_commitments_with_halo2_generators(
+          &mut commitments,
+          &scalars_table,
+          bases,
+        )
+      },
+    );
+
+    blitzar_commitments.extend(commitments);
+  }
+
+  blitzar_commitments
+}
+
+/// Bases converted into the representation that Blitzar computes with
+pub type PreparedBases = Vec<ArkAffine>;
+
+/// Converts bases into the representation that Blitzar computes with, which the functions above
+/// otherwise do on every call
+pub fn prepare_bases(bases: &[Affine]) -> PreparedBases {
+  bases
+    .par_iter()
+    .map(convert_to_ark_bn254_g1_affine)
+    .collect()
+}
+
+/// Performs a multi-scalar multiplication in variable time with bases produced by `prepare_bases`
+pub fn vartime_multiscalar_mul_prepared(scalars: &[Scalar], bases: &[ArkAffine]) -> Point {
+  let _timer = super::timing::start("msm", "blitzar", scalars.len());
+  let mut blitzar_commitments = [ArkAffine::default()];
 
-  let scalars_table: Vec<blitzar::sequence::Sequence<'_>> =
-    scalar_bytes.par_iter().map(|s| s.into()).collect();
+  let scalar_bytes: Vec<[u8; 32]> = scalars.par_iter().map(|s| s.to_bytes()).collect();
 
-  blitzar::compute::compute_bn254_g1_uncompressed_commitments_with_halo2_generators(
-    &mut blitzar_commitments,
-    &scalars_table,
-    bases,
+  on_device(
+    "msm",
+    std::mem::size_of_val(scalar_bytes.as_slice()) + std::mem::size_of_val(bases),
+    std::mem::size_of_val(blitzar_commitments.as_slice()),
+    || {
+      blitzar::compute::compute_bn254_g1_uncompressed_commitments_with_generators(
+        &mut blitzar_commitments,
+        &[(&scalar_bytes).into()],
+        bases,
+      )
+    },
   );
 
+  convert_to_halo2_bn256_g1_affine(&blitzar_commitments[0]).into()
+}
+
+/// Performs a multi-scalar multiplication in variable time of `scalars` with the bases at
+/// `indices` among those produced by `prepare_bases`, handing only the nonzero scalars to Blitzar
+pub fn vartime_multiscalar_mul_sparse(
+  indices: &[usize],
+  scalars: &[Scalar],
+  bases: &[ArkAffine],
+) -> Point {
+  let (scalars, bases) = super::traits::sparse_terms(indices, scalars, bases);
+  vartime_multiscalar_mul_prepared(&scalars, &bases)
+}
+
+/// Performs a batch of multi-scalar multiplications in variable time with bases produced by
+/// `prepare_bases`
+pub fn batch_vartime_multiscalar_mul_prepared(
+  scalars: &[Vec<Scalar>],
+  bases: &[ArkAffine],
+) -> Vec<Point> {
+  let _timer = super::timing::start("batch_msm", "blitzar", scalars.iter().map(Vec::len).sum());
+  let scalar_bytes = scalars_to_bytes(scalars);
+  let mut blitzar_commitments = Vec::with_capacity(scalar_bytes.len());
+
+  for chunk in batch_chunks(&scalar_bytes) {
+    let mut commitments = vec![ArkAffine::default(); chunk.len()];
+
+    let scalars_table: Vec<blitzar::sequence::Sequence<'_>> =
+      chunk.par_iter().map(|s| s.into()).collect();
+
+    on_device(
+      "batch_msm",
+      scalar_bytes_len(chunk) + std::mem::size_of_val(bases),
+      std::mem::size_of_val(commitments.as_slice()),
+      || {
+        blitzar::compute::compute_bn254_g1_uncompressed_commitments_with_generators(
+          &mut commitments,
+          &scalars_table,
+          bases,
+        )
+      },
+    );
+
+    blitzar_commitments.extend(commitments);
+  }
+
   blitzar_commitments
+    .par_iter()
+    .map(|c| convert_to_halo2_bn256_g1_affine(c).into())
+    .collect()
+}
+
+// a batch carries the time of its submission, from which the device stage traces how long it queued
+type Job = (Vec<Vec<Scalar>>, Arc<[Affine]>, Sender<Vec<Point>>, Instant);
+type PreparedJob = (
+  Vec<Vec<[u8; 32]>>,
+  Arc<[Affine]>,
+  Sender<Vec<Point>>,
+  Instant,
+);
+
+/// A handle to the result of a batch of MSMs submitted to an `MsmQueue`
+#[derive(Debug)]
+pub struct MsmFuture {
+  result: Receiver<Vec<Point>>,
+}
+
+impl MsmFuture {
+  /// Blocks until the batch has been computed and returns one point per MSM in the batch
+  pub fn wait(self) -> Vec<Point> {
+    self
+      .result
+      .recv()
+      .expect("the MSM queue stopped before computing a submitted batch")
+  }
+}
+
+/// A queue that computes independent batches of MSMs on the GPU.
+///
+/// Every batch goes through two stages that run on their own threads: the host stage converts
+/// the scalars into the layout expected by the device, and the device stage copies them over and
+/// runs the kernels. Each stage buffers at most one batch ahead, so the preparation of a batch
+/// overlaps with the device work of the previous one while bounding host memory. Submitting
+/// returns immediately with an `MsmFuture`, so a caller can submit all of its MSMs up front and
+/// wait for the results only when it needs them.
+///
+/// The copy of a batch to the device and its kernels run within a single call into Blitzar, which
+/// does not expose them separately, so they do not overlap with each other, nor with those of other
+/// batches. The queue is meant for applications that compute many independent MSMs, and is not
+/// used by `RecursiveSNARK::prove_step`: each of its commitments is absorbed into the transcript
+/// before the next one can be computed, so none of them could be submitted ahead.
+#[derive(Debug)]
+pub struct MsmQueue {
+  jobs: Option<SyncSender<Job>>,
+  workers: Vec<JoinHandle<()>>,
+}
+
+impl Default for MsmQueue {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl MsmQueue {
+  /// Starts the worker threads of a new queue
+  pub fn new() -> Self {
+    let (jobs, job_receiver) = sync_channel::<Job>(1);
+    let (prepared, prepared_receiver) = sync_channel::<PreparedJob>(1);
+
+    let host = thread::spawn(move || {
+      for (scalars, bases, result, submitted) in job_receiver {
+        if prepared
+          .send((scalars_to_bytes(&scalars), bases, result, submitted))
+          .is_err()
+        {
+          break;
+        }
+      }
+    });
+
+    let device = thread::spawn(move || {
+      for (scalar_bytes, bases, result, submitted) in prepared_receiver {
+        // the span covers the calls into Blitzar for the batch, and records the time it spent in
+        // the host stage and behind the batches submitted before it
+        #[cfg(feature = "timing")]
+        let _span = tracing::debug_span!(
+          target: super::timing::TARGET,
+          "blitzar_queue",
+          batch_len = scalar_bytes.len(),
+          queued_us = submitted.elapsed().as_micros() as u64,
+        )
+        .entered();
+        #[cfg(not(feature = "timing"))]
+        let _ = submitted;
+
+        // the caller may have dropped its future, in which case the result is discarded
+        let _ = result.send(batch_vartime_multiscalar_mul_bytes(&scalar_bytes, &bases));
+      }
+    });
+
+    Self {
+      jobs: Some(jobs),
+      workers: vec![host, device],
+    }
+  }
+
+  /// Submits a batch of MSMs of `scalars` with `bases`, which must be at least as long as the
+  /// longest vector of scalars. Blocks only if both stages already have a batch buffered.
+  pub fn submit(&self, scalars: Vec<Vec<Scalar>>, bases: Arc<[Affine]>) -> MsmFuture {
+    let (sender, result) = channel();
+    self
+      .jobs
+      .as_ref()
+      .expect("the queue is only closed on drop")
+      .send((scalars, bases, sender, Instant::now()))
+      .expect("the MSM queue workers have stopped");
+    MsmFuture { result }
+  }
+}
+
+impl Drop for MsmQueue {
+  fn drop(&mut self) {
+    // closing the job channel lets the workers finish the batches in flight and exit
+    self.jobs.take();
+    for worker in self.workers.drain(..) {
+      let _ = worker.join();
+    }
+  }
 }
 
 #[cfg(test)]
@@ -212,4 +594,162 @@ mod tests {
 
     assert_eq!(result, expected);
   }
+
+  #[test]
+  fn test_vartime_multiscalar_mul_prepared() {
+    let mut rng = rand::thread_rng();
+    let batch_len = 4;
+    let sample_len = 100;
+
+    let scalars: Vec<Vec<Scalar>> = (0..batch_len)
+      .map(|_| (0..sample_len).map(|_| Scalar::random(&mut rng)).collect())
+      .collect();
+    let bases: Vec<Affine> = (0..sample_len).map(|_| Affine::random(&mut rng)).collect();
+    let prepared = prepare_bases(&bases);
+
+    assert_eq!(
+      vartime_multiscalar_mul_prepared(&scalars[0], &prepared),
+      msm_best(&scalars[0], &bases)
+    );
+    assert_eq!(
+      batch_vartime_multiscalar_mul_prepared(&scalars, &prepared),
+      batch_vartime_multiscalar_mul(&scalars, &bases)
+    );
+  }
+
+  #[test]
+  fn test_init_backend_after_first_use() {
+    // an MSM initializes Blitzar, after which its configuration is fixed
+    let mut rng = rand::thread_rng();
+    let scalars = vec![Scalar::random(&mut rng)];
+    let bases = vec![Affine::random(&mut rng)];
+    assert_eq!(
+      vartime_multiscalar_mul(&scalars, &bases),
+      msm_best(&scalars, &bases)
+    );
+    assert!(init_backend(Config::default()).is_err());
+  }
+
+  #[test]
+  fn test_batch_chunks() {
+    let batch = (0..10).collect::<Vec<usize>>();
+    let chunks = batch_chunks(&batch).collect::<Vec<_>>();
+    match config().batch_chunk_size {
+      Some(size) => assert_eq!(chunks.len(), batch.len().div_ceil(size)),
+      None => assert_eq!(chunks.len(), 1),
+    }
+    assert_eq!(chunks.concat(), batch);
+  }
+
+  #[cfg(feature = "timing")]
+  #[test]
+  fn test_device_spans() {
+    use std::sync::Mutex;
+    use tracing::{
+      field::{Field as SpanField, Visit},
+      span, Event, Metadata, Subscriber,
+    };
+
+    /// A subscriber that records the names and fields of the spans
+    #[derive(Default)]
+    struct Recorder(Arc<Mutex<Vec<(String, Vec<String>)>>>);
+
+    struct Fields(Vec<String>);
+
+    impl Visit for Fields {
+      fn record_debug(&mut self, field: &SpanField, _value: &dyn std::fmt::Debug) {
+        self.0.push(field.name().to_string());
+      }
+    }
+
+    impl Subscriber for Recorder {
+      fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == crate::provider::timing::TARGET
+      }
+
+      fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        let mut fields = Fields(Vec::new());
+        span.record(&mut fields);
+        let mut spans = self.0.lock().unwrap();
+        spans.push((span.metadata().name().to_string(), fields.0));
+        span::Id::from_u64(spans.len() as u64)
+      }
+
+      fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+        let mut fields = Fields(Vec::new());
+        values.record(&mut fields);
+        self.0.lock().unwrap()[id.into_u64() as usize - 1]
+          .1
+          .extend(fields.0);
+      }
+
+      fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+      fn event(&self, _event: &Event<'_>) {}
+
+      fn enter(&self, _span: &span::Id) {}
+
+      fn exit(&self, _span: &span::Id) {}
+    }
+
+    let mut rng = rand::thread_rng();
+    let scalars = (0..10)
+      .map(|_| Scalar::random(&mut rng))
+      .collect::<Vec<_>>();
+    let bases = (0..10)
+      .map(|_| Affine::random(&mut rng))
+      .collect::<Vec<_>>();
+
+    let recorder = Recorder::default();
+    let spans = recorder.0.clone();
+    tracing::subscriber::with_default(recorder, || vartime_multiscalar_mul(&scalars, &bases));
+
+    let spans = spans.lock().unwrap();
+    assert_eq!(spans.len(), 1);
+    let (name, fields) = &spans[0];
+    assert_eq!(name, "blitzar");
+    for field in [
+      "op",
+      "device",
+      "transfer_bytes",
+      "result_bytes",
+      "device_us",
+    ] {
+      assert!(fields.iter().any(|f| f == field), "missing field {field}");
+    }
+  }
+
+  #[test]
+  fn test_msm_queue() {
+    let mut rng = rand::thread_rng();
+    let batch_len = 4;
+    let sample_len = 100;
+
+    let bases: Arc<[Affine]> = (0..sample_len)
+      .map(|_| Affine::random(&mut rng))
+      .collect::<Vec<_>>()
+      .into();
+
+    let batches: Vec<Vec<Vec<Scalar>>> = (0..3)
+      .map(|_| {
+        (0..batch_len)
+          .map(|_| (0..sample_len).map(|_| Scalar::random(&mut rng)).collect())
+          .collect()
+      })
+      .collect();
+
+    let queue = MsmQueue::new();
+    let futures = batches
+      .iter()
+      .map(|scalars| queue.submit(scalars.clone(), bases.clone()))
+      .collect::<Vec<_>>();
+
+    for (scalars, future) in batches.iter().zip(futures) {
+      let expected = scalars
+        .iter()
+        .map(|scalar| msm_best(scalar, &bases))
+        .collect::<Vec<_>>();
+      assert_eq!(future.wait(), expected);
+    }
+  }
 }
@@ -1,5 +1,6 @@
 //! This module implements the Nova traits for `bn256::Point`, `bn256::Scalar`, `grumpkin::Point`, `grumpkin::Scalar`.
 use crate::{
+  encoding::{encode_scalar, TRANSCRIPT_BYTE_ORDER},
   impl_traits,
   provider::{
     msm::{msm, msm_small},
@@ -7,21 +8,18 @@ use crate::{
   },
   traits::{Group, PrimeFieldExt, TranscriptReprTrait},
 };
-use digest::{ExtendableOutput, Update};
 use ff::FromUniformBytes;
 use halo2curves::{
   bn256::{Bn256, G1Affine as Bn256Affine, G2Affine, G2Compressed, Gt, G1 as Bn256Point, G2},
   group::{cofactor::CofactorCurveAffine, Curve, Group as AnotherGroup},
   grumpkin::{G1Affine as GrumpkinAffine, G1 as GrumpkinPoint},
-  pairing::Engine as H2CEngine,
+  pairing::{Engine as H2CEngine, MillerLoopResult, MultiMillerLoop},
   CurveAffine, CurveExt,
 };
 use num_bigint::BigInt;
 use num_integer::Integer;
 use num_traits::{Num, ToPrimitive};
 use rayon::prelude::*;
-use sha3::Shake256;
-use std::io::Read;
 
 /// Re-exports that give access to the standard aliases used in the code base, for bn256
 pub mod bn256 {
@@ -42,21 +40,93 @@ crate::impl_traits_no_dlog_ext!(
 );
 
 impl DlogGroupExt for bn256::Point {
+  #[cfg(feature = "blitzar")]
+  type PreparedBases = super::blitzar::PreparedBases;
   #[cfg(not(feature = "blitzar"))]
+  type PreparedBases = ();
+
+  #[cfg(feature = "blitzar")]
+  fn prepare_bases(bases: &[Self::AffineGroupElement]) -> Self::PreparedBases {
+    super::blitzar::prepare_bases(bases)
+  }
+
+  #[cfg(not(feature = "blitzar"))]
+  fn prepare_bases(_bases: &[Self::AffineGroupElement]) -> Self::PreparedBases {}
+
   fn vartime_multiscalar_mul(scalars: &[Self::Scalar], bases: &[Self::AffineGroupElement]) -> Self {
+    #[cfg(feature = "blitzar")]
+    if super::msm_backend() == super::MsmBackend::Blitzar
+      && super::blitzar::is_worth_offloading(scalars.len())
+    {
+      return super::blitzar::vartime_multiscalar_mul(scalars, bases);
+    }
     msm(scalars, bases)
   }
 
-  fn vartime_multiscalar_mul_small<T: Integer + Into<u64> + Copy + Sync + ToPrimitive>(
-    scalars: &[T],
+  #[cfg(feature = "blitzar")]
+  fn vartime_multiscalar_mul_prepared(
+    scalars: &[Self::Scalar],
     bases: &[Self::AffineGroupElement],
+    prepared: Option<&Self::PreparedBases>,
+    offset: usize,
   ) -> Self {
-    msm_small(scalars, bases)
+    match prepared {
+      Some(prepared)
+        if super::msm_backend() == super::MsmBackend::Blitzar
+          && super::blitzar::is_worth_offloading(scalars.len()) =>
+      {
+        super::blitzar::vartime_multiscalar_mul_prepared(
+          scalars,
+          &prepared[offset..offset + scalars.len()],
+        )
+      }
+      _ => Self::vartime_multiscalar_mul(scalars, &bases[offset..offset + scalars.len()]),
+    }
   }
 
   #[cfg(feature = "blitzar")]
-  fn vartime_multiscalar_mul(scalars: &[Self::Scalar], bases: &[Self::AffineGroupElement]) -> Self {
-    super::blitzar::vartime_multiscalar_mul(scalars, bases)
+  fn vartime_multiscalar_mul_sparse(
+    indices: &[usize],
+    scalars: &[Self::Scalar],
+    bases: &[Self::AffineGroupElement],
+    prepared: Option<&Self::PreparedBases>,
+  ) -> Self {
+    match prepared {
+      Some(prepared)
+        if super::msm_backend() == super::MsmBackend::Blitzar
+          && super::blitzar::is_worth_offloading(scalars.len()) =>
+      {
+        super::blitzar::vartime_multiscalar_mul_sparse(indices, scalars, prepared)
+      }
+      _ => {
+        let (scalars, bases) = super::traits::sparse_terms(indices, scalars, bases);
+        Self::vartime_multiscalar_mul(&scalars, &bases)
+      }
+    }
+  }
+
+  #[cfg(feature = "blitzar")]
+  fn batch_vartime_multiscalar_mul_prepared(
+    scalars: &[Vec<Self::Scalar>],
+    bases: &[Self::AffineGroupElement],
+    prepared: Option<&Self::PreparedBases>,
+  ) -> Vec<Self> {
+    match prepared {
+      Some(prepared)
+        if super::msm_backend() == super::MsmBackend::Blitzar
+          && super::blitzar::is_worth_offloading(max_len(scalars)) =>
+      {
+        super::blitzar::batch_vartime_multiscalar_mul_prepared(scalars, prepared)
+      }
+      _ => Self::batch_vartime_multiscalar_mul(scalars, bases),
+    }
+  }
+
+  fn vartime_multiscalar_mul_small<T: Integer + Into<u64> + Copy + Sync + ToPrimitive>(
+    scalars: &[T],
+    bases: &[Self::AffineGroupElement],
+  ) -> Self {
+    msm_small(scalars, bases)
   }
 
   #[cfg(feature = "blitzar")]
@@ -64,10 +134,24 @@ impl DlogGroupExt for bn256::Point {
     scalars: &[Vec<Self::Scalar>],
     bases: &[Self::AffineGroupElement],
   ) -> Vec<Self> {
-    super::blitzar::batch_vartime_multiscalar_mul(scalars, bases)
+    if super::msm_backend() == super::MsmBackend::Blitzar
+      && super::blitzar::is_worth_offloading(max_len(scalars))
+    {
+      return super::blitzar::batch_vartime_multiscalar_mul(scalars, bases);
+    }
+    scalars
+      .par_iter()
+      .map(|scalar| msm(scalar, &bases[..scalar.len()]))
+      .collect()
   }
 }
 
+/// Returns the number of scalars of the largest MSM of a batch
+#[cfg(feature = "blitzar")]
+fn max_len(scalars: &[Vec<bn256::Scalar>]) -> usize {
+  scalars.iter().map(Vec::len).max().unwrap_or(0)
+}
+
 impl_traits!(
   grumpkin,
   GrumpkinPoint,
@@ -83,6 +167,17 @@ impl PairingGroup for Bn256Point {
   fn pairing(p: &Self, q: &Self::G2) -> Self::GT {
     <Bn256 as H2CEngine>::pairing(&p.affine(), &q.affine())
   }
+
+  fn pairing_eq(p1: &Self, q1: &Self::G2, p2: &Self, q2: &Self::G2) -> bool {
+    // e(p1, q1) * e(-p2, q2) is the identity, with a single final exponentiation
+    let (p1, p2) = (p1.affine(), (-p2).affine());
+    let (q1, q2) = (q1.affine(), q2.affine());
+    bool::from(
+      Bn256::multi_miller_loop(&[(&p1, &q1), (&p2, &q2)])
+        .final_exponentiation()
+        .is_identity(),
+    )
+  }
 }
 
 impl Group for G2 {
@@ -118,8 +213,24 @@ impl DlogGroup for G2 {
     G2::from(*p)
   }
 
-  fn from_label(_label: &'static [u8], _n: usize) -> Vec<Self::AffineGroupElement> {
-    unimplemented!()
+  fn from_label_with(
+    spec: &crate::provider::GeneratorSpec,
+    label: &[u8],
+    n: usize,
+  ) -> Vec<Self::AffineGroupElement> {
+    let uniform_bytes_vec = spec.uniform_bytes(label, n);
+    let gens_proj: Vec<G2> = uniform_bytes_vec
+      .into_par_iter()
+      .map(|uniform_bytes| {
+        // the hash-to-curve function of G2 clears the cofactor, so generators are in the subgroup
+        let hash = G2::hash_to_curve(&spec.domain);
+        hash(&uniform_bytes)
+      })
+      .collect();
+
+    let mut gens = vec![G2Affine::identity(); n];
+    G2::batch_normalize(&gens_proj, &mut gens);
+    gens
   }
 
   fn zero() -> Self {
@@ -130,8 +241,27 @@ impl DlogGroup for G2 {
     G2::generator()
   }
 
+  /// Returns the first limbs, `c0`, of the coordinates over `Fq2`, which do not determine the
+  /// point; `to_coordinate_limbs` returns both limbs
   fn to_coordinates(&self) -> (Self::Base, Self::Base, bool) {
-    unimplemented!()
+    let (x, y, is_infinity) = self.to_coordinate_limbs();
+    (x[0], y[0], is_infinity)
+  }
+
+  fn to_coordinate_limbs(&self) -> (Vec<Self::Base>, Vec<Self::Base>, bool) {
+    let affine = self.affine();
+    let coordinates = affine.coordinates();
+    if coordinates.is_some().unwrap_u8() == 1 && (G2Affine::identity() != affine) {
+      let coordinates = coordinates.unwrap();
+      let (x, y) = (coordinates.x(), coordinates.y());
+      (vec![*x.c0(), *x.c1()], vec![*y.c0(), *y.c1()], false)
+    } else {
+      (
+        vec![Self::Base::zero(); 2],
+        vec![Self::Base::zero(); 2],
+        true,
+      )
+    }
   }
 }
 
@@ -143,6 +273,10 @@ impl<G: DlogGroup> TranscriptReprTrait<G> for G2Compressed {
 
 impl<G: DlogGroup> TranscriptReprTrait<G> for G2Affine {
   fn to_transcript_bytes(&self) -> Vec<u8> {
-    unimplemented!()
+    let (x, y, _) = G2::from(*self).to_coordinate_limbs();
+    x.iter()
+      .chain(y.iter())
+      .flat_map(|limb| encode_scalar(limb, TRANSCRIPT_BYTE_ORDER))
+      .collect()
   }
 }
@@ -0,0 +1,515 @@
+//! This module implements an experimental hash-based polynomial commitment scheme for multilinear
+//! polynomials, which combines a sum-check with FRI as in BaseFold, and needs no trusted setup or
+//! pairing.
+//!
+//! A multilinear polynomial in `n` variables, given by its `2^n` evaluations on the hypercube, is
+//! committed to with the Merkle root of the Reed-Solomon encoding, at rate `2^-log_blowup`, of the
+//! univariate polynomial whose coefficients are those of the multilinear polynomial in the monomial
+//! basis. Folding that codeword with FRI at a challenge `a` binds a variable of the multilinear
+//! polynomial to `a`, so the evaluation `v = f(r)` is proven by running the sum-check for
+//! `v = sum_b f(b) eq(r, b)` and FRI on the codeword with the same challenges. The final FRI
+//! constant is then `f(a)` for the random point `a` of the sum-check, which ends the sum-check.
+//!
+//! Unlike `ipa_pc` and `hyperkzg`, this scheme does not implement `EvaluationEngineTrait`:
+//! evaluation engines open the commitments of the commitment engine of an `Engine`, and folding
+//! requires those to be additively homomorphic, which Merkle roots are not. It thus serves
+//! polynomials committed to outside of folding, and its security relies on the conjectured
+//! proximity gaps of Reed-Solomon codes, at about `log_blowup` bits per query.
+use crate::{
+  errors::NovaError,
+  provider::util::{fft, root_of_unity},
+  spartan::polys::eq::EqPolynomial,
+  traits::{Engine, Group, TranscriptEngineTrait, TranscriptReprTrait},
+};
+use ff::{Field, PrimeField};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+/// The parameters of the scheme, which the prover and the verifier must agree on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriParams {
+  /// The logarithm of the inverse of the rate of the code
+  pub log_blowup: usize,
+  /// The number of positions at which the verifier checks the folding of the codewords
+  pub num_queries: usize,
+}
+
+impl Default for FriParams {
+  /// Returns parameters for about 128 bits of conjectured security
+  fn default() -> Self {
+    Self {
+      log_blowup: 2,
+      num_queries: 64,
+    }
+  }
+}
+
+/// The root of a Merkle tree
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Root([u8; 32]);
+
+impl<G: Group> TranscriptReprTrait<G> for Root {
+  fn to_transcript_bytes(&self) -> Vec<u8> {
+    self.0.to_vec()
+  }
+}
+
+/// A commitment to a multilinear polynomial
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commitment {
+  root: Root,
+  num_vars: usize,
+}
+
+impl<G: Group> TranscriptReprTrait<G> for Commitment {
+  fn to_transcript_bytes(&self) -> Vec<u8> {
+    [
+      self.root.0.as_slice(),
+      &(self.num_vars as u64).to_le_bytes(),
+    ]
+    .concat()
+  }
+}
+
+/// A Merkle tree over pairs of values of a codeword, where leaf `l` holds the values at positions
+/// `l` and `l + N/2` of a codeword of length `N`, which are the values at `x` and `-x`
+#[derive(Clone, Debug)]
+struct MerkleTree {
+  layers: Vec<Vec<[u8; 32]>>,
+}
+
+fn hash_leaf<F: PrimeField>(a: &F, b: &F) -> [u8; 32] {
+  let mut hasher = Keccak256::new();
+  hasher.update([0u8]);
+  hasher.update(a.to_repr().as_ref());
+  hasher.update(b.to_repr().as_ref());
+  hasher.finalize().into()
+}
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+  let mut hasher = Keccak256::new();
+  hasher.update([1u8]);
+  hasher.update(left);
+  hasher.update(right);
+  hasher.finalize().into()
+}
+
+impl MerkleTree {
+  fn new<F: PrimeField>(codeword: &[F]) -> Self {
+    let (lo, hi) = codeword.split_at(codeword.len() / 2);
+    let mut layers = vec![lo
+      .par_iter()
+      .zip(hi.par_iter())
+      .map(|(a, b)| hash_leaf(a, b))
+      .collect::<Vec<_>>()];
+    while layers[layers.len() - 1].len() > 1 {
+      let layer = layers[layers.len() - 1]
+        .par_chunks(2)
+        .map(|pair| hash_node(&pair[0], &pair[1]))
+        .collect();
+      layers.push(layer);
+    }
+    Self { layers }
+  }
+
+  fn root(&self) -> Root {
+    Root(self.layers[self.layers.len() - 1][0])
+  }
+
+  fn path(&self, mut index: usize) -> Vec<[u8; 32]> {
+    self.layers[..self.layers.len() - 1]
+      .iter()
+      .map(|layer| {
+        let sibling = layer[index ^ 1];
+        index /= 2;
+        sibling
+      })
+      .collect()
+  }
+}
+
+/// Checks that `path` opens the leaf at `index` to `(a, b)` under `root`
+fn verify_path<F: PrimeField>(
+  root: &Root,
+  mut index: usize,
+  a: &F,
+  b: &F,
+  path: &[[u8; 32]],
+) -> bool {
+  let mut node = hash_leaf(a, b);
+  for sibling in path {
+    node = if index % 2 == 0 {
+      hash_node(&node, sibling)
+    } else {
+      hash_node(sibling, &node)
+    };
+    index /= 2;
+  }
+  index == 0 && node == root.0
+}
+
+/// Folds the values `a` and `b` of a codeword at `x` and `-x` into the value at `x^2` of the folded
+/// codeword, which binds the lowest variable to `alpha`
+fn fold<F: PrimeField>(a: &F, b: &F, x_inv: &F, alpha: &F) -> F {
+  (*a + b + *alpha * (*a - b) * x_inv) * F::TWO_INV
+}
+
+fn fold_codeword<F: PrimeField>(codeword: &[F], omega: &F, alpha: &F) -> Vec<F> {
+  let (lo, hi) = codeword.split_at(codeword.len() / 2);
+  let omega_inv = omega.invert().unwrap();
+  let x_invs = (0..lo.len())
+    .scan(F::ONE, |x_inv, _| {
+      let cur = *x_inv;
+      *x_inv *= omega_inv;
+      Some(cur)
+    })
+    .collect::<Vec<_>>();
+  lo.par_iter()
+    .zip(hi.par_iter())
+    .zip(x_invs.par_iter())
+    .map(|((a, b), x_inv)| fold(a, b, x_inv, alpha))
+    .collect()
+}
+
+/// Binds the lowest variable of the multilinear polynomial with evaluations `evals` to `alpha`
+fn bind_low<F: PrimeField>(evals: &[F], alpha: &F) -> Vec<F> {
+  evals
+    .par_chunks(2)
+    .map(|pair| pair[0] + *alpha * (pair[1] - pair[0]))
+    .collect()
+}
+
+/// Returns the evaluation at `t` of the quadratic polynomial with evaluations `evals` at 0, 1, 2
+fn interpolate<F: PrimeField>(evals: &[F; 3], t: &F) -> F {
+  let (t1, t2) = (*t - F::ONE, *t - F::from(2));
+  (evals[0] * t1 * t2 - evals[1].double() * t * t2 + evals[2] * t * t1) * F::TWO_INV
+}
+
+/// Returns the index of the first position of the first codeword that a query checks
+fn squeeze_index<E: Engine>(transcript: &mut E::TE, half: usize) -> Result<usize, NovaError> {
+  let c = transcript.squeeze(b"q")?;
+  let bytes: [u8; 8] = c.to_repr().as_ref()[..8].try_into().unwrap();
+  Ok((u64::from_le_bytes(bytes) as usize) & (half - 1))
+}
+
+/// The data that the prover keeps to open a commitment
+#[derive(Clone, Debug)]
+pub struct ProverData<E: Engine> {
+  evals: Vec<E::Scalar>,
+  codeword: Vec<E::Scalar>,
+  tree: MerkleTree,
+}
+
+/// The opening of a leaf of the Merkle tree of a codeword
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+struct LeafOpening<E: Engine> {
+  values: [E::Scalar; 2],
+  path: Vec<[u8; 32]>,
+}
+
+/// An argument of the evaluation of a committed multilinear polynomial
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct EvaluationArgument<E: Engine> {
+  sumcheck: Vec<[E::Scalar; 2]>,
+  roots: Vec<Root>,
+  final_value: E::Scalar,
+  queries: Vec<Vec<LeafOpening<E>>>,
+}
+
+/// A polynomial commitment scheme for multilinear polynomials based on FRI
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct FriPCS {
+  params: FriParams,
+}
+
+impl FriPCS {
+  /// Creates an instance of the scheme with `params`
+  pub fn new(params: FriParams) -> Self {
+    Self { params }
+  }
+
+  /// Commits to the multilinear polynomial with evaluations `poly` on the hypercube, whose length
+  /// must be a power of two larger than one
+  pub fn commit<E: Engine>(
+    &self,
+    poly: &[E::Scalar],
+  ) -> Result<(Commitment, ProverData<E>), NovaError> {
+    if poly.len() < 2 || !poly.len().is_power_of_two() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    let num_vars = poly.len().trailing_zeros() as usize;
+    let omega = root_of_unity::<E::Scalar>(num_vars + self.params.log_blowup)?;
+
+    // the coefficients in the monomial basis, with the Moebius transform
+    let mut codeword = poly.to_vec();
+    let mut half = 1;
+    while half < codeword.len() {
+      codeword.par_chunks_mut(2 * half).for_each(|chunk| {
+        let (lo, hi) = chunk.split_at_mut(half);
+        for (x, y) in lo.iter().zip(hi.iter_mut()) {
+          *y -= x;
+        }
+      });
+      half *= 2;
+    }
+    codeword.resize(poly.len() << self.params.log_blowup, E::Scalar::ZERO);
+    fft(&mut codeword, omega);
+
+    let tree = MerkleTree::new(&codeword);
+    let comm = Commitment {
+      root: tree.root(),
+      num_vars,
+    };
+    let data = ProverData {
+      evals: poly.to_vec(),
+      codeword,
+      tree,
+    };
+    Ok((comm, data))
+  }
+
+  /// Proves that the polynomial committed to in `comm`, with prover data `data`, evaluates to `eval`
+  /// at `point`
+  pub fn prove<E: Engine>(
+    &self,
+    transcript: &mut E::TE,
+    comm: &Commitment,
+    data: &ProverData<E>,
+    point: &[E::Scalar],
+    eval: &E::Scalar,
+  ) -> Result<EvaluationArgument<E>, NovaError> {
+    let n = comm.num_vars;
+    if point.len() != n || data.evals.len() != 1 << n {
+      return Err(NovaError::InvalidInputLength);
+    }
+    transcript.absorb(b"C", comm);
+    transcript.absorb(b"r", &point);
+    transcript.absorb(b"v", eval);
+
+    let mut evals = data.evals.clone();
+    let mut eq = EqPolynomial::evals_from_points(point);
+    let mut omega = root_of_unity::<E::Scalar>(n + self.params.log_blowup)?;
+    let mut codewords = vec![data.codeword.clone()];
+    let mut trees = Vec::with_capacity(n);
+    let mut sumcheck = Vec::with_capacity(n);
+    let mut roots = Vec::with_capacity(n - 1);
+    for i in 0..n {
+      let (g0, g2) = evals
+        .par_chunks(2)
+        .zip(eq.par_chunks(2))
+        .map(|(e, q)| (e[0] * q[0], (e[1].double() - e[0]) * (q[1].double() - q[0])))
+        .reduce(
+          || (E::Scalar::ZERO, E::Scalar::ZERO),
+          |a, b| (a.0 + b.0, a.1 + b.1),
+        );
+      transcript.absorb(b"g", &[g0, g2].as_slice());
+      sumcheck.push([g0, g2]);
+      let alpha = transcript.squeeze(b"a")?;
+
+      evals = bind_low(&evals, &alpha);
+      eq = bind_low(&eq, &alpha);
+      let codeword = fold_codeword(&codewords[i], &omega, &alpha);
+      omega = omega.square();
+      if i < n - 1 {
+        let tree = MerkleTree::new(&codeword);
+        transcript.absorb(b"R", &tree.root());
+        roots.push(tree.root());
+        trees.push(tree);
+      }
+      codewords.push(codeword);
+    }
+    let final_value = codewords[n][0];
+    transcript.absorb(b"f", &final_value);
+
+    let queries = (0..self.params.num_queries)
+      .map(|_| {
+        let mut k = squeeze_index::<E>(transcript, data.codeword.len() / 2)?;
+        Ok(
+          (0..n)
+            .map(|i| {
+              let half = codewords[i].len() / 2;
+              let l = k % half;
+              k = l;
+              let tree = if i == 0 { &data.tree } else { &trees[i - 1] };
+              LeafOpening {
+                values: [codewords[i][l], codewords[i][l + half]],
+                path: tree.path(l),
+              }
+            })
+            .collect(),
+        )
+      })
+      .collect::<Result<Vec<_>, NovaError>>()?;
+
+    Ok(EvaluationArgument {
+      sumcheck,
+      roots,
+      final_value,
+      queries,
+    })
+  }
+
+  /// Verifies that the polynomial committed to in `comm` evaluates to `eval` at `point`
+  pub fn verify<E: Engine>(
+    &self,
+    transcript: &mut E::TE,
+    comm: &Commitment,
+    point: &[E::Scalar],
+    eval: &E::Scalar,
+    arg: &EvaluationArgument<E>,
+  ) -> Result<(), NovaError> {
+    let n = comm.num_vars;
+    if n == 0
+      || point.len() != n
+      || arg.sumcheck.len() != n
+      || arg.roots.len() != n - 1
+      || arg.queries.len() != self.params.num_queries
+    {
+      return Err(NovaError::InvalidPCS);
+    }
+    transcript.absorb(b"C", comm);
+    transcript.absorb(b"r", &point);
+    transcript.absorb(b"v", eval);
+
+    // the sum-check, whose challenges are also those of the folding
+    let mut claim = *eval;
+    let mut alphas = Vec::with_capacity(n);
+    for (i, [g0, g2]) in arg.sumcheck.iter().enumerate() {
+      transcript.absorb(b"g", &[*g0, *g2].as_slice());
+      let alpha = transcript.squeeze(b"a")?;
+      claim = interpolate(&[*g0, claim - g0, *g2], &alpha);
+      alphas.push(alpha);
+      if i < n - 1 {
+        transcript.absorb(b"R", &arg.roots[i]);
+      }
+    }
+    transcript.absorb(b"f", &arg.final_value);
+
+    // the lowest variable is bound first, and it is the last coordinate of the point
+    let eq = EqPolynomial::new(point.iter().rev().copied().collect()).evaluate(&alphas);
+    if claim != arg.final_value * eq {
+      return Err(NovaError::InvalidPCS);
+    }
+
+    // the queries, which check that each codeword folds into the next one
+    let log_n0 = n + self.params.log_blowup;
+    let omega = root_of_unity::<E::Scalar>(log_n0)?;
+    for query in &arg.queries {
+      if query.len() != n {
+        return Err(NovaError::InvalidPCS);
+      }
+      let mut k = squeeze_index::<E>(transcript, 1 << (log_n0 - 1))?;
+      let mut expected = None;
+      let mut omega_i = omega;
+      for (i, opening) in query.iter().enumerate() {
+        let half = 1 << (log_n0 - 1 - i);
+        let l = k % half;
+        let [a, b] = &opening.values;
+        if let Some(expected) = expected {
+          if expected != if k < half { *a } else { *b } {
+            return Err(NovaError::InvalidPCS);
+          }
+        }
+        let root = if i == 0 {
+          &comm.root
+        } else {
+          &arg.roots[i - 1]
+        };
+        if opening.path.len() != log_n0 - 1 - i || !verify_path(root, l, a, b, &opening.path) {
+          return Err(NovaError::InvalidPCS);
+        }
+        let x_inv = omega_i.pow_vartime([l as u64]).invert().unwrap();
+        expected = Some(fold(a, b, &x_inv, &alphas[i]));
+        omega_i = omega_i.square();
+        k = l;
+      }
+      if expected != Some(arg.final_value) {
+        return Err(NovaError::InvalidPCS);
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::{Bn256EngineKZG, PallasEngine},
+    spartan::polys::multilinear::MultilinearPolynomial,
+  };
+  use rand_core::OsRng;
+
+  fn test_fri_with<E: Engine>(num_vars: usize) {
+    let pcs = FriPCS::default();
+    let poly = (0..1 << num_vars)
+      .map(|_| E::Scalar::random(&mut OsRng))
+      .collect::<Vec<_>>();
+    let point = (0..num_vars)
+      .map(|_| E::Scalar::random(&mut OsRng))
+      .collect::<Vec<_>>();
+    let eval = MultilinearPolynomial::evaluate_with(&poly, &point);
+
+    let (comm, data) = pcs.commit::<E>(&poly).unwrap();
+    let arg = pcs
+      .prove::<E>(&mut E::TE::new(b"fri"), &comm, &data, &point, &eval)
+      .unwrap();
+    let verify = |point: &[E::Scalar], eval: &E::Scalar, arg: &EvaluationArgument<E>| {
+      pcs.verify::<E>(&mut E::TE::new(b"fri"), &comm, point, eval, arg)
+    };
+    assert!(verify(&point, &eval, &arg).is_ok());
+
+    // wrong evaluations and points are rejected
+    assert_eq!(
+      verify(&point, &(eval + E::Scalar::ONE), &arg),
+      Err(NovaError::InvalidPCS)
+    );
+    let mut other_point = point.clone();
+    other_point[0] += E::Scalar::ONE;
+    assert_eq!(
+      verify(&other_point, &eval, &arg),
+      Err(NovaError::InvalidPCS)
+    );
+
+    // so are arguments with a tampered opening or final value
+    let mut forged = arg.clone();
+    forged.queries[0][num_vars - 1].values[0] += E::Scalar::ONE;
+    assert_eq!(verify(&point, &eval, &forged), Err(NovaError::InvalidPCS));
+    let mut forged = arg.clone();
+    forged.final_value += E::Scalar::ONE;
+    assert_eq!(verify(&point, &eval, &forged), Err(NovaError::InvalidPCS));
+
+    // and arguments for another polynomial with the same evaluation
+    let mut other_poly = poly.clone();
+    other_poly[0] += E::Scalar::ONE;
+    other_poly[1] += E::Scalar::ONE;
+    let other_point = vec![E::Scalar::ONE; num_vars];
+    let other_eval = MultilinearPolynomial::evaluate_with(&poly, &other_point);
+    let (other_comm, other_data) = pcs.commit::<E>(&other_poly).unwrap();
+    let other_arg = pcs
+      .prove::<E>(
+        &mut E::TE::new(b"fri"),
+        &other_comm,
+        &other_data,
+        &other_point,
+        &other_eval,
+      )
+      .unwrap();
+    assert_eq!(
+      verify(&other_point, &other_eval, &other_arg),
+      Err(NovaError::InvalidPCS)
+    );
+  }
+
+  #[test]
+  fn test_fri() {
+    for num_vars in [1, 4, 9] {
+      test_fri_with::<PallasEngine>(num_vars);
+      test_fri_with::<Bn256EngineKZG>(num_vars);
+    }
+  }
+}
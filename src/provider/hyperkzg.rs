@@ -7,32 +7,40 @@
 //! and within the KZG commitment scheme implementation itself).
 #![allow(non_snake_case)]
 use crate::{
+  encoding::encode_point,
   errors::NovaError,
   gadgets::utils::to_bignat_repr,
   provider::{
     ptau::PtauFileError,
     read_ptau,
-    traits::{DlogGroup, DlogGroupExt, PairingGroup},
+    traits::{blinding_generator, DlogGroup, DlogGroupExt, PairingGroup, PreparedBasesCell},
+    util::{fft, fixed_base_exp_comb_batch, fixed_base_mul_batch, root_of_unity},
     write_ptau,
   },
   traits::{
-    commitment::{CommitmentEngineTrait, CommitmentTrait, Len},
+    calldata::{CalldataDecoder, CalldataEncoder, CalldataEncoding},
+    commitment::{
+      BlindingGenerator, CommitmentEngineTrait, CommitmentKeyAttestation, CommitmentTrait, Len,
+    },
     evaluation::EvaluationEngineTrait,
     AbsorbInRO2Trait, AbsorbInROTrait, Engine, ROTrait, TranscriptEngineTrait, TranscriptReprTrait,
   },
 };
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use core::{
   iter,
   marker::PhantomData,
   ops::{Add, Mul, MulAssign},
   slice,
 };
-use ff::{Field, PrimeFieldBits};
+use ff::{Field, PrimeField};
+use halo2curves::{group::GroupEncoding, CurveAffine};
 use num_integer::Integer;
 use num_traits::ToPrimitive;
-use rand_core::OsRng;
+use rand_core::{OsRng, RngCore};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
 /// Alias to points on G1 that are in preprocessed form
 type G1Affine<E> = <<E as Engine>::GE as DlogGroup>::AffineGroupElement;
@@ -49,6 +57,8 @@ where
   ck: Vec<<E::GE as DlogGroup>::AffineGroupElement>,
   h: <E::GE as DlogGroup>::AffineGroupElement,
   tau_H: <<E::GE as PairingGroup>::G2 as DlogGroup>::AffineGroupElement, // needed only for the verifier key
+  #[serde(skip)]
+  prepared: PreparedBasesCell<E::GE>,
 }
 
 impl<E: Engine> CommitmentKey<E>
@@ -61,7 +71,12 @@ where
     h: <E::GE as DlogGroup>::AffineGroupElement,
     tau_H: <<E::GE as PairingGroup>::G2 as DlogGroup>::AffineGroupElement,
   ) -> Self {
-    Self { ck, h, tau_H }
+    Self {
+      ck,
+      h,
+      tau_H,
+      prepared: Default::default(),
+    }
   }
 
   /// Returns a reference to the ck field
@@ -80,6 +95,17 @@ where
   }
 }
 
+impl<E> CommitmentKey<E>
+where
+  E: Engine<CE = CommitmentEngine<E>>,
+  E::GE: PairingGroup,
+{
+  /// Produces an attestation of the commitment key (see `CommitmentKeyAttestation`)
+  pub fn attest(&self) -> CommitmentKeyAttestation<E> {
+    CommitmentKeyAttestation::new(self)
+  }
+}
+
 impl<E: Engine> Len for CommitmentKey<E>
 where
   E::GE: PairingGroup,
@@ -98,6 +124,16 @@ where
   h: <E::GE as DlogGroup>::AffineGroupElement,
 }
 
+impl<E: Engine> DerandKey<E>
+where
+  E::GE: DlogGroup,
+{
+  /// Returns the blinding generator
+  pub fn blinding_generator(&self) -> E::GE {
+    E::GE::group(&self.h)
+  }
+}
+
 /// A KZG commitment
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(bound = "")]
@@ -122,6 +158,22 @@ where
   }
 }
 
+impl<E: Engine> CalldataEncoding for Commitment<E>
+where
+  E::GE: PairingGroup,
+  G1Affine<E>: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.comm.affine().encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Commitment {
+      comm: E::GE::group(&CalldataEncoding::decode(dec)?),
+    })
+  }
+}
+
 impl<E: Engine> CommitmentTrait<E> for Commitment<E>
 where
   E::GE: PairingGroup,
@@ -166,13 +218,7 @@ where
 {
   fn to_transcript_bytes(&self) -> Vec<u8> {
     let (x, y, is_infinity) = self.comm.to_coordinates();
-    let is_infinity_byte = (!is_infinity).into();
-    [
-      x.to_transcript_bytes(),
-      y.to_transcript_bytes(),
-      [is_infinity_byte].to_vec(),
-    ]
-    .concat()
+    encode_point(&x, &y, is_infinity)
   }
 }
 
@@ -302,13 +348,18 @@ where
     let gen = <E::GE as DlogGroup>::gen();
 
     let ck = fixed_base_exp_comb_batch::<4, 16, 64, 2, 32, _>(gen, powers_of_tau);
-    let ck = ck.par_iter().map(|p| p.affine()).collect();
+    let ck = E::GE::batch_affine(&ck);
 
     let h = *E::GE::from_label(label, 1).first().unwrap();
 
     let tau_H = (<<E::GE as PairingGroup>::G2 as DlogGroup>::gen() * tau).affine();
 
-    Self { ck, h, tau_H }
+    Self {
+      ck,
+      h,
+      tau_H,
+      prepared: Default::default(),
+    }
   }
 
   fn setup_from_tau_direct(
@@ -318,16 +369,22 @@ where
   ) -> Self {
     let num_gens = powers_of_tau.len();
 
-    let ck: Vec<G1Affine<E>> = (0..num_gens)
+    let ck = (0..num_gens)
       .into_par_iter()
-      .map(|i| (<E::GE as DlogGroup>::gen() * powers_of_tau[i]).affine())
-      .collect();
+      .map(|i| <E::GE as DlogGroup>::gen() * powers_of_tau[i])
+      .collect::<Vec<_>>();
+    let ck: Vec<G1Affine<E>> = E::GE::batch_affine(&ck);
 
     let h = *E::GE::from_label(label, 1).first().unwrap();
 
     let tau_H = (<<E::GE as PairingGroup>::G2 as DlogGroup>::gen() * tau).affine();
 
-    Self { ck, h, tau_H }
+    Self {
+      ck,
+      h,
+      tau_H,
+      prepared: Default::default(),
+    }
   }
 
   fn compute_powers_serial(tau: E::Scalar, n: usize) -> Vec<E::Scalar> {
@@ -340,7 +397,7 @@ where
   }
 
   fn compute_powers_par(tau: E::Scalar, n: usize) -> Vec<E::Scalar> {
-    let num_threads = rayon::current_num_threads();
+    let num_threads = crate::provider::util::num_chunks();
     (0..n)
       .collect::<Vec<_>>()
       .par_chunks(std::cmp::max(n / num_threads, 1))
@@ -358,95 +415,6 @@ where
   }
 }
 
-// * Implementation of https://www.weimerskirch.org/files/Weimerskirch_FixedBase.pdf
-fn fixed_base_exp_comb_batch<
-  const H: usize,
-  const POW_2_H: usize,
-  const A: usize,
-  const B: usize,
-  const V: usize,
-  G: DlogGroup,
->(
-  gen: G,
-  scalars: &[G::Scalar],
-) -> Vec<G> {
-  assert_eq!(1 << H, POW_2_H);
-  assert_eq!(A, V * B);
-  assert!(A <= 64);
-
-  let zero = G::zero();
-  let one = gen;
-
-  let gi = {
-    let mut res = [one; H];
-    for i in 1..H {
-      let prod = (0..A).fold(res[i - 1], |acc, _| acc + acc);
-      res[i] = prod;
-    }
-    res
-  };
-
-  let mut precompute_res = (1..POW_2_H)
-    .into_par_iter()
-    .map(|i| {
-      let mut res = [zero; V];
-
-      // * G[0][i]
-      let mut g_0_i = zero;
-      for (j, item) in gi.iter().enumerate().take(H) {
-        if (1 << j) & i > 0 {
-          g_0_i += item;
-        }
-      }
-
-      res[0] = g_0_i;
-
-      // * G[j][i]
-      for j in 1..V {
-        res[j] = (0..B).fold(res[j - 1], |acc, _| acc + acc);
-      }
-
-      res
-    })
-    .collect::<Vec<_>>();
-
-  precompute_res.insert(0, [zero; V]);
-
-  let precomputed_g: [_; POW_2_H] = std::array::from_fn(|j| precompute_res[j]);
-
-  let zero = G::zero();
-
-  scalars
-    .par_iter()
-    .map(|e| {
-      let mut a = zero;
-      let mut bits = e.to_le_bits().into_iter().collect::<Vec<_>>();
-
-      while bits.len() % A != 0 {
-        bits.push(false);
-      }
-
-      for k in (0..B).rev() {
-        a += a;
-        for j in (0..V).rev() {
-          let i_j_k = (0..H)
-            .map(|h| {
-              let b = bits[h * A + j * B + k];
-              (1 << h) * b as usize
-            })
-            .sum::<usize>();
-
-          if i_j_k > 0 {
-            a += precomputed_g[i_j_k][j];
-          }
-        }
-      }
-
-      a
-    })
-    .collect::<Vec<_>>()
-}
-
 impl<E: Engine> CommitmentEngineTrait<E> for CommitmentEngine<E>
 where
   E::GE: PairingGroup,
@@ -460,19 +428,57 @@ where
     Self::CommitmentKey::setup_from_rng(label, n, OsRng)
   }
 
+  fn setup_with_blinding(
+    label: &'static [u8],
+    n: usize,
+    blinding: &BlindingGenerator<E>,
+  ) -> Result<Self::CommitmentKey, NovaError> {
+    let mut ck = Self::setup(label, n);
+    ck.h = blinding_generator(blinding, ck.h)?;
+    Ok(ck)
+  }
+
   fn derand_key(ck: &Self::CommitmentKey) -> Self::DerandKey {
     Self::DerandKey { h: ck.h }
   }
 
+  fn prepare(ck: &Self::CommitmentKey) {
+    ck.prepared.prepare(&ck.ck);
+  }
+
   fn commit(ck: &Self::CommitmentKey, v: &[E::Scalar], r: &E::Scalar) -> Self::Commitment {
+    let v = &v[..trimmed_len(v, is_zero::<E::Scalar>)];
     assert!(ck.ck.len() >= v.len());
 
     Commitment {
-      comm: E::GE::vartime_multiscalar_mul(v, &ck.ck[..v.len()])
+      comm: E::GE::vartime_multiscalar_mul_prepared(v, &ck.ck, ck.prepared.get(), 0)
         + <E::GE as DlogGroup>::group(&ck.h) * r,
     }
   }
 
+  fn commit_sparse(
+    ck: &Self::CommitmentKey,
+    indices: &[usize],
+    values: &[E::Scalar],
+    r: &E::Scalar,
+  ) -> Self::Commitment {
+    assert!(indices.iter().all(|i| *i < ck.ck.len()));
+
+    Commitment {
+      comm: E::GE::vartime_multiscalar_mul_sparse(indices, values, &ck.ck, ck.prepared.get())
+        + <E::GE as DlogGroup>::group(&ck.h) * r,
+    }
+  }
+
+  fn commit_at(ck: &Self::CommitmentKey, v: &[E::Scalar], offset: usize) -> Self::Commitment {
+    let v = &v[..trimmed_len(v, is_zero::<E::Scalar>)];
+    assert!(ck.ck.len() >= offset + v.len());
+
+    Commitment {
+      comm: E::GE::vartime_multiscalar_mul_prepared(v, &ck.ck, ck.prepared.get(), offset),
+    }
+  }
+
   fn batch_commit(
     ck: &Self::CommitmentKey,
     v: &[Vec<<E as Engine>::Scalar>],
@@ -480,12 +486,13 @@ where
   ) -> Vec<Self::Commitment> {
     assert!(v.len() == r.len());
 
+    let v = trim_batch(v, is_zero::<E::Scalar>);
     let max = v.iter().map(|v| v.len()).max().unwrap_or(0);
     assert!(ck.ck.len() >= max);
 
     let h = <E::GE as DlogGroup>::group(&ck.h);
 
-    E::GE::batch_vartime_multiscalar_mul(v, &ck.ck[..max])
+    E::GE::batch_vartime_multiscalar_mul_prepared(&v, &ck.ck[..max], ck.prepared.get())
       .par_iter()
       .zip(r.par_iter())
       .map(|(commit, r_i)| Commitment {
@@ -499,6 +506,7 @@ where
     v: &[T],
     r: &E::Scalar,
   ) -> Self::Commitment {
+    let v = &v[..trimmed_len(v, T::is_zero)];
     assert!(ck.ck.len() >= v.len());
     Commitment {
       comm: E::GE::vartime_multiscalar_mul_small(v, &ck.ck[..v.len()])
@@ -513,12 +521,13 @@ where
   ) -> Vec<Self::Commitment> {
     assert!(v.len() == r.len());
 
+    let v = trim_batch(v, T::is_zero);
     let max = v.iter().map(|v| v.len()).max().unwrap_or(0);
     assert!(ck.ck.len() >= max);
 
     let h = <E::GE as DlogGroup>::group(&ck.h);
 
-    E::GE::batch_vartime_multiscalar_mul_small(v, &ck.ck[..max])
+    E::GE::batch_vartime_multiscalar_mul_small(&v, &ck.ck[..max])
       .iter()
       .zip(r.iter())
       .map(|(commit, r_i)| Commitment {
@@ -537,6 +546,21 @@ where
     }
   }
 
+  fn derandomize_batch(
+    dk: &Self::DerandKey,
+    commits: &[Self::Commitment],
+    r: &[E::Scalar],
+  ) -> Vec<Self::Commitment> {
+    assert!(commits.len() == r.len());
+    fixed_base_mul_batch(<E::GE as DlogGroup>::group(&dk.h), r)
+      .into_par_iter()
+      .zip(commits.par_iter())
+      .map(|(blind, commit)| Commitment {
+        comm: commit.comm - blind,
+      })
+      .collect()
+  }
+
   fn load_setup(
     reader: &mut (impl std::io::Read + std::io::Seek),
     label: &'static [u8],
@@ -544,6 +568,10 @@ where
   ) -> Result<Self::CommitmentKey, PtauFileError> {
     let num = n.next_power_of_two();
 
+    if CommitmentKey::<E>::is_compressed_key_file(reader)? {
+      return CommitmentKey::load_compressed(reader, label, num);
+    }
+
     let (g1_points, g2_points) = read_ptau(reader, num, 2)?;
 
     let ck = g1_points.to_vec();
@@ -552,7 +580,350 @@ where
 
     let h = *E::GE::from_label(label, 1).first().unwrap();
 
-    Ok(CommitmentKey { ck, h, tau_H })
+    Ok(CommitmentKey {
+      ck,
+      h,
+      tau_H,
+      prepared: Default::default(),
+    })
+  }
+}
+
+/// The basis in which a vector `v` of length `n` represents a univariate polynomial `p` of degree
+/// less than `n`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Basis {
+  /// `v[i]` is the coefficient of `X^i` in `p`, which is how `CommitmentEngineTrait::commit`
+  /// interprets vectors. The evaluations of a multilinear polynomial over the Boolean hypercube
+  /// are in this basis, as they are the coefficients of the univariate polynomial that HyperKZG
+  /// commits to.
+  Coefficient,
+  /// `v[i] = p(ω^i)`, where `n` is a power of two and `ω = domain_generator(n)`
+  Evaluation,
+}
+
+/// Returns the generator `ω` of the multiplicative subgroup of order `n`, a power of two, over
+/// which polynomials are evaluated in `Basis::Evaluation`
+pub fn domain_generator<F: PrimeField>(n: usize) -> Result<F, NovaError> {
+  if !n.is_power_of_two() {
+    return Err(NovaError::InvalidInputLength);
+  }
+  root_of_unity(n.trailing_zeros() as usize)
+}
+
+/// Converts the coefficients of a polynomial into its evaluations over the powers of
+/// `domain_generator(coeffs.len())`, with a number-theoretic transform
+pub fn coeffs_to_evals<F: PrimeField>(coeffs: &[F]) -> Result<Vec<F>, NovaError> {
+  if coeffs.is_empty() {
+    return Ok(Vec::new());
+  }
+  let omega = domain_generator::<F>(coeffs.len())?;
+  let mut evals = coeffs.to_vec();
+  fft(&mut evals, omega);
+  Ok(evals)
+}
+
+/// Converts the evaluations of a polynomial over the powers of `domain_generator(evals.len())`
+/// into its coefficients, with an inverse number-theoretic transform
+pub fn evals_to_coeffs<F: PrimeField>(evals: &[F]) -> Result<Vec<F>, NovaError> {
+  if evals.is_empty() {
+    return Ok(Vec::new());
+  }
+  let omega_inv = domain_generator::<F>(evals.len())?.invert().unwrap();
+  let n_inv = F::from(evals.len() as u64).invert().unwrap();
+  let mut coeffs = evals.to_vec();
+  fft(&mut coeffs, omega_inv);
+  coeffs.par_iter_mut().for_each(|c| *c *= n_inv);
+  Ok(coeffs)
+}
+
+impl<E: Engine> CommitmentEngine<E>
+where
+  E::GE: PairingGroup,
+{
+  /// Commits to the polynomial that `v` represents in `basis`, converting it into the coefficient
+  /// basis that `commit` expects first if needed. The commitments of both representations of a
+  /// polynomial are equal.
+  pub fn commit_in_basis(
+    ck: &CommitmentKey<E>,
+    v: &[E::Scalar],
+    basis: Basis,
+    r: &E::Scalar,
+  ) -> Result<Commitment<E>, NovaError> {
+    match basis {
+      Basis::Coefficient => Ok(Self::commit(ck, v, r)),
+      Basis::Evaluation => Ok(Self::commit(ck, &evals_to_coeffs(v)?, r)),
+    }
+  }
+}
+
+/// How thoroughly `CommitmentKey::load_setup_validated` checks the contents of a key file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetupValidation {
+  /// Trusts the contents of the file, as `load_setup` does
+  Trusted,
+  /// Checks that all points lie in the prime-order subgroups, and that the powers of tau are
+  /// consistent at the given number of random indices, with one pair of pairings per index
+  SpotCheck(usize),
+  /// Checks that all points lie in the prime-order subgroups, and that all the powers of tau are
+  /// consistent, for first-time loads of externally provided files
+  Strict,
+}
+
+impl<E: Engine> CommitmentKey<E>
+where
+  E::GE: PairingGroup,
+{
+  /// Loads a commitment key as `load_setup` does, and validates it as specified by `validation`
+  pub fn load_setup_validated(
+    reader: &mut (impl std::io::Read + std::io::Seek),
+    label: &'static [u8],
+    n: usize,
+    validation: SetupValidation,
+  ) -> Result<Self, PtauFileError> {
+    let ck = CommitmentEngine::<E>::load_setup(reader, label, n)?;
+    ck.validate(validation)?;
+    Ok(ck)
+  }
+
+  /// Validates the commitment key as specified by `validation`.
+  ///
+  /// The powers of tau `ck[i]` are consistent if `e(ck[i], H) = e(ck[i - 1], tau_H)`, where `H`
+  /// generates the second group, and the first power must be the generator of the first group.
+  /// The strict mode checks all of them at once against a random linear combination.
+  pub fn validate(&self, validation: SetupValidation) -> Result<(), PtauFileError> {
+    let num_checks = match validation {
+      SetupValidation::Trusted => return Ok(()),
+      SetupValidation::SpotCheck(num_checks) => num_checks,
+      SetupValidation::Strict => usize::MAX,
+    };
+
+    let in_subgroup = self.ck.par_iter().all(in_prime_order_subgroup::<E::GE>)
+      && in_prime_order_subgroup::<<E::GE as PairingGroup>::G2>(&self.tau_H);
+    if !in_subgroup {
+      return Err(PtauFileError::PointNotInSubgroup);
+    }
+
+    let G = E::GE::gen();
+    let H = <<E::GE as PairingGroup>::G2 as DlogGroup>::gen();
+    let tau_H = <<E::GE as PairingGroup>::G2 as DlogGroup>::group(&self.tau_H);
+    if self.ck.first().map(E::GE::group) != Some(G) || tau_H == DlogGroup::zero() {
+      return Err(PtauFileError::InconsistentPowers);
+    }
+
+    let num_powers = self.ck.len() - 1;
+    let consistent = if num_checks >= num_powers {
+      let rho = (0..num_powers)
+        .map(|_| E::Scalar::random(OsRng))
+        .collect::<Vec<_>>();
+      let higher = E::GE::vartime_multiscalar_mul(&rho, &self.ck[1..]);
+      let lower = E::GE::vartime_multiscalar_mul(&rho, &self.ck[..num_powers]);
+      E::GE::pairing(&higher, &H) == E::GE::pairing(&lower, &tau_H)
+    } else {
+      (0..num_checks).all(|_| {
+        let i = 1 + (OsRng.next_u64() % num_powers as u64) as usize;
+        E::GE::pairing(&E::GE::group(&self.ck[i]), &H)
+          == E::GE::pairing(&E::GE::group(&self.ck[i - 1]), &tau_H)
+      })
+    };
+    if !consistent {
+      return Err(PtauFileError::InconsistentPowers);
+    }
+
+    Ok(())
+  }
+}
+
+/// Returns whether `p` lies on the curve and in its subgroup of prime order, i.e., whether `r * p`
+/// is the identity, where `r` is the order of the scalar field
+fn in_prime_order_subgroup<G: DlogGroup>(p: &G::AffineGroupElement) -> bool {
+  let p_group = G::group(p);
+  bool::from(p.is_on_curve()) && p_group * -G::Scalar::ONE + p_group == G::zero()
+}
+
+/// Returns whether `x` is zero, for trimming vectors of field elements
+fn is_zero<F: Field>(x: &F) -> bool {
+  bool::from(x.is_zero())
+}
+
+/// Returns the length of `v` without its trailing zeros, which do not change the committed
+/// polynomial but would otherwise add terms to MSMs, e.g., the padding of witnesses
+fn trimmed_len<T>(v: &[T], is_zero: impl Fn(&T) -> bool) -> usize {
+  v.iter().rposition(|x| !is_zero(x)).map_or(0, |i| i + 1)
+}
+
+/// Returns the vectors of `v` without their trailing zeros, copying them only if one has some
+fn trim_batch<T: Clone>(v: &[Vec<T>], is_zero: impl Fn(&T) -> bool + Copy) -> Cow<'_, [Vec<T>]> {
+  let lens = v
+    .iter()
+    .map(|v| trimmed_len(v, is_zero))
+    .collect::<Vec<_>>();
+  if lens.iter().zip(v).all(|(len, v)| *len == v.len()) {
+    Cow::Borrowed(v)
+  } else {
+    Cow::Owned(
+      v.iter()
+        .zip(lens)
+        .map(|(v, len)| v[..len].to_vec())
+        .collect(),
+    )
+  }
+}
+
+/// The magic string of compressed key files (see `CommitmentKey::save_compressed_to`)
+const COMPRESSED_KEY_FILE_MAGIC: &[u8; 4] = b"nkzg";
+
+/// The version of the format of compressed key files
+const COMPRESSED_KEY_FILE_VERSION: u32 = 1;
+
+/// The header of a compressed key file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyFileHeader {
+  /// The basis of the G1 points of the file. In `Basis::Coefficient`, they are the powers of tau,
+  /// and in `Basis::Evaluation`, they are the Lagrange polynomials of the domain of size
+  /// `num_gens` evaluated at tau, from which the powers of tau are recovered on load.
+  pub basis: Basis,
+  /// The number of G1 points of the file
+  pub num_gens: usize,
+}
+
+impl KeyFileHeader {
+  /// Reads the header of a compressed key file
+  pub fn read_from(reader: &mut impl std::io::Read) -> Result<Self, PtauFileError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != COMPRESSED_KEY_FILE_MAGIC {
+      return Err(PtauFileError::InvalidHead);
+    }
+    let version = reader.read_u32::<LittleEndian>()?;
+    if version != COMPRESSED_KEY_FILE_VERSION {
+      return Err(PtauFileError::UnsupportedVersion(version));
+    }
+    let basis = match reader.read_u8()? {
+      0 => Basis::Coefficient,
+      1 => Basis::Evaluation,
+      b => return Err(PtauFileError::InvalidPointEncoding(b)),
+    };
+    let num_gens = reader.read_u64::<LittleEndian>()? as usize;
+    Ok(Self { basis, num_gens })
+  }
+
+  /// Writes the header of a compressed key file
+  fn write_to(&self, writer: &mut impl std::io::Write) -> Result<(), PtauFileError> {
+    writer.write_all(COMPRESSED_KEY_FILE_MAGIC)?;
+    writer.write_u32::<LittleEndian>(COMPRESSED_KEY_FILE_VERSION)?;
+    writer.write_u8(match self.basis {
+      Basis::Coefficient => 0,
+      Basis::Evaluation => 1,
+    })?;
+    writer.write_u64::<LittleEndian>(self.num_gens as u64)?;
+    Ok(())
+  }
+}
+
+impl<E: Engine> CommitmentKey<E>
+where
+  E::GE: PairingGroup,
+{
+  /// Saves the key in the compressed format, which stores each point in its compressed encoding,
+  /// so that the file is about half the size of the one of `save_to`, and which `load_setup`
+  /// reads as well.
+  ///
+  /// With `Basis::Evaluation`, the file holds the Lagrange basis of the domain of size
+  /// `self.ck().len()`, which must be a power of two, and the powers of tau are recovered from it
+  /// on load with an FFT over G1. Keys are kept in memory in the monomial basis either way.
+  pub fn save_compressed_to(
+    &self,
+    writer: &mut impl std::io::Write,
+    basis: Basis,
+  ) -> Result<(), PtauFileError> {
+    let num_gens = self.ck.len();
+    let points = match basis {
+      Basis::Coefficient => self.ck.clone(),
+      Basis::Evaluation => {
+        // L_j(tau) = (1/n) sum_i omega^{-ij} tau^i
+        let omega = domain_generator::<E::Scalar>(num_gens)
+          .map_err(|_| PtauFileError::InvalidLagrangeDomain(num_gens))?;
+        let mut points = self.ck.par_iter().map(E::GE::group).collect::<Vec<_>>();
+        fft(&mut points, omega.invert().unwrap());
+        let n_inv = E::Scalar::from(num_gens as u64).invert().unwrap();
+        points.par_iter_mut().for_each(|p| *p *= n_inv);
+        E::GE::batch_affine(&points)
+      }
+    };
+
+    KeyFileHeader { basis, num_gens }.write_to(writer)?;
+    for point in &points {
+      writer.write_all(point.to_bytes().as_ref())?;
+    }
+    writer.write_all(self.tau_H.to_bytes().as_ref())?;
+    Ok(())
+  }
+
+  /// Returns whether `reader` is at the start of a compressed key file, without consuming it
+  fn is_compressed_key_file(
+    reader: &mut (impl std::io::Read + std::io::Seek),
+  ) -> Result<bool, PtauFileError> {
+    let start = reader.stream_position()?;
+    let mut magic = [0u8; 4];
+    let read = reader.read_exact(&mut magic);
+    reader.seek(std::io::SeekFrom::Start(start))?;
+    Ok(read.is_ok() && &magic == COMPRESSED_KEY_FILE_MAGIC)
+  }
+
+  /// Loads the first `num` powers of tau from a compressed key file
+  fn load_compressed(
+    reader: &mut (impl std::io::Read + std::io::Seek),
+    label: &'static [u8],
+    num: usize,
+  ) -> Result<Self, PtauFileError> {
+    let header = KeyFileHeader::read_from(reader)?;
+    if header.num_gens < num {
+      return Err(PtauFileError::InsufficientGenerators {
+        count: header.num_gens,
+        required: num,
+      });
+    }
+
+    // all the points of a Lagrange basis are needed to recover any power of tau
+    let num_read = match header.basis {
+      Basis::Coefficient => num,
+      Basis::Evaluation => header.num_gens,
+    };
+    let mut reprs = vec![<G1Affine<E> as GroupEncoding>::Repr::default(); num_read];
+    for repr in reprs.iter_mut() {
+      reader.read_exact(repr.as_mut())?;
+    }
+    let repr_len = reprs.first().map_or(0, |repr| repr.as_ref().len());
+    reader.seek(std::io::SeekFrom::Current(
+      ((header.num_gens - num_read) * repr_len) as i64,
+    ))?;
+    let points = reprs
+      .par_iter()
+      .map(|repr| Option::from(G1Affine::<E>::from_bytes(repr)).ok_or(PtauFileError::InvalidPoint))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let ck = match header.basis {
+      Basis::Coefficient => points,
+      Basis::Evaluation => {
+        // tau^i = sum_j omega^{ij} L_j(tau)
+        let omega = domain_generator::<E::Scalar>(header.num_gens)
+          .map_err(|_| PtauFileError::InvalidLagrangeDomain(header.num_gens))?;
+        let mut points = points.par_iter().map(E::GE::group).collect::<Vec<_>>();
+        fft(&mut points, omega);
+        points.truncate(num);
+        E::GE::batch_affine(&points)
+      }
+    };
+
+    let mut repr = <G2Affine<E> as GroupEncoding>::Repr::default();
+    reader.read_exact(repr.as_mut())?;
+    let tau_H =
+      Option::from(G2Affine::<E>::from_bytes(&repr)).ok_or(PtauFileError::InvalidPoint)?;
+
+    let h = *E::GE::from_label(label, 1).first().unwrap();
+
+    Ok(Self::new(ck, h, tau_H))
   }
 }
 
@@ -575,6 +946,27 @@ where
   tau_H: G2Affine<E>,
 }
 
+impl<E: Engine> CalldataEncoding for VerifierKey<E>
+where
+  E::GE: PairingGroup,
+  G1Affine<E>: CalldataEncoding,
+  G2Affine<E>: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.G.encode(enc);
+    self.H.encode(enc);
+    self.tau_H.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(VerifierKey {
+      G: CalldataEncoding::decode(dec)?,
+      H: CalldataEncoding::decode(dec)?,
+      tau_H: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
+
 /// Provides an implementation of a polynomial evaluation argument
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
@@ -609,6 +1001,27 @@ where
   }
 }
 
+impl<E: Engine> CalldataEncoding for EvaluationArgument<E>
+where
+  E::GE: PairingGroup,
+  E::Scalar: CalldataEncoding,
+  G1Affine<E>: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.com.encode(enc);
+    self.w.encode(enc);
+    self.v.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      com: CalldataEncoding::decode(dec)?,
+      w: CalldataEncoding::decode(dec)?,
+      v: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
+
 /// Provides an implementation of a polynomial evaluation engine using KZG
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EvaluationEngine<E: Engine> {
@@ -803,15 +1216,22 @@ where
     // Phase 1  -- create commitments com_1, ..., com_\ell
     // We do not compute final Pi (and its commitment) as it is constant and equals to 'eval'
     // also known to verifier, so can be derived on its side as well
+    // The trailing zeros of hat_P, e.g., the padding of a witness, fold into trailing zeros of
+    // every Pi, so we store each Pi only up to its last nonzero entry: this shrinks the foldings,
+    // the commitments, and the openings below without changing the polynomials they represent
     let mut polys: Vec<Vec<E::Scalar>> = Vec::new();
-    polys.push(hat_P.to_vec());
+    polys.push(hat_P[..trimmed_len(hat_P, is_zero::<E::Scalar>)].to_vec());
     for i in 0..ell - 1 {
-      let Pi_len = polys[i].len() / 2;
+      let P = &polys[i];
+      let Pi_len = P.len().div_ceil(2);
       let mut Pi = vec![E::Scalar::ZERO; Pi_len];
 
-      #[allow(clippy::needless_range_loop)]
       Pi.par_iter_mut().enumerate().for_each(|(j, Pi_j)| {
-        *Pi_j = x[ell - i - 1] * (polys[i][2 * j + 1] - polys[i][2 * j]) + polys[i][2 * j];
+        let (lo, hi) = (
+          P[2 * j],
+          P.get(2 * j + 1).copied().unwrap_or(E::Scalar::ZERO),
+        );
+        *Pi_j = x[ell - i - 1] * (hi - lo) + lo;
       });
 
       polys.push(Pi);
@@ -820,10 +1240,11 @@ where
     // We do not need to commit to the first polynomial as it is already committed.
     // Compute commitments in parallel
     let r = vec![E::Scalar::ZERO; ell - 1];
-    let com: Vec<G1Affine<E>> = E::CE::batch_commit(ck, &polys[1..], r.as_slice())
-      .par_iter()
-      .map(|i| i.comm.affine())
-      .collect();
+    let com = E::CE::batch_commit(ck, &polys[1..], r.as_slice())
+      .iter()
+      .map(|c| c.comm)
+      .collect::<Vec<_>>();
+    let com: Vec<G1Affine<E>> = E::GE::batch_affine(&com);
 
     // Phase 2
     // We do not need to add x to the transcript, because in our context x was obtained from the transcript.
@@ -953,9 +1374,12 @@ where
     let R = R0 + R1 * d_0 + R2 * d_1;
 
     // Check that e(L, vk.H) == e(R, vk.tau_H)
-    if (E::GE::pairing(&L, &DlogGroup::group(&vk.H)))
-      != (E::GE::pairing(&R, &DlogGroup::group(&vk.tau_H)))
-    {
+    if !E::GE::pairing_eq(
+      &L,
+      &DlogGroup::group(&vk.H),
+      &R,
+      &DlogGroup::group(&vk.tau_H),
+    ) {
       return Err(NovaError::ProofVerifyError {
         reason: "Pairing check failed".to_string(),
       });
@@ -969,7 +1393,7 @@ where
 mod tests {
   use std::{
     fs::OpenOptions,
-    io::{BufReader, BufWriter},
+    io::{BufReader, BufWriter, Cursor},
   };
 
   use super::*;
@@ -1174,6 +1598,152 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_derandomize_batch() {
+    let mut rng = rand::thread_rng();
+    let ck = CommitmentEngine::<E>::setup(b"test", 8);
+    let dk = CommitmentEngine::<E>::derand_key(&ck);
+
+    // batches on both sides of the threshold of the comb method
+    for n in [3, 100] {
+      let v: Vec<Vec<<E as Engine>::Scalar>> = (0..n)
+        .map(|_| (0..8).map(|_| Field::random(&mut rng)).collect())
+        .collect();
+      let r: Vec<<E as Engine>::Scalar> = (0..n).map(|_| Field::random(&mut rng)).collect();
+      let zero = vec![<E as Engine>::Scalar::ZERO; n];
+      let blinded = CommitmentEngine::<E>::batch_commit(&ck, &v, &r);
+      let unblinded = CommitmentEngine::<E>::batch_commit(&ck, &v, &zero);
+
+      assert_eq!(
+        CommitmentEngine::<E>::derandomize_batch(&dk, &blinded, &r),
+        unblinded
+      );
+      assert_eq!(
+        CommitmentEngine::<E>::rerandomize_batch(&dk, &unblinded, &r),
+        blinded
+      );
+    }
+  }
+
+  #[test]
+  fn test_trailing_zeros() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let ell = 5;
+
+    // the key is shorter than the polynomial, but not than its nonzero entries
+    let ck: CommitmentKey<E> = CommitmentEngine::setup(b"test", 16);
+    let (pk, vk): (ProverKey<E>, VerifierKey<E>) = EvaluationEngine::setup(&ck);
+
+    for len in [0, 1, 13, 16] {
+      let mut poly = (0..len).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+      let trimmed = poly.clone();
+      poly.resize(1 << ell, Fr::ZERO);
+
+      let C = CommitmentEngine::commit(&ck, &poly, &Fr::ZERO);
+      assert_eq!(C, CommitmentEngine::commit(&ck, &trimmed, &Fr::ZERO));
+      assert_eq!(
+        CommitmentEngine::batch_commit(&ck, &[poly.clone(), trimmed], &[Fr::ZERO; 2]),
+        vec![C; 2]
+      );
+
+      let point = (0..ell).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+      let eval = MultilinearPolynomial::evaluate_with(&poly, &point);
+      let mut tr = Keccak256Transcript::new(b"TestEval");
+      let proof = EvaluationEngine::prove(&ck, &pk, &mut tr, &C, &poly, &point, &eval).unwrap();
+      let mut tr = Keccak256Transcript::new(b"TestEval");
+      assert!(EvaluationEngine::verify(&vk, &mut tr, &C, &point, &eval, &proof).is_ok());
+    }
+
+    let small = [3u64, 0, 7, 0, 0, 0];
+    let r = Fr::random(&mut rng);
+    assert_eq!(
+      CommitmentEngine::<E>::commit_small(&ck, &small, &r),
+      CommitmentEngine::<E>::commit_small(&ck, &small[..3], &r)
+    );
+    assert_eq!(
+      CommitmentEngine::<E>::batch_commit_small(&ck, &[small.to_vec(), vec![0; 20]], &[r; 2]),
+      vec![
+        CommitmentEngine::<E>::commit_small(&ck, &small[..3], &r),
+        CommitmentEngine::<E>::commit_small(&ck, &[0u64], &r)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_commit_sparse() {
+    let ck = CommitmentEngine::<E>::setup(b"test", 64);
+    let r = Fr::random(OsRng);
+    let indices = [63, 5, 0];
+    let values = [Fr::random(OsRng), Fr::ZERO, Fr::random(OsRng)];
+    let mut v = vec![Fr::ZERO; 64];
+    for (i, value) in indices.iter().zip(&values) {
+      v[*i] = *value;
+    }
+    assert_eq!(
+      CommitmentEngine::<E>::commit_sparse(&ck, &indices, &values, &r),
+      CommitmentEngine::<E>::commit(&ck, &v, &r)
+    );
+  }
+
+  #[test]
+  fn test_commit_in_basis() {
+    let ck = CommitmentEngine::<E>::setup(b"test", 64);
+    let r = Fr::random(OsRng);
+    let coeffs = (0..64).map(|_| Fr::random(OsRng)).collect::<Vec<_>>();
+
+    // the evaluations are those of the polynomial over the domain, and convert back
+    let evals = coeffs_to_evals(&coeffs).unwrap();
+    let omega = domain_generator::<Fr>(64).unwrap();
+    let x = omega.pow_vartime([5u64]);
+    assert_eq!(
+      evals[5],
+      coeffs.iter().rev().fold(Fr::ZERO, |acc, c| acc * x + c)
+    );
+    assert_eq!(evals_to_coeffs(&evals).unwrap(), coeffs);
+
+    // both representations have the same commitment
+    let comm = CommitmentEngine::<E>::commit(&ck, &coeffs, &r);
+    assert_eq!(
+      CommitmentEngine::<E>::commit_in_basis(&ck, &coeffs, Basis::Coefficient, &r).unwrap(),
+      comm
+    );
+    assert_eq!(
+      CommitmentEngine::<E>::commit_in_basis(&ck, &evals, Basis::Evaluation, &r).unwrap(),
+      comm
+    );
+
+    // evaluations are only defined over domains of power-of-two size
+    assert_eq!(
+      CommitmentEngine::<E>::commit_in_basis(&ck, &evals[..63], Basis::Evaluation, &r),
+      Err(NovaError::InvalidInputLength)
+    );
+  }
+
+  #[test]
+  fn test_basis_round_trip_small() {
+    let ck = CommitmentEngine::<E>::setup(b"test", 2);
+    let r = Fr::random(OsRng);
+
+    // a constant polynomial evaluates to its coefficient
+    let coeffs = [Fr::random(OsRng)];
+    assert_eq!(coeffs_to_evals(&coeffs).unwrap(), coeffs);
+    assert_eq!(evals_to_coeffs(&coeffs).unwrap(), coeffs);
+    assert_eq!(
+      CommitmentEngine::<E>::commit_in_basis(&ck, &coeffs, Basis::Evaluation, &r).unwrap(),
+      CommitmentEngine::<E>::commit(&ck, &coeffs, &r)
+    );
+
+    // a linear polynomial is evaluated at 1 and -1
+    let coeffs = [Fr::random(OsRng), Fr::random(OsRng)];
+    let evals = coeffs_to_evals(&coeffs).unwrap();
+    assert_eq!(evals, [coeffs[0] + coeffs[1], coeffs[0] - coeffs[1]]);
+    assert_eq!(evals_to_coeffs(&evals).unwrap(), coeffs);
+    assert_eq!(
+      CommitmentEngine::<E>::commit_in_basis(&ck, &evals, Basis::Evaluation, &r).unwrap(),
+      CommitmentEngine::<E>::commit(&ck, &coeffs, &r)
+    );
+  }
+
   #[test]
   fn test_key_gen() {
     let n = 100;
@@ -1226,6 +1796,114 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_save_load_compressed_ck() {
+    let ck: CommitmentKey<E> = CommitmentEngine::setup(b"test", 64);
+    let mut ptau = Cursor::new(Vec::new());
+    ck.save_to(&mut ptau).unwrap();
+
+    for basis in [Basis::Coefficient, Basis::Evaluation] {
+      let mut writer = Cursor::new(Vec::new());
+      ck.save_compressed_to(&mut writer, basis).unwrap();
+      let bytes = writer.into_inner();
+      assert!(2 * bytes.len() < ptau.get_ref().len() + 64);
+
+      let header = KeyFileHeader::read_from(&mut &bytes[..]).unwrap();
+      assert_eq!(
+        header,
+        KeyFileHeader {
+          basis,
+          num_gens: 64
+        }
+      );
+
+      // keys of any supported size are recovered in the monomial basis
+      for n in [64, 16] {
+        let read_ck =
+          CommitmentEngine::<E>::load_setup(&mut Cursor::new(&bytes), b"test", n).unwrap();
+        assert_eq!(read_ck.ck, ck.ck[..n]);
+        assert_eq!(read_ck.h, ck.h);
+        assert_eq!(read_ck.tau_H, ck.tau_H);
+      }
+      assert!(matches!(
+        CommitmentEngine::<E>::load_setup(&mut Cursor::new(&bytes), b"test", 128),
+        Err(PtauFileError::InsufficientGenerators {
+          count: 64,
+          required: 128
+        })
+      ));
+    }
+
+    // the Lagrange basis commits to evaluations as the monomial basis commits to coefficients
+    let mut writer = Cursor::new(Vec::new());
+    ck.save_compressed_to(&mut writer, Basis::Evaluation)
+      .unwrap();
+    let bytes = writer.into_inner();
+    let mut reader = &bytes[..];
+    KeyFileHeader::read_from(&mut reader).unwrap();
+    let lagrange = (0..64)
+      .map(|_| {
+        let mut repr = <G1Affine<E> as GroupEncoding>::Repr::default();
+        std::io::Read::read_exact(&mut reader, repr.as_mut()).unwrap();
+        G1Affine::<E>::from_bytes(&repr).unwrap()
+      })
+      .collect::<Vec<_>>();
+    let coeffs = (0..64).map(|_| Fr::random(OsRng)).collect::<Vec<_>>();
+    let evals = coeffs_to_evals(&coeffs).unwrap();
+    assert_eq!(
+      <E as Engine>::GE::vartime_multiscalar_mul(&evals, &lagrange),
+      <E as Engine>::GE::vartime_multiscalar_mul(&coeffs, &ck.ck)
+    );
+
+    // Lagrange bases are only defined over domains of power-of-two size
+    let odd_ck = CommitmentKey::<E>::new(ck.ck[..63].to_vec(), ck.h, ck.tau_H);
+    assert!(matches!(
+      odd_ck.save_compressed_to(&mut Vec::new(), Basis::Evaluation),
+      Err(PtauFileError::InvalidLagrangeDomain(63))
+    ));
+  }
+
+  #[test]
+  fn test_validate_ck() {
+    let ck: CommitmentKey<E> = CommitmentEngine::setup(b"test", 16);
+
+    let mut writer = Cursor::new(Vec::new());
+    ck.save_to(&mut writer).unwrap();
+    let mut reader = Cursor::new(writer.into_inner());
+    for validation in [
+      SetupValidation::Trusted,
+      SetupValidation::SpotCheck(4),
+      SetupValidation::Strict,
+    ] {
+      reader.set_position(0);
+      let read_ck =
+        CommitmentKey::<E>::load_setup_validated(&mut reader, b"test", 16, validation).unwrap();
+      assert_eq!(ck.ck, read_ck.ck);
+    }
+
+    // a power of tau that is out of place
+    let mut powers = ck.ck.clone();
+    powers.swap(5, 6);
+    let bad_ck = CommitmentKey::<E>::new(powers, ck.h, ck.tau_H);
+    assert!(bad_ck.validate(SetupValidation::Trusted).is_ok());
+    assert!(matches!(
+      bad_ck.validate(SetupValidation::Strict),
+      Err(PtauFileError::InconsistentPowers)
+    ));
+
+    // a point that is not on the curve
+    let mut powers = ck.ck.clone();
+    let mut bytes = [0u8; 64];
+    bytes[0] = 1;
+    bytes[32] = 1;
+    powers[3] = halo2curves::serde::SerdeObject::read_raw(&mut &bytes[..]).unwrap();
+    let bad_ck = CommitmentKey::<E>::new(powers, ck.h, ck.tau_H);
+    assert!(matches!(
+      bad_ck.validate(SetupValidation::SpotCheck(1)),
+      Err(PtauFileError::PointNotInSubgroup)
+    ));
+  }
+
   #[ignore = "only available with external ptau files"]
   #[test]
   fn test_load_ptau() {
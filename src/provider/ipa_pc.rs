@@ -1,11 +1,14 @@
 //! This module implements `EvaluationEngine` using an IPA-based polynomial commitment scheme
 use crate::{
   errors::NovaError,
+  field::batch_inverse,
   provider::{pedersen::CommitmentKeyExtTrait, traits::DlogGroup},
   spartan::polys::eq::EqPolynomial,
   traits::{
-    commitment::CommitmentEngineTrait, evaluation::EvaluationEngineTrait, Engine,
-    TranscriptEngineTrait, TranscriptReprTrait,
+    calldata::{CalldataDecoder, CalldataEncoder, CalldataEncoding},
+    commitment::{CommitmentEngineTrait, Len},
+    evaluation::EvaluationEngineTrait,
+    Engine, TranscriptEngineTrait, TranscriptReprTrait,
   },
   Commitment, CommitmentKey, CE,
 };
@@ -15,6 +18,12 @@ use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
+/// Returns the window of the tables that `EvaluationEngine::preprocess` precomputes over `n`
+/// generators when its key has no window set with `VerifierKey::with_table_window`
+fn preprocess_table_window(n: usize) -> usize {
+  (n.max(1).ilog2() as usize).clamp(4, 16)
+}
+
 /// Provides an implementation of the prover key
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
@@ -28,6 +37,30 @@ pub struct ProverKey<E: Engine> {
 pub struct VerifierKey<E: Engine> {
   ck_v: CommitmentKey<E>,
   ck_s: CommitmentKey<E>,
+  // the window of the tables precomputed over `ck_v` on the first verification, or 0 if none are
+  #[serde(skip)]
+  table_window: usize,
+}
+
+impl<E: Engine> VerifierKey<E> {
+  /// Makes the key precompute tables of multiples of its generators, with windows of `window`
+  /// bits, the first time it verifies an argument, which speeds up the multiexponentation that
+  /// dominates every subsequent verification with the key.
+  ///
+  /// Larger windows store fewer group elements per generator, `ceil(NUM_BITS / window)`, but each
+  /// verification then accumulates `2^window` buckets, so windows around 16 suit keys with about
+  /// `2^16` to `2^20` generators. Windows are capped at 24 bits, and a window of 0, the default,
+  /// disables precomputation. The window is not serialized, and a key keeps the tables it
+  /// computed first.
+  pub fn with_table_window(mut self, window: usize) -> Self {
+    self.table_window = window;
+    self
+  }
+
+  /// Returns the window set with `with_table_window`
+  pub fn table_window(&self) -> usize {
+    self.table_window
+  }
 }
 
 /// Provides an implementation of a polynomial evaluation engine using IPA
@@ -55,11 +88,20 @@ where
     let vk = VerifierKey {
       ck_v: ck.clone(),
       ck_s: ck_c,
+      table_window: 0,
     };
 
     (pk, vk)
   }
 
+  fn preprocess(vk: &Self::VerifierKey) {
+    let window = match vk.table_window {
+      0 => preprocess_table_window(vk.ck_v.length()),
+      window => window,
+    };
+    vk.ck_v.precompute_tables(window);
+  }
+
   fn prove(
     ck: &CommitmentKey<E>,
     pk: &Self::ProverKey,
@@ -86,6 +128,10 @@ where
   ) -> Result<(), NovaError> {
     let u = InnerProductInstance::new(comm, &EqPolynomial::new(point.to_vec()).evals(), eval);
 
+    if vk.table_window > 0 {
+      vk.ck_v.precompute_tables(vk.table_window);
+    }
+
     arg.verify(
       &vk.ck_v,
       &vk.ck_s,
@@ -160,6 +206,26 @@ pub struct InnerProductArgument<E: Engine> {
   a_hat: E::Scalar,
 }
 
+impl<E: Engine> CalldataEncoding for InnerProductArgument<E>
+where
+  E::Scalar: CalldataEncoding,
+  Commitment<E>: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.L_vec.encode(enc);
+    self.R_vec.encode(enc);
+    self.a_hat.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      L_vec: CalldataEncoding::decode(dec)?,
+      R_vec: CalldataEncoding::decode(dec)?,
+      a_hat: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
+
 impl<E> InnerProductArgument<E>
 where
   E: Engine,
@@ -290,10 +356,9 @@ where
     U: &InnerProductInstance<E>,
     transcript: &mut E::TE,
   ) -> Result<(), NovaError> {
-    let (ck, _) = ck.split_at(U.b_vec.len());
-
     transcript.dom_sep(Self::protocol_name());
     if U.b_vec.len() != n
+      || ck.length() < n
       || n != (1 << self.L_vec.len())
       || self.L_vec.len() != self.R_vec.len()
       || self.L_vec.len() >= 32
@@ -310,32 +375,6 @@ where
 
     let P = U.comm_a_vec + CE::<E>::commit(&ck_c, &[U.c], &E::Scalar::ZERO);
 
-    let batch_invert = |v: &[E::Scalar]| -> Result<Vec<E::Scalar>, NovaError> {
-      let mut products = vec![E::Scalar::ZERO; v.len()];
-      let mut acc = E::Scalar::ONE;
-
-      for i in 0..v.len() {
-        products[i] = acc;
-        acc *= v[i];
-      }
-
-      // return error if acc is zero
-      acc = match Option::from(acc.invert()) {
-        Some(inv) => inv,
-        None => return Err(NovaError::InternalError),
-      };
-
-      // compute the inverse once for all entries
-      let mut inv = vec![E::Scalar::ZERO; v.len()];
-      for i in (0..v.len()).rev() {
-        let tmp = acc * v[i];
-        inv[i] = products[i] * acc;
-        acc = tmp;
-      }
-
-      Ok(inv)
-    };
-
     // compute a vector of public coins using self.L_vec and self.R_vec
     let r = (0..self.L_vec.len())
       .map(|i| {
@@ -350,7 +389,7 @@ where
       .into_par_iter()
       .map(|i| r[i] * r[i])
       .collect();
-    let r_inverse = batch_invert(&r)?;
+    let r_inverse = batch_inverse(&r)?;
     let r_inverse_square: Vec<E::Scalar> = (0..self.L_vec.len())
       .into_par_iter()
       .map(|i| r_inverse[i] * r_inverse[i])
@@ -374,7 +413,8 @@ where
     };
 
     let ck_hat = {
-      let c = CE::<E>::commit(&ck, &s, &E::Scalar::ZERO);
+      // the generators are not split off, so the commitment can use tables precomputed over them
+      let c = CE::<E>::commit_at(ck, &s, 0);
       CommitmentKey::<E>::reinterpret_commitments_as_ck(&[c])?
     };
 
@@ -413,3 +453,42 @@ where
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{provider::PallasEngine, spartan::polys::multilinear::MultilinearPolynomial};
+  use rand::rngs::OsRng;
+
+  type E = PallasEngine;
+  type Fr = <E as Engine>::Scalar;
+
+  #[test]
+  fn test_verifier_tables() {
+    let num_vars = 6;
+    let ck: CommitmentKey<E> = <E as Engine>::CE::setup(b"test", 1 << num_vars);
+    let (pk, vk) = EvaluationEngine::<E>::setup(&ck);
+
+    let poly = (0..1 << num_vars)
+      .map(|_| Fr::random(OsRng))
+      .collect::<Vec<_>>();
+    let point = (0..num_vars).map(|_| Fr::random(OsRng)).collect::<Vec<_>>();
+    let eval = MultilinearPolynomial::evaluate_with(&poly, &point);
+    let comm = <E as Engine>::CE::commit(&ck, &poly, &Fr::ZERO);
+
+    let mut transcript = <E as Engine>::TE::new(b"test");
+    let arg =
+      EvaluationEngine::<E>::prove(&ck, &pk, &mut transcript, &comm, &poly, &point, &eval).unwrap();
+    let verify = |vk: &VerifierKey<E>, eval: &Fr| {
+      let mut transcript = <E as Engine>::TE::new(b"test");
+      EvaluationEngine::<E>::verify(vk, &mut transcript, &comm, &point, eval, &arg)
+    };
+
+    assert!(verify(&vk, &eval).is_ok());
+
+    // a verifier key with tables accepts and rejects the same arguments
+    let vk = vk.with_table_window(8);
+    assert!(verify(&vk, &eval).is_ok());
+    assert_eq!(verify(&vk, &(eval + Fr::ONE)), Err(NovaError::InvalidPCS));
+  }
+}
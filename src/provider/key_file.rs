@@ -0,0 +1,111 @@
+//! This module names the files that commitment keys are cached in after the stable identifiers of
+//! their engines (see `Engine::ID`), so that caches remain valid across builds, and checks on load
+//! that a key file was produced for the engine loading it.
+//!
+//! A key file for `n` generators of the engine `E` is named `{E::ID}_{n}.keys`.
+use crate::{errors::NovaError, traits::Engine};
+use std::path::{Path, PathBuf};
+
+/// The extension of key files
+pub const KEY_FILE_EXTENSION: &str = "keys";
+
+/// Returns the name of the key file holding `num_gens` generators of the engine `E`
+pub fn key_file_name<E: Engine>(num_gens: usize) -> String {
+  format!("{}_{num_gens}.{KEY_FILE_EXTENSION}", E::ID)
+}
+
+/// Returns the path of the key file holding `num_gens` generators of the engine `E` in `dir`
+pub fn key_file_path<E: Engine>(dir: impl AsRef<Path>, num_gens: usize) -> PathBuf {
+  dir.as_ref().join(key_file_name::<E>(num_gens))
+}
+
+/// Parses the name of a key file into the identifier of its engine and its number of generators,
+/// returning `None` if the name does not follow the convention of `key_file_name`
+pub fn parse_key_file_name(name: &str) -> Option<(&str, usize)> {
+  let stem = name.strip_suffix(KEY_FILE_EXTENSION)?.strip_suffix('.')?;
+  let (id, num_gens) = stem.rsplit_once('_')?;
+  Some((id, num_gens.parse().ok()?))
+}
+
+/// Checks that the key file at `path`, if named by `key_file_name`, was produced for the engine
+/// `E` and holds at least `num_gens` generators. Files named otherwise are not checked.
+pub fn check_key_file<E: Engine>(path: &Path, num_gens: usize) -> Result<(), NovaError> {
+  let Some((id, file_num_gens)) = path
+    .file_name()
+    .and_then(|name| name.to_str())
+    .and_then(parse_key_file_name)
+  else {
+    return Ok(());
+  };
+
+  if id != E::ID {
+    return Err(NovaError::SetupError {
+      reason: format!(
+        "the key file {} is for the engine {id}, not {}",
+        path.display(),
+        E::ID
+      ),
+    });
+  }
+  if file_num_gens < num_gens {
+    return Err(NovaError::SetupError {
+      reason: format!(
+        "the key file {} holds {file_num_gens} generators, but {num_gens} are required",
+        path.display()
+      ),
+    });
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::provider::{
+    Bn256EngineIPA, Bn256EngineKZG, GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine,
+    VestaEngine,
+  };
+
+  #[test]
+  fn test_engine_ids() {
+    let ids = [
+      Bn256EngineKZG::ID,
+      Bn256EngineIPA::ID,
+      GrumpkinEngine::ID,
+      Secp256k1Engine::ID,
+      Secq256k1Engine::ID,
+      PallasEngine::ID,
+      VestaEngine::ID,
+    ];
+    for (i, id) in ids.iter().enumerate() {
+      assert!(id
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'));
+      assert!(id
+        .rsplit_once("-v")
+        .is_some_and(|(_, version)| version.parse::<u32>().is_ok()));
+      // no two engines share key files
+      assert!(!ids[..i].contains(id));
+    }
+  }
+
+  #[test]
+  fn test_key_file_name() {
+    let name = key_file_name::<Bn256EngineKZG>(1 << 10);
+    assert_eq!(name, "bn256-hyperkzg-v1_1024.keys");
+    assert_eq!(
+      parse_key_file_name(&name),
+      Some((Bn256EngineKZG::ID, 1 << 10))
+    );
+    assert_eq!(parse_key_file_name("ck.keys"), None);
+    assert_eq!(parse_key_file_name("bn256-hyperkzg-v1_1024.bin"), None);
+
+    let path = key_file_path::<Bn256EngineKZG>("/tmp", 1 << 10);
+    assert!(check_key_file::<Bn256EngineKZG>(&path, 1 << 10).is_ok());
+    assert!(check_key_file::<Bn256EngineKZG>(&path, 1 << 11).is_err());
+    // a key file of another engine over the same curve is rejected
+    assert!(check_key_file::<Bn256EngineIPA>(&path, 1 << 10).is_err());
+    // files named otherwise are not checked
+    assert!(check_key_file::<Bn256EngineIPA>(Path::new("/tmp/ck.keys"), 1 << 10).is_ok());
+  }
+}
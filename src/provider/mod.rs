@@ -1,16 +1,24 @@
 //! This module implements Nova's traits using the following several different combinations
 
 // public modules to be used as an evaluation engine with Spartan
+#[cfg(feature = "ark-compat")]
+pub mod ark;
+#[cfg(feature = "blitzar")]
+pub mod blitzar;
 pub mod bn256_grumpkin;
+#[cfg(feature = "experimental")]
+pub mod fri;
 pub mod hyperkzg;
 pub mod ipa_pc;
+pub mod key_file;
 pub mod pasta;
 pub mod poseidon;
+pub mod recorder;
 pub mod secp_secq;
+pub mod timing;
+pub mod util;
 
 // crate-private modules
-#[cfg(feature = "blitzar")]
-pub(crate) mod blitzar;
 pub(crate) mod keccak;
 pub(crate) mod pedersen;
 pub(crate) mod ptau;
@@ -28,10 +36,13 @@ use crate::{
     poseidon::{PoseidonRO, PoseidonROCircuit},
     secp_secq::{secp256k1, secq256k1},
   },
-  traits::Engine,
+  traits::{Capabilities, Engine},
 };
+pub use msm::{msm_backend, set_msm_backend, MsmBackend};
+pub use pedersen::CEREMONY_LABEL;
 pub use ptau::{check_sanity_of_ptau_file, read_ptau, write_ptau};
 use serde::{Deserialize, Serialize};
+pub use traits::{GeneratorSpec, LabelHash};
 
 /// An implementation of Nova traits with HyperKZG over the BN256 curve
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -46,6 +57,8 @@ pub struct GrumpkinEngine;
 pub struct Bn256EngineIPA;
 
 impl Engine for Bn256EngineKZG {
+  const ID: &'static str = "bn256-hyperkzg-v1";
+
   type Base = bn256::Base;
   type Scalar = bn256::Scalar;
   type GE = bn256::Point;
@@ -55,9 +68,15 @@ impl Engine for Bn256EngineKZG {
   type RO2Circuit = PoseidonROCircuit<Self::Scalar>;
   type TE = Keccak256Transcript<Self>;
   type CE = HyperKZGCommitmentEngine<Self>;
+
+  fn capabilities() -> Capabilities {
+    bn256_capabilities()
+  }
 }
 
 impl Engine for Bn256EngineIPA {
+  const ID: &'static str = "bn256-pedersen-v1";
+
   type Base = bn256::Base;
   type Scalar = bn256::Scalar;
   type GE = bn256::Point;
@@ -67,9 +86,15 @@ impl Engine for Bn256EngineIPA {
   type RO2Circuit = PoseidonROCircuit<Self::Scalar>;
   type TE = Keccak256Transcript<Self>;
   type CE = PedersenCommitmentEngine<Self>;
+
+  fn capabilities() -> Capabilities {
+    bn256_capabilities()
+  }
 }
 
 impl Engine for GrumpkinEngine {
+  const ID: &'static str = "grumpkin-pedersen-v1";
+
   type Base = grumpkin::Base;
   type Scalar = grumpkin::Scalar;
   type GE = grumpkin::Point;
@@ -81,6 +106,19 @@ impl Engine for GrumpkinEngine {
   type CE = PedersenCommitmentEngine<Self>;
 }
 
+/// The capabilities of engines over BN256, which admits a pairing and, with the `blitzar`
+/// feature, GPU MSMs
+fn bn256_capabilities() -> Capabilities {
+  Capabilities {
+    #[cfg(feature = "blitzar")]
+    gpu_msm: blitzar::device() == blitzar::Device::Gpu,
+    #[cfg(not(feature = "blitzar"))]
+    gpu_msm: false,
+    simd: Capabilities::cpu_simd(),
+    pairing: true,
+  }
+}
+
 /// An implementation of the Nova `Engine` trait with Secp256k1 curve and Pedersen commitment scheme
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Secp256k1Engine;
@@ -90,6 +128,8 @@ pub struct Secp256k1Engine;
 pub struct Secq256k1Engine;
 
 impl Engine for Secp256k1Engine {
+  const ID: &'static str = "secp256k1-pedersen-v1";
+
   type Base = secp256k1::Base;
   type Scalar = secp256k1::Scalar;
   type GE = secp256k1::Point;
@@ -102,6 +142,8 @@ impl Engine for Secp256k1Engine {
 }
 
 impl Engine for Secq256k1Engine {
+  const ID: &'static str = "secq256k1-pedersen-v1";
+
   type Base = secq256k1::Base;
   type Scalar = secq256k1::Scalar;
   type GE = secq256k1::Point;
@@ -122,6 +164,8 @@ pub struct PallasEngine;
 pub struct VestaEngine;
 
 impl Engine for PallasEngine {
+  const ID: &'static str = "pallas-pedersen-v1";
+
   type Base = pallas::Base;
   type Scalar = pallas::Scalar;
   type GE = pallas::Point;
@@ -134,6 +178,8 @@ impl Engine for PallasEngine {
 }
 
 impl Engine for VestaEngine {
+  const ID: &'static str = "vesta-pedersen-v1";
+
   type Base = vesta::Base;
   type Scalar = vesta::Scalar;
   type GE = vesta::Point;
@@ -198,4 +244,116 @@ mod tests {
   fn test_secp256k1_from_label() {
     impl_cycle_pair_test!(secp256k1);
   }
+
+  #[test]
+  fn test_batch_to_coordinates() {
+    let mut points = (1..10u64)
+      .map(|i| bn256::Point::gen() * bn256::Scalar::from(i))
+      .collect::<Vec<_>>();
+    points.push(bn256::Point::zero());
+    let coordinates = bn256::Point::batch_to_coordinates(&points);
+    assert_eq!(
+      coordinates,
+      points
+        .iter()
+        .map(|p| p.to_coordinates())
+        .collect::<Vec<_>>()
+    );
+    assert!(coordinates[9].2);
+  }
+
+  #[test]
+  fn test_bn256_g2_from_label() {
+    use crate::traits::TranscriptReprTrait;
+    use ff::Field;
+    use halo2curves::{bn256::G2, group::Group, CurveAffine};
+
+    let gens = <G2 as DlogGroup>::from_label(b"test_from_label", 8);
+    assert_eq!(gens, <G2 as DlogGroup>::from_label(b"test_from_label", 8));
+    assert_ne!(gens, <G2 as DlogGroup>::from_label(b"other_label", 8));
+    for gen in &gens {
+      assert!(bool::from(gen.is_on_curve()));
+      // generators are in the prime-order subgroup
+      let order = -bn256::Scalar::ONE;
+      assert_eq!(G2::from(*gen) * order + G2::from(*gen), G2::identity());
+    }
+
+    // the limbs of the coordinates determine the point
+    let (x, y, is_infinity) = G2::from(gens[0]).to_coordinate_limbs();
+    assert!(!is_infinity);
+    let coords = gens[0].coordinates().unwrap();
+    assert_eq!((x[0], x[1]), (*coords.x().c0(), *coords.x().c1()));
+    assert_eq!((y[0], y[1]), (*coords.y().c0(), *coords.y().c1()));
+    assert_eq!(G2::from(gens[0]).to_coordinates(), (x[0], y[0], false));
+    assert!(<G2 as DlogGroup>::zero().to_coordinate_limbs().2);
+
+    let bytes = <_ as TranscriptReprTrait<bn256::Point>>::to_transcript_bytes(&gens[0]);
+    assert_eq!(bytes.len(), 4 * 32);
+    assert_ne!(
+      bytes,
+      <_ as TranscriptReprTrait<bn256::Point>>::to_transcript_bytes(&gens[1])
+    );
+  }
+
+  #[test]
+  fn test_generator_spec() {
+    use crate::provider::{GeneratorSpec, LabelHash};
+    use sha3::{Digest, Keccak256};
+
+    let label = b"test_generator_spec";
+    let default = GeneratorSpec::default();
+    let keccak = GeneratorSpec {
+      domain: "other".to_string(),
+      hash: LabelHash::Keccak256,
+    };
+
+    let mut shake = Shake256::default();
+    shake.update(label);
+    let mut expected = [0u8; 64];
+    shake.finalize_xof().read_exact(&mut expected).unwrap();
+    assert_eq!(default.uniform_bytes(label, 2).concat(), expected);
+
+    let bytes = keccak.uniform_bytes(label, 3);
+    assert_eq!(
+      bytes[2],
+      <[u8; 32]>::from(
+        Keccak256::new_with_prefix(label)
+          .chain_update(2u64.to_le_bytes())
+          .finalize()
+      )
+    );
+    // expansions of different lengths agree on their common prefix
+    assert_eq!(keccak.uniform_bytes(label, 2), bytes[..2]);
+    assert_ne!(
+      GeneratorSpec {
+        hash: LabelHash::Sha3_256,
+        ..keccak
+      }
+      .uniform_bytes(label, 3),
+      bytes
+    );
+  }
+
+  #[test]
+  fn test_capabilities() {
+    use crate::{
+      provider::{Bn256EngineIPA, Bn256EngineKZG, GrumpkinEngine, PallasEngine},
+      traits::{Capabilities, Engine},
+    };
+
+    assert!(Bn256EngineKZG::capabilities().pairing);
+    assert!(Bn256EngineIPA::capabilities().pairing);
+    assert!(!GrumpkinEngine::capabilities().pairing);
+    assert!(!PallasEngine::capabilities().gpu_msm);
+    assert_eq!(PallasEngine::capabilities().simd, Capabilities::cpu_simd());
+    assert_eq!(
+      Capabilities {
+        gpu_msm: false,
+        simd: true,
+        pairing: true,
+      }
+      .to_string(),
+      "gpu msm: no, simd: yes, pairing: yes"
+    );
+  }
 }
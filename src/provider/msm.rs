@@ -1,11 +1,64 @@
 //! This module provides a multi-scalar multiplication routine
 //! The generic implementation is adapted from halo2; we add an optimization to commit to bits more efficiently
 //! The specialized implementations are adapted from jolt, with additional optimizations and parallelization.
+use super::util::num_chunks;
 use ff::{Field, PrimeField};
 use halo2curves::{group::Group, CurveAffine};
 use num_integer::Integer;
 use num_traits::{ToPrimitive, Zero};
-use rayon::{current_num_threads, prelude::*};
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// The implementation used for multi-scalar multiplications on curves with accelerator support.
+///
+/// Accelerated backends are only available for some curves (currently BN254, with the `blitzar`
+/// feature); other curves always use the CPU implementation regardless of this setting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MsmBackend {
+  /// The multi-threaded CPU implementation in this module
+  Cpu,
+  /// Blitzar's GPU implementation
+  #[cfg(feature = "blitzar")]
+  Blitzar,
+}
+
+impl MsmBackend {
+  const fn default_backend() -> Self {
+    #[cfg(feature = "blitzar")]
+    return MsmBackend::Blitzar;
+    #[cfg(not(feature = "blitzar"))]
+    return MsmBackend::Cpu;
+  }
+
+  const fn to_u8(self) -> u8 {
+    match self {
+      MsmBackend::Cpu => 0,
+      #[cfg(feature = "blitzar")]
+      MsmBackend::Blitzar => 1,
+    }
+  }
+
+  fn from_u8(v: u8) -> Self {
+    match v {
+      #[cfg(feature = "blitzar")]
+      1 => MsmBackend::Blitzar,
+      _ => MsmBackend::Cpu,
+    }
+  }
+}
+
+static MSM_BACKEND: AtomicU8 = AtomicU8::new(MsmBackend::default_backend().to_u8());
+
+/// Returns the backend currently used for multi-scalar multiplications
+pub fn msm_backend() -> MsmBackend {
+  MsmBackend::from_u8(MSM_BACKEND.load(Ordering::Relaxed))
+}
+
+/// Selects the backend used for subsequent multi-scalar multiplications, process-wide.
+/// By default, the GPU backend is used whenever the crate is built with support for it.
+pub fn set_msm_backend(backend: MsmBackend) {
+  MSM_BACKEND.store(backend.to_u8(), Ordering::Relaxed);
+}
 
 #[derive(Clone, Copy)]
 enum Bucket<C: CurveAffine> {
@@ -112,8 +165,9 @@ fn cpu_msm_serial<C: CurveAffine>(coeffs: &[C::Scalar], bases: &[C]) -> C::Curve
 /// Adapted from zcash/halo2
 pub fn msm<C: CurveAffine>(coeffs: &[C::Scalar], bases: &[C]) -> C::Curve {
   assert_eq!(coeffs.len(), bases.len());
+  let _timer = super::timing::start("msm", "cpu", coeffs.len());
 
-  let num_threads = current_num_threads();
+  let num_threads = num_chunks();
   if coeffs.len() > num_threads {
     let chunk = coeffs.len() / num_threads;
     coeffs
@@ -140,8 +194,9 @@ pub fn msm_small<C: CurveAffine, T: Integer + Into<u64> + Copy + Sync + ToPrimit
   bases: &[C],
 ) -> C::Curve {
   assert_eq!(bases.len(), scalars.len());
+  let _timer = super::timing::start("msm_small", "cpu", scalars.len());
 
-  let max_num_bits = num_bits(scalars.iter().max().unwrap().to_usize().unwrap());
+  let max_num_bits = num_bits(scalars.iter().max().map_or(0, |m| m.to_usize().unwrap()));
   match max_num_bits {
     0 => C::identity().into(),
     1 => msm_binary(scalars, bases),
@@ -152,13 +207,13 @@ pub fn msm_small<C: CurveAffine, T: Integer + Into<u64> + Copy + Sync + ToPrimit
 
 fn msm_binary<C: CurveAffine, T: Integer + Sync>(scalars: &[T], bases: &[C]) -> C::Curve {
   assert_eq!(scalars.len(), bases.len());
-  let num_threads = current_num_threads();
+  let num_threads = num_chunks();
   let process_chunk = |scalars: &[T], bases: &[C]| {
     let mut acc = C::Curve::identity();
     scalars
       .iter()
       .zip(bases.iter())
-      .filter(|(scalar, _)| (!scalar.is_zero()))
+      .filter(|(scalar, _)| !scalar.is_zero())
       .for_each(|(_, base)| {
         acc += *base;
       });
@@ -209,7 +264,7 @@ fn msm_10<C: CurveAffine, T: Into<u64> + Zero + Copy + Sync>(
     result
   }
 
-  let num_threads = current_num_threads();
+  let num_threads = num_chunks();
   if scalars.len() > num_threads {
     let chunk_size = scalars.len() / num_threads;
     scalars
@@ -319,7 +374,7 @@ fn msm_small_rest<C: CurveAffine, T: Into<u64> + Zero + Copy + Sync>(
         })
   }
 
-  let num_threads = current_num_threads();
+  let num_threads = num_chunks();
   if scalars.len() > num_threads {
     let chunk_size = scalars.len() / num_threads;
     scalars
@@ -413,4 +468,13 @@ mod tests {
     test_msm_ux_with::<secp256k1::Scalar, secp256k1::Affine>();
     test_msm_ux_with::<secq256k1::Scalar, secq256k1::Affine>();
   }
+
+  #[test]
+  fn test_msm_backend() {
+    let backend = msm_backend();
+    set_msm_backend(MsmBackend::Cpu);
+    assert_eq!(msm_backend(), MsmBackend::Cpu);
+    set_msm_backend(backend);
+    assert_eq!(msm_backend(), backend);
+  }
 }
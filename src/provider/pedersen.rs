@@ -1,28 +1,47 @@
 //! This module provides an implementation of a commitment engine
 use crate::{
+  encoding::encode_point,
   errors::NovaError,
   gadgets::utils::to_bignat_repr,
   provider::{
-    ptau::{read_points, write_points, PtauFileError},
-    traits::{DlogGroup, DlogGroupExt},
+    ptau::{read_points, PtauFileError},
+    traits::{
+      blinding_generator, DlogGroup, DlogGroupExt, GeneratorSpec, LabelHash, PreparedBasesCell,
+    },
+    util::fixed_base_mul_batch,
   },
   traits::{
-    commitment::{CommitmentEngineTrait, CommitmentTrait, Len},
+    calldata::{CalldataDecoder, CalldataEncoder, CalldataEncoding},
+    commitment::{
+      BlindingGenerator, CommitmentEngineTrait, CommitmentKeyAttestation, CommitmentTrait, Len,
+    },
     AbsorbInRO2Trait, AbsorbInROTrait, Engine, ROTrait, TranscriptReprTrait,
   },
 };
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use core::{
   fmt::Debug,
   marker::PhantomData,
   ops::{Add, Mul, MulAssign},
 };
-use ff::Field;
+use ff::{Field, PrimeField};
+use halo2curves::{group::GroupEncoding, serde::SerdeObject};
 use num_integer::Integer;
 use num_traits::ToPrimitive;
+use once_cell::sync::OnceCell;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
 
-const KEY_FILE_HEAD: [u8; 12] = *b"PEDERSEN_KEY";
+/// The magic string of key files
+const KEY_FILE_MAGIC: [u8; 8] = *b"NOVA_KEY";
+
+/// The version of the key file format
+const KEY_FILE_VERSION: u32 = 2;
+
+/// The magic string of key files written before the format had a header, which hold the
+/// uncompressed points right after it
+const LEGACY_KEY_FILE_HEAD: [u8; 12] = *b"PEDERSEN_KEY";
 
 /// A type that holds commitment generators
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -32,8 +51,194 @@ where
 {
   ck: Vec<<E::GE as DlogGroup>::AffineGroupElement>,
   h: <E::GE as DlogGroup>::AffineGroupElement,
+  /// The specification the generators were derived with, which key files record. It is not part of
+  /// the serialized key, so that digests of public parameters only cover the generators, and
+  /// deserialized keys have the default specification.
+  #[serde(skip)]
+  spec: GeneratorSpec,
+  #[serde(skip)]
+  prepared: PreparedBasesCell<E::GE>,
+  #[serde(skip)]
+  tables: FixedBaseTablesCell<E::GE>,
+}
+
+impl<E> CommitmentKey<E>
+where
+  E: Engine,
+  E::GE: DlogGroup,
+{
+  /// Derives a commitment key with `n` generators, rounded up to a power of two, from `label` with
+  /// the specification `spec`, like `CommitmentEngine::setup` does with the default one. The key
+  /// records `spec`, which key files it is saved to carry along.
+  pub fn from_label_with(spec: &GeneratorSpec, label: &[u8], n: usize) -> Self {
+    let gens = E::GE::from_label_with(spec, label, n.next_power_of_two() + 1);
+
+    let (h, ck) = gens.split_first().unwrap();
+
+    Self {
+      ck: ck.to_vec(),
+      h: *h,
+      spec: spec.clone(),
+      prepared: Default::default(),
+      tables: Default::default(),
+    }
+  }
+
+  /// Returns the specification the generators were derived with
+  pub fn spec(&self) -> &GeneratorSpec {
+    &self.spec
+  }
 }
 
+impl<E> CommitmentKey<E>
+where
+  E: Engine,
+  E::GE: DlogGroupExt,
+{
+  /// Computes a multiexponentation of `v` with `ck[offset..offset + v.len()]`, using the
+  /// precomputed tables if any
+  fn msm(&self, v: &[E::Scalar], offset: usize) -> E::GE {
+    match self.tables.0.get() {
+      Some(tables) => tables.msm(v, offset),
+      None => E::GE::vartime_multiscalar_mul_prepared(v, &self.ck, self.prepared.get(), offset),
+    }
+  }
+}
+
+impl<E> CommitmentKey<E>
+where
+  E: Engine<CE = CommitmentEngine<E>>,
+  E::GE: DlogGroupExt,
+{
+  /// Produces an attestation of the commitment key (see `CommitmentKeyAttestation`)
+  pub fn attest(&self) -> CommitmentKeyAttestation<E> {
+    CommitmentKeyAttestation::new(self)
+  }
+
+  /// Derives a commitment key with `n` generators, rounded up to a power of two, from `beacon`, the
+  /// transcript of a public ceremony or the value of a public randomness beacon, so that anyone
+  /// holding `beacon` can re-derive the key (see `verify_beacon`).
+  ///
+  /// The generators are derived as in `CommitmentEngine::setup`, from the label
+  /// `CEREMONY_LABEL || beacon`, with the default `GeneratorSpec`.
+  pub fn from_beacon(beacon: &[u8], n: usize) -> Self {
+    let label = [CEREMONY_LABEL, beacon].concat();
+    Self::from_label_with(&GeneratorSpec::default(), &label, n)
+  }
+
+  /// Checks that the commitment key, e.g., the secondary key of production public parameters, is
+  /// the key that `from_beacon` derives from `beacon`, generator by generator
+  pub fn verify_beacon(&self, beacon: &[u8]) -> Result<(), NovaError> {
+    if !self.ck.len().is_power_of_two() {
+      return Err(NovaError::InvalidCommitmentKeyLength);
+    }
+    let expected = Self::from_beacon(beacon, self.ck.len());
+    if expected.h == self.h && expected.ck == self.ck {
+      Ok(())
+    } else {
+      Err(NovaError::CommitmentKeyMismatch)
+    }
+  }
+}
+
+/// The label that prefixes beacons when deriving commitment keys from them (see
+/// `CommitmentKey::from_beacon`), which separates these keys from those derived from other labels
+pub const CEREMONY_LABEL: &[u8] = b"nova-ceremony-v1";
+
+/// Multiples `2^(window * j) * G` of generators `G`, which turn a multiexponentation with the
+/// generators into a multiexponentation with `window`-bit scalars, removing all doublings and all
+/// but one bucket accumulation from Pippenger's algorithm
+#[derive(Clone, Debug)]
+struct FixedBaseTables<G: DlogGroup> {
+  window: usize,
+  num_windows: usize,
+  points: Vec<G::AffineGroupElement>, // `num_windows` multiples of each generator in turn
+}
+
+impl<G: DlogGroupExt> FixedBaseTables<G> {
+  fn new(bases: &[G::AffineGroupElement], window: usize) -> Self {
+    let window = window.clamp(1, MAX_TABLE_WINDOW);
+    let num_windows = (G::Scalar::NUM_BITS as usize).div_ceil(window);
+    let points = bases
+      .par_iter()
+      .flat_map_iter(|base| {
+        let mut p = G::group(base);
+        (0..num_windows).map(move |_| {
+          let multiple = p;
+          for _ in 0..window {
+            p = p + p;
+          }
+          multiple
+        })
+      })
+      .collect::<Vec<_>>();
+    let points = G::batch_affine(&points);
+
+    Self {
+      window,
+      num_windows,
+      points,
+    }
+  }
+
+  fn msm(&self, v: &[G::Scalar], offset: usize) -> G {
+    let (window, num_windows) = (self.window, self.num_windows);
+    let digits = v
+      .par_iter()
+      .flat_map_iter(|s| {
+        let repr = s.to_repr();
+        let bytes = repr.as_ref().to_vec();
+        (0..num_windows).map(move |j| {
+          // a digit spans at most 4 bytes, since it starts within a byte and has at most 24 bits
+          let start = j * window;
+          let word = (0..4).fold(0u32, |word, k| {
+            word | (*bytes.get(start / 8 + k).unwrap_or(&0) as u32) << (8 * k)
+          });
+          (word >> (start % 8)) & ((1 << window) - 1)
+        })
+      })
+      .collect::<Vec<u32>>();
+
+    let start = offset * num_windows;
+    G::vartime_multiscalar_mul_small(&digits, &self.points[start..start + digits.len()])
+  }
+}
+
+/// The largest window of fixed-base tables
+const MAX_TABLE_WINDOW: usize = 24;
+
+/// Fixed-base tables precomputed over the generators of a commitment key. The tables are derived from
+/// the generators, so they are neither serialized nor taken into account when comparing keys.
+struct FixedBaseTablesCell<G: DlogGroup>(OnceCell<FixedBaseTables<G>>);
+
+impl<G: DlogGroup> Default for FixedBaseTablesCell<G> {
+  fn default() -> Self {
+    Self(OnceCell::new())
+  }
+}
+
+impl<G: DlogGroup> Clone for FixedBaseTablesCell<G> {
+  fn clone(&self) -> Self {
+    Self(self.0.clone())
+  }
+}
+
+impl<G: DlogGroup> Debug for FixedBaseTablesCell<G> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("FixedBaseTablesCell")
+      .field("window", &self.0.get().map(|tables| tables.window))
+      .finish()
+  }
+}
+
+impl<G: DlogGroup> PartialEq for FixedBaseTablesCell<G> {
+  fn eq(&self, _other: &Self) -> bool {
+    true
+  }
+}
+
+impl<G: DlogGroup> Eq for FixedBaseTablesCell<G> {}
+
 impl<E: Engine> Len for CommitmentKey<E>
 where
   E::GE: DlogGroup,
@@ -52,6 +257,16 @@ where
   h: <E::GE as DlogGroup>::AffineGroupElement,
 }
 
+impl<E: Engine> DerandKey<E>
+where
+  E::GE: DlogGroup,
+{
+  /// Returns the blinding generator
+  pub fn blinding_generator(&self) -> E::GE {
+    E::GE::group(&self.h)
+  }
+}
+
 /// A type that holds a commitment
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(bound = "")]
@@ -85,13 +300,7 @@ where
 {
   fn to_transcript_bytes(&self) -> Vec<u8> {
     let (x, y, is_infinity) = self.comm.to_coordinates();
-    let is_infinity_byte = (!is_infinity).into();
-    [
-      x.to_transcript_bytes(),
-      y.to_transcript_bytes(),
-      [is_infinity_byte].to_vec(),
-    ]
-    .concat()
+    encode_point(&x, &y, is_infinity)
   }
 }
 
@@ -182,22 +391,287 @@ where
   }
 }
 
+impl<E: Engine> CalldataEncoding for Commitment<E>
+where
+  E::GE: DlogGroup,
+  <E::GE as DlogGroup>::AffineGroupElement: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.comm.affine().encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Commitment {
+      comm: E::GE::group(&CalldataEncoding::decode(dec)?),
+    })
+  }
+}
+
 /// Provides a commitment engine
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CommitmentEngine<E: Engine> {
   _p: PhantomData<E>,
 }
 
+/// The header of a key file, which precedes the blinding generator and the generators, all encoded
+/// the same way. It is laid out as follows, with integers in little-endian order:
+/// * the magic string `NOVA_KEY`,
+/// * the version of the format, as a `u32`,
+/// * the identifier of the curve (see `KeyFileHeader::curve_id`), in 32 bytes,
+/// * the number of generators, excluding the blinding generator, as a `u64`,
+/// * whether points are compressed, as a byte that is either 0 or 1,
+/// * the SHA3-256 digest of the encoded points, in 32 bytes, and
+/// * since version 2, the specification of the generators (see `GeneratorSpec`): the hash
+///   function, as a byte, followed by the length of the domain, as a `u32`, and the domain.
+///
+/// Files of version 1 have the default specification.
+struct KeyFileHeader {
+  curve_id: [u8; 32],
+  count: u64,
+  compressed: bool,
+  checksum: [u8; 32],
+  spec: GeneratorSpec,
+}
+
+impl KeyFileHeader {
+  /// Identifies a curve by the digest of its base and scalar fields' moduli
+  fn curve_id<E: Engine>() -> [u8; 32] {
+    Sha3_256::new()
+      .chain_update(b"NOVA_KEY_CURVE")
+      .chain_update(E::Base::MODULUS.as_bytes())
+      .chain_update(E::Scalar::MODULUS.as_bytes())
+      .finalize()
+      .into()
+  }
+
+  fn write(&self, writer: &mut impl std::io::Write) -> Result<(), PtauFileError> {
+    writer.write_all(&KEY_FILE_MAGIC)?;
+    writer.write_u32::<LittleEndian>(KEY_FILE_VERSION)?;
+    writer.write_all(&self.curve_id)?;
+    writer.write_u64::<LittleEndian>(self.count)?;
+    writer.write_u8(self.compressed.into())?;
+    writer.write_all(&self.checksum)?;
+    writer.write_u8(self.spec.hash.to_u8())?;
+    writer.write_u32::<LittleEndian>(self.spec.domain.len() as u32)?;
+    writer.write_all(self.spec.domain.as_bytes())?;
+    Ok(())
+  }
+
+  /// Reads the header that follows the magic string
+  fn read(reader: &mut impl std::io::Read) -> Result<Self, PtauFileError> {
+    let version = reader.read_u32::<LittleEndian>()?;
+    if version == 0 || version > KEY_FILE_VERSION {
+      return Err(PtauFileError::UnsupportedVersion(version));
+    }
+    let mut curve_id = [0u8; 32];
+    reader.read_exact(&mut curve_id)?;
+    let count = reader.read_u64::<LittleEndian>()?;
+    let compressed = match reader.read_u8()? {
+      0 => false,
+      1 => true,
+      flag => return Err(PtauFileError::InvalidPointEncoding(flag)),
+    };
+    let mut checksum = [0u8; 32];
+    reader.read_exact(&mut checksum)?;
+    let spec = if version >= 2 {
+      let hash = reader.read_u8()?;
+      let hash = LabelHash::from_u8(hash).ok_or(PtauFileError::InvalidLabelHash(hash))?;
+      // the length is untrusted, so only as many bytes as the reader holds are allocated
+      let len = reader.read_u32::<LittleEndian>()?;
+      let mut domain = Vec::new();
+      std::io::Read::read_to_end(
+        &mut std::io::Read::take(&mut *reader, len.into()),
+        &mut domain,
+      )?;
+      if domain.len() != len as usize {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+      }
+      GeneratorSpec {
+        domain: String::from_utf8(domain).map_err(|e| e.utf8_error())?,
+        hash,
+      }
+    } else {
+      GeneratorSpec::default()
+    };
+    Ok(Self {
+      curve_id,
+      count,
+      compressed,
+      checksum,
+      spec,
+    })
+  }
+}
+
+/// Returns the size of the encoding of points, compressed or not
+fn point_size<G: DlogGroup>(compressed: bool) -> usize {
+  if compressed {
+    <G::AffineGroupElement as GroupEncoding>::Repr::default()
+      .as_ref()
+      .len()
+  } else {
+    let mut buf = Vec::new();
+    G::gen().affine().write_raw(&mut buf).unwrap();
+    buf.len()
+  }
+}
+
+fn encode_points<G: DlogGroup>(points: &[G::AffineGroupElement], compressed: bool) -> Vec<u8> {
+  points
+    .par_iter()
+    .flat_map_iter(|p| {
+      if compressed {
+        p.to_bytes().as_ref().to_vec()
+      } else {
+        let mut buf = Vec::new();
+        p.write_raw(&mut buf).unwrap();
+        buf
+      }
+    })
+    .collect()
+}
+
+fn decode_points<G: DlogGroup>(
+  bytes: &[u8],
+  compressed: bool,
+) -> Result<Vec<G::AffineGroupElement>, PtauFileError> {
+  bytes
+    .par_chunks(point_size::<G>(compressed))
+    .map(|chunk| {
+      if compressed {
+        let mut repr = <G::AffineGroupElement as GroupEncoding>::Repr::default();
+        repr.as_mut().copy_from_slice(chunk);
+        Option::from(G::AffineGroupElement::from_bytes(&repr)).ok_or(PtauFileError::InvalidPoint)
+      } else {
+        G::AffineGroupElement::read_raw(&mut &chunk[..]).map_err(|_| PtauFileError::InvalidPoint)
+      }
+    })
+    .collect()
+}
+
 impl<E: Engine> CommitmentKey<E>
 where
   E::GE: DlogGroup,
 {
+  /// Saves the key with uncompressed points, which load faster, in a key file with a header that
+  /// `CommitmentEngineTrait::load_setup` validates. The header records the specification the key
+  /// was derived with (see `spec`), and loading the file expecting another specification fails.
   pub fn save_to(&self, writer: &mut impl std::io::Write) -> Result<(), PtauFileError> {
-    writer.write_all(&KEY_FILE_HEAD)?;
+    self.save_with(writer, false)
+  }
+
+  /// Saves the key with compressed points, which halves the size of the key file, but makes
+  /// loading it slower, since points are then decompressed
+  pub fn save_compressed_to(&self, writer: &mut impl std::io::Write) -> Result<(), PtauFileError> {
+    self.save_with(writer, true)
+  }
+
+  fn save_with(
+    &self,
+    writer: &mut impl std::io::Write,
+    compressed: bool,
+  ) -> Result<(), PtauFileError> {
     let mut points = Vec::with_capacity(self.ck.len() + 1);
     points.push(self.h);
     points.extend(self.ck.iter().cloned());
-    write_points(writer, points)
+    let bytes = encode_points::<E::GE>(&points, compressed);
+
+    KeyFileHeader {
+      curve_id: KeyFileHeader::curve_id::<E>(),
+      count: self.ck.len() as u64,
+      compressed,
+      checksum: Sha3_256::digest(&bytes).into(),
+      spec: self.spec.clone(),
+    }
+    .write(writer)?;
+    writer.write_all(&bytes)?;
+    Ok(())
+  }
+
+  /// Loads a key with `n` generators, rounded up to a power of two, from a key file like
+  /// `CommitmentEngineTrait::load_setup`, except that the generators must have been derived with
+  /// `spec` rather than with the default specification
+  pub fn load_with_spec(
+    reader: &mut impl std::io::Read,
+    spec: &GeneratorSpec,
+    n: usize,
+  ) -> Result<Self, PtauFileError> {
+    let num = n.next_power_of_two();
+
+    // files with a header start with its magic string, and legacy files with a longer one
+    let mut head = [0u8; 12];
+    reader.read_exact(&mut head[..KEY_FILE_MAGIC.len()])?;
+    if head[..KEY_FILE_MAGIC.len()] == KEY_FILE_MAGIC {
+      return Self::load_with_header(reader, spec, num);
+    }
+    reader.read_exact(&mut head[KEY_FILE_MAGIC.len()..])?;
+    if head == LEGACY_KEY_FILE_HEAD {
+      // legacy files predate other specifications than the default one
+      if *spec != GeneratorSpec::default() {
+        return Err(PtauFileError::GeneratorSpecMismatch);
+      }
+      return Self::load_legacy(reader, num);
+    }
+    Err(PtauFileError::InvalidHead)
+  }
+
+  /// Loads `num` generators from a key file with a header, checking the header and the checksum
+  fn load_with_header(
+    reader: &mut impl std::io::Read,
+    spec: &GeneratorSpec,
+    num: usize,
+  ) -> Result<Self, PtauFileError> {
+    let header = KeyFileHeader::read(reader)?;
+    if header.curve_id != KeyFileHeader::curve_id::<E>() {
+      return Err(PtauFileError::InvalidCurve);
+    }
+    if header.spec != *spec {
+      return Err(PtauFileError::GeneratorSpecMismatch);
+    }
+    let count = usize::try_from(header.count).map_err(|_| PtauFileError::InvalidPoint)?;
+    if count < num {
+      return Err(PtauFileError::InsufficientGenerators {
+        count,
+        required: num,
+      });
+    }
+
+    let len = (count + 1)
+      .checked_mul(point_size::<E::GE>(header.compressed))
+      .ok_or(PtauFileError::InvalidPoint)?;
+    let mut bytes = Vec::new();
+    std::io::Read::read_to_end(&mut std::io::Read::take(reader, len as u64), &mut bytes)?;
+    if bytes.len() != len {
+      return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    if <[u8; 32]>::from(Sha3_256::digest(&bytes)) != header.checksum {
+      return Err(PtauFileError::InvalidChecksum);
+    }
+
+    // only the requested generators are decoded, the others are covered by the checksum
+    let size = point_size::<E::GE>(header.compressed);
+    let points = decode_points::<E::GE>(&bytes[..(num + 1) * size], header.compressed)?;
+    let (h, ck) = points.split_first().unwrap();
+    Ok(Self {
+      ck: ck.to_vec(),
+      h: *h,
+      spec: header.spec,
+      prepared: Default::default(),
+      tables: Default::default(),
+    })
+  }
+
+  /// Loads `num` generators from a key file written before the format had a header
+  fn load_legacy(reader: &mut impl std::io::Read, num: usize) -> Result<Self, PtauFileError> {
+    let points = read_points(reader, num + 1)?;
+    let (h, ck) = points.split_first().unwrap();
+    Ok(Self {
+      ck: ck.to_vec(),
+      h: *h,
+      spec: GeneratorSpec::default(),
+      prepared: Default::default(),
+      tables: Default::default(),
+    })
   }
 }
 
@@ -210,29 +684,78 @@ where
   type DerandKey = DerandKey<E>;
 
   fn setup(label: &'static [u8], n: usize) -> Self::CommitmentKey {
-    let gens = E::GE::from_label(label, n.next_power_of_two() + 1);
-
-    let (h, ck) = gens.split_first().unwrap();
+    Self::CommitmentKey::from_label_with(&GeneratorSpec::default(), label, n)
+  }
 
-    Self::CommitmentKey {
-      ck: ck.to_vec(),
-      h: *h,
-    }
+  fn setup_with_blinding(
+    label: &'static [u8],
+    n: usize,
+    blinding: &BlindingGenerator<E>,
+  ) -> Result<Self::CommitmentKey, NovaError> {
+    let mut ck = Self::setup(label, n);
+    ck.h = blinding_generator(blinding, ck.h)?;
+    Ok(ck)
   }
 
   fn derand_key(ck: &Self::CommitmentKey) -> Self::DerandKey {
     Self::DerandKey { h: ck.h }
   }
 
+  fn prepare(ck: &Self::CommitmentKey) {
+    ck.prepared.prepare(&ck.ck);
+  }
+
   fn commit(ck: &Self::CommitmentKey, v: &[E::Scalar], r: &E::Scalar) -> Self::Commitment {
     assert!(ck.ck.len() >= v.len());
 
     Commitment {
-      comm: E::GE::vartime_multiscalar_mul(v, &ck.ck[..v.len()])
+      comm: ck.msm(v, 0) + <E::GE as DlogGroup>::group(&ck.h) * r,
+    }
+  }
+
+  fn commit_sparse(
+    ck: &Self::CommitmentKey,
+    indices: &[usize],
+    values: &[E::Scalar],
+    r: &E::Scalar,
+  ) -> Self::Commitment {
+    assert!(indices.iter().all(|i| *i < ck.ck.len()));
+
+    Commitment {
+      comm: E::GE::vartime_multiscalar_mul_sparse(indices, values, &ck.ck, ck.prepared.get())
         + <E::GE as DlogGroup>::group(&ck.h) * r,
     }
   }
 
+  fn commit_at(ck: &Self::CommitmentKey, v: &[E::Scalar], offset: usize) -> Self::Commitment {
+    assert!(ck.ck.len() >= offset + v.len());
+
+    Commitment {
+      comm: ck.msm(v, offset),
+    }
+  }
+
+  fn batch_commit(
+    ck: &Self::CommitmentKey,
+    v: &[Vec<E::Scalar>],
+    r: &[E::Scalar],
+  ) -> Vec<Self::Commitment> {
+    assert!(v.len() == r.len());
+
+    let max = v.iter().map(|v| v.len()).max().unwrap_or(0);
+    assert!(ck.ck.len() >= max);
+
+    let h = <E::GE as DlogGroup>::group(&ck.h);
+
+    E::GE::batch_vartime_multiscalar_mul_prepared(v, &ck.ck[..max], ck.prepared.get())
+      .par_iter()
+      .zip(r.par_iter())
+      .map(|(commit, r_i)| Commitment {
+        comm: *commit + (h * r_i),
+      })
+      .collect()
+  }
+
   fn commit_small<T: Integer + Into<u64> + Copy + Sync + ToPrimitive>(
     ck: &Self::CommitmentKey,
     v: &[T],
@@ -246,6 +769,27 @@ where
     }
   }
 
+  fn batch_commit_small<T: Integer + Into<u64> + Copy + Sync + ToPrimitive>(
+    ck: &Self::CommitmentKey,
+    v: &[Vec<T>],
+    r: &[E::Scalar],
+  ) -> Vec<Self::Commitment> {
+    assert!(v.len() == r.len());
+
+    let max = v.iter().map(|v| v.len()).max().unwrap_or(0);
+    assert!(ck.ck.len() >= max);
+
+    let h = <E::GE as DlogGroup>::group(&ck.h);
+
+    E::GE::batch_vartime_multiscalar_mul_small(v, &ck.ck[..max])
+      .par_iter()
+      .zip(r.par_iter())
+      .map(|(commit, r_i)| Commitment {
+        comm: *commit + (h * r_i),
+      })
+      .collect()
+  }
+
   fn derandomize(
     dk: &Self::DerandKey,
     commit: &Self::Commitment,
@@ -256,28 +800,27 @@ where
     }
   }
 
+  fn derandomize_batch(
+    dk: &Self::DerandKey,
+    commits: &[Self::Commitment],
+    r: &[E::Scalar],
+  ) -> Vec<Self::Commitment> {
+    assert!(commits.len() == r.len());
+    fixed_base_mul_batch(<E::GE as DlogGroup>::group(&dk.h), r)
+      .into_par_iter()
+      .zip(commits.par_iter())
+      .map(|(blind, commit)| Commitment {
+        comm: commit.comm - blind,
+      })
+      .collect()
+  }
+
   fn load_setup(
     reader: &mut (impl std::io::Read + std::io::Seek),
     _label: &'static [u8],
     n: usize,
   ) -> Result<Self::CommitmentKey, PtauFileError> {
-    let num = n.next_power_of_two();
-    {
-      let mut head = [0u8; 12];
-      reader.read_exact(&mut head)?;
-      if head != KEY_FILE_HEAD {
-        return Err(PtauFileError::InvalidHead);
-      }
-    }
-
-    let points = read_points(reader, num + 1)?;
-
-    let (first, second) = points.split_at(1);
-
-    Ok(Self::CommitmentKey {
-      ck: second.to_vec(),
-      h: first[0],
-    })
+    Self::CommitmentKey::load_with_spec(reader, &GeneratorSpec::default(), n)
   }
 }
 
@@ -306,6 +849,11 @@ where
   ) -> Result<Self, NovaError>
   where
     Self: Sized;
+
+  /// Precomputes tables of multiples of the generators with windows of `window` bits, at most
+  /// 24, which speed up subsequent commitments at the cost of storing `ceil(NUM_BITS / window)`
+  /// group elements per generator. Does nothing if tables were already precomputed.
+  fn precompute_tables(&self, window: usize);
 }
 
 impl<E: Engine<CE = CommitmentEngine<E>>> CommitmentKeyExtTrait<E> for CommitmentKey<E>
@@ -317,10 +865,16 @@ where
       CommitmentKey {
         ck: self.ck[0..n].to_vec(),
         h: self.h,
+        spec: self.spec.clone(),
+        prepared: Default::default(),
+        tables: Default::default(),
       },
       CommitmentKey {
         ck: self.ck[n..].to_vec(),
         h: self.h,
+        spec: self.spec.clone(),
+        prepared: Default::default(),
+        tables: Default::default(),
       },
     )
   }
@@ -331,7 +885,13 @@ where
       c.extend(other.ck.clone());
       c
     };
-    CommitmentKey { ck, h: self.h }
+    CommitmentKey {
+      ck,
+      h: self.h,
+      spec: self.spec.clone(),
+      prepared: Default::default(),
+      tables: Default::default(),
+    }
   }
 
   // combines the left and right halves of `self` using `w1` and `w2` as the weights
@@ -343,41 +903,59 @@ where
       .into_par_iter()
       .map(|i| {
         let bases = [L.ck[i], R.ck[i]].to_vec();
-        E::GE::vartime_multiscalar_mul(&w, &bases).affine()
+        E::GE::vartime_multiscalar_mul(&w, &bases)
       })
-      .collect();
+      .collect::<Vec<_>>();
+    let ck = E::GE::batch_affine(&ck);
 
-    CommitmentKey { ck, h: self.h }
+    CommitmentKey {
+      ck,
+      h: self.h,
+      spec: self.spec.clone(),
+      prepared: Default::default(),
+      tables: Default::default(),
+    }
   }
 
   /// Scales each element in `self` by `r`
   fn scale(&self, r: &E::Scalar) -> Self {
     let ck_scaled = self
       .ck
-      .clone()
-      .into_par_iter()
-      .map(|g| E::GE::vartime_multiscalar_mul(&[*r], &[g]).affine())
-      .collect();
+      .par_iter()
+      .map(|g| E::GE::vartime_multiscalar_mul(&[*r], &[*g]))
+      .collect::<Vec<_>>();
+    let ck_scaled = E::GE::batch_affine(&ck_scaled);
 
     CommitmentKey {
       ck: ck_scaled,
       h: self.h,
+      spec: self.spec.clone(),
+      prepared: Default::default(),
+      tables: Default::default(),
     }
   }
 
+  fn precompute_tables(&self, window: usize) {
+    self
+      .tables
+      .0
+      .get_or_init(|| FixedBaseTables::new(&self.ck, window));
+  }
+
   /// reinterprets a vector of commitments as a set of generators
   fn reinterpret_commitments_as_ck(c: &[Commitment<E>]) -> Result<Self, NovaError> {
-    let ck = (0..c.len())
-      .into_par_iter()
-      .map(|i| c[i].comm.affine())
-      .collect();
+    let ck = E::GE::batch_affine(&c.iter().map(|c| c.comm).collect::<Vec<_>>());
 
     // cmt is derandomized by the point that this is called
     Ok(CommitmentKey {
       ck,
-      h: E::GE::zero().affine(), // this is okay, since this method is used in IPA only,
-                                 // and we only use non-blinding commits afterwards
-                                 // bc we don't use ZK IPA
+      // this is okay, since this method is used in IPA only,
+      // and we only use non-blinding commits afterwards
+      // bc we don't use ZK IPA
+      h: E::GE::zero().affine(),
+      spec: GeneratorSpec::default(),
+      prepared: Default::default(),
+      tables: Default::default(),
     })
   }
 }
@@ -386,7 +964,10 @@ where
 mod tests {
   use super::*;
 
-  use crate::{provider::GrumpkinEngine, CommitmentKey};
+  use crate::{
+    provider::{ptau::write_points, GrumpkinEngine},
+    CommitmentKey,
+  };
   use std::{fs::File, io::BufWriter};
 
   type E = GrumpkinEngine;
@@ -411,4 +992,353 @@ mod tests {
     assert_eq!(keys_read.h, keys.h);
     assert_eq!(keys_read.ck, keys.ck);
   }
+
+  #[test]
+  fn test_key_file_header() {
+    let keys = CommitmentEngine::<E>::setup(b"test", 100);
+    let load = |bytes: &[u8], n: usize| {
+      CommitmentEngine::<E>::load_setup(&mut std::io::Cursor::new(bytes), b"test", n)
+    };
+
+    let mut uncompressed = Vec::new();
+    keys.save_to(&mut uncompressed).unwrap();
+    let mut compressed = Vec::new();
+    keys.save_compressed_to(&mut compressed).unwrap();
+    assert!(compressed.len() < uncompressed.len());
+    for bytes in [&uncompressed, &compressed] {
+      assert_eq!(load(bytes, 100).unwrap(), keys);
+      // a prefix of the generators can be loaded
+      assert_eq!(load(bytes, 64).unwrap().ck, keys.ck[..64]);
+    }
+
+    // legacy files hold the uncompressed points right after their magic string
+    let mut legacy = LEGACY_KEY_FILE_HEAD.to_vec();
+    let mut points = vec![keys.h];
+    points.extend(keys.ck.iter().cloned());
+    write_points(&mut legacy, points).unwrap();
+    assert_eq!(load(&legacy, 100).unwrap(), keys);
+
+    assert!(matches!(
+      load(&uncompressed, 200),
+      Err(PtauFileError::InsufficientGenerators {
+        count: 128,
+        required: 256
+      })
+    ));
+    let mut corrupted = uncompressed.clone();
+    *corrupted.last_mut().unwrap() ^= 1;
+    assert!(matches!(
+      load(&corrupted, 100),
+      Err(PtauFileError::InvalidChecksum)
+    ));
+    assert!(matches!(
+      load(&uncompressed[..uncompressed.len() - 1], 100),
+      Err(PtauFileError::IoError(_))
+    ));
+    // the generator specification of the file must be the selected one
+    let mut header = &uncompressed[KEY_FILE_MAGIC.len()..];
+    let mut header = KeyFileHeader::read(&mut header).unwrap();
+    assert_eq!(header.spec, GeneratorSpec::default());
+    header.spec.hash = LabelHash::Keccak256;
+    let mut other_spec = Vec::new();
+    header.write(&mut other_spec).unwrap();
+    other_spec.extend_from_slice(&uncompressed[other_spec.len()..]);
+    assert!(matches!(
+      load(&other_spec, 100),
+      Err(PtauFileError::GeneratorSpecMismatch)
+    ));
+
+    // keys record the specification they were derived with, which their files carry along
+    let keccak = GeneratorSpec {
+      domain: "other".to_string(),
+      hash: LabelHash::Keccak256,
+    };
+    let keccak_keys = CommitmentKey::<E>::from_label_with(&keccak, b"test", 100);
+    assert_ne!(keccak_keys.ck, keys.ck);
+    let mut keccak_file = Vec::new();
+    keccak_keys.save_to(&mut keccak_file).unwrap();
+    assert!(matches!(
+      load(&keccak_file, 100),
+      Err(PtauFileError::GeneratorSpecMismatch)
+    ));
+    let loaded = CommitmentKey::<E>::load_with_spec(&mut &keccak_file[..], &keccak, 100).unwrap();
+    assert_eq!(loaded, keccak_keys);
+    assert_eq!(loaded.spec(), &keccak);
+    assert!(matches!(
+      CommitmentKey::<E>::load_with_spec(&mut &legacy[..], &keccak, 100),
+      Err(PtauFileError::GeneratorSpecMismatch)
+    ));
+
+    let mut other_curve = Vec::new();
+    CommitmentEngine::<crate::provider::PallasEngine>::setup(b"test", 100)
+      .save_to(&mut other_curve)
+      .unwrap();
+    assert!(matches!(
+      load(&other_curve, 100),
+      Err(PtauFileError::InvalidCurve)
+    ));
+    assert!(matches!(
+      load(b"NOT_A_KEY_FILE", 100),
+      Err(PtauFileError::InvalidHead)
+    ));
+  }
+
+  #[test]
+  fn test_batch_commit() {
+    let mut rng = rand::thread_rng();
+    let ck = CommitmentEngine::<E>::setup(b"test", 100);
+
+    let v: Vec<Vec<<E as Engine>::Scalar>> = [0, 1, 17, 100]
+      .iter()
+      .map(|n| (0..*n).map(|_| Field::random(&mut rng)).collect())
+      .collect();
+    let r: Vec<<E as Engine>::Scalar> = (0..v.len()).map(|_| Field::random(&mut rng)).collect();
+
+    let batched = CommitmentEngine::<E>::batch_commit(&ck, &v, &r);
+    for ((v_i, r_i), comm) in v.iter().zip(r.iter()).zip(batched.iter()) {
+      assert_eq!(CommitmentEngine::<E>::commit(&ck, v_i, r_i), *comm);
+    }
+
+    let v_small: Vec<Vec<u64>> = vec![vec![], vec![1, 0, 3], (0..100).collect()];
+    let batched = CommitmentEngine::<E>::batch_commit_small(&ck, &v_small, &r[..3]);
+    for ((v_i, r_i), comm) in v_small.iter().zip(r.iter()).zip(batched.iter()) {
+      assert_eq!(CommitmentEngine::<E>::commit_small(&ck, v_i, r_i), *comm);
+    }
+  }
+
+  #[test]
+  fn test_derandomize_batch() {
+    let mut rng = rand::thread_rng();
+    let ck = CommitmentEngine::<E>::setup(b"test", 8);
+    let dk = CommitmentEngine::<E>::derand_key(&ck);
+
+    // batches on both sides of the threshold of the comb method
+    for n in [3, 100] {
+      let v: Vec<Vec<<E as Engine>::Scalar>> = (0..n)
+        .map(|_| (0..8).map(|_| Field::random(&mut rng)).collect())
+        .collect();
+      let r: Vec<<E as Engine>::Scalar> = (0..n).map(|_| Field::random(&mut rng)).collect();
+      let zero = vec![<E as Engine>::Scalar::ZERO; n];
+      let blinded = CommitmentEngine::<E>::batch_commit(&ck, &v, &r);
+      let unblinded = CommitmentEngine::<E>::batch_commit(&ck, &v, &zero);
+
+      assert_eq!(
+        CommitmentEngine::<E>::derandomize_batch(&dk, &blinded, &r),
+        unblinded
+      );
+      assert_eq!(
+        CommitmentEngine::<E>::rerandomize_batch(&dk, &unblinded, &r),
+        blinded
+      );
+    }
+  }
+
+  #[test]
+  fn test_commit_sparse() {
+    let mut rng = rand::thread_rng();
+    let ck = CommitmentEngine::<E>::setup(b"test", 100);
+    let r = <E as Engine>::Scalar::random(&mut rng);
+
+    // the indices need not be sorted, and zero values are skipped
+    let indices = [99, 3, 0, 42, 17];
+    let mut values: Vec<<E as Engine>::Scalar> = (0..indices.len())
+      .map(|_| Field::random(&mut rng))
+      .collect();
+    values[3] = <E as Engine>::Scalar::ZERO;
+    let mut v = vec![<E as Engine>::Scalar::ZERO; 100];
+    for (i, value) in indices.iter().zip(&values) {
+      v[*i] = *value;
+    }
+    assert_eq!(
+      CommitmentEngine::<E>::commit_sparse(&ck, &indices, &values, &r),
+      CommitmentEngine::<E>::commit(&ck, &v, &r)
+    );
+    assert_eq!(
+      CommitmentEngine::<E>::commit_sparse(&ck, &[], &[], &r),
+      CommitmentEngine::<E>::commit(&ck, &[], &r)
+    );
+
+    // the values at a repeated index add up
+    v[indices[0]] += values[1];
+    assert_eq!(
+      CommitmentEngine::<E>::commit_sparse(
+        &ck,
+        &[indices.as_slice(), &[indices[0]]].concat(),
+        &[values.as_slice(), &[values[1]]].concat(),
+        &r
+      ),
+      CommitmentEngine::<E>::commit(&ck, &v, &r)
+    );
+  }
+
+  #[test]
+  fn test_update() {
+    let mut rng = rand::thread_rng();
+    let ck = CommitmentEngine::<E>::setup(b"test", 100);
+    let mut v: Vec<<E as Engine>::Scalar> = (0..100).map(|_| Field::random(&mut rng)).collect();
+    let r = <E as Engine>::Scalar::random(&mut rng);
+
+    let mut comm = CommitmentEngine::<E>::commit(&ck, &v, &r);
+    for index in [0, 17, 99] {
+      let new = <E as Engine>::Scalar::random(&mut rng);
+      comm = CommitmentEngine::<E>::update(&ck, &comm, index, &v[index], &new).unwrap();
+      v[index] = new;
+      assert_eq!(comm, CommitmentEngine::<E>::commit(&ck, &v, &r));
+    }
+
+    // an entry past the generators cannot be updated
+    assert_eq!(
+      CommitmentEngine::<E>::update(&ck, &comm, ck.length(), &v[0], &v[1]),
+      Err(NovaError::InvalidIndex)
+    );
+  }
+
+  #[test]
+  fn test_prepare() {
+    type E = crate::provider::Bn256EngineIPA;
+    let mut rng = rand::thread_rng();
+    let ck = CommitmentEngine::<E>::setup(b"test", 100);
+    let v: Vec<<E as Engine>::Scalar> = (0..100).map(|_| Field::random(&mut rng)).collect();
+    let r = <E as Engine>::Scalar::random(&mut rng);
+
+    let commit = |ck: &CommitmentKey<E>| {
+      (
+        CommitmentEngine::<E>::commit(ck, &v, &r),
+        CommitmentEngine::<E>::commit_at(ck, &v[..10], 20),
+        CommitmentEngine::<E>::batch_commit(ck, &[v.clone(), v[..10].to_vec()], &[r, r]),
+      )
+    };
+    let unprepared = commit(&ck);
+
+    // preparing the key changes neither its commitments nor its identity
+    let prepared = ck.clone();
+    CommitmentEngine::<E>::prepare(&prepared);
+    assert!(prepared.prepared.get().is_some());
+    assert_eq!(commit(&prepared), unprepared);
+    assert_eq!(prepared, ck);
+  }
+
+  #[test]
+  fn test_precompute_tables() {
+    let mut rng = rand::thread_rng();
+    let ck = CommitmentEngine::<E>::setup(b"test", 100);
+    let v: Vec<<E as Engine>::Scalar> = (0..100).map(|_| Field::random(&mut rng)).collect();
+    let r = <E as Engine>::Scalar::random(&mut rng);
+
+    let commit = |ck: &CommitmentKey<E>| {
+      (
+        CommitmentEngine::<E>::commit(ck, &v, &r),
+        CommitmentEngine::<E>::commit_at(ck, &v[..10], 20),
+        CommitmentEngine::<E>::commit_at(ck, &[-<E as Engine>::Scalar::ONE], 99),
+      )
+    };
+    let plain = commit(&ck);
+
+    // windows that do and do not divide the scalar size give the same commitments
+    for window in [1, 7, 16, 24] {
+      let with_tables = ck.clone();
+      with_tables.precompute_tables(window);
+      assert!(with_tables.tables.0.get().is_some());
+      assert_eq!(commit(&with_tables), plain);
+      assert_eq!(with_tables, ck);
+    }
+  }
+
+  #[test]
+  fn test_attest() {
+    let ck = CommitmentEngine::<E>::setup(b"test", 100);
+    let attestation = ck.attest();
+    assert_eq!(attestation.num_generators(), ck.length());
+    assert!(attestation.verify(&ck).is_ok());
+
+    // keys with different generators are rejected, including the blinding generator
+    let mut other_h = ck.clone();
+    other_h.h = <E as Engine>::GE::zero().affine();
+    assert_eq!(
+      attestation.verify(&other_h),
+      Err(NovaError::CommitmentKeyMismatch)
+    );
+    let other = CommitmentEngine::<E>::setup(b"other", 100);
+    assert_eq!(
+      attestation.verify(&other),
+      Err(NovaError::CommitmentKeyMismatch)
+    );
+    let shorter = CommitmentEngine::<E>::setup(b"test", 64);
+    assert_eq!(
+      attestation.verify(&shorter),
+      Err(NovaError::InvalidCommitmentKeyLength)
+    );
+  }
+
+  #[test]
+  fn test_from_beacon() {
+    let beacon = b"round 1234: 0x5f2e...";
+    let ck = CommitmentKey::<E>::from_beacon(beacon, 100);
+    assert_eq!(ck.length(), 128);
+    assert_eq!(ck, CommitmentKey::<E>::from_beacon(beacon, 128));
+    assert!(ck.verify_beacon(beacon).is_ok());
+
+    assert_eq!(
+      ck.verify_beacon(b"round 1235: 0x5f2e..."),
+      Err(NovaError::CommitmentKeyMismatch)
+    );
+    let mut other_h = ck.clone();
+    other_h.h = <E as Engine>::GE::zero().affine();
+    assert_eq!(
+      other_h.verify_beacon(beacon),
+      Err(NovaError::CommitmentKeyMismatch)
+    );
+    let mut truncated = ck.clone();
+    truncated.ck.pop();
+    assert_eq!(
+      truncated.verify_beacon(beacon),
+      Err(NovaError::InvalidCommitmentKeyLength)
+    );
+
+    // the label separates keys derived from beacons from those set up from labels
+    let setup = CommitmentEngine::<E>::setup(b"round 1234: 0x5f2e...", 100);
+    assert_eq!(
+      setup.verify_beacon(beacon),
+      Err(NovaError::CommitmentKeyMismatch)
+    );
+  }
+
+  #[test]
+  fn test_setup_with_blinding() {
+    let mut rng = rand::thread_rng();
+    let setup = |blinding| CommitmentEngine::<E>::setup_with_blinding(b"test", 8, &blinding);
+    let ck = CommitmentEngine::<E>::setup(b"test", 8);
+    assert_eq!(setup(BlindingGenerator::FromKeyLabel).unwrap(), ck);
+
+    let h = <E as Engine>::GE::gen() * <E as Engine>::Scalar::from(5u64);
+    let label = <E as Engine>::GE::group(&<E as Engine>::GE::from_label(b"blinding", 1)[0]);
+    for (blinding, h) in [
+      (BlindingGenerator::Fixed(h), h),
+      (BlindingGenerator::Label(b"blinding"), label),
+    ] {
+      // only the blinding generator changes, and the derandomization key records it
+      let ck_h = setup(blinding).unwrap();
+      assert_eq!(ck_h.ck, ck.ck);
+      let dk = CommitmentEngine::<E>::derand_key(&ck_h);
+      assert_eq!(dk.blinding_generator(), h);
+
+      let v: Vec<<E as Engine>::Scalar> = (0..8).map(|_| Field::random(&mut rng)).collect();
+      let r = <E as Engine>::Scalar::random(&mut rng);
+      let comm = CommitmentEngine::<E>::commit(&ck_h, &v, &r);
+      assert_eq!(
+        comm,
+        CommitmentEngine::<E>::commit(&ck, &v, &<E as Engine>::Scalar::ZERO)
+          + Commitment { comm: h * r }
+      );
+      assert_eq!(
+        CommitmentEngine::<E>::derandomize(&dk, &comm, &r),
+        CommitmentEngine::<E>::commit(&ck, &v, &<E as Engine>::Scalar::ZERO)
+      );
+    }
+
+    assert!(matches!(
+      setup(BlindingGenerator::Fixed(<E as Engine>::GE::zero())),
+      Err(NovaError::SetupError { .. })
+    ));
+  }
 }
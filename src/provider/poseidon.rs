@@ -1,5 +1,16 @@
 //! Poseidon Constants and Poseidon-based RO used in Nova
+//!
+//! Neither `PoseidonRO` nor `PoseidonTranscript` hashes when values are absorbed. Absorbed values
+//! are buffered, and a squeeze hashes the whole buffer with a single sponge pass, whose
+//! permutations each absorb as many elements as the rate of the sponge. The challenges are thus
+//! fixed by the sequence of field elements in the buffer when squeezing, which is:
+//! - for `PoseidonRO`, the previous challenge, if any, followed by the absorbed elements;
+//! - for `PoseidonTranscript`, the previous challenge, if any, followed by `pack_bytes` of each
+//!   byte string absorbed since: the label and then the transcript representation of each absorbed
+//!   value, the tag and the bytes of each domain separator, and finally the label of the squeeze.
+//!   `PERSONA_TAG` and the label of the transcript come first.
 use crate::{
+  errors::NovaError,
   frontend::{
     gadgets::poseidon::{
       Elt, IOPattern, PoseidonConstants, Simplex, Sponge, SpongeAPI, SpongeCircuit, SpongeOp,
@@ -8,8 +19,10 @@ use crate::{
     num::AllocatedNum,
     AllocatedBit, Boolean, ConstraintSystem, SynthesisError,
   },
-  traits::{ROCircuitTrait, ROTrait},
+  gadgets::poseidon::PoseidonSponge,
+  traits::{Engine, ROCircuitTrait, ROTrait, TranscriptEngineTrait, TranscriptReprTrait},
 };
+use core::marker::PhantomData;
 use ff::{PrimeField, PrimeFieldBits};
 use generic_array::typenum::U24;
 use serde::{Deserialize, Serialize};
@@ -25,6 +38,60 @@ impl<Scalar: PrimeField> Default for PoseidonConstantsCircuit<Scalar> {
   }
 }
 
+impl<Scalar: PrimeField> PoseidonConstantsCircuit<Scalar> {
+  /// Hashes `input` into a single field element, absorbing all of it and then squeezing once
+  pub(crate) fn hash(&self, input: &[Scalar]) -> Scalar {
+    let mut sponge = Sponge::new_with_constants(&self.0, Simplex);
+    let acc = &mut ();
+    let parameter = IOPattern(vec![
+      SpongeOp::Absorb(input.len() as u32),
+      SpongeOp::Squeeze(1u32),
+    ]);
+
+    sponge.start(parameter, None, acc);
+    SpongeAPI::absorb(&mut sponge, input.len() as u32, input, acc);
+    let hash = SpongeAPI::squeeze(&mut sponge, 1, acc);
+    sponge.finish(acc).unwrap();
+    hash[0]
+  }
+
+  /// Constrains the output to be the hash of `input`, as computed by `hash` outside circuits
+  pub(crate) fn hash_circuit<CS: ConstraintSystem<Scalar>>(
+    &self,
+    mut cs: CS,
+    input: &[AllocatedNum<Scalar>],
+  ) -> Result<AllocatedNum<Scalar>, SynthesisError> {
+    let parameter = IOPattern(vec![
+      SpongeOp::Absorb(input.len() as u32),
+      SpongeOp::Squeeze(1u32),
+    ]);
+
+    let mut ns = cs.namespace(|| "sponge");
+    let hash = {
+      let mut sponge = SpongeCircuit::new_with_constants(&self.0, Simplex);
+      let acc = &mut ns;
+
+      sponge.start(parameter, None, acc);
+      SpongeAPI::absorb(
+        &mut sponge,
+        input.len() as u32,
+        &input
+          .iter()
+          .map(|x| Elt::Allocated(x.clone()))
+          .collect::<Vec<Elt<Scalar>>>(),
+        acc,
+      );
+
+      let output = SpongeAPI::squeeze(&mut sponge, 1, acc);
+      sponge.finish(acc).unwrap();
+      output
+    };
+
+    let hash = Elt::ensure_allocated(&hash[0], &mut ns.namespace(|| "ensure allocated"), true)?;
+    Ok(hash)
+  }
+}
+
 /// A Poseidon-based RO to use outside circuits
 #[derive(Serialize, Deserialize)]
 pub struct PoseidonRO<Base: PrimeField> {
@@ -52,25 +119,19 @@ where
     self.state.push(e);
   }
 
+  fn absorb_batch(&mut self, es: &[Base]) {
+    self.state.extend_from_slice(es);
+  }
+
   /// Compute a challenge by hashing the current state
   fn squeeze(&mut self, num_bits: usize) -> Base {
-    let mut sponge = Sponge::new_with_constants(&self.constants.0, Simplex);
-    let acc = &mut ();
-    let parameter = IOPattern(vec![
-      SpongeOp::Absorb(self.state.len() as u32),
-      SpongeOp::Squeeze(1u32),
-    ]);
-
-    sponge.start(parameter, None, acc);
-    SpongeAPI::absorb(&mut sponge, self.state.len() as u32, &self.state, acc);
-    let hash = SpongeAPI::squeeze(&mut sponge, 1, acc);
-    sponge.finish(acc).unwrap();
+    let hash = self.constants.hash(&self.state);
 
     // reset the state to only contain the squeezed value
-    self.state = vec![hash[0]];
+    self.state = vec![hash];
 
     // Only return `num_bits`
-    let bits = hash[0].to_le_bits();
+    let bits = hash.to_le_bits();
     let mut res = Base::ZERO;
     let mut coeff = Base::ONE;
     for bit in bits[0..num_bits].into_iter() {
@@ -117,32 +178,8 @@ where
     mut cs: CS,
     num_bits: usize,
   ) -> Result<Vec<AllocatedBit>, SynthesisError> {
-    let parameter = IOPattern(vec![
-      SpongeOp::Absorb(self.state.len() as u32),
-      SpongeOp::Squeeze(1u32),
-    ]);
     let mut ns = cs.namespace(|| "ns");
-
-    let hash = {
-      let mut sponge = SpongeCircuit::new_with_constants(&self.constants.0, Simplex);
-      let acc = &mut ns;
-
-      sponge.start(parameter, None, acc);
-      SpongeAPI::absorb(
-        &mut sponge,
-        self.state.len() as u32,
-        &(0..self.state.len())
-          .map(|i| Elt::Allocated(self.state[i].clone()))
-          .collect::<Vec<Elt<Scalar>>>(),
-        acc,
-      );
-
-      let output = SpongeAPI::squeeze(&mut sponge, 1, acc);
-      sponge.finish(acc).unwrap();
-      output
-    };
-
-    let hash = Elt::ensure_allocated(&hash[0], &mut ns.namespace(|| "ensure allocated"), true)?;
+    let hash = self.constants.hash_circuit(&mut ns, &self.state)?;
 
     // reset the state to only contain the squeezed value
     self.state = vec![hash.clone()];
@@ -162,6 +199,110 @@ where
   }
 }
 
+const PERSONA_TAG: &[u8] = b"NoTR";
+const DOM_SEP_TAG: &[u8] = b"NoDS";
+
+/// Returns the number of bytes that `pack_bytes` packs into each field element
+pub fn bytes_per_element<F: PrimeField>() -> usize {
+  (F::CAPACITY / 8) as usize
+}
+
+/// Encodes bytes as the field elements that `PoseidonTranscript` absorbs: the number of bytes,
+/// followed by chunks of `bytes_per_element` bytes, each read as a little-endian integer
+pub fn pack_bytes<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+  let chunks = bytes.chunks(bytes_per_element::<F>()).map(|chunk| {
+    chunk
+      .iter()
+      .rev()
+      .fold(F::ZERO, |acc, b| acc * F::from(256) + F::from(*b as u64))
+  });
+  std::iter::once(F::from(bytes.len() as u64))
+    .chain(chunks)
+    .collect()
+}
+
+/// The default number of bytes that `PoseidonTranscript` buffers before packing them into field
+/// elements
+pub const DEFAULT_ABSORB_BATCH_SIZE: usize = 1024;
+
+/// Provides an implementation of `TranscriptEngineTrait` with the Poseidon sponge that Nova's
+/// random oracle uses, over the scalar field.
+///
+/// Unlike `Keccak256Transcript`, the transcript can be replayed cheaply by a circuit over the scalar
+/// field, with `gadgets::poseidon::PoseidonTranscriptGadget`, since absorbing bytes amounts to
+/// packing them into field elements (see `pack_bytes`), and challenges are outputs of the sponge.
+#[derive(Clone)]
+pub struct PoseidonTranscript<E: Engine> {
+  sponge: PoseidonSponge<E::Scalar>,
+  pending: Vec<u8>,         // byte strings absorbed but not packed yet, back to back
+  pending_lens: Vec<usize>, // the lengths of the byte strings in `pending`
+  batch_size: usize,        // the number of bytes in `pending` that triggers packing them
+  _p: PhantomData<E>,
+}
+
+impl<E: Engine> PoseidonTranscript<E> {
+  /// Sets the number of bytes that the transcript buffers before packing them into field elements,
+  /// at once and into a buffer of the right size, which is `DEFAULT_ABSORB_BATCH_SIZE` by default.
+  /// With `0`, bytes are packed as they are absorbed. The batch size does not change the
+  /// challenges, only how often the transcript allocates.
+  pub fn with_absorb_batch_size(mut self, bytes: usize) -> Self {
+    self.batch_size = bytes;
+    self
+  }
+
+  /// Buffers `bytes`, which are packed on their own, as if they were absorbed right away
+  fn absorb_bytes(&mut self, bytes: &[u8]) {
+    self.pending.extend_from_slice(bytes);
+    self.pending_lens.push(bytes.len());
+    if self.pending.len() >= self.batch_size {
+      self.flush();
+    }
+  }
+
+  /// Packs the buffered byte strings into the sponge
+  fn flush(&mut self) {
+    let mut elements = Vec::with_capacity(
+      self.pending_lens.len() + self.pending.len() / bytes_per_element::<E::Scalar>() + 1,
+    );
+    let mut offset = 0;
+    for len in self.pending_lens.drain(..) {
+      elements.extend(pack_bytes::<E::Scalar>(&self.pending[offset..offset + len]));
+      offset += len;
+    }
+    self.pending.clear();
+    self.sponge.absorb_batch(&elements);
+  }
+}
+
+impl<E: Engine> TranscriptEngineTrait<E> for PoseidonTranscript<E> {
+  fn new(label: &'static [u8]) -> Self {
+    let mut transcript = Self {
+      sponge: PoseidonSponge::new(PoseidonConstantsCircuit::default()),
+      pending: Vec::new(),
+      pending_lens: Vec::new(),
+      batch_size: DEFAULT_ABSORB_BATCH_SIZE,
+      _p: PhantomData,
+    };
+    transcript.absorb_bytes(&[PERSONA_TAG, label].concat());
+    transcript
+  }
+
+  fn squeeze(&mut self, label: &'static [u8]) -> Result<E::Scalar, NovaError> {
+    self.absorb_bytes(label);
+    self.flush();
+    Ok(self.sponge.squeeze())
+  }
+
+  fn absorb<T: TranscriptReprTrait<E::GE>>(&mut self, label: &'static [u8], o: &T) {
+    self.absorb_bytes(label);
+    self.absorb_bytes(&o.to_transcript_bytes());
+  }
+
+  fn dom_sep(&mut self, bytes: &'static [u8]) {
+    self.absorb_bytes(&[DOM_SEP_TAG, bytes].concat());
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -170,9 +311,11 @@ mod tests {
     frontend::solver::SatisfyingAssignment,
     gadgets::utils::le_bits_to_num,
     provider::{
-      Bn256EngineKZG, GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine, VestaEngine,
+      pasta::pallas, pedersen::CommitmentEngine as PedersenCommitmentEngine, Bn256EngineKZG,
+      GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine, VestaEngine,
     },
-    traits::Engine,
+    spartan::polys::multilinear::MultilinearPolynomial,
+    traits::{commitment::CommitmentEngineTrait, evaluation::EvaluationEngineTrait, Engine},
   };
   use ff::Field;
   use rand::rngs::OsRng;
@@ -209,4 +352,84 @@ mod tests {
     test_poseidon_ro_with::<Secp256k1Engine>();
     test_poseidon_ro_with::<Secq256k1Engine>();
   }
+
+  /// An engine over Pallas whose transcript is `PoseidonTranscript`
+  #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+  struct PallasPoseidonEngine;
+
+  impl Engine for PallasPoseidonEngine {
+    const ID: &'static str = "pallas-poseidon-test-v1";
+
+    type Base = pallas::Base;
+    type Scalar = pallas::Scalar;
+    type GE = pallas::Point;
+    type RO = PoseidonRO<Self::Base>;
+    type ROCircuit = PoseidonROCircuit<Self::Base>;
+    type RO2 = PoseidonRO<Self::Scalar>;
+    type RO2Circuit = PoseidonROCircuit<Self::Scalar>;
+    type TE = PoseidonTranscript<Self>;
+    type CE = PedersenCommitmentEngine<Self>;
+  }
+
+  #[test]
+  fn test_poseidon_transcript_ipa() {
+    type E = PallasPoseidonEngine;
+    type EE = crate::provider::ipa_pc::EvaluationEngine<E>;
+
+    let num_vars = 5;
+    let ck = <E as Engine>::CE::setup(b"test", 1 << num_vars);
+    let (pk, vk) = EE::setup(&ck);
+    let poly = (0..1 << num_vars)
+      .map(|_| pallas::Scalar::random(OsRng))
+      .collect::<Vec<_>>();
+    let point = (0..num_vars)
+      .map(|_| pallas::Scalar::random(OsRng))
+      .collect::<Vec<_>>();
+    let eval = MultilinearPolynomial::evaluate_with(&poly, &point);
+    let comm = <E as Engine>::CE::commit(&ck, &poly, &pallas::Scalar::ZERO);
+
+    let mut transcript = PoseidonTranscript::<E>::new(b"test");
+    let arg = EE::prove(&ck, &pk, &mut transcript, &comm, &poly, &point, &eval).unwrap();
+    let mut transcript = PoseidonTranscript::<E>::new(b"test");
+    assert!(EE::verify(&vk, &mut transcript, &comm, &point, &eval, &arg).is_ok());
+    let mut transcript = PoseidonTranscript::<E>::new(b"other");
+    assert!(EE::verify(&vk, &mut transcript, &comm, &point, &eval, &arg).is_err());
+  }
+
+  /// Absorbs a fixed sequence of values, buffering `batch_size` bytes at a time, and returns the
+  /// challenges squeezed in between
+  fn transcript_challenges(batch_size: usize) -> Vec<pallas::Scalar> {
+    let mut transcript =
+      PoseidonTranscript::<PallasPoseidonEngine>::new(b"layout").with_absorb_batch_size(batch_size);
+    let mut challenges = Vec::new();
+    for i in 0..4u64 {
+      transcript.dom_sep(b"round");
+      for j in 0..=i {
+        transcript.absorb(b"x", &pallas::Scalar::from(i * 10 + j));
+      }
+      challenges.push(transcript.squeeze(b"c").unwrap());
+    }
+    challenges
+  }
+
+  #[test]
+  fn test_transcript_layout() {
+    // batching absorbed values does not change the challenges
+    let expected = transcript_challenges(DEFAULT_ABSORB_BATCH_SIZE);
+    for batch_size in [0, 1, 40, 1 << 20] {
+      assert_eq!(transcript_challenges(batch_size), expected);
+    }
+
+    // the layout of the transcript is fixed
+    let expect =
+      expect_test::expect!["b1d188e658c382917095cb0f1af17c3560d67ce6ec27a6d851fe3de29088761a"];
+    expect.assert_eq(&hex::encode(expected.last().unwrap().to_repr()));
+
+    let mut ro = PoseidonRO::new(PoseidonConstantsCircuit::<pallas::Base>::default());
+    ro.absorb(pallas::Base::from(1));
+    ro.absorb_batch(&[pallas::Base::from(2), pallas::Base::from(3)]);
+    let expect =
+      expect_test::expect!["a388b83484bd7fadbb79744dccf39fa100000000000000000000000000000000"];
+    expect.assert_eq(&hex::encode(ro.squeeze(NUM_CHALLENGE_BITS).to_repr()));
+  }
 }
@@ -29,6 +29,36 @@ pub enum PtauFileError {
   #[error("Insufficient power for G2")]
   InsufficientPowerForG2 { power: u32, required: usize },
 
+  #[error("The key file is for a different curve")]
+  InvalidCurve,
+
+  #[error("Invalid point encoding")]
+  InvalidPointEncoding(u8),
+
+  #[error("Invalid point")]
+  InvalidPoint,
+
+  #[error("Point not in the prime-order subgroup")]
+  PointNotInSubgroup,
+
+  #[error("The powers of tau are inconsistent")]
+  InconsistentPowers,
+
+  #[error("Invalid label hash")]
+  InvalidLabelHash(u8),
+
+  #[error("The key file was derived with a different generator specification")]
+  GeneratorSpecMismatch,
+
+  #[error("The key file checksum does not match its contents")]
+  InvalidChecksum,
+
+  #[error("Lagrange-basis keys require a power-of-two number of generators")]
+  InvalidLagrangeDomain(usize),
+
+  #[error("Insufficient number of generators")]
+  InsufficientGenerators { count: usize, required: usize },
+
   #[error(transparent)]
   IoError(#[from] io::Error),
   #[error(transparent)]
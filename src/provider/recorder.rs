@@ -0,0 +1,399 @@
+//! This module provides `TranscriptRecorder`, a wrapper around any `TranscriptEngineTrait`
+//! implementation that logs every operation performed on the transcript.
+//!
+//! Recording is scoped to a `TranscriptSession` that is passed explicitly: the prover creates its
+//! transcripts with `TranscriptRecorder::with_session`, hands them to the functions that take a
+//! transcript (such as `EvaluationEngineTrait::prove`), and saves the `TranscriptLog` returned by
+//! `TranscriptSession::log`. A verifier (possibly running a different version of the library, or
+//! on a different machine) does the same around its calls to `verify` and compares both logs with
+//! `TranscriptLog::first_divergence`, which pinpoints the first absorb/squeeze on which the two
+//! parties disagree. Concurrent provers record into their own sessions.
+//!
+//! A `TranscriptRecorder` created with `TranscriptEngineTrait::new` belongs to no session and
+//! simply forwards to the wrapped transcript.
+use crate::{
+  encoding::{encode_scalar, ByteOrder},
+  errors::NovaError,
+  traits::{Engine, TranscriptEngineTrait, TranscriptReprTrait},
+};
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::HashMap,
+  io,
+  sync::{Arc, Mutex},
+};
+
+/// A single operation performed on a transcript
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TranscriptEvent {
+  /// An object was absorbed under `label`; `bytes` holds its transcript representation
+  Absorb {
+    /// The label under which the object was absorbed
+    label: Vec<u8>,
+    /// The transcript representation of the absorbed object
+    bytes: Vec<u8>,
+  },
+  /// A challenge was squeezed under `label`; `challenge` holds its canonical representation
+  Squeeze {
+    /// The label under which the challenge was squeezed
+    label: Vec<u8>,
+    /// The canonical value of the challenge, in little-endian order (see `encoding`)
+    challenge: Vec<u8>,
+  },
+  /// A domain separator was added
+  DomSep {
+    /// The domain separator
+    bytes: Vec<u8>,
+  },
+}
+
+/// The events of a single transcript, together with what identifies it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptRecord {
+  /// The identifier of the engine the transcript was instantiated for (see `Engine::ID`)
+  pub engine: String,
+  /// The label passed to `TranscriptEngineTrait::new`
+  pub label: Vec<u8>,
+  /// The events in the order in which they were applied
+  pub events: Vec<TranscriptEvent>,
+}
+
+/// A structured log of every transcript created with a recording session
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptLog {
+  records: Vec<TranscriptRecord>,
+}
+
+/// Describes the first point at which two transcript logs disagree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptDivergence {
+  /// The identifier of the engine of the diverging transcript
+  pub engine: String,
+  /// The label of the diverging transcript
+  pub label: Vec<u8>,
+  /// Among the transcripts with the same engine and label, the position of the diverging one
+  pub occurrence: usize,
+  /// The index of the first diverging event within that transcript
+  pub index: usize,
+  /// The event found in the reference log (`None` if the reference transcript ended earlier)
+  pub expected: Option<TranscriptEvent>,
+  /// The event found in the log being checked (`None` if that transcript ended earlier)
+  pub actual: Option<TranscriptEvent>,
+}
+
+impl TranscriptLog {
+  /// Returns the records of all transcripts created with the session
+  pub fn records(&self) -> &[TranscriptRecord] {
+    &self.records
+  }
+
+  /// Writes the log to the provided writer
+  pub fn save_to(&self, writer: &mut impl io::Write) -> Result<(), io::Error> {
+    bincode::serialize_into(writer, self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+  }
+
+  /// Reads a log previously written with `save_to`
+  pub fn load_from(reader: &mut impl io::Read) -> Result<Self, io::Error> {
+    bincode::deserialize_from(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+  }
+
+  /// Compares `self` against a `reference` log and returns the first divergence, if any.
+  ///
+  /// Transcripts are matched by engine, label, and order of creation among transcripts sharing
+  /// the same engine and label, so concurrently created transcripts for different engines
+  /// do not need to be interleaved identically in both logs.
+  pub fn first_divergence(&self, reference: &TranscriptLog) -> Option<TranscriptDivergence> {
+    let expected = reference.keyed();
+    let actual = self.keyed();
+
+    for (key, record) in reference.keyed_in_order() {
+      let (engine, label, occurrence) = key.clone();
+      let empty = Vec::new();
+      let expected_events = &record.events;
+      let actual_events = actual.get(&key).map_or(&empty, |r| &r.events);
+      if let Some(index) = first_mismatch(expected_events, actual_events) {
+        return Some(TranscriptDivergence {
+          engine,
+          label,
+          occurrence,
+          index,
+          expected: expected_events.get(index).cloned(),
+          actual: actual_events.get(index).cloned(),
+        });
+      }
+    }
+
+    // transcripts that only exist in `self`
+    for (key, record) in self.keyed_in_order() {
+      if !expected.contains_key(&key) {
+        let (engine, label, occurrence) = key;
+        return Some(TranscriptDivergence {
+          engine,
+          label,
+          occurrence,
+          index: 0,
+          expected: None,
+          actual: record.events.first().cloned(),
+        });
+      }
+    }
+
+    None
+  }
+
+  fn keyed_in_order(&self) -> Vec<((String, Vec<u8>, usize), &TranscriptRecord)> {
+    let mut occurrences: HashMap<(String, Vec<u8>), usize> = HashMap::new();
+    self
+      .records
+      .iter()
+      .map(|r| {
+        let n = occurrences
+          .entry((r.engine.clone(), r.label.clone()))
+          .or_insert(0);
+        let key = (r.engine.clone(), r.label.clone(), *n);
+        *n += 1;
+        (key, r)
+      })
+      .collect()
+  }
+
+  fn keyed(&self) -> HashMap<(String, Vec<u8>, usize), &TranscriptRecord> {
+    self.keyed_in_order().into_iter().collect()
+  }
+}
+
+fn first_mismatch(expected: &[TranscriptEvent], actual: &[TranscriptEvent]) -> Option<usize> {
+  let common = expected.len().min(actual.len());
+  (0..common)
+    .find(|&i| expected[i] != actual[i])
+    .or(if expected.len() != actual.len() {
+      Some(common)
+    } else {
+      None
+    })
+}
+
+/// A recording session, which collects the logs of the transcripts created with it. Clones share
+/// the same log.
+#[derive(Clone, Debug, Default)]
+pub struct TranscriptSession(Arc<Mutex<TranscriptLog>>);
+
+impl TranscriptSession {
+  /// Creates a session with an empty log
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns the log of the transcripts created with the session so far
+  pub fn log(&self) -> TranscriptLog {
+    self.0.lock().unwrap().clone()
+  }
+}
+
+/// A transcript engine that forwards to `TE` while logging every operation to its session, if any.
+///
+/// A clone of a recorder logs to a new record of the same session, which starts with the events
+/// logged so far, so a transcript forked for a sub-protocol does not interleave its events with
+/// those of the original.
+#[derive(Debug)]
+pub struct TranscriptRecorder<E: Engine, TE: TranscriptEngineTrait<E>> {
+  inner: TE,
+  record: Option<(TranscriptSession, usize)>, // the session and the index of the record in its log
+  _p: PhantomData<E>,
+}
+
+impl<E: Engine, TE: TranscriptEngineTrait<E>> TranscriptRecorder<E, TE> {
+  /// Creates a transcript with `label` whose operations are logged to `session`
+  pub fn with_session(label: &'static [u8], session: &TranscriptSession) -> Self {
+    let index = {
+      let mut log = session.0.lock().unwrap();
+      log.records.push(TranscriptRecord {
+        engine: E::ID.to_string(),
+        label: label.to_vec(),
+        events: Vec::new(),
+      });
+      log.records.len() - 1
+    };
+
+    Self {
+      inner: TE::new(label),
+      record: Some((session.clone(), index)),
+      _p: PhantomData,
+    }
+  }
+
+  fn log(&self, event: TranscriptEvent) {
+    if let Some((session, index)) = &self.record {
+      session.0.lock().unwrap().records[*index].events.push(event);
+    }
+  }
+}
+
+impl<E: Engine, TE: TranscriptEngineTrait<E> + Clone> Clone for TranscriptRecorder<E, TE> {
+  fn clone(&self) -> Self {
+    let record = self.record.as_ref().map(|(session, index)| {
+      let mut log = session.0.lock().unwrap();
+      let record = log.records[*index].clone();
+      log.records.push(record);
+      (session.clone(), log.records.len() - 1)
+    });
+
+    Self {
+      inner: self.inner.clone(),
+      record,
+      _p: PhantomData,
+    }
+  }
+}
+
+impl<E: Engine, TE: TranscriptEngineTrait<E>> TranscriptEngineTrait<E>
+  for TranscriptRecorder<E, TE>
+{
+  fn new(label: &'static [u8]) -> Self {
+    Self {
+      inner: TE::new(label),
+      record: None,
+      _p: PhantomData,
+    }
+  }
+
+  fn squeeze(&mut self, label: &'static [u8]) -> Result<E::Scalar, NovaError> {
+    let c = self.inner.squeeze(label)?;
+    self.log(TranscriptEvent::Squeeze {
+      label: label.to_vec(),
+      challenge: encode_scalar(&c, ByteOrder::LittleEndian),
+    });
+    Ok(c)
+  }
+
+  fn absorb<T: TranscriptReprTrait<E::GE>>(&mut self, label: &'static [u8], o: &T) {
+    self.log(TranscriptEvent::Absorb {
+      label: label.to_vec(),
+      bytes: o.to_transcript_bytes(),
+    });
+    self.inner.absorb(label, o);
+  }
+
+  fn dom_sep(&mut self, bytes: &'static [u8]) {
+    self.log(TranscriptEvent::DomSep {
+      bytes: bytes.to_vec(),
+    });
+    self.inner.dom_sep(bytes);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::provider::{keccak::Keccak256Transcript, PallasEngine};
+
+  type E = PallasEngine;
+  type TE = TranscriptRecorder<E, Keccak256Transcript<E>>;
+  type Scalar = <E as Engine>::Scalar;
+
+  fn run(session: Option<&TranscriptSession>, last: u64) -> Scalar {
+    let mut transcript = match session {
+      Some(session) => TE::with_session(b"test", session),
+      None => TE::new(b"test"),
+    };
+    transcript.absorb(b"s1", &Scalar::from(2u64));
+    transcript.dom_sep(b"ds");
+    let _ = transcript.squeeze(b"c1").unwrap();
+    transcript.absorb(b"s2", &Scalar::from(last));
+    transcript.squeeze(b"c2").unwrap()
+  }
+
+  #[test]
+  fn test_record_and_replay() {
+    // a recorder without a session is a transparent wrapper
+    let mut reference = Keccak256Transcript::<E>::new(b"test");
+    reference.absorb(b"s1", &Scalar::from(2u64));
+    reference.dom_sep(b"ds");
+    let _ = reference.squeeze(b"c1").unwrap();
+    reference.absorb(b"s2", &Scalar::from(5u64));
+    assert_eq!(run(None, 5), reference.squeeze(b"c2").unwrap());
+
+    // "prover" side
+    let session = TranscriptSession::new();
+    run(Some(&session), 5);
+    let prover_log = session.log();
+    assert_eq!(prover_log.records().len(), 1);
+    assert_eq!(prover_log.records()[0].events.len(), 5);
+
+    let mut bytes = Vec::new();
+    prover_log.save_to(&mut bytes).unwrap();
+    let prover_log = TranscriptLog::load_from(&mut bytes.as_slice()).unwrap();
+
+    // "verifier" side that agrees
+    let session = TranscriptSession::new();
+    run(Some(&session), 5);
+    let verifier_log = session.log();
+    assert_eq!(verifier_log.first_divergence(&prover_log), None);
+
+    // "verifier" side that absorbs a different value
+    let session = TranscriptSession::new();
+    run(Some(&session), 6);
+    let verifier_log = session.log();
+    let divergence = verifier_log.first_divergence(&prover_log).unwrap();
+    assert_eq!(divergence.label, b"test".to_vec());
+    assert_eq!(divergence.occurrence, 0);
+    assert_eq!(divergence.index, 3);
+    assert!(matches!(
+      divergence.actual,
+      Some(TranscriptEvent::Absorb { ref label, .. }) if label == b"s2"
+    ));
+
+    // "verifier" side that stops early
+    let session = TranscriptSession::new();
+    let _ = TE::with_session(b"test", &session);
+    let verifier_log = session.log();
+    let divergence = verifier_log.first_divergence(&prover_log).unwrap();
+    assert_eq!(divergence.index, 0);
+    assert_eq!(divergence.actual, None);
+  }
+
+  #[test]
+  fn test_clone() {
+    // a clone logs to its own record, which starts with the events of the original
+    let session = TranscriptSession::new();
+    let mut transcript = TE::with_session(b"test", &session);
+    transcript.absorb(b"s1", &Scalar::from(2u64));
+    let mut fork = transcript.clone();
+    fork.absorb(b"s2", &Scalar::from(3u64));
+    transcript.dom_sep(b"ds");
+
+    let log = session.log();
+    assert_eq!(log.records().len(), 2);
+    let (original, forked) = (&log.records()[0].events, &log.records()[1].events);
+    assert_eq!(original.len(), 2);
+    assert_eq!(forked.len(), 2);
+    assert_eq!(original[0], forked[0]);
+    assert!(matches!(original[1], TranscriptEvent::DomSep { .. }));
+    assert!(matches!(forked[1], TranscriptEvent::Absorb { ref label, .. } if label == b"s2"));
+  }
+
+  #[test]
+  fn test_concurrent_sessions() {
+    // transcripts recorded concurrently land in the logs of their own sessions
+    let sessions = [TranscriptSession::new(), TranscriptSession::new()];
+    std::thread::scope(|s| {
+      for (session, last) in sessions.iter().zip([5, 6]) {
+        s.spawn(move || {
+          for _ in 0..10 {
+            run(Some(session), last);
+          }
+        });
+      }
+    });
+
+    for (session, last) in sessions.iter().zip([5, 6]) {
+      let expected = TranscriptSession::new();
+      for _ in 0..10 {
+        run(Some(&expected), last);
+      }
+      assert_eq!(session.log().records().len(), 10);
+      assert_eq!(session.log(), expected.log());
+    }
+  }
+}
@@ -7,7 +7,6 @@ use crate::{
   },
   traits::{Group, PrimeFieldExt, TranscriptReprTrait},
 };
-use digest::{ExtendableOutput, Update};
 use ff::FromUniformBytes;
 use halo2curves::{
   group::{cofactor::CofactorCurveAffine, Curve, Group as AnotherGroup},
@@ -19,8 +18,6 @@ use num_bigint::BigInt;
 use num_integer::Integer;
 use num_traits::{Num, ToPrimitive};
 use rayon::prelude::*;
-use sha3::Shake256;
-use std::io::Read;
 
 /// Re-exports that give access to the standard aliases used in the code base, for secp
 pub mod secp256k1 {
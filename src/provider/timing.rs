@@ -0,0 +1,166 @@
+//! This module times the expensive operations of the providers, such as multi-scalar
+//! multiplications, without writing anything to the standard output.
+//!
+//! With the `timing` feature, which is enabled by default, every timed operation emits a `tracing`
+//! event at the `DEBUG` level with the target `TARGET` when it completes. The event has the fields
+//! * `op`: the operation, e.g., `msm` or `msm_small`,
+//! * `backend`: the implementation that ran it, e.g., `cpu` or `blitzar`,
+//! * `size`: the size of the operation, e.g., the number of scalars,
+//! * `elapsed_us`: its duration in microseconds, and
+//! * `thread`: the identifier of the thread that ran it.
+//!
+//! The phases of proving and verifying, e.g., `prove_step` or `compress`, are `DEBUG` spans with the
+//! same target, within which the operations that run on the calling thread are timed.
+//!
+//! With the `blitzar` feature, every call into Blitzar is a `DEBUG` span named `blitzar` with the same
+//! target and the fields `op`, `device`, `transfer_bytes` and `result_bytes`, the sizes of the copies
+//! to and from the device, and `device_us`, the duration of the call. The batches of an `MsmQueue` are
+//! `blitzar_queue` spans whose `queued_us` is the time from their submission to the device stage.
+//!
+//! Events are only recorded when the application installs a `tracing` subscriber, which selects
+//! them as usual, e.g., with an env-filter such as `RUST_LOG=nova_snark::timing=debug`. Building
+//! without the feature removes the timing altogether.
+
+/// The target of the events emitted by this module
+pub const TARGET: &str = "nova_snark::timing";
+
+/// Times an operation from its creation until it is dropped
+#[cfg(feature = "timing")]
+pub(crate) struct Timer {
+  op: &'static str,
+  backend: &'static str,
+  size: usize,
+  start: std::time::Instant,
+}
+
+/// Times an operation from its creation until it is dropped
+#[cfg(not(feature = "timing"))]
+pub(crate) struct Timer;
+
+/// Starts timing the operation `op` of size `size`, run by `backend`
+#[cfg(feature = "timing")]
+pub(crate) fn start(op: &'static str, backend: &'static str, size: usize) -> Timer {
+  Timer {
+    op,
+    backend,
+    size,
+    start: std::time::Instant::now(),
+  }
+}
+
+/// Starts timing the operation `op` of size `size`, run by `backend`
+#[cfg(not(feature = "timing"))]
+pub(crate) fn start(_op: &'static str, _backend: &'static str, _size: usize) -> Timer {
+  Timer
+}
+
+/// Enters a span for the phase `$name` of proving or verifying, which lasts until the returned
+/// guard is dropped
+#[cfg(feature = "timing")]
+macro_rules! phase {
+  ($name:literal) => {
+    tracing::debug_span!(target: $crate::provider::timing::TARGET, $name).entered()
+  };
+}
+
+/// Enters a span for the phase `$name` of proving or verifying, which lasts until the returned
+/// guard is dropped
+#[cfg(not(feature = "timing"))]
+macro_rules! phase {
+  ($name:literal) => {
+    $crate::provider::timing::Timer
+  };
+}
+
+pub(crate) use phase;
+
+#[cfg(feature = "timing")]
+impl Drop for Timer {
+  fn drop(&mut self) {
+    tracing::debug!(
+      target: TARGET,
+      op = self.op,
+      backend = self.backend,
+      size = self.size,
+      elapsed_us = self.start.elapsed().as_micros() as u64,
+      thread = ?std::thread::current().id(),
+    );
+  }
+}
+
+#[cfg(all(test, feature = "timing"))]
+mod tests {
+  use super::*;
+  use crate::provider::{bn256_grumpkin::bn256, msm::msm};
+  use ff::Field;
+  use rand_core::OsRng;
+  use std::sync::{Arc, Mutex};
+  use tracing::{
+    field::{Field as EventField, Visit},
+    span, Event, Metadata, Subscriber,
+  };
+
+  /// A subscriber that records the fields of the timing events
+  #[derive(Default)]
+  struct Recorder(Arc<Mutex<Vec<Vec<(String, String)>>>>);
+
+  struct Fields(Vec<(String, String)>);
+
+  impl Visit for Fields {
+    fn record_debug(&mut self, field: &EventField, value: &dyn std::fmt::Debug) {
+      self
+        .0
+        .push((field.name().to_string(), format!("{value:?}")));
+    }
+  }
+
+  impl Subscriber for Recorder {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+      metadata.target() == TARGET
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+      span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+      let mut fields = Fields(Vec::new());
+      event.record(&mut fields);
+      self.0.lock().unwrap().push(fields.0);
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+  }
+
+  #[test]
+  fn test_timing_events() {
+    let recorder = Recorder::default();
+    let events = recorder.0.clone();
+
+    let bases = vec![bn256::Affine::generator(); 10];
+    let scalars = (0..10)
+      .map(|_| bn256::Scalar::random(OsRng))
+      .collect::<Vec<_>>();
+    tracing::subscriber::with_default(recorder, || msm(&scalars, &bases));
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    let field = |name: &str| {
+      events[0]
+        .iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, value)| value.clone())
+    };
+    assert_eq!(field("op").as_deref(), Some("\"msm\""));
+    assert_eq!(field("backend").as_deref(), Some("\"cpu\""));
+    assert_eq!(field("size").as_deref(), Some("10"));
+    assert!(field("elapsed_us").is_some());
+    assert!(field("thread").is_some());
+  }
+}
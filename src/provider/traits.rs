@@ -1,13 +1,119 @@
-use crate::traits::{commitment::ScalarMul, Group, TranscriptReprTrait};
+use crate::{
+  errors::NovaError,
+  traits::{
+    commitment::{BlindingGenerator, ScalarMul},
+    Engine, Group, TranscriptReprTrait,
+  },
+};
 use core::{
+  any::Any,
   fmt::Debug,
+  marker::PhantomData,
   ops::{Add, AddAssign, Sub, SubAssign},
 };
 use halo2curves::{serde::SerdeObject, CurveAffine};
 use num_integer::Integer;
 use num_traits::ToPrimitive;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use once_cell::sync::OnceCell;
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
+use sha3::{
+  digest::{ExtendableOutput, Update, XofReader},
+  Digest, Keccak256, Sha3_256, Shake256,
+};
+use std::sync::Arc;
+
+/// The hash function that expands a label into the uniform bytes that are mapped to generators
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum LabelHash {
+  /// Reads 32 bytes per generator from the SHAKE256 stream of the label
+  Shake256,
+  /// Hashes the label followed by the index of each generator, as a little-endian `u64`, with
+  /// SHA3-256
+  Sha3_256,
+  /// Hashes the label followed by the index of each generator, as a little-endian `u64`, with
+  /// Keccak256
+  Keccak256,
+}
+
+impl LabelHash {
+  pub(crate) fn to_u8(self) -> u8 {
+    match self {
+      LabelHash::Shake256 => 0,
+      LabelHash::Sha3_256 => 1,
+      LabelHash::Keccak256 => 2,
+    }
+  }
+
+  pub(crate) fn from_u8(v: u8) -> Option<Self> {
+    match v {
+      0 => Some(LabelHash::Shake256),
+      1 => Some(LabelHash::Sha3_256),
+      2 => Some(LabelHash::Keccak256),
+      _ => None,
+    }
+  }
+}
+
+/// Specifies how `DlogGroup::from_label_with` derives generators from a label: the label is expanded
+/// into 32 uniform bytes per generator with `hash`, and each string of bytes is mapped to the curve
+/// with the curve's hash-to-curve function, under the domain `domain`.
+///
+/// The default specification, a SHAKE256 stream and the domain `from_uniform_bytes`, is the one
+/// that upstream Nova uses, so keys derived with it are interchangeable with upstream keys.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneratorSpec {
+  /// The domain of the hash-to-curve function
+  pub domain: String,
+  /// The hash function that expands labels
+  pub hash: LabelHash,
+}
+
+impl Default for GeneratorSpec {
+  fn default() -> Self {
+    Self {
+      domain: "from_uniform_bytes".to_string(),
+      hash: LabelHash::Shake256,
+    }
+  }
+}
+
+impl GeneratorSpec {
+  /// Expands `label` into `n` strings of 32 uniform bytes
+  pub(crate) fn uniform_bytes(&self, label: &[u8], n: usize) -> Vec<[u8; 32]> {
+    match self.hash {
+      LabelHash::Shake256 => {
+        let mut shake = Shake256::default();
+        shake.update(label);
+        let mut reader = shake.finalize_xof();
+        (0..n)
+          .map(|_| {
+            let mut uniform_bytes = [0u8; 32];
+            reader.read(&mut uniform_bytes);
+            uniform_bytes
+          })
+          .collect()
+      }
+      LabelHash::Sha3_256 => (0..n)
+        .map(|i| {
+          Sha3_256::new_with_prefix(label)
+            .chain_update((i as u64).to_le_bytes())
+            .finalize()
+            .into()
+        })
+        .collect(),
+      LabelHash::Keccak256 => (0..n)
+        .map(|i| {
+          Keccak256::new_with_prefix(label)
+            .chain_update((i as u64).to_le_bytes())
+            .finalize()
+            .into()
+        })
+        .collect(),
+    }
+  }
+}
 
 /// A helper trait for types with a group operation.
 pub trait GroupOps<Rhs = Self, Output = Self>:
@@ -28,6 +134,26 @@ impl<T, Rhs, Output> GroupOpsOwned<Rhs, Output> for T where T: for<'r> GroupOps<
 pub trait ScalarMulOwned<Rhs, Output = Self>: for<'r> ScalarMul<&'r Rhs, Output> {}
 impl<T, Rhs, Output> ScalarMulOwned<Rhs, Output> for T where T: for<'r> ScalarMul<&'r Rhs, Output> {}
 
+/// Returns the blinding generator of a commitment key that `blinding` selects, where `h` is the one
+/// derived from the label of the key. Fixed generators must not be the identity, as commitments
+/// blinded with it would not hide.
+pub(crate) fn blinding_generator<E: Engine>(
+  blinding: &BlindingGenerator<E>,
+  h: <E::GE as DlogGroup>::AffineGroupElement,
+) -> Result<<E::GE as DlogGroup>::AffineGroupElement, NovaError>
+where
+  E::GE: DlogGroup,
+{
+  match blinding {
+    BlindingGenerator::FromKeyLabel => Ok(h),
+    BlindingGenerator::Label(label) => Ok(E::GE::from_label(label, 1).remove(0)),
+    BlindingGenerator::Fixed(h) if *h == E::GE::zero() => Err(NovaError::SetupError {
+      reason: "the blinding generator is the identity".to_string(),
+    }),
+    BlindingGenerator::Fixed(h) => Ok(h.affine()),
+  }
+}
+
 /// A trait that defines the core discrete logarithm group functionality
 pub trait DlogGroup:
   Group
@@ -51,12 +177,24 @@ pub trait DlogGroup:
     + CurveAffine
     + SerdeObject;
 
-  /// Produce a vector of group elements using a static label
-  fn from_label(label: &'static [u8], n: usize) -> Vec<Self::AffineGroupElement>;
+  /// Produce a vector of group elements using a static label, with the default `GeneratorSpec`
+  fn from_label(label: &'static [u8], n: usize) -> Vec<Self::AffineGroupElement> {
+    Self::from_label_with(&GeneratorSpec::default(), label, n)
+  }
+
+  /// Produce a vector of group elements from a label with the specification `spec`
+  fn from_label_with(spec: &GeneratorSpec, label: &[u8], n: usize)
+    -> Vec<Self::AffineGroupElement>;
 
   /// Produces a preprocessed element
   fn affine(&self) -> Self::AffineGroupElement;
 
+  /// Produces preprocessed elements, normalizing them in batches when the group supports it (see
+  /// `provider::util::batch_to_affine`)
+  fn batch_affine(points: &[Self]) -> Vec<Self::AffineGroupElement> {
+    points.par_iter().map(|p| p.affine()).collect()
+  }
+
   /// Returns a group element from a preprocessed group element
   fn group(p: &Self::AffineGroupElement) -> Self;
 
@@ -68,13 +206,60 @@ pub trait DlogGroup:
 
   /// Returns the affine coordinates (x, y, infinity) for the point
   fn to_coordinates(&self) -> (<Self as Group>::Base, <Self as Group>::Base, bool);
+
+  /// Returns the affine coordinates (x, y, infinity) for a preprocessed point, which groups
+  /// override to read them off without normalizing the point again
+  fn affine_to_coordinates(
+    p: &Self::AffineGroupElement,
+  ) -> (<Self as Group>::Base, <Self as Group>::Base, bool) {
+    Self::group(p).to_coordinates()
+  }
+
+  /// Returns the affine coordinates (x, y, infinity) for each of the points, normalizing them with
+  /// `batch_affine` so that groups that support it invert once rather than once per point
+  fn batch_to_coordinates(
+    points: &[Self],
+  ) -> Vec<(<Self as Group>::Base, <Self as Group>::Base, bool)> {
+    Self::batch_affine(points)
+      .par_iter()
+      .map(Self::affine_to_coordinates)
+      .collect()
+  }
+
+  /// Returns the affine coordinates (x, y, infinity) for the point as limbs over the base field:
+  /// one limb per coordinate for groups defined over the base field, and one limb per coefficient
+  /// for groups defined over an extension of it, such as the G2 group of pairing-friendly curves
+  fn to_coordinate_limbs(&self) -> (Vec<<Self as Group>::Base>, Vec<<Self as Group>::Base>, bool) {
+    let (x, y, is_infinity) = self.to_coordinates();
+    (vec![x], vec![y], is_infinity)
+  }
 }
 
 /// Extension trait for DlogGroup that provides multi-scalar multiplication operations
 pub trait DlogGroupExt: DlogGroup {
+  /// Bases of multiexponentations in the representation preferred by the MSM backend of the group,
+  /// which is `()` when the backend computes with affine bases directly
+  type PreparedBases: Clone + Debug + Send + Sync + 'static;
+
+  /// Converts bases into the representation preferred by the MSM backend, so that the conversion
+  /// is performed once rather than in every multiexponentation
+  fn prepare_bases(bases: &[Self::AffineGroupElement]) -> Self::PreparedBases;
+
   /// A method to compute a multiexponentation
   fn vartime_multiscalar_mul(scalars: &[Self::Scalar], bases: &[Self::AffineGroupElement]) -> Self;
 
+  /// A method to compute a multiexponentation with `bases[offset..offset + scalars.len()]`, which
+  /// uses `prepared`, the output of `prepare_bases(bases)`, if it is provided and the current
+  /// backend can use it
+  fn vartime_multiscalar_mul_prepared(
+    scalars: &[Self::Scalar],
+    bases: &[Self::AffineGroupElement],
+    _prepared: Option<&Self::PreparedBases>,
+    offset: usize,
+  ) -> Self {
+    Self::vartime_multiscalar_mul(scalars, &bases[offset..offset + scalars.len()])
+  }
+
   /// A method to compute a batch of multiexponentations
   fn batch_vartime_multiscalar_mul(
     scalars: &[Vec<Self::Scalar>],
@@ -86,6 +271,29 @@ pub trait DlogGroupExt: DlogGroup {
       .collect::<Vec<_>>()
   }
 
+  /// A method to compute a batch of multiexponentations, which uses `prepared`, the output of
+  /// `prepare_bases(bases)`, if it is provided and the current backend can use it
+  fn batch_vartime_multiscalar_mul_prepared(
+    scalars: &[Vec<Self::Scalar>],
+    bases: &[Self::AffineGroupElement],
+    _prepared: Option<&Self::PreparedBases>,
+  ) -> Vec<Self> {
+    Self::batch_vartime_multiscalar_mul(scalars, bases)
+  }
+
+  /// A method to compute a multiexponentation of `scalars` with the bases at `indices`, skipping
+  /// the zero scalars, which uses `prepared`, the output of `prepare_bases(bases)`, if it is
+  /// provided and the current backend can use it
+  fn vartime_multiscalar_mul_sparse(
+    indices: &[usize],
+    scalars: &[Self::Scalar],
+    bases: &[Self::AffineGroupElement],
+    _prepared: Option<&Self::PreparedBases>,
+  ) -> Self {
+    let (scalars, bases) = sparse_terms(indices, scalars, bases);
+    Self::vartime_multiscalar_mul(&scalars, &bases)
+  }
+
   /// A method to compute a multiexponentation with small scalars
   fn vartime_multiscalar_mul_small<T: Integer + Into<u64> + Copy + Sync + ToPrimitive>(
     scalars: &[T],
@@ -104,6 +312,72 @@ pub trait DlogGroupExt: DlogGroup {
   }
 }
 
+/// Returns the nonzero entries of `scalars` along with the entries of `bases` at the matching
+/// `indices`, which are the terms of a sparse multiexponentation
+pub(crate) fn sparse_terms<F: ff::Field, B: Clone + Send + Sync>(
+  indices: &[usize],
+  scalars: &[F],
+  bases: &[B],
+) -> (Vec<F>, Vec<B>) {
+  assert_eq!(indices.len(), scalars.len());
+  indices
+    .par_iter()
+    .zip(scalars.par_iter())
+    .filter(|(_, s)| !bool::from(s.is_zero()))
+    .map(|(i, s)| (*s, bases[*i].clone()))
+    .unzip()
+}
+
+/// Caches the representation of a commitment key's bases for the MSM backend of the group.
+///
+/// The cached representation is derived from the bases, so it is neither serialized nor taken into
+/// account when comparing keys. It is stored without its type, so that keys holding the cell only
+/// require `DlogGroup` of their group, while preparing and reading it requires `DlogGroupExt`.
+pub(crate) struct PreparedBasesCell<G: DlogGroup>(
+  OnceCell<Arc<dyn Any + Send + Sync>>,
+  PhantomData<fn() -> G>,
+);
+
+impl<G: DlogGroupExt> PreparedBasesCell<G> {
+  /// Returns the cached representation, if the bases were prepared
+  pub(crate) fn get(&self) -> Option<&G::PreparedBases> {
+    self.0.get().and_then(|prepared| prepared.downcast_ref())
+  }
+
+  /// Prepares `bases` unless they were already prepared
+  pub(crate) fn prepare(&self, bases: &[G::AffineGroupElement]) {
+    self.0.get_or_init(|| Arc::new(G::prepare_bases(bases)));
+  }
+}
+
+impl<G: DlogGroup> Default for PreparedBasesCell<G> {
+  fn default() -> Self {
+    Self(OnceCell::new(), PhantomData)
+  }
+}
+
+impl<G: DlogGroup> Clone for PreparedBasesCell<G> {
+  fn clone(&self) -> Self {
+    Self(self.0.clone(), PhantomData)
+  }
+}
+
+impl<G: DlogGroup> Debug for PreparedBasesCell<G> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("PreparedBasesCell")
+      .field("prepared", &self.0.get().is_some())
+      .finish()
+  }
+}
+
+impl<G: DlogGroup> PartialEq for PreparedBasesCell<G> {
+  fn eq(&self, _other: &Self) -> bool {
+    true
+  }
+}
+
+impl<G: DlogGroup> Eq for PreparedBasesCell<G> {}
+
 /// A trait that defines extensions to the DlogGroup trait, to be implemented for
 /// elliptic curve groups that are pairing friendly
 pub trait PairingGroup: DlogGroupExt {
@@ -115,6 +389,12 @@ pub trait PairingGroup: DlogGroupExt {
 
   /// A method to compute a pairing
   fn pairing(p: &Self, q: &Self::G2) -> Self::GT;
+
+  /// Checks that `e(p1, q1) == e(p2, q2)`. Implementations should override this to share the final
+  /// exponentiation between both sides, which halves the cost of the check.
+  fn pairing_eq(p1: &Self, q1: &Self::G2, p2: &Self, q2: &Self::G2) -> bool {
+    Self::pairing(p1, q1) == Self::pairing(p2, q2)
+  }
 }
 
 /// Implements Nova's traits except DlogGroupExt so that the MSM can be implemented differently
@@ -152,50 +432,25 @@ macro_rules! impl_traits_no_dlog_ext {
         $name::Point::from(*p)
       }
 
-      fn from_label(label: &'static [u8], n: usize) -> Vec<Self::AffineGroupElement> {
-        let mut shake = Shake256::default();
-        shake.update(label);
-        let mut reader = shake.finalize_xof();
-        let mut uniform_bytes_vec = Vec::new();
-        for _ in 0..n {
-          let mut uniform_bytes = [0u8; 32];
-          reader.read_exact(&mut uniform_bytes).unwrap();
-          uniform_bytes_vec.push(uniform_bytes);
-        }
+      fn from_label_with(
+        spec: &$crate::provider::GeneratorSpec,
+        label: &[u8],
+        n: usize,
+      ) -> Vec<Self::AffineGroupElement> {
+        let uniform_bytes_vec = spec.uniform_bytes(label, n);
         let gens_proj: Vec<$name_curve> = (0..n)
           .into_par_iter()
           .map(|i| {
-            let hash = $name_curve::hash_to_curve("from_uniform_bytes");
+            let hash = $name_curve::hash_to_curve(&spec.domain);
             hash(&uniform_bytes_vec[i])
           })
           .collect();
 
-        let num_threads = rayon::current_num_threads();
-        if gens_proj.len() > num_threads {
-          let chunk = (gens_proj.len() as f64 / num_threads as f64).ceil() as usize;
-          (0..num_threads)
-            .into_par_iter()
-            .flat_map(|i| {
-              let start = i * chunk;
-              let end = if i == num_threads - 1 {
-                gens_proj.len()
-              } else {
-                core::cmp::min((i + 1) * chunk, gens_proj.len())
-              };
-              if end > start {
-                let mut gens = vec![$name_curve_affine::identity(); end - start];
-                <Self as Curve>::batch_normalize(&gens_proj[start..end], &mut gens);
-                gens
-              } else {
-                vec![]
-              }
-            })
-            .collect()
-        } else {
-          let mut gens = vec![$name_curve_affine::identity(); n];
-          <Self as Curve>::batch_normalize(&gens_proj, &mut gens);
-          gens
-        }
+        Self::batch_affine(&gens_proj)
+      }
+
+      fn batch_affine(points: &[Self]) -> Vec<Self::AffineGroupElement> {
+        $crate::provider::util::batch_to_affine(points)
       }
 
       fn zero() -> Self {
@@ -207,11 +462,14 @@ macro_rules! impl_traits_no_dlog_ext {
       }
 
       fn to_coordinates(&self) -> (Self::Base, Self::Base, bool) {
-        let coordinates = self.affine().coordinates();
-        if coordinates.is_some().unwrap_u8() == 1
-          && ($name_curve_affine::identity() != self.affine())
-        {
-          (*coordinates.unwrap().x(), *coordinates.unwrap().y(), false)
+        Self::affine_to_coordinates(&self.affine())
+      }
+
+      fn affine_to_coordinates(p: &Self::AffineGroupElement) -> (Self::Base, Self::Base, bool) {
+        let coordinates = p.coordinates();
+        if coordinates.is_some().unwrap_u8() == 1 && ($name_curve_affine::identity() != *p) {
+          let coordinates = coordinates.unwrap();
+          (*coordinates.x(), *coordinates.y(), false)
         } else {
           (Self::Base::zero(), Self::Base::zero(), true)
         }
@@ -227,16 +485,14 @@ macro_rules! impl_traits_no_dlog_ext {
 
     impl<G: Group> TranscriptReprTrait<G> for $name::Scalar {
       fn to_transcript_bytes(&self) -> Vec<u8> {
-        self.to_bytes().into_iter().rev().collect()
+        $crate::encoding::encode_scalar(self, $crate::encoding::TRANSCRIPT_BYTE_ORDER)
       }
     }
 
     impl<G: DlogGroup> TranscriptReprTrait<G> for $name::Affine {
       fn to_transcript_bytes(&self) -> Vec<u8> {
         let coords = self.coordinates().unwrap();
-        let x_bytes = coords.x().to_bytes().into_iter();
-        let y_bytes = coords.y().to_bytes().into_iter();
-        x_bytes.rev().chain(y_bytes.rev()).collect()
+        $crate::encoding::encode_coordinates(coords.x(), coords.y())
       }
     }
   };
@@ -261,6 +517,10 @@ macro_rules! impl_traits {
     );
 
     impl DlogGroupExt for $name::Point {
+      type PreparedBases = ();
+
+      fn prepare_bases(_bases: &[Self::AffineGroupElement]) -> Self::PreparedBases {}
+
       fn vartime_multiscalar_mul(
         scalars: &[Self::Scalar],
         bases: &[Self::AffineGroupElement],
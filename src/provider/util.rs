@@ -0,0 +1,239 @@
+//! This module provides utilities shared by the providers
+use crate::{errors::NovaError, provider::traits::DlogGroup};
+use core::ops::{Add, AddAssign, Mul, Sub};
+use ff::{PrimeField, PrimeFieldBits};
+use halo2curves::group::Curve;
+use rayon::prelude::*;
+
+/// Returns the number of chunks to split parallel work into, one per thread of the current rayon
+/// pool. The arithmetic is exact, so results do not depend on the chunking.
+pub(crate) fn num_chunks() -> usize {
+  rayon::current_num_threads()
+}
+
+/// Converts points to their affine representation with one field inversion per chunk, by
+/// splitting them into `num_chunks()` chunks and normalizing each chunk in a batch
+pub fn batch_to_affine<C>(points: &[C]) -> Vec<C::AffineRepr>
+where
+  C: Curve + Send + Sync,
+  C::AffineRepr: Copy + Send + Sync,
+{
+  if points.is_empty() {
+    return Vec::new();
+  }
+
+  let chunk = points.len().div_ceil(num_chunks());
+  let mut affine = vec![C::identity().to_affine(); points.len()];
+  points
+    .par_chunks(chunk)
+    .zip(affine.par_chunks_mut(chunk))
+    .for_each(|(points, affine)| C::batch_normalize(points, affine));
+  affine
+}
+
+// * Implementation of https://www.weimerskirch.org/files/Weimerskirch_FixedBase.pdf
+pub(crate) fn fixed_base_exp_comb_batch<
+  const H: usize,
+  const POW_2_H: usize,
+  const A: usize,
+  const B: usize,
+  const V: usize,
+  G: DlogGroup,
+>(
+  gen: G,
+  scalars: &[G::Scalar],
+) -> Vec<G> {
+  assert_eq!(1 << H, POW_2_H);
+  assert_eq!(A, V * B);
+  assert!(A <= 64);
+
+  let zero = G::zero();
+  let one = gen;
+
+  let gi = {
+    let mut res = [one; H];
+    for i in 1..H {
+      let prod = (0..A).fold(res[i - 1], |acc, _| acc + acc);
+      res[i] = prod;
+    }
+    res
+  };
+
+  let mut precompute_res = (1..POW_2_H)
+    .into_par_iter()
+    .map(|i| {
+      let mut res = [zero; V];
+
+      // * G[0][i]
+      let mut g_0_i = zero;
+      for (j, item) in gi.iter().enumerate().take(H) {
+        if (1 << j) & i > 0 {
+          g_0_i += item;
+        }
+      }
+
+      res[0] = g_0_i;
+
+      // * G[j][i]
+      for j in 1..V {
+        res[j] = (0..B).fold(res[j - 1], |acc, _| acc + acc);
+      }
+
+      res
+    })
+    .collect::<Vec<_>>();
+
+  precompute_res.insert(0, [zero; V]);
+
+  let precomputed_g: [_; POW_2_H] = std::array::from_fn(|j| precompute_res[j]);
+
+  let zero = G::zero();
+
+  scalars
+    .par_iter()
+    .map(|e| {
+      let mut a = zero;
+      let mut bits = e.to_le_bits().into_iter().collect::<Vec<_>>();
+
+      while bits.len() % A != 0 {
+        bits.push(false);
+      }
+
+      for k in (0..B).rev() {
+        a += a;
+        for j in (0..V).rev() {
+          let i_j_k = (0..H)
+            .map(|h| {
+              let b = bits[h * A + j * B + k];
+              (1 << h) * b as usize
+            })
+            .sum::<usize>();
+
+          if i_j_k > 0 {
+            a += precomputed_g[i_j_k][j];
+          }
+        }
+      }
+
+      a
+    })
+    .collect::<Vec<_>>()
+}
+
+/// The number of scalars from which `fixed_base_mul_batch` amortizes the precomputation of the
+/// comb method over the base
+const FIXED_BASE_COMB_THRESHOLD: usize = 64;
+
+/// Multiplies `base` by each of `scalars`, with the comb method for large batches, whose
+/// precomputation over `base` is then shared by all the scalars
+pub(crate) fn fixed_base_mul_batch<G: DlogGroup>(base: G, scalars: &[G::Scalar]) -> Vec<G> {
+  if scalars.len() < FIXED_BASE_COMB_THRESHOLD {
+    scalars.par_iter().map(|s| base * s).collect()
+  } else {
+    fixed_base_exp_comb_batch::<4, 16, 64, 2, 32, _>(base, scalars)
+  }
+}
+
+/// Evaluates, in place, the polynomial with coefficients `a` over the powers of `omega`, a root of
+/// unity of order `a.len()`. The coefficients may also be group elements, whose scalar field is
+/// `F`, which transforms a commitment key between the monomial and the Lagrange bases.
+pub(crate) fn fft<F, T>(a: &mut [T], omega: F)
+where
+  F: PrimeField,
+  T: Copy + Send + Sync + Add<Output = T> + Sub<Output = T> + AddAssign + Mul<F, Output = T>,
+{
+  let n = a.len();
+  // a polynomial with a single coefficient is its own evaluation
+  if n <= 1 {
+    return;
+  }
+  let log_n = n.trailing_zeros();
+  for k in 0..n {
+    let rk = k.reverse_bits() >> (usize::BITS - log_n);
+    if k < rk {
+      a.swap(k, rk);
+    }
+  }
+  let mut m = 1;
+  while m < n {
+    let w_m = omega.pow_vartime([(n / (2 * m)) as u64]);
+    a.par_chunks_mut(2 * m).for_each(|chunk| {
+      let (lo, hi) = chunk.split_at_mut(m);
+      let mut w = F::ONE;
+      for (x, y) in lo.iter_mut().zip(hi.iter_mut()) {
+        let t = *y * w;
+        *y = *x - t;
+        *x += t;
+        w *= w_m;
+      }
+    });
+    m *= 2;
+  }
+}
+
+/// Returns a root of unity of order `2^log_n`
+pub(crate) fn root_of_unity<F: PrimeField>(log_n: usize) -> Result<F, NovaError> {
+  if log_n > F::S as usize {
+    return Err(NovaError::InvalidInputLength);
+  }
+  Ok(F::ROOT_OF_UNITY.pow_vartime([1u64 << (F::S as usize - log_n)]))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::provider::{bn256_grumpkin::bn256, pasta::pallas};
+  use ff::Field;
+  use rand_core::OsRng;
+
+  fn test_batch_to_affine_with<C>()
+  where
+    C: Curve + Send + Sync,
+    C::AffineRepr: Copy + Send + Sync + PartialEq + core::fmt::Debug,
+  {
+    // sizes around the number of threads, and points that include the identity
+    let threads = rayon::current_num_threads();
+    for n in [
+      0,
+      1,
+      2,
+      threads - 1,
+      threads,
+      threads + 1,
+      2 * threads + 1,
+      100,
+    ] {
+      let points = (0..n)
+        .map(|i| {
+          if i % 7 == 3 {
+            C::identity()
+          } else {
+            C::random(OsRng)
+          }
+        })
+        .collect::<Vec<_>>();
+      let expected = points.iter().map(|p| p.to_affine()).collect::<Vec<_>>();
+      assert_eq!(batch_to_affine(&points), expected);
+    }
+  }
+
+  #[test]
+  fn test_batch_to_affine() {
+    test_batch_to_affine_with::<bn256::Point>();
+    test_batch_to_affine_with::<pallas::Point>();
+  }
+
+  #[test]
+  fn test_fft() {
+    type F = pallas::Scalar;
+    let coeffs = (0..16).map(|_| F::random(&mut OsRng)).collect::<Vec<_>>();
+    let omega = root_of_unity::<F>(4).unwrap();
+    let mut evals = coeffs.clone();
+    fft(&mut evals, omega);
+    for (k, eval) in evals.iter().enumerate() {
+      let x = omega.pow_vartime([k as u64]);
+      let expected = coeffs.iter().rev().fold(F::ZERO, |acc, c| acc * x + c);
+      assert_eq!(*eval, expected);
+    }
+  }
+}
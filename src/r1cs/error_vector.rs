@@ -0,0 +1,201 @@
+//! # Error Vectors
+//!
+//! This module defines the error vector `E` of a Relaxed R1CS witness. The error vector is zero for
+//! witnesses obtained from R1CS witnesses and stays mostly zero over the first few folds of a chain,
+//! so it is stored as its nonzero entries until those make up a sizable fraction of it, and only
+//! then densified.
+use super::{wipe, SPARSE_COMMIT_RATIO};
+use crate::{
+  traits::{commitment::CommitmentEngineTrait, Engine},
+  Commitment, CommitmentKey, CE,
+};
+use ff::PrimeField;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// The error vector of a Relaxed R1CS witness, serialized as a dense vector
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "Vec<F>", into = "Vec<F>")]
+pub(crate) enum ErrorVector<F: PrimeField> {
+  /// A vector of length `len` that is zero outside of `indices`, which are sorted
+  Sparse {
+    len: usize,
+    indices: Vec<usize>,
+    values: Vec<F>,
+  },
+  /// A vector with all of its entries
+  Dense(Vec<F>),
+}
+
+impl<F: PrimeField> ErrorVector<F> {
+  /// The zero vector of length `len`
+  pub fn zero(len: usize) -> Self {
+    ErrorVector::Sparse {
+      len,
+      indices: Vec::new(),
+      values: Vec::new(),
+    }
+  }
+
+  /// Stores `v` sparsely if fewer than one in `SPARSE_COMMIT_RATIO` of its entries are nonzero
+  pub fn from_dense(v: Vec<F>) -> Self {
+    let num_nonzero = v.par_iter().filter(|x| !bool::from(x.is_zero())).count();
+    if num_nonzero * SPARSE_COMMIT_RATIO >= v.len() {
+      return ErrorVector::Dense(v);
+    }
+    let (indices, values) = v
+      .par_iter()
+      .enumerate()
+      .filter(|(_, x)| !bool::from(x.is_zero()))
+      .map(|(i, x)| (i, *x))
+      .unzip();
+    ErrorVector::Sparse {
+      len: v.len(),
+      indices,
+      values,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    match self {
+      ErrorVector::Sparse { len, .. } => *len,
+      ErrorVector::Dense(v) => v.len(),
+    }
+  }
+
+  #[cfg(test)]
+  pub fn is_sparse(&self) -> bool {
+    matches!(self, ErrorVector::Sparse { .. })
+  }
+
+  /// Returns the entries of the vector, materializing them if it is sparse
+  pub fn to_dense(&self) -> Cow<'_, [F]> {
+    match self {
+      ErrorVector::Sparse { .. } => Cow::Owned(self.clone().into_dense()),
+      ErrorVector::Dense(v) => Cow::Borrowed(v),
+    }
+  }
+
+  pub fn into_dense(self) -> Vec<F> {
+    match self {
+      ErrorVector::Sparse {
+        len,
+        indices,
+        values,
+      } => {
+        let mut v = vec![F::ZERO; len];
+        for (i, value) in indices.into_iter().zip(values) {
+          v[i] = value;
+        }
+        v
+      }
+      ErrorVector::Dense(v) => v,
+    }
+  }
+
+  /// Returns the entry at `row` if the vector is dense, and zero otherwise; the entries of a sparse
+  /// vector are accounted for separately with `sub_sparse_from`
+  pub fn dense_entry(&self, row: usize) -> F {
+    match self {
+      ErrorVector::Sparse { .. } => F::ZERO,
+      ErrorVector::Dense(v) => v[row],
+    }
+  }
+
+  /// Subtracts the vector from `T` if it is sparse, and does nothing otherwise
+  pub fn sub_sparse_from(&self, T: &mut [F]) {
+    if let ErrorVector::Sparse {
+      indices, values, ..
+    } = self
+    {
+      for (i, value) in indices.iter().zip(values) {
+        T[*i] -= *value;
+      }
+    }
+  }
+
+  /// Adds `c ⋅ self` to `acc`
+  fn add_scaled_to(&self, acc: &mut [F], c: &F) {
+    match self {
+      ErrorVector::Sparse {
+        indices, values, ..
+      } => {
+        for (i, value) in indices.iter().zip(values) {
+          acc[*i] += *c * *value;
+        }
+      }
+      ErrorVector::Dense(v) => acc
+        .par_iter_mut()
+        .zip(v.par_iter())
+        .for_each(|(a, value)| *a += *c * *value),
+    }
+  }
+
+  /// Computes `self + r ⋅ T + r^2 ⋅ E2`, which stays sparse as long as the result is sparse
+  pub fn fold(&self, T: &[F], r: &F, E2: Option<&Self>) -> Self {
+    let mut E = T.par_iter().map(|t| *r * *t).collect::<Vec<F>>();
+    self.add_scaled_to(&mut E, &F::ONE);
+    if let Some(E2) = E2 {
+      E2.add_scaled_to(&mut E, &r.square());
+    }
+    ErrorVector::from_dense(E)
+  }
+
+  /// Extends the vector with zeros to length `len`
+  pub fn pad(&self, len: usize) -> Self {
+    match self {
+      ErrorVector::Sparse {
+        indices, values, ..
+      } => ErrorVector::Sparse {
+        len,
+        indices: indices.clone(),
+        values: values.clone(),
+      },
+      ErrorVector::Dense(v) => {
+        let mut v = v.clone();
+        v.resize(len, F::ZERO);
+        ErrorVector::Dense(v)
+      }
+    }
+  }
+
+  /// Overwrites the stored entries with zeros
+  pub fn wipe(&mut self) {
+    match self {
+      ErrorVector::Sparse { values, .. } => wipe(values),
+      ErrorVector::Dense(v) => wipe(v),
+    }
+  }
+
+  /// Commits to the vector with the blind `r`, skipping the zero entries of a sparse vector
+  pub fn commit<E: Engine<Scalar = F>>(&self, ck: &CommitmentKey<E>, r: &F) -> Commitment<E> {
+    match self {
+      ErrorVector::Sparse {
+        indices, values, ..
+      } => CE::<E>::commit_sparse(ck, indices, values, r),
+      ErrorVector::Dense(v) => CE::<E>::commit(ck, v, r),
+    }
+  }
+}
+
+impl<F: PrimeField> From<Vec<F>> for ErrorVector<F> {
+  fn from(v: Vec<F>) -> Self {
+    ErrorVector::from_dense(v)
+  }
+}
+
+impl<F: PrimeField> From<ErrorVector<F>> for Vec<F> {
+  fn from(E: ErrorVector<F>) -> Self {
+    E.into_dense()
+  }
+}
+
+/// Vectors are equal if their entries are, regardless of how they are stored
+impl<F: PrimeField> PartialEq for ErrorVector<F> {
+  fn eq(&self, other: &Self) -> bool {
+    self.len() == other.len() && self.to_dense() == other.to_dense()
+  }
+}
+
+impl<F: PrimeField> Eq for ErrorVector<F> {}
@@ -0,0 +1,350 @@
+//! This module defines an interchange format for `R1CSShape`, so that external tools can consume
+//! exactly the constraint system that Nova proves, and shapes produced offline can be loaded
+//! without synthesizing circuits.
+//!
+//! A shape with `num_cons` constraints, `num_vars` variables, and `num_io` public inputs is
+//! satisfied by `z = (W, 1, X)` when `(A z) ∘ (B z) = C z`, where `W` holds the `num_vars`
+//! variables and `X` the `num_io` public inputs. Each matrix has `num_cons` rows and
+//! `num_vars + 1 + num_io` columns: column `j < num_vars` is `W[j]`, column `num_vars` is the
+//! constant `1`, and column `num_vars + 1 + j` is `X[j]`. A matrix is a list of
+//! `(row, column, value)` triples sorted by row and then by column, without duplicates.
+//!
+//! The binary format (`R1CSShape::to_bytes`) is, with integers in little-endian order:
+//! * the magic bytes `NOVAR1CS` and the format version as a `u32`,
+//! * the modulus of the scalar field (`PrimeField::MODULUS`), as a `u32` length followed by as
+//!   many ASCII bytes,
+//! * `num_cons`, `num_vars`, and `num_io` as `u64`s,
+//! * for each of `A`, `B`, and `C`, the number of triples as a `u64` followed by the triples, each
+//!   made of the row and the column as `u64`s and the canonical representation of the value
+//!   (`PrimeField::to_repr`).
+//!
+//! The JSON format (`R1CSShape::to_json`) holds the same data, with values as decimal strings:
+//! `{"format": "nova-r1cs", "version": 1, "field_modulus": "0x..", "num_constraints": ..,
+//! "num_variables": .., "num_public_inputs": .., "A": [[row, column, "value"], ..], "B": ..,
+//! "C": ..}`.
+use super::{R1CSShape, SparseMatrix};
+use crate::{
+  encoding::{decode_scalar, encode_scalar, scalar_len, ByteOrder},
+  errors::NovaError,
+  gadgets::nonnative::util::f_to_nat,
+  traits::Engine,
+};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+const MAGIC: &[u8; 8] = b"NOVAR1CS";
+const FORMAT: &str = "nova-r1cs";
+const VERSION: u32 = 1;
+
+/// The JSON representation of a shape
+#[derive(Serialize, Deserialize)]
+struct ShapeJson {
+  format: String,
+  version: u32,
+  field_modulus: String,
+  num_constraints: usize,
+  num_variables: usize,
+  num_public_inputs: usize,
+  A: Vec<(usize, usize, String)>,
+  B: Vec<(usize, usize, String)>,
+  C: Vec<(usize, usize, String)>,
+}
+
+fn invalid_encoding(reason: impl Into<String>) -> NovaError {
+  NovaError::InvalidEncoding {
+    reason: reason.into(),
+  }
+}
+
+/// Returns the triples of `M` in row-major order. Synthesized matrices hold the entries of a row
+/// in the order of the terms of their linear combinations, so they are sorted by column here, and
+/// entries for the same column are summed.
+fn entries<F: PrimeField>(M: &SparseMatrix<F>) -> Vec<(usize, usize, F)> {
+  let mut entries = Vec::with_capacity(M.data.len());
+  for (row, ptrs) in M.indptr.windows(2).enumerate() {
+    let start = entries.len();
+    entries.extend(
+      M.get_row_unchecked(&[ptrs[0], ptrs[1]])
+        .map(|(val, col)| (row, *col, *val)),
+    );
+    entries[start..].sort_by_key(|(_, col, _)| *col);
+  }
+  entries.dedup_by(|next, prev| {
+    let same = (next.0, next.1) == (prev.0, prev.1);
+    if same {
+      prev.2 += next.2;
+    }
+    same
+  });
+  entries
+}
+
+/// Builds a matrix of a shape from its triples, checking that they follow the interchange format
+fn matrix<F: PrimeField>(
+  entries: &[(usize, usize, F)],
+  num_cons: usize,
+  cols: usize,
+) -> Result<SparseMatrix<F>, NovaError> {
+  if entries
+    .iter()
+    .any(|(row, col, _)| *row >= num_cons || *col >= cols)
+  {
+    return Err(invalid_encoding("matrix entry out of bounds"));
+  }
+  if entries
+    .windows(2)
+    .any(|w| (w[0].0, w[0].1) >= (w[1].0, w[1].1))
+  {
+    return Err(invalid_encoding("matrix entries are not sorted"));
+  }
+  Ok(SparseMatrix::new(entries, num_cons, cols))
+}
+
+fn shape<E: Engine>(
+  num_cons: usize,
+  num_vars: usize,
+  num_io: usize,
+  [A, B, C]: [Vec<(usize, usize, E::Scalar)>; 3],
+) -> Result<R1CSShape<E>, NovaError> {
+  let cols = num_vars
+    .checked_add(num_io)
+    .and_then(|n| n.checked_add(1))
+    .ok_or_else(|| invalid_encoding("too many variables"))?;
+  R1CSShape::new(
+    num_cons,
+    num_vars,
+    num_io,
+    matrix(&A, num_cons, cols)?,
+    matrix(&B, num_cons, cols)?,
+    matrix(&C, num_cons, cols)?,
+  )
+}
+
+impl<E: Engine> R1CSShape<E> {
+  /// Encodes the shape in the binary interchange format
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    // writing to a vector cannot fail
+    let write = |bytes: &mut Vec<u8>, n: usize| bytes.write_u64::<LittleEndian>(n as u64).unwrap();
+    bytes.write_u32::<LittleEndian>(VERSION).unwrap();
+    let modulus = <E::Scalar as PrimeField>::MODULUS.as_bytes();
+    bytes
+      .write_u32::<LittleEndian>(modulus.len() as u32)
+      .unwrap();
+    bytes.extend_from_slice(modulus);
+    write(&mut bytes, self.num_cons);
+    write(&mut bytes, self.num_vars);
+    write(&mut bytes, self.num_io);
+    for M in [&self.A, &self.B, &self.C] {
+      let entries = entries(M);
+      write(&mut bytes, entries.len());
+      for (row, col, val) in entries {
+        write(&mut bytes, row);
+        write(&mut bytes, col);
+        bytes.extend_from_slice(&encode_scalar(&val, ByteOrder::LittleEndian));
+      }
+    }
+    bytes
+  }
+
+  /// Decodes a shape from the binary interchange format, failing if the bytes are not the
+  /// encoding of a valid shape over the scalar field of `E`
+  pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, NovaError> {
+    let truncated = |_| invalid_encoding("truncated shape");
+    let read = |bytes: &mut &[u8]| -> Result<usize, NovaError> {
+      let n = bytes.read_u64::<LittleEndian>().map_err(truncated)?;
+      usize::try_from(n).map_err(|_| invalid_encoding("integer too large"))
+    };
+
+    if !bytes.starts_with(MAGIC) {
+      return Err(invalid_encoding("not an R1CS shape"));
+    }
+    bytes = &bytes[MAGIC.len()..];
+    if bytes.read_u32::<LittleEndian>().map_err(truncated)? != VERSION {
+      return Err(invalid_encoding("unsupported shape format version"));
+    }
+    let modulus_len = bytes.read_u32::<LittleEndian>().map_err(truncated)? as usize;
+    if bytes.len() < modulus_len {
+      return Err(invalid_encoding("truncated shape"));
+    }
+    let (modulus, rest) = bytes.split_at(modulus_len);
+    if modulus != <E::Scalar as PrimeField>::MODULUS.as_bytes() {
+      return Err(invalid_encoding("shape is over a different field"));
+    }
+    bytes = rest;
+
+    let num_cons = read(&mut bytes)?;
+    let num_vars = read(&mut bytes)?;
+    let num_io = read(&mut bytes)?;
+
+    let repr_len = scalar_len::<E::Scalar>();
+    let mut matrices: [Vec<(usize, usize, E::Scalar)>; 3] = Default::default();
+    for M in matrices.iter_mut() {
+      let nnz = read(&mut bytes)?;
+      // the count is untrusted, so it only bounds the allocation by the bytes that are left
+      M.reserve(nnz.min(bytes.len() / (16 + repr_len)));
+      for _ in 0..nnz {
+        let row = read(&mut bytes)?;
+        let col = read(&mut bytes)?;
+        if bytes.len() < repr_len {
+          return Err(invalid_encoding("truncated shape"));
+        }
+        let val = decode_scalar(&bytes[..repr_len], ByteOrder::LittleEndian)?;
+        bytes = &bytes[repr_len..];
+        M.push((row, col, val));
+      }
+    }
+    if !bytes.is_empty() {
+      return Err(invalid_encoding("trailing bytes"));
+    }
+
+    shape(num_cons, num_vars, num_io, matrices)
+  }
+
+  /// Encodes the shape in the JSON interchange format
+  pub fn to_json(&self) -> String {
+    let encode = |M: &SparseMatrix<E::Scalar>| {
+      entries(M)
+        .into_iter()
+        .map(|(row, col, val)| (row, col, f_to_nat(&val).to_string()))
+        .collect()
+    };
+    let json = ShapeJson {
+      format: FORMAT.to_string(),
+      version: VERSION,
+      field_modulus: <E::Scalar as PrimeField>::MODULUS.to_string(),
+      num_constraints: self.num_cons,
+      num_variables: self.num_vars,
+      num_public_inputs: self.num_io,
+      A: encode(&self.A),
+      B: encode(&self.B),
+      C: encode(&self.C),
+    };
+    // the representation only holds strings and integers, so serializing it cannot fail
+    serde_json::to_string(&json).expect("failed to serialize a shape to JSON")
+  }
+
+  /// Decodes a shape from the JSON interchange format, failing if the JSON does not describe a
+  /// valid shape over the scalar field of `E`
+  pub fn from_json(json: &str) -> Result<Self, NovaError> {
+    let json: ShapeJson =
+      serde_json::from_str(json).map_err(|e| invalid_encoding(e.to_string()))?;
+    if json.format != FORMAT || json.version != VERSION {
+      return Err(invalid_encoding("unsupported shape format"));
+    }
+    if json.field_modulus != <E::Scalar as PrimeField>::MODULUS {
+      return Err(invalid_encoding("shape is over a different field"));
+    }
+
+    let decode = |entries: Vec<(usize, usize, String)>| {
+      entries
+        .into_iter()
+        .map(|(row, col, val)| {
+          // decimal strings are only accepted in their canonical form, i.e., reduced and without
+          // leading zeros
+          E::Scalar::from_str_vartime(&val)
+            .filter(|f| f_to_nat(f).to_string() == val)
+            .map(|f| (row, col, f))
+            .ok_or_else(|| invalid_encoding("non-canonical field element"))
+        })
+        .collect::<Result<Vec<_>, _>>()
+    };
+    shape(
+      json.num_constraints,
+      json.num_variables,
+      json.num_public_inputs,
+      [decode(json.A)?, decode(json.B)?, decode(json.C)?],
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::{num::AllocatedNum, r1cs::NovaShape, shape_cs::ShapeCS, ConstraintSystem},
+    nova::PublicParams,
+    provider::{Bn256EngineKZG, PallasEngine, VestaEngine},
+    traits::circuit::{NonTrivialCircuit, StepCircuit, TrivialCircuit},
+  };
+
+  fn test_shape<E: Engine>() -> R1CSShape<E> {
+    let mut cs: ShapeCS<E> = ShapeCS::new();
+    let z = AllocatedNum::alloc_input(cs.namespace(|| "z"), || Ok(E::Scalar::from(3u64))).unwrap();
+    let z_next = NonTrivialCircuit::new(4)
+      .synthesize(&mut cs.namespace(|| "step"), &[z])
+      .unwrap();
+    z_next[0].inputize(cs.namespace(|| "z_next")).unwrap();
+    cs.r1cs_shape_without_ck()
+  }
+
+  fn test_round_trip_with<E: Engine>() {
+    let S = test_shape::<E>();
+
+    let bytes = S.to_bytes();
+    assert_eq!(R1CSShape::<E>::from_bytes(&bytes).unwrap(), S);
+    let json = S.to_json();
+    assert_eq!(R1CSShape::<E>::from_json(&json).unwrap(), S);
+    assert_eq!(
+      R1CSShape::<E>::from_json(&json).unwrap().digest(),
+      S.digest()
+    );
+
+    // truncated or extended encodings are rejected
+    assert!(R1CSShape::<E>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(R1CSShape::<E>::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_err());
+  }
+
+  #[test]
+  fn test_round_trip() {
+    test_round_trip_with::<PallasEngine>();
+    test_round_trip_with::<Bn256EngineKZG>();
+  }
+
+  #[test]
+  fn test_round_trip_augmented_circuit() {
+    // the rows of the augmented circuit mix inputs and variables out of column order
+    let (S, _) = PublicParams::<PallasEngine, VestaEngine, TrivialCircuit<_>>::synthesize_shapes(
+      &TrivialCircuit::default(),
+    )
+    .unwrap();
+    let decoded = R1CSShape::<PallasEngine>::from_bytes(&S.to_bytes()).unwrap();
+    assert_eq!(decoded.to_bytes(), S.to_bytes());
+    assert_eq!(
+      R1CSShape::<PallasEngine>::from_json(&S.to_json()).unwrap(),
+      decoded
+    );
+
+    // both shapes hold the same constraints
+    let z = (0..S.num_vars + 1 + S.num_io)
+      .map(|i| <PallasEngine as Engine>::Scalar::from(i as u64 + 1))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      S.multiply_vec(&z).unwrap(),
+      decoded.multiply_vec(&z).unwrap()
+    );
+  }
+
+  #[test]
+  fn test_reject_invalid() {
+    let S = test_shape::<PallasEngine>();
+
+    // a shape over another field is rejected
+    assert!(R1CSShape::<VestaEngine>::from_bytes(&S.to_bytes()).is_err());
+    assert!(R1CSShape::<VestaEngine>::from_json(&S.to_json()).is_err());
+
+    // so are unsorted, out-of-bounds, and non-canonical entries
+    let mut json: serde_json::Value = serde_json::from_str(&S.to_json()).unwrap();
+    let tamper = |json: &serde_json::Value, f: &dyn Fn(&mut Vec<serde_json::Value>)| {
+      let mut json = json.clone();
+      f(json["A"].as_array_mut().unwrap());
+      R1CSShape::<PallasEngine>::from_json(&json.to_string())
+    };
+    assert!(tamper(&json, &|A| A.reverse()).is_err());
+    assert!(tamper(&json, &|A| A[0][0] = S.num_cons.into()).is_err());
+    assert!(tamper(&json, &|A| A[0][2] = "01".into()).is_err());
+    json["version"] = 2.into();
+    assert!(R1CSShape::<PallasEngine>::from_json(&json.to_string()).is_err());
+  }
+}
@@ -8,21 +8,28 @@ use crate::{
     utils::scalar_as_base,
   },
   traits::{
-    commitment::CommitmentEngineTrait, AbsorbInRO2Trait, AbsorbInROTrait, Engine, ROTrait,
-    TranscriptReprTrait,
+    calldata::{CalldataDecoder, CalldataEncoder, CalldataEncoding},
+    commitment::CommitmentEngineTrait,
+    AbsorbInRO2Trait, AbsorbInROTrait, Engine, ROTrait, TranscriptReprTrait,
   },
   Commitment, CommitmentKey, DerandKey, CE,
 };
-use core::cmp::max;
+use core::cmp::{max, min};
 use ff::Field;
 use once_cell::sync::OnceCell;
 use rand_core::OsRng;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
+mod error_vector;
+mod interchange;
 mod sparse;
+pub(crate) use error_vector::ErrorVector;
 pub(crate) use sparse::SparseMatrix;
 
+/// The number of consecutive constraints processed by a single task when computing the cross-term
+const CROSS_TERM_TILE_SIZE: usize = 1 << 10;
+
 /// A type that holds the shape of the R1CS matrices
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct R1CSShape<E: Engine> {
@@ -58,7 +65,7 @@ pub struct R1CSInstance<E: Engine> {
 pub struct RelaxedR1CSWitness<E: Engine> {
   pub(crate) W: Vec<E::Scalar>,
   pub(crate) r_W: E::Scalar,
-  pub(crate) E: Vec<E::Scalar>,
+  pub(crate) E: ErrorVector<E::Scalar>,
   pub(crate) r_E: E::Scalar,
 }
 
@@ -127,10 +134,42 @@ impl<E: Engine> R1CSShape<E> {
   ///   to provide is the ck_floor field defined in the trait `RelaxedR1CSSNARKTrait`.
   ///
   pub fn commitment_key(&self, ck_floor: &CommitmentKeyHint<E>) -> CommitmentKey<E> {
-    let num_cons = self.num_cons;
-    let num_vars = self.num_vars;
-    let ck_hint = ck_floor(self);
-    E::CE::setup(b"ck", max(max(num_cons, num_vars), ck_hint))
+    E::CE::setup(b"ck", self.commitment_key_size(ck_floor))
+  }
+
+  /// Returns the number of generators `commitment_key` requires for this shape and `ck_floor`
+  pub fn commitment_key_size(&self, ck_floor: &CommitmentKeyHint<E>) -> usize {
+    max(max(self.num_cons, self.num_vars), ck_floor(self))
+  }
+
+  /// Returns the number of leading constraints that `self` and `other` have in common.
+  ///
+  /// Constraints are compared after accounting for a different number of variables, so a
+  /// constraint over the same variables and public IO is considered identical in both shapes.
+  pub fn common_prefix_len(&self, other: &R1CSShape<E>) -> usize {
+    let normalize = |S: &R1CSShape<E>, M: &SparseMatrix<E::Scalar>, row: usize| {
+      M.get_row_unchecked(&[M.indptr[row], M.indptr[row + 1]])
+        .map(|(val, col)| {
+          if *col < S.num_vars {
+            (*val, false, *col)
+          } else {
+            (*val, true, *col - S.num_vars)
+          }
+        })
+        .collect::<Vec<_>>()
+    };
+
+    (0..min(self.num_cons, other.num_cons))
+      .take_while(|&row| {
+        [
+          (&self.A, &other.A),
+          (&self.B, &other.B),
+          (&self.C, &other.C),
+        ]
+        .into_iter()
+        .all(|(M1, M2)| normalize(self, M1, row) == normalize(other, M2, row))
+      })
+      .count()
   }
 
   /// returned the digest of the `R1CSShape`
@@ -182,19 +221,20 @@ impl<E: Engine> R1CSShape<E> {
     // verify if Az * Bz = u*Cz + E
     let res_eq = {
       let z = [W.W.clone(), vec![U.u], U.X.clone()].concat();
+      let E = W.E.to_dense();
       let (Az, Bz, Cz) = self.multiply_vec(&z)?;
       assert_eq!(Az.len(), self.num_cons);
       assert_eq!(Bz.len(), self.num_cons);
       assert_eq!(Cz.len(), self.num_cons);
 
-      (0..self.num_cons).all(|i| Az[i] * Bz[i] == U.u * Cz[i] + W.E[i])
+      (0..self.num_cons).all(|i| Az[i] * Bz[i] == U.u * Cz[i] + E[i])
     };
 
     // verify if comm_E and comm_W are commitments to E and W
     let res_comm = {
       let (comm_W, comm_E) = rayon::join(
         || CE::<E>::commit(ck, &W.W, &W.r_W),
-        || CE::<E>::commit(ck, &W.E, &W.r_E),
+        || W.E.commit::<E>(ck, &W.r_E),
       );
       U.comm_W == comm_W && U.comm_E == comm_E
     };
@@ -253,6 +293,24 @@ impl<E: Engine> R1CSShape<E> {
     Ok(())
   }
 
+  /// Checks if a witness satisfies the constraints of the shape on public IO `X`, without
+  /// checking any commitment
+  pub fn check_witness(&self, X: &[E::Scalar], W: &[E::Scalar]) -> Result<(), NovaError> {
+    if X.len() != self.num_io || W.len() != self.num_vars {
+      return Err(NovaError::InvalidWitnessLength);
+    }
+
+    let z = [W, &[E::Scalar::ONE], X].concat();
+    let (Az, Bz, Cz) = self.multiply_vec(&z)?;
+    if (0..self.num_cons).any(|i| Az[i] * Bz[i] != Cz[i]) {
+      return Err(NovaError::UnSat {
+        reason: "R1CS is unsatisfiable".to_string(),
+      });
+    }
+
+    Ok(())
+  }
+
   /// A method to compute a commitment to the cross-term `T` given a
   /// Relaxed R1CS instance-witness pair and an R1CS instance-witness pair
   pub fn commit_T(
@@ -264,31 +322,28 @@ impl<E: Engine> R1CSShape<E> {
     W2: &R1CSWitness<E>,
     r_T: &E::Scalar,
   ) -> Result<(Vec<E::Scalar>, Commitment<E>), NovaError> {
-    let Z1 = [W1.W.clone(), vec![U1.u], U1.X.clone()].concat();
-    let Z2 = [W2.W.clone(), vec![E::Scalar::ONE], U2.X.clone()].concat();
+    let T = self.compute_T(U1, W1, U2, W2)?;
+    let comm_T = CE::<E>::commit(ck, &T, r_T);
+
+    Ok((T, comm_T))
+  }
 
+  /// Computes the cross-term `T` that `commit_T` commits to, so that the cross-terms of many
+  /// foldings can be committed to in one batch
+  pub(crate) fn compute_T(
+    &self,
+    U1: &RelaxedR1CSInstance<E>,
+    W1: &RelaxedR1CSWitness<E>,
+    U2: &R1CSInstance<E>,
+    W2: &R1CSWitness<E>,
+  ) -> Result<Vec<E::Scalar>, NovaError> {
     // The following code uses the optimization suggested in
     // Section 5.2 of [Mova](https://eprint.iacr.org/2024/1220.pdf)
-    let Z = Z1
-      .into_par_iter()
-      .zip(Z2.into_par_iter())
-      .map(|(z1, z2)| z1 + z2)
-      .collect::<Vec<E::Scalar>>();
     let u = U1.u + E::Scalar::ONE; // U2.u = 1
-
-    let (AZ, BZ, CZ) = self.multiply_vec(&Z)?;
-
-    let T = AZ
-      .par_iter()
-      .zip(BZ.par_iter())
-      .zip(CZ.par_iter())
-      .zip(W1.E.par_iter())
-      .map(|(((az, bz), cz), e)| *az * *bz - u * *cz - *e)
-      .collect::<Vec<E::Scalar>>();
-
-    let comm_T = CE::<E>::commit(ck, &T, r_T);
-
-    Ok((T, comm_T))
+    let mut Z = Self::sum_z(&W1.W, &U1.u, &U1.X, &W2.W, &E::Scalar::ONE, &U2.X);
+    let T = self.cross_term(&Z, &u, &W1.E, None);
+    wipe_scratch(&mut Z);
+    T
   }
 
   /// A method to compute a commitment to the cross-term `T` given two
@@ -302,32 +357,90 @@ impl<E: Engine> R1CSShape<E> {
     W2: &RelaxedR1CSWitness<E>,
     r_T: &E::Scalar,
   ) -> Result<(Vec<E::Scalar>, Commitment<E>), NovaError> {
-    let Z1 = [W1.W.clone(), vec![U1.u], U1.X.clone()].concat();
-    let Z2 = [W2.W.clone(), vec![U2.u], U2.X.clone()].concat();
+    let T = self.compute_T_relaxed(U1, W1, U2, W2)?;
+    let comm_T = CE::<E>::commit(ck, &T, r_T);
 
+    Ok((T, comm_T))
+  }
+
+  /// Computes the cross-term `T` that `commit_T_relaxed` commits to
+  pub(crate) fn compute_T_relaxed(
+    &self,
+    U1: &RelaxedR1CSInstance<E>,
+    W1: &RelaxedR1CSWitness<E>,
+    U2: &RelaxedR1CSInstance<E>,
+    W2: &RelaxedR1CSWitness<E>,
+  ) -> Result<Vec<E::Scalar>, NovaError> {
     // The following code uses the optimization suggested in
     // Section 5.2 of [Mova](https://eprint.iacr.org/2024/1220.pdf)
-    let Z = Z1
-      .into_par_iter()
-      .zip(Z2.into_par_iter())
-      .map(|(z1, z2)| z1 + z2)
-      .collect::<Vec<E::Scalar>>();
     let u = U1.u + U2.u;
+    let mut Z = Self::sum_z(&W1.W, &U1.u, &U1.X, &W2.W, &U2.u, &U2.X);
+    let T = self.cross_term(&Z, &u, &W1.E, Some(&W2.E));
+    wipe_scratch(&mut Z);
+    T
+  }
 
-    let (AZ, BZ, CZ) = self.multiply_vec(&Z)?;
+  /// Computes `Z1 + Z2` for `Z1 = (W1, u1, X1)` and `Z2 = (W2, u2, X2)` without materializing `Z1` and `Z2`
+  fn sum_z(
+    W1: &[E::Scalar],
+    u1: &E::Scalar,
+    X1: &[E::Scalar],
+    W2: &[E::Scalar],
+    u2: &E::Scalar,
+    X2: &[E::Scalar],
+  ) -> Vec<E::Scalar> {
+    let mut Z = Vec::with_capacity(W1.len() + 1 + X1.len());
+    W1.par_iter()
+      .zip(W2.par_iter())
+      .map(|(w1, w2)| *w1 + *w2)
+      .collect_into_vec(&mut Z);
+    Z.push(*u1 + *u2);
+    Z.extend(X1.iter().zip(X2.iter()).map(|(x1, x2)| *x1 + *x2));
+    Z
+  }
 
-    let T = AZ
-      .par_iter()
-      .zip(BZ.par_iter())
-      .zip(CZ.par_iter())
-      .zip(W1.E.par_iter())
-      .zip(W2.E.par_iter())
-      .map(|((((az, bz), cz), e1), e2)| *az * *bz - u * *cz - *e1 - *e2)
-      .collect::<Vec<E::Scalar>>();
+  /// Computes `T = AZ ∘ BZ - u ⋅ CZ - E1 - E2`, which is the cross-term of folding when `Z` and `u`
+  /// are the sums of the respective values of the two instances.
+  ///
+  /// Constraints are processed in parallel in tiles of consecutive rows. Each tile evaluates its rows of
+  /// `AZ`, `BZ`, and `CZ` and combines them right away, so the products are never materialized in full
+  /// and the rows of all three matrices are read from contiguous memory.
+  fn cross_term(
+    &self,
+    Z: &[E::Scalar],
+    u: &E::Scalar,
+    E1: &ErrorVector<E::Scalar>,
+    E2: Option<&ErrorVector<E::Scalar>>,
+  ) -> Result<Vec<E::Scalar>, NovaError> {
+    if Z.len() != self.num_io + self.num_vars + 1
+      || E1.len() != self.num_cons
+      || E2.is_some_and(|E2| E2.len() != self.num_cons)
+    {
+      return Err(NovaError::InvalidWitnessLength);
+    }
 
-    let comm_T = CE::<E>::commit(ck, &T, r_T);
+    let mut T = vec![E::Scalar::ZERO; self.num_cons];
+    T.par_chunks_mut(CROSS_TERM_TILE_SIZE)
+      .enumerate()
+      .for_each(|(tile, T)| {
+        let offset = tile * CROSS_TERM_TILE_SIZE;
+        for (j, t) in T.iter_mut().enumerate() {
+          let row = offset + j;
+          let az = self.A.multiply_row_unchecked(row, Z);
+          let bz = self.B.multiply_row_unchecked(row, Z);
+          let cz = self.C.multiply_row_unchecked(row, Z);
+          *t = az * bz
+            - *u * cz
+            - E1.dense_entry(row)
+            - E2.map_or(E::Scalar::ZERO, |E2| E2.dense_entry(row));
+        }
+      });
+    E1.sub_sparse_from(&mut T);
+    if let Some(E2) = E2 {
+      E2.sub_sparse_from(&mut T);
+    }
 
-    Ok((T, comm_T))
+    Ok(T)
   }
 
   /// Pads the `R1CSShape` so that the shape passes `is_regular_shape`
@@ -391,6 +504,44 @@ impl<E: Engine> R1CSShape<E> {
     }
   }
 
+  /// Samples a shape whose matrices have up to three random entries in every row, from `seed`
+  #[cfg(any(test, feature = "testing"))]
+  pub(crate) fn random(num_cons: usize, num_vars: usize, num_io: usize, seed: u64) -> Self {
+    use rand_chacha::ChaCha8Rng;
+    use rand_core::{RngCore, SeedableRng};
+
+    let cols = num_vars + num_io + 1;
+    // every row of every matrix is sampled from its own stream
+    let random_matrix = |matrix: usize| {
+      let entries = (0..num_cons)
+        .into_par_iter()
+        .flat_map_iter(|row| {
+          let mut rng = ChaCha8Rng::seed_from_u64(seed);
+          rng.set_stream((3 * row + matrix) as u64);
+          let mut cols = (0..3)
+            .map(|_| rng.next_u64() as usize % cols)
+            .collect::<Vec<_>>();
+          cols.sort_unstable();
+          cols.dedup();
+          cols
+            .into_iter()
+            .map(|col| (row, col, E::Scalar::random(&mut rng)))
+            .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+      SparseMatrix::new(&entries, num_cons, cols)
+    };
+    Self::new(
+      num_cons,
+      num_vars,
+      num_io,
+      random_matrix(0),
+      random_matrix(1),
+      random_matrix(2),
+    )
+    .unwrap()
+  }
+
   /// Samples a new random `RelaxedR1CSInstance`/`RelaxedR1CSWitness` pair
   pub fn sample_random_instance_witness(
     &self,
@@ -433,14 +584,59 @@ impl<E: Engine> R1CSShape<E> {
       RelaxedR1CSWitness {
         W: Z[..self.num_vars].to_vec(),
         r_W,
-        E,
+        E: ErrorVector::from_dense(E),
         r_E,
       },
     ))
   }
 }
 
+/// Overwrites `values` with zeros, in a way that the writes are not optimized away
+/// even if `values` is dropped right after
+pub(crate) fn wipe<F: Field>(values: &mut [F]) {
+  values.par_iter_mut().for_each(|v| *v = F::ZERO);
+  core::hint::black_box(values);
+}
+
+/// Wipes a scratch buffer that holds values derived from a witness, if the `zeroize` feature is enabled
+pub(crate) fn wipe_scratch<F: Field>(values: &mut [F]) {
+  #[cfg(feature = "zeroize")]
+  wipe(values);
+  #[cfg(not(feature = "zeroize"))]
+  let _ = values;
+}
+
+/// Vectors with fewer than one nonzero entry in `SPARSE_COMMIT_RATIO` are committed to with
+/// `CommitmentEngineTrait::commit_sparse`, which skips their zero entries, and error vectors with
+/// as few nonzero entries are stored sparsely
+const SPARSE_COMMIT_RATIO: usize = 4;
+
+/// Commits to `v` with the blind `r`, skipping the zero entries of `v` if they dominate it
+pub(crate) fn commit_detect_density<E: Engine>(
+  ck: &CommitmentKey<E>,
+  v: &[E::Scalar],
+  r: &E::Scalar,
+) -> Commitment<E> {
+  let num_nonzero = v.par_iter().filter(|x| !bool::from(x.is_zero())).count();
+  if num_nonzero * SPARSE_COMMIT_RATIO >= v.len() {
+    return CE::<E>::commit(ck, v, r);
+  }
+  let (indices, values): (Vec<usize>, Vec<E::Scalar>) = v
+    .par_iter()
+    .enumerate()
+    .filter(|(_, x)| !bool::from(x.is_zero()))
+    .map(|(i, x)| (i, *x))
+    .unzip();
+  CE::<E>::commit_sparse(ck, &indices, &values, r)
+}
+
 impl<E: Engine> R1CSWitness<E> {
+  /// Overwrites the witness and its blind with zeros
+  pub(crate) fn wipe(&mut self) {
+    wipe(&mut self.W);
+    wipe(core::slice::from_mut(&mut self.r_W));
+  }
+
   /// A method to create a witness object using a vector of scalars
   pub fn new(S: &R1CSShape<E>, W: &[E::Scalar]) -> Result<R1CSWitness<E>, NovaError> {
     let mut W = W.to_vec();
@@ -452,9 +648,10 @@ impl<E: Engine> R1CSWitness<E> {
     })
   }
 
-  /// Commits to the witness using the supplied generators
+  /// Commits to the witness using the supplied generators, skipping its zero entries if they
+  /// dominate it
   pub fn commit(&self, ck: &CommitmentKey<E>) -> Commitment<E> {
-    CE::<E>::commit(ck, &self.W, &self.r_W)
+    commit_detect_density::<E>(ck, &self.W, &self.r_W)
   }
 
   /// Pads the provided witness to the correct length
@@ -484,6 +681,24 @@ impl<E: Engine> R1CSInstance<E> {
   }
 }
 
+impl<E: Engine> CalldataEncoding for R1CSInstance<E>
+where
+  E::Scalar: CalldataEncoding,
+  Commitment<E>: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.comm_W.encode(enc);
+    self.X.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      comm_W: CalldataEncoding::decode(dec)?,
+      X: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
+
 impl<E: Engine> AbsorbInROTrait<E> for R1CSInstance<E> {
   fn absorb_in_ro(&self, ro: &mut E::RO) {
     self.comm_W.absorb_in_ro(ro);
@@ -507,13 +722,35 @@ impl<E: Engine> AbsorbInRO2Trait<E> for R1CSInstance<E> {
   }
 }
 
+#[cfg(feature = "zeroize")]
+impl<E: Engine> zeroize::Zeroize for R1CSWitness<E> {
+  fn zeroize(&mut self) {
+    self.wipe();
+  }
+}
+
+#[cfg(feature = "zeroize")]
+impl<E: Engine> zeroize::Zeroize for RelaxedR1CSWitness<E> {
+  fn zeroize(&mut self) {
+    self.wipe();
+  }
+}
+
 impl<E: Engine> RelaxedR1CSWitness<E> {
+  /// Overwrites the witness, the error vector, and their blinds with zeros
+  pub(crate) fn wipe(&mut self) {
+    wipe(&mut self.W);
+    self.E.wipe();
+    wipe(core::slice::from_mut(&mut self.r_W));
+    wipe(core::slice::from_mut(&mut self.r_E));
+  }
+
   /// Produces a default `RelaxedR1CSWitness` given an `R1CSShape`
   pub fn default(S: &R1CSShape<E>) -> RelaxedR1CSWitness<E> {
     RelaxedR1CSWitness {
       W: vec![E::Scalar::ZERO; S.num_vars],
       r_W: E::Scalar::ZERO,
-      E: vec![E::Scalar::ZERO; S.num_cons],
+      E: ErrorVector::zero(S.num_cons),
       r_E: E::Scalar::ZERO,
     }
   }
@@ -523,16 +760,17 @@ impl<E: Engine> RelaxedR1CSWitness<E> {
     RelaxedR1CSWitness {
       W: witness.W.clone(),
       r_W: witness.r_W,
-      E: vec![E::Scalar::ZERO; S.num_cons],
+      E: ErrorVector::zero(S.num_cons),
       r_E: E::Scalar::ZERO,
     }
   }
 
-  /// Commits to the witness using the supplied generators
+  /// Commits to the witness using the supplied generators, skipping the zero entries of each
+  /// vector if they dominate it
   pub fn commit(&self, ck: &CommitmentKey<E>) -> (Commitment<E>, Commitment<E>) {
     (
-      CE::<E>::commit(ck, &self.W, &self.r_W),
-      CE::<E>::commit(ck, &self.E, &self.r_E),
+      commit_detect_density::<E>(ck, &self.W, &self.r_W),
+      self.E.commit::<E>(ck, &self.r_E),
     )
   }
 
@@ -556,11 +794,10 @@ impl<E: Engine> RelaxedR1CSWitness<E> {
       .zip(W2)
       .map(|(a, b)| *a + *r * *b)
       .collect::<Vec<E::Scalar>>();
-    let E = E1
-      .par_iter()
-      .zip(T)
-      .map(|(a, b)| *a + *r * *b)
-      .collect::<Vec<E::Scalar>>();
+    if T.len() != E1.len() {
+      return Err(NovaError::InvalidWitnessLength);
+    }
+    let E = E1.fold(T, r, None);
 
     let r_W = *r_W1 + *r * r_W2;
     let r_E = *r_E1 + *r * r_T;
@@ -589,12 +826,10 @@ impl<E: Engine> RelaxedR1CSWitness<E> {
       .zip(W2)
       .map(|(a, b)| *a + *r * *b)
       .collect::<Vec<E::Scalar>>();
-    let E = E1
-      .par_iter()
-      .zip(T)
-      .zip(E2.par_iter())
-      .map(|((a, b), c)| *a + *r * *b + *r * *r * *c)
-      .collect::<Vec<E::Scalar>>();
+    if T.len() != E1.len() || E2.len() != E1.len() {
+      return Err(NovaError::InvalidWitnessLength);
+    }
+    let E = E1.fold(T, r, Some(E2));
 
     let r_W = *r_W1 + *r * r_W2;
     let r_E = *r_E1 + *r * r_T + *r * *r * *r_E2;
@@ -607,8 +842,7 @@ impl<E: Engine> RelaxedR1CSWitness<E> {
     let mut W = self.W.clone();
     W.extend(vec![E::Scalar::ZERO; S.num_vars - W.len()]);
 
-    let mut E = self.E.clone();
-    E.extend(vec![E::Scalar::ZERO; S.num_cons - E.len()]);
+    let E = self.E.pad(S.num_cons);
 
     Self {
       W,
@@ -735,15 +969,38 @@ impl<E: Engine> RelaxedR1CSInstance<E> {
     r_W: &E::Scalar,
     r_E: &E::Scalar,
   ) -> RelaxedR1CSInstance<E> {
+    let comms = CE::<E>::derandomize_batch(dk, &[self.comm_W, self.comm_E], &[*r_W, *r_E]);
     RelaxedR1CSInstance {
-      comm_W: CE::<E>::derandomize(dk, &self.comm_W, r_W),
-      comm_E: CE::<E>::derandomize(dk, &self.comm_E, r_E),
+      comm_W: comms[0],
+      comm_E: comms[1],
       X: self.X.clone(),
       u: self.u,
     }
   }
 }
 
+impl<E: Engine> CalldataEncoding for RelaxedR1CSInstance<E>
+where
+  E::Scalar: CalldataEncoding,
+  Commitment<E>: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.comm_W.encode(enc);
+    self.comm_E.encode(enc);
+    self.X.encode(enc);
+    self.u.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      comm_W: CalldataEncoding::decode(dec)?,
+      comm_E: CalldataEncoding::decode(dec)?,
+      X: CalldataEncoding::decode(dec)?,
+      u: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
+
 impl<E: Engine> TranscriptReprTrait<E::GE> for RelaxedR1CSInstance<E> {
   fn to_transcript_bytes(&self) -> Vec<u8> {
     [
@@ -764,10 +1021,24 @@ impl<E: Engine> AbsorbInROTrait<E> for RelaxedR1CSInstance<E> {
 
     // absorb each element of self.X in bignum format
     for x in &self.X {
-      let limbs: Vec<E::Scalar> = nat_to_limbs(&f_to_nat(x), BN_LIMB_WIDTH, BN_N_LIMBS).unwrap();
-      for limb in limbs {
-        ro.absorb(scalar_as_base::<E>(limb));
-      }
+      let limbs: Vec<E::Base> = nat_to_limbs(&f_to_nat(x), BN_LIMB_WIDTH, BN_N_LIMBS)
+        .unwrap()
+        .into_iter()
+        .map(scalar_as_base::<E>)
+        .collect();
+      ro.absorb_batch(&limbs);
+    }
+  }
+}
+
+impl<E: Engine> AbsorbInRO2Trait<E> for RelaxedR1CSInstance<E> {
+  fn absorb_in_ro2(&self, ro: &mut E::RO2) {
+    self.comm_W.absorb_in_ro2(ro);
+    self.comm_E.absorb_in_ro2(ro);
+    ro.absorb(self.u);
+
+    for x in &self.X {
+      ro.absorb(*x);
     }
   }
 }
@@ -780,6 +1051,7 @@ mod tests {
   use crate::{
     provider::{Bn256EngineKZG, PallasEngine, Secp256k1Engine},
     r1cs::sparse::SparseMatrix,
+    testing::MockEngine,
     traits::{snark::default_ck_hint, Engine},
   };
 
@@ -849,6 +1121,55 @@ mod tests {
     res.unwrap()
   }
 
+  fn test_cross_term_with<E: Engine>() {
+    use rand::{rngs::OsRng, RngCore};
+
+    // a random shape with several tiles of constraints, the last of which is partial
+    let (num_cons, num_vars, num_io) = (2 * CROSS_TERM_TILE_SIZE + 7, 100, 2);
+    let S = R1CSShape::<E>::random(num_cons, num_vars, num_io, OsRng.next_u64());
+
+    let random_vec = |n: usize| (0..n).map(|_| E::Scalar::random(OsRng)).collect::<Vec<_>>();
+    let (W1, X1, E1) = (
+      random_vec(num_vars),
+      random_vec(num_io),
+      random_vec(num_cons),
+    );
+    let (W2, X2) = (random_vec(num_vars), random_vec(num_io));
+    // a sparse error vector, whose entries are subtracted separately
+    let mut E2 = vec![E::Scalar::ZERO; num_cons];
+    E2[1] = E::Scalar::random(OsRng);
+    let (u1, u2) = (E::Scalar::random(OsRng), E::Scalar::random(OsRng));
+
+    let Z = R1CSShape::<E>::sum_z(&W1, &u1, &X1, &W2, &u2, &X2);
+    let T = S
+      .cross_term(
+        &Z,
+        &(u1 + u2),
+        &ErrorVector::Dense(E1.clone()),
+        Some(&ErrorVector::from_dense(E2.clone())),
+      )
+      .unwrap();
+
+    // compare against the direct evaluation of the matrix-vector products
+    let (AZ, BZ, CZ) = S.multiply_vec(&Z).unwrap();
+    let expected = (0..num_cons)
+      .map(|i| AZ[i] * BZ[i] - (u1 + u2) * CZ[i] - E1[i] - E2[i])
+      .collect::<Vec<_>>();
+    assert_eq!(T, expected);
+
+    assert_eq!(
+      S.cross_term(&Z[1..], &u1, &ErrorVector::Dense(E1), None),
+      Err(NovaError::InvalidWitnessLength)
+    );
+  }
+
+  #[test]
+  fn test_cross_term() {
+    test_cross_term_with::<PallasEngine>();
+    test_cross_term_with::<Bn256EngineKZG>();
+    test_cross_term_with::<MockEngine>();
+  }
+
   fn test_pad_tiny_r1cs_with<E: Engine>() {
     let padded_r1cs = tiny_r1cs::<E>(3).pad();
     assert!(padded_r1cs.is_regular_shape());
@@ -863,6 +1184,7 @@ mod tests {
     test_pad_tiny_r1cs_with::<PallasEngine>();
     test_pad_tiny_r1cs_with::<Bn256EngineKZG>();
     test_pad_tiny_r1cs_with::<Secp256k1Engine>();
+    test_pad_tiny_r1cs_with::<MockEngine>();
   }
 
   fn test_random_sample_with<E: Engine>() {
@@ -877,5 +1199,82 @@ mod tests {
     test_random_sample_with::<PallasEngine>();
     test_random_sample_with::<Bn256EngineKZG>();
     test_random_sample_with::<Secp256k1Engine>();
+    test_random_sample_with::<MockEngine>();
+  }
+
+  fn test_commit_detect_density_with<E: Engine>() {
+    let ck = CE::<E>::setup(b"test", 64);
+    let r = E::Scalar::random(&mut OsRng);
+    // a sparse vector, committed to with `commit_sparse`, and a dense one
+    let mut sparse = vec![E::Scalar::ZERO; 64];
+    sparse[7] = E::Scalar::random(&mut OsRng);
+    sparse[63] = E::Scalar::ONE;
+    let dense = (0..64)
+      .map(|_| E::Scalar::random(&mut OsRng))
+      .collect::<Vec<_>>();
+    for v in [sparse, dense, vec![E::Scalar::ZERO; 64], vec![]] {
+      assert_eq!(
+        commit_detect_density::<E>(&ck, &v, &r),
+        CE::<E>::commit(&ck, &v, &r)
+      );
+    }
+  }
+
+  #[test]
+  fn test_commit_detect_density() {
+    test_commit_detect_density_with::<PallasEngine>();
+    test_commit_detect_density_with::<Bn256EngineKZG>();
+  }
+
+  fn test_error_vector_with<E: Engine>() {
+    let ck = CE::<E>::setup(b"test", 64);
+    let (r, r_E) = (E::Scalar::random(&mut OsRng), E::Scalar::random(&mut OsRng));
+    let fold_dense = |E1: &[E::Scalar], T: &[E::Scalar], E2: &[E::Scalar]| {
+      (0..E1.len())
+        .map(|i| E1[i] + r * T[i] + r * r * E2[i])
+        .collect::<Vec<_>>()
+    };
+
+    // folding in a sparse cross-term keeps the error vector sparse
+    let E0 = ErrorVector::<E::Scalar>::zero(64);
+    let mut T = vec![E::Scalar::ZERO; 64];
+    T[3] = E::Scalar::random(&mut OsRng);
+    T[40] = E::Scalar::random(&mut OsRng);
+    let E1 = E0.fold(&T, &r, None);
+    assert!(E1.is_sparse());
+    let E1_dense = fold_dense(&E0.to_dense(), &T, &[E::Scalar::ZERO; 64]);
+    assert_eq!(E1.to_dense().to_vec(), E1_dense);
+    assert_eq!(
+      E1.commit::<E>(&ck, &r_E),
+      CE::<E>::commit(&ck, &E1_dense, &r_E)
+    );
+
+    // and folding in a dense one densifies it
+    let T = (0..64)
+      .map(|_| E::Scalar::random(&mut OsRng))
+      .collect::<Vec<_>>();
+    let E2 = E1.fold(&T, &r, Some(&E1));
+    assert!(!E2.is_sparse());
+    let E2_dense = fold_dense(&E1_dense, &T, &E1_dense);
+    assert_eq!(E2, ErrorVector::Dense(E2_dense.clone()));
+    assert_eq!(
+      E2.commit::<E>(&ck, &r_E),
+      CE::<E>::commit(&ck, &E2_dense, &r_E)
+    );
+
+    // padding and the conversion to and from dense vectors preserve the entries
+    let E1_padded = E1.pad(128);
+    assert!(E1_padded.is_sparse());
+    assert_eq!(E1_padded.len(), 128);
+    assert_eq!(&E1_padded.to_dense()[..64], &E1_dense[..]);
+    assert_eq!(ErrorVector::from(E1_dense.clone()), E1);
+    assert_eq!(Vec::from(E1), E1_dense);
+  }
+
+  #[test]
+  fn test_error_vector() {
+    test_error_vector_with::<PallasEngine>();
+    test_error_vector_with::<Bn256EngineKZG>();
+    test_error_vector_with::<MockEngine>();
   }
 }
@@ -96,6 +96,15 @@ impl<F: PrimeField> SparseMatrix<F> {
       .collect()
   }
 
+  /// Computes the inner product of a row with a dense vector.
+  /// This does not check that the shape of the matrix/vector are compatible.
+  pub fn multiply_row_unchecked(&self, row: usize, vector: &[F]) -> F {
+    self
+      .get_row_unchecked(&[self.indptr[row], self.indptr[row + 1]])
+      .map(|(val, col_idx)| *val * vector[*col_idx])
+      .sum()
+  }
+
   /// number of non-zero entries
   pub fn len(&self) -> usize {
     *self.indptr.last().unwrap()
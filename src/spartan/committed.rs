@@ -0,0 +1,207 @@
+//! This module provides a commit-and-prove interface, which links a commitment to external data,
+//! produced outside of Nova (e.g., by a data-availability layer), to witness values of a circuit.
+//!
+//! The data is treated as a multilinear polynomial, as in the evaluation engines. The point at which
+//! it is evaluated is derived from the external commitment alone, so the prover cannot choose the data
+//! after seeing it. The circuit allocates the data as witness values, takes the point as input, and
+//! computes the evaluation with `evaluate_committed`; outside of the circuit, a `CommittedDataProof`
+//! shows that the external commitment opens to a polynomial with the same evaluation. The data is
+//! thus never committed to again, and the verifier only checks an evaluation argument.
+//!
+//! The external commitment must be made with the commitment key of the evaluation engine and with a
+//! zero blind, i.e., it is `commit(ck, data, 0)`.
+use crate::{
+  errors::NovaError,
+  frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+  gadgets::utils::alloc_zero,
+  spartan::polys::multilinear::MultilinearPolynomial,
+  traits::{evaluation::EvaluationEngineTrait, Engine, TranscriptEngineTrait},
+  Commitment, CommitmentKey,
+};
+use ff::{Field, PrimeField};
+use serde::{Deserialize, Serialize};
+
+/// Returns the number of variables of the multilinear polynomial holding `num_entries` entries
+fn num_vars(num_entries: usize) -> usize {
+  num_entries.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Returns the point at which data of `num_entries` entries committed to in `comm` is evaluated,
+/// together with the transcript that the evaluation argument continues
+fn challenge<E: Engine>(
+  comm: &Commitment<E>,
+  num_entries: usize,
+) -> Result<(Vec<E::Scalar>, E::TE), NovaError> {
+  let mut transcript = E::TE::new(b"CommittedData");
+  transcript.absorb(b"C", comm);
+  transcript.absorb(b"n", &E::Scalar::from(num_entries as u64));
+  let point = (0..num_vars(num_entries))
+    .map(|_| transcript.squeeze(b"r"))
+    .collect::<Result<Vec<_>, NovaError>>()?;
+  Ok((point, transcript))
+}
+
+/// Returns the point at which data of `num_entries` entries committed to in `comm` is evaluated,
+/// which the circuit that uses the data takes as input
+pub fn committed_data_point<E: Engine>(
+  comm: &Commitment<E>,
+  num_entries: usize,
+) -> Result<Vec<E::Scalar>, NovaError> {
+  Ok(challenge::<E>(comm, num_entries)?.0)
+}
+
+/// A proof that an external commitment opens to data whose evaluation, at the point derived from
+/// the commitment, is a given value
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CommittedDataProof<E: Engine, EE: EvaluationEngineTrait<E>> {
+  num_entries: usize,
+  eval: E::Scalar,
+  arg: EE::EvaluationArgument,
+}
+
+impl<E: Engine, EE: EvaluationEngineTrait<E>> CommittedDataProof<E, EE> {
+  /// Proves that `comm`, the commitment to `data` with a zero blind, opens to `data`
+  pub fn prove(
+    ck: &CommitmentKey<E>,
+    pk: &EE::ProverKey,
+    comm: &Commitment<E>,
+    data: &[E::Scalar],
+  ) -> Result<Self, NovaError> {
+    if data.is_empty() {
+      return Err(NovaError::InvalidInputLength);
+    }
+
+    let (point, mut transcript) = challenge::<E>(comm, data.len())?;
+    let mut poly = data.to_vec();
+    poly.resize(data.len().next_power_of_two(), E::Scalar::ZERO);
+    let eval = MultilinearPolynomial::evaluate_with(&poly, &point);
+    let arg = EE::prove(ck, pk, &mut transcript, comm, &poly, &point, &eval)?;
+
+    Ok(Self {
+      num_entries: data.len(),
+      eval,
+      arg,
+    })
+  }
+
+  /// Returns the number of entries of the committed data
+  pub fn num_entries(&self) -> usize {
+    self.num_entries
+  }
+
+  /// Verifies the proof against `comm`, and returns the point and the evaluation of the committed
+  /// data at that point. The caller checks that the circuit was given the point as input and that
+  /// the value `evaluate_committed` computed in it equals the evaluation.
+  pub fn verify(
+    &self,
+    vk: &EE::VerifierKey,
+    comm: &Commitment<E>,
+  ) -> Result<(Vec<E::Scalar>, E::Scalar), NovaError> {
+    if self.num_entries == 0 {
+      return Err(NovaError::InvalidInputLength);
+    }
+
+    let (point, mut transcript) = challenge::<E>(comm, self.num_entries)?;
+    EE::verify(vk, &mut transcript, comm, &point, &self.eval, &self.arg)?;
+    Ok((point, self.eval))
+  }
+}
+
+/// Computes in the circuit the evaluation of `data`, as a multilinear polynomial padded with zeros,
+/// at `point`, which must have `log2(data.len().next_power_of_two())` coordinates.
+///
+/// The evaluation costs one constraint per entry of the padded data, less one.
+pub fn evaluate_committed<F, CS>(
+  mut cs: CS,
+  data: &[AllocatedNum<F>],
+  point: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError>
+where
+  F: PrimeField,
+  CS: ConstraintSystem<F>,
+{
+  if data.is_empty() || point.len() != num_vars(data.len()) {
+    return Err(SynthesisError::Unsatisfiable);
+  }
+
+  let mut evals = data.to_vec();
+  if evals.len() < evals.len().next_power_of_two() {
+    let zero = alloc_zero(cs.namespace(|| "zero"));
+    evals.resize(evals.len().next_power_of_two(), zero);
+  }
+
+  // binds the top variable first, as `MultilinearPolynomial::bind_poly_var_top` does
+  for (i, r) in point.iter().enumerate() {
+    let half = evals.len() / 2;
+    evals = (0..half)
+      .map(|j| {
+        let (lo, hi) = (&evals[j], &evals[j + half]);
+        let bound = AllocatedNum::alloc(cs.namespace(|| format!("bound {i} {j}")), || {
+          let lo_val = lo.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+          let hi_val = hi.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+          let r_val = r.get_value().ok_or(SynthesisError::AssignmentMissing)?;
+          Ok(lo_val + r_val * (hi_val - lo_val))
+        })?;
+        cs.enforce(
+          || format!("bind {i} {j}"),
+          |lc| lc + r.get_variable(),
+          |lc| lc + hi.get_variable() - lo.get_variable(),
+          |lc| lc + bound.get_variable() - lo.get_variable(),
+        );
+        Ok(bound)
+      })
+      .collect::<Result<Vec<_>, SynthesisError>>()?;
+  }
+
+  Ok(evals.remove(0))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::test_cs::TestConstraintSystem,
+    provider::{hyperkzg, ipa_pc, Bn256EngineKZG, PallasEngine},
+    traits::commitment::CommitmentEngineTrait,
+  };
+
+  fn test_committed_data_with<E: Engine, EE: EvaluationEngineTrait<E>>() {
+    let mut rng = rand::thread_rng();
+    let data: Vec<E::Scalar> = (0..13).map(|_| E::Scalar::random(&mut rng)).collect();
+    let ck = E::CE::setup(b"test", 16);
+    let (pk, vk) = EE::setup(&ck);
+
+    // the data is committed to outside of Nova
+    let comm = E::CE::commit(&ck, &data, &E::Scalar::ZERO);
+    let proof = CommittedDataProof::<E, EE>::prove(&ck, &pk, &comm, &data).unwrap();
+    let (point, eval) = proof.verify(&vk, &comm).unwrap();
+    assert_eq!(point, committed_data_point::<E>(&comm, data.len()).unwrap());
+
+    // the circuit computes the same evaluation from its witness values
+    let mut cs = TestConstraintSystem::<E::Scalar>::new();
+    let alloc = |cs: &mut TestConstraintSystem<E::Scalar>, name: &str, v: &[E::Scalar]| {
+      v.iter()
+        .enumerate()
+        .map(|(i, v)| AllocatedNum::alloc_infallible(cs.namespace(|| format!("{name} {i}")), || *v))
+        .collect::<Vec<_>>()
+    };
+    let data_vars = alloc(&mut cs, "data", &data);
+    let point_vars = alloc(&mut cs, "point", &point);
+    let result = evaluate_committed(cs.namespace(|| "eval"), &data_vars, &point_vars).unwrap();
+    assert!(cs.is_satisfied());
+    assert_eq!(result.get_value(), Some(eval));
+
+    // the proof does not verify against the commitment to other data
+    let mut other = data.clone();
+    other[0] += E::Scalar::ONE;
+    let other_comm = E::CE::commit(&ck, &other, &E::Scalar::ZERO);
+    assert!(proof.verify(&vk, &other_comm).is_err());
+  }
+
+  #[test]
+  fn test_committed_data() {
+    test_committed_data_with::<PallasEngine, ipa_pc::EvaluationEngine<_>>();
+    test_committed_data_with::<Bn256EngineKZG, hyperkzg::EvaluationEngine<_>>();
+  }
+}
@@ -0,0 +1,393 @@
+//! This module provides commitments to matrices, such as the rows of a database table or the
+//! trace of an inference, that open to whole rows or whole columns, so that applications folding
+//! over tabular data do not flatten it into a vector of their own layout.
+//!
+//! A `MatrixCommitment` holds a commitment to each row, with a zero blind, and a digest that binds
+//! them all, which applications absorb wherever they need a single value. A `RowOpening` reveals any
+//! set of rows, and a `ColumnOpening` reveals one column, each with a single evaluation argument over
+//! a random combination of the row commitments. As for evaluation engines, a row of `n` entries is
+//! a multilinear polynomial in `log2(n.next_power_of_two())` variables, and at least one.
+use crate::{
+  errors::NovaError,
+  spartan::polys::multilinear::MultilinearPolynomial,
+  traits::{
+    commitment::{CommitmentEngineTrait, Len},
+    evaluation::EvaluationEngineTrait,
+    Engine, TranscriptEngineTrait,
+  },
+  Commitment, CommitmentKey,
+};
+use ff::Field;
+use serde::{Deserialize, Serialize};
+
+/// Returns the number of entries that rows of `num_cols` entries are padded to
+fn padded_len(num_cols: usize) -> usize {
+  num_cols.next_power_of_two().max(2)
+}
+
+/// A commitment to a matrix, made of a commitment to each of its rows
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct MatrixCommitment<E: Engine> {
+  num_cols: usize,
+  rows: Vec<Commitment<E>>,
+}
+
+impl<E: Engine> MatrixCommitment<E> {
+  /// Commits to `matrix`, given as its rows, which must all have the same, nonzero, number of
+  /// entries. Returns `NovaError::InvalidCommitmentKeyLength` if `ck` is too short for the rows.
+  pub fn commit(ck: &CommitmentKey<E>, matrix: &[Vec<E::Scalar>]) -> Result<Self, NovaError> {
+    let num_cols = matrix.first().map_or(0, |row| row.len());
+    if num_cols == 0 || matrix.iter().any(|row| row.len() != num_cols) {
+      return Err(NovaError::InvalidInputLength);
+    }
+    if ck.length() < padded_len(num_cols) {
+      return Err(NovaError::InvalidCommitmentKeyLength);
+    }
+
+    let rows = E::CE::batch_commit(ck, matrix, &vec![E::Scalar::ZERO; matrix.len()]);
+    Ok(Self { num_cols, rows })
+  }
+
+  /// Returns the number of rows of the matrix
+  pub fn num_rows(&self) -> usize {
+    self.rows.len()
+  }
+
+  /// Returns the number of columns of the matrix
+  pub fn num_cols(&self) -> usize {
+    self.num_cols
+  }
+
+  /// Returns the commitments to the rows of the matrix
+  pub fn rows(&self) -> &[Commitment<E>] {
+    &self.rows
+  }
+
+  /// Returns a digest that binds the dimensions of the matrix and the commitments to its rows
+  pub fn digest(&self) -> Result<E::Scalar, NovaError> {
+    self.transcript().squeeze(b"digest")
+  }
+
+  /// Returns a transcript that has absorbed the commitment, which openings continue
+  fn transcript(&self) -> E::TE {
+    let mut transcript = E::TE::new(b"MatrixCommitment");
+    transcript.absorb(b"m", &E::Scalar::from(self.rows.len() as u64));
+    transcript.absorb(b"n", &E::Scalar::from(self.num_cols as u64));
+    transcript.absorb(b"C", &self.rows.as_slice());
+    transcript
+  }
+
+  /// Returns the commitment to the combination of the rows at `indices` with the powers of `rho`
+  fn combine(&self, indices: impl Iterator<Item = usize>, rho: &E::Scalar) -> Commitment<E> {
+    let mut power = E::Scalar::ONE;
+    let mut comm = Commitment::<E>::default();
+    for i in indices {
+      comm = comm + self.rows[i] * power;
+      power *= rho;
+    }
+    comm
+  }
+}
+
+/// Returns the combination of `rows` with the powers of `rho`, padded to `padded_len(num_cols)`
+fn combine_rows<'a, F: Field>(
+  rows: impl Iterator<Item = &'a [F]>,
+  num_cols: usize,
+  rho: &F,
+) -> Vec<F> {
+  let mut combined = vec![F::ZERO; padded_len(num_cols)];
+  let mut power = F::ONE;
+  for row in rows {
+    for (c, v) in combined.iter_mut().zip(row) {
+      *c += power * v;
+    }
+    power *= rho;
+  }
+  combined
+}
+
+/// An opening of a `MatrixCommitment` to the values of some of its rows
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RowOpening<E: Engine, EE: EvaluationEngineTrait<E>> {
+  indices: Vec<usize>,
+  rows: Vec<Vec<E::Scalar>>,
+  arg: EE::EvaluationArgument,
+}
+
+impl<E: Engine, EE: EvaluationEngineTrait<E>> RowOpening<E, EE> {
+  /// Opens `comm`, the commitment to `matrix`, at the rows `indices`
+  pub fn prove(
+    ck: &CommitmentKey<E>,
+    pk: &EE::ProverKey,
+    comm: &MatrixCommitment<E>,
+    matrix: &[Vec<E::Scalar>],
+    indices: &[usize],
+  ) -> Result<Self, NovaError> {
+    if matrix.len() != comm.num_rows() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    if indices.is_empty() || indices.iter().any(|i| *i >= comm.num_rows()) {
+      return Err(NovaError::InvalidIndex);
+    }
+
+    let rows = indices
+      .iter()
+      .map(|i| matrix[*i].clone())
+      .collect::<Vec<_>>();
+    let mut claim = row_claim(comm, indices, &rows)?;
+    let arg = EE::prove(
+      ck,
+      pk,
+      &mut claim.transcript,
+      &claim.comm,
+      &claim.poly,
+      &claim.point,
+      &claim.eval,
+    )?;
+    Ok(Self {
+      indices: indices.to_vec(),
+      rows,
+      arg,
+    })
+  }
+
+  /// Verifies the opening against `comm`, and returns the opened rows, in the order of `indices`
+  pub fn verify(
+    &self,
+    vk: &EE::VerifierKey,
+    comm: &MatrixCommitment<E>,
+  ) -> Result<&[Vec<E::Scalar>], NovaError> {
+    if self.indices.is_empty() || self.indices.iter().any(|i| *i >= comm.num_rows()) {
+      return Err(NovaError::InvalidIndex);
+    }
+    let mut claim = row_claim(comm, &self.indices, &self.rows)?;
+    EE::verify(
+      vk,
+      &mut claim.transcript,
+      &claim.comm,
+      &claim.point,
+      &claim.eval,
+      &self.arg,
+    )?;
+    Ok(&self.rows)
+  }
+
+  /// Returns the indices of the opened rows
+  pub fn indices(&self) -> &[usize] {
+    &self.indices
+  }
+}
+
+/// The claim that the combination of some rows, committed to in `comm`, evaluates to `eval` at
+/// `point`, along with the transcript that the evaluation argument continues
+struct RowClaim<E: Engine> {
+  transcript: E::TE,
+  point: Vec<E::Scalar>,
+  comm: Commitment<E>,
+  poly: Vec<E::Scalar>,
+  eval: E::Scalar,
+}
+
+/// Derives the claim about the combination of the rows `rows` at `indices` of the matrix
+/// committed to in `comm`
+fn row_claim<E: Engine>(
+  comm: &MatrixCommitment<E>,
+  indices: &[usize],
+  rows: &[Vec<E::Scalar>],
+) -> Result<RowClaim<E>, NovaError> {
+  if rows.len() != indices.len() || rows.iter().any(|row| row.len() != comm.num_cols()) {
+    return Err(NovaError::InvalidInputLength);
+  }
+
+  let mut transcript = comm.transcript();
+  for (i, row) in indices.iter().zip(rows) {
+    transcript.absorb(b"i", &E::Scalar::from(*i as u64));
+    transcript.absorb(b"row", &row.as_slice());
+  }
+  let rho = transcript.squeeze(b"rho")?;
+  let num_vars = padded_len(comm.num_cols()).trailing_zeros() as usize;
+  let point = (0..num_vars)
+    .map(|_| transcript.squeeze(b"r"))
+    .collect::<Result<Vec<_>, NovaError>>()?;
+
+  let poly = combine_rows(rows.iter().map(|row| row.as_slice()), comm.num_cols(), &rho);
+  let eval = MultilinearPolynomial::evaluate_with(&poly, &point);
+  Ok(RowClaim {
+    transcript,
+    point,
+    comm: comm.combine(indices.iter().copied(), &rho),
+    poly,
+    eval,
+  })
+}
+
+/// An opening of a `MatrixCommitment` to the values of one of its columns
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ColumnOpening<E: Engine, EE: EvaluationEngineTrait<E>> {
+  index: usize,
+  values: Vec<E::Scalar>,
+  arg: EE::EvaluationArgument,
+}
+
+impl<E: Engine, EE: EvaluationEngineTrait<E>> ColumnOpening<E, EE> {
+  /// Opens `comm`, the commitment to `matrix`, at the column `index`
+  pub fn prove(
+    ck: &CommitmentKey<E>,
+    pk: &EE::ProverKey,
+    comm: &MatrixCommitment<E>,
+    matrix: &[Vec<E::Scalar>],
+    index: usize,
+  ) -> Result<Self, NovaError> {
+    if matrix.len() != comm.num_rows() || matrix.iter().any(|row| row.len() != comm.num_cols()) {
+      return Err(NovaError::InvalidInputLength);
+    }
+    if index >= comm.num_cols() {
+      return Err(NovaError::InvalidIndex);
+    }
+
+    let values = matrix.iter().map(|row| row[index]).collect::<Vec<_>>();
+    let (mut transcript, point, rho, eval) = Self::claim(comm, index, &values)?;
+    let combined = combine_rows(
+      matrix.iter().map(|row| row.as_slice()),
+      comm.num_cols(),
+      &rho,
+    );
+    let arg = EE::prove(
+      ck,
+      pk,
+      &mut transcript,
+      &comm.combine(0..comm.num_rows(), &rho),
+      &combined,
+      &point,
+      &eval,
+    )?;
+    Ok(Self { index, values, arg })
+  }
+
+  /// Verifies the opening against `comm`, and returns the values of the column, one per row
+  pub fn verify(
+    &self,
+    vk: &EE::VerifierKey,
+    comm: &MatrixCommitment<E>,
+  ) -> Result<&[E::Scalar], NovaError> {
+    if self.index >= comm.num_cols() {
+      return Err(NovaError::InvalidIndex);
+    }
+    let (mut transcript, point, rho, eval) = Self::claim(comm, self.index, &self.values)?;
+    EE::verify(
+      vk,
+      &mut transcript,
+      &comm.combine(0..comm.num_rows(), &rho),
+      &point,
+      &eval,
+      &self.arg,
+    )?;
+    Ok(&self.values)
+  }
+
+  /// Returns the index of the opened column
+  pub fn index(&self) -> usize {
+    self.index
+  }
+
+  /// Derives the evaluation claim about the combination of all rows: the transcript that the
+  /// argument continues, the point that selects the column, the combination challenge, and the
+  /// combination of the values of the column
+  fn claim(
+    comm: &MatrixCommitment<E>,
+    index: usize,
+    values: &[E::Scalar],
+  ) -> Result<(E::TE, Vec<E::Scalar>, E::Scalar, E::Scalar), NovaError> {
+    if values.len() != comm.num_rows() {
+      return Err(NovaError::InvalidInputLength);
+    }
+
+    let mut transcript = comm.transcript();
+    transcript.absorb(b"j", &E::Scalar::from(index as u64));
+    transcript.absorb(b"col", &values);
+    let rho = transcript.squeeze(b"rho")?;
+
+    // the entry at `index` is the evaluation at its bits, with the top variable first
+    let num_vars = padded_len(comm.num_cols()).trailing_zeros() as usize;
+    let point = (0..num_vars)
+      .map(|k| {
+        if (index >> (num_vars - 1 - k)) & 1 == 1 {
+          E::Scalar::ONE
+        } else {
+          E::Scalar::ZERO
+        }
+      })
+      .collect();
+
+    let mut power = E::Scalar::ONE;
+    let mut eval = E::Scalar::ZERO;
+    for v in values {
+      eval += power * v;
+      power *= rho;
+    }
+    Ok((transcript, point, rho, eval))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::provider::{hyperkzg, ipa_pc, Bn256EngineKZG, PallasEngine};
+
+  fn test_matrix_commitment_with<E: Engine, EE: EvaluationEngineTrait<E>>() {
+    let mut rng = rand::thread_rng();
+    let matrix: Vec<Vec<E::Scalar>> = (0..5)
+      .map(|_| (0..6).map(|_| E::Scalar::random(&mut rng)).collect())
+      .collect();
+    let ck = E::CE::setup(b"test", 8);
+    let (pk, vk) = EE::setup(&ck);
+
+    let comm = MatrixCommitment::<E>::commit(&ck, &matrix).unwrap();
+    assert_eq!((comm.num_rows(), comm.num_cols()), (5, 6));
+    assert_eq!(
+      MatrixCommitment::<E>::commit(&ck, &[vec![E::Scalar::ONE; 9]]),
+      Err(NovaError::InvalidCommitmentKeyLength)
+    );
+
+    // rows and columns open to the values of the matrix
+    let rows = RowOpening::<E, EE>::prove(&ck, &pk, &comm, &matrix, &[3, 1]).unwrap();
+    assert_eq!(
+      rows.verify(&vk, &comm).unwrap(),
+      [matrix[3].clone(), matrix[1].clone()]
+    );
+    for j in [0, 4, 5] {
+      let col = ColumnOpening::<E, EE>::prove(&ck, &pk, &comm, &matrix, j).unwrap();
+      let values = matrix.iter().map(|row| row[j]).collect::<Vec<_>>();
+      assert_eq!(col.verify(&vk, &comm).unwrap(), values);
+    }
+    assert_eq!(
+      RowOpening::<E, EE>::prove(&ck, &pk, &comm, &matrix, &[5]).err(),
+      Some(NovaError::InvalidIndex)
+    );
+
+    // openings do not verify against another matrix, or with other values
+    let mut other = matrix.clone();
+    other[1][2] += E::Scalar::ONE;
+    let other_comm = MatrixCommitment::<E>::commit(&ck, &other).unwrap();
+    assert_ne!(other_comm.digest().unwrap(), comm.digest().unwrap());
+    assert!(rows.verify(&vk, &other_comm).is_err());
+    let col = ColumnOpening::<E, EE>::prove(&ck, &pk, &comm, &matrix, 2).unwrap();
+    assert!(col.verify(&vk, &other_comm).is_err());
+
+    let mut forged = rows.clone();
+    forged.rows[1][2] += E::Scalar::ONE;
+    assert!(forged.verify(&vk, &comm).is_err());
+    let mut forged = col.clone();
+    forged.values[1] += E::Scalar::ONE;
+    assert!(forged.verify(&vk, &comm).is_err());
+  }
+
+  #[test]
+  fn test_matrix_commitment() {
+    test_matrix_commitment_with::<PallasEngine, ipa_pc::EvaluationEngine<PallasEngine>>();
+    test_matrix_commitment_with::<Bn256EngineKZG, hyperkzg::EvaluationEngine<Bn256EngineKZG>>();
+  }
+}
@@ -2,10 +2,15 @@
 //! over the polynomial commitment and evaluation argument (i.e., a PCS)
 //! We provide two implementations, one in snark.rs (which does not use any preprocessing)
 //! and another in ppsnark.rs (which uses preprocessing to keep the verifier's state small if the PCS provides a succinct verifier)
+//! snark.rs also provides a variant that leaves its final evaluation claims open, so that the claims of many proofs can be opened together.
 //! We also provide direct.rs that allows proving a step circuit directly with either of the two SNARKs.
+//! In committed.rs we provide a commit-and-prove interface, which links commitments to external data to witness values.
+//! In matrix.rs we provide commitments to matrices that open to whole rows or columns.
 //!
 //! In polynomial.rs we also provide foundational types and functions for manipulating multilinear polynomials.
+pub mod committed;
 pub mod direct;
+pub mod matrix;
 pub mod ppsnark;
 pub mod snark;
 
@@ -52,7 +57,7 @@ impl<E: Engine> PolyEvalWitness<E> {
 
     let size_max = W.iter().map(|w| w.p.len()).max().unwrap();
     // Scale the input polynomials by the power of s
-    let num_chunks = rayon::current_num_threads().next_power_of_two();
+    let num_chunks = crate::provider::util::num_chunks().next_power_of_two();
     let chunk_size = size_max / num_chunks;
 
     let p = if chunk_size > 0 {
@@ -121,7 +126,7 @@ impl<E: Engine> PolyEvalWitness<E> {
 
     let powers_of_s = powers::<E>(s, p_vec.len());
 
-    let num_chunks = rayon::current_num_threads().next_power_of_two();
+    let num_chunks = crate::provider::util::num_chunks().next_power_of_two();
     let chunk_size = p_vec[0].len() / num_chunks;
 
     let p = if chunk_size > 0 {
@@ -277,3 +282,24 @@ fn compute_eval_table_sparse<E: Engine>(
 
   (A_evals, B_evals, C_evals)
 }
+
+/// Bounds "row" variables of the combination A + r * B + r^2 * C of the R1CS matrices viewed as
+/// 2d multilinear polynomials, without materializing the table of each matrix
+fn compute_eval_table_sparse_combined<E: Engine>(
+  S: &R1CSShape<E>,
+  rx: &[E::Scalar],
+  r: &E::Scalar,
+) -> Vec<E::Scalar> {
+  assert_eq!(rx.len(), S.num_cons);
+
+  let mut evals = vec![E::Scalar::ZERO; 2 * S.num_vars];
+  for (M, coeff) in [(&S.A, E::Scalar::ONE), (&S.B, *r), (&S.C, *r * r)] {
+    for (row_idx, ptrs) in M.indptr.windows(2).enumerate() {
+      let scaled_rx = coeff * rx[row_idx];
+      for (val, col_idx) in M.get_row_unchecked(ptrs.try_into().unwrap()) {
+        evals[*col_idx] += scaled_rx * val;
+      }
+    }
+  }
+  evals
+}
@@ -2,6 +2,10 @@
 use ff::PrimeField;
 use rayon::prelude::*;
 
+/// The number of trailing variables whose table `EqPolynomial::evals_from_points` computes directly,
+/// and scales to fill the blocks of larger tables; a block of `2^12` scalars stays in cache
+const EQ_BLOCK_VARS: usize = 12;
+
 /// Represents the multilinear extension polynomial (MLE) of the equality polynomial $eq(x,e)$, denoted as $\tilde{eq}(x, e)$.
 ///
 /// The polynomial is defined by the formula:
@@ -51,6 +55,37 @@ impl<Scalar: PrimeField> EqPolynomial<Scalar> {
   ///
   /// Returns a vector of Scalars, each corresponding to the polynomial evaluation at a specific point.
   pub fn evals_from_points(r: &[Scalar]) -> Vec<Scalar> {
+    Self::evals_from_points_blocked(r, EQ_BLOCK_VARS)
+  }
+
+  /// Computes the table of `evals_from_points` in blocks of `2^block_vars` scalars
+  fn evals_from_points_blocked(r: &[Scalar], block_vars: usize) -> Vec<Scalar> {
+    if r.len() <= block_vars {
+      return Self::evals_from_points_unblocked(r);
+    }
+
+    // eq(r, x) factors over any split of the variables, so the table is the outer product of the
+    // tables of the leading and the trailing variables. Each block of the result is a scaled copy of
+    // the small table of the trailing variables, which is written once, in parallel, instead of
+    // sweeping over the whole table once per variable.
+    let (r_hi, r_lo) = r.split_at(r.len() - block_vars);
+    let evals_hi = Self::evals_from_points_blocked(r_hi, block_vars);
+    let evals_lo = Self::evals_from_points_unblocked(r_lo);
+
+    let mut evals = vec![Scalar::ZERO; evals_hi.len() * evals_lo.len()];
+    evals
+      .par_chunks_mut(evals_lo.len())
+      .zip(evals_hi.par_iter())
+      .for_each(|(block, hi)| {
+        for (e, lo) in block.iter_mut().zip(evals_lo.iter()) {
+          *e = *hi * lo;
+        }
+      });
+    evals
+  }
+
+  /// Computes the table of `evals_from_points` by doubling it once per variable
+  fn evals_from_points_unblocked(r: &[Scalar]) -> Vec<Scalar> {
     let ell = r.len();
     let mut evals: Vec<Scalar> = vec![Scalar::ZERO; (2_usize).pow(ell as u32)];
     let mut size = 1;
@@ -102,6 +137,38 @@ mod tests {
     }
   }
 
+  fn test_evals_blocked_with<F: PrimeField>() {
+    let mut rng = rand::thread_rng();
+    let r = (0..EQ_BLOCK_VARS + 2)
+      .map(|_| F::random(&mut rng))
+      .collect::<Vec<_>>();
+    let expected = EqPolynomial::evals_from_points_unblocked(&r);
+    assert_eq!(EqPolynomial::evals_from_points(&r), expected);
+
+    // blocks of any size, including ones that split the leading variables again
+    for block_vars in [1, 3, 5, r.len()] {
+      assert_eq!(
+        EqPolynomial::evals_from_points_blocked(&r, block_vars),
+        expected
+      );
+    }
+
+    // the entry at index i is eq(r, x), where the bits of x are those of i, most significant first
+    let ell = r.len();
+    for i in [0, 1, (1 << ell) / 3, (1 << ell) - 1] {
+      let x = (0..ell)
+        .map(|j| F::from(((i >> (ell - 1 - j)) & 1) as u64))
+        .collect::<Vec<_>>();
+      assert_eq!(expected[i], EqPolynomial::new(r.clone()).evaluate(&x));
+    }
+  }
+
+  #[test]
+  fn test_evals_blocked() {
+    test_evals_blocked_with::<pallas::Scalar>();
+    test_evals_blocked_with::<bn256::Scalar>();
+  }
+
   #[test]
   fn test_eq_polynomial() {
     test_eq_polynomial_with::<pallas::Scalar>();
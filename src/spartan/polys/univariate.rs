@@ -1,8 +1,14 @@
 //! Main components:
 //! - `UniPoly`: an univariate dense polynomial in coefficient form (big endian),
 //! - `CompressedUniPoly`: a univariate dense polynomial, compressed (omitted linear term), in coefficient form (little endian),
-use crate::traits::{AbsorbInRO2Trait, Engine, Group, ROTrait, TranscriptReprTrait};
-use core::panic;
+use crate::{
+  errors::NovaError,
+  field::batch_invert,
+  traits::{
+    calldata::{CalldataDecoder, CalldataEncoder, CalldataEncoding},
+    AbsorbInRO2Trait, Engine, Group, ROTrait, TranscriptReprTrait,
+  },
+};
 use ff::PrimeField;
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
@@ -21,25 +27,65 @@ pub struct CompressedUniPoly<Scalar: PrimeField> {
   coeffs_except_linear_term: Vec<Scalar>,
 }
 
+impl<Scalar: PrimeField + CalldataEncoding> CalldataEncoding for CompressedUniPoly<Scalar> {
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.coeffs_except_linear_term.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      coeffs_except_linear_term: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
+
 impl<Scalar: PrimeField> UniPoly<Scalar> {
+  /// Interpolates the polynomial of degree less than `evals.len()` that evaluates to `evals[i]`
+  /// at `i`, computing its Lagrange basis with barycentric weights inverted in a single batch
   pub fn from_evals(evals: &[Scalar]) -> Self {
     let n = evals.len();
-    let xs: Vec<Scalar> = (0..n).map(|x| Scalar::from(x as u64)).collect();
-
-    let mut matrix: Vec<Vec<Scalar>> = Vec::with_capacity(n);
-    for i in 0..n {
-      let mut row = Vec::with_capacity(n);
-      let x = xs[i];
-      row.push(Scalar::ONE);
-      row.push(x);
-      for j in 2..n {
-        row.push(row[j - 1] * x);
+
+    // the weight of the point j is 1 / prod_{k != j} (j - k) = 1 / ((-1)^(n-1-j) j! (n-1-j)!)
+    let mut factorials = vec![Scalar::ONE; n];
+    for i in 1..n {
+      factorials[i] = factorials[i - 1] * Scalar::from(i as u64);
+    }
+    let mut weights = (0..n)
+      .map(|j| {
+        let w = factorials[j] * factorials[n - 1 - j];
+        if (n - 1 - j) % 2 == 1 {
+          -w
+        } else {
+          w
+        }
+      })
+      .collect::<Vec<_>>();
+    batch_invert(&mut weights).expect("the interpolation points are distinct");
+
+    // the coefficients of prod_k (x - k), in little endian
+    let mut vanishing = vec![Scalar::ONE];
+    for k in 0..n {
+      let k = Scalar::from(k as u64);
+      vanishing.push(Scalar::ZERO);
+      for i in (1..vanishing.len()).rev() {
+        vanishing[i] = vanishing[i - 1] - k * vanishing[i];
+      }
+      vanishing[0] *= -k;
+    }
+
+    // add evals[j] * weights[j] * prod_{k != j} (x - k), dividing the product of all factors by
+    // (x - j) synthetically
+    let mut coeffs = vec![Scalar::ZERO; n];
+    for j in 0..n {
+      let scale = evals[j] * weights[j];
+      let x = Scalar::from(j as u64);
+      let mut q = Scalar::ZERO;
+      for i in (0..n).rev() {
+        q = vanishing[i + 1] + x * q;
+        coeffs[i] += scale * q;
       }
-      row.push(evals[i]);
-      matrix.push(row);
     }
 
-    let coeffs = gaussian_elimination(&mut matrix);
     Self { coeffs }
   }
 
@@ -114,78 +160,6 @@ impl<E: Engine> AbsorbInRO2Trait<E> for UniPoly<E::Scalar> {
   }
 }
 
-// This code is based on code from https://github.com/a16z/jolt/blob/main/jolt-core/src/utils/gaussian_elimination.rs, which itself is
-// inspired by https://github.com/TheAlgorithms/Rust/blob/master/src/math/gaussian_elimination.rs
-pub fn gaussian_elimination<F: PrimeField>(matrix: &mut [Vec<F>]) -> Vec<F> {
-  let size = matrix.len();
-  assert_eq!(size, matrix[0].len() - 1);
-
-  for i in 0..size - 1 {
-    for j in i..size - 1 {
-      echelon(matrix, i, j);
-    }
-  }
-
-  for i in (1..size).rev() {
-    eliminate(matrix, i);
-  }
-
-  // Disable cargo clippy warnings about needless range loops.
-  // Checking the diagonal like this is simpler than any alternative.
-  #[allow(clippy::needless_range_loop)]
-  for i in 0..size {
-    if matrix[i][i] == F::ZERO {
-      println!("Infinitely many solutions");
-    }
-  }
-
-  let mut result: Vec<F> = vec![F::ZERO; size];
-  for i in 0..size {
-    result[i] = div_f(matrix[i][size], matrix[i][i]);
-  }
-
-  result
-}
-
-fn echelon<F: PrimeField>(matrix: &mut [Vec<F>], i: usize, j: usize) {
-  let size = matrix.len();
-  if matrix[i][i] != F::ZERO {
-    let factor = div_f(matrix[j + 1][i], matrix[i][i]);
-    (i..size + 1).for_each(|k| {
-      let tmp = matrix[i][k];
-      matrix[j + 1][k] -= factor * tmp;
-    });
-  }
-}
-
-fn eliminate<F: PrimeField>(matrix: &mut [Vec<F>], i: usize) {
-  let size = matrix.len();
-  if matrix[i][i] != F::ZERO {
-    for j in (1..i + 1).rev() {
-      let factor = div_f(matrix[j - 1][i], matrix[i][i]);
-      for k in (0..size + 1).rev() {
-        let tmp = matrix[i][k];
-        matrix[j - 1][k] -= factor * tmp;
-      }
-    }
-  }
-}
-
-/// Division of two prime fields
-///
-/// # Panics
-///
-/// Panics if `b` is zero.
-pub fn div_f<F: PrimeField>(a: F, b: F) -> F {
-  let inverse_b = b.invert();
-
-  if inverse_b.into_option().is_none() {
-    panic!("Division by zero");
-  }
-
-  a * inverse_b.unwrap()
-}
-
 #[cfg(test)]
 mod tests {
   use super::*;
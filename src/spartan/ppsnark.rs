@@ -6,6 +6,7 @@
 use crate::{
   digest::{DigestComputer, SimpleDigestible},
   errors::NovaError,
+  field::batch_inverse,
   r1cs::{R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness},
   spartan::{
     math::Math,
@@ -394,33 +395,6 @@ impl<E: Engine> MemorySumcheckInstance<E> {
       || hash_func_vec(mem_col, addr_col, L_col),
     );
 
-    let batch_invert = |v: &[E::Scalar]| -> Result<Vec<E::Scalar>, NovaError> {
-      let mut products = vec![E::Scalar::ZERO; v.len()];
-      let mut acc = E::Scalar::ONE;
-
-      for i in 0..v.len() {
-        products[i] = acc;
-        acc *= v[i];
-      }
-
-      // we can compute an inversion only if acc is non-zero
-      if acc == E::Scalar::ZERO {
-        return Err(NovaError::InternalError);
-      }
-
-      // compute the inverse once for all entries
-      acc = acc.invert().unwrap();
-
-      let mut inv = vec![E::Scalar::ZERO; v.len()];
-      for i in 0..v.len() {
-        let tmp = acc * v[v.len() - 1 - i];
-        inv[v.len() - 1 - i] = products[v.len() - 1 - i] * acc;
-        acc = tmp;
-      }
-
-      Ok(inv)
-    };
-
     // compute vectors TS[i]/(T[i] + r) and 1/(W[i] + r)
     let helper = |T: &[E::Scalar],
                   W: &[E::Scalar],
@@ -440,7 +414,7 @@ impl<E: Engine> MemorySumcheckInstance<E> {
         || {
           rayon::join(
             || {
-              let inv = batch_invert(&T.par_iter().map(|e| *e + *r).collect::<Vec<E::Scalar>>())?;
+              let inv = batch_inverse(&T.par_iter().map(|e| *e + *r).collect::<Vec<E::Scalar>>())?;
 
               // compute inv[i] * TS[i] in parallel
               Ok(
@@ -448,7 +422,7 @@ impl<E: Engine> MemorySumcheckInstance<E> {
                   .collect::<Vec<_>>(),
               )
             },
-            || batch_invert(&W.par_iter().map(|e| *e + *r).collect::<Vec<E::Scalar>>()),
+            || batch_inverse(&W.par_iter().map(|e| *e + *r).collect::<Vec<E::Scalar>>()),
           )
         },
         || {
@@ -473,31 +447,6 @@ impl<E: Engine> MemorySumcheckInstance<E> {
     let t_plus_r_inv_col = t_plus_r_inv_col?;
     let w_plus_r_inv_col = w_plus_r_inv_col?;
 
-    let (
-      (comm_t_plus_r_inv_row, comm_w_plus_r_inv_row),
-      (comm_t_plus_r_inv_col, comm_w_plus_r_inv_col),
-    ) = rayon::join(
-      || {
-        rayon::join(
-          || E::CE::commit(ck, &t_plus_r_inv_row, &E::Scalar::ZERO),
-          || E::CE::commit(ck, &w_plus_r_inv_row, &E::Scalar::ZERO),
-        )
-      },
-      || {
-        rayon::join(
-          || E::CE::commit(ck, &t_plus_r_inv_col, &E::Scalar::ZERO),
-          || E::CE::commit(ck, &w_plus_r_inv_col, &E::Scalar::ZERO),
-        )
-      },
-    );
-
-    let comm_vec = [
-      comm_t_plus_r_inv_row,
-      comm_w_plus_r_inv_row,
-      comm_t_plus_r_inv_col,
-      comm_w_plus_r_inv_col,
-    ];
-
     let poly_vec = [
       t_plus_r_inv_row,
       w_plus_r_inv_row,
@@ -505,6 +454,11 @@ impl<E: Engine> MemorySumcheckInstance<E> {
       w_plus_r_inv_col,
     ];
 
+    // commit to all four oracles with a single batched call
+    let comm_vec: [Commitment<E>; 4] = E::CE::batch_commit(ck, &poly_vec, &[E::Scalar::ZERO; 4])
+      .try_into()
+      .map_err(|_| NovaError::InternalError)?;
+
     let aux_poly_vec = [t_plus_r_row?, w_plus_r_row?, t_plus_r_col?, w_plus_r_col?];
 
     Ok((comm_vec, poly_vec, aux_poly_vec))
@@ -972,9 +926,9 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARK<E, EE> {
 
       let evals: Vec<Vec<E::Scalar>> = evals_mem
         .into_iter()
-        .chain(evals_outer.into_iter())
-        .chain(evals_inner.into_iter())
-        .chain(evals_witness.into_iter())
+        .chain(evals_outer)
+        .chain(evals_inner)
+        .chain(evals_witness)
         .collect::<Vec<Vec<E::Scalar>>>();
       assert_eq!(evals.len(), claims.len());
 
@@ -1115,18 +1069,15 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let z = [W.W.clone(), vec![U.u], U.X.clone()].concat();
 
     // compute Az, Bz, Cz
-    let (mut Az, mut Bz, mut Cz) = S.multiply_vec(&z)?;
+    let (Az, Bz, Cz) = S.multiply_vec(&z)?;
 
-    // commit to Az, Bz, Cz
-    let (comm_Az, (comm_Bz, comm_Cz)) = rayon::join(
-      || E::CE::commit(ck, &Az, &E::Scalar::ZERO),
-      || {
-        rayon::join(
-          || E::CE::commit(ck, &Bz, &E::Scalar::ZERO),
-          || E::CE::commit(ck, &Cz, &E::Scalar::ZERO),
-        )
-      },
-    );
+    // commit to Az, Bz, Cz with a single batched call
+    let ABCz = [Az, Bz, Cz];
+    let [comm_Az, comm_Bz, comm_Cz]: [Commitment<E>; 3] =
+      E::CE::batch_commit(ck, &ABCz, &[E::Scalar::ZERO; 3])
+        .try_into()
+        .map_err(|_| NovaError::InternalError)?;
+    let [mut Az, mut Bz, mut Cz] = ABCz;
 
     transcript.absorb(b"c", &[comm_Az, comm_Bz, comm_Cz].as_slice());
 
@@ -1141,7 +1092,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
       Az.resize(pk.S_repr.N, E::Scalar::ZERO);
       Bz.resize(pk.S_repr.N, E::Scalar::ZERO);
       Cz.resize(pk.S_repr.N, E::Scalar::ZERO);
-      let E = padded::<E>(&W.E, pk.S_repr.N, &E::Scalar::ZERO);
+      let E = padded::<E>(&W.E.to_dense(), pk.S_repr.N, &E::Scalar::ZERO);
       let W = padded::<E>(&W.W, pk.S_repr.N, &E::Scalar::ZERO);
 
       (Az, Bz, Cz, W, E)
@@ -1158,10 +1109,12 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     // L_row(i) = eq(tau, row(i)) for all i
     // L_col(i) = z(col(i)) for all i
     let (mem_row, mem_col, L_row, L_col) = pk.S_repr.evaluation_oracles(&S, &tau, &z);
-    let (comm_L_row, comm_L_col) = rayon::join(
-      || E::CE::commit(ck, &L_row, &E::Scalar::ZERO),
-      || E::CE::commit(ck, &L_col, &E::Scalar::ZERO),
-    );
+    let L = [L_row, L_col];
+    let [comm_L_row, comm_L_col]: [Commitment<E>; 2] =
+      E::CE::batch_commit(ck, &L, &[E::Scalar::ZERO; 2])
+        .try_into()
+        .map_err(|_| NovaError::InternalError)?;
+    let [L_row, L_col] = L;
 
     // since all the three polynomials are opened at tau,
     // we can combine them into a single polynomial opened at tau
@@ -1638,4 +1591,9 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
 
     Ok(())
   }
+
+  fn preprocess(vk: &Self::VerifierKey) {
+    vk.digest();
+    EE::preprocess(&vk.vk_ee);
+  }
 }
@@ -9,7 +9,7 @@ use crate::{
   errors::NovaError,
   r1cs::{R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness, SparseMatrix},
   spartan::{
-    compute_eval_table_sparse,
+    compute_eval_table_sparse, compute_eval_table_sparse_combined,
     math::Math,
     polys::{eq::EqPolynomial, multilinear::MultilinearPolynomial, multilinear::SparsePolynomial},
     powers,
@@ -17,11 +17,12 @@ use crate::{
     PolyEvalInstance, PolyEvalWitness,
   },
   traits::{
+    calldata::{CalldataDecoder, CalldataEncoder, CalldataEncoding},
     evaluation::EvaluationEngineTrait,
-    snark::{DigestHelperTrait, RelaxedR1CSSNARKTrait},
-    Engine, TranscriptEngineTrait,
+    snark::{DigestHelperTrait, MemoryBudget, RelaxedR1CSSNARKTrait},
+    Engine, TranscriptEngineTrait, TranscriptReprTrait,
   },
-  zip_with, CommitmentKey,
+  zip_with, Commitment, CommitmentKey,
 };
 use ff::Field;
 use itertools::Itertools as _;
@@ -76,59 +77,154 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> DigestHelperTrait<E> for VerifierK
 /// A succinct proof of knowledge of a witness to a relaxed R1CS instance
 /// The proof is produced using Spartan's combination of the sum-check and
 /// the commitment to a vector viewed as a polynomial commitment
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct RelaxedR1CSSNARK<E: Engine, EE: EvaluationEngineTrait<E>> {
-  sc_proof_outer: SumcheckProof<E>,
-  claims_outer: (E::Scalar, E::Scalar, E::Scalar),
-  eval_E: E::Scalar,
-  sc_proof_inner: SumcheckProof<E>,
-  eval_W: E::Scalar,
+  deferred: DeferredRelaxedR1CSSNARK<E>,
   sc_proof_batch: SumcheckProof<E>,
   evals_batch: Vec<E::Scalar>,
   eval_arg: EE::EvaluationArgument,
 }
 
-impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for RelaxedR1CSSNARK<E, EE> {
-  type ProverKey = ProverKey<E, EE>;
-  type VerifierKey = VerifierKey<E, EE>;
+impl<E: Engine, EE: EvaluationEngineTrait<E>> CalldataEncoding for RelaxedR1CSSNARK<E, EE>
+where
+  E::Scalar: CalldataEncoding,
+  EE::EvaluationArgument: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.deferred.encode(enc);
+    self.sc_proof_batch.encode(enc);
+    self.evals_batch.encode(enc);
+    self.eval_arg.encode(enc);
+  }
 
-  fn setup(
-    ck: &CommitmentKey<E>,
-    S: &R1CSShape<E>,
-  ) -> Result<(Self::ProverKey, Self::VerifierKey), NovaError> {
-    let (pk_ee, vk_ee) = EE::setup(ck);
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      deferred: CalldataEncoding::decode(dec)?,
+      sc_proof_batch: CalldataEncoding::decode(dec)?,
+      evals_batch: CalldataEncoding::decode(dec)?,
+      eval_arg: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
 
-    let S = S.pad();
+/// A claim that the polynomial committed to in `comm` evaluates to `eval` at `point`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct EvaluationClaim<E: Engine> {
+  /// The commitment to the polynomial
+  pub comm: Commitment<E>,
+  /// The point at which the polynomial is evaluated
+  pub point: Vec<E::Scalar>,
+  /// The claimed evaluation
+  pub eval: E::Scalar,
+}
 
-    let vk: VerifierKey<E, EE> = VerifierKey::new(S, vk_ee);
+impl<E: Engine> TranscriptReprTrait<E::GE> for EvaluationClaim<E> {
+  fn to_transcript_bytes(&self) -> Vec<u8> {
+    [
+      self.comm.to_transcript_bytes(),
+      self.point.as_slice().to_transcript_bytes(),
+      self.eval.to_transcript_bytes(),
+    ]
+    .concat()
+  }
+}
 
-    let pk = ProverKey {
-      pk_ee,
-      vk_digest: vk.digest(),
-    };
+impl<E: Engine> From<PolyEvalInstance<E>> for EvaluationClaim<E> {
+  fn from(u: PolyEvalInstance<E>) -> Self {
+    EvaluationClaim {
+      comm: u.c,
+      point: u.x,
+      eval: u.e,
+    }
+  }
+}
 
-    Ok((pk, vk))
+/// An evaluation claim left open by a deferred proof, along with the polynomial that the prover
+/// needs to open it
+#[derive(Clone, Debug)]
+pub struct DeferredOpening<E: Engine> {
+  claim: EvaluationClaim<E>,
+  poly: Vec<E::Scalar>,
+}
+
+impl<E: Engine> DeferredOpening<E> {
+  /// Returns the claim to be opened
+  pub fn claim(&self) -> &EvaluationClaim<E> {
+    &self.claim
   }
+}
 
-  /// produces a succinct proof of satisfiability of a `RelaxedR1CS` instance
-  fn prove(
-    ck: &CommitmentKey<E>,
-    pk: &Self::ProverKey,
+/// A Spartan proof of satisfiability of a relaxed R1CS instance that ends with the sum-checks,
+/// leaving the claims about evaluations of the committed witness and error vectors open
+///
+/// `RelaxedR1CSSNARK` proves these claims with its evaluation engine right away. A deferred proof
+/// instead hands them to the caller, who can hold the claims of many proofs and open them all
+/// at once with a `BatchedEvaluationArgument`. A deferred proof convinces a verifier only along
+/// with an opening of the claims that its verification returns.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct DeferredRelaxedR1CSSNARK<E: Engine> {
+  sc_proof_outer: SumcheckProof<E>,
+  claims_outer: (E::Scalar, E::Scalar, E::Scalar),
+  eval_E: E::Scalar,
+  sc_proof_inner: SumcheckProof<E>,
+  eval_W: E::Scalar,
+}
+
+impl<E: Engine> CalldataEncoding for DeferredRelaxedR1CSSNARK<E>
+where
+  E::Scalar: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.sc_proof_outer.encode(enc);
+    self.claims_outer.encode(enc);
+    self.eval_E.encode(enc);
+    self.sc_proof_inner.encode(enc);
+    self.eval_W.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      sc_proof_outer: CalldataEncoding::decode(dec)?,
+      claims_outer: CalldataEncoding::decode(dec)?,
+      eval_E: CalldataEncoding::decode(dec)?,
+      sc_proof_inner: CalldataEncoding::decode(dec)?,
+      eval_W: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
+
+impl<E: Engine> DeferredRelaxedR1CSSNARK<E> {
+  /// Estimates the size in bytes of the tables that the prover holds simultaneously for the
+  /// padded shape `S` when it does not stream them
+  fn table_size(S: &R1CSShape<E>) -> usize {
+    // the witness, z, and the inner sum-check tables span up to 11 vectors of num_vars elements,
+    // and the error vector along with the outer sum-check tables span up to 7 vectors of num_cons elements
+    (11 * S.num_vars + 7 * S.num_cons) * std::mem::size_of::<E::Scalar>()
+  }
+
+  /// Runs the sum-checks on `transcript`, and returns the proof along with the evaluation claims
+  /// it leaves open and the polynomials they are about
+  fn prove_with_transcript(
+    vk_digest: &E::Scalar,
     S: &R1CSShape<E>,
     U: &RelaxedR1CSInstance<E>,
     W: &RelaxedR1CSWitness<E>,
-  ) -> Result<Self, NovaError> {
+    budget: MemoryBudget,
+    transcript: &mut E::TE,
+  ) -> Result<(Self, Vec<PolyEvalInstance<E>>, Vec<PolyEvalWitness<E>>), NovaError> {
     // pad the R1CSShape
     let S = S.pad();
     // sanity check that R1CSShape has all required size characteristics
     assert!(S.is_regular_shape());
 
     let W = W.pad(&S); // pad the witness
-    let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
-
+    let E = W.E.to_dense();
+    let streaming = !budget.allows(Self::table_size(&S));
     // append the digest of vk (which includes R1CS matrices) and the RelaxedR1CSInstance to the transcript
-    transcript.absorb(b"vk", &pk.vk_digest);
+    transcript.absorb(b"vk", vk_digest);
     transcript.absorb(b"U", U);
 
     // compute the full satisfying assignment by concatenating W.W, U.u, and U.X
@@ -145,18 +241,22 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
       .collect::<Result<EqPolynomial<_>, NovaError>>()?;
 
     let mut poly_tau = MultilinearPolynomial::new(tau.evals());
-    let (mut poly_Az, mut poly_Bz, poly_Cz, mut poly_uCz_E) = {
-      let (poly_Az, poly_Bz, poly_Cz) = S.multiply_vec(&z)?;
-      let poly_uCz_E = (0..S.num_cons)
-        .map(|i| U.u * poly_Cz[i] + W.E[i])
-        .collect::<Vec<E::Scalar>>();
-      (
-        MultilinearPolynomial::new(poly_Az),
-        MultilinearPolynomial::new(poly_Bz),
-        MultilinearPolynomial::new(poly_Cz),
-        MultilinearPolynomial::new(poly_uCz_E),
-      )
+    let (poly_Az, poly_Bz, mut poly_Cz) = S.multiply_vec(&z)?;
+    let poly_uCz_E = if streaming {
+      // fold C * z into u * C * z + E in place, and recompute its evaluation after the sum-check
+      poly_Cz
+        .par_iter_mut()
+        .zip(E.par_iter())
+        .for_each(|(c, e)| *c = U.u * *c + e);
+      std::mem::take(&mut poly_Cz)
+    } else {
+      zip_with!(par_iter, (poly_Cz, E), |c, e| U.u * c + e).collect()
     };
+    let (mut poly_Az, mut poly_Bz, mut poly_uCz_E) = (
+      MultilinearPolynomial::new(poly_Az),
+      MultilinearPolynomial::new(poly_Bz),
+      MultilinearPolynomial::new(poly_uCz_E),
+    );
 
     let comb_func_outer =
       |poly_A_comp: &E::Scalar,
@@ -172,13 +272,26 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
       &mut poly_Bz,
       &mut poly_uCz_E,
       comb_func_outer,
-      &mut transcript,
+      transcript,
     )?;
+    drop((poly_tau, poly_Az, poly_Bz, poly_uCz_E));
 
     // claims from the end of sum-check
+    let evals_rx = EqPolynomial::evals_from_points(&r_x);
     let (claim_Az, claim_Bz): (E::Scalar, E::Scalar) = (claims_outer[1], claims_outer[2]);
-    let claim_Cz = poly_Cz.evaluate(&r_x);
-    let eval_E = MultilinearPolynomial::new(W.E.clone()).evaluate(&r_x);
+    let (claim_Cz, eval_E) = if streaming {
+      let claim_Cz = (0..S.num_cons)
+        .into_par_iter()
+        .map(|i| evals_rx[i] * S.C.multiply_row_unchecked(i, &z))
+        .sum();
+      let eval_E = zip_with!(par_iter, (evals_rx, E), |a, b| *a * b).sum();
+      (claim_Cz, eval_E)
+    } else {
+      (
+        MultilinearPolynomial::new(poly_Cz).evaluate(&r_x),
+        MultilinearPolynomial::new(E.to_vec()).evaluate(&r_x),
+      )
+    };
     transcript.absorb(
       b"claims_outer",
       &[claim_Az, claim_Bz, claim_Cz, eval_E].as_slice(),
@@ -188,10 +301,10 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let r = transcript.squeeze(b"r")?;
     let claim_inner_joint = claim_Az + r * claim_Bz + r * r * claim_Cz;
 
-    let poly_ABC = {
-      // compute the initial evaluation table for R(\tau, x)
-      let evals_rx = EqPolynomial::evals_from_points(&r_x.clone());
-
+    // compute the initial evaluation table for R(\tau, x)
+    let poly_ABC = if streaming {
+      compute_eval_table_sparse_combined(&S, &evals_rx, &r)
+    } else {
       let (evals_A, evals_B, evals_C) = compute_eval_table_sparse(&S, &evals_rx);
 
       assert_eq!(evals_A.len(), evals_B.len());
@@ -201,6 +314,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
         .map(|i| evals_A[i] + r * evals_B[i] + r * r * evals_C[i])
         .collect::<Vec<E::Scalar>>()
     };
+    drop(evals_rx);
 
     let poly_z = {
       z.resize(S.num_vars * 2, E::Scalar::ZERO);
@@ -216,7 +330,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
       &mut MultilinearPolynomial::new(poly_ABC),
       &mut MultilinearPolynomial::new(poly_z),
       comb_func,
-      &mut transcript,
+      transcript,
     )?;
 
     // Add additional claims about W and E polynomials to the list from CC
@@ -229,7 +343,10 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     // to the batched polynomial.
     let eval_W = MultilinearPolynomial::evaluate_with(&W.W, &r_y[1..]);
 
-    let w_vec = vec![PolyEvalWitness { p: W.W }, PolyEvalWitness { p: W.E }];
+    let w_vec = vec![
+      PolyEvalWitness { p: W.W },
+      PolyEvalWitness { p: E.into_owned() },
+    ];
     let u_vec = vec![
       PolyEvalInstance {
         c: U.comm_W,
@@ -243,42 +360,34 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
       },
     ];
 
-    let (batched_u, batched_w, sc_proof_batch, claims_batch_left) =
-      batch_eval_reduce(u_vec, w_vec, &mut transcript)?;
-
-    let eval_arg = EE::prove(
-      ck,
-      &pk.pk_ee,
-      &mut transcript,
-      &batched_u.c,
-      &batched_w.p,
-      &batched_u.x,
-      &batched_u.e,
-    )?;
-
-    Ok(RelaxedR1CSSNARK {
-      sc_proof_outer,
-      claims_outer: (claim_Az, claim_Bz, claim_Cz),
-      eval_E,
-      sc_proof_inner,
-      eval_W,
-      sc_proof_batch,
-      evals_batch: claims_batch_left,
-      eval_arg,
-    })
+    Ok((
+      DeferredRelaxedR1CSSNARK {
+        sc_proof_outer,
+        claims_outer: (claim_Az, claim_Bz, claim_Cz),
+        eval_E,
+        sc_proof_inner,
+        eval_W,
+      },
+      u_vec,
+      w_vec,
+    ))
   }
 
-  /// verifies a proof of satisfiability of a `RelaxedR1CS` instance
-  fn verify(&self, vk: &Self::VerifierKey, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError> {
-    let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
-
+  /// Verifies the sum-checks on `transcript`, and returns the evaluation claims left open
+  fn verify_with_transcript(
+    &self,
+    S: &R1CSShape<E>,
+    vk_digest: &E::Scalar,
+    U: &RelaxedR1CSInstance<E>,
+    transcript: &mut E::TE,
+  ) -> Result<Vec<PolyEvalInstance<E>>, NovaError> {
     // append the digest of R1CS matrices and the RelaxedR1CSInstance to the transcript
-    transcript.absorb(b"vk", &vk.digest());
+    transcript.absorb(b"vk", vk_digest);
     transcript.absorb(b"U", U);
 
     let (num_rounds_x, num_rounds_y) = (
-      usize::try_from(vk.S.num_cons.ilog2()).unwrap(),
-      (usize::try_from(vk.S.num_vars.ilog2()).unwrap() + 1),
+      usize::try_from(S.num_cons.ilog2()).unwrap(),
+      (usize::try_from(S.num_vars.ilog2()).unwrap() + 1),
     );
 
     // outer sum-check
@@ -289,7 +398,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let (claim_outer_final, r_x) =
       self
         .sc_proof_outer
-        .verify(E::Scalar::ZERO, num_rounds_x, 3, &mut transcript)?;
+        .verify(E::Scalar::ZERO, num_rounds_x, 3, transcript)?;
 
     // verify claim_outer_final
     let (claim_Az, claim_Bz, claim_Cz) = self.claims_outer;
@@ -319,7 +428,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let (claim_inner_final, r_y) =
       self
         .sc_proof_inner
-        .verify(claim_inner_joint, num_rounds_y, 2, &mut transcript)?;
+        .verify(claim_inner_joint, num_rounds_y, 2, transcript)?;
 
     // verify claim_inner_final
     let eval_Z = {
@@ -329,7 +438,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
           .into_iter()
           .chain(U.X.iter().cloned())
           .collect::<Vec<E::Scalar>>();
-        SparsePolynomial::new(vk.S.num_vars.log_2(), X).evaluate(&r_y[1..])
+        SparsePolynomial::new(S.num_vars.log_2(), X).evaluate(&r_y[1..])
       };
       (E::Scalar::ONE - r_y[0]) * self.eval_W + r_y[0] * eval_X
     };
@@ -363,7 +472,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
         .collect()
     };
 
-    let evals = multi_evaluate(&[&vk.S.A, &vk.S.B, &vk.S.C], &r_x, &r_y);
+    let evals = multi_evaluate(&[&S.A, &S.B, &S.C], &r_x, &r_y);
 
     let claim_inner_final_expected = (evals[0] + r * evals[1] + r * r * evals[2]) * eval_Z;
     if claim_inner_final != claim_inner_final_expected {
@@ -384,6 +493,247 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
       },
     ];
 
+    Ok(u_vec)
+  }
+
+  /// Produces a proof of satisfiability of a `RelaxedR1CS` instance whose evaluation claims are
+  /// left open, and returns it along with the openings that the prover holds for them
+  pub fn prove<EE: EvaluationEngineTrait<E>>(
+    pk: &ProverKey<E, EE>,
+    S: &R1CSShape<E>,
+    U: &RelaxedR1CSInstance<E>,
+    W: &RelaxedR1CSWitness<E>,
+  ) -> Result<(Self, Vec<DeferredOpening<E>>), NovaError> {
+    let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
+    let (proof, u_vec, w_vec) = Self::prove_with_transcript(
+      &pk.vk_digest,
+      S,
+      U,
+      W,
+      MemoryBudget::Unlimited,
+      &mut transcript,
+    )?;
+    let openings = zip_with!(into_iter, (u_vec, w_vec), |u, w| DeferredOpening {
+      claim: u.into(),
+      poly: w.p,
+    })
+    .collect();
+    Ok((proof, openings))
+  }
+
+  /// Verifies the sum-checks of the proof, and returns the evaluation claims that must be opened,
+  /// e.g., with a `BatchedEvaluationArgument`, for the instance `U` to be accepted
+  pub fn verify<EE: EvaluationEngineTrait<E>>(
+    &self,
+    vk: &VerifierKey<E, EE>,
+    U: &RelaxedR1CSInstance<E>,
+  ) -> Result<Vec<EvaluationClaim<E>>, NovaError> {
+    let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
+    let u_vec = self.verify_with_transcript(&vk.S, &vk.digest(), U, &mut transcript)?;
+    Ok(u_vec.into_iter().map(EvaluationClaim::from).collect())
+  }
+}
+
+/// An argument that opens a batch of evaluation claims, such as those left open by several
+/// `DeferredRelaxedR1CSSNARK`s, with a single invocation of the evaluation engine
+///
+/// The claims may be about polynomials of different sizes and at different points; they are
+/// reduced to a single claim with a sum-check before the evaluation engine is invoked.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BatchedEvaluationArgument<E: Engine, EE: EvaluationEngineTrait<E>> {
+  sc_proof_batch: SumcheckProof<E>,
+  evals_batch: Vec<E::Scalar>,
+  eval_arg: EE::EvaluationArgument,
+}
+
+impl<E: Engine, EE: EvaluationEngineTrait<E>> BatchedEvaluationArgument<E, EE> {
+  /// Returns a transcript that is bound to `claims`
+  fn transcript(claims: &[EvaluationClaim<E>]) -> E::TE {
+    let mut transcript = E::TE::new(b"BatchedEvaluationArgument");
+    transcript.absorb(b"claims", &claims);
+    transcript
+  }
+
+  /// Proves the claims of `openings`
+  pub fn prove(
+    ck: &CommitmentKey<E>,
+    pk: &EE::ProverKey,
+    openings: Vec<DeferredOpening<E>>,
+  ) -> Result<Self, NovaError> {
+    if openings.is_empty()
+      || openings
+        .iter()
+        .any(|o| o.poly.len() != 1 << o.claim.point.len())
+    {
+      return Err(NovaError::InvalidInputLength);
+    }
+
+    let (u_vec, w_vec): (Vec<_>, Vec<_>) = openings
+      .into_iter()
+      .map(|o| {
+        (
+          PolyEvalInstance {
+            c: o.claim.comm,
+            x: o.claim.point,
+            e: o.claim.eval,
+          },
+          PolyEvalWitness { p: o.poly },
+        )
+      })
+      .unzip();
+    let claims = u_vec
+      .iter()
+      .map(|u| EvaluationClaim {
+        comm: u.c,
+        point: u.x.clone(),
+        eval: u.e,
+      })
+      .collect::<Vec<_>>();
+    let mut transcript = Self::transcript(&claims);
+
+    let (batched_u, batched_w, sc_proof_batch, evals_batch) =
+      batch_eval_reduce(u_vec, w_vec, &mut transcript)?;
+    let eval_arg = EE::prove(
+      ck,
+      pk,
+      &mut transcript,
+      &batched_u.c,
+      &batched_w.p,
+      &batched_u.x,
+      &batched_u.e,
+    )?;
+
+    Ok(Self {
+      sc_proof_batch,
+      evals_batch,
+      eval_arg,
+    })
+  }
+
+  /// Verifies that the argument opens `claims`
+  pub fn verify(
+    &self,
+    vk: &EE::VerifierKey,
+    claims: &[EvaluationClaim<E>],
+  ) -> Result<(), NovaError> {
+    if claims.is_empty() {
+      return Err(NovaError::InvalidInputLength);
+    }
+    if self.evals_batch.len() != claims.len() {
+      return Err(NovaError::ProofVerifyError {
+        reason: "the number of batched evaluations does not match the number of claims".to_string(),
+      });
+    }
+
+    let mut transcript = Self::transcript(claims);
+    let u_vec = claims
+      .iter()
+      .map(|claim| PolyEvalInstance {
+        c: claim.comm,
+        x: claim.point.clone(),
+        e: claim.eval,
+      })
+      .collect();
+    let batched_u = batch_eval_verify(
+      u_vec,
+      &mut transcript,
+      &self.sc_proof_batch,
+      &self.evals_batch,
+    )?;
+    EE::verify(
+      vk,
+      &mut transcript,
+      &batched_u.c,
+      &batched_u.x,
+      &batched_u.e,
+      &self.eval_arg,
+    )
+  }
+}
+
+impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for RelaxedR1CSSNARK<E, EE> {
+  type ProverKey = ProverKey<E, EE>;
+  type VerifierKey = VerifierKey<E, EE>;
+
+  fn setup(
+    ck: &CommitmentKey<E>,
+    S: &R1CSShape<E>,
+  ) -> Result<(Self::ProverKey, Self::VerifierKey), NovaError> {
+    let (pk_ee, vk_ee) = EE::setup(ck);
+
+    let S = S.pad();
+
+    let vk: VerifierKey<E, EE> = VerifierKey::new(S, vk_ee);
+
+    let pk = ProverKey {
+      pk_ee,
+      vk_digest: vk.digest(),
+    };
+
+    Ok((pk, vk))
+  }
+
+  /// produces a succinct proof of satisfiability of a `RelaxedR1CS` instance
+  fn prove(
+    ck: &CommitmentKey<E>,
+    pk: &Self::ProverKey,
+    S: &R1CSShape<E>,
+    U: &RelaxedR1CSInstance<E>,
+    W: &RelaxedR1CSWitness<E>,
+  ) -> Result<Self, NovaError> {
+    Self::prove_with_budget(ck, pk, S, U, W, MemoryBudget::Unlimited)
+  }
+
+  /// produces a succinct proof of satisfiability of a `RelaxedR1CS` instance, streaming the
+  /// evaluation tables if holding all of them would exceed `budget`
+  fn prove_with_budget(
+    ck: &CommitmentKey<E>,
+    pk: &Self::ProverKey,
+    S: &R1CSShape<E>,
+    U: &RelaxedR1CSInstance<E>,
+    W: &RelaxedR1CSWitness<E>,
+    budget: MemoryBudget,
+  ) -> Result<Self, NovaError> {
+    let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
+    let (deferred, u_vec, w_vec) = DeferredRelaxedR1CSSNARK::prove_with_transcript(
+      &pk.vk_digest,
+      S,
+      U,
+      W,
+      budget,
+      &mut transcript,
+    )?;
+
+    // the evaluation claims are proven right away, batched into one with a sum-check
+    let (batched_u, batched_w, sc_proof_batch, claims_batch_left) =
+      batch_eval_reduce(u_vec, w_vec, &mut transcript)?;
+
+    let eval_arg = EE::prove(
+      ck,
+      &pk.pk_ee,
+      &mut transcript,
+      &batched_u.c,
+      &batched_w.p,
+      &batched_u.x,
+      &batched_u.e,
+    )?;
+
+    Ok(RelaxedR1CSSNARK {
+      deferred,
+      sc_proof_batch,
+      evals_batch: claims_batch_left,
+      eval_arg,
+    })
+  }
+
+  /// verifies a proof of satisfiability of a `RelaxedR1CS` instance
+  fn verify(&self, vk: &Self::VerifierKey, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError> {
+    let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
+    let u_vec = self
+      .deferred
+      .verify_with_transcript(&vk.S, &vk.digest(), U, &mut transcript)?;
+
     let batched_u = batch_eval_verify(
       u_vec,
       &mut transcript,
@@ -403,6 +753,11 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
 
     Ok(())
   }
+
+  fn preprocess(vk: &Self::VerifierKey) {
+    vk.digest();
+    EE::preprocess(&vk.vk_ee);
+  }
 }
 
 /// Reduces a batch of polynomial evaluation claims using Sumcheck
@@ -543,3 +898,95 @@ fn batch_eval_verify<E: Engine>(
 
   Ok(u_joint)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::{
+      num::AllocatedNum,
+      r1cs::{NovaShape, NovaWitness},
+      shape_cs::ShapeCS,
+      solver::SatisfyingAssignment,
+      ConstraintSystem,
+    },
+    provider::{hyperkzg, ipa_pc, Bn256EngineKZG, PallasEngine},
+    traits::commitment::CommitmentEngineTrait,
+  };
+
+  /// Synthesizes a circuit that squares `x` `num_squarings` times and outputs the result
+  fn synthesize<E: Engine, CS: ConstraintSystem<E::Scalar>>(
+    cs: &mut CS,
+    x: E::Scalar,
+    num_squarings: usize,
+  ) {
+    let mut y = AllocatedNum::alloc_infallible(cs.namespace(|| "x"), || x);
+    for i in 0..num_squarings {
+      y = y.square(cs.namespace(|| format!("square {i}"))).unwrap();
+    }
+    y.inputize(cs.namespace(|| "y")).unwrap();
+  }
+
+  fn test_deferred_with<E: Engine, EE: EvaluationEngineTrait<E>>() {
+    // circuits of different sizes, so that the claims are about polynomials of different sizes
+    let shapes = [3, 40].map(|num_squarings| {
+      let mut cs = ShapeCS::<E>::new();
+      synthesize::<E, _>(&mut cs, E::Scalar::ZERO, num_squarings);
+      (num_squarings, cs.r1cs_shape_without_ck())
+    });
+    let ck_floor = RelaxedR1CSSNARK::<E, EE>::ck_floor();
+    let ck_size = shapes
+      .iter()
+      .map(|(_, S)| S.commitment_key_size(&*ck_floor))
+      .max()
+      .unwrap();
+    let ck = E::CE::setup(b"ck", ck_size);
+    let (pk_ee, vk_ee) = EE::setup(&ck);
+
+    let mut claims = Vec::new();
+    let mut openings = Vec::new();
+    for (num_squarings, S) in &shapes {
+      let (pk, vk) = RelaxedR1CSSNARK::<E, EE>::setup(&ck, S).unwrap();
+      let mut cs = SatisfyingAssignment::<E>::new();
+      synthesize::<E, _>(&mut cs, E::Scalar::from(3), *num_squarings);
+      let (u, w) = cs.r1cs_instance_and_witness(S, &ck).unwrap();
+      let U = RelaxedR1CSInstance::from_r1cs_instance_unchecked(&u.comm_W, &u.X);
+      let W = RelaxedR1CSWitness::from_r1cs_witness(S, &w);
+      let (W, blind_W, blind_E) = W.derandomize();
+      let U = U.derandomize(&E::CE::derand_key(&ck), &blind_W, &blind_E);
+
+      let (proof, proof_openings) = DeferredRelaxedR1CSSNARK::prove(&pk, S, &U, &W).unwrap();
+      let proof_claims = proof.verify(&vk, &U).unwrap();
+      assert_eq!(
+        proof_claims,
+        proof_openings
+          .iter()
+          .map(|o| o.claim().clone())
+          .collect::<Vec<_>>()
+      );
+
+      // the proof does not verify against another instance
+      let mut other = U.clone();
+      other.X[0] += E::Scalar::ONE;
+      assert!(proof.verify(&vk, &other).is_err());
+
+      claims.extend(proof_claims);
+      openings.extend(proof_openings);
+    }
+
+    // the claims of both proofs are opened together
+    let arg = BatchedEvaluationArgument::<E, EE>::prove(&ck, &pk_ee, openings).unwrap();
+    assert!(arg.verify(&vk_ee, &claims).is_ok());
+
+    let mut wrong = claims.clone();
+    wrong[2].eval += E::Scalar::ONE;
+    assert!(arg.verify(&vk_ee, &wrong).is_err());
+    assert!(arg.verify(&vk_ee, &claims[..3]).is_err());
+  }
+
+  #[test]
+  fn test_deferred_evaluations() {
+    test_deferred_with::<PallasEngine, ipa_pc::EvaluationEngine<_>>();
+    test_deferred_with::<Bn256EngineKZG, hyperkzg::EvaluationEngine<_>>();
+  }
+}
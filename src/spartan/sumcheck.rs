@@ -4,7 +4,10 @@ use crate::{
     multilinear::MultilinearPolynomial,
     univariate::{CompressedUniPoly, UniPoly},
   },
-  traits::{Engine, TranscriptEngineTrait},
+  traits::{
+    calldata::{CalldataDecoder, CalldataEncoder, CalldataEncoding},
+    Engine, TranscriptEngineTrait,
+  },
 };
 use ff::Field;
 use itertools::Itertools as _;
@@ -38,6 +41,21 @@ pub(crate) struct SumcheckProof<E: Engine> {
   compressed_polys: Vec<CompressedUniPoly<E::Scalar>>,
 }
 
+impl<E: Engine> CalldataEncoding for SumcheckProof<E>
+where
+  E::Scalar: CalldataEncoding,
+{
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.compressed_polys.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok(Self {
+      compressed_polys: CalldataEncoding::decode(dec)?,
+    })
+  }
+}
+
 impl<E: Engine> SumcheckProof<E> {
   pub fn new(compressed_polys: Vec<CompressedUniPoly<E::Scalar>>) -> Self {
     Self { compressed_polys }
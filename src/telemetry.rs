@@ -0,0 +1,160 @@
+//! This module installs tracing pipelines that export the phases of proving and verifying and the
+//! timed operations within them (see `provider::timing`), so that any binary using the crate can
+//! profile it without further setup.
+//!
+//! Each of `init_jaeger`, `init_otlp`, and `init_chrome_trace` installs a global `tracing`
+//! subscriber, which fails if the binary already installed one, and returns a `TelemetryGuard`
+//! that flushes the pending spans when dropped. The guard must thus be held until the end of the
+//! work to trace. Spans and events are selected with the `RUST_LOG` environment variable if it is
+//! set, and otherwise with the target of `provider::timing` at the `DEBUG` level.
+//!
+//! `init_jaeger` exports to a Jaeger instance on the local machine, which can be run with
+//! `docker run -p 16686:16686 -p 4318:4318 jaegertracing/all-in-one`, and whose UI is then at
+//! `http://localhost:16686`. `init_otlp` exports to any other collector, and `init_chrome_trace`
+//! writes a file to open with `chrome://tracing` or Perfetto on machines without a collector.
+//! Exports use OTLP over HTTP with a blocking client, which must not be called from within an
+//! asynchronous runtime.
+use crate::{errors::NovaError, provider::timing::TARGET};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use std::{fs::File, io::BufWriter, path::Path};
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+/// The endpoint at which a local Jaeger instance receives traces over OTLP
+pub const JAEGER_ENDPOINT: &str = "http://localhost:4318/v1/traces";
+
+/// The name of the service that `init_otlp` reports traces for
+pub const SERVICE_NAME: &str = "nova-snark";
+
+/// A guard that flushes the spans of the pipeline installed with it when dropped
+#[must_use = "the pipeline stops exporting spans when the guard is dropped"]
+pub struct TelemetryGuard {
+  provider: Option<SdkTracerProvider>,
+  _chrome: Option<FlushGuard>,
+}
+
+impl Drop for TelemetryGuard {
+  fn drop(&mut self) {
+    if let Some(provider) = self.provider.take() {
+      // errors cannot be reported from here, and spans are lost either way
+      let _ = provider.force_flush();
+      let _ = provider.shutdown();
+    }
+  }
+}
+
+fn telemetry_error(e: impl ToString) -> NovaError {
+  NovaError::TelemetryError {
+    reason: e.to_string(),
+  }
+}
+
+/// Returns the filter selecting the spans and events to export
+fn filter() -> EnvFilter {
+  EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(format!("{TARGET}=debug")))
+}
+
+/// Exports spans over OTLP to `endpoint`, e.g., `http://collector:4318/v1/traces`, on behalf of
+/// the service `service`
+fn init_otlp_service(endpoint: &str, service: &str) -> Result<TelemetryGuard, NovaError> {
+  let exporter = SpanExporter::builder()
+    .with_http()
+    .with_endpoint(endpoint)
+    .build()
+    .map_err(telemetry_error)?;
+  let provider = SdkTracerProvider::builder()
+    .with_batch_exporter(exporter)
+    .with_resource(
+      Resource::builder()
+        .with_service_name(service.to_string())
+        .build(),
+    )
+    .build();
+  let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME));
+  tracing_subscriber::registry()
+    .with(layer.with_filter(filter()))
+    .try_init()
+    .map_err(telemetry_error)?;
+  Ok(TelemetryGuard {
+    provider: Some(provider),
+    _chrome: None,
+  })
+}
+
+/// Exports spans to a Jaeger instance on the local machine (see `JAEGER_ENDPOINT`), under the
+/// service name `service`
+pub fn init_jaeger(service: &str) -> Result<TelemetryGuard, NovaError> {
+  init_otlp_service(JAEGER_ENDPOINT, service)
+}
+
+/// Exports spans over OTLP to the collector at `endpoint`, the full URL at which it receives
+/// traces, e.g., `http://collector:4318/v1/traces`
+pub fn init_otlp(endpoint: &str) -> Result<TelemetryGuard, NovaError> {
+  init_otlp_service(endpoint, SERVICE_NAME)
+}
+
+/// Writes spans and events to the file at `path` in the trace event format of `chrome://tracing`
+pub fn init_chrome_trace(path: impl AsRef<Path>) -> Result<TelemetryGuard, NovaError> {
+  let file = File::create(path).map_err(telemetry_error)?;
+  let (layer, guard) = ChromeLayerBuilder::new()
+    .writer(BufWriter::new(file))
+    .include_args(true)
+    .build();
+  tracing_subscriber::registry()
+    .with(layer.with_filter(filter()))
+    .try_init()
+    .map_err(telemetry_error)?;
+  Ok(TelemetryGuard {
+    provider: None,
+    _chrome: Some(guard),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::Bn256EngineIPA,
+    traits::{commitment::CommitmentEngineTrait, Engine},
+  };
+  use ff::Field;
+  use rand_core::OsRng;
+
+  type E = Bn256EngineIPA;
+
+  #[test]
+  fn test_chrome_trace() {
+    let path = std::env::temp_dir().join(format!("nova-trace-{}.json", std::process::id()));
+    let guard = init_chrome_trace(&path).unwrap();
+
+    // a second pipeline cannot be installed
+    assert!(matches!(
+      init_otlp(JAEGER_ENDPOINT),
+      Err(NovaError::TelemetryError { .. })
+    ));
+
+    let ck = <E as Engine>::CE::setup(b"test", 10);
+    let v = (0..10)
+      .map(|_| <E as Engine>::Scalar::random(OsRng))
+      .collect::<Vec<_>>();
+    {
+      let _phase = crate::provider::timing::phase!("test_phase");
+      <E as Engine>::CE::commit(&ck, &v, &<E as Engine>::Scalar::ZERO);
+    }
+    drop(guard);
+
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let events: serde_json::Value = serde_json::from_str(&trace).unwrap();
+    let names = events
+      .as_array()
+      .unwrap()
+      .iter()
+      .filter_map(|event| event["name"].as_str())
+      .collect::<Vec<_>>();
+    assert!(names.contains(&"test_phase"));
+    assert!(names.iter().any(|name| name.contains("timing")));
+  }
+}
@@ -0,0 +1,374 @@
+//! This module provides `MockEngine`, an engine over a small prime field whose commitments are
+//! computed with field operations instead of multi-scalar multiplications, so that the algorithms
+//! that are generic over engines, such as folding, the sum-check protocol, and serialization, can
+//! be unit-tested in milliseconds.
+//!
+//! The commitment to `v` is `sum_i v_i * g_i + r * h` for field elements `g_i` and `h` derived from
+//! the label of the key. Anyone can solve for openings of such commitments, so `MockEngine` must
+//! never be used outside of tests. It has no evaluation engine and no cycle of curves: its base and
+//! scalar fields are the same field, and its group is only a placeholder.
+use crate::{
+  encoding::{encode_scalar, TRANSCRIPT_BYTE_ORDER},
+  provider::{
+    keccak::Keccak256Transcript,
+    poseidon::{PoseidonRO, PoseidonROCircuit},
+    ptau::PtauFileError,
+  },
+  traits::{
+    commitment::{CommitmentEngineTrait, CommitmentTrait, Len},
+    AbsorbInRO2Trait, AbsorbInROTrait, Engine, Group, PrimeFieldExt, ROTrait, TranscriptReprTrait,
+  },
+};
+use core::ops::{Add, Mul, MulAssign};
+use ff::{Field, FieldBits, PrimeField, PrimeFieldBits};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{Num, ToPrimitive};
+use rayon::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha3::{Digest, Sha3_256};
+
+/// A prime field of 189 bits with a 2-adicity of 32, over which `x^5` is a permutation, as
+/// Poseidon requires. Poseidon also needs a field of at least 128 bits, for its domain tags, whose
+/// representation has no spare bytes, for its round constants, hence three limbs.
+#[derive(ff::PrimeField)]
+#[PrimeFieldModulus = "392318858461667547739736838950479151006397215738563657729"]
+#[PrimeFieldGenerator = "7"]
+#[PrimeFieldReprEndianness = "little"]
+pub struct MockField([u64; 3]);
+
+impl PrimeFieldBits for MockField {
+  type ReprBits = [u64; 3];
+
+  fn to_le_bits(&self) -> FieldBits<Self::ReprBits> {
+    let repr = self.to_repr();
+    let mut limbs = [0u64; 3];
+    for (limb, bytes) in limbs.iter_mut().zip(repr.0.chunks(8)) {
+      *limb = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+    FieldBits::new(limbs)
+  }
+
+  fn char_le_bits() -> FieldBits<Self::ReprBits> {
+    FieldBits::new([0x6b00000001, 0, 0x1000000000000000])
+  }
+}
+
+impl PrimeFieldExt for MockField {
+  fn from_uniform(bytes: &[u8]) -> Self {
+    bytes.iter().rev().fold(MockField::ZERO, |acc, b| {
+      acc * MockField::from(256) + MockField::from(*b as u64)
+    })
+  }
+}
+
+impl<G: Group> TranscriptReprTrait<G> for MockField {
+  fn to_transcript_bytes(&self) -> Vec<u8> {
+    encode_scalar(self, TRANSCRIPT_BYTE_ORDER)
+  }
+}
+
+impl Serialize for MockField {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    self.to_repr().0.serialize(serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for MockField {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let bytes = <[u8; 24]>::deserialize(deserializer)?;
+    Option::from(MockField::from_repr(MockFieldRepr(bytes)))
+      .ok_or_else(|| serde::de::Error::custom("the value is not reduced"))
+  }
+}
+
+/// The placeholder group of `MockEngine`, whose elements are never computed with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MockGroup;
+
+impl Group for MockGroup {
+  type Base = MockField;
+  type Scalar = MockField;
+
+  fn group_params() -> (Self::Base, Self::Base, BigInt, BigInt) {
+    let modulus = BigInt::from_str_radix(&MockField::MODULUS[2..], 16).unwrap();
+    (MockField::ZERO, MockField::ZERO, modulus.clone(), modulus)
+  }
+}
+
+/// A commitment of `MockCommitmentEngine`, which is a field element
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MockCommitment(MockField);
+
+impl CommitmentTrait<MockEngine> for MockCommitment {
+  fn to_coordinates(&self) -> (MockField, MockField, bool) {
+    (self.0, MockField::ZERO, false)
+  }
+}
+
+impl TranscriptReprTrait<MockGroup> for MockCommitment {
+  fn to_transcript_bytes(&self) -> Vec<u8> {
+    <MockField as TranscriptReprTrait<MockGroup>>::to_transcript_bytes(&self.0)
+  }
+}
+
+impl AbsorbInROTrait<MockEngine> for MockCommitment {
+  fn absorb_in_ro(&self, ro: &mut PoseidonRO<MockField>) {
+    ro.absorb(self.0);
+  }
+}
+
+impl AbsorbInRO2Trait<MockEngine> for MockCommitment {
+  fn absorb_in_ro2(&self, ro: &mut PoseidonRO<MockField>) {
+    ro.absorb(self.0);
+  }
+}
+
+impl Add for MockCommitment {
+  type Output = MockCommitment;
+
+  fn add(self, other: MockCommitment) -> MockCommitment {
+    MockCommitment(self.0 + other.0)
+  }
+}
+
+impl Mul<MockField> for MockCommitment {
+  type Output = MockCommitment;
+
+  fn mul(self, scalar: MockField) -> MockCommitment {
+    MockCommitment(self.0 * scalar)
+  }
+}
+
+impl MulAssign<MockField> for MockCommitment {
+  fn mul_assign(&mut self, scalar: MockField) {
+    self.0 *= scalar;
+  }
+}
+
+/// A commitment key of `MockCommitmentEngine`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MockCommitmentKey {
+  gens: Vec<MockField>,
+  h: MockField,
+}
+
+impl Len for MockCommitmentKey {
+  fn length(&self) -> usize {
+    self.gens.len()
+  }
+}
+
+/// A derandomization key of `MockCommitmentEngine`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MockDerandKey {
+  h: MockField,
+}
+
+/// The insecure commitment engine of `MockEngine`
+#[derive(Clone, Debug)]
+pub struct MockCommitmentEngine;
+
+impl CommitmentEngineTrait<MockEngine> for MockCommitmentEngine {
+  type CommitmentKey = MockCommitmentKey;
+  type DerandKey = MockDerandKey;
+  type Commitment = MockCommitment;
+
+  fn load_setup(
+    _reader: &mut (impl std::io::Read + std::io::Seek),
+    _label: &'static [u8],
+    _n: usize,
+  ) -> Result<Self::CommitmentKey, PtauFileError> {
+    Err(PtauFileError::InvalidHead)
+  }
+
+  fn setup(label: &'static [u8], n: usize) -> Self::CommitmentKey {
+    let hash = |i: usize| {
+      let digest = Sha3_256::new()
+        .chain_update(label)
+        .chain_update(i.to_le_bytes())
+        .finalize();
+      MockField::from_uniform(&digest)
+    };
+    MockCommitmentKey {
+      gens: (0..n).into_par_iter().map(hash).collect(),
+      h: hash(n),
+    }
+  }
+
+  fn derand_key(ck: &Self::CommitmentKey) -> Self::DerandKey {
+    MockDerandKey { h: ck.h }
+  }
+
+  fn commit(ck: &Self::CommitmentKey, v: &[MockField], r: &MockField) -> Self::Commitment {
+    assert!(ck.gens.len() >= v.len());
+    let comm = v
+      .par_iter()
+      .zip(ck.gens.par_iter())
+      .map(|(v, g)| *v * g)
+      .sum::<MockField>();
+    MockCommitment(comm + *r * ck.h)
+  }
+
+  fn commit_small<T: Integer + Into<u64> + Copy + Sync + ToPrimitive>(
+    ck: &Self::CommitmentKey,
+    v: &[T],
+    r: &MockField,
+  ) -> Self::Commitment {
+    let v = v
+      .iter()
+      .map(|x| MockField::from((*x).into()))
+      .collect::<Vec<_>>();
+    Self::commit(ck, &v, r)
+  }
+
+  fn derandomize(
+    dk: &Self::DerandKey,
+    commit: &Self::Commitment,
+    r: &MockField,
+  ) -> Self::Commitment {
+    MockCommitment(commit.0 - *r * dk.h)
+  }
+}
+
+/// An insecure engine over `MockField` for fast tests, see the module documentation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MockEngine;
+
+impl Engine for MockEngine {
+  const ID: &'static str = "mock-v1";
+
+  type Base = MockField;
+  type Scalar = MockField;
+  type GE = MockGroup;
+  type RO = PoseidonRO<Self::Base>;
+  type ROCircuit = PoseidonROCircuit<Self::Base>;
+  type RO2 = PoseidonRO<Self::Scalar>;
+  type RO2Circuit = PoseidonROCircuit<Self::Scalar>;
+  type TE = Keccak256Transcript<Self>;
+  type CE = MockCommitmentEngine;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    spartan::{polys::multilinear::MultilinearPolynomial, sumcheck::SumcheckProof},
+    traits::TranscriptEngineTrait,
+  };
+  use rand_core::OsRng;
+
+  #[test]
+  fn test_mock_field() {
+    // the root of unity has order 2^S
+    let omega = MockField::ROOT_OF_UNITY;
+    assert_eq!(omega.pow_vartime([1u64 << MockField::S]), MockField::ONE);
+    assert_ne!(
+      omega.pow_vartime([1u64 << (MockField::S - 1)]),
+      MockField::ONE
+    );
+
+    // bits and serialization agree with the canonical representation
+    let x = MockField::random(OsRng);
+    let y = x.to_le_bits().iter().rev().fold(MockField::ZERO, |acc, b| {
+      acc.double() + MockField::from(*b as u64)
+    });
+    assert_eq!(x, y);
+    let bytes = bincode::serialize(&x).unwrap();
+    assert_eq!(bincode::deserialize::<MockField>(&bytes).unwrap(), x);
+    assert!(bincode::deserialize::<MockField>(&[0xff; 24]).is_err());
+  }
+
+  #[test]
+  fn test_mock_commitments() {
+    type CE = MockCommitmentEngine;
+    let ck = CE::setup(b"test", 8);
+    let v = (0..8).map(|_| MockField::random(OsRng)).collect::<Vec<_>>();
+    let w = (0..8).map(|_| MockField::random(OsRng)).collect::<Vec<_>>();
+    let (r, s, c) = (
+      MockField::random(OsRng),
+      MockField::random(OsRng),
+      MockField::random(OsRng),
+    );
+
+    // commitments are additively homomorphic, as folding requires
+    let sum = v
+      .iter()
+      .zip(&w)
+      .map(|(v, w)| *v + c * w)
+      .collect::<Vec<_>>();
+    assert_eq!(
+      CE::commit(&ck, &v, &r) + CE::commit(&ck, &w, &s) * c,
+      CE::commit(&ck, &sum, &(r + c * s))
+    );
+    assert_eq!(
+      CE::derandomize(&CE::derand_key(&ck), &CE::commit(&ck, &v, &r), &r),
+      CE::commit(&ck, &v, &MockField::ZERO)
+    );
+    assert_eq!(
+      CE::commit_small(&ck, &[3u64, 5], &r),
+      CE::commit(&ck, &[MockField::from(3), MockField::from(5)], &r)
+    );
+
+    // the default sparse commitment adds up the values at a repeated index
+    assert_eq!(
+      CE::commit_sparse(&ck, &[4, 1, 4], &[v[0], v[1], v[2]], &r),
+      CE::commit(
+        &ck,
+        &[
+          MockField::ZERO,
+          v[1],
+          MockField::ZERO,
+          MockField::ZERO,
+          v[0] + v[2]
+        ],
+        &r
+      )
+    );
+  }
+
+  #[test]
+  fn test_mock_sumcheck() {
+    let num_vars = 6;
+    let A = (0..1 << num_vars)
+      .map(|_| MockField::random(OsRng))
+      .collect::<Vec<_>>();
+    let B = (0..1 << num_vars)
+      .map(|_| MockField::random(OsRng))
+      .collect::<Vec<_>>();
+    let claim = A.iter().zip(&B).map(|(a, b)| *a * b).sum::<MockField>();
+
+    let mut transcript = <MockEngine as Engine>::TE::new(b"test");
+    let (proof, r_prover, _) = SumcheckProof::<MockEngine>::prove_quad(
+      &claim,
+      num_vars,
+      &mut MultilinearPolynomial::new(A.clone()),
+      &mut MultilinearPolynomial::new(B.clone()),
+      |a, b| *a * *b,
+      &mut transcript,
+    )
+    .unwrap();
+
+    // the proof survives a serialization round trip and reduces the claim to evaluations at `r`
+    let bytes = bincode::serialize(&proof).unwrap();
+    let proof: SumcheckProof<MockEngine> = bincode::deserialize(&bytes).unwrap();
+    let mut transcript = <MockEngine as Engine>::TE::new(b"test");
+    let (e, r) = proof.verify(claim, num_vars, 2, &mut transcript).unwrap();
+    assert_eq!(r, r_prover);
+    assert_eq!(
+      e,
+      MultilinearPolynomial::new(A.clone()).evaluate(&r)
+        * MultilinearPolynomial::new(B.clone()).evaluate(&r)
+    );
+
+    // a false claim is reduced to a false evaluation
+    let mut transcript = <MockEngine as Engine>::TE::new(b"test");
+    let (e, r) = proof
+      .verify(claim + MockField::ONE, num_vars, 2, &mut transcript)
+      .unwrap();
+    assert_ne!(
+      e,
+      MultilinearPolynomial::new(A).evaluate(&r) * MultilinearPolynomial::new(B).evaluate(&r)
+    );
+  }
+}
@@ -0,0 +1,317 @@
+//! This module defines conformance suites that implementations of the traits of this crate,
+//! including implementations outside of it, can run in their own tests.
+//!
+//! `ee_conformance` checks an implementation of `EvaluationEngineTrait`: that honest evaluation
+//! arguments verify, that arguments for false claims do not, and that keys and arguments survive
+//! a serialization round trip. `ee_benchmark` measures the cost of proving and verifying an
+//! evaluation, for use in benchmarks of custom backends. `CrossTermBenchmark` exposes the
+//! computation of the cross-term of folding on random shapes to the benchmarks in `benches/`.
+//! Outside of the crate, the suites are available with the `testing` feature.
+//!
+//! `MockEngine` is an insecure engine over a small field, for fast tests of the algorithms that are
+//! generic over engines.
+mod mock;
+
+pub use mock::{
+  MockCommitment, MockCommitmentEngine, MockCommitmentKey, MockDerandKey, MockEngine, MockField,
+  MockGroup,
+};
+
+use crate::{
+  r1cs::{R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness},
+  spartan::polys::multilinear::MultilinearPolynomial,
+  traits::{
+    commitment::CommitmentEngineTrait, evaluation::EvaluationEngineTrait, Engine,
+    TranscriptEngineTrait,
+  },
+  Commitment, CommitmentKey,
+};
+use ff::Field;
+use rand_core::OsRng;
+use rayon::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::{Duration, Instant};
+
+/// The numbers of variables of the polynomials that `ee_conformance` checks evaluations of
+const CONFORMANCE_NUM_VARS: [usize; 4] = [1, 2, 5, 8];
+
+/// The label of the commitment keys and transcripts of the suites
+const LABEL: &[u8] = b"ee_conformance";
+
+/// A random claim that a committed multilinear polynomial evaluates to `eval` at `point`
+#[derive(Clone)]
+struct Claim<E: Engine> {
+  poly: Vec<E::Scalar>,
+  comm: Commitment<E>,
+  point: Vec<E::Scalar>,
+  eval: E::Scalar,
+}
+
+impl<E: Engine> Claim<E> {
+  fn random(ck: &CommitmentKey<E>, num_vars: usize) -> Self {
+    let poly = (0..1 << num_vars)
+      .map(|_| E::Scalar::random(OsRng))
+      .collect::<Vec<_>>();
+    let point = (0..num_vars)
+      .map(|_| E::Scalar::random(OsRng))
+      .collect::<Vec<_>>();
+    let eval = MultilinearPolynomial::evaluate_with(&poly, &point);
+    let comm = E::CE::commit(ck, &poly, &E::Scalar::ZERO);
+    Self {
+      poly,
+      comm,
+      point,
+      eval,
+    }
+  }
+
+  fn prove<EE: EvaluationEngineTrait<E>>(
+    &self,
+    ck: &CommitmentKey<E>,
+    pk: &EE::ProverKey,
+  ) -> EE::EvaluationArgument {
+    let mut transcript = E::TE::new(LABEL);
+    EE::prove(
+      ck,
+      pk,
+      &mut transcript,
+      &self.comm,
+      &self.poly,
+      &self.point,
+      &self.eval,
+    )
+    .expect("proving a true evaluation claim failed")
+  }
+
+  fn verifies<EE: EvaluationEngineTrait<E>>(
+    &self,
+    vk: &EE::VerifierKey,
+    arg: &EE::EvaluationArgument,
+  ) -> bool {
+    let mut transcript = E::TE::new(LABEL);
+    EE::verify(
+      vk,
+      &mut transcript,
+      &self.comm,
+      &self.point,
+      &self.eval,
+      arg,
+    )
+    .is_ok()
+  }
+}
+
+/// Serializes `value` with bincode and deserializes it back
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T, what: &str) -> T {
+  let bytes = bincode::serialize(value).unwrap_or_else(|err| panic!("serializing {what}: {err}"));
+  bincode::deserialize(&bytes).unwrap_or_else(|err| panic!("deserializing {what}: {err}"))
+}
+
+/// Runs the conformance suite of evaluation engines against `EE`, and panics with a description of
+/// the first check that fails.
+///
+/// For polynomials of several sizes, the suite checks that:
+/// - an argument for a true evaluation verifies;
+/// - the argument does not verify for another evaluation, another point, another commitment, or
+///   another transcript;
+/// - an argument for another polynomial does not verify;
+/// - the prover key, the verifier key, and the argument are serialized and deserialized with
+///   bincode, and the deserialized argument verifies with the deserialized verifier key.
+pub fn ee_conformance<E: Engine, EE: EvaluationEngineTrait<E>>() {
+  let max_num_vars = CONFORMANCE_NUM_VARS.iter().max().unwrap();
+  let ck = E::CE::setup(LABEL, 1 << max_num_vars);
+  let (pk, vk) = EE::setup(&ck);
+
+  for num_vars in CONFORMANCE_NUM_VARS {
+    let claim = Claim::<E>::random(&ck, num_vars);
+    let arg = claim.prove::<EE>(&ck, &pk);
+    assert!(
+      claim.verifies::<EE>(&vk, &arg),
+      "an argument for a true evaluation with {num_vars} variables does not verify"
+    );
+
+    // false claims
+    let other = Claim::<E>::random(&ck, num_vars);
+    let false_claims = [
+      (
+        "another evaluation",
+        Claim {
+          eval: claim.eval + E::Scalar::ONE,
+          ..claim.clone()
+        },
+      ),
+      (
+        "another point",
+        Claim {
+          point: other.point.clone(),
+          ..claim.clone()
+        },
+      ),
+      (
+        "another commitment",
+        Claim {
+          comm: other.comm,
+          ..claim.clone()
+        },
+      ),
+    ];
+    for (what, false_claim) in false_claims {
+      assert!(
+        !false_claim.verifies::<EE>(&vk, &arg),
+        "an argument with {num_vars} variables verifies for {what}"
+      );
+    }
+
+    let mut transcript = E::TE::new(b"ee_conformance_other");
+    assert!(
+      EE::verify(
+        &vk,
+        &mut transcript,
+        &claim.comm,
+        &claim.point,
+        &claim.eval,
+        &arg
+      )
+      .is_err(),
+      "an argument with {num_vars} variables verifies with another transcript"
+    );
+
+    let other_arg = other.prove::<EE>(&ck, &pk);
+    assert!(
+      !claim.verifies::<EE>(&vk, &other_arg),
+      "an argument with {num_vars} variables verifies for another polynomial"
+    );
+
+    // serialization round trips
+    let _pk: EE::ProverKey = round_trip(&pk, "the prover key");
+    let vk_read: EE::VerifierKey = round_trip(&vk, "the verifier key");
+    let arg_read: EE::EvaluationArgument = round_trip(&arg, "the evaluation argument");
+    assert!(
+      claim.verifies::<EE>(&vk_read, &arg_read),
+      "a deserialized argument with {num_vars} variables does not verify"
+    );
+  }
+}
+
+/// The cost of proving and verifying an evaluation of a multilinear polynomial, measured by
+/// `ee_benchmark`
+#[derive(Clone, Debug)]
+pub struct EvaluationBenchmark {
+  /// The number of variables of the polynomial
+  pub num_vars: usize,
+  /// The time taken to prove the evaluation
+  pub prove: Duration,
+  /// The time taken to verify the evaluation
+  pub verify: Duration,
+  /// The size in bytes of the evaluation argument, serialized with bincode
+  pub argument_size: usize,
+}
+
+/// Measures the cost of proving and verifying with `EE` the evaluation of a random multilinear
+/// polynomial in `num_vars` variables, for use in benchmarks of custom backends.
+///
+/// Panics if the argument does not verify.
+pub fn ee_benchmark<E: Engine, EE: EvaluationEngineTrait<E>>(
+  num_vars: usize,
+) -> EvaluationBenchmark {
+  let ck = E::CE::setup(LABEL, 1 << num_vars);
+  let (pk, vk) = EE::setup(&ck);
+
+  let claim = Claim::<E>::random(&ck, num_vars);
+
+  let start = Instant::now();
+  let arg = claim.prove::<EE>(&ck, &pk);
+  let prove = start.elapsed();
+
+  let start = Instant::now();
+  assert!(
+    claim.verifies::<EE>(&vk, &arg),
+    "the argument does not verify"
+  );
+  let verify = start.elapsed();
+
+  EvaluationBenchmark {
+    num_vars,
+    prove,
+    verify,
+    argument_size: bincode::serialized_size(&arg).unwrap() as usize,
+  }
+}
+
+/// Two random relaxed R1CS instance-witness pairs over a random shape, whose cross-term, which
+/// every folding step computes, is measured by the `nifs` benchmark
+pub struct CrossTermBenchmark<E: Engine> {
+  S: R1CSShape<E>,
+  U1: RelaxedR1CSInstance<E>,
+  W1: RelaxedR1CSWitness<E>,
+  U2: RelaxedR1CSInstance<E>,
+  W2: RelaxedR1CSWitness<E>,
+}
+
+impl<E: Engine> CrossTermBenchmark<E> {
+  /// Samples a shape with `num_cons` constraints and as many variables, whose matrices have up to
+  /// three entries in every row, and two instance-witness pairs for it
+  pub fn new(num_cons: usize) -> Self {
+    let S = R1CSShape::random(num_cons, num_cons, 2, 0);
+    let ck = E::CE::setup(LABEL, num_cons);
+    let (U1, W1) = S.sample_random_instance_witness(&ck).unwrap();
+    let (U2, W2) = S.sample_random_instance_witness(&ck).unwrap();
+    Self { S, U1, W1, U2, W2 }
+  }
+
+  /// Computes the cross-term as folding does, in one pass over tiles of constraints
+  pub fn cross_term(&self) -> Vec<E::Scalar> {
+    self
+      .S
+      .compute_T_relaxed(&self.U1, &self.W1, &self.U2, &self.W2)
+      .unwrap()
+  }
+
+  /// Computes the cross-term from the full products `AZ`, `BZ`, and `CZ`, as a baseline for
+  /// `cross_term`
+  pub fn cross_term_unfused(&self) -> Vec<E::Scalar> {
+    let Z1 = [self.W1.W.clone(), vec![self.U1.u], self.U1.X.clone()].concat();
+    let Z2 = [self.W2.W.clone(), vec![self.U2.u], self.U2.X.clone()].concat();
+    let Z = Z1
+      .into_par_iter()
+      .zip(Z2.into_par_iter())
+      .map(|(z1, z2)| z1 + z2)
+      .collect::<Vec<E::Scalar>>();
+    let u = self.U1.u + self.U2.u;
+
+    let (AZ, BZ, CZ) = self.S.multiply_vec(&Z).unwrap();
+    let (E1, E2) = (self.W1.E.to_dense(), self.W2.E.to_dense());
+    AZ.par_iter()
+      .zip(BZ.par_iter())
+      .zip(CZ.par_iter())
+      .zip(E1.par_iter())
+      .zip(E2.par_iter())
+      .map(|((((az, bz), cz), e1), e2)| *az * *bz - u * *cz - *e1 - *e2)
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::provider::{hyperkzg, ipa_pc, Bn256EngineKZG, PallasEngine};
+
+  #[test]
+  fn test_ee_conformance() {
+    ee_conformance::<PallasEngine, ipa_pc::EvaluationEngine<_>>();
+    ee_conformance::<Bn256EngineKZG, hyperkzg::EvaluationEngine<_>>();
+  }
+
+  #[test]
+  fn test_cross_term_benchmark() {
+    let bench = CrossTermBenchmark::<PallasEngine>::new(1 << 12);
+    assert_eq!(bench.cross_term(), bench.cross_term_unfused());
+  }
+
+  #[test]
+  fn test_ee_benchmark() {
+    let bench = ee_benchmark::<PallasEngine, ipa_pc::EvaluationEngine<_>>(4);
+    assert_eq!(bench.num_vars, 4);
+    assert!(bench.argument_size > 0);
+  }
+}
@@ -0,0 +1,279 @@
+//! This module defines `CalldataEncoding`, an encoding of proofs suitable for EVM calldata.
+//!
+//! Every value is encoded as a sequence of 32-byte words, without any padding or offsets:
+//! * a field element is a single word holding its canonical value in big-endian order,
+//! * a G1 point is two words `(x, y)`, with the point at infinity encoded as `(0, 0)` as in EIP-196,
+//! * a G2 point is four words `(x.c1, x.c0, y.c1, y.c0)`, as expected by the pairing precompile of EIP-197,
+//! * a vector is a word holding its length followed by its elements, and
+//! * arrays, tuples, and structs are the concatenation of their elements or fields, in declaration order.
+//!
+//! A verifier contract can therefore read any value with `calldataload` at an offset that only
+//! depends on the lengths of the vectors that precede it.
+use crate::{
+  encoding::{decode_scalar, encode_scalar, ByteOrder},
+  errors::NovaError,
+};
+use halo2curves::{
+  bn256::{Fq, Fq2, Fr, G1Affine, G2Affine},
+  group::prime::PrimeCurveAffine,
+  grumpkin::G1Affine as GrumpkinAffine,
+  CurveAffine,
+};
+
+/// The size in bytes of a single calldata word
+pub const WORD_SIZE: usize = 32;
+
+/// A type that can be encoded into and decoded from calldata words
+pub trait CalldataEncoding: Sized {
+  /// Appends the encoding of `self` to `enc`
+  fn encode(&self, enc: &mut CalldataEncoder);
+
+  /// Decodes a value from the words at the current position of `dec`
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError>;
+}
+
+/// Encodes `value` into calldata
+pub fn to_calldata<T: CalldataEncoding>(value: &T) -> Vec<u8> {
+  let mut enc = CalldataEncoder::default();
+  value.encode(&mut enc);
+  enc.into_bytes()
+}
+
+/// Decodes a value from calldata, which must contain the encoding of exactly one value
+pub fn from_calldata<T: CalldataEncoding>(bytes: &[u8]) -> Result<T, NovaError> {
+  let mut dec = CalldataDecoder::new(bytes)?;
+  let value = T::decode(&mut dec)?;
+  if dec.remaining_words() != 0 {
+    return Err(invalid_encoding("trailing words"));
+  }
+  Ok(value)
+}
+
+fn invalid_encoding(reason: &str) -> NovaError {
+  NovaError::InvalidEncoding {
+    reason: reason.to_string(),
+  }
+}
+
+/// Accumulates calldata words
+#[derive(Clone, Debug, Default)]
+pub struct CalldataEncoder {
+  bytes: Vec<u8>,
+}
+
+impl CalldataEncoder {
+  /// Appends a word
+  pub fn write_word(&mut self, word: &[u8; WORD_SIZE]) {
+    self.bytes.extend_from_slice(word);
+  }
+
+  /// Appends a length as a big-endian word
+  pub fn write_len(&mut self, len: usize) {
+    let mut word = [0u8; WORD_SIZE];
+    word[WORD_SIZE - 8..].copy_from_slice(&(len as u64).to_be_bytes());
+    self.write_word(&word);
+  }
+
+  /// Returns the encoded bytes
+  pub fn into_bytes(self) -> Vec<u8> {
+    self.bytes
+  }
+}
+
+/// Reads calldata words in order
+#[derive(Clone, Debug)]
+pub struct CalldataDecoder<'a> {
+  words: core::slice::ChunksExact<'a, u8>,
+}
+
+impl<'a> CalldataDecoder<'a> {
+  /// Creates a decoder for `bytes`, which must consist of whole words
+  pub fn new(bytes: &'a [u8]) -> Result<Self, NovaError> {
+    if bytes.len() % WORD_SIZE != 0 {
+      return Err(invalid_encoding(
+        "length is not a multiple of the word size",
+      ));
+    }
+    Ok(Self {
+      words: bytes.chunks_exact(WORD_SIZE),
+    })
+  }
+
+  /// Returns the number of words that have not been read yet
+  pub fn remaining_words(&self) -> usize {
+    self.words.len()
+  }
+
+  /// Reads a word
+  pub fn read_word(&mut self) -> Result<[u8; WORD_SIZE], NovaError> {
+    let word = self
+      .words
+      .next()
+      .ok_or_else(|| invalid_encoding("unexpected end of calldata"))?;
+    Ok(word.try_into().expect("chunks have the size of a word"))
+  }
+
+  /// Reads a length, which cannot exceed the number of remaining words
+  pub fn read_len(&mut self) -> Result<usize, NovaError> {
+    let word = self.read_word()?;
+    if word[..WORD_SIZE - 8].iter().any(|b| *b != 0) {
+      return Err(invalid_encoding("length does not fit in 64 bits"));
+    }
+    let len = u64::from_be_bytes(word[WORD_SIZE - 8..].try_into().unwrap());
+    usize::try_from(len)
+      .ok()
+      .filter(|len| *len <= self.remaining_words())
+      .ok_or_else(|| invalid_encoding("length exceeds the remaining calldata"))
+  }
+}
+
+impl<T: CalldataEncoding> CalldataEncoding for Vec<T> {
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    enc.write_len(self.len());
+    self.iter().for_each(|v| v.encode(enc));
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    let len = dec.read_len()?;
+    (0..len).map(|_| T::decode(dec)).collect()
+  }
+}
+
+impl<T: CalldataEncoding, const N: usize> CalldataEncoding for [T; N] {
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.iter().for_each(|v| v.encode(enc));
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    let values = (0..N)
+      .map(|_| T::decode(dec))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(
+      values
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("exactly N values are decoded")),
+    )
+  }
+}
+
+impl<A: CalldataEncoding, B: CalldataEncoding, C: CalldataEncoding> CalldataEncoding for (A, B, C) {
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.0.encode(enc);
+    self.1.encode(enc);
+    self.2.encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    Ok((A::decode(dec)?, B::decode(dec)?, C::decode(dec)?))
+  }
+}
+
+/// Implements `CalldataEncoding` for prime fields whose canonical representation is 32 little-endian bytes
+macro_rules! impl_calldata_field {
+  ($($field:ty),*) => {
+    $(
+      impl CalldataEncoding for $field {
+        fn encode(&self, enc: &mut CalldataEncoder) {
+          let mut word = [0u8; WORD_SIZE];
+          word.copy_from_slice(&encode_scalar(self, ByteOrder::BigEndian));
+          enc.write_word(&word);
+        }
+
+        fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+          decode_scalar(&dec.read_word()?, ByteOrder::BigEndian)
+        }
+      }
+    )*
+  };
+}
+
+/// Implements `CalldataEncoding` for affine points whose coordinates implement `CalldataEncoding`
+macro_rules! impl_calldata_point {
+  ($($point:ty),*) => {
+    $(
+      impl CalldataEncoding for $point {
+        fn encode(&self, enc: &mut CalldataEncoder) {
+          // the point at infinity is encoded as (0, 0), which is not on the curve
+          let (x, y) = Option::from(self.coordinates())
+            .map(|c: halo2curves::Coordinates<$point>| (*c.x(), *c.y()))
+            .unwrap_or_default();
+          x.encode(enc);
+          y.encode(enc);
+        }
+
+        fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+          let x = CalldataEncoding::decode(dec)?;
+          let y = CalldataEncoding::decode(dec)?;
+          if x == Default::default() && y == Default::default() {
+            return Ok(<$point>::identity());
+          }
+          Option::from(<$point>::from_xy(x, y)).ok_or_else(|| invalid_encoding("point is not on the curve"))
+        }
+      }
+    )*
+  };
+}
+
+impl_calldata_field!(Fq, Fr);
+impl_calldata_point!(G1Affine, GrumpkinAffine, G2Affine);
+
+/// Elements of the quadratic extension are encoded as `(c1, c0)`, as expected by EIP-197
+impl CalldataEncoding for Fq2 {
+  fn encode(&self, enc: &mut CalldataEncoder) {
+    self.c1().encode(enc);
+    self.c0().encode(enc);
+  }
+
+  fn decode(dec: &mut CalldataDecoder<'_>) -> Result<Self, NovaError> {
+    let c1 = Fq::decode(dec)?;
+    let c0 = Fq::decode(dec)?;
+    Ok(Fq2::new(c0, c1))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ff::Field;
+  use halo2curves::group::{Curve, Group};
+  use rand::rngs::OsRng;
+
+  fn round_trip<T: CalldataEncoding + PartialEq + core::fmt::Debug>(value: &T, words: usize) {
+    let bytes = to_calldata(value);
+    assert_eq!(bytes.len(), words * WORD_SIZE);
+    assert_eq!(&from_calldata::<T>(&bytes).unwrap(), value);
+  }
+
+  #[test]
+  fn test_calldata_round_trip() {
+    let x = Fr::random(OsRng);
+    round_trip(&x, 1);
+    round_trip(&Fq::random(OsRng), 1);
+    round_trip(&vec![x, x.double()], 3);
+    round_trip(&[x; 3], 3);
+    round_trip(&halo2curves::bn256::G1::random(OsRng).to_affine(), 2);
+    round_trip(&G1Affine::identity(), 2);
+    round_trip(&halo2curves::grumpkin::G1::random(OsRng).to_affine(), 2);
+    round_trip(&halo2curves::bn256::G2::random(OsRng).to_affine(), 4);
+
+    // field elements are big-endian
+    let bytes = to_calldata(&Fr::from(258u64));
+    assert_eq!(bytes[30..], [1, 2]);
+    assert!(bytes[..30].iter().all(|b| *b == 0));
+
+    // the generator of G1 is encoded as (1, 2), and the point at infinity as (0, 0)
+    let bytes = to_calldata(&G1Affine::generator());
+    assert_eq!((bytes[31], bytes[63]), (1, 2));
+    assert!(to_calldata(&G1Affine::identity()).iter().all(|b| *b == 0));
+  }
+
+  #[test]
+  fn test_calldata_invalid() {
+    let bytes = to_calldata(&vec![Fr::ONE]);
+    assert!(from_calldata::<Vec<Fr>>(&bytes[..WORD_SIZE + 1]).is_err());
+    assert!(from_calldata::<Vec<Fr>>(&bytes[..WORD_SIZE]).is_err());
+    assert!(from_calldata::<Fr>(&bytes).is_err());
+    assert!(from_calldata::<Fr>(&[0xff; WORD_SIZE]).is_err());
+    assert!(from_calldata::<G1Affine>(&to_calldata(&[Fq::ONE, Fq::ONE])).is_err());
+  }
+}
@@ -1,14 +1,28 @@
 //! This module defines traits that a step function must implement
-use crate::frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError};
-use core::marker::PhantomData;
-use ff::PrimeField;
+use crate::{
+  errors::NovaError,
+  frontend::{num::AllocatedNum, AllocatedBit, Boolean, ConstraintSystem, SynthesisError},
+  gadgets::{
+    merkle::{self, IncrementalMerkleTree},
+    nonnative::util::f_to_nat,
+    poseidon,
+    utils::conditionally_select_vec,
+  },
+  provider::poseidon::PoseidonConstantsCircuit,
+};
+use core::{marker::PhantomData, ops::Range};
+use ff::{PrimeField, PrimeFieldBits};
+use std::sync::Arc;
 
 /// A helper trait for a step of the incremental computation (i.e., circuit for F)
 pub trait StepCircuit<F: PrimeField>: Send + Sync + Clone {
   /// Return the number of inputs or outputs of each step
   /// (this method is called only at circuit synthesis time)
   /// `synthesize` and `output` methods are expected to take as
-  /// input a vector of size equal to arity and output a vector of size equal to arity
+  /// input a vector of size equal to arity and output a vector of size equal to arity.
+  ///
+  /// The arity may be zero, for steps that only fold statements about their advice and carry no
+  /// state from one step to the next. The initial inputs and the outputs are then empty.
   fn arity(&self) -> usize;
 
   /// Synthesize the circuit for a computation step and return variable
@@ -18,6 +32,104 @@ pub trait StepCircuit<F: PrimeField>: Send + Sync + Clone {
     cs: &mut CS,
     z: &[AllocatedNum<F>],
   ) -> Result<Vec<AllocatedNum<F>>, SynthesisError>;
+
+  /// Return a key that identifies this circuit together with any advice it holds, so that
+  /// the witness it generates is fully determined by this key and the step inputs `z`.
+  /// Circuits returning `Some` can have their witness replayed from a `RecursiveSNARK`'s witness
+  /// cache (see `RecursiveSNARK::enable_witness_cache`). By default, circuits are not cached.
+  fn witness_cache_key(&self) -> Option<Vec<u8>> {
+    None
+  }
+
+  /// Return the range of the auxiliary variables, in the order `synthesize` allocates them, that
+  /// are assigned the same values at every step, such as a table of constants. A `RecursiveSNARK`
+  /// commits to these values once, when it is created, and then only commits to the rest of the
+  /// witness at each step. By default, no variable is constant.
+  fn constant_vars(&self) -> Range<usize> {
+    0..0
+  }
+}
+
+/// Derives `StepCircuit` for a struct whose fields are field elements or fixed-size arrays of them,
+/// leaving only the logic of the step to implement, with `StepLogic`.
+///
+/// The fields marked `#[state]` form the state `z` of the computation, in the order of the fields,
+/// so the arity is the number of field elements they hold. Their values only serve as the initial
+/// inputs, which the derived `initial_inputs` method returns. The other fields are the advice of a
+/// step, and are allocated as auxiliary variables, in the order of the fields, before the step
+/// runs. The derive generates a struct `<Name>Vars` with the same fields, holding the variables of
+/// the state and of the advice, which `StepLogic::step` receives.
+///
+/// The field of the circuit is the first type parameter of the struct, unless it is given by
+/// `#[step_circuit(field = ...)]`. Lengths of arrays must not depend on the generics of the struct.
+///
+/// ```
+/// use nova_snark::{
+///   frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+///   traits::circuit::{StepCircuit, StepLogic},
+/// };
+/// use ff::PrimeField;
+///
+/// /// Computes `(a, b) -> (b, a + b + x)` for some advice `x`
+/// #[derive(Clone, StepCircuit)]
+/// struct Fibonacci<F: PrimeField> {
+///   #[state]
+///   ab: [F; 2],
+///   x: F,
+/// }
+///
+/// impl<F: PrimeField> StepLogic<F> for Fibonacci<F> {
+///   type Vars = FibonacciVars<F>;
+///
+///   fn step<CS: ConstraintSystem<F>>(
+///     &self,
+///     cs: &mut CS,
+///     vars: Self::Vars,
+///   ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+///     let [a, b] = vars.ab;
+///     let sum = a.add(cs.namespace(|| "a + b"), &b)?;
+///     let next = sum.add(cs.namespace(|| "a + b + x"), &vars.x)?;
+///     Ok(vec![b, next])
+///   }
+/// }
+/// ```
+pub use nova_snark_derive::StepCircuit;
+
+/// The logic of a step circuit whose `StepCircuit` implementation is derived, which runs on the
+/// variables that the derive allocates for the fields of the circuit
+pub trait StepLogic<F: PrimeField> {
+  /// The variables of the fields of the circuit, i.e., the `<Name>Vars` struct that the derive
+  /// generates
+  type Vars;
+
+  /// Synthesizes the step on the variables of the fields and returns the output `z_{i+1}`, which
+  /// must hold as many variables as the state fields hold field elements
+  fn step<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    vars: Self::Vars,
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError>;
+}
+
+/// The values that a step circuit allocates on some input, and which of them form its output
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StepAssignment<F: PrimeField> {
+  /// The values of the auxiliary variables, in the order in which the circuit allocates them
+  pub aux: Vec<F>,
+  /// The offsets into `aux` of the values that form the output `z_{i+1}` of the step
+  pub outputs: Vec<usize>,
+}
+
+/// A source of precomputed step-circuit assignments, such as a witness calculator generated from
+/// the same circuit description as the step circuit (see `RecursiveSNARK::prove_step_with_witness`).
+///
+/// The step circuit is still used to synthesize the shapes of the augmented circuits, but when
+/// proving a step, the assignment returned by the source replaces the witness generated by
+/// `StepCircuit::synthesize`. The assignment must thus allocate exactly the variables that the
+/// step circuit allocates, in the same order, and must not allocate inputs.
+pub trait WitnessSource<F: PrimeField> {
+  /// Computes the assignment of the step circuit on input `z`
+  fn assignment(&self, z: &[F]) -> Result<StepAssignment<F>, SynthesisError>;
 }
 
 /// A trivial step circuit that simply returns the input
@@ -40,6 +152,473 @@ impl<F: PrimeField> StepCircuit<F> for TrivialCircuit<F> {
   }
 }
 
+/// A step circuit that wraps `C` so that any step can be turned into a no-op.
+///
+/// The wrapped circuit is always synthesized, so both kinds of steps share the same R1CS shape,
+/// but a no-op step outputs its input `z` unchanged instead of the output of `C`. This allows
+/// chains to be padded to a fixed number of steps (see `RecursiveSNARK::prove_noop_steps`).
+/// Note that `C` must admit a satisfying witness on any input it may be padded with.
+#[derive(Clone, Debug)]
+pub struct PaddedCircuit<F: PrimeField, C: StepCircuit<F>> {
+  circuit: C,
+  is_noop: bool,
+  _p: PhantomData<F>,
+}
+
+impl<F: PrimeField, C: StepCircuit<F>> PaddedCircuit<F, C> {
+  /// Create a step that applies `circuit`
+  pub fn step(circuit: C) -> Self {
+    Self {
+      circuit,
+      is_noop: false,
+      _p: PhantomData,
+    }
+  }
+
+  /// Create a no-op step that synthesizes `circuit` but outputs its input unchanged
+  pub fn noop(circuit: C) -> Self {
+    Self {
+      circuit,
+      is_noop: true,
+      _p: PhantomData,
+    }
+  }
+
+  /// Returns true if this step is a no-op
+  pub fn is_noop(&self) -> bool {
+    self.is_noop
+  }
+
+  /// Returns the wrapped circuit
+  pub fn inner(&self) -> &C {
+    &self.circuit
+  }
+}
+
+impl<F: PrimeField, C: StepCircuit<F>> StepCircuit<F> for PaddedCircuit<F, C> {
+  fn arity(&self) -> usize {
+    self.circuit.arity()
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    let is_noop = Boolean::from(AllocatedBit::alloc(
+      cs.namespace(|| "is_noop"),
+      Some(self.is_noop),
+    )?);
+    let z_next = self.circuit.synthesize(&mut cs.namespace(|| "step"), z)?;
+    if z_next.len() != z.len() {
+      return Err(SynthesisError::IncompatibleLengthVector(
+        "z_next".to_string(),
+      ));
+    }
+    conditionally_select_vec(cs.namespace(|| "select output"), z, &z_next, &is_noop)
+  }
+
+  fn witness_cache_key(&self) -> Option<Vec<u8>> {
+    self
+      .circuit
+      .witness_cache_key()
+      .map(|key| [vec![self.is_noop as u8], key].concat())
+  }
+
+  fn constant_vars(&self) -> Range<usize> {
+    // the wrapped circuit's variables follow the bit that selects no-op steps
+    let range = self.circuit.constant_vars();
+    range.start + 1..range.end + 1
+  }
+}
+
+/// A step circuit that wraps `C` and absorbs public values specific to each step, such as a block
+/// hash, into a running hash carried as an extra, last entry of `z`.
+///
+/// The arity is `C::arity() + 1` however many values each step takes, but every step must take the
+/// same number of values, since it determines the R1CS shape. The running hash starts at zero (see
+/// `WithStepInputs::z0`) and is updated as `h_{i+1} = H(h_i, x_i)` with the Poseidon hash of
+/// `gadgets::poseidon`, so a verifier holding the values `x_0, ..., x_{n-1}` of all steps checks
+/// them against the output of the last step with `WithStepInputs::check_outputs`.
+#[derive(Clone)]
+pub struct WithStepInputs<F: PrimeField, C: StepCircuit<F>> {
+  circuit: C,
+  inputs: Vec<F>,
+  constants: Arc<PoseidonConstantsCircuit<F>>,
+}
+
+impl<F: PrimeField, C: StepCircuit<F>> WithStepInputs<F, C> {
+  /// Create a step that applies `circuit` and absorbs `inputs` into the running hash
+  pub fn new(circuit: C, inputs: Vec<F>, constants: Arc<PoseidonConstantsCircuit<F>>) -> Self {
+    Self {
+      circuit,
+      inputs,
+      constants,
+    }
+  }
+
+  /// Returns the wrapped circuit
+  pub fn inner(&self) -> &C {
+    &self.circuit
+  }
+
+  /// Returns the values this step absorbs
+  pub fn inputs(&self) -> &[F] {
+    &self.inputs
+  }
+
+  /// Returns the initial input `z0` of the wrapped circuit, extended with the initial running hash
+  pub fn z0(z0: &[F]) -> Vec<F> {
+    [z0, &[F::ZERO]].concat()
+  }
+
+  /// Computes the running hash after absorbing the values of each step in `inputs`
+  pub fn digest(constants: &PoseidonConstantsCircuit<F>, inputs: &[Vec<F>]) -> F {
+    inputs.iter().fold(F::ZERO, |h, x| {
+      poseidon::hash(constants, &[&[h], x.as_slice()].concat())
+    })
+  }
+
+  /// Checks that `zn`, the output of the last step, carries the running hash of `inputs`, and
+  /// returns the output of the wrapped computation
+  pub fn check_outputs<'a>(
+    constants: &PoseidonConstantsCircuit<F>,
+    zn: &'a [F],
+    inputs: &[Vec<F>],
+  ) -> Result<&'a [F], NovaError> {
+    match zn.split_last() {
+      Some((h, zn)) if *h == Self::digest(constants, inputs) => Ok(zn),
+      _ => Err(NovaError::ProofVerifyError {
+        reason: "The per-step public inputs do not match the proof".to_string(),
+      }),
+    }
+  }
+}
+
+impl<F: PrimeField, C: StepCircuit<F>> StepCircuit<F> for WithStepInputs<F, C> {
+  fn arity(&self) -> usize {
+    self.circuit.arity() + 1
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    check_output_len(z, self.arity(), "z")?;
+    let (h, z) = z.split_last().unwrap();
+    let mut z_next = self.circuit.synthesize(&mut cs.namespace(|| "step"), z)?;
+    check_output_len(&z_next, self.circuit.arity(), "z_next")?;
+
+    let inputs = self
+      .inputs
+      .iter()
+      .enumerate()
+      .map(|(j, x)| AllocatedNum::alloc(cs.namespace(|| format!("input {j}")), || Ok(*x)))
+      .collect::<Result<Vec<_>, _>>()?;
+    let h_next = poseidon::hash_gadget(
+      cs.namespace(|| "absorb inputs"),
+      &self.constants,
+      &[std::slice::from_ref(h), inputs.as_slice()].concat(),
+    )?;
+    z_next.push(h_next);
+    Ok(z_next)
+  }
+
+  fn witness_cache_key(&self) -> Option<Vec<u8>> {
+    let inputs = self
+      .inputs
+      .iter()
+      .flat_map(|x| x.to_repr().as_ref().to_vec())
+      .collect();
+    combine_cache_keys([self.circuit.witness_cache_key(), Some(inputs)])
+  }
+
+  fn constant_vars(&self) -> Range<usize> {
+    self.circuit.constant_vars()
+  }
+}
+
+/// A step circuit that wraps `C` and appends the hash of the input `z_i` of every step to an
+/// incremental Merkle tree (see `gadgets::merkle`) whose root is carried in `z`.
+///
+/// The entries of `z` are the state of `C`, followed by the number of leaves and the frontier of
+/// the tree, which has the given depth, and by its root, for an arity of `C::arity() + depth + 2`.
+/// After `n` steps, the root commits to the hashes `H(z_0), ..., H(z_{n-1})` of all the states of
+/// the wrapped computation but the last one, which is public anyway, so applications can later
+/// prove statements about any intermediate state against the root with `gadgets::merkle` paths,
+/// without storing the states themselves. States are hashed with the Poseidon hash of
+/// `gadgets::poseidon` (see `WithStateTree::leaf`), and a tree of depth `d` holds `2^d - 1` states.
+#[derive(Clone)]
+pub struct WithStateTree<F: PrimeField, C: StepCircuit<F>> {
+  circuit: C,
+  depth: usize,
+  constants: Arc<PoseidonConstantsCircuit<F>>,
+}
+
+impl<F: PrimeField, C: StepCircuit<F>> WithStateTree<F, C> {
+  /// Create a step that applies `circuit` and appends the hash of its input to a tree of the given
+  /// depth.
+  ///
+  /// Panics if `depth` is zero or exceeds `gadgets::merkle::MAX_DEPTH`.
+  pub fn new(circuit: C, depth: usize, constants: Arc<PoseidonConstantsCircuit<F>>) -> Self {
+    assert!(
+      (1..=merkle::MAX_DEPTH).contains(&depth),
+      "unsupported depth"
+    );
+    Self {
+      circuit,
+      depth,
+      constants,
+    }
+  }
+
+  /// Returns the wrapped circuit
+  pub fn inner(&self) -> &C {
+    &self.circuit
+  }
+
+  /// Returns the leaf that a state of the wrapped circuit is appended to the tree as
+  pub fn leaf(constants: &PoseidonConstantsCircuit<F>, z: &[F]) -> F {
+    poseidon::hash(constants, z)
+  }
+
+  /// Returns the initial input `z0` of the wrapped circuit, extended with an empty tree
+  pub fn z0(constants: &PoseidonConstantsCircuit<F>, depth: usize, z0: &[F]) -> Vec<F> {
+    let tree = IncrementalMerkleTree::<F>::new(depth);
+    let mut z = z0.to_vec();
+    z.push(F::ZERO);
+    z.extend(tree.frontier());
+    z.push(tree.root(constants));
+    z
+  }
+
+  /// Splits `zn`, the output of the last step, into the output of the wrapped computation and the
+  /// tree of the states of all steps, which it checks against the root that `zn` carries
+  pub fn split_outputs<'a>(
+    constants: &PoseidonConstantsCircuit<F>,
+    depth: usize,
+    zn: &'a [F],
+  ) -> Result<(&'a [F], IncrementalMerkleTree<F>), NovaError> {
+    if zn.len() < depth + 2 {
+      return Err(NovaError::InvalidStepOutputLength);
+    }
+    let (zn, state) = zn.split_at(zn.len() - depth - 2);
+    let num_leaves = u64::try_from(f_to_nat(&state[0])).map_err(|_| NovaError::InvalidIndex)?;
+    let tree = IncrementalMerkleTree::from_parts(num_leaves, state[1..=depth].to_vec())?;
+    if tree.root(constants) != state[depth + 1] {
+      return Err(NovaError::ProofVerifyError {
+        reason: "The root of the state tree does not match its frontier".to_string(),
+      });
+    }
+    Ok((zn, tree))
+  }
+}
+
+impl<F: PrimeField + PrimeFieldBits, C: StepCircuit<F>> StepCircuit<F> for WithStateTree<F, C> {
+  fn arity(&self) -> usize {
+    self.circuit.arity() + self.depth + 2
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    check_output_len(z, self.arity(), "z")?;
+    let (z, state) = z.split_at(self.circuit.arity());
+    let mut z_next = self.circuit.synthesize(&mut cs.namespace(|| "step"), z)?;
+    check_output_len(&z_next, self.circuit.arity(), "z_next")?;
+
+    // the root carried in `z` is recomputed rather than updated, so it is not an input
+    let (num_leaves, frontier) = (&state[0], &state[1..=self.depth]);
+    let leaf = poseidon::hash_gadget(cs.namespace(|| "leaf"), &self.constants, z)?;
+    let (num_leaves, frontier) = merkle::append_gadget(
+      cs.namespace(|| "append"),
+      &self.constants,
+      num_leaves,
+      frontier,
+      &leaf,
+    )?;
+    let root = merkle::root_gadget(
+      cs.namespace(|| "root"),
+      &self.constants,
+      &num_leaves,
+      &frontier,
+    )?;
+
+    z_next.push(num_leaves);
+    z_next.extend(frontier);
+    z_next.push(root);
+    Ok(z_next)
+  }
+
+  fn witness_cache_key(&self) -> Option<Vec<u8>> {
+    let depth = (self.depth as u64).to_le_bytes().to_vec();
+    combine_cache_keys([self.circuit.witness_cache_key(), Some(depth)])
+  }
+
+  fn constant_vars(&self) -> Range<usize> {
+    self.circuit.constant_vars()
+  }
+}
+
+/// Checks that a step circuit produced an output of the expected length
+fn check_output_len<F: PrimeField>(
+  z_next: &[AllocatedNum<F>],
+  arity: usize,
+  name: &str,
+) -> Result<(), SynthesisError> {
+  if z_next.len() != arity {
+    return Err(SynthesisError::IncompatibleLengthVector(name.to_string()));
+  }
+  Ok(())
+}
+
+/// Combines the witness cache keys of several circuits, or returns `None` if one of them opted out
+fn combine_cache_keys(keys: impl IntoIterator<Item = Option<Vec<u8>>>) -> Option<Vec<u8>> {
+  let mut combined = Vec::new();
+  for key in keys {
+    let key = key?;
+    combined.extend((key.len() as u64).to_le_bytes());
+    combined.extend(key);
+  }
+  Some(combined)
+}
+
+/// A step circuit that applies `C1` and then `C2` to its output.
+///
+/// Both circuits must have the same arity, which is also the arity of the chain.
+#[derive(Clone, Debug, Default)]
+pub struct Chain<C1, C2> {
+  first: C1,
+  second: C2,
+}
+
+impl<C1, C2> Chain<C1, C2> {
+  /// Create a step that applies `first` and then `second`
+  pub fn new(first: C1, second: C2) -> Self {
+    Self { first, second }
+  }
+}
+
+impl<F: PrimeField, C1: StepCircuit<F>, C2: StepCircuit<F>> StepCircuit<F> for Chain<C1, C2> {
+  fn arity(&self) -> usize {
+    self.first.arity()
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    if self.second.arity() != self.first.arity() {
+      return Err(SynthesisError::IncompatibleLengthVector(
+        "arity of chained circuits".to_string(),
+      ));
+    }
+    let z_mid = self.first.synthesize(&mut cs.namespace(|| "first"), z)?;
+    check_output_len(&z_mid, self.first.arity(), "z_mid")?;
+    let z_next = self
+      .second
+      .synthesize(&mut cs.namespace(|| "second"), &z_mid)?;
+    check_output_len(&z_next, self.second.arity(), "z_next")?;
+    Ok(z_next)
+  }
+
+  fn witness_cache_key(&self) -> Option<Vec<u8>> {
+    combine_cache_keys([
+      self.first.witness_cache_key(),
+      self.second.witness_cache_key(),
+    ])
+  }
+}
+
+/// A step circuit that applies `C1` and `C2` side by side.
+///
+/// The arity is the sum of the arities of `C1` and `C2`: the first `C1::arity()` entries of `z`
+/// are passed to `C1`, the remaining ones to `C2`, and the outputs are concatenated in that order.
+#[derive(Clone, Debug, Default)]
+pub struct Parallel<C1, C2> {
+  left: C1,
+  right: C2,
+}
+
+impl<C1, C2> Parallel<C1, C2> {
+  /// Create a step that applies `left` and `right` to disjoint parts of `z`
+  pub fn new(left: C1, right: C2) -> Self {
+    Self { left, right }
+  }
+}
+
+impl<F: PrimeField, C1: StepCircuit<F>, C2: StepCircuit<F>> StepCircuit<F> for Parallel<C1, C2> {
+  fn arity(&self) -> usize {
+    self.left.arity() + self.right.arity()
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    check_output_len(z, self.arity(), "z")?;
+    let (z_left, z_right) = z.split_at(self.left.arity());
+    let mut z_next = self.left.synthesize(&mut cs.namespace(|| "left"), z_left)?;
+    check_output_len(&z_next, self.left.arity(), "z_next_left")?;
+    let z_next_right = self
+      .right
+      .synthesize(&mut cs.namespace(|| "right"), z_right)?;
+    check_output_len(&z_next_right, self.right.arity(), "z_next_right")?;
+    z_next.extend(z_next_right);
+    Ok(z_next)
+  }
+
+  fn witness_cache_key(&self) -> Option<Vec<u8>> {
+    combine_cache_keys([
+      self.left.witness_cache_key(),
+      self.right.witness_cache_key(),
+    ])
+  }
+}
+
+/// A step circuit that applies `C` to its own output `N` times
+#[derive(Clone, Debug, Default)]
+pub struct Repeat<C, const N: usize> {
+  circuit: C,
+}
+
+impl<C, const N: usize> Repeat<C, N> {
+  /// Create a step that applies `circuit` `N` times
+  pub fn new(circuit: C) -> Self {
+    Self { circuit }
+  }
+}
+
+impl<F: PrimeField, C: StepCircuit<F>, const N: usize> StepCircuit<F> for Repeat<C, N> {
+  fn arity(&self) -> usize {
+    self.circuit.arity()
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    z: &[AllocatedNum<F>],
+  ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    let mut z_next = z.to_vec();
+    for i in 0..N {
+      z_next = self
+        .circuit
+        .synthesize(&mut cs.namespace(|| format!("iteration {i}")), &z_next)?;
+      check_output_len(&z_next, self.arity(), "z_next")?;
+    }
+    Ok(z_next)
+  }
+
+  fn witness_cache_key(&self) -> Option<Vec<u8>> {
+    self.circuit.witness_cache_key()
+  }
+}
+
 /// A non-trivial step circuit that repeats the squaring operation `num_cons` times
 #[derive(Clone, Debug, Default)]
 pub struct NonTrivialCircuit<F: PrimeField> {
@@ -76,3 +655,127 @@ impl<F: PrimeField> StepCircuit<F> for NonTrivialCircuit<F> {
     Ok(vec![y])
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{frontend::test_cs::TestConstraintSystem, provider::pasta::pallas::Scalar};
+
+  fn synthesize<C: StepCircuit<Scalar>>(c: &C, z: &[u64]) -> Result<Vec<Scalar>, SynthesisError> {
+    let mut cs = TestConstraintSystem::<Scalar>::new();
+    let z = z
+      .iter()
+      .enumerate()
+      .map(|(i, v)| AllocatedNum::alloc(cs.namespace(|| format!("z_{i}")), || Ok(Scalar::from(*v))))
+      .collect::<Result<Vec<_>, _>>()?;
+    let z_next = c.synthesize(&mut cs, &z)?;
+    assert!(cs.is_satisfied());
+    Ok(z_next.iter().map(|v| v.get_value().unwrap()).collect())
+  }
+
+  /// Computes `(a, [b, c]) -> (a * x, [b + y[0], c + y[1]])`
+  #[derive(Clone, StepCircuit)]
+  struct ScaleAndShift<F: PrimeField> {
+    #[state]
+    a: F,
+    x: F,
+    #[state]
+    bc: [F; 2],
+    y: [F; 2],
+  }
+
+  impl<F: PrimeField> StepLogic<F> for ScaleAndShift<F> {
+    type Vars = ScaleAndShiftVars<F>;
+
+    fn step<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      vars: Self::Vars,
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      let a = vars.a.mul(cs.namespace(|| "a * x"), &vars.x)?;
+      let b = vars.bc[0].add(cs.namespace(|| "b + y[0]"), &vars.y[0])?;
+      let c = vars.bc[1].add(cs.namespace(|| "c + y[1]"), &vars.y[1])?;
+      Ok(vec![a, b, c])
+    }
+  }
+
+  /// Squares its state, over a field given by the attribute
+  #[derive(Clone, StepCircuit)]
+  #[step_circuit(field = Scalar)]
+  struct Square {
+    #[state]
+    a: Scalar,
+  }
+
+  impl StepLogic<Scalar> for Square {
+    type Vars = SquareVars<Scalar>;
+
+    fn step<CS: ConstraintSystem<Scalar>>(
+      &self,
+      cs: &mut CS,
+      vars: Self::Vars,
+    ) -> Result<Vec<AllocatedNum<Scalar>>, SynthesisError> {
+      Ok(vec![vars.a.square(cs.namespace(|| "a^2"))?])
+    }
+  }
+
+  #[test]
+  fn test_derive_step_circuit() {
+    let circuit = ScaleAndShift {
+      a: Scalar::from(2u64),
+      x: Scalar::from(5u64),
+      bc: [Scalar::from(3u64), Scalar::from(4u64)],
+      y: [Scalar::from(10u64), Scalar::from(20u64)],
+    };
+    assert_eq!(StepCircuit::<Scalar>::arity(&circuit), 3);
+    assert_eq!(
+      circuit.initial_inputs(),
+      [2u64, 3, 4].map(Scalar::from).to_vec()
+    );
+    // the state comes from the inputs, not from the fields
+    assert_eq!(
+      synthesize(&circuit, &[7, 8, 9]).unwrap(),
+      [35u64, 18, 29].map(Scalar::from).to_vec()
+    );
+
+    let square = Square {
+      a: Scalar::from(3u64),
+    };
+    assert_eq!(StepCircuit::<Scalar>::arity(&square), 1);
+    assert_eq!(square.initial_inputs(), vec![Scalar::from(3u64)]);
+    assert_eq!(synthesize(&square, &[3]).unwrap(), vec![Scalar::from(9u64)]);
+  }
+
+  #[test]
+  fn test_combinators() {
+    let square = NonTrivialCircuit::<Scalar>::new(1);
+    let trivial = TrivialCircuit::<Scalar>::default();
+
+    let chain = Chain::new(square.clone(), square.clone());
+    assert_eq!(StepCircuit::<Scalar>::arity(&chain), 1);
+    assert_eq!(synthesize(&chain, &[3]).unwrap(), vec![Scalar::from(81u64)]);
+
+    let parallel = Parallel::new(
+      square.clone(),
+      Parallel::new(trivial.clone(), square.clone()),
+    );
+    assert_eq!(StepCircuit::<Scalar>::arity(&parallel), 3);
+    assert_eq!(
+      synthesize(&parallel, &[2, 5, 3]).unwrap(),
+      vec![Scalar::from(4u64), Scalar::from(5u64), Scalar::from(9u64)]
+    );
+
+    let repeat = Repeat::<_, 3>::new(square.clone());
+    assert_eq!(
+      synthesize(&repeat, &[2]).unwrap(),
+      vec![Scalar::from(256u64)]
+    );
+
+    // chained circuits must agree on their arity
+    let mismatched = Chain::new(square, Parallel::new(trivial.clone(), trivial));
+    assert!(matches!(
+      synthesize(&mismatched, &[2]),
+      Err(SynthesisError::IncompatibleLengthVector(_))
+    ));
+  }
+}
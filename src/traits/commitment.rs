@@ -1,17 +1,22 @@
 //! This module defines a collection of traits that define the behavior of a commitment engine
 //! We require the commitment engine to provide a commitment to vectors with a single group element
 use crate::{
+  errors::NovaError,
   provider::ptau::PtauFileError,
   traits::{AbsorbInRO2Trait, AbsorbInROTrait, Engine, TranscriptReprTrait},
+  Commitment, CommitmentKey,
 };
 use core::{
   fmt::Debug,
   ops::{Add, Mul, MulAssign},
 };
+use ff::Field;
 use num_integer::Integer;
 use num_traits::ToPrimitive;
+use rand_core::OsRng;
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
 
 /// A helper trait for types implementing scalar multiplication.
 pub trait ScalarMul<Rhs, Output = Self>: Mul<Rhs, Output = Output> + MulAssign<Rhs> {}
@@ -48,6 +53,19 @@ pub trait Len {
   fn length(&self) -> usize;
 }
 
+/// Where the blinding generator of a commitment key comes from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlindingGenerator<E: Engine> {
+  /// The generator is derived from the label of the commitment key, as `setup` does
+  #[default]
+  FromKeyLabel,
+  /// The generator is derived from a separate label, independently of the other generators
+  Label(&'static [u8]),
+  /// The generator is fixed by the application, e.g., to match the `H` of an external system
+  /// that commits with Pedersen commitments
+  Fixed(E::GE),
+}
+
 /// A trait that ties different pieces of the commitment generation together
 pub trait CommitmentEngineTrait<E: Engine>: Clone + Send + Sync {
   /// Holds the type of the commitment key
@@ -70,6 +88,30 @@ pub trait CommitmentEngineTrait<E: Engine>: Clone + Send + Sync {
   /// Samples a new commitment key of a specified size
   fn setup(label: &'static [u8], n: usize) -> Self::CommitmentKey;
 
+  /// Samples a new commitment key of a specified size like `setup`, except that its blinding
+  /// generator comes from `blinding`. The blinding generator is recorded in the derandomization key.
+  ///
+  /// Engines whose blinding generator cannot be chosen return `NovaError::SetupError` unless
+  /// `blinding` is `BlindingGenerator::FromKeyLabel`.
+  fn setup_with_blinding(
+    label: &'static [u8],
+    n: usize,
+    blinding: &BlindingGenerator<E>,
+  ) -> Result<Self::CommitmentKey, NovaError> {
+    match blinding {
+      BlindingGenerator::FromKeyLabel => Ok(Self::setup(label, n)),
+      _ => Err(NovaError::SetupError {
+        reason: "the commitment engine does not support choosing the blinding generator"
+          .to_string(),
+      }),
+    }
+  }
+
+  /// Converts the commitment key, once, into the representation preferred by the MSM backend in use,
+  /// so that subsequent commitments skip the conversion. Keys are not prepared by default, and
+  /// preparing them has no effect for backends that compute with the stored representation.
+  fn prepare(_ck: &Self::CommitmentKey) {}
+
   /// Extracts the blinding generator
   fn derand_key(ck: &Self::CommitmentKey) -> Self::DerandKey;
 
@@ -77,6 +119,9 @@ pub trait CommitmentEngineTrait<E: Engine>: Clone + Send + Sync {
   fn commit(ck: &Self::CommitmentKey, v: &[E::Scalar], r: &E::Scalar) -> Self::Commitment;
 
   /// Batch commits to the provided vectors using the provided generators and random blind
+  ///
+  /// The vectors may have different lengths. Implementations backed by hardware accelerators
+  /// should override this method so that the whole batch is handed to the device in one call.
   fn batch_commit(
     ck: &Self::CommitmentKey,
     v: &[Vec<E::Scalar>],
@@ -89,6 +134,55 @@ pub trait CommitmentEngineTrait<E: Engine>: Clone + Send + Sync {
       .collect()
   }
 
+  /// Commits with the blind `r` to the vector whose entries at `indices` are `values` and whose
+  /// other entries are zero.
+  ///
+  /// This is the commitment that `commit` produces for the dense vector, but implementations
+  /// should override this method so that the cost depends on the number of nonzero entries rather
+  /// than on the length of the vector. Zero entries of `values` are allowed, and the values at a
+  /// repeated index add up, as they do in a multiexponentiation.
+  fn commit_sparse(
+    ck: &Self::CommitmentKey,
+    indices: &[usize],
+    values: &[E::Scalar],
+    r: &E::Scalar,
+  ) -> Self::Commitment {
+    assert!(indices.len() == values.len());
+    let mut v = vec![E::Scalar::ZERO; indices.iter().max().map_or(0, |i| i + 1)];
+    for (i, value) in indices.iter().zip(values) {
+      v[*i] += *value;
+    }
+    Self::commit(ck, &v, r)
+  }
+
+  /// Commits to `v` placed at position `offset` of a longer vector, with a zero blind.
+  ///
+  /// Commitments to consecutive chunks of a vector add up to the commitment to the whole vector,
+  /// so a vector can be committed to while it is still being produced.
+  fn commit_at(ck: &Self::CommitmentKey, v: &[E::Scalar], offset: usize) -> Self::Commitment {
+    let mut padded = vec![E::Scalar::ZERO; offset];
+    padded.extend_from_slice(v);
+    Self::commit(ck, &padded, &E::Scalar::ZERO)
+  }
+
+  /// Updates `commit`, a commitment to a vector `v`, into the commitment to `v` with the entry at
+  /// position `index` changed from `old` to `new`, by adding `(new - old) * G_index`.
+  ///
+  /// The cost does not depend on the length of `v`, and the blind of `commit` carries over.
+  /// Returns `NovaError::InvalidIndex` if the commitment key has no generator at `index`.
+  fn update(
+    ck: &Self::CommitmentKey,
+    commit: &Self::Commitment,
+    index: usize,
+    old: &E::Scalar,
+    new: &E::Scalar,
+  ) -> Result<Self::Commitment, NovaError> {
+    if index >= ck.length() {
+      return Err(NovaError::InvalidIndex);
+    }
+    Ok(*commit + Self::commit_at(ck, &[*new - *old], index))
+  }
+
   /// Commits to the provided vector of "small" scalars (at most 64 bits) using the provided generators and random blind
   fn commit_small<T: Integer + Into<u64> + Copy + Sync + ToPrimitive>(
     ck: &Self::CommitmentKey,
@@ -115,4 +209,101 @@ pub trait CommitmentEngineTrait<E: Engine>: Clone + Send + Sync {
     commit: &Self::Commitment,
     r: &E::Scalar,
   ) -> Self::Commitment;
+
+  /// Removes the blinds `r` from the commitments `commits`, as `derandomize` does for each of them.
+  ///
+  /// Implementations should override this method to compute the multiples of the blinding
+  /// generator of the whole batch at once.
+  fn derandomize_batch(
+    dk: &Self::DerandKey,
+    commits: &[Self::Commitment],
+    r: &[E::Scalar],
+  ) -> Vec<Self::Commitment> {
+    assert!(commits.len() == r.len());
+    commits
+      .par_iter()
+      .zip(r.par_iter())
+      .map(|(commit, r_i)| Self::derandomize(dk, commit, r_i))
+      .collect()
+  }
+
+  /// Adds the fresh blinds `r` to the commitments `commits`, which is the inverse of
+  /// `derandomize_batch`, so that commitments published without blinds can be republished
+  /// without revealing that they commit to the same vectors
+  fn rerandomize_batch(
+    dk: &Self::DerandKey,
+    commits: &[Self::Commitment],
+    r: &[E::Scalar],
+  ) -> Vec<Self::Commitment> {
+    let neg_r = r.iter().map(|r_i| -*r_i).collect::<Vec<_>>();
+    Self::derandomize_batch(dk, commits, &neg_r)
+  }
+}
+
+/// A short summary of a commitment key that parties holding their own copy of the key can check
+/// it against, without exchanging the full key.
+///
+/// An attestation holds the number of generators, a digest of the serialized key, and the
+/// commitment to the powers of a random challenge, which is a random linear combination of the
+/// generators, including the blinding generator. Keys with equal digests are accepted right away; otherwise the linear combination is
+/// recomputed, so that equal keys are accepted even if their serializations differ.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CommitmentKeyAttestation<E: Engine> {
+  num_generators: usize,
+  digest: [u8; 32],
+  challenge: E::Scalar,
+  check: Commitment<E>,
+}
+
+impl<E: Engine> CommitmentKeyAttestation<E> {
+  /// Produces an attestation of the commitment key `ck`
+  pub fn new(ck: &CommitmentKey<E>) -> Self {
+    let challenge = E::Scalar::random(OsRng);
+    Self {
+      num_generators: ck.length(),
+      digest: Self::digest_of(ck),
+      challenge,
+      check: Self::check_of(ck, &challenge),
+    }
+  }
+
+  /// Returns the number of generators of the attested key
+  pub fn num_generators(&self) -> usize {
+    self.num_generators
+  }
+
+  /// Returns the SHA3-256 digest of the serialized attested key
+  pub fn digest(&self) -> &[u8; 32] {
+    &self.digest
+  }
+
+  /// Checks that `ck` is the attested commitment key
+  pub fn verify(&self, ck: &CommitmentKey<E>) -> Result<(), NovaError> {
+    if ck.length() != self.num_generators {
+      return Err(NovaError::InvalidCommitmentKeyLength);
+    }
+    if Self::digest_of(ck) == self.digest || Self::check_of(ck, &self.challenge) == self.check {
+      Ok(())
+    } else {
+      Err(NovaError::CommitmentKeyMismatch)
+    }
+  }
+
+  fn digest_of(ck: &CommitmentKey<E>) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    bincode::serialize_into(&mut hasher, ck).expect("serializing a commitment key cannot fail");
+    hasher.finalize().into()
+  }
+
+  fn check_of(ck: &CommitmentKey<E>, challenge: &E::Scalar) -> Commitment<E> {
+    let mut powers = Vec::with_capacity(ck.length());
+    let mut power = E::Scalar::ONE;
+    for _ in 0..ck.length() {
+      powers.push(power);
+      power *= challenge;
+    }
+    // the next power blinds the commitment, which covers the blinding generator too
+    E::CE::commit(ck, &powers, &power)
+  }
 }
@@ -23,6 +23,10 @@ pub trait EvaluationEngineTrait<E: Engine>: Clone + Send + Sync {
     ck: &<<E as Engine>::CE as CommitmentEngineTrait<E>>::CommitmentKey,
   ) -> (Self::ProverKey, Self::VerifierKey);
 
+  /// Precomputes, once, what verifying with `vk` derives from its fixed elements, so that
+  /// subsequent verifications with it are faster. Does nothing by default.
+  fn preprocess(_vk: &Self::VerifierKey) {}
+
   /// A method to prove the evaluation of a multilinear polynomial
   fn prove(
     ck: &<<E as Engine>::CE as CommitmentEngineTrait<E>>::CommitmentKey,
@@ -27,6 +27,12 @@ pub trait Group: Clone + Copy + Debug + Send + Sync + Sized + Eq + PartialEq {
 
 /// A collection of engines that are required by the library
 pub trait Engine: Clone + Copy + Debug + Send + Sync + Sized + Eq + PartialEq {
+  /// A stable identifier of the engine, which names its key files (see `provider::key_file`)
+  /// instead of its type name, as the latter changes across compiler versions and refactors.
+  /// It consists of lowercase letters, digits, and dashes, and ends in a version tag such as
+  /// `-v1`, which is bumped whenever the commitment keys of the engine change.
+  const ID: &'static str;
+
   /// A type representing an element of the base field of the group
   type Base: PrimeFieldBits + TranscriptReprTrait<Self::GE> + Serialize + for<'de> Deserialize<'de>;
 
@@ -43,24 +49,80 @@ pub trait Engine: Clone + Copy + Debug + Send + Sync + Sized + Eq + PartialEq {
   type GE: Group<Base = Self::Base, Scalar = Self::Scalar> + Serialize + for<'de> Deserialize<'de>;
 
   /// A type that represents a circuit-friendly sponge that consumes
-  /// elements from the base field
-  type RO: ROTrait<Self::Base>;
+  /// elements from the base field.
+  ///
+  /// It derives the folding challenges of instances over this engine, and its circuit model
+  /// `Self::ROCircuit` recomputes them in the augmented circuit over the base field, i.e., in the
+  /// circuit of the other engine of a cycle. Each engine of a cycle picks its own RO, so the RO of
+  /// each augmented circuit can be chosen independently to minimize its constraints.
+  type RO: ROTrait<Self::Base, CircuitRO = Self::ROCircuit>;
 
   /// An alternate implementation of `Self::RO` in the circuit model
-  type ROCircuit: ROCircuitTrait<Self::Base>;
+  type ROCircuit: ROCircuitTrait<Self::Base, NativeRO = Self::RO>;
 
   /// A type that represents a circuit-friendly sponge that consumes
   /// elements from the scalar field
-  type RO2: ROTrait<Self::Scalar>;
+  type RO2: ROTrait<Self::Scalar, CircuitRO = Self::RO2Circuit>;
 
   /// An alternate implementation of `Self::RO2` in the circuit model
-  type RO2Circuit: ROCircuitTrait<Self::Scalar>;
+  type RO2Circuit: ROCircuitTrait<Self::Scalar, NativeRO = Self::RO2>;
 
   /// A type that provides a generic Fiat-Shamir transcript to be used when externalizing proofs
   type TE: TranscriptEngineTrait<Self>;
 
   /// A type that defines a commitment engine over scalars in the group
   type CE: CommitmentEngineTrait<Self>;
+
+  /// Reports the hardware and curve features available to the engine at runtime.
+  ///
+  /// The default reports SIMD support of the CPU, and neither GPU MSMs nor pairings.
+  fn capabilities() -> Capabilities {
+    Capabilities {
+      simd: Capabilities::cpu_simd(),
+      ..Capabilities::default()
+    }
+  }
+}
+
+/// The hardware and curve features available to an `Engine` at runtime, see
+/// `Engine::capabilities`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+  /// Whether multi-scalar multiplications over the group can run on a GPU
+  pub gpu_msm: bool,
+  /// Whether the CPU has the vector and wide-multiplication extensions that the optimized field
+  /// arithmetic relies on
+  pub simd: bool,
+  /// Whether the group admits a pairing, as pairing-based commitment engines like HyperKZG need
+  pub pairing: bool,
+}
+
+impl Capabilities {
+  /// Returns whether the CPU has the SIMD extensions reported in `simd`, i.e., AVX2 with BMI2
+  /// and ADX on x86-64, and NEON on AArch64
+  pub fn cpu_simd() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    return std::arch::is_x86_feature_detected!("avx2")
+      && std::arch::is_x86_feature_detected!("bmi2")
+      && std::arch::is_x86_feature_detected!("adx");
+    #[cfg(target_arch = "aarch64")]
+    return std::arch::is_aarch64_feature_detected!("neon");
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    return false;
+  }
+}
+
+impl core::fmt::Display for Capabilities {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    write!(
+      f,
+      "gpu msm: {}, simd: {}, pairing: {}",
+      yes_no(self.gpu_msm),
+      yes_no(self.simd),
+      yes_no(self.pairing)
+    )
+  }
 }
 
 /// A helper trait to absorb different objects in RO
@@ -89,6 +151,13 @@ pub trait ROTrait<Base: PrimeField> {
   /// Adds a scalar to the internal state
   fn absorb(&mut self, e: Base);
 
+  /// Adds scalars to the internal state, in order, as `absorb` does one at a time
+  fn absorb_batch(&mut self, es: &[Base]) {
+    for e in es {
+      self.absorb(*e);
+    }
+  }
+
   /// Returns a challenge of `num_bits` by hashing the internal state
   fn squeeze(&mut self, num_bits: usize) -> Base;
 }
@@ -131,6 +200,8 @@ pub type RO2ConstantsCircuit<E> =
   <<E as Engine>::RO2Circuit as ROCircuitTrait<<E as Engine>::Scalar>>::Constants;
 
 /// This trait allows types to implement how they want to be added to `TranscriptEngine`
+///
+/// Field elements and points follow the canonical layouts of `encoding`.
 pub trait TranscriptReprTrait<G: Group>: Send + Sync {
   /// returns a byte representation of self to be added to the transcript
   fn to_transcript_bytes(&self) -> Vec<u8>;
@@ -166,6 +237,7 @@ impl<G: Group, T: TranscriptReprTrait<G>> TranscriptReprTrait<G> for &[T] {
   }
 }
 
+pub mod calldata;
 pub mod circuit;
 pub mod evaluation;
 pub mod snark;
@@ -1,7 +1,7 @@
 //! This module defines a collection of traits that define the behavior of a `zkSNARK` for `RelaxedR1CS`
 use crate::{
   errors::NovaError,
-  r1cs::{R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness},
+  r1cs::{CommitmentKeyHint, R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness},
   traits::Engine,
   CommitmentKey,
 };
@@ -16,6 +16,72 @@ pub fn default_ck_hint<E: Engine>() -> Box<dyn for<'a> Fn(&'a R1CSShape<E>) -> u
   Box::new(|_shape: &R1CSShape<E>| 0)
 }
 
+/// Named presets for the commitment key hint passed to `PublicParams::setup`.
+///
+/// Public parameters must be generated with a commitment key that is large enough for the
+/// compressing SNARK that will later be used with them. These presets name the common choices,
+/// and `CompressedSNARK::setup` reports `NovaError::InvalidCommitmentKeyLength` if the key turns
+/// out to be too small for the chosen SNARK.
+pub enum CommitmentKeyHintPreset<E: Engine> {
+  /// The public parameters are only used for recursive proving, so no additional floor is needed
+  ForRecursiveOnly,
+  /// The public parameters are used with a compressing SNARK with the provided `ck_floor`;
+  /// use `CommitmentKeyHintPreset::for_compressed_snark` to construct this variant
+  ForCompressedSNARK(Box<CommitmentKeyHint<E>>),
+  /// The commitment key has at least the specified number of generators
+  Custom(usize),
+}
+
+impl<E: Engine> CommitmentKeyHintPreset<E> {
+  /// Returns the preset for public parameters that are compressed with the SNARK `S`
+  pub fn for_compressed_snark<S: RelaxedR1CSSNARKTrait<E>>() -> Self {
+    Self::ForCompressedSNARK(S::ck_floor())
+  }
+
+  /// Returns the floor on the number of generators this preset requires for `shape`
+  pub fn floor(&self, shape: &R1CSShape<E>) -> usize {
+    match self {
+      Self::ForRecursiveOnly => 0,
+      Self::ForCompressedSNARK(ck_floor) => ck_floor(shape),
+      Self::Custom(n) => *n,
+    }
+  }
+
+  /// Converts the preset into a hint that can be passed to `PublicParams::setup`
+  pub fn into_hint(self) -> Box<dyn for<'a> Fn(&'a R1CSShape<E>) -> usize> {
+    match self {
+      Self::ForRecursiveOnly => default_ck_hint(),
+      Self::ForCompressedSNARK(ck_floor) => ck_floor,
+      Self::Custom(n) => Box::new(move |_shape: &R1CSShape<E>| n),
+    }
+  }
+}
+
+/// A bound on the memory a prover may use for its intermediate tables.
+///
+/// Provers that can trade CPU for memory, such as the one of `spartan::snark`, switch to a
+/// streaming mode that recomputes some tables instead of storing them when their regular mode is
+/// estimated to exceed the budget, while the others, such as the one of `spartan::ppsnark`, reject
+/// budgets. The budget is best-effort: it selects a strategy, and is not enforced as a hard limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MemoryBudget {
+  /// The prover may hold all of its tables simultaneously
+  #[default]
+  Unlimited,
+  /// The prover should use at most the specified number of bytes for its tables
+  Bytes(usize),
+}
+
+impl MemoryBudget {
+  /// Returns true if a strategy that needs `bytes` bytes fits in the budget
+  pub fn allows(&self, bytes: usize) -> bool {
+    match self {
+      Self::Unlimited => true,
+      Self::Bytes(budget) => bytes <= *budget,
+    }
+  }
+}
+
 /// A trait that defines the behavior of a `zkSNARK`
 pub trait RelaxedR1CSSNARKTrait<E: Engine>:
   Send + Sync + Serialize + for<'de> Deserialize<'de>
@@ -50,8 +116,34 @@ pub trait RelaxedR1CSSNARKTrait<E: Engine>:
     W: &RelaxedR1CSWitness<E>,
   ) -> Result<Self, NovaError>;
 
+  /// Produces a new SNARK for a relaxed R1CS while keeping the prover's tables within `budget`.
+  ///
+  /// The default implementation is for provers that cannot bound the memory of their tables: it
+  /// calls `prove` if `budget` is `MemoryBudget::Unlimited`, and returns
+  /// `NovaError::MemoryBudgetUnsupported` otherwise.
+  fn prove_with_budget(
+    ck: &CommitmentKey<E>,
+    pk: &Self::ProverKey,
+    S: &R1CSShape<E>,
+    U: &RelaxedR1CSInstance<E>,
+    W: &RelaxedR1CSWitness<E>,
+    budget: MemoryBudget,
+  ) -> Result<Self, NovaError> {
+    match budget {
+      MemoryBudget::Unlimited => Self::prove(ck, pk, S, U, W),
+      MemoryBudget::Bytes(_) => Err(NovaError::MemoryBudgetUnsupported),
+    }
+  }
+
   /// Verifies a SNARK for a relaxed R1CS
   fn verify(&self, vk: &Self::VerifierKey, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError>;
+
+  /// Precomputes, once, what `verify` derives from the fixed elements of `vk`, such as its digest
+  /// and the tables of its evaluation engine, so that every verification with it reuses them.
+  /// The default implementation computes the digest.
+  fn preprocess(vk: &Self::VerifierKey) {
+    vk.digest();
+  }
 }
 
 /// A helper trait that defines the behavior of a verifier key of `zkSNARK`
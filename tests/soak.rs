@@ -0,0 +1,115 @@
+//! A soak test that proves a long run of steps and checks that proving does not degrade over time:
+//! * the resident memory of the process stays within a bound once the first window of steps is done,
+//! * the mean latency of a step in every window stays within a factor of that of the first windows,
+//! * the digest of the public parameters is reproducible and does not change while proving, and
+//! * the outputs match a direct computation at the end of every window, where the proof is verified.
+//!
+//! The test is meant to catch leaks and drift in long-running provers, e.g., after changes to the
+//! MSM backends or to how folding batches work, so it is gated behind the `soak` feature:
+//! `cargo test --release --features soak --test soak -- --nocapture`
+//!
+//! The number of steps defaults to `DEFAULT_STEPS` and can be set with `NOVA_SOAK_STEPS`. Memory is
+//! read from `/proc/self/status`, so its bound is only checked on Linux. The primary engine uses
+//! Pedersen commitments, whose keys are derived from labels, so that the setup is deterministic.
+use nova_snark::{
+  nova::{PublicParams, RecursiveSNARK},
+  provider::{Bn256EngineIPA, GrumpkinEngine},
+  traits::{circuit::NonTrivialCircuit, snark::default_ck_hint, Engine},
+};
+use std::time::{Duration, Instant};
+
+type E1 = Bn256EngineIPA;
+type E2 = GrumpkinEngine;
+type F = <E1 as Engine>::Scalar;
+type C = NonTrivialCircuit<F>;
+
+/// The number of steps proven unless `NOVA_SOAK_STEPS` is set
+const DEFAULT_STEPS: usize = 20_000;
+/// The number of steps in a window, after which the proof is verified and measurements are taken
+const WINDOW: usize = 500;
+/// The number of windows whose fastest mean step latency is the baseline for later windows
+const BASELINE_WINDOWS: usize = 3;
+/// The factor by which the mean step latency of a window may exceed the baseline
+const MAX_LATENCY_DRIFT: f64 = 2.0;
+/// The number of bytes by which the resident memory may grow after the first window
+const MAX_RSS_GROWTH: u64 = 64 << 20;
+/// The number of squarings in each step
+const NUM_CONS: usize = 16;
+
+/// Returns the resident memory of the process in bytes, where it is known
+fn resident_memory() -> Option<u64> {
+  let status = std::fs::read_to_string("/proc/self/status").ok()?;
+  let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+  let kb = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+  Some(kb << 10)
+}
+
+/// Returns the output of a step on input `x`, computed directly
+fn step(x: F) -> F {
+  (0..NUM_CONS).fold(x, |x, _| x.square())
+}
+
+#[test]
+fn test_soak() {
+  let num_steps = std::env::var("NOVA_SOAK_STEPS")
+    .map(|s| s.parse::<usize>().expect("NOVA_SOAK_STEPS is not a number"))
+    .unwrap_or(DEFAULT_STEPS);
+  assert!(
+    num_steps >= WINDOW * (BASELINE_WINDOWS + 1),
+    "the soak test needs at least {} steps",
+    WINDOW * (BASELINE_WINDOWS + 1)
+  );
+
+  let circuit = C::new(NUM_CONS);
+  let pp =
+    PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+  let digest = pp.digest();
+  let again =
+    PublicParams::<E1, E2, C>::setup(&circuit, &*default_ck_hint(), &*default_ck_hint()).unwrap();
+  assert_eq!(again.digest(), digest, "the setup is not deterministic");
+  drop(again);
+
+  let z0 = vec![F::from(2u64)];
+  let mut expected = z0[0];
+  let mut snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+  let mut baseline = Duration::MAX;
+  let mut rss_base = None;
+
+  for window in 0..num_steps / WINDOW {
+    let start = Instant::now();
+    for _ in 0..WINDOW {
+      snark.prove_step(&pp, &circuit).unwrap();
+      expected = step(expected);
+    }
+    let latency = start.elapsed() / WINDOW as u32;
+
+    let outputs = snark.verify(&pp, snark.num_steps(), &z0).unwrap();
+    assert_eq!(outputs, vec![expected], "window {window}: outputs drifted");
+    assert_eq!(pp.digest(), digest, "window {window}: digest changed");
+
+    let rss = resident_memory();
+    println!(
+      "window {window}: {} steps, {latency:?} per step, {} MiB resident",
+      snark.num_steps(),
+      rss.map_or_else(|| "?".to_string(), |rss| (rss >> 20).to_string())
+    );
+
+    if window < BASELINE_WINDOWS {
+      baseline = baseline.min(latency);
+    } else {
+      assert!(
+        latency.as_secs_f64() <= baseline.as_secs_f64() * MAX_LATENCY_DRIFT,
+        "window {window}: {latency:?} per step exceeds the baseline of {baseline:?}"
+      );
+    }
+
+    match (rss_base, rss) {
+      (Some(base), Some(rss)) => assert!(
+        rss <= base + MAX_RSS_GROWTH,
+        "window {window}: resident memory grew by {} MiB",
+        (rss - base) >> 20
+      ),
+      _ => rss_base = rss,
+    }
+  }
+}